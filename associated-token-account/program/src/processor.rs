@@ -48,10 +48,14 @@ pub fn process_instruction(
         &[bump_seed],
     ];
 
+    // Mints with extensions require larger token accounts
+    let account_len =
+        ppl_token::state::get_account_len_for_mint(&ppl_token_mint_info.data.borrow())?;
+
     // Fund the associated token account with the minimum balance to be rent exempt
     let rent = &Rent::from_account_info(rent_sysvar_info)?;
     let required_lamports = rent
-        .minimum_balance(ppl_token::state::Account::LEN)
+        .minimum_balance(account_len)
         .max(1)
         .saturating_sub(associated_token_account_info.lamports());
 
@@ -78,7 +82,7 @@ pub fn process_instruction(
     invoke_signed(
         &system_instruction::allocate(
             associated_token_account_info.key,
-            ppl_token::state::Account::LEN as u64,
+            account_len as u64,
         ),
         &[
             associated_token_account_info.clone(),
@@ -9,7 +9,7 @@ pub mod state;
 
 // Export current SDK types for downstream users building with a different SDK version
 pub use put_program;
-use put_program::{program_pack::Pack, pubkey::Pubkey};
+use put_program::{program_error::ProgramError, program_pack::Pack, pubkey::Pubkey};
 
 put_program::declare_id!("PutFP11111111111111111111111111111111111111");
 
@@ -65,9 +65,9 @@ pub fn get_feature_id_address(feature_proposal_address: &Pubkey) -> Pubkey {
 }
 
 /// Convert the UI representation of a token amount (using the decimals field defined in its mint)
-/// to the raw amount
-pub fn ui_amount_to_amount(ui_amount: f64) -> u128 {
-    (ui_amount * 10_usize.pow(ppl_token::native_mint::DECIMALS as u32) as f64) as u128
+/// to the raw amount, exactly
+pub fn ui_amount_to_amount(ui_amount: &str) -> Result<u128, ProgramError> {
+    ppl_token::ui_amount_str_to_amount(ui_amount, ppl_token::native_mint::DECIMALS)
 }
 
 /// Convert a raw amount to its UI representation (using the decimals field defined in its mint)
@@ -0,0 +1,547 @@
+use crate::{
+    check_fee_payer_balance,
+    config::Config,
+    output::{println_display, CliDistribution},
+    parse_ui_amount, token_account_len, with_instructions_sysvar, CommandResult, Error,
+};
+use ppl_associated_token_account::{create_associated_token_account, get_associated_token_address};
+use ppl_token::{
+    instruction::transfer_checked,
+    state::{Account, BaseState, Mint},
+};
+use put_sdk::{
+    commitment_config::CommitmentConfig,
+    hash::Hash,
+    instruction::Instruction,
+    message::Message,
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+    transaction::Transaction,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    str::FromStr,
+};
+
+// Upper bound of `getMultipleAccounts`
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+struct DistributionRow {
+    line: usize,
+    recipient: String,
+    amount: String,
+    parsed: Result<(Pubkey, u128), String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum DistributionStatus {
+    Pending,
+    Confirmed,
+    Failed,
+}
+
+impl DistributionStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            DistributionStatus::Pending => "pending",
+            DistributionStatus::Confirmed => "confirmed",
+            DistributionStatus::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DistributionRecord {
+    recipient: String,
+    amount: String,
+    signature: String,
+    blockhash: String,
+    status: DistributionStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+// Every transaction is recorded here, with its signature, before it is sent. A rerun
+// after a crash resolves those pending transactions against the cluster first, so that
+// no row is paid twice.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DistributionState {
+    mint: String,
+    /// Records by line number of the input file
+    records: BTreeMap<usize, DistributionRecord>,
+}
+
+impl DistributionState {
+    fn load(path: &str, token: &Pubkey) -> Result<Self, Error> {
+        let state = match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str::<Self>(&contents)
+                .map_err(|err| format!("Invalid state file {}: {}", path, err))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Self {
+                mint: token.to_string(),
+                records: BTreeMap::new(),
+            },
+            Err(err) => return Err(format!("Could not read {}: {}", path, err).into()),
+        };
+        if state.mint != token.to_string() {
+            return Err(format!(
+                "State file {} belongs to a distribution of {}, not {}",
+                path, state.mint, token
+            )
+            .into());
+        }
+        Ok(state)
+    }
+
+    fn save(&self, path: &str) -> Result<(), Error> {
+        // Write a new file and move it over the old one, so that a crash never leaves a
+        // truncated state behind
+        let tmp_path = format!("{}.tmp", path);
+        fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+}
+
+fn parse_rows(input: &str, decimals: u8) -> Result<Vec<DistributionRow>, Error> {
+    let contents =
+        fs::read_to_string(input).map_err(|err| format!("Could not read {}: {}", input, err))?;
+    let mut rows = vec![];
+    for (i, line) in contents.lines().enumerate() {
+        let line_number = i + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<_> = line.split(',').map(str::trim).collect();
+        if rows.is_empty() && fields[0].eq_ignore_ascii_case("recipient") {
+            // Header
+            continue;
+        }
+        let recipient = fields[0].to_string();
+        let amount = fields
+            .get(1)
+            .map(|amount| amount.to_string())
+            .unwrap_or_default();
+        let parsed = if fields.len() != 2 {
+            Err(format!(
+                "Expected `recipient,amount`, found {} fields",
+                fields.len()
+            ))
+        } else {
+            Pubkey::from_str(&recipient)
+                .map_err(|_| format!("Invalid recipient address {}", recipient))
+                .and_then(|recipient| {
+                    parse_ui_amount(&amount, decimals)
+                        .map(|amount| (recipient, amount))
+                        .map_err(|err| err.to_string())
+                })
+                .and_then(|(recipient, amount)| {
+                    if amount == 0 {
+                        Err("Amount must be greater than zero".to_string())
+                    } else {
+                        Ok((recipient, amount))
+                    }
+                })
+        };
+        rows.push(DistributionRow {
+            line: line_number,
+            recipient,
+            amount,
+            parsed,
+        });
+    }
+    Ok(rows)
+}
+
+// Looks a transaction up in the whole status history of the cluster. `None` means it
+// may still land: the cluster does not know about it but its blockhash has not expired.
+fn settled_status(
+    config: &Config,
+    signature: &Signature,
+    blockhash: &Hash,
+) -> Result<Option<(DistributionStatus, Option<String>)>, Error> {
+    // Checked before the status, so that a transaction missing from the history cannot
+    // land in between
+    let blockhash_expired = !config
+        .rpc_client
+        .is_blockhash_valid(blockhash, CommitmentConfig::processed())?;
+    let status = config
+        .rpc_client
+        .get_signature_status_with_commitment_and_history(
+            signature,
+            config.rpc_client.commitment(),
+            true,
+        )?;
+    Ok(match status {
+        Some(Ok(())) => Some((DistributionStatus::Confirmed, None)),
+        Some(Err(err)) => Some((DistributionStatus::Failed, Some(err.to_string()))),
+        None if blockhash_expired => Some((
+            DistributionStatus::Failed,
+            Some("Transaction expired".to_string()),
+        )),
+        None => None,
+    })
+}
+
+// Settles the transactions left pending by an earlier run. A transaction that the
+// cluster does not know about may only be retried once its blockhash has expired.
+fn resolve_pending(config: &Config, state: &mut DistributionState) -> Result<(), Error> {
+    let mut resolved: HashMap<String, (DistributionStatus, Option<String>)> = HashMap::new();
+    for record in state.records.values_mut() {
+        if record.status != DistributionStatus::Pending {
+            continue;
+        }
+        if !resolved.contains_key(&record.signature) {
+            let signature = Signature::from_str(&record.signature)?;
+            let blockhash = Hash::from_str(&record.blockhash)?;
+            let resolution = settled_status(config, &signature, &blockhash)?.ok_or_else(|| {
+                format!(
+                    "Transaction {} may still land; rerun once its blockhash has expired",
+                    record.signature
+                )
+            })?;
+            resolved.insert(record.signature.clone(), resolution);
+        }
+        let (status, error) = &resolved[&record.signature];
+        record.status = *status;
+        record.error = error.clone();
+    }
+    Ok(())
+}
+
+fn transaction_size(instructions: &[Instruction], fee_payer: &Pubkey) -> usize {
+    let message = Message::new(instructions, Some(fee_payer));
+    // Compact length of the signatures, then the signatures and the message
+    1 + message.header.num_required_signatures as usize * 64 + message.serialize().len()
+}
+
+struct Batch<'a> {
+    rows: Vec<&'a DistributionRow>,
+    instructions: Vec<Instruction>,
+    created_accounts: HashSet<Pubkey>,
+}
+
+impl<'a> Batch<'a> {
+    fn new() -> Self {
+        Self {
+            rows: vec![],
+            instructions: vec![],
+            created_accounts: HashSet::new(),
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn send_batch(
+    config: &Config,
+    signers: &[Box<dyn Signer>],
+    state: &mut DistributionState,
+    state_file: &str,
+    batch: &Batch,
+    rent_per_account: u128,
+    existing_accounts: &mut HashSet<Pubkey>,
+) -> Result<(), Error> {
+    let message = Message::new(&batch.instructions, Some(&config.fee_payer));
+    let (recent_blockhash, fee_calculator) = config
+        .blockhash_query
+        .get_blockhash_and_fee_calculator(&config.rpc_client, config.rpc_client.commitment())?;
+    check_fee_payer_balance(
+        config,
+        rent_per_account * batch.created_accounts.len() as u128
+            + fee_calculator.calculate_fee(&message),
+    )?;
+
+    let signer_keys = message.signer_keys();
+    let message_signers = signers
+        .iter()
+        .filter(|signer| signer_keys.contains(&&signer.pubkey()))
+        .map(|signer| signer.as_ref())
+        .collect::<Vec<_>>();
+    let mut transaction = Transaction::new_unsigned(message);
+    transaction.try_sign(&message_signers, recent_blockhash)?;
+    let signature = transaction.signatures[0];
+
+    for row in &batch.rows {
+        state.records.insert(
+            row.line,
+            DistributionRecord {
+                recipient: row.recipient.clone(),
+                amount: row.amount.clone(),
+                signature: signature.to_string(),
+                blockhash: recent_blockhash.to_string(),
+                status: DistributionStatus::Pending,
+                error: None,
+            },
+        );
+    }
+    state.save(state_file)?;
+
+    let (status, error) = match config
+        .rpc_client
+        .send_and_confirm_transaction_with_spinner(&transaction)
+    {
+        Ok(_) => (DistributionStatus::Confirmed, None),
+        Err(err) => settled_status(config, &signature, &recent_blockhash)?
+            .unwrap_or((DistributionStatus::Pending, Some(err.to_string()))),
+    };
+    if status == DistributionStatus::Confirmed {
+        existing_accounts.extend(batch.created_accounts.iter());
+    }
+    for row in &batch.rows {
+        let record = state.records.get_mut(&row.line).unwrap();
+        record.status = status;
+        record.error = error.clone();
+    }
+    state.save(state_file)?;
+
+    println_display(
+        config,
+        format!(
+            "{} {} transfer(s): {}",
+            signature,
+            batch.rows.len(),
+            status.as_str()
+        ),
+    );
+    Ok(())
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains(',') || field.contains('"') || field.contains('\n') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn write_results(
+    path: &str,
+    rows: &[DistributionRow],
+    state: &DistributionState,
+) -> Result<(), Error> {
+    let mut results = "recipient,amount,signature,status,error\n".to_string();
+    for row in rows {
+        let (signature, status, error) = match (&row.parsed, state.records.get(&row.line)) {
+            (Err(err), _) => ("", "failed", err.as_str()),
+            (Ok(_), Some(record)) => (
+                record.signature.as_str(),
+                record.status.as_str(),
+                record.error.as_deref().unwrap_or(""),
+            ),
+            (Ok(_), None) => ("", "skipped", ""),
+        };
+        results.push_str(
+            &[
+                csv_field(&row.recipient),
+                csv_field(&row.amount),
+                signature.to_string(),
+                status.to_string(),
+                csv_field(error),
+            ]
+            .join(","),
+        );
+        results.push('\n');
+    }
+    fs::write(path, results).map_err(|err| format!("Could not write {}: {}", path, err).into())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn command_distribute(
+    config: &Config,
+    signers: &[Box<dyn Signer>],
+    token: Pubkey,
+    source: Option<Pubkey>,
+    owner: Pubkey,
+    input: &str,
+    state_file: &str,
+    results_file: &str,
+) -> CommandResult {
+    let mint_account = config.rpc_client.get_account(&token)?;
+    let decimals = Mint::unpack_base(&mint_account.data)
+        .map_err(|_| format!("Invalid mint account {:?}", token))?
+        .decimals;
+    let source = source.unwrap_or_else(|| get_associated_token_address(&owner, &token));
+
+    let rows = parse_rows(input, decimals)?;
+    let mut state = DistributionState::load(state_file, &token)?;
+    for row in &rows {
+        if let Some(record) = state.records.get(&row.line) {
+            if record.recipient != row.recipient || record.amount != row.amount {
+                return Err(format!(
+                    "Line {} of {} no longer matches the state file {}",
+                    row.line, input, state_file
+                )
+                .into());
+            }
+        }
+    }
+    resolve_pending(config, &mut state)?;
+    state.save(state_file)?;
+
+    let remaining_rows = rows
+        .iter()
+        .filter(|row| row.parsed.is_ok())
+        .filter(|row| {
+            state
+                .records
+                .get(&row.line)
+                .map(|record| record.status != DistributionStatus::Confirmed)
+                .unwrap_or(true)
+        })
+        .collect::<Vec<_>>();
+
+    let remaining_amount = remaining_rows
+        .iter()
+        .map(|row| row.parsed.as_ref().unwrap().1)
+        .sum::<u128>();
+    let source_account = Account::unpack_base(&config.rpc_client.get_account_data(&source)?)
+        .map_err(|_| format!("Could not find token account {}", source))?;
+    if source_account.mint != token {
+        return Err(format!("Source {:?} does not contain {:?} tokens", source, token).into());
+    }
+    if source_account.amount < remaining_amount {
+        return Err(format!(
+            "Error: Sender has insufficient funds: {} required, {} available",
+            ppl_token::amount_to_ui_amount(remaining_amount, decimals),
+            ppl_token::amount_to_ui_amount(source_account.amount, decimals)
+        )
+        .into());
+    }
+    println_display(
+        config,
+        format!(
+            "Distributing {} tokens to {} recipients\n  Source: {}",
+            ppl_token::amount_to_ui_amount(remaining_amount, decimals),
+            remaining_rows.len(),
+            source
+        ),
+    );
+
+    let recipient_accounts = remaining_rows
+        .iter()
+        .map(|row| get_associated_token_address(&row.parsed.as_ref().unwrap().0, &token))
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    let mut existing_accounts = HashSet::new();
+    for chunk in recipient_accounts.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        for (address, account) in chunk
+            .iter()
+            .zip(config.rpc_client.get_multiple_accounts(chunk)?)
+        {
+            if matches!(account, Some(account) if account.owner == ppl_token::id()) {
+                existing_accounts.insert(*address);
+            }
+        }
+    }
+    let rent_per_account = config
+        .rpc_client
+        .get_minimum_balance_for_rent_exemption(token_account_len(config, &token)?)?;
+
+    let row_instructions = |row: &DistributionRow,
+                            batch: &Batch,
+                            existing_accounts: &HashSet<Pubkey>|
+     -> Result<(Vec<Instruction>, Option<Pubkey>), Error> {
+        let (recipient, amount) = *row.parsed.as_ref().unwrap();
+        let recipient_account = get_associated_token_address(&recipient, &token);
+        let mut instructions = vec![];
+        let created_account = if existing_accounts.contains(&recipient_account)
+            || batch.created_accounts.contains(&recipient_account)
+        {
+            None
+        } else {
+            instructions.push(create_associated_token_account(
+                &config.fee_payer,
+                &recipient,
+                &token,
+            ));
+            Some(recipient_account)
+        };
+        instructions.push(with_instructions_sysvar(transfer_checked(
+            &ppl_token::id(),
+            &source,
+            &token,
+            &recipient_account,
+            &owner,
+            &config.multisigner_pubkeys,
+            amount,
+            decimals,
+        )?));
+        Ok((instructions, created_account))
+    };
+
+    let mut transactions = 0;
+    let mut batch = Batch::new();
+    for row in remaining_rows {
+        let (mut instructions, mut created_account) =
+            row_instructions(row, &batch, &existing_accounts)?;
+        let mut candidate = batch.instructions.clone();
+        candidate.extend(instructions.iter().cloned());
+        if !batch.rows.is_empty()
+            && transaction_size(&candidate, &config.fee_payer) > PACKET_DATA_SIZE
+        {
+            send_batch(
+                config,
+                signers,
+                &mut state,
+                state_file,
+                &batch,
+                rent_per_account,
+                &mut existing_accounts,
+            )?;
+            transactions += 1;
+            batch = Batch::new();
+            let (new_instructions, new_created_account) =
+                row_instructions(row, &batch, &existing_accounts)?;
+            instructions = new_instructions;
+            created_account = new_created_account;
+        }
+        batch.rows.push(row);
+        batch.instructions.extend(instructions);
+        batch.created_accounts.extend(created_account);
+    }
+    if !batch.rows.is_empty() {
+        send_batch(
+            config,
+            signers,
+            &mut state,
+            state_file,
+            &batch,
+            rent_per_account,
+            &mut existing_accounts,
+        )?;
+        transactions += 1;
+    }
+
+    write_results(results_file, &rows, &state)?;
+
+    let count = |status: DistributionStatus| {
+        rows.iter()
+            .filter(|row| {
+                row.parsed.is_ok()
+                    && state.records.get(&row.line).map(|record| record.status) == Some(status)
+            })
+            .count()
+    };
+    let cli_distribution = CliDistribution {
+        rows: rows.len(),
+        transactions,
+        confirmed: count(DistributionStatus::Confirmed),
+        failed: count(DistributionStatus::Failed)
+            + rows.iter().filter(|row| row.parsed.is_err()).count(),
+        pending: count(DistributionStatus::Pending),
+        state_file: state_file.to_string(),
+        results_file: results_file.to_string(),
+    };
+    println!(
+        "{}",
+        config.output_format.formatted_string(&cli_distribution)
+    );
+    Ok(None)
+}
@@ -0,0 +1,400 @@
+use crate::{
+    authority_type_name,
+    config::Config,
+    output::{CliHistory, CliHistoryEntry},
+    CommandResult, Error,
+};
+use ppl_token::{
+    amount_to_ui_amount,
+    instruction::{decode, AccountRole, DecodedTokenInstruction, TokenInstruction},
+};
+use put_client::{
+    rpc_client::GetConfirmedSignaturesForAddress2Config, rpc_config::RpcTransactionConfig,
+};
+use put_sdk::{
+    commitment_config::CommitmentConfig,
+    instruction::{AccountMeta, Instruction},
+    program_option::COption,
+    pubkey::Pubkey,
+    signature::Signature,
+};
+use put_transaction_status::{
+    option_serializer::OptionSerializer, EncodedConfirmedTransactionWithStatusMeta, UiInstruction,
+    UiTransactionEncoding, UiTransactionTokenBalance,
+};
+use std::str::FromStr;
+
+// Upper bound of `getSignaturesForAddress`
+const MAX_SIGNATURES: usize = 1000;
+
+pub(crate) fn command_history(
+    config: &Config,
+    address: Pubkey,
+    since: Option<Signature>,
+    before: Option<Signature>,
+    limit: usize,
+) -> CommandResult {
+    // `getTransaction` does not serve processed transactions
+    let commitment = if config.rpc_client.commitment().is_at_least_confirmed() {
+        config.rpc_client.commitment()
+    } else {
+        CommitmentConfig::confirmed()
+    };
+
+    // Signatures come newest first, each page continuing before the oldest of the last one
+    let mut signatures = vec![];
+    let mut before = before;
+    let mut more = true;
+    while more && signatures.len() < limit {
+        let page_limit = (limit - signatures.len()).min(MAX_SIGNATURES);
+        let page = config.rpc_client.get_signatures_for_address_with_config(
+            &address,
+            GetConfirmedSignaturesForAddress2Config {
+                before,
+                until: since,
+                limit: Some(page_limit),
+                commitment: Some(commitment),
+            },
+        )?;
+        more = page.len() == page_limit;
+        for status in page {
+            let signature = Signature::from_str(&status.signature)?;
+            before = Some(signature);
+            signatures.push(signature);
+        }
+    }
+
+    let mut entries = vec![];
+    for signature in signatures.iter().rev() {
+        let transaction = config.rpc_client.get_transaction_with_config(
+            signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(commitment),
+                max_supported_transaction_version: Some(0),
+            },
+        )?;
+        entries.push(history_entry(&address, signature, transaction)?);
+    }
+
+    let cli_history = CliHistory {
+        address: address.to_string(),
+        entries,
+        before: if more {
+            before.map(|signature| signature.to_string())
+        } else {
+            None
+        },
+    };
+    println!("{}", config.output_format.formatted_string(&cli_history));
+    Ok(None)
+}
+
+fn history_entry(
+    address: &Pubkey,
+    signature: &Signature,
+    confirmed: EncodedConfirmedTransactionWithStatusMeta,
+) -> Result<CliHistoryEntry, Error> {
+    let transaction = confirmed
+        .transaction
+        .transaction
+        .decode()
+        .ok_or_else(|| format!("Could not decode transaction {}", signature))?;
+    let meta = confirmed
+        .transaction
+        .meta
+        .ok_or_else(|| format!("Transaction {} has no status", signature))?;
+    let message = &transaction.message;
+
+    // Accounts loaded from lookup tables follow the static keys, writable ones first
+    let mut account_keys = message.static_account_keys().to_vec();
+    if let OptionSerializer::Some(loaded_addresses) = &meta.loaded_addresses {
+        for key in loaded_addresses
+            .writable
+            .iter()
+            .chain(loaded_addresses.readonly.iter())
+        {
+            account_keys.push(Pubkey::from_str(key)?);
+        }
+    }
+    let to_instruction = |program_id_index: u8, accounts: &[u8], data: Vec<u8>| {
+        let accounts = accounts
+            .iter()
+            .map(|&index| {
+                let index = index as usize;
+                Some(AccountMeta {
+                    pubkey: *account_keys.get(index)?,
+                    is_signer: message.is_signer(index),
+                    is_writable: message.is_maybe_writable(index),
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(Instruction {
+            program_id: *account_keys.get(program_id_index as usize)?,
+            accounts,
+            data,
+        })
+    };
+
+    // Each instruction is followed by the instructions it invoked
+    let inner_instructions = match &meta.inner_instructions {
+        OptionSerializer::Some(inner_instructions) => inner_instructions.as_slice(),
+        _ => &[],
+    };
+    let mut instructions = vec![];
+    for (index, compiled) in message.instructions().iter().enumerate() {
+        instructions.extend(to_instruction(
+            compiled.program_id_index,
+            &compiled.accounts,
+            compiled.data.clone(),
+        ));
+        for inner in inner_instructions
+            .iter()
+            .filter(|inner| inner.index as usize == index)
+        {
+            for instruction in &inner.instructions {
+                if let UiInstruction::Compiled(compiled) = instruction {
+                    if let Ok(data) = bs58::decode(&compiled.data).into_vec() {
+                        instructions.extend(to_instruction(
+                            compiled.program_id_index,
+                            &compiled.accounts,
+                            data,
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    let pre_balance = token_balance(address, &account_keys, &meta.pre_token_balances);
+    let post_balance = token_balance(address, &account_keys, &meta.post_token_balances);
+    let decimals = post_balance
+        .or(pre_balance)
+        .map(|(_, decimals)| decimals)
+        .or_else(|| mint_decimals(address, &meta.post_token_balances));
+
+    let mut descriptions = vec![];
+    let mut memos = vec![];
+    for instruction in &instructions {
+        if instruction.program_id == ppl_memo::id() {
+            memos.push(String::from_utf8_lossy(&instruction.data).into_owned());
+        } else if let Ok(decoded) = decode(instruction) {
+            if decoded
+                .accounts
+                .iter()
+                .any(|account| account.pubkey == *address)
+            {
+                descriptions.push(describe_instruction(&decoded, decimals));
+            }
+        }
+    }
+
+    // An account missing from the balances before the transaction was created by it, and
+    // one missing after it was closed
+    let (change, balance) = match (pre_balance, post_balance, decimals) {
+        (None, None, _) | (_, _, None) => (None, None),
+        (pre_balance, post_balance, Some(decimals)) => {
+            let pre_amount = pre_balance.map(|(amount, _)| amount).unwrap_or_default();
+            let post_amount = post_balance.map(|(amount, _)| amount).unwrap_or_default();
+            let change = if post_amount >= pre_amount {
+                format!(
+                    "+{}",
+                    amount_to_ui_amount(post_amount - pre_amount, decimals)
+                )
+            } else {
+                format!(
+                    "-{}",
+                    amount_to_ui_amount(pre_amount - post_amount, decimals)
+                )
+            };
+            (
+                Some(change),
+                Some(amount_to_ui_amount(post_amount, decimals)),
+            )
+        }
+    };
+
+    Ok(CliHistoryEntry {
+        signature: signature.to_string(),
+        slot: confirmed.slot,
+        block_time: confirmed.block_time,
+        error: meta.err.map(|err| err.to_string()),
+        instructions: descriptions,
+        memos,
+        change,
+        balance,
+    })
+}
+
+// Raw amount and decimals of the token account `address` in the balances of a transaction
+fn token_balance(
+    address: &Pubkey,
+    account_keys: &[Pubkey],
+    balances: &OptionSerializer<Vec<UiTransactionTokenBalance>>,
+) -> Option<(u128, u8)> {
+    match balances {
+        OptionSerializer::Some(balances) => balances
+            .iter()
+            .find(|balance| account_keys.get(balance.account_index as usize) == Some(address))
+            .and_then(|balance| {
+                Some((
+                    balance.ui_token_amount.amount.parse().ok()?,
+                    balance.ui_token_amount.decimals,
+                ))
+            }),
+        _ => None,
+    }
+}
+
+// Decimals of the mint `address`, if any account of the transaction holds its tokens
+fn mint_decimals(
+    address: &Pubkey,
+    balances: &OptionSerializer<Vec<UiTransactionTokenBalance>>,
+) -> Option<u8> {
+    match balances {
+        OptionSerializer::Some(balances) => balances
+            .iter()
+            .find(|balance| balance.mint == address.to_string())
+            .map(|balance| balance.ui_token_amount.decimals),
+        _ => None,
+    }
+}
+
+fn describe_instruction(decoded: &DecodedTokenInstruction, decimals: Option<u8>) -> String {
+    let ui_amount = |amount: u128| match decimals {
+        Some(decimals) => amount_to_ui_amount(amount, decimals),
+        None => amount.to_string(),
+    };
+    let account = |role: AccountRole| {
+        decoded
+            .account(role)
+            .map(|pubkey| pubkey.to_string())
+            .unwrap_or_else(|| "?".to_string())
+    };
+    let authority = account(AccountRole::Authority);
+    match &decoded.instruction {
+        TokenInstruction::Transfer { amount }
+        | TokenInstruction::TransferChecked { amount, .. }
+        | TokenInstruction::TransferWithEncryptedNote { amount, .. } => format!(
+            "Transfer {} from {} to {}",
+            ui_amount(*amount),
+            account(AccountRole::Source),
+            account(AccountRole::Destination)
+        ),
+        TokenInstruction::BatchTransfer { amounts }
+        | TokenInstruction::BatchTransferChecked { amounts, .. } => format!(
+            "Transfer from {} to {}",
+            account(AccountRole::Source),
+            decoded
+                .accounts_with_role(AccountRole::Destination)
+                .zip(amounts)
+                .map(|(destination, amount)| format!("{} ({})", destination, ui_amount(*amount)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        TokenInstruction::MintTo { amount } | TokenInstruction::MintToChecked { amount, .. } => {
+            format!(
+                "Mint {} to {}",
+                ui_amount(*amount),
+                account(AccountRole::Destination)
+            )
+        }
+        TokenInstruction::Burn { amount } | TokenInstruction::BurnChecked { amount, .. } => {
+            format!(
+                "Burn {} from {}",
+                ui_amount(*amount),
+                account(AccountRole::Source)
+            )
+        }
+        TokenInstruction::Approve { amount } => format!(
+            "Approve {} for {} from {}",
+            account(AccountRole::Delegate),
+            ui_amount(*amount),
+            account(AccountRole::Source)
+        ),
+        TokenInstruction::ApproveChecked { amount, period, .. } => format!(
+            "Approve {} for {}{} from {}",
+            account(AccountRole::Delegate),
+            ui_amount(*amount),
+            period
+                .map(|period| format!(" every {} seconds", period))
+                .unwrap_or_default(),
+            account(AccountRole::Source)
+        ),
+        TokenInstruction::Revoke => {
+            format!("Revoke the delegate of {}", account(AccountRole::Source))
+        }
+        TokenInstruction::ApproveOperator { limit, period } => format!(
+            "Approve operator {} for {}{} on behalf of {}",
+            account(AccountRole::Delegate),
+            limit.map_or_else(|| "any amount".to_string(), ui_amount),
+            if *period > 0 {
+                format!(" every {} seconds", period)
+            } else {
+                String::new()
+            },
+            authority
+        ),
+        TokenInstruction::RevokeOperator => format!(
+            "Revoke operator approval {}",
+            account(AccountRole::OperatorApproval)
+        ),
+        TokenInstruction::FreezeAccount => format!("Freeze {}", account(AccountRole::Account)),
+        TokenInstruction::ThawAccount => format!("Thaw {}", account(AccountRole::Account)),
+        TokenInstruction::CloseAccount => format!(
+            "Close {}, sending its lamports to {}",
+            decoded
+                .account(AccountRole::Account)
+                .or_else(|| decoded.account(AccountRole::Mint))
+                .map(|pubkey| pubkey.to_string())
+                .unwrap_or_else(|| "?".to_string()),
+            account(AccountRole::Destination)
+        ),
+        TokenInstruction::SetAuthority {
+            authority_type,
+            new_authority,
+        } => {
+            let target = decoded
+                .accounts
+                .first()
+                .map(|account| account.pubkey.to_string())
+                .unwrap_or_else(|| "?".to_string());
+            match new_authority {
+                COption::Some(new_authority) => format!(
+                    "Set the {} of {} to {}",
+                    authority_type_name(authority_type),
+                    target,
+                    new_authority
+                ),
+                COption::None => format!(
+                    "Disable the {} of {}",
+                    authority_type_name(authority_type),
+                    target
+                ),
+            }
+        }
+        TokenInstruction::UpdateSymbol { new_symbol } => format!("Set symbol to {:?}", new_symbol),
+        TokenInstruction::UpdateName { new_name } => format!("Set name to {:?}", new_name),
+        TokenInstruction::UpdateIcon { new_icon } => format!("Set icon to {:?}", new_icon),
+        TokenInstruction::InitMintMetaAccount { symbol, name, icon } => format!(
+            "Set symbol to {:?}, name to {:?} and icon to {:?}",
+            symbol, name, icon
+        ),
+        TokenInstruction::SetMintMetaField { key, value } => {
+            format!("Set meta field {:?} to {:?}", key, value)
+        }
+        TokenInstruction::RemoveMintMetaField { key } => format!("Remove meta field {:?}", key),
+        TokenInstruction::DepositNative { amount } => format!(
+            "Deposit {} into {}",
+            ui_amount(*amount),
+            account(AccountRole::Account)
+        ),
+        TokenInstruction::WithdrawNative { amount } => format!(
+            "Withdraw {} from {} to {}",
+            ui_amount(*amount),
+            account(AccountRole::Source),
+            account(AccountRole::Destination)
+        ),
+        instruction => format!("{:?}", instruction),
+    }
+}
@@ -2,17 +2,23 @@ use clap::{
     crate_description, crate_name, crate_version, value_t, value_t_or_exit, App, AppSettings, Arg,
     ArgMatches, SubCommand,
 };
-use console::Emoji;
 use ppl_associated_token_account::*;
 use ppl_token::{
     self,
     instruction::*,
     native_mint,
-    state::{Account, Mint, MintMeta, Multisig},
+    state::{
+        get_account_len_for_mint, get_account_type, get_additional_metadata, get_extension,
+        get_mint_meta_base, Account, AccountState,
+        AccountType, BaseState, ExtensionType, InterestBearingConfig, Mint, MintCloseAuthority,
+        MintMeta, Multisig, PermanentDelegate, TransferFeeAmount, TransferFeeConfig, MAX_ICON_LEN,
+        MAX_METADATA_KEY_LEN, MAX_METADATA_VALUE_LEN, MAX_NAME_LEN, MAX_SYMBOL_LEN,
+    },
 };
+use ppl_vesting::state::{VestingGrant, VestingSchedule};
 use put_account_decoder::{
     parse_token::{TokenAccountType, UiAccountState},
-    UiAccountData,
+    UiAccountData, UiAccountEncoding,
 };
 use put_clap_utils::{
     fee_payer::fee_payer_arg,
@@ -26,32 +32,48 @@ use put_clap_utils::{
     offline::{self, *},
     ArgConstant,
 };
-use put_cli_output::{display::println_name_value, return_signers, OutputFormat};
+use put_cli_output::{return_signers, CliSignature, OutputFormat};
 use put_client::{
-    blockhash_query::BlockhashQuery, rpc_client::RpcClient, rpc_request::TokenAccountsFilter,
+    blockhash_query::BlockhashQuery,
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType},
+    rpc_request::TokenAccountsFilter,
 };
 use put_remote_wallet::remote_wallet::RemoteWalletManager;
 use put_sdk::{
     commitment_config::CommitmentConfig,
-    instruction::Instruction,
+    instruction::{AccountMeta, Instruction},
     message::Message,
     native_token::*,
     program_option::COption,
     program_pack::Pack,
     pubkey::Pubkey,
-    signature::{Keypair, Signer},
-    system_instruction, system_program,
+    signature::{Keypair, Signature, Signer},
+    system_instruction, system_program, sysvar,
     transaction::Transaction,
 };
-use std::{collections::HashMap, process::exit, str::FromStr, sync::Arc};
+use std::{collections::HashMap, fs, process::exit, str::FromStr, sync::Arc};
 
 mod config;
 use config::Config;
 
+mod distribute;
+use distribute::command_distribute;
+
+mod output;
+use output::*;
+
+mod history;
+use history::command_history;
+
 mod sort;
 use sort::sort_and_parse_token_accounts;
 
-static WARNING: Emoji = Emoji("⚠️", "!");
+mod transaction_file;
+use transaction_file::{
+    command_broadcast, command_merge_signatures, command_sign_transaction, export_transaction,
+};
 
 pub const OWNER_ADDRESS_ARG: ArgConstant<'static> = ArgConstant {
     name: "owner",
@@ -126,6 +148,37 @@ fn is_mint_decimals(string: String) -> Result<(), String> {
     is_parsable::<u8>(string)
 }
 
+fn check_meta_len(string: &str, max_len: usize) -> Result<(), String> {
+    if string.len() > max_len {
+        Err(format!("must be at most {} bytes long", max_len))
+    } else {
+        Ok(())
+    }
+}
+
+fn is_valid_symbol(string: String) -> Result<(), String> {
+    check_meta_len(&string, MAX_SYMBOL_LEN)
+}
+
+fn is_valid_name(string: String) -> Result<(), String> {
+    check_meta_len(&string, MAX_NAME_LEN)
+}
+
+fn is_valid_icon(string: String) -> Result<(), String> {
+    check_meta_len(&string, MAX_ICON_LEN)
+}
+
+fn is_valid_metadata_field(string: String) -> Result<(), String> {
+    let (key, value) = string
+        .split_once('=')
+        .ok_or_else(|| "must be of the form KEY=VALUE".to_string())?;
+    if key.is_empty() {
+        return Err("key must not be empty".to_string());
+    }
+    check_meta_len(key, MAX_METADATA_KEY_LEN)?;
+    check_meta_len(value, MAX_METADATA_VALUE_LEN)
+}
+
 pub fn mint_decimals_arg<'a, 'b>() -> Arg<'a, 'b> {
     Arg::with_name(MINT_DECIMALS_ARG.name)
         .long(MINT_DECIMALS_ARG.long)
@@ -262,34 +315,72 @@ pub fn signers_of(
 }
 
 
-pub fn parse_amount(amount:&str) -> (u128, f64){
-    let num:Vec<&str> = amount.split('.').collect();
-    if num.len() == 1 {
-        return (num[0].parse::<u128>().unwrap(),0_f64)
-    }else if num.len() == 2  {
-        let frac  = "0.".to_owned() + num[1];
-        return (num[0].parse::<u128>().unwrap(),frac.parse::<f64>().unwrap())
-    }else {
-        return (0_u128,0_f64)
-    }
+fn parse_ui_amount(ui_amount: &str, decimals: u8) -> Result<u128, Error> {
+    ppl_token::ui_amount_str_to_amount(ui_amount, decimals).map_err(|err| {
+        if err == ppl_token::error::TokenError::Overflow.into() {
+            format!("Amount {} is too large", ui_amount).into()
+        } else {
+            format!(
+                "Invalid amount {}: expected a decimal number with at most {} decimal places",
+                ui_amount, decimals
+            )
+            .into()
+        }
+    })
 }
     
 
+fn token_account_len(config: &Config, token: &Pubkey) -> Result<usize, Error> {
+    if config.sign_only || *token == native_mint::id() {
+        return Ok(Account::LEN);
+    }
+    let mint_account = config.rpc_client.get_account(token)?;
+    Ok(get_account_len_for_mint(&mint_account.data)?)
+}
+
+#[allow(clippy::too_many_arguments)]
 fn command_create_token(
     config: &Config,
     decimals: u8,
     token: Pubkey,
     authority: Pubkey,
     enable_freeze: bool,
+    transfer_fee: Option<(u16, u128)>,
+    interest_rate: Option<i16>,
+    non_transferable: bool,
+    enable_permanent_delegate: bool,
+    default_frozen: bool,
+    enable_close: bool,
     symbol: Option<String>,
     name: Option<String>,
     icon: Option<String>,
     memo: Option<String>,
 ) -> CommandResult {
+    let mut extension_types = vec![];
+    if transfer_fee.is_some() {
+        extension_types.push(ExtensionType::TransferFeeConfig);
+    }
+    if interest_rate.is_some() {
+        extension_types.push(ExtensionType::InterestBearingConfig);
+    }
+    if non_transferable {
+        extension_types.push(ExtensionType::NonTransferable);
+    }
+    if enable_permanent_delegate {
+        extension_types.push(ExtensionType::PermanentDelegate);
+    }
+    if default_frozen {
+        extension_types.push(ExtensionType::DefaultAccountState);
+    }
+    if enable_close {
+        extension_types.push(ExtensionType::MintCloseAuthority);
+    }
+    let mint_len = ExtensionType::get_account_len::<Mint>(&extension_types);
+
     let minimum_balance_for_rent_exemption = if !config.sign_only {
         config
             .rpc_client
-            .get_minimum_balance_for_rent_exemption(Mint::LEN)?
+            .get_minimum_balance_for_rent_exemption(mint_len)?
     } else {
         0
     };
@@ -325,18 +416,78 @@ fn command_create_token(
     let (mint_info, _) =
         Pubkey::find_program_address(&[b"MintMeta", &token.to_bytes()], &ppl_token::id());
 
-    println!("Creating token {}", token);
-    println!("Creating token meta {}", mint_info);
+    println_display(config, format!("Creating token {}", token));
+    println_display(config, format!("Creating token meta {}", mint_info));
 
     let mut instructions = vec![
         system_instruction::create_account(
             &config.fee_payer,
             &token,
             minimum_balance_for_rent_exemption,
-            Mint::LEN as u64,
+            mint_len as u64,
             &ppl_token::id(),
         ),
         create_mint_meta_account(&ppl_token::id(), &config.fee_payer, &token, &mint_info)?,
+    ];
+    if let Some((transfer_fee_basis_points, maximum_fee)) = transfer_fee {
+        println_display(
+            config,
+            format!(
+                "  Transfer fee: {} basis points, at most {}",
+                transfer_fee_basis_points,
+                ppl_token::amount_to_ui_amount(maximum_fee, decimals)
+            ),
+        );
+        instructions.push(initialize_transfer_fee_config(
+            &ppl_token::id(),
+            &token,
+            Some(&authority),
+            Some(&authority),
+            transfer_fee_basis_points,
+            maximum_fee,
+        )?);
+    }
+    if let Some(rate) = interest_rate {
+        println_display(
+            config,
+            format!("  Interest rate: {} basis points per year", rate),
+        );
+        instructions.push(initialize_interest_bearing_mint(
+            &ppl_token::id(),
+            &token,
+            Some(&authority),
+            rate,
+        )?);
+    }
+    if non_transferable {
+        println_display(config, "  Non-transferable".to_string());
+        instructions.push(initialize_non_transferable_mint(&ppl_token::id(), &token)?);
+    }
+    if enable_permanent_delegate {
+        println_display(config, format!("  Permanent delegate: {}", authority));
+        instructions.push(initialize_permanent_delegate(
+            &ppl_token::id(),
+            &token,
+            &authority,
+        )?);
+    }
+    if default_frozen {
+        println_display(config, "  New accounts start frozen".to_string());
+        instructions.push(initialize_default_account_state(
+            &ppl_token::id(),
+            &token,
+            &AccountState::Frozen,
+        )?);
+    }
+    if enable_close {
+        println_display(config, format!("  Close authority: {}", authority));
+        instructions.push(initialize_mint_close_authority(
+            &ppl_token::id(),
+            &token,
+            Some(&authority),
+        )?);
+    }
+    instructions.extend(vec![
         initialize_mint(
             &ppl_token::id(),
             &token,
@@ -352,7 +503,7 @@ fn command_create_token(
             name,
             icon,
         )?,
-    ];
+    ]);
     if let Some(text) = memo {
         instructions.push(ppl_memo::build_memo(text.as_bytes(), &[&config.fee_payer]));
     }
@@ -368,16 +519,17 @@ fn command_create_account(
     owner: Pubkey,
     maybe_account: Option<Pubkey>,
 ) -> CommandResult {
+    let account_len = token_account_len(config, &token)?;
     let minimum_balance_for_rent_exemption = if !config.sign_only {
         config
             .rpc_client
-            .get_minimum_balance_for_rent_exemption(Account::LEN)?
+            .get_minimum_balance_for_rent_exemption(account_len)?
     } else {
         0
     };
 
     let (account, system_account_ok, instructions) = if let Some(account) = maybe_account {
-        println!("Creating account {}", account);
+        println_display(config, format!("Creating account {}", account));
         (
             account,
             false,
@@ -386,7 +538,7 @@ fn command_create_account(
                     &config.fee_payer,
                     &account,
                     minimum_balance_for_rent_exemption,
-                    Account::LEN as u64,
+                    account_len as u64,
                     &ppl_token::id(),
                 ),
                 initialize_account(&ppl_token::id(), &account, &token, &owner)?,
@@ -394,7 +546,7 @@ fn command_create_account(
         )
     } else {
         let account = get_associated_token_address(&owner, &token);
-        println!("Creating account {}", account);
+        println_display(config, format!("Creating account {}", account));
         (
             account,
             true,
@@ -430,11 +582,14 @@ fn command_create_multisig(
     minimum_signers: u8,
     multisig_members: Vec<Pubkey>,
 ) -> CommandResult {
-    println!(
-        "Creating {}/{} multisig {}",
-        minimum_signers,
-        multisig_members.len(),
-        multisig
+    println_display(
+        config,
+        format!(
+            "Creating {}/{} multisig {}",
+            minimum_signers,
+            multisig_members.len(),
+            multisig
+        ),
     );
 
     let minimum_balance_for_rent_exemption = if !config.sign_only {
@@ -466,6 +621,22 @@ fn command_create_multisig(
     )))
 }
 
+/// Name of an authority type as printed by the CLI
+pub(crate) fn authority_type_name(authority_type: &AuthorityType) -> &'static str {
+    match authority_type {
+        AuthorityType::MintTokens => "mint authority",
+        AuthorityType::FreezeAccount => "freeze authority",
+        AuthorityType::AccountOwner => "owner",
+        AuthorityType::CloseAccount => "close authority",
+        AuthorityType::UpdateMeta => "update mint meta authority",
+        AuthorityType::TransferFeeConfig => "transfer fee config authority",
+        AuthorityType::WithheldWithdraw => "withdraw withheld authority",
+        AuthorityType::InterestRate => "interest rate authority",
+        AuthorityType::PermanentDelegate => "permanent delegate",
+    AuthorityType::CloseMint => "mint close authority",
+    }
+}
+
 fn command_authorize(
     config: &Config,
     account: Pubkey,
@@ -474,16 +645,10 @@ fn command_authorize(
     new_authority: Option<Pubkey>,
     force_authorize: bool,
 ) -> CommandResult {
-    let auth_str = match authority_type {
-        AuthorityType::MintTokens => "mint authority",
-        AuthorityType::FreezeAccount => "freeze authority",
-        AuthorityType::AccountOwner => "owner",
-        AuthorityType::CloseAccount => "close authority",
-        AuthorityType::UpdateMeta => "update mint meta authority",
-    };
+    let auth_str = authority_type_name(&authority_type);
     let previous_authority = if !config.sign_only {
         let target_account = config.rpc_client.get_account(&account)?;
-        if let Ok(mint) = Mint::unpack(&target_account.data) {
+        if let Ok(mint) = Mint::unpack_base(&target_account.data) {
             match authority_type {
                 AuthorityType::AccountOwner | AuthorityType::CloseAccount => Err(format!(
                     "Authority type `{}` not supported for PPL Token mints",
@@ -503,8 +668,38 @@ fn command_authorize(
                         Ok(meta_data.authority)
                     }
                 },
+                AuthorityType::TransferFeeConfig | AuthorityType::WithheldWithdraw => {
+                    match get_extension::<TransferFeeConfig>(&target_account.data)? {
+                        Some(transfer_fee_config) => {
+                            if authority_type == AuthorityType::TransferFeeConfig {
+                                Ok(transfer_fee_config.transfer_fee_config_authority)
+                            } else {
+                                Ok(transfer_fee_config.withdraw_withheld_authority)
+                            }
+                        }
+                        None => Err(format!("Mint {} has no transfer fee", account)),
+                    }
+                }
+                AuthorityType::InterestRate => {
+                    match get_extension::<InterestBearingConfig>(&target_account.data)? {
+                        Some(interest_bearing_config) => Ok(interest_bearing_config.rate_authority),
+                        None => Err(format!("Mint {} does not bear interest", account)),
+                    }
+                }
+                AuthorityType::PermanentDelegate => {
+                    match get_extension::<PermanentDelegate>(&target_account.data)? {
+                        Some(permanent_delegate) => Ok(permanent_delegate.delegate),
+                        None => Err(format!("Mint {} has no permanent delegate", account)),
+                    }
+                }
+                AuthorityType::CloseMint => {
+                    match get_extension::<MintCloseAuthority>(&target_account.data)? {
+                        Some(mint_close_authority) => Ok(mint_close_authority.close_authority),
+                        None => Err(format!("Mint {} has no close authority", account)),
+                    }
+                }
             }
-        } else if let Ok(token_account) = Account::unpack(&target_account.data) {
+        } else if let Ok(token_account) = Account::unpack_base(&target_account.data) {
             let check_associated_token_account = || -> Result<(), Error> {
                 let maybe_associated_token_account =
                     get_associated_token_address(&token_account.owner, &token_account.mint);
@@ -523,7 +718,14 @@ fn command_authorize(
             };
 
             match authority_type {
-                AuthorityType::MintTokens | AuthorityType::FreezeAccount | AuthorityType::UpdateMeta => Err(format!(
+                AuthorityType::MintTokens
+                | AuthorityType::FreezeAccount
+                | AuthorityType::UpdateMeta
+                | AuthorityType::TransferFeeConfig
+                | AuthorityType::WithheldWithdraw
+                | AuthorityType::InterestRate
+                | AuthorityType::PermanentDelegate
+                | AuthorityType::CloseMint => Err(format!(
                     "Authority type `{}` not supported for PPL Token accounts",
                     auth_str
                 )),
@@ -544,30 +746,43 @@ fn command_authorize(
     } else {
         COption::None
     };
-    println!(
-        "Updating {}\n  Current {}: {}\n  New {}: {}",
-        account,
-        auth_str,
-        previous_authority
-            .map(|pubkey| pubkey.to_string())
-            .unwrap_or_else(|| "disabled".to_string()),
-        auth_str,
-        new_authority
-            .map(|pubkey| pubkey.to_string())
-            .unwrap_or_else(|| "disabled".to_string())
+    println_display(
+        config,
+        format!(
+            "Updating {}\n  Current {}: {}\n  New {}: {}",
+            account,
+            auth_str,
+            previous_authority
+                .map(|pubkey| pubkey.to_string())
+                .unwrap_or_else(|| "disabled".to_string()),
+            auth_str,
+            new_authority
+                .map(|pubkey| pubkey.to_string())
+                .unwrap_or_else(|| "disabled".to_string())
+        ),
     );
 
-    let instructions = vec![set_authority(
+    let instructions = vec![with_instructions_sysvar(set_authority(
         &ppl_token::id(),
         &account,
         new_authority.as_ref(),
         authority_type,
         &authority,
         &config.multisigner_pubkeys,
-    )?];
+    )?)];
     Ok(Some((0, vec![instructions])))
 }
 
+/// Appends the instructions sysvar to a token instruction, letting the program check
+/// that it runs at the top level for accounts with a CPI guard, and find the memo
+/// required by some recipients
+fn with_instructions_sysvar(mut instruction: Instruction) -> Instruction {
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(sysvar::instructions::id(), false));
+    instruction
+}
+
 fn resolve_mint_info(
     config: &Config,
     token_account: &Pubkey,
@@ -601,7 +816,7 @@ fn resolve_mint_info(
 fn validate_mint(config: &Config, token: Pubkey) -> Result<(), Error> {
     let mint = config.rpc_client.get_account(&token);
 
-    if mint.is_err() || Mint::unpack(&mint.unwrap().data).is_err() {
+    if mint.is_err() || Mint::unpack_base(&mint.unwrap().data).is_err() {
         return Err(format!("Invalid mint account {:?}", token).into());
     }
     Ok(())
@@ -612,7 +827,7 @@ fn validate_mint_meta_auth(config: &Config, token_meta: &Pubkey, auth: &Pubkey)
     if mint_meta.is_err() {
         return Err(format!("Invalid mint_meta account {:?}", token_meta).into());
     }
-    let meta_account = MintMeta::unpack(&mint_meta.unwrap().data)?;
+    let meta_account = MintMeta::unpack(get_mint_meta_base(&mint_meta.unwrap().data)?)?;
     if let COption::Some(meta_auth) = meta_account.authority {
         if meta_auth != *auth {
             return Err(format!(
@@ -639,6 +854,7 @@ fn command_transfer(
     fund_recipient: bool,
     mint_decimals: Option<u8>,
     recipient_is_ata_owner: bool,
+    memo: Option<String>,
 ) -> CommandResult {
     let sender = if let Some(sender) = sender {
         sender
@@ -646,11 +862,9 @@ fn command_transfer(
         get_associated_token_address(&sender_owner, &token)
     };
     let (mint_pubkey, decimals) = resolve_mint_info(config, &sender, Some(token), mint_decimals)?;
-    let maybe_transfer_balance =
-        ui_amount.map(|ui_amount| {
-            let (inte,frac) = parse_amount(ui_amount.as_str());
-            ppl_token::ui_amount_to_amount(inte,frac, decimals)
-        });
+    let maybe_transfer_balance = ui_amount
+        .map(|ui_amount| parse_ui_amount(&ui_amount, decimals))
+        .transpose()?;
     let transfer_balance = if !config.sign_only {
         let sender_token_amount = config
             .rpc_client
@@ -669,11 +883,14 @@ fn command_transfer(
         })?;
 
         let transfer_balance = maybe_transfer_balance.unwrap_or(sender_balance);
-        println!(
-            "Transfer {} tokens\n  Sender: {}\n  Recipient: {}",
-            ppl_token::amount_to_ui_amount(transfer_balance, decimals),
-            sender,
-            recipient
+        println_display(
+            config,
+            format!(
+                "Transfer {} tokens\n  Sender: {}\n  Recipient: {}",
+                ppl_token::amount_to_ui_amount(transfer_balance, decimals),
+                sender,
+                recipient
+            ),
         );
 
         if transfer_balance > sender_balance {
@@ -698,7 +915,10 @@ fn command_transfer(
             .rpc_client
             .get_account_with_commitment(&recipient, config.rpc_client.commitment())?
             .value
-            .map(|account| account.owner == ppl_token::id() && account.data.len() == Account::LEN);
+            .map(|account| {
+                account.owner == ppl_token::id()
+                    && get_account_type(&account.data) == Ok(AccountType::Account)
+            });
 
         if recipient_account_info.is_none() && !allow_unfunded_recipient {
             return Err("Error: The recipient address is not funded. \
@@ -714,9 +934,12 @@ fn command_transfer(
 
     if !recipient_is_token_account {
         recipient_token_account = get_associated_token_address(&recipient, &mint_pubkey);
-        println!(
-            "  Recipient associated token account: {}",
-            recipient_token_account
+        println_display(
+            config,
+            format!(
+                "  Recipient associated token account: {}",
+                recipient_token_account
+            ),
         );
 
         let needs_funding = if !config.sign_only {
@@ -749,11 +972,17 @@ fn command_transfer(
                 if !config.sign_only {
                     minimum_balance_for_rent_exemption += config
                         .rpc_client
-                        .get_minimum_balance_for_rent_exemption(Account::LEN)?;
-                    println!(
-                        "  Funding recipient: {} ({} PUT)",
-                        recipient_token_account,
-                        lamports_to_put(minimum_balance_for_rent_exemption)
+                        .get_minimum_balance_for_rent_exemption(token_account_len(
+                            config,
+                            &mint_pubkey,
+                        )?)?;
+                    println_display(
+                        config,
+                        format!(
+                            "  Funding recipient: {} ({} PUT)",
+                            recipient_token_account,
+                            lamports_to_put(minimum_balance_for_rent_exemption)
+                        ),
                     );
                 }
                 instructions.push(create_associated_token_account(
@@ -771,7 +1000,10 @@ fn command_transfer(
         }
     }
 
-    instructions.push(transfer_checked(
+    if let Some(text) = memo {
+        instructions.push(ppl_memo::build_memo(text.as_bytes(), &[]));
+    }
+    instructions.push(with_instructions_sysvar(transfer_checked(
         &ppl_token::id(),
         &sender,
         &mint_pubkey,
@@ -780,7 +1012,7 @@ fn command_transfer(
         &config.multisigner_pubkeys,
         transfer_balance,
         decimals,
-    )?);
+    )?));
     Ok(Some((
         minimum_balance_for_rent_exemption,
         vec![instructions],
@@ -795,11 +1027,13 @@ fn command_burn(
     mint_address: Option<Pubkey>,
     mint_decimals: Option<u8>,
 ) -> CommandResult {
-    println!("Burn {} tokens\n  Source: {}", ui_amount, source);
+    println_display(
+        config,
+        format!("Burn {} tokens\n  Source: {}", ui_amount, source),
+    );
 
     let (mint_pubkey, decimals) = resolve_mint_info(config, &source, mint_address, mint_decimals)?;
-    let (inte,frac) = parse_amount(ui_amount.as_str());
-    let amount = ppl_token::ui_amount_to_amount(inte,frac, decimals);
+    let amount = parse_ui_amount(&ui_amount, decimals)?;
 
     let instructions = vec![burn_checked(
         &ppl_token::id(),
@@ -821,16 +1055,17 @@ fn command_mint(
     mint_decimals: Option<u8>,
     mint_authority: Pubkey,
 ) -> CommandResult {
-    println!(
-        "Minting {} tokens\n  Token: {}\n  Recipient: {}",
-        ui_amount, token, recipient
+    println_display(
+        config,
+        format!(
+            "Minting {} tokens\n  Token: {}\n  Recipient: {}",
+            ui_amount, token, recipient
+        ),
     );
 
     let (_, decimals) = resolve_mint_info(config, &recipient, None, mint_decimals)?;
 
-    let (inte,frac) = parse_amount(ui_amount.as_str());
-
-    let amount = ppl_token::ui_amount_to_amount(inte,frac, decimals);
+    let amount = parse_ui_amount(&ui_amount, decimals)?;
 
     if amount == 0 {
         return Err(format!("Minting Token amount too small ,  token decimals is {}", decimals).into());
@@ -856,7 +1091,10 @@ fn command_freeze(
 ) -> CommandResult {
     let (token, _) = resolve_mint_info(config, &account, mint_address, None)?;
 
-    println!("Freezing account: {}\n  Token: {}", account, token);
+    println_display(
+        config,
+        format!("Freezing account: {}\n  Token: {}", account, token),
+    );
 
     let instructions = vec![freeze_account(
         &ppl_token::id(),
@@ -876,7 +1114,10 @@ fn command_thaw(
 ) -> CommandResult {
     let (token, _) = resolve_mint_info(config, &account, mint_address, None)?;
 
-    println!("Freezing account: {}\n  Token: {}", account, token);
+    println_display(
+        config,
+        format!("Freezing account: {}\n  Token: {}", account, token),
+    );
 
     let instructions = vec![thaw_account(
         &ppl_token::id(),
@@ -888,20 +1129,224 @@ fn command_thaw(
     Ok(Some((0, vec![instructions])))
 }
 
+fn command_required_transfer_memos(
+    config: &Config,
+    account: Pubkey,
+    owner: Pubkey,
+    enable: bool,
+) -> CommandResult {
+    let instruction = if enable {
+        println_display(
+            config,
+            format!("Requiring memos on transfers into {}", account),
+        );
+        enable_required_transfer_memos(
+            &ppl_token::id(),
+            &account,
+            &owner,
+            &config.multisigner_pubkeys,
+        )?
+    } else {
+        println_display(
+            config,
+            format!("No longer requiring memos on transfers into {}", account),
+        );
+        disable_required_transfer_memos(
+            &ppl_token::id(),
+            &account,
+            &owner,
+            &config.multisigner_pubkeys,
+        )?
+    };
+    Ok(Some((0, vec![vec![instruction]])))
+}
+
+fn command_cpi_guard(
+    config: &Config,
+    account: Pubkey,
+    owner: Pubkey,
+    enable: bool,
+) -> CommandResult {
+    let instruction = if enable {
+        println_display(config, format!("Enabling CPI guard on {}", account));
+        enable_cpi_guard(
+            &ppl_token::id(),
+            &account,
+            &owner,
+            &config.multisigner_pubkeys,
+        )?
+    } else {
+        println_display(config, format!("Disabling CPI guard on {}", account));
+        disable_cpi_guard(
+            &ppl_token::id(),
+            &account,
+            &owner,
+            &config.multisigner_pubkeys,
+        )?
+    };
+    Ok(Some((0, vec![vec![instruction]])))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn command_create_vesting(
+    config: &Config,
+    token: Pubkey,
+    ui_amount: String,
+    recipient: Pubkey,
+    source: Option<Pubkey>,
+    source_owner: Pubkey,
+    grant: Pubkey,
+    schedule: VestingSchedule,
+    revoke_authority: Option<Pubkey>,
+    mint_decimals: Option<u8>,
+) -> CommandResult {
+    if !schedule.is_valid() {
+        return Err("The cliff must fall between the start and the end of the schedule".into());
+    }
+    let source = source.unwrap_or_else(|| get_associated_token_address(&source_owner, &token));
+    let (mint_pubkey, decimals) = resolve_mint_info(config, &source, Some(token), mint_decimals)?;
+    let amount = parse_ui_amount(&ui_amount, decimals)?;
+
+    println_display(
+        config,
+        format!(
+            "Creating vesting grant {}\n  Recipient: {}\n  Amount: {}\n  Vault: {}",
+            grant,
+            recipient,
+            ui_amount,
+            ppl_vesting::get_vault_address(&grant)
+        ),
+    );
+    if let Some(revoke_authority) = revoke_authority {
+        println_display(config, format!("  Revoke authority: {}", revoke_authority));
+    }
+
+    let minimum_balance_for_rent_exemption = if !config.sign_only {
+        config
+            .rpc_client
+            .get_minimum_balance_for_rent_exemption(VestingGrant::LEN)?
+            + config
+                .rpc_client
+                .get_minimum_balance_for_rent_exemption(token_account_len(config, &token)?)?
+    } else {
+        0
+    };
+
+    let instructions = vec![ppl_vesting::instruction::create(
+        &config.fee_payer,
+        &grant,
+        &mint_pubkey,
+        &source,
+        &source_owner,
+        &recipient,
+        schedule,
+        amount,
+        revoke_authority,
+    )];
+    Ok(Some((
+        minimum_balance_for_rent_exemption,
+        vec![instructions],
+    )))
+}
+
+fn get_vesting_grant(config: &Config, grant: &Pubkey) -> Result<VestingGrant, Error> {
+    let account = config.rpc_client.get_account(grant)?;
+    if account.owner != ppl_vesting::id() {
+        return Err(format!("Account {} is not a vesting grant", grant).into());
+    }
+    Ok(VestingGrant::unpack(&account.data)?)
+}
+
+fn command_claim_vesting(
+    config: &Config,
+    grant: Pubkey,
+    recipient: Pubkey,
+    destination: Option<Pubkey>,
+) -> CommandResult {
+    let vesting_grant = get_vesting_grant(config, &grant)?;
+    if vesting_grant.recipient != recipient {
+        return Err(format!(
+            "Recipient of vesting grant {} is {}, not {}",
+            grant, vesting_grant.recipient, recipient
+        )
+        .into());
+    }
+    let destination = destination
+        .unwrap_or_else(|| get_associated_token_address(&recipient, &vesting_grant.mint));
+
+    println_display(
+        config,
+        format!(
+            "Claiming vested tokens from {}\n  Destination: {}",
+            grant, destination
+        ),
+    );
+    let instructions = vec![ppl_vesting::instruction::claim(
+        &grant,
+        &vesting_grant.mint,
+        &destination,
+        &recipient,
+    )];
+    Ok(Some((0, vec![instructions])))
+}
+
+fn command_revoke_vesting(
+    config: &Config,
+    grant: Pubkey,
+    revoke_authority: Pubkey,
+    destination: Option<Pubkey>,
+) -> CommandResult {
+    let vesting_grant = get_vesting_grant(config, &grant)?;
+    match vesting_grant.revoke_authority {
+        None => return Err(format!("Vesting grant {} is not revocable", grant).into()),
+        Some(authority) if authority != revoke_authority => {
+            return Err(format!(
+                "Revoke authority of vesting grant {} is {}, not {}",
+                grant, authority, revoke_authority
+            )
+            .into())
+        }
+        Some(_) => {}
+    }
+    if vesting_grant.revoked_at.is_some() {
+        return Err(format!("Vesting grant {} is already revoked", grant).into());
+    }
+    let destination = destination
+        .unwrap_or_else(|| get_associated_token_address(&revoke_authority, &vesting_grant.mint));
+
+    println_display(
+        config,
+        format!(
+            "Revoking vesting grant {}\n  Destination: {}",
+            grant, destination
+        ),
+    );
+    let instructions = vec![ppl_vesting::instruction::revoke(
+        &grant,
+        &vesting_grant.mint,
+        &destination,
+        &revoke_authority,
+    )];
+    Ok(Some((0, vec![instructions])))
+}
+
 fn command_wrap(
     config: &Config,
     put: String,
     wallet_address: Pubkey,
     wrapped_put_account: Option<Pubkey>,
 ) -> CommandResult {
-    let lamports = put_to_lamports(&put);
+    let lamports = parse_ui_amount(&put, native_mint::DECIMALS)?;
 
     if lamports == 0 {
        return Err(format!("Wrap PUT amount too small ,  PUT decimals is 9.").into());
     }
 
     let instructions = if let Some(wrapped_put_account) = wrapped_put_account {
-        println!("Wrapping {} PUT into {}", put, wrapped_put_account);
+        println_display(
+            config,
+            format!("Wrapping {} PUT into {}", put, wrapped_put_account),
+        );
         vec![
             system_instruction::create_account(
                 &wallet_address,
@@ -920,23 +1365,40 @@ fn command_wrap(
     } else {
         let account = get_associated_token_address(&wallet_address, &native_mint::id());
 
+        let mut account_exists = false;
         if !config.sign_only {
             if let Some(account_data) = config
                 .rpc_client
                 .get_account_with_commitment(&account, config.rpc_client.commitment())?
                 .value
             {
-                if account_data.owner != system_program::id() {
+                if account_data.owner == ppl_token::id() {
+                    account_exists = true;
+                } else if account_data.owner != system_program::id() {
                     return Err(format!("Error: Account already exists: {}", account).into());
                 }
             }
         }
 
-        println!("Wrapping {} PUT into {}", put, account);
-        vec![
-            system_instruction::transfer(&wallet_address, &account, lamports),
-            create_associated_token_account(&config.fee_payer, &wallet_address, &native_mint::id()),
-        ]
+        println_display(config, format!("Wrapping {} PUT into {}", put, account));
+        if account_exists {
+            // Top up the existing account rather than creating another one
+            vec![deposit_native(
+                &ppl_token::id(),
+                &account,
+                &wallet_address,
+                lamports,
+            )?]
+        } else {
+            vec![
+                system_instruction::transfer(&wallet_address, &account, lamports),
+                create_associated_token_account(
+                    &config.fee_payer,
+                    &wallet_address,
+                    &native_mint::id(),
+                ),
+            ]
+        }
     };
     if !config.sign_only {
         check_wallet_balance(config, &wallet_address, lamports)?;
@@ -948,31 +1410,65 @@ fn command_unwrap(
     config: &Config,
     wallet_address: Pubkey,
     address: Option<Pubkey>,
+    put: Option<String>,
 ) -> CommandResult {
     let use_associated_account = address.is_none();
     let address = address
         .unwrap_or_else(|| get_associated_token_address(&wallet_address, &native_mint::id()));
-    println!("Unwrapping {}", address);
+    let lamports = put
+        .map(|put| parse_ui_amount(&put, native_mint::DECIMALS))
+        .transpose()?;
+    println_display(config, format!("Unwrapping {}", address));
     if !config.sign_only {
-        let lamports = config.rpc_client.get_balance(&address)?;
-        if lamports == 0 {
+        let balance = config.rpc_client.get_balance(&address)?;
+        if balance == 0 {
             if use_associated_account {
                 return Err("No wrapped PUT in associated account; did you mean to specify an auxiliary address?".to_string().into());
             } else {
                 return Err(format!("No wrapped PUT in {}", address).into());
             }
         }
-        println!("  Amount: {} PUT", lamports_to_put(lamports),);
+        if let Some(lamports) = lamports {
+            let account = Account::unpack_base(&config.rpc_client.get_account_data(&address)?)?;
+            if account.amount < lamports {
+                return Err(format!(
+                    "Error: Insufficient wrapped PUT in {}: {} required, {} available",
+                    address,
+                    lamports_to_put(lamports),
+                    lamports_to_put(account.amount)
+                )
+                .into());
+            }
+        }
+        println_display(
+            config,
+            format!(
+                "  Amount: {} PUT",
+                lamports_to_put(lamports.unwrap_or(balance))
+            ),
+        );
     }
-    println!("  Recipient: {}", &wallet_address);
+    println_display(config, format!("  Recipient: {}", &wallet_address));
 
-    let instructions = vec![close_account(
-        &ppl_token::id(),
-        &address,
-        &wallet_address,
-        &wallet_address,
-        &config.multisigner_pubkeys,
-    )?];
+    // Unwrapping part of the balance keeps the account open
+    let instructions = if let Some(lamports) = lamports {
+        vec![with_instructions_sysvar(withdraw_native(
+            &ppl_token::id(),
+            &address,
+            &wallet_address,
+            &wallet_address,
+            &config.multisigner_pubkeys,
+            lamports,
+        )?)]
+    } else {
+        vec![close_account(
+            &ppl_token::id(),
+            &address,
+            &wallet_address,
+            &wallet_address,
+            &config.multisigner_pubkeys,
+        )?]
+    };
     Ok(Some((0, vec![instructions])))
 }
 
@@ -985,16 +1481,18 @@ fn command_approve(
     mint_address: Option<Pubkey>,
     mint_decimals: Option<u8>,
 ) -> CommandResult {
-    println!(
-        "Approve {} tokens\n  Account: {}\n  Delegate: {}",
-        ui_amount, account, delegate
+    println_display(
+        config,
+        format!(
+            "Approve {} tokens\n  Account: {}\n  Delegate: {}",
+            ui_amount, account, delegate
+        ),
     );
 
     let (mint_pubkey, decimals) = resolve_mint_info(config, &account, mint_address, mint_decimals)?;
-    let (inte,frac) = parse_amount(ui_amount.as_str());
-    let amount = ppl_token::ui_amount_to_amount(inte,frac, decimals);
+    let amount = parse_ui_amount(&ui_amount, decimals)?;
 
-    let instructions = vec![approve_checked(
+    let instructions = vec![with_instructions_sysvar(approve_checked(
         &ppl_token::id(),
         &account,
         &mint_pubkey,
@@ -1003,7 +1501,7 @@ fn command_approve(
         &config.multisigner_pubkeys,
         amount,
         decimals,
-    )?];
+    )?)];
     Ok(Some((0, vec![instructions])))
 }
 
@@ -1029,9 +1527,12 @@ fn command_revoke(
     };
 
     if let Some(delegate) = delegate {
-        println!(
-            "Revoking approval\n  Account: {}\n  Delegate: {}",
-            account, delegate
+        println_display(
+            config,
+            format!(
+                "Revoking approval\n  Account: {}\n  Delegate: {}",
+                account, delegate
+            ),
         );
     } else {
         return Err(format!("No delegate on account {}", account).into());
@@ -1078,12 +1579,42 @@ fn command_close(
         }
     }
 
-    let instructions = vec![close_account(
+    let instructions = vec![with_instructions_sysvar(close_account(
         &ppl_token::id(),
         &account,
         &recipient,
         &close_authority,
         &config.multisigner_pubkeys,
+    )?)];
+    Ok(Some((0, vec![instructions])))
+}
+
+fn command_close_mint(
+    config: &Config,
+    token: Pubkey,
+    close_authority: Pubkey,
+    recipient: Pubkey,
+) -> CommandResult {
+    if !config.sign_only {
+        let mint = config.rpc_client.get_account(&token)?;
+        let supply = Mint::unpack_base(&mint.data)
+            .map_err(|_| format!("Could not find token mint {}", token))?
+            .supply;
+        if supply > 0 {
+            return Err(format!(
+                "Mint {} still has a supply of {}; burn all tokens in order to close it.",
+                token, supply
+            )
+            .into());
+        }
+    }
+
+    let instructions = vec![close_mint(
+        &ppl_token::id(),
+        &token,
+        &recipient,
+        &close_authority,
+        &config.multisigner_pubkeys,
     )?];
     Ok(Some((0, vec![instructions])))
 }
@@ -1093,21 +1624,21 @@ fn command_balance(config: &Config, address: Pubkey) -> CommandResult {
         .rpc_client
         .get_token_account_balance(&address)
         .map_err(|_| format!("Could not find token account {}", address))?;
-
-    if config.verbose {
-        println!("ui amount: {}", balance.real_number_string_trimmed());
-        println!("decimals: {}", balance.decimals);
-        println!("amount: {}", balance.amount);
-    } else {
-        println!("{}", balance.real_number_string_trimmed());
-    }
+    let cli_token_amount = CliTokenAmount { amount: balance };
+    println!(
+        "{}",
+        config.output_format.formatted_string(&cli_token_amount)
+    );
     Ok(None)
 }
 
 fn command_supply(config: &Config, address: Pubkey) -> CommandResult {
     let supply = config.rpc_client.get_token_supply(&address)?;
-
-    println!("{}", supply.real_number_string_trimmed());
+    let cli_token_amount = CliTokenAmount { amount: supply };
+    println!(
+        "{}",
+        config.output_format.formatted_string(&cli_token_amount)
+    );
     Ok(None)
 }
 
@@ -1122,129 +1653,180 @@ fn command_accounts(config: &Config, token: Option<Pubkey>, owner: Pubkey) -> Co
             None => TokenAccountsFilter::ProgramId(ppl_token::id()),
         },
     )?;
-    if accounts.is_empty() {
-        println!("None");
-        return Ok(None);
-    }
 
     let (mint_accounts, unsupported_accounts, max_len_balance, includes_aux) =
         sort_and_parse_token_accounts(&owner, accounts);
     let aux_len = if includes_aux { 10 } else { 0 };
-    let mut gc_alert = false;
 
-    if config.verbose {
-        if token.is_some() {
-            println!("{:<44}  {:<2$}", "Account", "Balance", max_len_balance);
-            println!("-------------------------------------------------------------");
-        } else {
-            println!(
-                "{:<44}  {:<44}  {:<3$}",
-                "Token", "Account", "Balance", max_len_balance
-            );
-            println!("----------------------------------------------------------------------------------------------------------");
-        }
-    } else if token.is_some() {
-        println!("{:<1$}", "Balance", max_len_balance);
-        println!("-------------");
-    } else {
-        println!("{:<44}  {:<2$}", "Token", "Balance", max_len_balance);
-        println!("---------------------------------------------------------------");
-    }
-    for (_mint, accounts_list) in mint_accounts.iter() {
-        let mut aux_counter = 1;
-        for account in accounts_list {
-            let maybe_aux = if !account.is_associated {
-                gc_alert = true;
-                let message = format!("  (Aux-{}*)", aux_counter);
-                aux_counter += 1;
-                message
-            } else {
-                "".to_string()
-            };
-            let maybe_frozen = if let UiAccountState::Frozen = account.ui_token_account.state {
-                format!(" {}  Frozen", WARNING)
-            } else {
-                "".to_string()
-            };
-            if config.verbose {
-                if token.is_some() {
-                    println!(
-                        "{:<44}  {:<4$}{:<5$}{}",
-                        account.address,
-                        account
-                            .ui_token_account
-                            .token_amount
-                            .real_number_string_trimmed(),
-                        maybe_aux,
-                        maybe_frozen,
-                        max_len_balance,
-                        aux_len,
-                    )
-                } else {
-                    println!(
-                        "{:<44}  {:<44}  {:<5$}{:<6$}{}",
-                        account.ui_token_account.mint,
-                        account.address,
-                        account
-                            .ui_token_account
-                            .token_amount
-                            .real_number_string_trimmed(),
-                        maybe_aux,
-                        maybe_frozen,
-                        max_len_balance,
-                        aux_len,
-                    )
-                }
-            } else if token.is_some() {
-                println!(
-                    "{:<3$}{:<4$}{}",
-                    account
-                        .ui_token_account
-                        .token_amount
-                        .real_number_string_trimmed(),
-                    maybe_aux,
-                    maybe_frozen,
-                    max_len_balance,
-                    aux_len,
-                )
-            } else {
-                println!(
-                    "{:<44}  {:<4$}{:<5$}{}",
-                    account.ui_token_account.mint,
-                    account
-                        .ui_token_account
-                        .token_amount
-                        .real_number_string_trimmed(),
-                    maybe_aux,
-                    maybe_frozen,
-                    max_len_balance,
-                    aux_len,
-                )
-            }
+    let cli_token_accounts = CliTokenAccounts {
+        accounts: mint_accounts.into_values().collect(),
+        unsupported_accounts,
+        max_len_balance,
+        aux_len,
+        token_is_some: token.is_some(),
+    };
+    println!(
+        "{}",
+        config.output_format.formatted_string(&cli_token_accounts)
+    );
+    Ok(None)
+}
+
+fn command_holders(
+    config: &Config,
+    token: Pubkey,
+    include_empty: bool,
+    csv_file: Option<&str>,
+) -> CommandResult {
+    let mint_account = config.rpc_client.get_account(&token)?;
+    let mint = Mint::unpack_base(&mint_account.data)
+        .map_err(|_| format!("Invalid mint account {:?}", token))?;
+
+    // The mint is the first field of the `Account` layout
+    let accounts = config.rpc_client.get_program_accounts_with_config(
+        &ppl_token::id(),
+        RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::Memcmp(Memcmp {
+                offset: 0,
+                bytes: MemcmpEncodedBytes::Base58(token.to_string()),
+                encoding: None,
+            })]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        },
+    )?;
+
+    // Fees withheld from transfers are still part of the supply, held on the mint once harvested
+    let mint_withheld = get_extension::<TransferFeeConfig>(&mint_account.data)?
+        .map_or(0, |transfer_fee_config| transfer_fee_config.withheld_amount);
+    let mut total: u128 = mint_withheld;
+    let mut withheld: u128 = mint_withheld;
+    let mut holdings: HashMap<Pubkey, (u128, u128, Vec<CliHolderAccount>)> = HashMap::new();
+    for (address, account) in accounts {
+        if get_account_type(&account.data) != Ok(AccountType::Account) {
+            continue;
         }
+        let token_account = match Account::unpack_base(&account.data) {
+            Ok(token_account) if token_account.mint == token => token_account,
+            _ => continue,
+        };
+        let withheld_amount = get_extension::<TransferFeeAmount>(&account.data)?
+            .map_or(0, |transfer_fee_amount| transfer_fee_amount.withheld_amount);
+        total += token_account.amount + withheld_amount;
+        withheld += withheld_amount;
+        let holding = holdings
+            .entry(token_account.owner)
+            .or_insert_with(|| (0, 0, vec![]));
+        holding.0 += token_account.amount;
+        holding.1 += withheld_amount;
+        let delegate: Option<Pubkey> = token_account.delegate.into();
+        holding.2.push(CliHolderAccount {
+            address: address.to_string(),
+            amount: token_account.amount.to_string(),
+            ui_amount: ppl_token::amount_to_ui_amount(token_account.amount, mint.decimals),
+            withheld_amount: if withheld_amount > 0 {
+                Some(withheld_amount.to_string())
+            } else {
+                None
+            },
+            frozen: token_account.is_frozen(),
+            delegate: delegate.map(|delegate| delegate.to_string()),
+            delegated_amount: delegate.map(|_| {
+                ppl_token::amount_to_ui_amount(token_account.delegated_amount, mint.decimals)
+            }),
+        });
     }
-    for unsupported_account in unsupported_accounts {
-        println!(
-            "{:<44}  {}",
-            unsupported_account.address, unsupported_account.err
+
+    let mut holdings = holdings
+        .into_iter()
+        .filter(|(_, (amount, withheld_amount, _))| {
+            include_empty || *amount > 0 || *withheld_amount > 0
+        })
+        .collect::<Vec<_>>();
+    holdings.sort_by(|(owner_a, (amount_a, _, _)), (owner_b, (amount_b, _, _))| {
+        amount_b.cmp(amount_a).then(owner_a.cmp(owner_b))
+    });
+    let holders = holdings
+        .into_iter()
+        .map(|(owner, (amount, withheld_amount, accounts))| CliHolder {
+            owner: owner.to_string(),
+            amount: amount.to_string(),
+            ui_amount: ppl_token::amount_to_ui_amount(amount, mint.decimals),
+            withheld_amount: withheld_amount.to_string(),
+            accounts,
+        })
+        .collect::<Vec<_>>();
+
+    if let Some(csv_file) = csv_file {
+        let mut csv =
+            "owner,amount,ui_amount,accounts,frozen_amount,delegates,withheld_amount\n".to_string();
+        for holder in &holders {
+            let frozen_amount: u128 = holder
+                .accounts
+                .iter()
+                .filter(|account| account.frozen)
+                .map(|account| account.amount.parse::<u128>().unwrap())
+                .sum();
+            let delegates = holder
+                .accounts
+                .iter()
+                .filter_map(|account| account.delegate.clone())
+                .collect::<Vec<_>>();
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{}\n",
+                holder.owner,
+                holder.amount,
+                holder.ui_amount,
+                holder.accounts.len(),
+                frozen_amount,
+                delegates.join(";"),
+                holder.withheld_amount,
+            ));
+        }
+        fs::write(csv_file, csv).map_err(|err| format!("Could not write {}: {}", csv_file, err))?;
+        println_display(
+            config,
+            format!("Wrote {} holders to {}", holders.len(), csv_file),
         );
     }
-    if gc_alert {
-        println!();
-        println!("* Please run `ppl-token gc` to clean up Aux accounts");
-    }
+
+    let cli_holders = CliHolders {
+        mint: token.to_string(),
+        decimals: mint.decimals,
+        supply: mint.supply.to_string(),
+        ui_supply: ppl_token::amount_to_ui_amount(mint.supply, mint.decimals),
+        total: total.to_string(),
+        ui_total: ppl_token::amount_to_ui_amount(total, mint.decimals),
+        withheld: withheld.to_string(),
+        ui_withheld: ppl_token::amount_to_ui_amount(withheld, mint.decimals),
+        mint_withheld: mint_withheld.to_string(),
+        reconciled: total == mint.supply,
+        max_len_balance: holders
+            .iter()
+            .map(|holder| holder.ui_amount.len())
+            .max()
+            .unwrap_or_default()
+            .max("Balance".len()),
+        holders,
+    };
+    println!("{}", config.output_format.formatted_string(&cli_holders));
     Ok(None)
 }
 
 fn command_address(config: &Config, token: Option<Pubkey>, owner: Pubkey) -> CommandResult {
+    let mut cli_address = CliWalletAddress {
+        wallet_address: owner.to_string(),
+        associated_token_address: None,
+    };
     if let Some(token) = token {
         validate_mint(config, token)?;
         let associated_token_address = get_associated_token_address(&owner, &token);
-        println!("Wallet address: {:?}", owner);
-        println!("Associated token address: {:?}", associated_token_address);
-    } else {
-        println!("Wallet address: {:?}", owner);
+        cli_address.associated_token_address = Some(associated_token_address.to_string());
     }
+    println!("{}", config.output_format.formatted_string(&cli_address));
     Ok(None)
 }
 
@@ -1257,41 +1839,15 @@ fn command_account_info(config: &Config, address: Pubkey) -> CommandResult {
     let mint = Pubkey::from_str(&account.mint).unwrap();
     let owner = Pubkey::from_str(&account.owner).unwrap();
     let is_associated = get_associated_token_address(&owner, &mint) == address;
-    let address_message = if is_associated {
-        address.to_string()
-    } else {
-        format!("{}  (Aux*)", address)
+    let cli_token_account = CliTokenAccount {
+        address: address.to_string(),
+        is_associated,
+        account,
     };
-    println!();
-    println_name_value("Address:", &address_message);
-    println_name_value(
-        "Balance:",
-        &account.token_amount.real_number_string_trimmed(),
-    );
-    let mint = format!(
-        "{}{}",
-        account.mint,
-        if account.is_native { " (native)" } else { "" }
-    );
-    println_name_value("Mint:", &mint);
-    println_name_value("Owner:", &account.owner);
-    println_name_value("State:", &format!("{:?}", account.state));
-    if let Some(delegate) = &account.delegate {
-        println!("Delegation:");
-        println_name_value("  Delegate:", delegate);
-        let allowance = account.delegated_amount.as_ref().unwrap();
-        println_name_value("  Allowance:", &allowance.real_number_string_trimmed());
-    } else {
-        println_name_value("Delegation:", "");
-    }
-    println_name_value(
-        "Close authority:",
-        account.close_authority.as_ref().unwrap_or(&String::new()),
+    println!(
+        "{}",
+        config.output_format.formatted_string(&cli_token_account)
     );
-    if !is_associated {
-        println!();
-        println!("* Please run `ppl-token gc` to clean up Aux accounts");
-    }
     Ok(None)
 }
 
@@ -1304,26 +1860,28 @@ fn command_multisig(config: &Config, address: Pubkey) -> CommandResult {
     let multisig = get_multisig(config, &address)?;
     let n = multisig.n as usize;
     assert!(n <= multisig.signers.len());
-    println!();
-    println_name_value("Address:", &address.to_string());
-    println_name_value("M/N:", &format!("{}/{}", multisig.m, n));
-    println_name_value("Signers:", " ");
-    let width = if n >= 9 { 4 } else { 3 };
-    for i in 0..n {
-        let title = format!("{1:>0$}:", width, i + 1);
-        let pubkey = &multisig.signers[i];
-        println_name_value(&title, &pubkey.to_string())
-    }
+    let cli_multisig = CliMultisig {
+        address: address.to_string(),
+        m: multisig.m,
+        n: multisig.n,
+        signers: multisig
+            .signers
+            .iter()
+            .take(n)
+            .map(|signer| signer.to_string())
+            .collect(),
+    };
+    println!("{}", config.output_format.formatted_string(&cli_multisig));
     Ok(None)
 }
 
 fn command_gc(config: &Config, owner: Pubkey) -> CommandResult {
-    println!("Fetching token accounts");
+    println_display(config, "Fetching token accounts".to_string());
     let accounts = config
         .rpc_client
         .get_token_accounts_by_owner(&owner, TokenAccountsFilter::ProgramId(ppl_token::id()))?;
     if accounts.is_empty() {
-        println!("Nothing to do");
+        println_display(config, "Nothing to do".to_string());
         return Ok(None);
     }
 
@@ -1383,7 +1941,7 @@ fn command_gc(config: &Config, owner: Pubkey) -> CommandResult {
     let mut lamports_needed = 0;
 
     for (token, accounts) in accounts_by_token.into_iter() {
-        println!("Processing token: {}", token);
+        println_display(config, format!("Processing token: {}", token));
         let associated_token_account = get_associated_token_address(&owner, &token);
         let total_balance: u128 = accounts.values().map(|account| account.0).sum();
 
@@ -1448,6 +2006,24 @@ fn command_sync_native(native_account_address: Pubkey) -> CommandResult {
     Ok(Some((0, vec![instructions])))
 }
 
+fn command_migrate_token_info(config: &Config, token: Pubkey) -> CommandResult {
+    let (mint_meta, _) =
+        Pubkey::find_program_address(&[b"MintMeta", &token.to_bytes()], &ppl_token::id());
+    if !config.sign_only {
+        let account = config.rpc_client.get_account(&mint_meta)?;
+        if !MintMeta::is_legacy_layout(&account.data) {
+            println_display(
+                config,
+                format!("Info of {} already uses the current layout", token),
+            );
+            return Ok(None);
+        }
+    }
+    println_display(config, format!("Migrating info of {}", token));
+    let instructions = vec![migrate_mint_meta(&ppl_token::id(), &mint_meta)?];
+    Ok(Some((0, vec![instructions])))
+}
+
 fn command_token_info(config: &Config, address: Pubkey) -> CommandResult {
     let (mut mint_info, _) =
         Pubkey::find_program_address(&[b"MintMeta", &address.to_bytes()], &ppl_token::id());
@@ -1460,20 +2036,28 @@ fn command_token_info(config: &Config, address: Pubkey) -> CommandResult {
 
     let token_data = MintMeta::unpack_from_slice(account_data.as_slice())?;
 
-    println_name_value("Token:", address.to_string().as_str());
-    let auth = match token_data.authority {
-        COption::Some(key) => key.to_string(),
-        COption::None => "None".to_string(),
+    let additional_metadata = if account_data.len() > MintMeta::LEN {
+        get_additional_metadata(&account_data)?
+            .into_iter()
+            .map(|(key, value)| CliMetadataField { key, value })
+            .collect()
+    } else {
+        vec![]
     };
-    println_name_value("Token Meta:", mint_info.to_string().as_str());
-    println_name_value("Token Meta Authority:",auth.to_string().as_str());
-    println_name_value("Token Symbol:", token_data.symbol.as_str());
-    println_name_value("Token Name:", token_data.name.as_str());
-    println_name_value("Token Icon:", token_data.icon.as_str());
-
+    let cli_token_info = CliTokenInfo {
+        address: address.to_string(),
+        meta_address: mint_info.to_string(),
+        meta_authority: token_data.authority.map(|key| key.to_string()).into(),
+        symbol: token_data.symbol,
+        name: token_data.name,
+        icon: token_data.icon,
+        additional_metadata,
+    };
+    println!("{}", config.output_format.formatted_string(&cli_token_info));
     Ok(None)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn command_update_token_info(
     config: &Config,
     token_meta: Pubkey,
@@ -1481,8 +2065,15 @@ fn command_update_token_info(
     symbol: Option<String>,
     name: Option<String>,
     icon: Option<String>,
+    fields: Vec<(String, String)>,
+    removed_fields: Vec<String>,
 ) -> CommandResult {
-    if symbol == None && name == None && icon == None {
+    if symbol == None
+        && name == None
+        && icon == None
+        && fields.is_empty()
+        && removed_fields.is_empty()
+    {
         Ok(None)
     } else {
         validate_mint_meta_auth(config, &token_meta, &meta_auth)?;
@@ -1531,6 +2122,28 @@ fn command_update_token_info(
             ins_icon.push(idx);
             instructions.push(ins_icon)
         }
+
+        for (key, value) in fields {
+            instructions.push(vec![set_mint_meta_field(
+                &ppl_token::id(),
+                &token_meta,
+                &config.fee_payer,
+                &meta_auth,
+                &config.multisigner_pubkeys,
+                key,
+                value,
+            )?]);
+        }
+        for key in removed_fields {
+            instructions.push(vec![remove_mint_meta_field(
+                &ppl_token::id(),
+                &token_meta,
+                &config.fee_payer,
+                &meta_auth,
+                &config.multisigner_pubkeys,
+                key,
+            )?]);
+        }
         Ok(Some((0, instructions)))
     }
 }
@@ -1592,6 +2205,25 @@ fn main() {
                 .global(true)
                 .help("Show additional information"),
         )
+        .arg(
+            Arg::with_name("output_format")
+                .long("output")
+                .value_name("FORMAT")
+                .global(true)
+                .takes_value(true)
+                .possible_values(&["json", "json-compact", "display"])
+                .help("Return information in specified output format"),
+        )
+        .arg(
+            Arg::with_name("export_transaction")
+                .long("export-transaction")
+                .value_name("PATH")
+                .global(true)
+                .takes_value(true)
+                .help("With --sign-only, write the partially signed transaction to a file \
+                    instead of printing it, for the remaining signers to add their signatures \
+                    with `ppl-token sign`"),
+        )
         .arg(
             Arg::with_name("json_rpc_url")
                 .short("u")
@@ -1649,10 +2281,71 @@ fn main() {
                             "Enable the mint authority to freeze associated token accounts."
                         ),
                 )
+                .arg(
+                    Arg::with_name("transfer_fee")
+                        .long("transfer-fee")
+                        .value_names(&["FEE_IN_BASIS_POINTS", "MAXIMUM_FEE"])
+                        .takes_value(true)
+                        .number_of_values(2)
+                        .help(
+                            "Withhold a fee on every transfer, in basis points of the \
+                             transferred amount and capped at MAXIMUM_FEE tokens. \
+                             The mint authority may change or withdraw the fees."
+                        ),
+                )
+                .arg(
+                    Arg::with_name("interest_rate")
+                        .long("interest-rate")
+                        .value_name("RATE_BPS")
+                        .takes_value(true)
+                        .allow_hyphen_values(true)
+                        .help(
+                            "Accrue interest on every balance at this rate, in basis points \
+                             per year. Raw amounts never change, only their UI value. \
+                             The mint authority may update the rate."
+                        ),
+                )
+                .arg(
+                    Arg::with_name("non_transferable")
+                        .long("non-transferable")
+                        .takes_value(false)
+                        .help(
+                            "Prevent the tokens from being transferred or delegated once minted. \
+                             They can still be burned."
+                        ),
+                )
+                .arg(
+                    Arg::with_name("enable_permanent_delegate")
+                        .long("enable-permanent-delegate")
+                        .takes_value(false)
+                        .help(
+                            "Enable the mint authority to transfer or burn tokens \
+                             from any account of the mint."
+                        ),
+                )
+                .arg(
+                    Arg::with_name("default_frozen")
+                        .long("default-frozen")
+                        .takes_value(false)
+                        .requires("enable_freeze")
+                        .help(
+                            "Create new token accounts frozen, until the freeze authority \
+                             thaws them."
+                        ),
+                )
+                .arg(
+                    Arg::with_name("enable_close")
+                        .long("enable-close")
+                        .takes_value(false)
+                        .help(
+                            "Enable the mint authority to close the mint once its supply is zero."
+                        ),
+                )
                 .arg(
                     Arg::with_name("symbol")
                         .long("symbol")
                         .takes_value(true)
+                        .validator(is_valid_symbol)
                         .help(
                             "Specify a symbol for the token is created. max len is 8 bytes."
                         ),
@@ -1660,6 +2353,7 @@ fn main() {
                     Arg::with_name("name")
                         .long("name")
                         .takes_value(true)
+                        .validator(is_valid_name)
                         .help(
                             "Specify a name for the token is created. max len is 32 bytes."
                         ),
@@ -1668,8 +2362,9 @@ fn main() {
                     Arg::with_name("icon")
                         .long("icon")
                         .takes_value(true)
+                        .validator(is_valid_icon)
                         .help(
-                            "Specify a icon url address for the token is created. max len is 128 bytes."
+                            "Specify a icon url address for the token is created. max len is 124 bytes."
                         ),
                 )
                 .arg(
@@ -1769,11 +2464,24 @@ fn main() {
                     Arg::with_name("authority_type")
                         .value_name("AUTHORITY_TYPE")
                         .takes_value(true)
-                        .possible_values(&["mint", "freeze", "owner", "close", "meta"])
+                        .possible_values(&[
+                            "mint",
+                            "freeze",
+                            "owner",
+                            "close",
+                            "meta",
+                            "transfer-fee-config",
+                            "withheld-withdraw",
+                            "interest-rate",
+                            "permanent-delegate",
+                            "close-mint",
+                        ])
                         .index(2)
                         .required(true)
                         .help("The new authority type. \
-                            Token mints support `mint` , `freeze` and `meta` authorities;\
+                            Token mints support `mint`, `freeze`, `meta`, `transfer-fee-config`, \
+                            `withheld-withdraw`, `interest-rate`, `permanent-delegate` \
+                            and `close-mint` authorities; \
                             Token accounts support `owner` and `close` authorities."),
                 )
                 .arg(
@@ -1893,11 +2601,75 @@ fn main() {
                         .requires("sign_only")
                         .help("In sign-only mode, specifies that the recipient is the owner of the associated token account rather than an actual token account"),
                 )
+                .arg(
+                    Arg::with_name("with_memo")
+                        .long("with-memo")
+                        .value_name("MEMO")
+                        .takes_value(true)
+                        .help("Precede the transfer with a memo, as required by some recipients"),
+                )
                 .arg(multisig_signer_arg())
                 .arg(mint_decimals_arg())
                 .nonce_args(true)
                 .offline_args_config(&SignOnlyNeedsMintDecimals{}),
         )
+        .subcommand(
+            SubCommand::with_name("distribute")
+                .about("Transfer tokens to every recipient listed in a CSV file")
+                .arg(
+                    Arg::with_name("token")
+                        .validator(is_valid_pubkey)
+                        .value_name("TOKEN_ADDRESS")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("Token to distribute"),
+                )
+                .arg(
+                    Arg::with_name("input")
+                        .long("input")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .required(true)
+                        .help("CSV file of `recipient,amount` rows, with amounts in tokens. \
+                            Recipients are wallet addresses, paid into their associated \
+                            token accounts, which are created if needed")
+                )
+                .arg(
+                    Arg::with_name("state_file")
+                        .long("state-file")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .help("File tracking the progress of the distribution, \
+                            so that an interrupted run can be resumed without paying twice \
+                            [default: <INPUT>.state.json]")
+                )
+                .arg(
+                    Arg::with_name("results")
+                        .long("results")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .help("CSV file to write the signature and status of every row to \
+                            [default: <INPUT>.results.csv]")
+                )
+                .arg(
+                    Arg::with_name("from")
+                        .validator(is_valid_pubkey)
+                        .value_name("SENDER_TOKEN_ACCOUNT_ADDRESS")
+                        .takes_value(true)
+                        .long("from")
+                        .help("Specify the sending token account \
+                            [default: owner's associated token account]")
+                )
+                .arg(owner_keypair_arg_with_value_name("SENDER_TOKEN_OWNER_KEYPAIR")
+                        .help(
+                            "Specify the owner of the sending token account. \
+                            This may be a keypair file, the ASK keyword. \
+                            Defaults to the client keypair.",
+                        ),
+                )
+                .arg(multisig_signer_arg()),
+        )
         .subcommand(
             SubCommand::with_name("burn")
                 .about("Burn tokens from an account")
@@ -2009,34 +2781,282 @@ fn main() {
                 .offline_args_config(&SignOnlyNeedsMintAddress{}),
         )
         .subcommand(
-            SubCommand::with_name("thaw")
-                .about("Thaw a token account")
+            SubCommand::with_name("thaw")
+                .about("Thaw a token account")
+                .arg(
+                    Arg::with_name("account")
+                        .validator(is_valid_pubkey)
+                        .value_name("TOKEN_ACCOUNT_ADDRESS")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("The address of the token account to thaw"),
+                )
+                .arg(
+                    Arg::with_name("freeze_authority")
+                        .long("freeze-authority")
+                        .alias("owner")
+                        .value_name("KEYPAIR")
+                        .validator(is_valid_signer)
+                        .takes_value(true)
+                        .help(
+                            "Specify the freeze authority keypair. \
+                             This may be a keypair file or the ASK keyword. \
+                             Defaults to the client keypair."
+                        ),
+                )
+                .arg(mint_address_arg())
+                .arg(multisig_signer_arg())
+                .nonce_args(true)
+                .offline_args_config(&SignOnlyNeedsMintAddress{}),
+        )
+        .subcommand(
+            SubCommand::with_name("enable-required-transfer-memos")
+                .about("Require a memo on every transfer into a token account")
+                .arg(
+                    Arg::with_name("account")
+                        .validator(is_valid_pubkey)
+                        .value_name("TOKEN_ACCOUNT_ADDRESS")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("The address of the token account, \
+                            which must have been allocated with room for the setting"),
+                )
+                .arg(owner_keypair_arg_with_value_name("OWNER_KEYPAIR")
+                        .help(
+                            "Specify the token account owner. \
+                            This may be a keypair file, the ASK keyword. \
+                            Defaults to the client keypair.",
+                        ),
+                )
+                .arg(multisig_signer_arg())
+                .nonce_args(true)
+                .offline_args(),
+        )
+        .subcommand(
+            SubCommand::with_name("disable-required-transfer-memos")
+                .about("Stop requiring a memo on transfers into a token account")
+                .arg(
+                    Arg::with_name("account")
+                        .validator(is_valid_pubkey)
+                        .value_name("TOKEN_ACCOUNT_ADDRESS")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("The address of the token account"),
+                )
+                .arg(owner_keypair_arg_with_value_name("OWNER_KEYPAIR")
+                        .help(
+                            "Specify the token account owner. \
+                            This may be a keypair file, the ASK keyword. \
+                            Defaults to the client keypair.",
+                        ),
+                )
+                .arg(multisig_signer_arg())
+                .nonce_args(true)
+                .offline_args(),
+        )
+        .subcommand(
+            SubCommand::with_name("enable-cpi-guard")
+                .about("Block the owner from moving or closing a token account through other programs")
+                .arg(
+                    Arg::with_name("account")
+                        .validator(is_valid_pubkey)
+                        .value_name("TOKEN_ACCOUNT_ADDRESS")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("The address of the token account, \
+                            which must have been allocated with room for the setting"),
+                )
+                .arg(owner_keypair_arg_with_value_name("OWNER_KEYPAIR")
+                        .help(
+                            "Specify the token account owner. \
+                            This may be a keypair file, the ASK keyword. \
+                            Defaults to the client keypair.",
+                        ),
+                )
+                .arg(multisig_signer_arg())
+                .nonce_args(true)
+                .offline_args(),
+        )
+        .subcommand(
+            SubCommand::with_name("disable-cpi-guard")
+                .about("Allow the owner to move or close a token account through other programs")
+                .arg(
+                    Arg::with_name("account")
+                        .validator(is_valid_pubkey)
+                        .value_name("TOKEN_ACCOUNT_ADDRESS")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("The address of the token account"),
+                )
+                .arg(owner_keypair_arg_with_value_name("OWNER_KEYPAIR")
+                        .help(
+                            "Specify the token account owner. \
+                            This may be a keypair file, the ASK keyword. \
+                            Defaults to the client keypair.",
+                        ),
+                )
+                .arg(multisig_signer_arg())
+                .nonce_args(true)
+                .offline_args(),
+        )
+        .subcommand(
+            SubCommand::with_name("create-vesting")
+                .about("Lock tokens in a vesting grant released on a cliff and linear schedule")
+                .arg(
+                    Arg::with_name("token")
+                        .validator(is_valid_pubkey)
+                        .value_name("TOKEN_ADDRESS")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("The token to vest"),
+                )
+                .arg(
+                    Arg::with_name("amount")
+                        .validator(is_amount)
+                        .value_name("TOKEN_AMOUNT")
+                        .takes_value(true)
+                        .index(2)
+                        .required(true)
+                        .help("Amount to vest, in tokens"),
+                )
+                .arg(
+                    Arg::with_name("recipient")
+                        .validator(is_valid_pubkey)
+                        .value_name("RECIPIENT_WALLET_ADDRESS")
+                        .takes_value(true)
+                        .index(3)
+                        .required(true)
+                        .help("The wallet allowed to claim the vested tokens"),
+                )
+                .arg(
+                    Arg::with_name("start")
+                        .long("start")
+                        .validator(is_parsable::<i64>)
+                        .value_name("UNIX_TIMESTAMP")
+                        .takes_value(true)
+                        .help("Time at which tokens start to vest [default: now]"),
+                )
+                .arg(
+                    Arg::with_name("cliff")
+                        .long("cliff")
+                        .validator(is_parsable::<i64>)
+                        .value_name("UNIX_TIMESTAMP")
+                        .takes_value(true)
+                        .help("Time before which nothing can be claimed. \
+                            Everything vested up to the cliff is released at once. \
+                            [default: the start time]"),
+                )
+                .arg(
+                    Arg::with_name("end")
+                        .long("end")
+                        .validator(is_parsable::<i64>)
+                        .value_name("UNIX_TIMESTAMP")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Time at which all tokens are vested"),
+                )
+                .arg(
+                    Arg::with_name("revoke_authority")
+                        .long("revoke-authority")
+                        .validator(is_valid_pubkey)
+                        .value_name("ADDRESS")
+                        .takes_value(true)
+                        .help("Allow this address to take back the unvested tokens. \
+                            Without it the grant is irrevocable."),
+                )
+                .arg(
+                    Arg::with_name("from")
+                        .validator(is_valid_pubkey)
+                        .value_name("SENDER_TOKEN_ACCOUNT_ADDRESS")
+                        .takes_value(true)
+                        .long("from")
+                        .help("Specify the token account funding the grant \
+                            [default: owner's associated token account]")
+                )
+                .arg(owner_keypair_arg_with_value_name("SENDER_TOKEN_OWNER_KEYPAIR")
+                        .help(
+                            "Specify the owner of the sending token account. \
+                            This may be a keypair file, the ASK keyword. \
+                            Defaults to the client keypair.",
+                        ),
+                )
+                .arg(mint_decimals_arg())
+                .nonce_args(true)
+                .offline_args_config(&SignOnlyNeedsMintDecimals{}),
+        )
+        .subcommand(
+            SubCommand::with_name("claim-vesting")
+                .about("Claim the vested tokens of a vesting grant")
+                .arg(
+                    Arg::with_name("grant")
+                        .validator(is_valid_pubkey)
+                        .value_name("GRANT_ADDRESS")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("The vesting grant to claim from"),
+                )
+                .arg(
+                    Arg::with_name("recipient")
+                        .long("recipient")
+                        .validator(is_valid_signer)
+                        .value_name("RECIPIENT_KEYPAIR")
+                        .takes_value(true)
+                        .help(
+                            "Specify the recipient of the grant. \
+                            This may be a keypair file, the ASK keyword. \
+                            Defaults to the client keypair.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("destination")
+                        .long("destination")
+                        .validator(is_valid_pubkey)
+                        .value_name("TOKEN_ACCOUNT_ADDRESS")
+                        .takes_value(true)
+                        .help("The token account receiving the tokens \
+                            [default: recipient's associated token account]"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("revoke-vesting")
+                .about("Take back the unvested tokens of a revocable vesting grant")
                 .arg(
-                    Arg::with_name("account")
+                    Arg::with_name("grant")
                         .validator(is_valid_pubkey)
-                        .value_name("TOKEN_ACCOUNT_ADDRESS")
+                        .value_name("GRANT_ADDRESS")
                         .takes_value(true)
                         .index(1)
                         .required(true)
-                        .help("The address of the token account to thaw"),
+                        .help("The vesting grant to revoke"),
                 )
                 .arg(
-                    Arg::with_name("freeze_authority")
-                        .long("freeze-authority")
-                        .alias("owner")
-                        .value_name("KEYPAIR")
+                    Arg::with_name("revoke_authority")
+                        .long("revoke-authority")
                         .validator(is_valid_signer)
+                        .value_name("REVOKE_AUTHORITY_KEYPAIR")
                         .takes_value(true)
                         .help(
-                            "Specify the freeze authority keypair. \
-                             This may be a keypair file or the ASK keyword. \
-                             Defaults to the client keypair."
+                            "Specify the revoke authority of the grant. \
+                            This may be a keypair file, the ASK keyword. \
+                            Defaults to the client keypair.",
                         ),
                 )
-                .arg(mint_address_arg())
-                .arg(multisig_signer_arg())
-                .nonce_args(true)
-                .offline_args_config(&SignOnlyNeedsMintAddress{}),
+                .arg(
+                    Arg::with_name("destination")
+                        .long("destination")
+                        .validator(is_valid_pubkey)
+                        .value_name("TOKEN_ACCOUNT_ADDRESS")
+                        .takes_value(true)
+                        .help("The token account receiving the unvested tokens \
+                            [default: revoke authority's associated token account]"),
+                ),
         )
         .subcommand(
             SubCommand::with_name("wrap")
@@ -2097,6 +3117,15 @@ fn main() {
                              Defaults to the client keypair."
                         ),
                 )
+                .arg(
+                    Arg::with_name("amount")
+                        .long("amount")
+                        .validator(is_amount)
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .help("Amount of PUT to unwrap, keeping the account open \
+                            [default: the whole balance, closing the account]"),
+                )
                 .arg(multisig_signer_arg())
                 .nonce_args(true)
                 .offline_args(),
@@ -2207,6 +3236,42 @@ fn main() {
                 .nonce_args(true)
                 .offline_args(),
         )
+        .subcommand(
+            SubCommand::with_name("close-mint")
+                .about("Close a token mint with zero supply, and its meta account")
+                .arg(
+                    Arg::with_name("token")
+                        .validator(is_valid_pubkey)
+                        .value_name("TOKEN_ADDRESS")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("Token to close"),
+                )
+                .arg(
+                    Arg::with_name("recipient")
+                        .long("recipient")
+                        .validator(is_valid_pubkey)
+                        .value_name("REFUND_ACCOUNT_ADDRESS")
+                        .takes_value(true)
+                        .help("The address of the account to receive remaining PUT [default: client keypair]"),
+                )
+                .arg(
+                    Arg::with_name("close_authority")
+                        .long("close-authority")
+                        .value_name("KEYPAIR")
+                        .validator(is_valid_signer)
+                        .takes_value(true)
+                        .help(
+                            "Specify the token's close authority. \
+                            This may be a keypair file, the ASK keyword. \
+                            Defaults to the client keypair.",
+                        ),
+                )
+                .arg(multisig_signer_arg())
+                .nonce_args(true)
+                .offline_args(),
+        )
         .subcommand(
             SubCommand::with_name("balance")
                 .about("Get token account balance")
@@ -2257,6 +3322,70 @@ fn main() {
                 )
                 .arg(owner_address_arg())
         )
+        .subcommand(
+            SubCommand::with_name("holders")
+                .about("List the owners of a token and their balances")
+                .arg(
+                    Arg::with_name("token")
+                        .validator(is_valid_pubkey)
+                        .value_name("TOKEN_ADDRESS")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("The token to list the holders of"),
+                )
+                .arg(
+                    Arg::with_name("include_empty")
+                        .long("include-empty")
+                        .takes_value(false)
+                        .help("Also list owners whose token accounts are all empty"),
+                )
+                .arg(
+                    Arg::with_name("csv")
+                        .long("csv")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .help("Also write the holders to a CSV file"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("history")
+                .about("List the transactions of a token account or mint, oldest first")
+                .arg(
+                    Arg::with_name("address")
+                        .validator(is_valid_pubkey)
+                        .value_name("ADDRESS")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("The token account or mint to list the transactions of"),
+                )
+                .arg(
+                    Arg::with_name("since")
+                        .long("since")
+                        .value_name("SIGNATURE")
+                        .validator(is_parsable::<Signature>)
+                        .takes_value(true)
+                        .help("Only list transactions newer than this one"),
+                )
+                .arg(
+                    Arg::with_name("before")
+                        .long("before")
+                        .value_name("SIGNATURE")
+                        .validator(is_parsable::<Signature>)
+                        .takes_value(true)
+                        .help("Only list transactions older than this one"),
+                )
+                .arg(
+                    Arg::with_name("limit")
+                        .long("limit")
+                        .value_name("COUNT")
+                        .validator(is_parsable::<usize>)
+                        .takes_value(true)
+                        .default_value("100")
+                        .help("Maximum number of transactions to list, the most recent ones"),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("address")
                 .about("Get wallet address")
@@ -2322,6 +3451,70 @@ fn main() {
                     .help("The address of the PPL Token multisig account to query"),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("sign")
+                .about("Add signatures to a transaction exported with --export-transaction")
+                .arg(
+                    Arg::with_name("file")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("The transaction file"),
+                )
+                .arg(
+                    Arg::with_name("signer")
+                        .long("signer")
+                        .value_name("KEYPAIR")
+                        .validator(is_valid_signer)
+                        .takes_value(true)
+                        .multiple(true)
+                        .help("Keypair to sign with; may be specified multiple times. \
+                            Defaults to the client keypair."),
+                )
+                .arg(
+                    Arg::with_name("out")
+                        .long("out")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .help("Write the signed transaction to this file \
+                            [default: update the transaction file in place]"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("merge-signatures")
+                .about("Combine the signatures of several copies of an exported transaction")
+                .arg(
+                    Arg::with_name("files")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .index(1)
+                        .multiple(true)
+                        .min_values(2)
+                        .required(true)
+                        .help("The transaction files"),
+                )
+                .arg(
+                    Arg::with_name("out")
+                        .long("out")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .help("Write the merged transaction to this file \
+                            [default: update the first transaction file in place]"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("broadcast")
+                .about("Send an exported transaction once all of its signatures are present")
+                .arg(
+                    Arg::with_name("file")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("The transaction file"),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("gc")
                 .about("Cleanup unnecessary token accounts")
@@ -2387,18 +3580,57 @@ fn main() {
                     Arg::with_name("symbol")
                         .long("symbol")
                         .takes_value(true)
+                        .validator(is_valid_symbol)
                         .help("Specify the symbol for token to update"),
                 ).arg(
                     Arg::with_name("name")
                         .long("name")
                         .takes_value(true)
+                        .validator(is_valid_name)
                         .help("Specify the name for token to update"),
                 )
                 .arg(
                     Arg::with_name("icon")
                         .long("icon")
                         .takes_value(true)
+                        .validator(is_valid_icon)
                         .help("Specify the icon for token to update"),
+                )
+                .arg(
+                    Arg::with_name("field")
+                        .long("field")
+                        .value_name("KEY=VALUE")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .validator(is_valid_metadata_field)
+                        .help(
+                            "Set an additional metadata field, such as website, \
+                             description or uri. May be repeated. \
+                             The fee payer funds the extra space."
+                        ),
+                )
+                .arg(
+                    Arg::with_name("remove_field")
+                        .long("remove-field")
+                        .value_name("KEY")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("Remove an additional metadata field. May be repeated."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("migrate-token-info")
+                .about("Rewrite the info of an PPL Token in the current layout")
+                .arg(
+                    Arg::with_name("token")
+                        .validator(is_valid_pubkey)
+                        .value_name("TOKEN_ADDRESS")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("Token address."),
                 ),
         )
         .get_matches();
@@ -2441,6 +3673,18 @@ fn main() {
         bulk_signers.push(signer);
 
         let verbose = matches.is_present("verbose");
+        let output_format = matches
+            .value_of("output_format")
+            .map(|value| match value {
+                "json" => OutputFormat::Json,
+                "json-compact" => OutputFormat::JsonCompact,
+                _ => OutputFormat::Display,
+            })
+            .unwrap_or(OutputFormat::Display);
+        let output_format = match output_format {
+            OutputFormat::Display if verbose => OutputFormat::DisplayVerbose,
+            output_format => output_format,
+        };
 
         let nonce_account = pubkey_of_signer(matches, NONCE_ARG.name, &mut wallet_manager)
             .unwrap_or_else(|e| {
@@ -2489,7 +3733,7 @@ fn main() {
 
         Config {
             rpc_client: RpcClient::new_with_commitment(json_rpc_url, CommitmentConfig::confirmed()),
-            verbose,
+            output_format,
             fee_payer,
             default_keypair_path: cli_config.keypair_path,
             nonce_account,
@@ -2511,6 +3755,27 @@ fn main() {
             let name = value_t!(arg_matches, "name", String).ok();
             let icon = value_t!(arg_matches, "icon", String).ok();
             let memo = value_t!(arg_matches, "memo", String).ok();
+            let transfer_fee = arg_matches.values_of("transfer_fee").map(|mut values| {
+                let transfer_fee_basis_points = values
+                    .next()
+                    .unwrap()
+                    .parse::<u16>()
+                    .unwrap_or_else(|e| {
+                        eprintln!("error: invalid transfer fee basis points: {}", e);
+                        exit(1);
+                    });
+                let maximum_fee =
+                    parse_ui_amount(values.next().unwrap(), decimals).unwrap_or_else(|e| {
+                        eprintln!("error: invalid maximum fee: {}", e);
+                        exit(1);
+                    });
+                (transfer_fee_basis_points, maximum_fee)
+            });
+            let interest_rate = if arg_matches.is_present("interest_rate") {
+                Some(value_t_or_exit!(arg_matches, "interest_rate", i16))
+            } else {
+                None
+            };
             let (token_signer, token) =
                 get_signer(arg_matches, "token_keypair", &mut wallet_manager)
                     .unwrap_or_else(new_throwaway_signer);
@@ -2522,6 +3787,12 @@ fn main() {
                 token,
                 mint_authority,
                 arg_matches.is_present("enable_freeze"),
+                transfer_fee,
+                interest_rate,
+                arg_matches.is_present("non_transferable"),
+                arg_matches.is_present("enable_permanent_delegate"),
+                arg_matches.is_present("default_frozen"),
+                arg_matches.is_present("enable_close"),
                 symbol,
                 name,
                 icon,
@@ -2578,6 +3849,11 @@ fn main() {
                 "owner" => AuthorityType::AccountOwner,
                 "close" => AuthorityType::CloseAccount,
                 "meta" => AuthorityType::UpdateMeta,
+                "transfer-fee-config" => AuthorityType::TransferFeeConfig,
+                "withheld-withdraw" => AuthorityType::WithheldWithdraw,
+                "interest-rate" => AuthorityType::InterestRate,
+                "permanent-delegate" => AuthorityType::PermanentDelegate,
+                "close-mint" => AuthorityType::CloseMint,
                 _ => unreachable!(),
             };
 
@@ -2620,6 +3896,7 @@ fn main() {
                 || matches.is_present("allow_unfunded_recipient");
             no_wait = matches.is_present("no_wait");
             let recipient_is_ata_owner = matches.is_present("recipient_is_ata_owner");
+            let memo = value_t!(arg_matches, "with_memo", String).ok();
 
             command_transfer(
                 &config,
@@ -2632,6 +3909,37 @@ fn main() {
                 fund_recipient,
                 mint_decimals,
                 recipient_is_ata_owner,
+                memo,
+            )
+        }
+        ("distribute", Some(arg_matches)) => {
+            let token = pubkey_of_signer(arg_matches, "token", &mut wallet_manager)
+                .unwrap()
+                .unwrap();
+            let input = arg_matches.value_of("input").unwrap();
+            let state_file = arg_matches
+                .value_of("state_file")
+                .map(|path| path.to_string())
+                .unwrap_or_else(|| format!("{}.state.json", input));
+            let results_file = arg_matches
+                .value_of("results")
+                .map(|path| path.to_string())
+                .unwrap_or_else(|| format!("{}.results.csv", input));
+            let source = pubkey_of_signer(arg_matches, "from", &mut wallet_manager).unwrap();
+
+            let (owner_signer, owner) =
+                config.signer_or_default(arg_matches, "owner", &mut wallet_manager);
+            bulk_signers.push(owner_signer);
+
+            command_distribute(
+                &config,
+                &bulk_signers,
+                token,
+                source,
+                owner,
+                input,
+                &state_file,
+                &results_file,
             )
         }
         ("burn", Some(arg_matches)) => {
@@ -2697,6 +4005,94 @@ fn main() {
                 pubkey_of_signer(arg_matches, MINT_ADDRESS_ARG.name, &mut wallet_manager).unwrap();
             command_thaw(&config, account, mint_address, freeze_authority)
         }
+        ("enable-required-transfer-memos", Some(arg_matches))
+        | ("disable-required-transfer-memos", Some(arg_matches)) => {
+            let (owner_signer, owner) =
+                config.signer_or_default(arg_matches, "owner", &mut wallet_manager);
+            bulk_signers.push(owner_signer);
+
+            let account = pubkey_of_signer(arg_matches, "account", &mut wallet_manager)
+                .unwrap()
+                .unwrap();
+            let enable = sub_command == "enable-required-transfer-memos";
+            command_required_transfer_memos(&config, account, owner, enable)
+        }
+        ("enable-cpi-guard", Some(arg_matches)) | ("disable-cpi-guard", Some(arg_matches)) => {
+            let (owner_signer, owner) =
+                config.signer_or_default(arg_matches, "owner", &mut wallet_manager);
+            bulk_signers.push(owner_signer);
+
+            let account = pubkey_of_signer(arg_matches, "account", &mut wallet_manager)
+                .unwrap()
+                .unwrap();
+            let enable = sub_command == "enable-cpi-guard";
+            command_cpi_guard(&config, account, owner, enable)
+        }
+        ("create-vesting", Some(arg_matches)) => {
+            let token = pubkey_of_signer(arg_matches, "token", &mut wallet_manager)
+                .unwrap()
+                .unwrap();
+            let amount = value_t_or_exit!(arg_matches, "amount", String);
+            let recipient = pubkey_of_signer(arg_matches, "recipient", &mut wallet_manager)
+                .unwrap()
+                .unwrap();
+            let source = pubkey_of_signer(arg_matches, "from", &mut wallet_manager).unwrap();
+            let revoke_authority =
+                pubkey_of_signer(arg_matches, "revoke_authority", &mut wallet_manager).unwrap();
+
+            let (owner_signer, owner) =
+                config.signer_or_default(arg_matches, "owner", &mut wallet_manager);
+            bulk_signers.push(owner_signer);
+            let (grant_signer, grant) = new_throwaway_signer();
+            bulk_signers.push(grant_signer);
+
+            let start = value_of::<i64>(arg_matches, "start").unwrap_or_else(|| {
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs() as i64
+            });
+            let schedule = VestingSchedule {
+                start,
+                cliff: value_of::<i64>(arg_matches, "cliff").unwrap_or(start),
+                end: value_t_or_exit!(arg_matches, "end", i64),
+            };
+            let mint_decimals = value_of::<u8>(arg_matches, MINT_DECIMALS_ARG.name);
+            command_create_vesting(
+                &config,
+                token,
+                amount,
+                recipient,
+                source,
+                owner,
+                grant,
+                schedule,
+                revoke_authority,
+                mint_decimals,
+            )
+        }
+        ("claim-vesting", Some(arg_matches)) => {
+            let grant = pubkey_of_signer(arg_matches, "grant", &mut wallet_manager)
+                .unwrap()
+                .unwrap();
+            let (recipient_signer, recipient) =
+                config.signer_or_default(arg_matches, "recipient", &mut wallet_manager);
+            bulk_signers.push(recipient_signer);
+            let destination =
+                pubkey_of_signer(arg_matches, "destination", &mut wallet_manager).unwrap();
+            command_claim_vesting(&config, grant, recipient, destination)
+        }
+        ("revoke-vesting", Some(arg_matches)) => {
+            let grant = pubkey_of_signer(arg_matches, "grant", &mut wallet_manager)
+                .unwrap()
+                .unwrap();
+            let (revoke_authority_signer, revoke_authority) =
+                config.signer_or_default(arg_matches, "revoke_authority", &mut wallet_manager);
+            bulk_signers.push(revoke_authority_signer);
+            let destination =
+                pubkey_of_signer(arg_matches, "destination", &mut wallet_manager).unwrap();
+            command_revoke_vesting(&config, grant, revoke_authority, destination)
+        }
         ("wrap", Some(arg_matches)) => {
             let amount = value_t_or_exit!(arg_matches, "amount", String);
             let account = if arg_matches.is_present("create_aux_account") {
@@ -2720,7 +4116,8 @@ fn main() {
             bulk_signers.push(wallet_signer);
 
             let address = pubkey_of_signer(arg_matches, "address", &mut wallet_manager).unwrap();
-            command_unwrap(&config, wallet_address, address)
+            let amount = value_t!(arg_matches, "amount", String).ok();
+            command_unwrap(&config, wallet_address, address, amount)
         }
         ("approve", Some(arg_matches)) => {
             let (owner_signer, owner_address) =
@@ -2773,6 +4170,17 @@ fn main() {
             let recipient = config.pubkey_or_default(arg_matches, "recipient", &mut wallet_manager);
             command_close(&config, address, close_authority, recipient)
         }
+        ("close-mint", Some(arg_matches)) => {
+            let (close_authority_signer, close_authority) =
+                config.signer_or_default(arg_matches, "close_authority", &mut wallet_manager);
+            bulk_signers.push(close_authority_signer);
+
+            let token = pubkey_of_signer(arg_matches, "token", &mut wallet_manager)
+                .unwrap()
+                .unwrap();
+            let recipient = config.pubkey_or_default(arg_matches, "recipient", &mut wallet_manager);
+            command_close_mint(&config, token, close_authority, recipient)
+        }
         ("balance", Some(arg_matches)) => {
             let address = config.associated_token_address_or_override(
                 arg_matches,
@@ -2792,6 +4200,23 @@ fn main() {
             let owner = config.pubkey_or_default(arg_matches, "owner", &mut wallet_manager);
             command_accounts(&config, token, owner)
         }
+        ("holders", Some(arg_matches)) => {
+            let token = pubkey_of_signer(arg_matches, "token", &mut wallet_manager)
+                .unwrap()
+                .unwrap();
+            let include_empty = arg_matches.is_present("include_empty");
+            let csv_file = arg_matches.value_of("csv");
+            command_holders(&config, token, include_empty, csv_file)
+        }
+        ("history", Some(arg_matches)) => {
+            let address = pubkey_of_signer(arg_matches, "address", &mut wallet_manager)
+                .unwrap()
+                .unwrap();
+            let since = value_of::<Signature>(arg_matches, "since");
+            let before = value_of::<Signature>(arg_matches, "before");
+            let limit = value_t_or_exit!(arg_matches, "limit", usize);
+            command_history(&config, address, since, before, limit)
+        }
         ("address", Some(arg_matches)) => {
             let token = pubkey_of_signer(arg_matches, "token", &mut wallet_manager).unwrap();
             let owner = config.pubkey_or_default(arg_matches, "owner", &mut wallet_manager);
@@ -2811,6 +4236,31 @@ fn main() {
                 .unwrap();
             command_multisig(&config, address)
         }
+        ("sign", Some(arg_matches)) => {
+            let file = arg_matches.value_of("file").unwrap();
+            let out = arg_matches.value_of("out");
+            let signers = signers_of(arg_matches, "signer", &mut wallet_manager)
+                .unwrap_or_else(|e| {
+                    eprintln!("error: {}", e);
+                    exit(1);
+                })
+                .unwrap_or_else(|| {
+                    vec![config.signer_or_default(arg_matches, "signer", &mut wallet_manager)]
+                })
+                .into_iter()
+                .map(|(signer, _)| signer)
+                .collect::<Vec<_>>();
+            command_sign_transaction(&config, file, &signers, out)
+        }
+        ("merge-signatures", Some(arg_matches)) => {
+            let files = arg_matches.values_of("files").unwrap().collect::<Vec<_>>();
+            let out = arg_matches.value_of("out");
+            command_merge_signatures(&config, &files, out)
+        }
+        ("broadcast", Some(arg_matches)) => {
+            let file = arg_matches.value_of("file").unwrap();
+            command_broadcast(&config, file)
+        }
         ("gc", Some(arg_matches)) => {
             let (owner_signer, owner_address) =
                 config.signer_or_default(arg_matches, "owner", &mut wallet_manager);
@@ -2834,6 +4284,12 @@ fn main() {
                 .unwrap();
             command_token_info(&config, address)
         }
+        ("migrate-token-info", Some(arg_matches)) => {
+            let token = pubkey_of_signer(arg_matches, "token", &mut wallet_manager)
+                .unwrap()
+                .unwrap();
+            command_migrate_token_info(&config, token)
+        }
         ("update-token-info", Some(arg_matches)) => {
             let token = pubkey_of_signer(arg_matches, "token", &mut wallet_manager)
                 .unwrap()
@@ -2850,6 +4306,20 @@ fn main() {
             let (mint_meta, _) =
                 Pubkey::find_program_address(&[b"MintMeta", &token.to_bytes()], &ppl_token::id());
 
+            let fields = arg_matches
+                .values_of("field")
+                .map(|values| {
+                    values
+                        .filter_map(|field| field.split_once('='))
+                        .map(|(key, value)| (key.to_string(), value.to_string()))
+                        .collect()
+                })
+                .unwrap_or_default();
+            let removed_fields = arg_matches
+                .values_of("remove_field")
+                .map(|values| values.map(str::to_string).collect())
+                .unwrap_or_default();
+
             command_update_token_info(
                 &config,
                 mint_meta,
@@ -2857,12 +4327,27 @@ fn main() {
                 symbol,
                 name,
                 icon,
+                fields,
+                removed_fields,
             )
         }
         _ => unreachable!(),
     }
     .and_then(|transaction_info| {
         if let Some((minimum_balance_for_rent_exemption, instruction_batches)) = transaction_info {
+            let export_path = matches.value_of("export_transaction");
+            if export_path.is_some() {
+                if !config.sign_only {
+                    return Err("--export-transaction requires --sign-only".into());
+                }
+                if instruction_batches.len() != 1 {
+                    return Err(format!(
+                        "--export-transaction needs a single transaction, this command sends {}",
+                        instruction_batches.len()
+                    )
+                    .into());
+                }
+            }
             let fee_payer = Some(&config.fee_payer);
             let signer_info = CliSignerInfo {
                 signers: bulk_signers,
@@ -2902,7 +4387,15 @@ fn main() {
 
                 if config.sign_only {
                     transaction.try_partial_sign(&signers, recent_blockhash)?;
-                    println!("{}", return_signers(&transaction, &OutputFormat::Display)?);
+                    if let Some(path) = export_path {
+                        let cli_transaction_file = export_transaction(&transaction, path)?;
+                        println!(
+                            "{}",
+                            config.output_format.formatted_string(&cli_transaction_file)
+                        );
+                    } else {
+                        println!("{}", return_signers(&transaction, &config.output_format)?);
+                    }
                 } else {
                     transaction.try_sign(&signers, recent_blockhash)?;
                     let signature = if no_wait {
@@ -2912,7 +4405,10 @@ fn main() {
                             .rpc_client
                             .send_and_confirm_transaction_with_spinner(&transaction)?
                     };
-                    println!("Signature: {}", signature);
+                    let cli_signature = CliSignature {
+                        signature: signature.to_string(),
+                    };
+                    println!("{}", config.output_format.formatted_string(&cli_signature));
                 }
             }
         }
@@ -0,0 +1,637 @@
+use crate::{config::Config, sort::UnsupportedAccount};
+use console::Emoji;
+use put_account_decoder::parse_token::{UiAccountState, UiTokenAccount, UiTokenAmount};
+use put_cli_output::{
+    display::{unix_timestamp_to_string, writeln_name_value},
+    OutputFormat, QuietDisplay, VerboseDisplay,
+};
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
+
+static WARNING: Emoji = Emoji("⚠️", "!");
+
+pub(crate) fn println_display(config: &Config, message: String) {
+    match config.output_format {
+        OutputFormat::Display | OutputFormat::DisplayVerbose => {
+            println!("{}", message);
+        }
+        _ => {}
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliTokenAmount {
+    #[serde(flatten)]
+    pub(crate) amount: UiTokenAmount,
+}
+
+impl QuietDisplay for CliTokenAmount {}
+impl VerboseDisplay for CliTokenAmount {
+    fn write_str(&self, w: &mut dyn fmt::Write) -> fmt::Result {
+        writeln!(w, "ui amount: {}", self.amount.real_number_string_trimmed())?;
+        writeln!(w, "decimals: {}", self.amount.decimals)?;
+        write!(w, "amount: {}", self.amount.amount)
+    }
+}
+
+impl fmt::Display for CliTokenAmount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.amount.real_number_string_trimmed())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliWalletAddress {
+    pub(crate) wallet_address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) associated_token_address: Option<String>,
+}
+
+impl QuietDisplay for CliWalletAddress {}
+impl VerboseDisplay for CliWalletAddress {}
+
+impl fmt::Display for CliWalletAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Wallet address: {}", self.wallet_address)?;
+        if let Some(associated_token_address) = &self.associated_token_address {
+            writeln!(f, "Associated token address: {}", associated_token_address)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliMultisig {
+    pub(crate) address: String,
+    pub(crate) m: u8,
+    pub(crate) n: u8,
+    pub(crate) signers: Vec<String>,
+}
+
+impl QuietDisplay for CliMultisig {}
+impl VerboseDisplay for CliMultisig {}
+
+impl fmt::Display for CliMultisig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f)?;
+        writeln_name_value(f, "Address:", &self.address)?;
+        writeln_name_value(f, "M/N:", &format!("{}/{}", self.m, self.n))?;
+        writeln_name_value(f, "Signers:", " ")?;
+        let width = if self.n >= 9 { 4 } else { 3 };
+        for i in 0..self.n as usize {
+            let title = format!("{1:>0$}:", width, i + 1);
+            writeln_name_value(f, &title, &self.signers[i])?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliTokenAccount {
+    pub(crate) address: String,
+    pub(crate) is_associated: bool,
+    #[serde(flatten)]
+    pub(crate) account: UiTokenAccount,
+}
+
+impl QuietDisplay for CliTokenAccount {}
+impl VerboseDisplay for CliTokenAccount {}
+
+impl fmt::Display for CliTokenAccount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f)?;
+        let address = if self.is_associated {
+            self.address.clone()
+        } else {
+            format!("{}  (Aux*)", self.address)
+        };
+        writeln_name_value(f, "Address:", &address)?;
+        writeln_name_value(
+            f,
+            "Balance:",
+            &self.account.token_amount.real_number_string_trimmed(),
+        )?;
+        let mint = format!(
+            "{}{}",
+            self.account.mint,
+            if self.account.is_native {
+                " (native)"
+            } else {
+                ""
+            }
+        );
+        writeln_name_value(f, "Mint:", &mint)?;
+        writeln_name_value(f, "Owner:", &self.account.owner)?;
+        writeln_name_value(f, "State:", &format!("{:?}", self.account.state))?;
+        if let Some(delegate) = &self.account.delegate {
+            writeln!(f, "Delegation:")?;
+            writeln_name_value(f, "  Delegate:", delegate)?;
+            let allowance = self.account.delegated_amount.as_ref().unwrap();
+            writeln_name_value(f, "  Allowance:", &allowance.real_number_string_trimmed())?;
+        } else {
+            writeln_name_value(f, "Delegation:", "")?;
+        }
+        writeln_name_value(
+            f,
+            "Close authority:",
+            self.account
+                .close_authority
+                .as_ref()
+                .unwrap_or(&String::new()),
+        )?;
+        if !self.is_associated {
+            writeln!(f)?;
+            writeln!(f, "* Please run `ppl-token gc` to clean up Aux accounts")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliTokenAccounts {
+    #[serde(serialize_with = "flattened")]
+    pub(crate) accounts: Vec<Vec<CliTokenAccount>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) unsupported_accounts: Vec<UnsupportedAccount>,
+    #[serde(skip)]
+    pub(crate) max_len_balance: usize,
+    #[serde(skip)]
+    pub(crate) aux_len: usize,
+    #[serde(skip)]
+    pub(crate) token_is_some: bool,
+}
+
+impl CliTokenAccounts {
+    fn write_accounts(&self, w: &mut dyn fmt::Write, verbose: bool) -> fmt::Result {
+        if self.accounts.is_empty() && self.unsupported_accounts.is_empty() {
+            return writeln!(w, "None");
+        }
+        if verbose {
+            if self.token_is_some {
+                writeln!(
+                    w,
+                    "{:<44}  {:<2$}",
+                    "Account", "Balance", self.max_len_balance
+                )?;
+                writeln!(
+                    w,
+                    "-------------------------------------------------------------"
+                )?;
+            } else {
+                writeln!(
+                    w,
+                    "{:<44}  {:<44}  {:<3$}",
+                    "Token", "Account", "Balance", self.max_len_balance
+                )?;
+                writeln!(w, "----------------------------------------------------------------------------------------------------------")?;
+            }
+        } else if self.token_is_some {
+            writeln!(w, "{:<1$}", "Balance", self.max_len_balance)?;
+            writeln!(w, "-------------")?;
+        } else {
+            writeln!(
+                w,
+                "{:<44}  {:<2$}",
+                "Token", "Balance", self.max_len_balance
+            )?;
+            writeln!(
+                w,
+                "---------------------------------------------------------------"
+            )?;
+        }
+        let mut gc_alert = false;
+        for accounts_list in self.accounts.iter() {
+            let mut aux_counter = 1;
+            for account in accounts_list {
+                let maybe_aux = if !account.is_associated {
+                    gc_alert = true;
+                    let message = format!("  (Aux-{}*)", aux_counter);
+                    aux_counter += 1;
+                    message
+                } else {
+                    "".to_string()
+                };
+                let maybe_frozen = if let UiAccountState::Frozen = account.account.state {
+                    format!(" {}  Frozen", WARNING)
+                } else {
+                    "".to_string()
+                };
+                let balance = account.account.token_amount.real_number_string_trimmed();
+                if verbose {
+                    if self.token_is_some {
+                        writeln!(
+                            w,
+                            "{:<44}  {:<4$}{:<5$}{}",
+                            account.address,
+                            balance,
+                            maybe_aux,
+                            maybe_frozen,
+                            self.max_len_balance,
+                            self.aux_len,
+                        )?;
+                    } else {
+                        writeln!(
+                            w,
+                            "{:<44}  {:<44}  {:<5$}{:<6$}{}",
+                            account.account.mint,
+                            account.address,
+                            balance,
+                            maybe_aux,
+                            maybe_frozen,
+                            self.max_len_balance,
+                            self.aux_len,
+                        )?;
+                    }
+                } else if self.token_is_some {
+                    writeln!(
+                        w,
+                        "{:<3$}{:<4$}{}",
+                        balance, maybe_aux, maybe_frozen, self.max_len_balance, self.aux_len,
+                    )?;
+                } else {
+                    writeln!(
+                        w,
+                        "{:<44}  {:<4$}{:<5$}{}",
+                        account.account.mint,
+                        balance,
+                        maybe_aux,
+                        maybe_frozen,
+                        self.max_len_balance,
+                        self.aux_len,
+                    )?;
+                }
+            }
+        }
+        for unsupported_account in &self.unsupported_accounts {
+            writeln!(
+                w,
+                "{:<44}  {}",
+                unsupported_account.address, unsupported_account.err
+            )?;
+        }
+        if gc_alert {
+            writeln!(w)?;
+            writeln!(w, "* Please run `ppl-token gc` to clean up Aux accounts")?;
+        }
+        Ok(())
+    }
+}
+
+impl QuietDisplay for CliTokenAccounts {}
+impl VerboseDisplay for CliTokenAccounts {
+    fn write_str(&self, w: &mut dyn fmt::Write) -> fmt::Result {
+        self.write_accounts(w, true)
+    }
+}
+
+impl fmt::Display for CliTokenAccounts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_accounts(f, false)
+    }
+}
+
+fn flattened<S: Serializer>(
+    vec: &[Vec<CliTokenAccount>],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let flattened: Vec<_> = vec.iter().flatten().collect();
+    flattened.serialize(serializer)
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliMetadataField {
+    pub(crate) key: String,
+    pub(crate) value: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliTokenInfo {
+    pub(crate) address: String,
+    pub(crate) meta_address: String,
+    pub(crate) meta_authority: Option<String>,
+    pub(crate) symbol: String,
+    pub(crate) name: String,
+    pub(crate) icon: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) additional_metadata: Vec<CliMetadataField>,
+}
+
+impl QuietDisplay for CliTokenInfo {}
+impl VerboseDisplay for CliTokenInfo {}
+
+impl fmt::Display for CliTokenInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln_name_value(f, "Token:", &self.address)?;
+        writeln_name_value(f, "Token Meta:", &self.meta_address)?;
+        writeln_name_value(
+            f,
+            "Token Meta Authority:",
+            self.meta_authority.as_deref().unwrap_or("None"),
+        )?;
+        writeln_name_value(f, "Token Symbol:", &self.symbol)?;
+        writeln_name_value(f, "Token Name:", &self.name)?;
+        writeln_name_value(f, "Token Icon:", &self.icon)?;
+        if !self.additional_metadata.is_empty() {
+            writeln!(f, "Additional Metadata:")?;
+            for field in &self.additional_metadata {
+                writeln_name_value(f, &format!("  {}:", field.key), &field.value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliDistribution {
+    pub(crate) rows: usize,
+    pub(crate) transactions: usize,
+    pub(crate) confirmed: usize,
+    pub(crate) failed: usize,
+    pub(crate) pending: usize,
+    pub(crate) state_file: String,
+    pub(crate) results_file: String,
+}
+
+impl QuietDisplay for CliDistribution {}
+impl VerboseDisplay for CliDistribution {}
+
+impl fmt::Display for CliDistribution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f)?;
+        writeln_name_value(f, "Rows:", &self.rows.to_string())?;
+        writeln_name_value(f, "Transactions sent:", &self.transactions.to_string())?;
+        writeln_name_value(f, "Confirmed:", &self.confirmed.to_string())?;
+        writeln_name_value(f, "Failed:", &self.failed.to_string())?;
+        writeln_name_value(f, "Pending:", &self.pending.to_string())?;
+        writeln_name_value(f, "State file:", &self.state_file)?;
+        writeln_name_value(f, "Results:", &self.results_file)?;
+        if self.pending > 0 {
+            writeln!(f)?;
+            writeln!(
+                f,
+                "* Rerun the same command to settle pending transfers once their blockhash expires"
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliHolderAccount {
+    pub(crate) address: String,
+    pub(crate) amount: String,
+    pub(crate) ui_amount: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) withheld_amount: Option<String>,
+    pub(crate) frozen: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) delegate: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) delegated_amount: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliHolder {
+    pub(crate) owner: String,
+    pub(crate) amount: String,
+    pub(crate) ui_amount: String,
+    pub(crate) withheld_amount: String,
+    pub(crate) accounts: Vec<CliHolderAccount>,
+}
+
+impl CliHolder {
+    fn flags(&self) -> String {
+        let mut flags = vec![];
+        if self.accounts.iter().any(|account| account.frozen) {
+            flags.push(format!("{}  Frozen", WARNING));
+        }
+        if self
+            .accounts
+            .iter()
+            .any(|account| account.delegate.is_some())
+        {
+            flags.push("Delegated".to_string());
+        }
+        flags.join(" ")
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliHolders {
+    pub(crate) mint: String,
+    pub(crate) decimals: u8,
+    pub(crate) supply: String,
+    pub(crate) ui_supply: String,
+    pub(crate) total: String,
+    pub(crate) ui_total: String,
+    pub(crate) withheld: String,
+    pub(crate) ui_withheld: String,
+    pub(crate) mint_withheld: String,
+    pub(crate) reconciled: bool,
+    pub(crate) holders: Vec<CliHolder>,
+    #[serde(skip)]
+    pub(crate) max_len_balance: usize,
+}
+
+impl CliHolders {
+    fn write_holders(&self, w: &mut dyn fmt::Write, verbose: bool) -> fmt::Result {
+        writeln_name_value(w, "Mint:", &self.mint)?;
+        writeln_name_value(w, "Supply:", &self.ui_supply)?;
+        writeln_name_value(w, "Held:", &self.ui_total)?;
+        if self.withheld != "0" {
+            writeln_name_value(w, "Withheld fees:", &self.ui_withheld)?;
+        }
+        writeln_name_value(w, "Holders:", &self.holders.len().to_string())?;
+        if !self.reconciled {
+            writeln!(
+                w,
+                "{}  Holdings do not add up to the supply; the mint may have changed while \
+                fetching accounts",
+                WARNING
+            )?;
+        }
+        writeln!(w)?;
+        writeln!(
+            w,
+            "{:<44}  {:<2$}  Accounts",
+            "Owner", "Balance", self.max_len_balance
+        )?;
+        writeln!(
+            w,
+            "---------------------------------------------------------------------------"
+        )?;
+        for holder in &self.holders {
+            writeln!(
+                w,
+                "{:<44}  {:<4$}  {:<8}  {}",
+                holder.owner,
+                holder.ui_amount,
+                holder.accounts.len(),
+                holder.flags(),
+                self.max_len_balance,
+            )?;
+            if verbose {
+                for account in &holder.accounts {
+                    let mut details = vec![];
+                    if account.frozen {
+                        details.push("frozen".to_string());
+                    }
+                    if let Some(withheld_amount) = &account.withheld_amount {
+                        details.push(format!("{} withheld", withheld_amount));
+                    }
+                    if let (Some(delegate), Some(delegated_amount)) =
+                        (&account.delegate, &account.delegated_amount)
+                    {
+                        details.push(format!("delegate {} ({})", delegate, delegated_amount));
+                    }
+                    writeln!(
+                        w,
+                        "  {:<42}  {:<3$}  {}",
+                        account.address,
+                        account.ui_amount,
+                        details.join(", "),
+                        self.max_len_balance,
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl QuietDisplay for CliHolders {}
+impl VerboseDisplay for CliHolders {
+    fn write_str(&self, w: &mut dyn fmt::Write) -> fmt::Result {
+        self.write_holders(w, true)
+    }
+}
+
+impl fmt::Display for CliHolders {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_holders(f, false)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliTransactionSigner {
+    pub(crate) pubkey: String,
+    pub(crate) signed: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliTransactionFile {
+    pub(crate) path: String,
+    pub(crate) blockhash: String,
+    pub(crate) signers: Vec<CliTransactionSigner>,
+    pub(crate) complete: bool,
+}
+
+impl QuietDisplay for CliTransactionFile {}
+impl VerboseDisplay for CliTransactionFile {}
+
+impl fmt::Display for CliTransactionFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f)?;
+        writeln_name_value(f, "Transaction file:", &self.path)?;
+        writeln_name_value(f, "Blockhash:", &self.blockhash)?;
+        writeln_name_value(f, "Signers:", " ")?;
+        for signer in &self.signers {
+            let status = if signer.signed { "signed" } else { "missing" };
+            writeln!(f, "  {:<44}  {}", signer.pubkey, status)?;
+        }
+        let signed = self.signers.iter().filter(|signer| signer.signed).count();
+        writeln_name_value(
+            f,
+            "Signatures:",
+            &format!("{}/{}", signed, self.signers.len()),
+        )?;
+        if self.complete {
+            writeln!(f)?;
+            writeln!(f, "* Run `ppl-token broadcast {}` to send it", self.path)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliHistoryEntry {
+    pub(crate) signature: String,
+    pub(crate) slot: u64,
+    pub(crate) block_time: Option<i64>,
+    pub(crate) error: Option<String>,
+    pub(crate) instructions: Vec<String>,
+    pub(crate) memos: Vec<String>,
+    pub(crate) change: Option<String>,
+    pub(crate) balance: Option<String>,
+}
+
+impl fmt::Display for CliHistoryEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let time = self
+            .block_time
+            .map(unix_timestamp_to_string)
+            .unwrap_or_else(|| "Unknown time".to_string());
+        writeln!(f, "{}  Slot {}  {}", time, self.slot, self.signature)?;
+        if let Some(error) = &self.error {
+            writeln!(f, "  Failed: {}", error)?;
+        }
+        for instruction in &self.instructions {
+            writeln!(f, "  {}", instruction)?;
+        }
+        for memo in &self.memos {
+            writeln!(f, "  Memo: {:?}", memo)?;
+        }
+        if let (Some(change), Some(balance)) = (&self.change, &self.balance) {
+            writeln!(f, "  Balance: {} ({})", balance, change)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliHistory {
+    pub(crate) address: String,
+    /// Oldest first
+    pub(crate) entries: Vec<CliHistoryEntry>,
+    /// Signature to continue before, if there may be older transactions
+    pub(crate) before: Option<String>,
+}
+
+impl QuietDisplay for CliHistory {}
+impl VerboseDisplay for CliHistory {}
+
+impl fmt::Display for CliHistory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f)?;
+        writeln_name_value(f, "Account:", &self.address)?;
+        writeln_name_value(f, "Transactions:", &self.entries.len().to_string())?;
+        for entry in &self.entries {
+            writeln!(f)?;
+            write!(f, "{}", entry)?;
+        }
+        if let Some(before) = &self.before {
+            writeln!(f)?;
+            writeln!(
+                f,
+                "* Run again with `--before {}` for older transactions",
+                before
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -1,29 +1,22 @@
-use crate::get_associated_token_address;
-use put_account_decoder::{
-    parse_token::{TokenAccountType, UiTokenAccount},
-    UiAccountData,
-};
+use crate::{get_associated_token_address, output::CliTokenAccount};
+use put_account_decoder::{parse_token::TokenAccountType, UiAccountData};
 use put_client::rpc_response::RpcKeyedAccount;
 use put_sdk::pubkey::Pubkey;
+use serde::{Deserialize, Serialize};
 use std::{
     collections::{btree_map::Entry, BTreeMap},
     str::FromStr,
 };
 
-pub type MintAccounts = BTreeMap<String, Vec<ParsedTokenAccount>>;
-
-pub struct ParsedTokenAccount {
-    pub address: String,
-    pub ui_token_account: UiTokenAccount,
-    pub is_associated: bool,
-}
+pub(crate) type MintAccounts = BTreeMap<String, Vec<CliTokenAccount>>;
 
-pub struct UnsupportedAccount {
+#[derive(Serialize, Deserialize)]
+pub(crate) struct UnsupportedAccount {
     pub address: String,
     pub err: String,
 }
 
-pub fn sort_and_parse_token_accounts(
+pub(crate) fn sort_and_parse_token_accounts(
     owner: &Pubkey,
     accounts: Vec<RpcKeyedAccount>,
 ) -> (MintAccounts, Vec<UnsupportedAccount>, usize, bool) {
@@ -55,10 +48,10 @@ pub fn sort_and_parse_token_accounts(
                             .real_number_string_trimmed()
                             .len();
                         max_len_balance = max_len_balance.max(len_balance);
-                        let parsed_account = ParsedTokenAccount {
+                        let parsed_account = CliTokenAccount {
                             address,
-                            ui_token_account,
                             is_associated,
+                            account: ui_token_account,
                         };
                         let entry = mint_accounts.entry(mint);
                         match entry {
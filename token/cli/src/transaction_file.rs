@@ -0,0 +1,234 @@
+use crate::{
+    config::Config,
+    output::{println_display, CliTransactionFile, CliTransactionSigner},
+    CommandResult, Error,
+};
+use ppl_token::state::Multisig;
+use put_cli_output::CliSignature;
+use put_sdk::{
+    message::Message,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+    transaction::Transaction,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, str::FromStr};
+
+// A transaction waiting for signatures, passed between the signers of a multisig. The
+// message is fixed when the file is exported, signers only add their signature to it.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TransactionFile {
+    /// Base58 encoded message
+    message: String,
+    /// Base58 encoded signatures by signer address
+    signatures: BTreeMap<String, String>,
+}
+
+impl TransactionFile {
+    fn from_transaction(transaction: &Transaction) -> Self {
+        let message_data = transaction.message_data();
+        let signatures = transaction
+            .message
+            .account_keys
+            .iter()
+            .zip(transaction.signatures.iter())
+            .filter(|(pubkey, signature)| signature.verify(pubkey.as_ref(), &message_data))
+            .map(|(pubkey, signature)| (pubkey.to_string(), signature.to_string()))
+            .collect();
+        Self {
+            message: bs58::encode(message_data).into_string(),
+            signatures,
+        }
+    }
+
+    fn load(path: &str) -> Result<(Self, Vec<u8>, Message), Error> {
+        let contents =
+            fs::read_to_string(path).map_err(|err| format!("Could not read {}: {}", path, err))?;
+        let file = serde_json::from_str::<Self>(&contents)
+            .map_err(|err| format!("Invalid transaction file {}: {}", path, err))?;
+        let message_data = bs58::decode(&file.message).into_vec()?;
+        let message = bincode::deserialize::<Message>(&message_data)?;
+        let signers = message.signer_keys();
+        for (pubkey, signature) in &file.signatures {
+            let pubkey = Pubkey::from_str(pubkey)?;
+            if !signers.contains(&&pubkey) {
+                return Err(
+                    format!("{} is not a signer of the transaction in {}", pubkey, path).into(),
+                );
+            }
+            if !Signature::from_str(signature)?.verify(pubkey.as_ref(), &message_data) {
+                return Err(format!("Invalid signature from {} in {}", pubkey, path).into());
+            }
+        }
+        Ok((file, message_data, message))
+    }
+
+    fn save(&self, path: &str) -> Result<(), Error> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .map_err(|err| format!("Could not write {}: {}", path, err).into())
+    }
+
+    fn summary(&self, path: &str, message: &Message) -> CliTransactionFile {
+        let signers = message
+            .signer_keys()
+            .into_iter()
+            .map(|pubkey| CliTransactionSigner {
+                pubkey: pubkey.to_string(),
+                signed: self.signatures.contains_key(&pubkey.to_string()),
+            })
+            .collect::<Vec<_>>();
+        CliTransactionFile {
+            path: path.to_string(),
+            blockhash: message.recent_blockhash.to_string(),
+            complete: signers.iter().all(|signer| signer.signed),
+            signers,
+        }
+    }
+}
+
+/// Writes a partially signed transaction to `path` for the remaining signers
+pub(crate) fn export_transaction(
+    transaction: &Transaction,
+    path: &str,
+) -> Result<CliTransactionFile, Error> {
+    let file = TransactionFile::from_transaction(transaction);
+    file.save(path)?;
+    Ok(file.summary(path, &transaction.message))
+}
+
+pub(crate) fn command_sign_transaction(
+    config: &Config,
+    path: &str,
+    signers: &[Box<dyn Signer>],
+    out: Option<&str>,
+) -> CommandResult {
+    let (mut file, message_data, message) = TransactionFile::load(path)?;
+    let message_signers = message.signer_keys();
+    for signer in signers {
+        let pubkey = signer.pubkey();
+        if !message_signers.contains(&&pubkey) {
+            return Err(
+                format!("{} is not a signer of the transaction in {}", pubkey, path).into(),
+            );
+        }
+        let signature = signer.try_sign_message(&message_data)?;
+        file.signatures
+            .insert(pubkey.to_string(), signature.to_string());
+        println_display(config, format!("Signed by {}", pubkey));
+    }
+
+    let out = out.unwrap_or(path);
+    file.save(out)?;
+    println!(
+        "{}",
+        config
+            .output_format
+            .formatted_string(&file.summary(out, &message))
+    );
+    Ok(None)
+}
+
+pub(crate) fn command_merge_signatures(
+    config: &Config,
+    paths: &[&str],
+    out: Option<&str>,
+) -> CommandResult {
+    let (mut merged, _, message) = TransactionFile::load(paths[0])?;
+    for path in &paths[1..] {
+        let (file, _, _) = TransactionFile::load(path)?;
+        if file.message != merged.message {
+            return Err(format!("{} holds a different transaction than {}", path, paths[0]).into());
+        }
+        merged.signatures.extend(file.signatures);
+    }
+
+    let out = out.unwrap_or(paths[0]);
+    merged.save(out)?;
+    println!(
+        "{}",
+        config
+            .output_format
+            .formatted_string(&merged.summary(out, &message))
+    );
+    Ok(None)
+}
+
+// Checks that every multisig account of the transaction has enough of its members
+// signing, so that the program does not reject the transaction after it is sent
+fn check_multisig_signatures(
+    config: &Config,
+    message: &Message,
+    file: &TransactionFile,
+) -> Result<(), Error> {
+    let accounts = config
+        .rpc_client
+        .get_multiple_accounts(&message.account_keys)?;
+    for (address, account) in message.account_keys.iter().zip(accounts) {
+        let account = match account {
+            Some(account)
+                if account.owner == ppl_token::id() && account.data.len() == Multisig::LEN =>
+            {
+                account
+            }
+            _ => continue,
+        };
+        let multisig = match Multisig::unpack(&account.data) {
+            Ok(multisig) => multisig,
+            Err(_) => continue,
+        };
+        let signed = multisig.signers[..multisig.n as usize]
+            .iter()
+            .filter(|signer| file.signatures.contains_key(&signer.to_string()))
+            .count();
+        if signed < multisig.m as usize {
+            return Err(format!(
+                "Multisig {} requires {} signatures from its members, found {}",
+                address, multisig.m, signed
+            )
+            .into());
+        }
+        println_display(
+            config,
+            format!(
+                "Multisig {}: {} of {} required signatures",
+                address, signed, multisig.m
+            ),
+        );
+    }
+    Ok(())
+}
+
+pub(crate) fn command_broadcast(config: &Config, path: &str) -> CommandResult {
+    let (file, _, message) = TransactionFile::load(path)?;
+    let missing = file
+        .summary(path, &message)
+        .signers
+        .into_iter()
+        .filter(|signer| !signer.signed)
+        .map(|signer| signer.pubkey)
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        return Err(format!("Missing signatures from {}", missing.join(", ")).into());
+    }
+    check_multisig_signatures(config, &message, &file)?;
+
+    let signatures = message
+        .signer_keys()
+        .into_iter()
+        .map(|pubkey| Signature::from_str(&file.signatures[&pubkey.to_string()]))
+        .collect::<Result<Vec<_>, _>>()?;
+    let transaction = Transaction {
+        signatures,
+        message,
+    };
+    let signature = config
+        .rpc_client
+        .send_and_confirm_transaction_with_spinner(&transaction)?;
+    let cli_signature = CliSignature {
+        signature: signature.to_string(),
+    };
+    println!("{}", config.output_format.formatted_string(&cli_signature));
+    Ok(None)
+}
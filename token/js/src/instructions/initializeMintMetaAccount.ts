@@ -1,5 +1,3 @@
-import { blob, struct, u16, u8, utf8 } from '@com.put/buffer-layout';
-import { publicKey } from '@com.put/buffer-layout-utils';
 import { AccountMeta, PublicKey, SYSVAR_RENT_PUBKEY, TransactionInstruction } from '@com.put/web3.js';
 import { TOKEN_PROGRAM_ID } from '../constants.js';
 import {
@@ -8,20 +6,18 @@ import {
     TokenInvalidInstructionProgramError,
     TokenInvalidInstructionTypeError,
 } from '../errors.js';
+import { MAX_ICON_LENGTH, MAX_NAME_LENGTH, MAX_SYMBOL_LENGTH } from '../state/mintmeta.js';
+import { decodeMintMetaString, encodeMintMetaString } from './internal.js';
 import { TokenInstruction } from './types.js';
 
-/** TODO: docs */
+/** Data of an InitializeMintMetaAccount instruction, each string packed as a length byte and its UTF-8 bytes */
 export interface InitializeMintMetaAccountInstructionData {
     instruction: TokenInstruction.InitMintMetaAccount;
-    meta: Uint8Array;
+    symbol: string;
+    name: string;
+    icon: string;
 }
 
-/** TODO: docs */
-export const initializeMintMetaAccountInstructionData = struct<InitializeMintMetaAccountInstructionData>([
-    u8('instruction'),
-    blob(168,'meta'),
-]);
-
 /**
  * Construct an InitializeMintMetaAccount instruction
  *
@@ -49,20 +45,12 @@ export function initializeMintMetaAccountInstruction(
         { pubkey: mintMeta, isSigner: false, isWritable: true },
     ];
 
-    const data = Buffer.alloc(initializeMintMetaAccountInstructionData.span);
-
-    let meta_str = symbol + '\n' + name + '\n' + icon + '\n';
-    let meta_buff = Buffer.from(meta_str.toString());
-    let buff_zeroPad = Buffer.alloc(168);
-    meta_buff.copy(buff_zeroPad);
-
-    initializeMintMetaAccountInstructionData.encode(
-        {
-            instruction: TokenInstruction.InitMintMetaAccount,
-            meta : buff_zeroPad,
-        },
-        data
-    );
+    const data = Buffer.concat([
+        Buffer.from([TokenInstruction.InitMintMetaAccount]),
+        encodeMintMetaString(symbol.toString(), MAX_SYMBOL_LENGTH),
+        encodeMintMetaString(name.toString(), MAX_NAME_LENGTH),
+        encodeMintMetaString(icon.toString(), MAX_ICON_LENGTH),
+    ]);
 
     return new TransactionInstruction({ keys, programId, data });
 }
@@ -76,7 +64,9 @@ export interface DecodedInitializeMintMetaAccountInstruction {
     };
     data: {
         instruction: TokenInstruction.InitMintMetaAccount;
-        meta: String;
+        symbol: string;
+        name: string;
+        icon: string;
     };
 }
 
@@ -93,7 +83,6 @@ export function decodeInitializeMintMetaAccountInstruction(
     programId = TOKEN_PROGRAM_ID
 ): DecodedInitializeMintMetaAccountInstruction {
     if (!instruction.programId.equals(programId)) throw new TokenInvalidInstructionProgramError();
-    if (instruction.data.length !== initializeMintMetaAccountInstructionData.span) throw new TokenInvalidInstructionDataError();
 
     const {
         keys: { mint, mintMeta},
@@ -123,7 +112,9 @@ export interface DecodedInitializeMintMetaAccountInstructionUnchecked {
     };
     data: {
         instruction: number;
-        meta: String;
+        symbol: string;
+        name: string;
+        icon: string;
     };
 }
 
@@ -139,8 +130,11 @@ export function decodeInitializeMintMetaAccountInstructionUnchecked({
     keys: [mint, mintMeta],
     data,
 }: TransactionInstruction): DecodedInitializeMintMetaAccountInstructionUnchecked {
-    const { instruction, meta } =
-        initializeMintMetaAccountInstructionData.decode(data);
+    const instruction = data[0];
+    const [symbol, afterSymbol] = decodeMintMetaString(data.subarray(1));
+    const [name, afterName] = decodeMintMetaString(afterSymbol);
+    const [icon, rest] = decodeMintMetaString(afterName);
+    if (rest.length) throw new TokenInvalidInstructionDataError();
 
     return {
         programId,
@@ -150,7 +144,9 @@ export function decodeInitializeMintMetaAccountInstructionUnchecked({
         },
         data: {
             instruction,
-            meta:new TextDecoder("utf-8").decode(meta),
+            symbol,
+            name,
+            icon,
         },
     };
 }
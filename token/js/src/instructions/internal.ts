@@ -1,4 +1,5 @@
 import { AccountMeta, PublicKey, Signer } from '@com.put/web3.js';
+import { TokenInvalidInstructionDataError } from '../errors.js';
 
 /** @internal */
 export function addSigners(keys: AccountMeta[], ownerOrAuthority: PublicKey, multiSigners: Signer[]): AccountMeta[] {
@@ -12,3 +13,23 @@ export function addSigners(keys: AccountMeta[], ownerOrAuthority: PublicKey, mul
     }
     return keys;
 }
+
+/** @internal Encodes a mint meta string as a length byte followed by its UTF-8 bytes */
+export function encodeMintMetaString(value: string, maxLength: number): Buffer {
+    const bytes = Buffer.from(value, 'utf8');
+    if (bytes.length > maxLength) throw new TokenInvalidInstructionDataError();
+    return Buffer.concat([Buffer.from([bytes.length]), bytes]);
+}
+
+/** @internal Decodes a length prefixed mint meta string, returning it along with the data that follows */
+export function decodeMintMetaString(data: Uint8Array): [string, Uint8Array] {
+    if (!data.length || data.length < 1 + data[0]) throw new TokenInvalidInstructionDataError();
+    const end = 1 + data[0];
+    let value: string;
+    try {
+        value = new TextDecoder('utf-8', { fatal: true }).decode(data.subarray(1, end));
+    } catch {
+        throw new TokenInvalidInstructionDataError();
+    }
+    return [value, data.subarray(end)];
+}
@@ -1,5 +1,3 @@
-import { blob, struct, u16, u8 } from '@com.put/buffer-layout';
-import { publicKey } from '@com.put/buffer-layout-utils';
 import { AccountMeta, PublicKey, SYSVAR_RENT_PUBKEY, TransactionInstruction } from '@com.put/web3.js';
 import { TOKEN_PROGRAM_ID } from '../constants.js';
 import {
@@ -8,20 +6,16 @@ import {
     TokenInvalidInstructionProgramError,
     TokenInvalidInstructionTypeError,
 } from '../errors.js';
+import { MAX_ICON_LENGTH } from '../state/mintmeta.js';
+import { decodeMintMetaString, encodeMintMetaString } from './internal.js';
 import { TokenInstruction } from './types.js';
 
-/** TODO: docs */
+/** Data of an UpdateTokenIcon instruction, the icon being packed as a length byte followed by its UTF-8 bytes */
 export interface UpdateTokenIconInstructionData {
     instruction: TokenInstruction.UpdateIcon;
-    icon: Uint8Array;
+    icon: string;
 }
 
-/** TODO: docs */
-export const updateTokenIconInstructionData = struct<UpdateTokenIconInstructionData>([
-    u8('instruction'),
-    blob(128,'icon'),
-]);
-
 /**
  * Construct an createUpdateTokenIcon instruction
  *
@@ -42,20 +36,10 @@ export function createUpdateTokenIconInstruction(
         {pubkey: mintMetaAuthority, isSigner: true, isWritable: false},
     ];
 
-    const data = Buffer.alloc(updateTokenIconInstructionData.span);
-
-
-    let icon_buff = Buffer.from((icon + '\n').toString());
-    const icon_zeroPad = Buffer.alloc(128);
-    icon_buff.copy(icon_zeroPad);
-
-    updateTokenIconInstructionData.encode(
-        {
-            instruction: TokenInstruction.UpdateIcon,
-            icon: icon_zeroPad,
-        },
-        data
-    );
+    const data = Buffer.concat([
+        Buffer.from([TokenInstruction.UpdateIcon]),
+        encodeMintMetaString(icon.toString(), MAX_ICON_LENGTH),
+    ]);
 
     return new TransactionInstruction({ keys, programId, data });
 }
@@ -69,7 +53,7 @@ export interface DecodedUpdateTokenIconInstruction {
     };
     data: {
         instruction: TokenInstruction.UpdateIcon;
-        icon: String;
+        icon: string;
     };
 }
 
@@ -86,7 +70,6 @@ export function decodeTokenIconInstruction(
     programId = TOKEN_PROGRAM_ID
 ): DecodedUpdateTokenIconInstruction {
     if (!instruction.programId.equals(programId)) throw new TokenInvalidInstructionProgramError();
-    if (instruction.data.length !== updateTokenIconInstructionData.span) throw new TokenInvalidInstructionDataError();
 
     const {
         keys: { mintMeta, mintMetaAuthority },
@@ -116,7 +99,7 @@ export interface DecodedTokenIconInstructionUnchecked {
     };
     data: {
         instruction: number;
-        icon: String;
+        icon: string;
     };
 }
 
@@ -132,9 +115,9 @@ export function decodeTokenIconInstructionUnchecked({
     keys: [mintMeta, mintMetaAuthority],
     data,
 }: TransactionInstruction): DecodedTokenIconInstructionUnchecked {
-    const { instruction, icon } = updateTokenIconInstructionData.decode(data);
-
-    let str_icon =  new TextDecoder("utf-8").decode(icon).split('\n')[0];
+    const instruction = data[0];
+    const [icon, rest] = decodeMintMetaString(data.subarray(1));
+    if (rest.length) throw new TokenInvalidInstructionDataError();
 
     return {
         programId,
@@ -144,7 +127,7 @@ export function decodeTokenIconInstructionUnchecked({
         },
         data: {
             instruction,
-            icon:str_icon,
+            icon,
         },
     };
 }
@@ -1,5 +1,3 @@
-import { blob, struct, u16, u8 } from '@com.put/buffer-layout';
-import { publicKey } from '@com.put/buffer-layout-utils';
 import { AccountMeta, PublicKey, SYSVAR_RENT_PUBKEY, TransactionInstruction } from '@com.put/web3.js';
 import { TOKEN_PROGRAM_ID } from '../constants.js';
 import {
@@ -8,20 +6,16 @@ import {
     TokenInvalidInstructionProgramError,
     TokenInvalidInstructionTypeError,
 } from '../errors.js';
+import { MAX_NAME_LENGTH } from '../state/mintmeta.js';
+import { decodeMintMetaString, encodeMintMetaString } from './internal.js';
 import { TokenInstruction } from './types.js';
 
-/** TODO: docs */
+/** Data of an UpdateTokenName instruction, the name being packed as a length byte followed by its UTF-8 bytes */
 export interface UpdateTokenNameInstructionData {
     instruction: TokenInstruction.UpdateName;
-    name: Uint8Array;
+    name: string;
 }
 
-/** TODO: docs */
-export const updateTokenNameInstructionData = struct<UpdateTokenNameInstructionData>([
-    u8('instruction'),
-    blob(128,'name'),
-]);
-
 /**
  * Construct an createUpdateTokenName instruction
  *
@@ -42,20 +36,10 @@ export function createUpdateTokenNameInstruction(
         {pubkey: mintMetaAuthority, isSigner: true, isWritable: false},
     ];
 
-    const data = Buffer.alloc(updateTokenNameInstructionData.span);
-
-
-    let name_buff = Buffer.from((name + '\n').toString());
-    const name_zeroPad = Buffer.alloc(128);
-    name_buff.copy(name_zeroPad);
-
-    updateTokenNameInstructionData.encode(
-        {
-            instruction: TokenInstruction.UpdateName,
-            name: name_zeroPad,
-        },
-        data
-    );
+    const data = Buffer.concat([
+        Buffer.from([TokenInstruction.UpdateName]),
+        encodeMintMetaString(name.toString(), MAX_NAME_LENGTH),
+    ]);
 
     return new TransactionInstruction({ keys, programId, data });
 }
@@ -69,7 +53,7 @@ export interface DecodedUpdateNameInstruction {
     };
     data: {
         instruction: TokenInstruction.UpdateName;
-        name: String;
+        name: string;
     };
 }
 
@@ -86,7 +70,6 @@ export function decodeUpdateTokenNameInstruction(
     programId = TOKEN_PROGRAM_ID
 ): DecodedUpdateNameInstruction {
     if (!instruction.programId.equals(programId)) throw new TokenInvalidInstructionProgramError();
-    if (instruction.data.length !== updateTokenNameInstructionData.span) throw new TokenInvalidInstructionDataError();
 
     const {
         keys: { mintMeta, mintMetaAuthority },
@@ -116,7 +99,7 @@ export interface DecodedUpdateTokenNameInstructionUnchecked {
     };
     data: {
         instruction: number;
-        name: String;
+        name: string;
     };
 }
 
@@ -132,9 +115,9 @@ export function decodeUpdateTokenNameInstructionUnchecked({
     keys: [mintMeta, mintMetaAuthority],
     data,
 }: TransactionInstruction): DecodedUpdateTokenNameInstructionUnchecked {
-    const { instruction, name } = updateTokenNameInstructionData.decode(data);
-
-    let str_name =  new TextDecoder("utf-8").decode(name).split('\n')[0];
+    const instruction = data[0];
+    const [name, rest] = decodeMintMetaString(data.subarray(1));
+    if (rest.length) throw new TokenInvalidInstructionDataError();
 
     return {
         programId,
@@ -144,7 +127,7 @@ export function decodeUpdateTokenNameInstructionUnchecked({
         },
         data: {
             instruction,
-            name: str_name,
+            name,
         },
     };
 }
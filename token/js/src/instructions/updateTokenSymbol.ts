@@ -1,5 +1,3 @@
-import { blob, struct, u16, u8 } from '@com.put/buffer-layout';
-import { publicKey } from '@com.put/buffer-layout-utils';
 import { AccountMeta, PublicKey, SYSVAR_RENT_PUBKEY, TransactionInstruction } from '@com.put/web3.js';
 import { TOKEN_PROGRAM_ID } from '../constants.js';
 import {
@@ -8,20 +6,16 @@ import {
     TokenInvalidInstructionProgramError,
     TokenInvalidInstructionTypeError,
 } from '../errors.js';
+import { MAX_SYMBOL_LENGTH } from '../state/mintmeta.js';
+import { decodeMintMetaString, encodeMintMetaString } from './internal.js';
 import { TokenInstruction } from './types.js';
 
-/** TODO: docs */
+/** Data of an UpdateTokenSymbol instruction, the symbol being packed as a length byte followed by its UTF-8 bytes */
 export interface UpdateTokenSymbolInstructionData {
     instruction: TokenInstruction.UpdateSymbol;
-    symbol: Uint8Array;
+    symbol: string;
 }
 
-/** TODO: docs */
-export const updateTokenSymbolInstructionData = struct<UpdateTokenSymbolInstructionData>([
-    u8('instruction'),
-    blob(128,'symbol'),
-]);
-
 /**
  * Construct an createUpdateTokenSymbol instruction
  *
@@ -43,20 +37,10 @@ export function createUpdateTokenSymbolInstruction(
         {pubkey: mintMetaAuthority, isSigner: true, isWritable: false},
     ];
 
-    const data = Buffer.alloc(updateTokenSymbolInstructionData.span);
-
-
-    let symbol_buff = Buffer.from((symbol + '\n').toString());
-    const symbol_zeroPad = Buffer.alloc(128);
-    symbol_buff.copy(symbol_zeroPad);
-
-    updateTokenSymbolInstructionData.encode(
-        {
-            instruction: TokenInstruction.UpdateSymbol,
-            symbol: symbol_zeroPad,
-        },
-        data
-    );
+    const data = Buffer.concat([
+        Buffer.from([TokenInstruction.UpdateSymbol]),
+        encodeMintMetaString(symbol.toString(), MAX_SYMBOL_LENGTH),
+    ]);
 
     return new TransactionInstruction({ keys, programId, data });
 }
@@ -70,7 +54,7 @@ export interface DecodedUpdateTokenSymbolInstruction {
     };
     data: {
         instruction: TokenInstruction.UpdateSymbol;
-        symbol: String;
+        symbol: string;
     };
 }
 
@@ -87,7 +71,6 @@ export function decodeTokenSymbolInstruction(
     programId = TOKEN_PROGRAM_ID
 ): DecodedUpdateTokenSymbolInstruction {
     if (!instruction.programId.equals(programId)) throw new TokenInvalidInstructionProgramError();
-    if (instruction.data.length !== updateTokenSymbolInstructionData.span) throw new TokenInvalidInstructionDataError();
 
     const {
         keys: { mintMeta, mintMetaAuthority },
@@ -117,7 +100,7 @@ export interface DecodedTokenSymbolInstructionUnchecked {
     };
     data: {
         instruction: number;
-        symbol: String;
+        symbol: string;
     };
 }
 
@@ -133,9 +116,9 @@ export function decodeTokenSymbolInstructionUnchecked({
     keys: [mintMeta, mintMetaAuthority],
     data,
 }: TransactionInstruction): DecodedTokenSymbolInstructionUnchecked {
-    const { instruction, symbol } = updateTokenSymbolInstructionData.decode(data);
-
-    let str_symbol =  new TextDecoder("utf-8").decode(symbol).split('\n')[0];
+    const instruction = data[0];
+    const [symbol, rest] = decodeMintMetaString(data.subarray(1));
+    if (rest.length) throw new TokenInvalidInstructionDataError();
 
     return {
         programId,
@@ -145,7 +128,7 @@ export function decodeTokenSymbolInstructionUnchecked({
         },
         data: {
             instruction,
-            symbol:str_symbol,
+            symbol,
         },
     };
 }
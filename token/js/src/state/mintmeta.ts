@@ -10,8 +10,14 @@ import {
 } from '../errors.js';
 import { AccountType, ACCOUNT_TYPE_SIZE } from '../extensions/accountType.js';
 
-
-
+/** Maximum length in bytes of a token symbol */
+export const MAX_SYMBOL_LENGTH = 8;
+/** Maximum length in bytes of a token name */
+export const MAX_NAME_LENGTH = 32;
+/** Maximum length in bytes of a token icon url */
+export const MAX_ICON_LENGTH = 124;
+/** Version of the mint meta layout storing each string behind a length byte */
+export const MINT_META_VERSION = 1;
 
 export interface Mintmeta {
     /** Address of the account */
@@ -77,19 +83,35 @@ export const MINT_META_SIZE = MintmetaLayout.span;
 
     const rawMint = MintmetaLayout.decode(info.data.slice(0, MINT_META_SIZE));
 
-    let meta = new TextDecoder("utf-8").decode(rawMint.meta).split('\n');
-    if (meta.length < 3) {
-        new TokenInvalidAccountSizeError();
-    }
+    const [symbol, name, icon] = decodeMeta(rawMint.meta);
     return {
         address,
         authorityOption: rawMint.authorityOption ? rawMint.authority : null,
-        symbol:meta[0],
-        name:meta[1],
-        icon:meta[2],
+        symbol,
+        name,
+        icon,
     };
 }
 
+/** Decodes the symbol, name and icon, stored behind length bytes, or separated by newlines in older records */
+function decodeMeta(meta: Uint8Array): string[] {
+    const decoder = new TextDecoder('utf-8');
+    if (meta[0] !== MINT_META_VERSION) {
+        const values = decoder.decode(meta).split('\n');
+        if (values.length < 3) throw new TokenInvalidAccountError();
+        return values.slice(0, 3);
+    }
+    const values = [];
+    let offset = 1;
+    for (let i = 0; i < 3; i++) {
+        const length = meta[offset];
+        if (length === undefined || offset + 1 + length > meta.length) throw new TokenInvalidAccountError();
+        values.push(decoder.decode(meta.subarray(offset + 1, offset + 1 + length)));
+        offset += 1 + length;
+    }
+    return values;
+}
+
 /** Get the minimum lamport balance for a mint to be rent exempt
  *
  * @param connection Connection to use
@@ -74,6 +74,52 @@ pub enum TokenError {
     /// Instruction does not support non-native tokens
     #[error("Instruction does not support non-native tokens")]
     NonNativeNotSupported,
+
+    // 20
+    /// Transfer fee exceeds the maximum of 10,000 basis points
+    #[error("Transfer fee exceeds maximum of 10,000 basis points")]
+    TransferFeeExceedsMaximum,
+    /// The mint must be provided to transfer from or to this account
+    #[error("Mint required for this account to transfer tokens, use `transfer_checked`")]
+    MintRequiredForTransfer,
+    /// Account can only be closed once its withheld transfer fees are withdrawn
+    #[error("Account has withheld transfer fees")]
+    AccountHasWithheldTransferFees,
+    /// No authority exists to perform the desired operation
+    #[error("No authority exists to perform the desired operation")]
+    NoAuthorityExists,
+    /// Mint or account does not carry the required extension
+    #[error("Mint or account does not carry the required extension")]
+    ExtensionNotFound,
+
+    // 25
+    /// Tokens of a non-transferable mint cannot be transferred or delegated
+    #[error("Transfers are disabled for this mint")]
+    NonTransferable,
+    /// Mint can only be closed once its supply is zero
+    #[error("Mint has a non-zero supply")]
+    MintHasSupply,
+    /// Transfers into the account must be preceded by a memo instruction
+    #[error("No memo in previous instruction; required for recipient to receive a transfer")]
+    NoMemo,
+    /// The account's CPI guard requires the owner to act at the top level
+    #[error("Owner action blocked by the account's CPI guard")]
+    CpiGuardBlocked,
+    /// A symbol, name or icon exceeds the space available in the mint meta account
+    #[error("Mint meta symbol, name or icon is too long")]
+    MintMetaTooLong,
+    /// Mint meta symbols, names and icons must be printable
+    #[error("Mint meta symbol, name or icon contains a control character")]
+    InvalidMintMetaCharacter,
+    /// The mint meta account carries no additional metadata under the key
+    #[error("Mint meta field not found")]
+    MintMetaFieldNotFound,
+    /// The encrypted note is malformed, or cannot be decrypted with the given key
+    #[error("Invalid encrypted note")]
+    InvalidEncryptedNote,
+    /// The operator approval does not allow moving that many more tokens this period
+    #[error("Operator approval limit exceeded")]
+    OperatorLimitExceeded,
 }
 impl From<TokenError> for ProgramError {
     fn from(e: TokenError) -> Self {
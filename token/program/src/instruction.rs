@@ -1,16 +1,32 @@
 //! Instruction types
 
-use crate::{check_program_account, error::TokenError};
+use crate::{
+    check_program_account,
+    error::TokenError,
+    get_balance_checkpoint_address, get_operator_approval_address,
+    note::MAX_NOTE_LEN,
+    state::{
+        AccountState, ExtensionType, MAX_ICON_LEN, MAX_METADATA_KEY_LEN, MAX_NAME_LEN,
+        MAX_SYMBOL_LEN,
+    },
+};
+use num_enum::TryFromPrimitive;
 use put_program::{
+    clock::Epoch,
     instruction::{AccountMeta, Instruction},
     program_error::ProgramError,
     program_option::COption,
     pubkey::Pubkey,
     system_program, sysvar,
 };
+#[cfg(feature = "serde-traits")]
+use serde::{Deserialize, Serialize};
 use std::convert::TryInto;
 use std::mem::size_of;
 
+mod decode;
+pub use decode::{decode, AccountRole, DecodedAccount, DecodedTokenInstruction};
+
 /// Minimum number of multisignature signers (min N)
 pub const MIN_SIGNERS: usize = 1;
 /// Maximum number of multisignature signers (max N)
@@ -19,6 +35,7 @@ pub const MAX_SIGNERS: usize = 11;
 /// Instructions supported by the token program.
 #[repr(C)]
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde-traits", derive(Serialize, Deserialize))]
 pub enum TokenInstruction {
     /// Initializes a new mint and optionally deposits all the newly minted
     /// tokens in an account.
@@ -38,8 +55,16 @@ pub enum TokenInstruction {
         /// Number of base 10 digits to the right of the decimal place.
         decimals: u8,
         /// The authority/multisignature to mint tokens.
+        #[cfg_attr(
+            feature = "serde-traits",
+            serde(with = "crate::serialization::pubkey_fromstr")
+        )]
         mint_authority: Pubkey,
         /// The freeze authority/multisignature of the mint.
+        #[cfg_attr(
+            feature = "serde-traits",
+            serde(with = "crate::serialization::coption_pubkey_fromstr")
+        )]
         freeze_authority: COption<Pubkey>,
     },
     /// Initializes a new account to hold tokens.  If this account is associated
@@ -90,6 +115,19 @@ pub enum TokenInstruction {
     /// amounts of PUT and Tokens will be transferred to the destination
     /// account.
     ///
+    /// If the destination requires incoming transfer memos, the instruction
+    /// must be preceded by a memo instruction, and the instructions sysvar
+    /// must be appended to the accounts below.
+    ///
+    /// If the source account has its CPI guard enabled, the owner may only
+    /// transfer at the top level of a transaction, and the instructions
+    /// sysvar must be appended to the accounts below.
+    ///
+    /// An operator approved by the source account's owner with
+    /// `ApproveOperator` may act as the authority, appending its writable
+    /// operator approval account to the accounts below. The CPI guard of the
+    /// source account binds the operator as it binds the owner.
+    ///
     /// Accounts expected by this instruction:
     ///
     ///   * Single owner/delegate
@@ -109,6 +147,10 @@ pub enum TokenInstruction {
     /// Approves a delegate.  A delegate is given the authority over tokens on
     /// behalf of the source account's owner.
     ///
+    /// If the source account has its CPI guard enabled, the owner may only
+    /// approve at the top level of a transaction, and the instructions
+    /// sysvar must be appended to the accounts below.
+    ///
     /// Accounts expected by this instruction:
     ///
     ///   * Single owner
@@ -140,6 +182,10 @@ pub enum TokenInstruction {
     Revoke,
     /// Sets a new authority of a mint or account.
     ///
+    /// If the account has its CPI guard enabled, the owner may only change
+    /// its close authority at the top level of a transaction, and the
+    /// instructions sysvar must be appended to the accounts below.
+    ///
     /// Accounts expected by this instruction:
     ///
     ///   * Single authority
@@ -154,6 +200,10 @@ pub enum TokenInstruction {
         /// The type of authority to update.
         authority_type: AuthorityType,
         /// The new authority
+        #[cfg_attr(
+            feature = "serde-traits",
+            serde(with = "crate::serialization::coption_pubkey_fromstr")
+        )]
         new_authority: COption<Pubkey>,
     },
     /// Mints new tokens to an account.  The native mint does not support
@@ -178,6 +228,15 @@ pub enum TokenInstruction {
     /// Burns tokens by removing them from an account.  `Burn` does not support
     /// accounts associated with the native mint, use `CloseAccount` instead.
     ///
+    /// If the account has its CPI guard enabled, the owner may only burn at
+    /// the top level of a transaction, and the instructions sysvar must be
+    /// appended to the accounts below.
+    ///
+    /// An operator approved by the account's owner with `ApproveOperator` may
+    /// act as the authority, appending its writable operator approval account
+    /// to the accounts below. The CPI guard of the account binds the operator
+    /// as it binds the owner.
+    ///
     /// Accounts expected by this instruction:
     ///
     ///   * Single owner/delegate
@@ -197,6 +256,13 @@ pub enum TokenInstruction {
     /// Close an account by transferring all its PUT to the destination account.
     /// Non-native accounts may only be closed if its token amount is zero.
     ///
+    /// A mint may also be closed, by its close authority, once its supply is
+    /// zero. Its `MintMeta` account is closed along with it.
+    ///
+    /// If the account has its CPI guard enabled, the owner may only close it
+    /// at the top level of a transaction, and the instructions sysvar must be
+    /// appended to the accounts below.
+    ///
     /// Accounts expected by this instruction:
     ///
     ///   * Single owner
@@ -209,6 +275,19 @@ pub enum TokenInstruction {
     ///   1. `[writable]` The destination account.
     ///   2. `[]` The account's multisignature owner.
     ///   3. ..3+M `[signer]` M signer accounts.
+    ///
+    ///   * Mint, single close authority
+    ///   0. `[writable]` The mint to close.
+    ///   1. `[writable]` The destination account.
+    ///   2. `[signer]` The mint's close authority.
+    ///   3. `[writable]` The mint's `MintMeta` account.
+    ///
+    ///   * Mint, multisignature close authority
+    ///   0. `[writable]` The mint to close.
+    ///   1. `[writable]` The destination account.
+    ///   2. `[]` The mint's multisignature close authority.
+    ///   3. `[writable]` The mint's `MintMeta` account.
+    ///   4. ..4+M `[signer]` M signer accounts.
     CloseAccount,
     /// Freeze an Initialized account using the Mint's freeze_authority (if
     /// set).
@@ -247,6 +326,19 @@ pub enum TokenInstruction {
     /// amounts of PUT and Tokens will be transferred to the destination
     /// account.
     ///
+    /// If the destination requires incoming transfer memos, the instruction
+    /// must be preceded by a memo instruction, and the instructions sysvar
+    /// must be appended to the accounts below.
+    ///
+    /// If the source account has its CPI guard enabled, the owner may only
+    /// transfer at the top level of a transaction, and the instructions
+    /// sysvar must be appended to the accounts below.
+    ///
+    /// An operator approved by the source account's owner with
+    /// `ApproveOperator` may act as the authority, appending its writable
+    /// operator approval account to the accounts below. The CPI guard of the
+    /// source account binds the operator as it binds the owner.
+    ///
     /// This instruction differs from Transfer in that the token mint and
     /// decimals value is checked by the caller.  This may be useful when
     /// creating transactions offline or within a hardware wallet.
@@ -278,6 +370,15 @@ pub enum TokenInstruction {
     /// decimals value is checked by the caller.  This may be useful when
     /// creating transactions offline or within a hardware wallet.
     ///
+    /// If the source account has its CPI guard enabled, the owner may only
+    /// approve at the top level of a transaction, and the instructions
+    /// sysvar must be appended to the accounts below.
+    ///
+    /// With a period, the delegation recurs: the delegate may move up to
+    /// `amount` tokens per period, the delegated amount being restored at the
+    /// start of every period. The source account must have been allocated
+    /// with room for the `RecurringDelegation` extension, see `Reallocate`.
+    ///
     /// Accounts expected by this instruction:
     ///
     ///   * Single owner
@@ -293,10 +394,13 @@ pub enum TokenInstruction {
     ///   3. `[]` The source account's multisignature owner.
     ///   4. ..4+M `[signer]` M signer accounts
     ApproveChecked {
-        /// The amount of tokens the delegate is approved for.
+        /// The amount of tokens the delegate is approved for, per period if
+        /// the delegation recurs.
         amount: u128,
         /// Expected number of base 10 digits to the right of the decimal place.
         decimals: u8,
+        /// Length of a period in seconds if the delegation recurs.
+        period: Option<u64>,
     },
     /// Mints new tokens to an account.  The native mint does not support
     /// minting.
@@ -327,6 +431,15 @@ pub enum TokenInstruction {
     /// support accounts associated with the native mint, use `CloseAccount`
     /// instead.
     ///
+    /// If the account has its CPI guard enabled, the owner may only burn at
+    /// the top level of a transaction, and the instructions sysvar must be
+    /// appended to the accounts below.
+    ///
+    /// An operator approved by the account's owner with `ApproveOperator` may
+    /// act as the authority, appending its writable operator approval account
+    /// to the accounts below. The CPI guard of the account binds the operator
+    /// as it binds the owner.
+    ///
     /// This instruction differs from Burn in that the decimals value is checked
     /// by the caller. This may be useful when creating transactions offline or
     /// within a hardware wallet.
@@ -361,6 +474,10 @@ pub enum TokenInstruction {
     ///   3. `[]` Rent sysvar
     InitializeAccount2 {
         /// The new account's owner/multisignature.
+        #[cfg_attr(
+            feature = "serde-traits",
+            serde(with = "crate::serialization::pubkey_fromstr")
+        )]
         owner: Pubkey,
     },
     /// Given a wrapped / native token account (a token account containing PUT)
@@ -373,7 +490,9 @@ pub enum TokenInstruction {
     ///
     ///   0. `[writable]`  The native token account to sync with its underlying lamports.
     SyncNative,
-    /// Update a new symbol name of a mint.
+    /// Update a new symbol name of a mint.  The symbol may be at most
+    /// `state::MAX_SYMBOL_LEN` bytes long, and is packed as a length byte
+    /// followed by its UTF-8 bytes.
     ///
     /// Accounts expected by this instruction:
     ///
@@ -389,7 +508,9 @@ pub enum TokenInstruction {
         /// The type of authority to update.
         new_symbol: String,
     },
-    /// Update a new name name of a mint.
+    /// Update a new name name of a mint.  The name may be at most
+    /// `state::MAX_NAME_LEN` bytes long, and is packed as a length byte
+    /// followed by its UTF-8 bytes.
     ///
     /// Accounts expected by this instruction:
     ///
@@ -405,7 +526,9 @@ pub enum TokenInstruction {
         /// The type of authority to update.
         new_name: String,
     },
-    /// Update a new icon url of a mint.
+    /// Update a new icon url of a mint.  The url may be at most
+    /// `state::MAX_ICON_LEN` bytes long, and is packed as a length byte
+    /// followed by its UTF-8 bytes.
     ///
     /// Accounts expected by this instruction:
     ///
@@ -433,7 +556,10 @@ pub enum TokenInstruction {
     ///
     CreateMintMetaAccount,
 
-    /// Init a expand meta account for a mint.
+    /// Init a expand meta account for a mint.  The symbol, name and icon url
+    /// may be at most `state::MAX_SYMBOL_LEN`, `state::MAX_NAME_LEN` and
+    /// `state::MAX_ICON_LEN` bytes long, and are each packed as a length byte
+    /// followed by their UTF-8 bytes.
     ///
     /// Accounts expected by this instruction:
     ///
@@ -449,6 +575,557 @@ pub enum TokenInstruction {
         /// token icon url
         icon: String,
     },
+    /// Initializes the transfer fee configuration of a new mint. The mint
+    /// account must have been allocated with room for the extension, see
+    /// `state::ExtensionType::get_account_len`.
+    ///
+    /// Fails if the mint has already been initialized, so it must be called
+    /// before `InitializeMint`, within the same transaction.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` The mint to initialize.
+    InitializeTransferFeeConfig {
+        /// Pubkey that may update the fees
+        #[cfg_attr(
+            feature = "serde-traits",
+            serde(with = "crate::serialization::coption_pubkey_fromstr")
+        )]
+        transfer_fee_config_authority: COption<Pubkey>,
+        /// Withdraw instructions must be signed by this key
+        #[cfg_attr(
+            feature = "serde-traits",
+            serde(with = "crate::serialization::coption_pubkey_fromstr")
+        )]
+        withdraw_withheld_authority: COption<Pubkey>,
+        /// Amount of transfer collected as fees, expressed as basis points of the
+        /// transfer amount
+        transfer_fee_basis_points: u16,
+        /// Maximum fee assessed on transfers
+        maximum_fee: u128,
+    },
+    /// Sets the transfer fee of a mint. The new fee only takes effect two epochs
+    /// after the current one, so holders have time to react.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single authority
+    ///   0. `[writable]` The mint.
+    ///   1. `[signer]` The mint's transfer fee config authority.
+    ///
+    ///   * Multisignature authority
+    ///   0. `[writable]` The mint.
+    ///   1. `[]` The mint's multisignature transfer fee config authority.
+    ///   2. ..2+M `[signer]` M signer accounts.
+    SetTransferFee {
+        /// Amount of transfer collected as fees, expressed as basis points of the
+        /// transfer amount
+        transfer_fee_basis_points: u16,
+        /// Maximum fee assessed on transfers
+        maximum_fee: u128,
+    },
+    /// Moves the fees harvested into the mint to a token account of the mint.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single authority
+    ///   0. `[writable]` The mint.
+    ///   1. `[writable]` The destination account.
+    ///   2. `[signer]` The mint's withdraw withheld authority.
+    ///
+    ///   * Multisignature authority
+    ///   0. `[writable]` The mint.
+    ///   1. `[writable]` The destination account.
+    ///   2. `[]` The mint's multisignature withdraw withheld authority.
+    ///   3. ..3+M `[signer]` M signer accounts.
+    WithdrawWithheldTokensFromMint,
+    /// Moves the fees withheld on token accounts of a mint to another token
+    /// account of the same mint.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single authority
+    ///   0. `[]` The mint.
+    ///   1. `[writable]` The destination account.
+    ///   2. `[signer]` The mint's withdraw withheld authority.
+    ///   3. ..3+N `[writable]` The source accounts to withdraw from.
+    ///
+    ///   * Multisignature authority
+    ///   0. `[]` The mint.
+    ///   1. `[writable]` The destination account.
+    ///   2. `[]` The mint's multisignature withdraw withheld authority.
+    ///   3. ..3+M `[signer]` M signer accounts.
+    ///   3+M. ..3+M+N `[writable]` The source accounts to withdraw from.
+    WithdrawWithheldTokensFromAccounts {
+        /// Number of token accounts harvested
+        num_token_accounts: u8,
+    },
+    /// Moves the fees withheld on token accounts into their mint, where the
+    /// withdraw withheld authority can collect them. Permissionless, and
+    /// accounts that are not of the given mint are skipped.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` The mint.
+    ///   1. ..1+N `[writable]` The source accounts to harvest from.
+    HarvestWithheldTokensToMint,
+    /// Initialize the interest-bearing config of a new mint. Raw amounts never
+    /// change; interest only affects their UI representation, see
+    /// `AmountToUiAmount`.
+    ///
+    /// Fails if the mint has already been initialized, so it must be called
+    /// before `InitializeMint`, within the same transaction.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` The mint to initialize.
+    InitializeInterestBearingMint {
+        /// Pubkey that may update the interest rate
+        #[cfg_attr(
+            feature = "serde-traits",
+            serde(with = "crate::serialization::coption_pubkey_fromstr")
+        )]
+        rate_authority: COption<Pubkey>,
+        /// Initial interest rate, in basis points per year
+        rate: i16,
+    },
+    /// Updates the interest rate of an interest-bearing mint.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single authority
+    ///   0. `[writable]` The mint.
+    ///   1. `[signer]` The mint's rate authority.
+    ///
+    ///   * Multisignature authority
+    ///   0. `[writable]` The mint.
+    ///   1. `[]` The mint's multisignature rate authority.
+    ///   2. ..2+M `[signer]` M signer accounts.
+    UpdateInterestRate {
+        /// The new interest rate, in basis points per year
+        rate: i16,
+    },
+    /// Converts a raw amount to its UI representation at the current
+    /// timestamp, including any interest accrued on an interest-bearing mint.
+    /// The result is logged and set as the return data of the instruction.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[]` The mint.
+    AmountToUiAmount {
+        /// The raw amount to convert
+        amount: u128,
+    },
+    /// Makes a new mint non-transferable: its tokens can be minted, burned and
+    /// their accounts closed, but `Transfer`, `TransferChecked`, `Approve` and
+    /// `ApproveChecked` fail.
+    ///
+    /// Fails if the mint has already been initialized, so it must be called
+    /// before `InitializeMint`, within the same transaction.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` The mint to initialize.
+    InitializeNonTransferableMint,
+    /// Initialize the permanent delegate of a new mint. The permanent delegate
+    /// may transfer or burn tokens from any account of the mint, using
+    /// `TransferChecked`, `Burn` or `BurnChecked`.
+    ///
+    /// Fails if the mint has already been initialized, so it must be called
+    /// before `InitializeMint`, within the same transaction.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` The mint to initialize.
+    InitializePermanentDelegate {
+        /// Authority that may transfer or burn from any account of the mint
+        #[cfg_attr(
+            feature = "serde-traits",
+            serde(with = "crate::serialization::pubkey_fromstr")
+        )]
+        delegate: Pubkey,
+    },
+    /// Initialize the state new token accounts of a new mint start in. With
+    /// `AccountState::Frozen`, the mint must have a freeze authority to thaw
+    /// them.
+    ///
+    /// Fails if the mint has already been initialized, so it must be called
+    /// before `InitializeMint`, within the same transaction.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` The mint to initialize.
+    InitializeDefaultAccountState {
+        /// State of new token accounts, either initialized or frozen
+        state: AccountState,
+    },
+    /// Updates the state new token accounts of a mint start in. Existing
+    /// accounts are not affected.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single authority
+    ///   0. `[writable]` The mint.
+    ///   1. `[signer]` The mint's freeze authority.
+    ///
+    ///   * Multisignature authority
+    ///   0. `[writable]` The mint.
+    ///   1. `[]` The mint's multisignature freeze authority.
+    ///   2. ..2+M `[signer]` M signer accounts.
+    UpdateDefaultAccountState {
+        /// State of new token accounts, either initialized or frozen
+        state: AccountState,
+    },
+    /// Initialize the close authority of a new mint, allowed to close the
+    /// mint with `CloseAccount` once its supply is zero.
+    ///
+    /// Fails if the mint has already been initialized, so it must be called
+    /// before `InitializeMint`, within the same transaction.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` The mint to initialize.
+    InitializeMintCloseAuthority {
+        /// Authority that may close the mint
+        #[cfg_attr(
+            feature = "serde-traits",
+            serde(with = "crate::serialization::coption_pubkey_fromstr")
+        )]
+        close_authority: COption<Pubkey>,
+    },
+    /// Requires a memo instruction to precede every transfer into a token
+    /// account. The account must have been allocated with room for the
+    /// setting.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable]` The account to update.
+    ///   1. `[signer]` The account's owner.
+    ///
+    ///   * Multisignature owner
+    ///   0. `[writable]` The account to update.
+    ///   1. `[]` The account's multisignature owner.
+    ///   2. ..2+M `[signer]` M signer accounts.
+    EnableRequiredTransferMemos,
+    /// Stops requiring memos on transfers into a token account.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable]` The account to update.
+    ///   1. `[signer]` The account's owner.
+    ///
+    ///   * Multisignature owner
+    ///   0. `[writable]` The account to update.
+    ///   1. `[]` The account's multisignature owner.
+    ///   2. ..2+M `[signer]` M signer accounts.
+    DisableRequiredTransferMemos,
+    /// Enables the CPI guard of a token account. While enabled, the owner
+    /// may only transfer, approve, change the close authority of, or close
+    /// the account at the top level of a transaction. The account must have
+    /// been allocated with room for the setting.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable]` The account to update.
+    ///   1. `[signer]` The account's owner.
+    ///
+    ///   * Multisignature owner
+    ///   0. `[writable]` The account to update.
+    ///   1. `[]` The account's multisignature owner.
+    ///   2. ..2+M `[signer]` M signer accounts.
+    EnableCpiGuard,
+    /// Disables the CPI guard of a token account. This must itself be
+    /// invoked at the top level of a transaction, with the instructions
+    /// sysvar appended to the accounts below.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable]` The account to update.
+    ///   1. `[signer]` The account's owner.
+    ///
+    ///   * Multisignature owner
+    ///   0. `[writable]` The account to update.
+    ///   1. `[]` The account's multisignature owner.
+    ///   2. ..2+M `[signer]` M signer accounts.
+    DisableCpiGuard,
+    /// Rewrites a mint meta account written before the `MintMeta` layout was
+    /// versioned in the current layout, keeping its contents. Anyone may
+    /// migrate a record; records already in the current layout are left
+    /// untouched.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` The mint meta account to migrate.
+    MigrateMintMeta,
+    /// Sets a key/value pair of additional metadata on a mint meta account,
+    /// replacing the value of an existing key. The account is reallocated to
+    /// fit the pair, and the payer tops up its rent-exempt balance.
+    ///
+    /// The key may be at most `state::MAX_METADATA_KEY_LEN` bytes long and
+    /// the value at most `state::MAX_METADATA_VALUE_LEN` bytes long.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single authority
+    ///   0. `[writable]` The mint meta account.
+    ///   1. `[writable, signer]` The payer.
+    ///   2. `[]` System program.
+    ///   3. `[]` Rent sysvar.
+    ///   4. `[signer]` The meta update authority.
+    ///
+    ///   * Multisignature authority
+    ///   0. `[writable]` The mint meta account.
+    ///   1. `[writable, signer]` The payer.
+    ///   2. `[]` System program.
+    ///   3. `[]` Rent sysvar.
+    ///   4. `[]` The meta's multisignature update authority.
+    ///   5. ..5+M `[signer]` M signer accounts.
+    SetMintMetaField {
+        /// Key of the pair
+        key: String,
+        /// Value of the pair
+        value: String,
+    },
+    /// Removes a key/value pair of additional metadata from a mint meta
+    /// account. The account is reallocated to the remaining pairs, and the
+    /// lamports no longer needed for rent exemption go to the destination.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single authority
+    ///   0. `[writable]` The mint meta account.
+    ///   1. `[writable]` The destination of the reclaimed lamports.
+    ///   2. `[]` Rent sysvar.
+    ///   3. `[signer]` The meta update authority.
+    ///
+    ///   * Multisignature authority
+    ///   0. `[writable]` The mint meta account.
+    ///   1. `[writable]` The destination of the reclaimed lamports.
+    ///   2. `[]` Rent sysvar.
+    ///   3. `[]` The meta's multisignature update authority.
+    ///   4. ..4+M `[signer]` M signer accounts.
+    RemoveMintMetaField {
+        /// Key of the pair to remove
+        key: String,
+    },
+    /// Transfers tokens from one account to each of N destination accounts,
+    /// as N `Transfer` instructions would. Either every transfer succeeds or
+    /// the instruction fails as a whole.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner/delegate
+    ///   0. `[writable]` The source account.
+    ///   1. ..1+N `[writable]` The N destination accounts, in the order of
+    ///      `amounts`.
+    ///   1+N. `[signer]` The source account's owner/delegate.
+    ///
+    ///   * Multisignature owner/delegate
+    ///   0. `[writable]` The source account.
+    ///   1. ..1+N `[writable]` The N destination accounts, in the order of
+    ///      `amounts`.
+    ///   1+N. `[]` The source account's multisignature owner/delegate.
+    ///   2+N. ..2+N+M `[signer]` M signer accounts.
+    BatchTransfer {
+        /// The amounts of tokens to transfer to each destination, 1 <= N <= 255.
+        amounts: Vec<u128>,
+    },
+    /// Transfers tokens from one account to each of N destination accounts,
+    /// as N `TransferChecked` instructions would. Either every transfer
+    /// succeeds or the instruction fails as a whole.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner/delegate
+    ///   0. `[writable]` The source account.
+    ///   1. `[]` The token mint.
+    ///   2. ..2+N `[writable]` The N destination accounts, in the order of
+    ///      `amounts`.
+    ///   2+N. `[signer]` The source account's owner/delegate.
+    ///
+    ///   * Multisignature owner/delegate
+    ///   0. `[writable]` The source account.
+    ///   1. `[]` The token mint.
+    ///   2. ..2+N `[writable]` The N destination accounts, in the order of
+    ///      `amounts`.
+    ///   2+N. `[]` The source account's multisignature owner/delegate.
+    ///   3+N. ..3+N+M `[signer]` M signer accounts.
+    BatchTransferChecked {
+        /// The amounts of tokens to transfer to each destination, 1 <= N <= 255.
+        amounts: Vec<u128>,
+        /// Expected number of base 10 digits to the right of the decimal place.
+        decimals: u8,
+    },
+    /// Records the balance of a token account in its balance checkpoint for the
+    /// current epoch, at `get_balance_checkpoint_address(account, epoch)`. The
+    /// checkpoint account is created on first use, funded by the payer, and
+    /// overwritten by later records within the same epoch. An owner holding
+    /// several accounts of a mint records each of them.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable, signer]` The payer funding the checkpoint account.
+    ///   1. `[writable]` The balance checkpoint account.
+    ///   2. `[]` The token account.
+    ///   3. `[]` The system program.
+    ///   4. `[]` Rent sysvar.
+    ///   5. `[signer]` The token account's owner.
+    ///
+    ///   * Multisignature owner
+    ///   0. `[writable, signer]` The payer funding the checkpoint account.
+    ///   1. `[writable]` The balance checkpoint account.
+    ///   2. `[]` The token account.
+    ///   3. `[]` The system program.
+    ///   4. `[]` Rent sysvar.
+    ///   5. `[]` The token account's multisignature owner.
+    ///   6. ..6+M `[signer]` M signer accounts.
+    RecordBalanceCheckpoint,
+    /// Transfers tokens as `TransferChecked` does, carrying a note encrypted
+    /// for the sender and the recipient. The program only checks the framing
+    /// of the note; see the `note` module to build and read one.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner/delegate
+    ///   0. `[writable]` The source account.
+    ///   1. `[]` The token mint.
+    ///   2. `[writable]` The destination account.
+    ///   3. `[signer]` The source account's owner/delegate.
+    ///
+    ///   * Multisignature owner/delegate
+    ///   0. `[writable]` The source account.
+    ///   1. `[]` The token mint.
+    ///   2. `[writable]` The destination account.
+    ///   3. `[]` The source account's multisignature owner/delegate.
+    ///   4. ..4+M `[signer]` M signer accounts.
+    TransferWithEncryptedNote {
+        /// The amount of tokens to transfer.
+        amount: u128,
+        /// Expected number of base 10 digits to the right of the decimal place.
+        decimals: u8,
+        /// The encrypted note, at most `note::MAX_NOTE_LEN` bytes.
+        note: Vec<u8>,
+    },
+    /// Grows a token account so that it has room for the given account
+    /// extensions, on top of the ones it already carries. The payer tops up
+    /// the rent-exempt balance of the account. Extensions are not initialized
+    /// here, the instructions enabling them do so once there is room.
+    ///
+    /// Does nothing if the account is already large enough.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable]` The token account.
+    ///   1. `[writable, signer]` The payer.
+    ///   2. `[]` System program.
+    ///   3. `[]` Rent sysvar.
+    ///   4. `[signer]` The token account's owner.
+    ///
+    ///   * Multisignature owner
+    ///   0. `[writable]` The token account.
+    ///   1. `[writable, signer]` The payer.
+    ///   2. `[]` System program.
+    ///   3. `[]` Rent sysvar.
+    ///   4. `[]` The token account's multisignature owner.
+    ///   5. ..5+M `[signer]` M signer accounts.
+    Reallocate {
+        /// Account extensions to make room for
+        extension_types: Vec<ExtensionType>,
+    },
+    /// Approves an operator over all the token accounts of a mint held by the
+    /// owner, at `get_operator_approval_address(mint, owner, operator)`. The
+    /// operator may then transfer and burn from those accounts as the owner
+    /// would, up to `limit` tokens per `period`. The approval account is
+    /// created on first use, funded by the payer. Approving an operator again
+    /// replaces its limit and starts a new period.
+    ///
+    /// As the approval reaches accounts that may have their CPI guard enabled,
+    /// it may only be granted at the top level of a transaction, with the
+    /// instructions sysvar appended to the accounts below.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable, signer]` The payer funding the approval account.
+    ///   1. `[writable]` The operator approval account.
+    ///   2. `[]` The token mint.
+    ///   3. `[]` The operator.
+    ///   4. `[]` The system program.
+    ///   5. `[]` Rent sysvar.
+    ///   6. `[signer]` The owner.
+    ///
+    ///   * Multisignature owner
+    ///   0. `[writable, signer]` The payer funding the approval account.
+    ///   1. `[writable]` The operator approval account.
+    ///   2. `[]` The token mint.
+    ///   3. `[]` The operator.
+    ///   4. `[]` The system program.
+    ///   5. `[]` Rent sysvar.
+    ///   6. `[]` The multisignature owner.
+    ///   7. ..7+M `[signer]` M signer accounts.
+    ApproveOperator {
+        /// Amount of tokens the operator may move per period, unlimited if
+        /// `None`.
+        limit: Option<u128>,
+        /// Length of a period in seconds. With a limit and no period, the
+        /// limit covers the lifetime of the approval.
+        period: u64,
+    },
+    /// Revokes an operator approval, closing its account and moving its
+    /// lamports to the destination.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable]` The operator approval account.
+    ///   1. `[writable]` The destination of the reclaimed lamports.
+    ///   2. `[signer]` The owner.
+    ///
+    ///   * Multisignature owner
+    ///   0. `[writable]` The operator approval account.
+    ///   1. `[writable]` The destination of the reclaimed lamports.
+    ///   2. `[]` The multisignature owner.
+    ///   3. ..3+M `[signer]` M signer accounts.
+    RevokeOperator,
+    /// Deposits lamports into a wrapped / native token account and updates
+    /// its amount field as `SyncNative` does, wrapping PUT without a
+    /// temporary account.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` The native token account.
+    ///   1. `[writable, signer]` The system account funding the deposit.
+    ///   2. `[]` System program.
+    DepositNative {
+        /// The amount of lamports to deposit.
+        amount: u128,
+    },
+    /// Withdraws lamports out of a wrapped / native token account, debiting
+    /// its amount field, so that part of the balance can be unwrapped without
+    /// closing the account. The rent-exempt reserve stays in the account.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable]` The native token account.
+    ///   1. `[writable]` The destination of the lamports.
+    ///   2. `[signer]` The account's owner.
+    ///
+    ///   * Multisignature owner
+    ///   0. `[writable]` The native token account.
+    ///   1. `[writable]` The destination of the lamports.
+    ///   2. `[]` The account's multisignature owner.
+    ///   3. ..3+M `[signer]` M signer accounts.
+    WithdrawNative {
+        /// The amount of lamports to withdraw.
+        amount: u128,
+    },
 }
 impl TokenInstruction {
     /// Unpacks a byte buffer into a [TokenInstruction](enum.TokenInstruction.html).
@@ -503,45 +1180,35 @@ impl TokenInstruction {
             10 => Self::FreezeAccount,
             11 => Self::ThawAccount,
             12 => {
-                let (amount, rest) = rest.split_at(16);
-                let amount = amount
-                    .try_into()
-                    .ok()
-                    .map(u128::from_le_bytes)
-                    .ok_or(InvalidInstruction)?;
+                let (amount, rest) = Self::unpack_u128(rest)?;
                 let (&decimals, _rest) = rest.split_first().ok_or(InvalidInstruction)?;
 
                 Self::TransferChecked { amount, decimals }
             }
             13 => {
-                let (amount, rest) = rest.split_at(16);
-                let amount = amount
-                    .try_into()
-                    .ok()
-                    .map(u128::from_le_bytes)
-                    .ok_or(InvalidInstruction)?;
-                let (&decimals, _rest) = rest.split_first().ok_or(InvalidInstruction)?;
-
-                Self::ApproveChecked { amount, decimals }
+                let (amount, rest) = Self::unpack_u128(rest)?;
+                let (&decimals, rest) = rest.split_first().ok_or(InvalidInstruction)?;
+                // The period is optional so that earlier clients keep working
+                let period = match rest.len() {
+                    0 => None,
+                    8 => Some(u64::from_le_bytes(rest.try_into().unwrap())),
+                    _ => return Err(InvalidInstruction.into()),
+                };
+
+                Self::ApproveChecked {
+                    amount,
+                    decimals,
+                    period,
+                }
             }
             14 => {
-                let (amount, rest) = rest.split_at(16);
-                let amount = amount
-                    .try_into()
-                    .ok()
-                    .map(u128::from_le_bytes)
-                    .ok_or(InvalidInstruction)?;
+                let (amount, rest) = Self::unpack_u128(rest)?;
                 let (&decimals, _rest) = rest.split_first().ok_or(InvalidInstruction)?;
 
                 Self::MintToChecked { amount, decimals }
             }
             15 => {
-                let (amount, rest) = rest.split_at(16);
-                let amount = amount
-                    .try_into()
-                    .ok()
-                    .map(u128::from_le_bytes)
-                    .ok_or(InvalidInstruction)?;
+                let (amount, rest) = Self::unpack_u128(rest)?;
                 let (&decimals, _rest) = rest.split_first().ok_or(InvalidInstruction)?;
 
                 Self::BurnChecked { amount, decimals }
@@ -552,41 +1219,142 @@ impl TokenInstruction {
             }
             17 => Self::SyncNative,
             18 => {
-                let src = String::from_utf8(rest.to_vec()).expect("Found invalid UTF-8");
-                let mut item = src.split('\n');
-                let data = item.next().expect("Found invalid Symbol");
-                Self::UpdateSymbol {
-                    new_symbol: data.to_string(),
-                }
+                let (new_symbol, rest) = Self::unpack_meta_string(rest, MAX_SYMBOL_LEN)?;
+                Self::check_end(rest)?;
+                Self::UpdateSymbol { new_symbol }
             }
             19 => {
-                let src = String::from_utf8(rest.to_vec()).expect("Found invalid UTF-8");
-                let mut item = src.split('\n');
-                let data = item.next().expect("Found invalid Name");
-                Self::UpdateName {
-                    new_name: data.to_string(),
-                }
+                let (new_name, rest) = Self::unpack_meta_string(rest, MAX_NAME_LEN)?;
+                Self::check_end(rest)?;
+                Self::UpdateName { new_name }
             }
             20 => {
-                let src = String::from_utf8(rest.to_vec()).expect("Found invalid UTF-8");
-                let mut item = src.split('\n');
-                let data = item.next().expect("Found invalid Icon");
-                Self::UpdateIcon {
-                    new_icon: data.to_string(),
-                }
+                let (new_icon, rest) = Self::unpack_meta_string(rest, MAX_ICON_LEN)?;
+                Self::check_end(rest)?;
+                Self::UpdateIcon { new_icon }
             }
             21 => Self::CreateMintMetaAccount,
             22 => {
-                let src = String::from_utf8(rest.to_vec()).expect("Found invalid UTF-8");
-                let mut item = src.split('\n');
-                let symbol = item.next().expect("Found invalid Symbol");
-                let name = item.next().expect("Found invalid Name");
-                let icon = item.next().expect("Found invalid Icon");
-
-                Self::InitMintMetaAccount {
-                    symbol: symbol.to_string(),
-                    name: name.to_string(),
-                    icon: icon.to_string(),
+                let (symbol, rest) = Self::unpack_meta_string(rest, MAX_SYMBOL_LEN)?;
+                let (name, rest) = Self::unpack_meta_string(rest, MAX_NAME_LEN)?;
+                let (icon, rest) = Self::unpack_meta_string(rest, MAX_ICON_LEN)?;
+                Self::check_end(rest)?;
+                Self::InitMintMetaAccount { symbol, name, icon }
+            }
+            23 => {
+                let (transfer_fee_config_authority, rest) = Self::unpack_pubkey_option(rest)?;
+                let (withdraw_withheld_authority, rest) = Self::unpack_pubkey_option(rest)?;
+                let (transfer_fee_basis_points, rest) = Self::unpack_u16(rest)?;
+                let (maximum_fee, _rest) = Self::unpack_u128(rest)?;
+                Self::InitializeTransferFeeConfig {
+                    transfer_fee_config_authority,
+                    withdraw_withheld_authority,
+                    transfer_fee_basis_points,
+                    maximum_fee,
+                }
+            }
+            24 => {
+                let (transfer_fee_basis_points, rest) = Self::unpack_u16(rest)?;
+                let (maximum_fee, _rest) = Self::unpack_u128(rest)?;
+                Self::SetTransferFee {
+                    transfer_fee_basis_points,
+                    maximum_fee,
+                }
+            }
+            25 => Self::WithdrawWithheldTokensFromMint,
+            26 => {
+                let &num_token_accounts = rest.get(0).ok_or(InvalidInstruction)?;
+                Self::WithdrawWithheldTokensFromAccounts { num_token_accounts }
+            }
+            27 => Self::HarvestWithheldTokensToMint,
+            28 => {
+                let (rate_authority, rest) = Self::unpack_pubkey_option(rest)?;
+                let (rate, _rest) = Self::unpack_i16(rest)?;
+                Self::InitializeInterestBearingMint {
+                    rate_authority,
+                    rate,
+                }
+            }
+            29 => {
+                let (rate, _rest) = Self::unpack_i16(rest)?;
+                Self::UpdateInterestRate { rate }
+            }
+            30 => {
+                let (amount, _rest) = Self::unpack_u128(rest)?;
+                Self::AmountToUiAmount { amount }
+            }
+            31 => Self::InitializeNonTransferableMint,
+            32 => {
+                let (delegate, _rest) = Self::unpack_pubkey(rest)?;
+                Self::InitializePermanentDelegate { delegate }
+            }
+            33 => {
+                let (state, _rest) = Self::unpack_account_state(rest)?;
+                Self::InitializeDefaultAccountState { state }
+            }
+            34 => {
+                let (state, _rest) = Self::unpack_account_state(rest)?;
+                Self::UpdateDefaultAccountState { state }
+            }
+            35 => {
+                let (close_authority, _rest) = Self::unpack_pubkey_option(rest)?;
+                Self::InitializeMintCloseAuthority { close_authority }
+            }
+            36 => Self::EnableRequiredTransferMemos,
+            37 => Self::DisableRequiredTransferMemos,
+            38 => Self::EnableCpiGuard,
+            39 => Self::DisableCpiGuard,
+            40 => Self::MigrateMintMeta,
+            41 => {
+                let (key, rest) = Self::unpack_metadata_key(rest)?;
+                let value = String::from_utf8(rest.to_vec()).map_err(|_| InvalidInstruction)?;
+                Self::SetMintMetaField { key, value }
+            }
+            42 => {
+                let (key, _rest) = Self::unpack_metadata_key(rest)?;
+                Self::RemoveMintMetaField { key }
+            }
+            43 => {
+                let (amounts, _rest) = Self::unpack_amounts(rest)?;
+                Self::BatchTransfer { amounts }
+            }
+            44 => {
+                let (amounts, rest) = Self::unpack_amounts(rest)?;
+                let (&decimals, _rest) = rest.split_first().ok_or(InvalidInstruction)?;
+                Self::BatchTransferChecked { amounts, decimals }
+            }
+            45 => Self::RecordBalanceCheckpoint,
+            46 => {
+                let (amount, rest) = Self::unpack_u128(rest)?;
+                let (&decimals, rest) = rest.split_first().ok_or(InvalidInstruction)?;
+                let (len, rest) = Self::unpack_u16(rest)?;
+                let note = rest.get(..len as usize).ok_or(InvalidInstruction)?.to_vec();
+                Self::TransferWithEncryptedNote {
+                    amount,
+                    decimals,
+                    note,
+                }
+            }
+            47 => {
+                let (extension_types, _rest) = Self::unpack_extension_types(rest)?;
+                Self::Reallocate { extension_types }
+            }
+            48 => {
+                let (limit, rest) = Self::unpack_u128_option(rest)?;
+                let period = rest
+                    .get(..8)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                Self::ApproveOperator { limit, period }
+            }
+            49 => Self::RevokeOperator,
+            50 | 51 => {
+                let (amount, _rest) = Self::unpack_u128(rest)?;
+                match tag {
+                    50 => Self::DepositNative { amount },
+                    51 => Self::WithdrawNative { amount },
+                    _ => unreachable!(),
                 }
             }
 
@@ -646,10 +1414,17 @@ impl TokenInstruction {
                 buf.extend_from_slice(&amount.to_le_bytes());
                 buf.push(decimals);
             }
-            &Self::ApproveChecked { amount, decimals } => {
+            &Self::ApproveChecked {
+                amount,
+                decimals,
+                period,
+            } => {
                 buf.push(13);
                 buf.extend_from_slice(&amount.to_le_bytes());
                 buf.push(decimals);
+                if let Some(period) = period {
+                    buf.extend_from_slice(&period.to_le_bytes());
+                }
             }
             &Self::MintToChecked { amount, decimals } => {
                 buf.push(14);
@@ -670,15 +1445,15 @@ impl TokenInstruction {
             }
             Self::UpdateSymbol { new_symbol } => {
                 buf.push(18);
-                buf.extend_from_slice(new_symbol.clone().as_ref());
+                Self::pack_meta_string(new_symbol, &mut buf);
             }
             Self::UpdateName { new_name } => {
                 buf.push(19);
-                buf.extend_from_slice(new_name.clone().as_ref());
+                Self::pack_meta_string(new_name, &mut buf);
             }
             Self::UpdateIcon { new_icon } => {
                 buf.push(20);
-                buf.extend_from_slice(new_icon.clone().as_ref());
+                Self::pack_meta_string(new_icon, &mut buf);
             }
             &Self::CreateMintMetaAccount => buf.push(21),
             &Self::InitMintMetaAccount {
@@ -687,8 +1462,128 @@ impl TokenInstruction {
                 ref icon,
             } => {
                 buf.push(22);
-                let data = symbol.to_string() + "\n" + name + "\n" + icon + "\n";
-                buf.extend_from_slice(data.as_ref());
+                Self::pack_meta_string(symbol, &mut buf);
+                Self::pack_meta_string(name, &mut buf);
+                Self::pack_meta_string(icon, &mut buf);
+            }
+            &Self::InitializeTransferFeeConfig {
+                ref transfer_fee_config_authority,
+                ref withdraw_withheld_authority,
+                transfer_fee_basis_points,
+                maximum_fee,
+            } => {
+                buf.push(23);
+                Self::pack_pubkey_option(transfer_fee_config_authority, &mut buf);
+                Self::pack_pubkey_option(withdraw_withheld_authority, &mut buf);
+                buf.extend_from_slice(&transfer_fee_basis_points.to_le_bytes());
+                buf.extend_from_slice(&maximum_fee.to_le_bytes());
+            }
+            &Self::SetTransferFee {
+                transfer_fee_basis_points,
+                maximum_fee,
+            } => {
+                buf.push(24);
+                buf.extend_from_slice(&transfer_fee_basis_points.to_le_bytes());
+                buf.extend_from_slice(&maximum_fee.to_le_bytes());
+            }
+            &Self::WithdrawWithheldTokensFromMint => buf.push(25),
+            &Self::WithdrawWithheldTokensFromAccounts { num_token_accounts } => {
+                buf.push(26);
+                buf.push(num_token_accounts);
+            }
+            &Self::HarvestWithheldTokensToMint => buf.push(27),
+            &Self::InitializeInterestBearingMint {
+                ref rate_authority,
+                rate,
+            } => {
+                buf.push(28);
+                Self::pack_pubkey_option(rate_authority, &mut buf);
+                buf.extend_from_slice(&rate.to_le_bytes());
+            }
+            &Self::UpdateInterestRate { rate } => {
+                buf.push(29);
+                buf.extend_from_slice(&rate.to_le_bytes());
+            }
+            &Self::AmountToUiAmount { amount } => {
+                buf.push(30);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            &Self::InitializeNonTransferableMint => buf.push(31),
+            &Self::InitializePermanentDelegate { ref delegate } => {
+                buf.push(32);
+                buf.extend_from_slice(delegate.as_ref());
+            }
+            &Self::InitializeDefaultAccountState { state } => {
+                buf.push(33);
+                buf.push(state as u8);
+            }
+            &Self::UpdateDefaultAccountState { state } => {
+                buf.push(34);
+                buf.push(state as u8);
+            }
+            &Self::InitializeMintCloseAuthority {
+                ref close_authority,
+            } => {
+                buf.push(35);
+                Self::pack_pubkey_option(close_authority, &mut buf);
+            }
+            &Self::EnableRequiredTransferMemos => buf.push(36),
+            &Self::DisableRequiredTransferMemos => buf.push(37),
+            &Self::EnableCpiGuard => buf.push(38),
+            &Self::DisableCpiGuard => buf.push(39),
+            &Self::MigrateMintMeta => buf.push(40),
+            Self::SetMintMetaField { key, value } => {
+                buf.push(41);
+                buf.push(key.len() as u8);
+                buf.extend_from_slice(key.as_bytes());
+                buf.extend_from_slice(value.as_bytes());
+            }
+            Self::RemoveMintMetaField { key } => {
+                buf.push(42);
+                buf.push(key.len() as u8);
+                buf.extend_from_slice(key.as_bytes());
+            }
+            Self::BatchTransfer { amounts } => {
+                buf.push(43);
+                Self::pack_amounts(amounts, &mut buf);
+            }
+            Self::BatchTransferChecked { amounts, decimals } => {
+                buf.push(44);
+                Self::pack_amounts(amounts, &mut buf);
+                buf.push(*decimals);
+            }
+            Self::RecordBalanceCheckpoint => buf.push(45),
+            Self::TransferWithEncryptedNote {
+                amount,
+                decimals,
+                note,
+            } => {
+                buf.push(46);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.push(*decimals);
+                buf.extend_from_slice(&(note.len() as u16).to_le_bytes());
+                buf.extend_from_slice(note);
+            }
+            Self::Reallocate { extension_types } => {
+                buf.push(47);
+                buf.push(extension_types.len() as u8);
+                for extension_type in extension_types {
+                    buf.extend_from_slice(&(*extension_type as u16).to_le_bytes());
+                }
+            }
+            Self::ApproveOperator { limit, period } => {
+                buf.push(48);
+                Self::pack_u128_option(limit, &mut buf);
+                buf.extend_from_slice(&period.to_le_bytes());
+            }
+            Self::RevokeOperator => buf.push(49),
+            Self::DepositNative { amount } => {
+                buf.push(50);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::WithdrawNative { amount } => {
+                buf.push(51);
+                buf.extend_from_slice(&amount.to_le_bytes());
             }
         };
         buf
@@ -704,6 +1599,131 @@ impl TokenInstruction {
         }
     }
 
+    fn unpack_meta_string(input: &[u8], max_len: usize) -> Result<(String, &[u8]), ProgramError> {
+        let (&len, rest) = input.split_first().ok_or(TokenError::InvalidInstruction)?;
+        let len = len as usize;
+        if len > max_len {
+            return Err(TokenError::MintMetaTooLong.into());
+        }
+        if rest.len() < len {
+            return Err(TokenError::InvalidInstruction.into());
+        }
+        let (value, rest) = rest.split_at(len);
+        let value =
+            String::from_utf8(value.to_vec()).map_err(|_| TokenError::InvalidMintMetaCharacter)?;
+        Ok((value, rest))
+    }
+
+    fn pack_meta_string(value: &str, buf: &mut Vec<u8>) {
+        // Builders reject values over their maximum length, which all fit a length byte
+        buf.push(value.len() as u8);
+        buf.extend_from_slice(value.as_bytes());
+    }
+
+    // Fails on trailing data, so that a value is never read short of what was packed
+    fn check_end(rest: &[u8]) -> Result<(), ProgramError> {
+        if !rest.is_empty() {
+            return Err(TokenError::InvalidInstruction.into());
+        }
+        Ok(())
+    }
+
+    fn unpack_metadata_key(input: &[u8]) -> Result<(String, &[u8]), ProgramError> {
+        let (&len, rest) = input.split_first().ok_or(TokenError::InvalidInstruction)?;
+        if rest.len() < len as usize {
+            return Err(TokenError::InvalidInstruction.into());
+        }
+        let (key, rest) = rest.split_at(len as usize);
+        let key = String::from_utf8(key.to_vec()).map_err(|_| TokenError::InvalidInstruction)?;
+        Ok((key, rest))
+    }
+
+    fn unpack_u16(input: &[u8]) -> Result<(u16, &[u8]), ProgramError> {
+        let value = input
+            .get(..2)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u16::from_le_bytes)
+            .ok_or(TokenError::InvalidInstruction)?;
+        Ok((value, &input[2..]))
+    }
+
+    fn unpack_i16(input: &[u8]) -> Result<(i16, &[u8]), ProgramError> {
+        let value = input
+            .get(..2)
+            .and_then(|slice| slice.try_into().ok())
+            .map(i16::from_le_bytes)
+            .ok_or(TokenError::InvalidInstruction)?;
+        Ok((value, &input[2..]))
+    }
+
+    fn unpack_u128(input: &[u8]) -> Result<(u128, &[u8]), ProgramError> {
+        let value = input
+            .get(..16)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u128::from_le_bytes)
+            .ok_or(TokenError::InvalidInstruction)?;
+        Ok((value, &input[16..]))
+    }
+
+    fn unpack_u128_option(input: &[u8]) -> Result<(Option<u128>, &[u8]), ProgramError> {
+        match input.split_first() {
+            Option::Some((&0, rest)) => Ok((None, rest)),
+            Option::Some((&1, rest)) => {
+                let (value, rest) = Self::unpack_u128(rest)?;
+                Ok((Some(value), rest))
+            }
+            _ => Err(TokenError::InvalidInstruction.into()),
+        }
+    }
+
+    fn pack_u128_option(value: &Option<u128>, buf: &mut Vec<u8>) {
+        match *value {
+            Some(value) => {
+                buf.push(1);
+                buf.extend_from_slice(&value.to_le_bytes());
+            }
+            None => buf.push(0),
+        }
+    }
+
+    fn unpack_amounts(input: &[u8]) -> Result<(Vec<u128>, &[u8]), ProgramError> {
+        let (&count, mut rest) = input.split_first().ok_or(TokenError::InvalidInstruction)?;
+        let mut amounts = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let (amount, remaining) = Self::unpack_u128(rest)?;
+            amounts.push(amount);
+            rest = remaining;
+        }
+        Ok((amounts, rest))
+    }
+
+    fn pack_amounts(amounts: &[u128], buf: &mut Vec<u8>) {
+        buf.push(amounts.len() as u8);
+        for amount in amounts {
+            buf.extend_from_slice(&amount.to_le_bytes());
+        }
+    }
+
+    fn unpack_extension_types(input: &[u8]) -> Result<(Vec<ExtensionType>, &[u8]), ProgramError> {
+        let (&count, mut rest) = input.split_first().ok_or(TokenError::InvalidInstruction)?;
+        let mut extension_types = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let (extension_type, remaining) = Self::unpack_u16(rest)?;
+            let extension_type = ExtensionType::try_from_primitive(extension_type)
+                .or(Err(TokenError::InvalidInstruction))?;
+            extension_types.push(extension_type);
+            rest = remaining;
+        }
+        Ok((extension_types, rest))
+    }
+
+    fn unpack_account_state(input: &[u8]) -> Result<(AccountState, &[u8]), ProgramError> {
+        let (&state, rest) = input.split_first().ok_or(TokenError::InvalidInstruction)?;
+        let state =
+            AccountState::try_from_primitive(state).or(Err(TokenError::InvalidInstruction))?;
+        Ok((state, rest))
+    }
+
     fn unpack_pubkey_option(input: &[u8]) -> Result<(COption<Pubkey>, &[u8]), ProgramError> {
         match input.split_first() {
             Option::Some((&0, rest)) => Ok((COption::None, rest)),
@@ -730,6 +1750,7 @@ impl TokenInstruction {
 /// Specifies the authority type for SetAuthority instructions
 #[repr(u8)]
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde-traits", derive(Serialize, Deserialize))]
 pub enum AuthorityType {
     /// Authority to mint new tokens
     MintTokens,
@@ -741,6 +1762,16 @@ pub enum AuthorityType {
     CloseAccount,
     /// Authority to update token meta data
     UpdateMeta,
+    /// Authority to set the transfer fee of a mint
+    TransferFeeConfig,
+    /// Authority to withdraw withheld transfer fees
+    WithheldWithdraw,
+    /// Authority to update the interest rate of a mint
+    InterestRate,
+    /// Delegate allowed to transfer or burn from any account of a mint
+    PermanentDelegate,
+    /// Authority to close a mint
+    CloseMint,
 }
 
 impl AuthorityType {
@@ -751,6 +1782,11 @@ impl AuthorityType {
             AuthorityType::AccountOwner => 2,
             AuthorityType::CloseAccount => 3,
             AuthorityType::UpdateMeta => 4,
+            AuthorityType::TransferFeeConfig => 5,
+            AuthorityType::WithheldWithdraw => 6,
+            AuthorityType::InterestRate => 7,
+            AuthorityType::PermanentDelegate => 8,
+            AuthorityType::CloseMint => 9,
         }
     }
 
@@ -761,6 +1797,11 @@ impl AuthorityType {
             2 => Ok(AuthorityType::AccountOwner),
             3 => Ok(AuthorityType::CloseAccount),
             4 => Ok(AuthorityType::UpdateMeta),
+            5 => Ok(AuthorityType::TransferFeeConfig),
+            6 => Ok(AuthorityType::WithheldWithdraw),
+            7 => Ok(AuthorityType::InterestRate),
+            8 => Ok(AuthorityType::PermanentDelegate),
+            9 => Ok(AuthorityType::CloseMint),
             _ => Err(TokenError::InvalidInstruction.into()),
         }
     }
@@ -1094,6 +2135,38 @@ pub fn close_account(
     })
 }
 
+/// Creates a `CloseAccount` instruction closing a mint and its `MintMeta` account.
+pub fn close_mint(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    close_authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let data = TokenInstruction::CloseAccount.pack();
+    let (mint_meta_pubkey, _) =
+        Pubkey::find_program_address(&[b"MintMeta", &mint_pubkey.to_bytes()], token_program_id);
+
+    let mut accounts = Vec::with_capacity(4 + signer_pubkeys.len());
+    accounts.push(AccountMeta::new(*mint_pubkey, false));
+    accounts.push(AccountMeta::new(*destination_pubkey, false));
+    accounts.push(AccountMeta::new_readonly(
+        *close_authority_pubkey,
+        signer_pubkeys.is_empty(),
+    ));
+    accounts.push(AccountMeta::new(mint_meta_pubkey, false));
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
 /// Creates a `FreezeAccount` instruction.
 pub fn freeze_account(
     token_program_id: &Pubkey,
@@ -1199,7 +2272,12 @@ pub fn approve_checked(
     decimals: u8,
 ) -> Result<Instruction, ProgramError> {
     check_program_account(token_program_id)?;
-    let data = TokenInstruction::ApproveChecked { amount, decimals }.pack();
+    let data = TokenInstruction::ApproveChecked {
+        amount,
+        decimals,
+        period: None,
+    }
+    .pack();
 
     let mut accounts = Vec::with_capacity(4 + signer_pubkeys.len());
     accounts.push(AccountMeta::new(*source_pubkey, false));
@@ -1220,6 +2298,39 @@ pub fn approve_checked(
     })
 }
 
+/// Creates an `ApproveChecked` instruction approving a delegate for
+/// `amount` tokens per `period` seconds.
+#[allow(clippy::too_many_arguments)]
+pub fn approve_recurring(
+    token_program_id: &Pubkey,
+    source_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    delegate_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    amount: u128,
+    decimals: u8,
+    period: u64,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = approve_checked(
+        token_program_id,
+        source_pubkey,
+        mint_pubkey,
+        delegate_pubkey,
+        owner_pubkey,
+        signer_pubkeys,
+        amount,
+        decimals,
+    )?;
+    instruction.data = TokenInstruction::ApproveChecked {
+        amount,
+        decimals,
+        period: Some(period),
+    }
+    .pack();
+    Ok(instruction)
+}
+
 /// Creates a `MintToChecked` instruction.
 pub fn mint_to_checked(
     token_program_id: &Pubkey,
@@ -1296,6 +2407,14 @@ pub fn sync_native(
     })
 }
 
+// Mint meta strings are packed behind a length byte, so longer values cannot be encoded
+fn check_meta_string_len(value: &str, max_len: usize) -> Result<(), ProgramError> {
+    if value.len() > max_len {
+        return Err(TokenError::MintMetaTooLong.into());
+    }
+    Ok(())
+}
+
 /// Creates a `UpdateSymbol` instruction
 pub fn update_token_symbol(
     token_program_id: &Pubkey,
@@ -1305,6 +2424,7 @@ pub fn update_token_symbol(
     new_symbol: String,
 ) -> Result<Instruction, ProgramError> {
     check_program_account(token_program_id)?;
+    check_meta_string_len(&new_symbol, MAX_SYMBOL_LEN)?;
     let data = TokenInstruction::UpdateSymbol { new_symbol }.pack();
     let mut accounts = Vec::with_capacity(2 + signer_pubkeys.len());
     accounts.push(AccountMeta::new(*mint_meta, false));
@@ -1332,6 +2452,7 @@ pub fn update_token_name(
     new_name: String,
 ) -> Result<Instruction, ProgramError> {
     check_program_account(token_program_id)?;
+    check_meta_string_len(&new_name, MAX_NAME_LEN)?;
     let data = TokenInstruction::UpdateName { new_name }.pack();
     let mut accounts = Vec::with_capacity(2 + signer_pubkeys.len());
     accounts.push(AccountMeta::new(*mint_meta, false));
@@ -1359,6 +2480,7 @@ pub fn update_token_icon(
     new_icon: String,
 ) -> Result<Instruction, ProgramError> {
     check_program_account(token_program_id)?;
+    check_meta_string_len(&new_icon, MAX_ICON_LEN)?;
     let data = TokenInstruction::UpdateIcon { new_icon }.pack();
     let mut accounts = Vec::with_capacity(2 + signer_pubkeys.len());
     accounts.push(AccountMeta::new(*mint_meta, false));
@@ -1413,6 +2535,9 @@ pub fn initialize_mint_meta(
     icon: String,
 ) -> Result<Instruction, ProgramError> {
     check_program_account(token_program_id)?;
+    check_meta_string_len(&symbol, MAX_SYMBOL_LEN)?;
+    check_meta_string_len(&name, MAX_NAME_LEN)?;
+    check_meta_string_len(&icon, MAX_ICON_LEN)?;
 
     let data = TokenInstruction::InitMintMetaAccount { symbol, name, icon }.pack();
 
@@ -1428,61 +2553,890 @@ pub fn initialize_mint_meta(
     })
 }
 
-/// Utility function that checks index is between MIN_SIGNERS and MAX_SIGNERS
-pub fn is_valid_signer_index(index: usize) -> bool {
-    (MIN_SIGNERS..=MAX_SIGNERS).contains(&index)
-}
+/// Creates a `InitializeTransferFeeConfig` instruction.
+pub fn initialize_transfer_fee_config(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    transfer_fee_config_authority: Option<&Pubkey>,
+    withdraw_withheld_authority: Option<&Pubkey>,
+    transfer_fee_basis_points: u16,
+    maximum_fee: u128,
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let data = TokenInstruction::InitializeTransferFeeConfig {
+        transfer_fee_config_authority: transfer_fee_config_authority.cloned().into(),
+        withdraw_withheld_authority: withdraw_withheld_authority.cloned().into(),
+        transfer_fee_basis_points,
+        maximum_fee,
+    }
+    .pack();
 
-#[cfg(test)]
-mod test {
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts: vec![AccountMeta::new(*mint_pubkey, false)],
+        data,
+    })
+}
 
-    use super::*;
+/// Creates a `SetTransferFee` instruction.
+pub fn set_transfer_fee(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    transfer_fee_basis_points: u16,
+    maximum_fee: u128,
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let data = TokenInstruction::SetTransferFee {
+        transfer_fee_basis_points,
+        maximum_fee,
+    }
+    .pack();
 
-    #[test]
-    fn test_instruction_packing() {
-        let check = TokenInstruction::InitializeMint {
-            decimals: 2,
-            mint_authority: Pubkey::new(&[1u8; 32]),
-            freeze_authority: COption::None,
-        };
-        let packed = check.pack();
-        let mut expect = Vec::from([0u8, 2]);
-        expect.extend_from_slice(&[1u8; 32]);
-        expect.extend_from_slice(&[0]);
-        assert_eq!(packed, expect);
-        let unpacked = TokenInstruction::unpack(&expect).unwrap();
-        assert_eq!(unpacked, check);
+    let mut accounts = Vec::with_capacity(2 + signer_pubkeys.len());
+    accounts.push(AccountMeta::new(*mint_pubkey, false));
+    accounts.push(AccountMeta::new_readonly(
+        *authority_pubkey,
+        signer_pubkeys.is_empty(),
+    ));
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
 
-        let check = TokenInstruction::InitializeMint {
-            decimals: 2,
-            mint_authority: Pubkey::new(&[2u8; 32]),
-            freeze_authority: COption::Some(Pubkey::new(&[3u8; 32])),
-        };
-        let packed = check.pack();
-        let mut expect = vec![0u8, 2];
-        expect.extend_from_slice(&[2u8; 32]);
-        expect.extend_from_slice(&[1]);
-        expect.extend_from_slice(&[3u8; 32]);
-        assert_eq!(packed, expect);
-        let unpacked = TokenInstruction::unpack(&expect).unwrap();
-        assert_eq!(unpacked, check);
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
 
-        let check = TokenInstruction::InitializeAccount;
-        let packed = check.pack();
-        let expect = Vec::from([1u8]);
-        assert_eq!(packed, expect);
-        let unpacked = TokenInstruction::unpack(&expect).unwrap();
-        assert_eq!(unpacked, check);
+/// Creates a `WithdrawWithheldTokensFromMint` instruction.
+pub fn withdraw_withheld_tokens_from_mint(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let data = TokenInstruction::WithdrawWithheldTokensFromMint.pack();
 
-        let check = TokenInstruction::InitializeMultisig { m: 1 };
-        let packed = check.pack();
-        let expect = Vec::from([2u8, 1]);
-        assert_eq!(packed, expect);
-        let unpacked = TokenInstruction::unpack(&expect).unwrap();
-        assert_eq!(unpacked, check);
+    let mut accounts = Vec::with_capacity(3 + signer_pubkeys.len());
+    accounts.push(AccountMeta::new(*mint_pubkey, false));
+    accounts.push(AccountMeta::new(*destination_pubkey, false));
+    accounts.push(AccountMeta::new_readonly(
+        *authority_pubkey,
+        signer_pubkeys.is_empty(),
+    ));
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
 
-        let check = TokenInstruction::Transfer { amount: 1 };
-        let packed = check.pack();
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `WithdrawWithheldTokensFromAccounts` instruction.
+pub fn withdraw_withheld_tokens_from_accounts(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    source_pubkeys: &[&Pubkey],
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let num_token_accounts: u8 = source_pubkeys
+        .len()
+        .try_into()
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+    let data = TokenInstruction::WithdrawWithheldTokensFromAccounts { num_token_accounts }.pack();
+
+    let mut accounts = Vec::with_capacity(3 + signer_pubkeys.len() + source_pubkeys.len());
+    accounts.push(AccountMeta::new_readonly(*mint_pubkey, false));
+    accounts.push(AccountMeta::new(*destination_pubkey, false));
+    accounts.push(AccountMeta::new_readonly(
+        *authority_pubkey,
+        signer_pubkeys.is_empty(),
+    ));
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+    for source_pubkey in source_pubkeys.iter() {
+        accounts.push(AccountMeta::new(**source_pubkey, false));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `HarvestWithheldTokensToMint` instruction.
+pub fn harvest_withheld_tokens_to_mint(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    source_pubkeys: &[&Pubkey],
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let data = TokenInstruction::HarvestWithheldTokensToMint.pack();
+
+    let mut accounts = Vec::with_capacity(1 + source_pubkeys.len());
+    accounts.push(AccountMeta::new(*mint_pubkey, false));
+    for source_pubkey in source_pubkeys.iter() {
+        accounts.push(AccountMeta::new(**source_pubkey, false));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `InitializeInterestBearingMint` instruction.
+pub fn initialize_interest_bearing_mint(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    rate_authority: Option<&Pubkey>,
+    rate: i16,
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let data = TokenInstruction::InitializeInterestBearingMint {
+        rate_authority: rate_authority.cloned().into(),
+        rate,
+    }
+    .pack();
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts: vec![AccountMeta::new(*mint_pubkey, false)],
+        data,
+    })
+}
+
+/// Creates a `UpdateInterestRate` instruction.
+pub fn update_interest_rate(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    rate_authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    rate: i16,
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let data = TokenInstruction::UpdateInterestRate { rate }.pack();
+
+    let mut accounts = Vec::with_capacity(2 + signer_pubkeys.len());
+    accounts.push(AccountMeta::new(*mint_pubkey, false));
+    accounts.push(AccountMeta::new_readonly(
+        *rate_authority_pubkey,
+        signer_pubkeys.is_empty(),
+    ));
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `AmountToUiAmount` instruction.
+pub fn amount_to_ui_amount(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    amount: u128,
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let data = TokenInstruction::AmountToUiAmount { amount }.pack();
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts: vec![AccountMeta::new_readonly(*mint_pubkey, false)],
+        data,
+    })
+}
+
+/// Creates a `InitializeNonTransferableMint` instruction.
+pub fn initialize_non_transferable_mint(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let data = TokenInstruction::InitializeNonTransferableMint.pack();
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts: vec![AccountMeta::new(*mint_pubkey, false)],
+        data,
+    })
+}
+
+/// Creates a `InitializePermanentDelegate` instruction.
+pub fn initialize_permanent_delegate(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    delegate: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let data = TokenInstruction::InitializePermanentDelegate {
+        delegate: *delegate,
+    }
+    .pack();
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts: vec![AccountMeta::new(*mint_pubkey, false)],
+        data,
+    })
+}
+
+/// Creates a `InitializeDefaultAccountState` instruction.
+pub fn initialize_default_account_state(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    state: &AccountState,
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let data = TokenInstruction::InitializeDefaultAccountState { state: *state }.pack();
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts: vec![AccountMeta::new(*mint_pubkey, false)],
+        data,
+    })
+}
+
+/// Creates a `UpdateDefaultAccountState` instruction.
+pub fn update_default_account_state(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    freeze_authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    state: &AccountState,
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let data = TokenInstruction::UpdateDefaultAccountState { state: *state }.pack();
+
+    let mut accounts = Vec::with_capacity(2 + signer_pubkeys.len());
+    accounts.push(AccountMeta::new(*mint_pubkey, false));
+    accounts.push(AccountMeta::new_readonly(
+        *freeze_authority_pubkey,
+        signer_pubkeys.is_empty(),
+    ));
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `InitializeMintCloseAuthority` instruction.
+pub fn initialize_mint_close_authority(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    close_authority: Option<&Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let data = TokenInstruction::InitializeMintCloseAuthority {
+        close_authority: close_authority.cloned().into(),
+    }
+    .pack();
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts: vec![AccountMeta::new(*mint_pubkey, false)],
+        data,
+    })
+}
+
+/// Creates a `EnableRequiredTransferMemos` instruction.
+pub fn enable_required_transfer_memos(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let data = TokenInstruction::EnableRequiredTransferMemos.pack();
+
+    let mut accounts = Vec::with_capacity(2 + signer_pubkeys.len());
+    accounts.push(AccountMeta::new(*account_pubkey, false));
+    accounts.push(AccountMeta::new_readonly(
+        *owner_pubkey,
+        signer_pubkeys.is_empty(),
+    ));
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `DisableRequiredTransferMemos` instruction.
+pub fn disable_required_transfer_memos(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let data = TokenInstruction::DisableRequiredTransferMemos.pack();
+
+    let mut accounts = Vec::with_capacity(2 + signer_pubkeys.len());
+    accounts.push(AccountMeta::new(*account_pubkey, false));
+    accounts.push(AccountMeta::new_readonly(
+        *owner_pubkey,
+        signer_pubkeys.is_empty(),
+    ));
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `EnableCpiGuard` instruction.
+pub fn enable_cpi_guard(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let data = TokenInstruction::EnableCpiGuard.pack();
+
+    let mut accounts = Vec::with_capacity(2 + signer_pubkeys.len());
+    accounts.push(AccountMeta::new(*account_pubkey, false));
+    accounts.push(AccountMeta::new_readonly(
+        *owner_pubkey,
+        signer_pubkeys.is_empty(),
+    ));
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `DisableCpiGuard` instruction.
+pub fn disable_cpi_guard(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let data = TokenInstruction::DisableCpiGuard.pack();
+
+    let mut accounts = Vec::with_capacity(3 + signer_pubkeys.len());
+    accounts.push(AccountMeta::new(*account_pubkey, false));
+    accounts.push(AccountMeta::new_readonly(
+        *owner_pubkey,
+        signer_pubkeys.is_empty(),
+    ));
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+    accounts.push(AccountMeta::new_readonly(sysvar::instructions::id(), false));
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `MigrateMintMeta` instruction.
+pub fn migrate_mint_meta(
+    token_program_id: &Pubkey,
+    mint_meta: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts: vec![AccountMeta::new(*mint_meta, false)],
+        data: TokenInstruction::MigrateMintMeta.pack(),
+    })
+}
+
+/// Creates a `SetMintMetaField` instruction.
+pub fn set_mint_meta_field(
+    token_program_id: &Pubkey,
+    mint_meta: &Pubkey,
+    payer: &Pubkey,
+    authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    key: String,
+    value: String,
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    if key.len() > MAX_METADATA_KEY_LEN {
+        return Err(TokenError::MintMetaTooLong.into());
+    }
+    let data = TokenInstruction::SetMintMetaField { key, value }.pack();
+
+    let mut accounts = Vec::with_capacity(5 + signer_pubkeys.len());
+    accounts.push(AccountMeta::new(*mint_meta, false));
+    accounts.push(AccountMeta::new(*payer, true));
+    accounts.push(AccountMeta::new_readonly(system_program::id(), false));
+    accounts.push(AccountMeta::new_readonly(sysvar::rent::id(), false));
+    accounts.push(AccountMeta::new_readonly(
+        *authority_pubkey,
+        signer_pubkeys.is_empty(),
+    ));
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `RemoveMintMetaField` instruction.
+pub fn remove_mint_meta_field(
+    token_program_id: &Pubkey,
+    mint_meta: &Pubkey,
+    destination: &Pubkey,
+    authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    key: String,
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    if key.len() > MAX_METADATA_KEY_LEN {
+        return Err(TokenError::MintMetaTooLong.into());
+    }
+    let data = TokenInstruction::RemoveMintMetaField { key }.pack();
+
+    let mut accounts = Vec::with_capacity(4 + signer_pubkeys.len());
+    accounts.push(AccountMeta::new(*mint_meta, false));
+    accounts.push(AccountMeta::new(*destination, false));
+    accounts.push(AccountMeta::new_readonly(sysvar::rent::id(), false));
+    accounts.push(AccountMeta::new_readonly(
+        *authority_pubkey,
+        signer_pubkeys.is_empty(),
+    ));
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `BatchTransfer` instruction.
+pub fn batch_transfer(
+    token_program_id: &Pubkey,
+    source_pubkey: &Pubkey,
+    destinations: &[(&Pubkey, u128)],
+    authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    check_batch_len(destinations)?;
+    let amounts = destinations.iter().map(|(_, amount)| *amount).collect();
+    let data = TokenInstruction::BatchTransfer { amounts }.pack();
+
+    let mut accounts = Vec::with_capacity(2 + destinations.len() + signer_pubkeys.len());
+    accounts.push(AccountMeta::new(*source_pubkey, false));
+    for (destination_pubkey, _) in destinations.iter() {
+        accounts.push(AccountMeta::new(**destination_pubkey, false));
+    }
+    accounts.push(AccountMeta::new_readonly(
+        *authority_pubkey,
+        signer_pubkeys.is_empty(),
+    ));
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `BatchTransferChecked` instruction.
+pub fn batch_transfer_checked(
+    token_program_id: &Pubkey,
+    source_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    destinations: &[(&Pubkey, u128)],
+    authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    decimals: u8,
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    check_batch_len(destinations)?;
+    let amounts = destinations.iter().map(|(_, amount)| *amount).collect();
+    let data = TokenInstruction::BatchTransferChecked { amounts, decimals }.pack();
+
+    let mut accounts = Vec::with_capacity(3 + destinations.len() + signer_pubkeys.len());
+    accounts.push(AccountMeta::new(*source_pubkey, false));
+    accounts.push(AccountMeta::new_readonly(*mint_pubkey, false));
+    for (destination_pubkey, _) in destinations.iter() {
+        accounts.push(AccountMeta::new(**destination_pubkey, false));
+    }
+    accounts.push(AccountMeta::new_readonly(
+        *authority_pubkey,
+        signer_pubkeys.is_empty(),
+    ));
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `RecordBalanceCheckpoint` instruction for the given epoch, which
+/// must be the current epoch when the instruction is processed.
+pub fn record_balance_checkpoint(
+    token_program_id: &Pubkey,
+    payer_pubkey: &Pubkey,
+    account_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    epoch: Epoch,
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let data = TokenInstruction::RecordBalanceCheckpoint.pack();
+    let checkpoint_pubkey = get_balance_checkpoint_address(account_pubkey, epoch);
+
+    let mut accounts = Vec::with_capacity(6 + signer_pubkeys.len());
+    accounts.push(AccountMeta::new(*payer_pubkey, true));
+    accounts.push(AccountMeta::new(checkpoint_pubkey, false));
+    accounts.push(AccountMeta::new_readonly(*account_pubkey, false));
+    accounts.push(AccountMeta::new_readonly(system_program::id(), false));
+    accounts.push(AccountMeta::new_readonly(sysvar::rent::id(), false));
+    accounts.push(AccountMeta::new_readonly(
+        *owner_pubkey,
+        signer_pubkeys.is_empty(),
+    ));
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `TransferWithEncryptedNote` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_with_encrypted_note(
+    token_program_id: &Pubkey,
+    source_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    amount: u128,
+    decimals: u8,
+    note: Vec<u8>,
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    if note.len() > MAX_NOTE_LEN {
+        return Err(TokenError::InvalidEncryptedNote.into());
+    }
+    let data = TokenInstruction::TransferWithEncryptedNote {
+        amount,
+        decimals,
+        note,
+    }
+    .pack();
+
+    let mut accounts = Vec::with_capacity(4 + signer_pubkeys.len());
+    accounts.push(AccountMeta::new(*source_pubkey, false));
+    accounts.push(AccountMeta::new_readonly(*mint_pubkey, false));
+    accounts.push(AccountMeta::new(*destination_pubkey, false));
+    accounts.push(AccountMeta::new_readonly(
+        *authority_pubkey,
+        signer_pubkeys.is_empty(),
+    ));
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `Reallocate` instruction.
+pub fn reallocate(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    payer: &Pubkey,
+    owner_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    extension_types: &[ExtensionType],
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    if extension_types.len() > u8::MAX as usize {
+        return Err(TokenError::InvalidInstruction.into());
+    }
+    let data = TokenInstruction::Reallocate {
+        extension_types: extension_types.to_vec(),
+    }
+    .pack();
+
+    let mut accounts = Vec::with_capacity(5 + signer_pubkeys.len());
+    accounts.push(AccountMeta::new(*account_pubkey, false));
+    accounts.push(AccountMeta::new(*payer, true));
+    accounts.push(AccountMeta::new_readonly(system_program::id(), false));
+    accounts.push(AccountMeta::new_readonly(sysvar::rent::id(), false));
+    accounts.push(AccountMeta::new_readonly(
+        *owner_pubkey,
+        signer_pubkeys.is_empty(),
+    ));
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `ApproveOperator` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn approve_operator(
+    token_program_id: &Pubkey,
+    payer: &Pubkey,
+    mint_pubkey: &Pubkey,
+    operator_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    limit: Option<u128>,
+    period: u64,
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let data = TokenInstruction::ApproveOperator { limit, period }.pack();
+    let operator_approval =
+        get_operator_approval_address(mint_pubkey, owner_pubkey, operator_pubkey);
+
+    let mut accounts = Vec::with_capacity(8 + signer_pubkeys.len());
+    accounts.push(AccountMeta::new(*payer, true));
+    accounts.push(AccountMeta::new(operator_approval, false));
+    accounts.push(AccountMeta::new_readonly(*mint_pubkey, false));
+    accounts.push(AccountMeta::new_readonly(*operator_pubkey, false));
+    accounts.push(AccountMeta::new_readonly(system_program::id(), false));
+    accounts.push(AccountMeta::new_readonly(sysvar::rent::id(), false));
+    accounts.push(AccountMeta::new_readonly(
+        *owner_pubkey,
+        signer_pubkeys.is_empty(),
+    ));
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+    accounts.push(AccountMeta::new_readonly(sysvar::instructions::id(), false));
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `RevokeOperator` instruction.
+pub fn revoke_operator(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    operator_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let data = TokenInstruction::RevokeOperator.pack();
+    let operator_approval =
+        get_operator_approval_address(mint_pubkey, owner_pubkey, operator_pubkey);
+
+    let mut accounts = Vec::with_capacity(3 + signer_pubkeys.len());
+    accounts.push(AccountMeta::new(operator_approval, false));
+    accounts.push(AccountMeta::new(*destination_pubkey, false));
+    accounts.push(AccountMeta::new_readonly(
+        *owner_pubkey,
+        signer_pubkeys.is_empty(),
+    ));
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `DepositNative` instruction.
+pub fn deposit_native(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    funder_pubkey: &Pubkey,
+    amount: u128,
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts: vec![
+            AccountMeta::new(*account_pubkey, false),
+            AccountMeta::new(*funder_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: TokenInstruction::DepositNative { amount }.pack(),
+    })
+}
+
+/// Creates a `WithdrawNative` instruction.
+pub fn withdraw_native(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    amount: u128,
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let data = TokenInstruction::WithdrawNative { amount }.pack();
+
+    let mut accounts = Vec::with_capacity(3 + signer_pubkeys.len());
+    accounts.push(AccountMeta::new(*account_pubkey, false));
+    accounts.push(AccountMeta::new(*destination_pubkey, false));
+    accounts.push(AccountMeta::new_readonly(
+        *owner_pubkey,
+        signer_pubkeys.is_empty(),
+    ));
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Appends the operator approval account to a `Transfer`, `TransferChecked`,
+/// `Burn` or `BurnChecked` instruction whose authority is an operator of the
+/// token account's owner.
+pub fn add_operator_approval(
+    instruction: &mut Instruction,
+    mint_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    operator_pubkey: &Pubkey,
+) {
+    instruction.accounts.push(AccountMeta::new(
+        get_operator_approval_address(mint_pubkey, owner_pubkey, operator_pubkey),
+        false,
+    ));
+}
+
+fn check_batch_len(destinations: &[(&Pubkey, u128)]) -> Result<(), ProgramError> {
+    if destinations.is_empty() || destinations.len() > u8::MAX as usize {
+        return Err(TokenError::InvalidInstruction.into());
+    }
+    Ok(())
+}
+
+/// Utility function that checks index is between MIN_SIGNERS and MAX_SIGNERS
+pub fn is_valid_signer_index(index: usize) -> bool {
+    (MIN_SIGNERS..=MAX_SIGNERS).contains(&index)
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test_instruction_packing() {
+        let check = TokenInstruction::InitializeMint {
+            decimals: 2,
+            mint_authority: Pubkey::new(&[1u8; 32]),
+            freeze_authority: COption::None,
+        };
+        let packed = check.pack();
+        let mut expect = Vec::from([0u8, 2]);
+        expect.extend_from_slice(&[1u8; 32]);
+        expect.extend_from_slice(&[0]);
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::InitializeMint {
+            decimals: 2,
+            mint_authority: Pubkey::new(&[2u8; 32]),
+            freeze_authority: COption::Some(Pubkey::new(&[3u8; 32])),
+        };
+        let packed = check.pack();
+        let mut expect = vec![0u8, 2];
+        expect.extend_from_slice(&[2u8; 32]);
+        expect.extend_from_slice(&[1]);
+        expect.extend_from_slice(&[3u8; 32]);
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::InitializeAccount;
+        let packed = check.pack();
+        let expect = Vec::from([1u8]);
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::InitializeMultisig { m: 1 };
+        let packed = check.pack();
+        let expect = Vec::from([2u8, 1]);
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::Transfer { amount: 1 };
+        let packed = check.pack();
         let expect = Vec::from([3u8, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
         assert_eq!(packed, expect);
         let unpacked = TokenInstruction::unpack(&expect).unwrap();
@@ -1562,6 +3516,7 @@ mod test {
         let check = TokenInstruction::ApproveChecked {
             amount: 1,
             decimals: 2,
+            period: None,
         };
         let packed = check.pack();
         let expect = Vec::from([13u8, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2]);
@@ -1569,6 +3524,24 @@ mod test {
         let unpacked = TokenInstruction::unpack(&expect).unwrap();
         assert_eq!(unpacked, check);
 
+        let check = TokenInstruction::ApproveChecked {
+            amount: 1,
+            decimals: 2,
+            period: Some(3),
+        };
+        let packed = check.pack();
+        let mut expect = vec![13u8];
+        expect.extend_from_slice(&1u128.to_le_bytes());
+        expect.push(2);
+        expect.extend_from_slice(&3u64.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert_eq!(
+            TokenInstruction::unpack(&expect[..expect.len() - 1]),
+            Err(TokenError::InvalidInstruction.into())
+        );
+
         let check = TokenInstruction::MintToChecked {
             amount: 1,
             decimals: 2,
@@ -1605,5 +3578,353 @@ mod test {
         assert_eq!(packed, expect);
         let unpacked = TokenInstruction::unpack(&expect).unwrap();
         assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::InitializeTransferFeeConfig {
+            transfer_fee_config_authority: COption::Some(Pubkey::new(&[3u8; 32])),
+            withdraw_withheld_authority: COption::None,
+            transfer_fee_basis_points: 111,
+            maximum_fee: 1_000,
+        };
+        let packed = check.pack();
+        let mut expect = vec![23u8, 1];
+        expect.extend_from_slice(&[3u8; 32]);
+        expect.extend_from_slice(&[0]);
+        expect.extend_from_slice(&111u16.to_le_bytes());
+        expect.extend_from_slice(&1_000u128.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::SetTransferFee {
+            transfer_fee_basis_points: 222,
+            maximum_fee: 2_000,
+        };
+        let packed = check.pack();
+        let mut expect = vec![24u8];
+        expect.extend_from_slice(&222u16.to_le_bytes());
+        expect.extend_from_slice(&2_000u128.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::WithdrawWithheldTokensFromMint;
+        let packed = check.pack();
+        let expect = vec![25u8];
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::WithdrawWithheldTokensFromAccounts {
+            num_token_accounts: 3,
+        };
+        let packed = check.pack();
+        let expect = vec![26u8, 3];
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::HarvestWithheldTokensToMint;
+        let packed = check.pack();
+        let expect = vec![27u8];
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::InitializeInterestBearingMint {
+            rate_authority: COption::Some(Pubkey::new(&[4u8; 32])),
+            rate: -25,
+        };
+        let packed = check.pack();
+        let mut expect = vec![28u8, 1];
+        expect.extend_from_slice(&[4u8; 32]);
+        expect.extend_from_slice(&(-25i16).to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::UpdateInterestRate { rate: 300 };
+        let packed = check.pack();
+        let mut expect = vec![29u8];
+        expect.extend_from_slice(&300i16.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::AmountToUiAmount { amount: 42 };
+        let packed = check.pack();
+        let mut expect = vec![30u8];
+        expect.extend_from_slice(&42u128.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::InitializeNonTransferableMint;
+        let packed = check.pack();
+        let expect = vec![31u8];
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::InitializePermanentDelegate {
+            delegate: Pubkey::new(&[5u8; 32]),
+        };
+        let packed = check.pack();
+        let mut expect = vec![32u8];
+        expect.extend_from_slice(&[5u8; 32]);
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::InitializeDefaultAccountState {
+            state: AccountState::Frozen,
+        };
+        let packed = check.pack();
+        let expect = vec![33u8, 2];
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::UpdateDefaultAccountState {
+            state: AccountState::Initialized,
+        };
+        let packed = check.pack();
+        let expect = vec![34u8, 1];
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        assert_eq!(
+            TokenInstruction::unpack(&[34u8, 3]),
+            Err(TokenError::InvalidInstruction.into())
+        );
+
+        let check = TokenInstruction::InitializeMintCloseAuthority {
+            close_authority: COption::Some(Pubkey::new(&[6u8; 32])),
+        };
+        let packed = check.pack();
+        let mut expect = vec![35u8, 1];
+        expect.extend_from_slice(&[6u8; 32]);
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::EnableRequiredTransferMemos;
+        let packed = check.pack();
+        let expect = vec![36u8];
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::DisableRequiredTransferMemos;
+        let packed = check.pack();
+        let expect = vec![37u8];
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::EnableCpiGuard;
+        let packed = check.pack();
+        let expect = vec![38u8];
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::DisableCpiGuard;
+        let packed = check.pack();
+        let expect = vec![39u8];
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::MigrateMintMeta;
+        let packed = check.pack();
+        let expect = vec![40u8];
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        // mint meta strings are length prefixed, so newlines survive
+        let check = TokenInstruction::UpdateName {
+            new_name: "two\nlines".to_string(),
+        };
+        let packed = check.pack();
+        let mut expect = vec![19u8, 9];
+        expect.extend_from_slice(b"two\nlines");
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::InitMintMetaAccount {
+            symbol: "TKN".to_string(),
+            name: "To\nken".to_string(),
+            icon: "".to_string(),
+        };
+        let packed = check.pack();
+        let mut expect = vec![22u8, 3];
+        expect.extend_from_slice(b"TKN");
+        expect.push(6);
+        expect.extend_from_slice(b"To\nken");
+        expect.push(0);
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        // over the maximum length, not UTF-8, or not consumed entirely
+        let mut too_long = vec![18u8, MAX_SYMBOL_LEN as u8 + 1];
+        too_long.extend_from_slice(&[b'S'; MAX_SYMBOL_LEN + 1]);
+        assert_eq!(
+            TokenInstruction::unpack(&too_long),
+            Err(TokenError::MintMetaTooLong.into())
+        );
+        assert_eq!(
+            TokenInstruction::unpack(&[20u8, 2, 0xc3, 0x28]),
+            Err(TokenError::InvalidMintMetaCharacter.into())
+        );
+        assert_eq!(
+            TokenInstruction::unpack(&[18u8, 1, b'S', b'\n']),
+            Err(TokenError::InvalidInstruction.into())
+        );
+        assert_eq!(
+            TokenInstruction::unpack(&[18u8, 2, b'S']),
+            Err(TokenError::InvalidInstruction.into())
+        );
+        assert_eq!(
+            update_token_symbol(
+                &crate::id(),
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+                &[],
+                "S".repeat(MAX_SYMBOL_LEN + 1),
+            ),
+            Err(TokenError::MintMetaTooLong.into())
+        );
+
+        let check = TokenInstruction::SetMintMetaField {
+            key: "uri".to_string(),
+            value: "https://example.com".to_string(),
+        };
+        let packed = check.pack();
+        let mut expect = vec![41u8, 3];
+        expect.extend_from_slice(b"uri");
+        expect.extend_from_slice(b"https://example.com");
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::RemoveMintMetaField {
+            key: "uri".to_string(),
+        };
+        let packed = check.pack();
+        let mut expect = vec![42u8, 3];
+        expect.extend_from_slice(b"uri");
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::BatchTransfer {
+            amounts: vec![1, 2],
+        };
+        let packed = check.pack();
+        let mut expect = vec![43u8, 2];
+        expect.extend_from_slice(&1u128.to_le_bytes());
+        expect.extend_from_slice(&2u128.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::BatchTransferChecked {
+            amounts: vec![1, 2],
+            decimals: 2,
+        };
+        let packed = check.pack();
+        let mut expect = vec![44u8, 2];
+        expect.extend_from_slice(&1u128.to_le_bytes());
+        expect.extend_from_slice(&2u128.to_le_bytes());
+        expect.push(2);
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::RecordBalanceCheckpoint;
+        let packed = check.pack();
+        let expect = vec![45u8];
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::TransferWithEncryptedNote {
+            amount: 1,
+            decimals: 2,
+            note: vec![3, 4, 5],
+        };
+        let packed = check.pack();
+        let mut expect = vec![46u8];
+        expect.extend_from_slice(&1u128.to_le_bytes());
+        expect.extend_from_slice(&[2, 3, 0, 3, 4, 5]);
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert_eq!(
+            TokenInstruction::unpack(&expect[..expect.len() - 1]),
+            Err(TokenError::InvalidInstruction.into())
+        );
+
+        let check = TokenInstruction::Reallocate {
+            extension_types: vec![ExtensionType::MemoTransfer, ExtensionType::CpiGuard],
+        };
+        let packed = check.pack();
+        let expect = vec![47u8, 2, 9, 0, 10, 0];
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert_eq!(
+            TokenInstruction::unpack(&[47u8, 1, 11, 0]),
+            Err(TokenError::InvalidInstruction.into())
+        );
+
+        let check = TokenInstruction::ApproveOperator {
+            limit: Some(1),
+            period: 2,
+        };
+        let packed = check.pack();
+        let mut expect = vec![48u8, 1];
+        expect.extend_from_slice(&1u128.to_le_bytes());
+        expect.extend_from_slice(&2u64.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::ApproveOperator {
+            limit: None,
+            period: 0,
+        };
+        let packed = check.pack();
+        let expect = vec![48u8, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::RevokeOperator;
+        let packed = check.pack();
+        let expect = vec![49u8];
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::DepositNative { amount: 1 };
+        let packed = check.pack();
+        let mut expect = vec![50u8];
+        expect.extend_from_slice(&1u128.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::WithdrawNative { amount: 1 };
+        let packed = check.pack();
+        let mut expect = vec![51u8];
+        expect.extend_from_slice(&1u128.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
     }
 }
@@ -0,0 +1,1200 @@
+//! Decoding of token instructions along with the roles of their accounts
+
+use super::{AuthorityType, TokenInstruction};
+use crate::check_program_account;
+use put_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar,
+};
+#[cfg(feature = "serde-traits")]
+use serde::{Deserialize, Serialize};
+
+/// Role of an account in a token instruction, following the account lists
+/// documented on each `TokenInstruction` variant
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-traits", derive(Serialize, Deserialize))]
+pub enum AccountRole {
+    /// Token account tokens are taken from, or whose delegate is approved
+    /// or revoked
+    Source,
+    /// Account tokens or lamports are moved to
+    Destination,
+    /// Token account being initialized, updated, frozen, thawed or closed,
+    /// or whose balance is recorded
+    Account,
+    /// Token mint
+    Mint,
+    /// Mint meta account of a mint
+    MintMeta,
+    /// Multisignature account being initialized
+    Multisig,
+    /// Owner of a token account being initialized
+    Owner,
+    /// Delegate or operator being approved
+    Delegate,
+    /// Owner, delegate or authority acting on the instruction, or its
+    /// multisignature account
+    Authority,
+    /// Signer of a multisignature authority, or signer of a multisignature
+    /// account being initialized
+    MultisigSigner,
+    /// Account paying for the creation or growth of another account, or
+    /// funding a deposit
+    Payer,
+    /// Balance checkpoint account
+    BalanceCheckpoint,
+    /// Operator approval account, appended when an operator transfers or
+    /// burns on behalf of the owner
+    OperatorApproval,
+    /// Rent sysvar
+    RentSysvar,
+    /// Instructions sysvar, appended for transfer memo and CPI guard checks
+    InstructionsSysvar,
+    /// System program
+    SystemProgram,
+    /// Token program
+    TokenProgram,
+    /// Account the instruction does not expect
+    Unknown,
+}
+
+/// Account of a decoded token instruction
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde-traits", derive(Serialize, Deserialize))]
+pub struct DecodedAccount {
+    /// Role of the account in the instruction
+    pub role: AccountRole,
+    /// Address of the account
+    #[cfg_attr(
+        feature = "serde-traits",
+        serde(with = "crate::serialization::pubkey_fromstr")
+    )]
+    pub pubkey: Pubkey,
+    /// Whether the instruction requires the account's signature
+    pub is_signer: bool,
+    /// Whether the instruction may write to the account
+    pub is_writable: bool,
+}
+
+impl From<&DecodedAccount> for AccountMeta {
+    fn from(account: &DecodedAccount) -> Self {
+        AccountMeta {
+            pubkey: account.pubkey,
+            is_signer: account.is_signer,
+            is_writable: account.is_writable,
+        }
+    }
+}
+
+/// Token instruction decoded along with the roles of its accounts
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde-traits", derive(Serialize, Deserialize))]
+pub struct DecodedTokenInstruction {
+    /// The instruction
+    pub instruction: TokenInstruction,
+    /// The accounts of the instruction, in order
+    pub accounts: Vec<DecodedAccount>,
+}
+
+impl DecodedTokenInstruction {
+    /// Address of the first account with the given role
+    pub fn account(&self, role: AccountRole) -> Option<&Pubkey> {
+        self.accounts_with_role(role).next()
+    }
+
+    /// Addresses of the accounts with the given role, in order
+    pub fn accounts_with_role(&self, role: AccountRole) -> impl Iterator<Item = &Pubkey> {
+        self.accounts
+            .iter()
+            .filter(move |account| account.role == role)
+            .map(|account| &account.pubkey)
+    }
+
+    /// Token account tokens are taken from
+    pub fn source(&self) -> Option<&Pubkey> {
+        self.account(AccountRole::Source)
+    }
+
+    /// Account tokens or lamports are moved to
+    pub fn destination(&self) -> Option<&Pubkey> {
+        self.account(AccountRole::Destination)
+    }
+
+    /// Owner, delegate or authority acting on the instruction
+    pub fn authority(&self) -> Option<&Pubkey> {
+        self.account(AccountRole::Authority)
+    }
+
+    /// Signers of a multisignature authority
+    pub fn multisig_signers(&self) -> Vec<&Pubkey> {
+        self.accounts_with_role(AccountRole::MultisigSigner)
+            .collect()
+    }
+}
+
+/// Decodes a token instruction, naming each of its accounts after the
+/// account list documented on its variant.
+///
+/// Accounts past those a variant expects are multisignature signers if the
+/// variant takes an authority, and `AccountRole::Unknown` otherwise, except
+/// for the instructions sysvar and writable non-signer accounts, which can
+/// only be operator approvals. Fails if the instruction is not for this
+/// program, cannot be unpacked, or lacks some of the accounts its variant
+/// expects.
+pub fn decode(instruction: &Instruction) -> Result<DecodedTokenInstruction, ProgramError> {
+    use AccountRole::*;
+
+    check_program_account(&instruction.program_id)?;
+    let token_instruction = TokenInstruction::unpack(&instruction.data)?;
+    let mut accounts = RoleAssigner::new(&instruction.accounts);
+
+    let accounts = match token_instruction {
+        TokenInstruction::InitializeMint { .. } => {
+            accounts.expect(&[Mint, RentSysvar])?;
+            accounts.finish(Unknown)
+        }
+        TokenInstruction::InitializeAccount => {
+            accounts.expect(&[Account, Mint, Owner, RentSysvar])?;
+            accounts.finish(Unknown)
+        }
+        TokenInstruction::InitializeAccount2 { .. } => {
+            accounts.expect(&[Account, Mint, RentSysvar])?;
+            accounts.finish(Unknown)
+        }
+        TokenInstruction::InitializeMultisig { .. } => {
+            accounts.expect(&[Multisig, RentSysvar])?;
+            accounts.finish(MultisigSigner)
+        }
+        TokenInstruction::Transfer { .. } => {
+            accounts.expect(&[Source, Destination, Authority])?;
+            accounts.finish(MultisigSigner)
+        }
+        TokenInstruction::Approve { .. } => {
+            accounts.expect(&[Source, Delegate, Authority])?;
+            accounts.finish(MultisigSigner)
+        }
+        TokenInstruction::Revoke => {
+            accounts.expect(&[Source, Authority])?;
+            accounts.finish(MultisigSigner)
+        }
+        TokenInstruction::SetAuthority {
+            ref authority_type, ..
+        } => {
+            let owned = match authority_type {
+                AuthorityType::AccountOwner | AuthorityType::CloseAccount => Account,
+                AuthorityType::UpdateMeta => MintMeta,
+                AuthorityType::MintTokens
+                | AuthorityType::FreezeAccount
+                | AuthorityType::TransferFeeConfig
+                | AuthorityType::WithheldWithdraw
+                | AuthorityType::InterestRate
+                | AuthorityType::PermanentDelegate
+                | AuthorityType::CloseMint => Mint,
+            };
+            accounts.expect(&[owned, Authority])?;
+            accounts.finish(MultisigSigner)
+        }
+        TokenInstruction::MintTo { .. } | TokenInstruction::MintToChecked { .. } => {
+            accounts.expect(&[Mint, Destination, Authority])?;
+            accounts.finish(MultisigSigner)
+        }
+        TokenInstruction::Burn { .. } | TokenInstruction::BurnChecked { .. } => {
+            accounts.expect(&[Source, Mint, Authority])?;
+            accounts.finish(MultisigSigner)
+        }
+        TokenInstruction::CloseAccount => {
+            // Closing a mint passes its writable mint meta account where
+            // closing a token account passes read-only signers, if anything
+            let closes_mint = instruction
+                .accounts
+                .get(3)
+                .map_or(false, |meta| meta.is_writable && !meta.is_signer);
+            if closes_mint {
+                accounts.expect(&[Mint, Destination, Authority, MintMeta])?;
+            } else {
+                accounts.expect(&[Account, Destination, Authority])?;
+            }
+            accounts.finish(MultisigSigner)
+        }
+        TokenInstruction::FreezeAccount | TokenInstruction::ThawAccount => {
+            accounts.expect(&[Account, Mint, Authority])?;
+            accounts.finish(MultisigSigner)
+        }
+        TokenInstruction::TransferChecked { .. }
+        | TokenInstruction::TransferWithEncryptedNote { .. } => {
+            accounts.expect(&[Source, Mint, Destination, Authority])?;
+            accounts.finish(MultisigSigner)
+        }
+        TokenInstruction::ApproveChecked { .. } => {
+            accounts.expect(&[Source, Mint, Delegate, Authority])?;
+            accounts.finish(MultisigSigner)
+        }
+        TokenInstruction::SyncNative => {
+            accounts.expect(&[Account])?;
+            accounts.finish(Unknown)
+        }
+        TokenInstruction::UpdateSymbol { .. }
+        | TokenInstruction::UpdateName { .. }
+        | TokenInstruction::UpdateIcon { .. } => {
+            accounts.expect(&[MintMeta, Authority])?;
+            accounts.finish(MultisigSigner)
+        }
+        TokenInstruction::CreateMintMetaAccount => {
+            accounts.expect(&[
+                Payer,
+                Mint,
+                MintMeta,
+                TokenProgram,
+                SystemProgram,
+                RentSysvar,
+            ])?;
+            accounts.finish(Unknown)
+        }
+        TokenInstruction::InitMintMetaAccount { .. } => {
+            accounts.expect(&[Mint, MintMeta])?;
+            accounts.finish(Unknown)
+        }
+        TokenInstruction::InitializeTransferFeeConfig { .. }
+        | TokenInstruction::InitializeInterestBearingMint { .. }
+        | TokenInstruction::AmountToUiAmount { .. }
+        | TokenInstruction::InitializeNonTransferableMint
+        | TokenInstruction::InitializePermanentDelegate { .. }
+        | TokenInstruction::InitializeDefaultAccountState { .. }
+        | TokenInstruction::InitializeMintCloseAuthority { .. } => {
+            accounts.expect(&[Mint])?;
+            accounts.finish(Unknown)
+        }
+        TokenInstruction::SetTransferFee { .. }
+        | TokenInstruction::UpdateInterestRate { .. }
+        | TokenInstruction::UpdateDefaultAccountState { .. } => {
+            accounts.expect(&[Mint, Authority])?;
+            accounts.finish(MultisigSigner)
+        }
+        TokenInstruction::WithdrawWithheldTokensFromMint => {
+            accounts.expect(&[Mint, Destination, Authority])?;
+            accounts.finish(MultisigSigner)
+        }
+        TokenInstruction::WithdrawWithheldTokensFromAccounts { num_token_accounts } => {
+            accounts.expect(&[Mint, Destination, Authority])?;
+            let num_signers = accounts
+                .remaining()
+                .checked_sub(num_token_accounts as usize)
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+            accounts.expect_n(MultisigSigner, num_signers)?;
+            accounts.expect_n(Source, num_token_accounts as usize)?;
+            accounts.finish(Unknown)
+        }
+        TokenInstruction::HarvestWithheldTokensToMint => {
+            accounts.expect(&[Mint])?;
+            accounts.finish(Source)
+        }
+        TokenInstruction::EnableRequiredTransferMemos
+        | TokenInstruction::DisableRequiredTransferMemos
+        | TokenInstruction::EnableCpiGuard
+        | TokenInstruction::DisableCpiGuard => {
+            accounts.expect(&[Account, Authority])?;
+            accounts.finish(MultisigSigner)
+        }
+        TokenInstruction::MigrateMintMeta => {
+            accounts.expect(&[MintMeta])?;
+            accounts.finish(Unknown)
+        }
+        TokenInstruction::SetMintMetaField { .. } => {
+            accounts.expect(&[MintMeta, Payer, SystemProgram, RentSysvar, Authority])?;
+            accounts.finish(MultisigSigner)
+        }
+        TokenInstruction::RemoveMintMetaField { .. } => {
+            accounts.expect(&[MintMeta, Destination, RentSysvar, Authority])?;
+            accounts.finish(MultisigSigner)
+        }
+        TokenInstruction::BatchTransfer { ref amounts } => {
+            accounts.expect(&[Source])?;
+            accounts.expect_n(Destination, amounts.len())?;
+            accounts.expect(&[Authority])?;
+            accounts.finish(MultisigSigner)
+        }
+        TokenInstruction::BatchTransferChecked { ref amounts, .. } => {
+            accounts.expect(&[Source, Mint])?;
+            accounts.expect_n(Destination, amounts.len())?;
+            accounts.expect(&[Authority])?;
+            accounts.finish(MultisigSigner)
+        }
+        TokenInstruction::RecordBalanceCheckpoint => {
+            accounts.expect(&[
+                Payer,
+                BalanceCheckpoint,
+                Account,
+                SystemProgram,
+                RentSysvar,
+                Authority,
+            ])?;
+            accounts.finish(MultisigSigner)
+        }
+        TokenInstruction::Reallocate { .. } => {
+            accounts.expect(&[Account, Payer, SystemProgram, RentSysvar, Authority])?;
+            accounts.finish(MultisigSigner)
+        }
+        TokenInstruction::ApproveOperator { .. } => {
+            accounts.expect(&[
+                Payer,
+                OperatorApproval,
+                Mint,
+                Delegate,
+                SystemProgram,
+                RentSysvar,
+                Authority,
+            ])?;
+            accounts.finish(MultisigSigner)
+        }
+        TokenInstruction::RevokeOperator => {
+            accounts.expect(&[OperatorApproval, Destination, Authority])?;
+            accounts.finish(MultisigSigner)
+        }
+        TokenInstruction::DepositNative { .. } => {
+            accounts.expect(&[Account, Payer, SystemProgram])?;
+            accounts.finish(Unknown)
+        }
+        TokenInstruction::WithdrawNative { .. } => {
+            accounts.expect(&[Source, Destination, Authority])?;
+            accounts.finish(MultisigSigner)
+        }
+    };
+
+    Ok(DecodedTokenInstruction {
+        instruction: token_instruction,
+        accounts,
+    })
+}
+
+/// Names the accounts of an instruction in order
+struct RoleAssigner<'a> {
+    metas: &'a [AccountMeta],
+    accounts: Vec<DecodedAccount>,
+}
+
+impl<'a> RoleAssigner<'a> {
+    fn new(metas: &'a [AccountMeta]) -> Self {
+        Self {
+            metas,
+            accounts: Vec::with_capacity(metas.len()),
+        }
+    }
+
+    fn remaining(&self) -> usize {
+        self.metas.len() - self.accounts.len()
+    }
+
+    fn push(&mut self, role: AccountRole) -> Result<(), ProgramError> {
+        let meta = self
+            .metas
+            .get(self.accounts.len())
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let role = if sysvar::instructions::check_id(&meta.pubkey) {
+            AccountRole::InstructionsSysvar
+        } else if role == AccountRole::MultisigSigner && meta.is_writable && !meta.is_signer {
+            AccountRole::OperatorApproval
+        } else {
+            role
+        };
+        self.accounts.push(DecodedAccount {
+            role,
+            pubkey: meta.pubkey,
+            is_signer: meta.is_signer,
+            is_writable: meta.is_writable,
+        });
+        Ok(())
+    }
+
+    fn expect(&mut self, roles: &[AccountRole]) -> Result<(), ProgramError> {
+        roles.iter().try_for_each(|role| self.push(*role))
+    }
+
+    fn expect_n(&mut self, role: AccountRole, n: usize) -> Result<(), ProgramError> {
+        (0..n).try_for_each(|_| self.push(role))
+    }
+
+    /// Names every remaining account `role`
+    fn finish(mut self, role: AccountRole) -> Vec<DecodedAccount> {
+        while self.remaining() > 0 {
+            self.push(role).unwrap();
+        }
+        self.accounts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AccountRole::*;
+    use super::*;
+    use crate::{
+        get_balance_checkpoint_address, get_operator_approval_address,
+        instruction::*,
+        state::{AccountState, ExtensionType},
+    };
+
+    fn check(instruction: Instruction, roles: &[(AccountRole, Pubkey)]) -> DecodedTokenInstruction {
+        let decoded = decode(&instruction).unwrap();
+        assert_eq!(
+            decoded.instruction,
+            TokenInstruction::unpack(&instruction.data).unwrap()
+        );
+        assert_eq!(
+            decoded
+                .accounts
+                .iter()
+                .map(|account| (account.role, account.pubkey))
+                .collect::<Vec<_>>(),
+            roles
+        );
+
+        // round trip
+        assert_eq!(decoded.instruction.pack(), instruction.data);
+        assert_eq!(
+            decoded
+                .accounts
+                .iter()
+                .map(AccountMeta::from)
+                .collect::<Vec<_>>(),
+            instruction.accounts
+        );
+        decoded
+    }
+
+    #[test]
+    fn test_decode_builders() {
+        let program_id = crate::id();
+        let mint = Pubkey::new_unique();
+        let account = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+        let destination2 = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let delegate = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let mint_meta = Pubkey::new_unique();
+        let signer1 = Pubkey::new_unique();
+        let signer2 = Pubkey::new_unique();
+        let rent = sysvar::rent::id();
+        let instructions = sysvar::instructions::id();
+        let system = put_program::system_program::id();
+
+        check(
+            initialize_mint(&program_id, &mint, &authority, Some(&delegate), 2).unwrap(),
+            &[(Mint, mint), (RentSysvar, rent)],
+        );
+        check(
+            initialize_account(&program_id, &account, &mint, &authority).unwrap(),
+            &[
+                (Account, account),
+                (Mint, mint),
+                (Owner, authority),
+                (RentSysvar, rent),
+            ],
+        );
+        check(
+            initialize_account2(&program_id, &account, &mint, &authority).unwrap(),
+            &[(Account, account), (Mint, mint), (RentSysvar, rent)],
+        );
+        check(
+            initialize_multisig(&program_id, &account, &[&signer1, &signer2], 2).unwrap(),
+            &[
+                (Multisig, account),
+                (RentSysvar, rent),
+                (MultisigSigner, signer1),
+                (MultisigSigner, signer2),
+            ],
+        );
+
+        // single and multisignature authorities
+        let decoded = check(
+            transfer(&program_id, &account, &destination, &authority, &[], 1).unwrap(),
+            &[
+                (Source, account),
+                (Destination, destination),
+                (Authority, authority),
+            ],
+        );
+        assert_eq!(decoded.source(), Some(&account));
+        assert_eq!(decoded.destination(), Some(&destination));
+        assert_eq!(decoded.authority(), Some(&authority));
+        assert!(decoded.multisig_signers().is_empty());
+        let decoded = check(
+            transfer(
+                &program_id,
+                &account,
+                &destination,
+                &authority,
+                &[&signer1, &signer2],
+                1,
+            )
+            .unwrap(),
+            &[
+                (Source, account),
+                (Destination, destination),
+                (Authority, authority),
+                (MultisigSigner, signer1),
+                (MultisigSigner, signer2),
+            ],
+        );
+        assert_eq!(decoded.multisig_signers(), vec![&signer1, &signer2]);
+
+        // the instructions sysvar appended for memo and CPI guard checks
+        let mut instruction = transfer(
+            &program_id,
+            &account,
+            &destination,
+            &authority,
+            &[&signer1],
+            1,
+        )
+        .unwrap();
+        instruction
+            .accounts
+            .push(AccountMeta::new_readonly(instructions, false));
+        check(
+            instruction,
+            &[
+                (Source, account),
+                (Destination, destination),
+                (Authority, authority),
+                (MultisigSigner, signer1),
+                (InstructionsSysvar, instructions),
+            ],
+        );
+
+        check(
+            approve(&program_id, &account, &delegate, &authority, &[&signer1], 1).unwrap(),
+            &[
+                (Source, account),
+                (Delegate, delegate),
+                (Authority, authority),
+                (MultisigSigner, signer1),
+            ],
+        );
+        check(
+            revoke(&program_id, &account, &authority, &[]).unwrap(),
+            &[(Source, account), (Authority, authority)],
+        );
+        check(
+            set_authority(
+                &program_id,
+                &mint,
+                Some(&delegate),
+                AuthorityType::MintTokens,
+                &authority,
+                &[],
+            )
+            .unwrap(),
+            &[(Mint, mint), (Authority, authority)],
+        );
+        check(
+            set_authority(
+                &program_id,
+                &account,
+                None,
+                AuthorityType::CloseAccount,
+                &authority,
+                &[&signer1],
+            )
+            .unwrap(),
+            &[
+                (Account, account),
+                (Authority, authority),
+                (MultisigSigner, signer1),
+            ],
+        );
+        let (derived_mint_meta, _) =
+            Pubkey::find_program_address(&[b"MintMeta", &mint.to_bytes()], &program_id);
+        check(
+            set_authority(
+                &program_id,
+                &mint,
+                Some(&delegate),
+                AuthorityType::UpdateMeta,
+                &authority,
+                &[],
+            )
+            .unwrap(),
+            &[(MintMeta, derived_mint_meta), (Authority, authority)],
+        );
+        check(
+            mint_to(&program_id, &mint, &account, &authority, &[], 1).unwrap(),
+            &[(Mint, mint), (Destination, account), (Authority, authority)],
+        );
+        check(
+            burn(&program_id, &account, &mint, &authority, &[], 1).unwrap(),
+            &[(Source, account), (Mint, mint), (Authority, authority)],
+        );
+        check(
+            close_account(&program_id, &account, &destination, &authority, &[&signer1]).unwrap(),
+            &[
+                (Account, account),
+                (Destination, destination),
+                (Authority, authority),
+                (MultisigSigner, signer1),
+            ],
+        );
+        check(
+            close_account(&program_id, &account, &destination, &authority, &[]).unwrap(),
+            &[
+                (Account, account),
+                (Destination, destination),
+                (Authority, authority),
+            ],
+        );
+        check(
+            close_mint(&program_id, &mint, &destination, &authority, &[]).unwrap(),
+            &[
+                (Mint, mint),
+                (Destination, destination),
+                (Authority, authority),
+                (MintMeta, derived_mint_meta),
+            ],
+        );
+        check(
+            close_mint(&program_id, &mint, &destination, &authority, &[&signer1]).unwrap(),
+            &[
+                (Mint, mint),
+                (Destination, destination),
+                (Authority, authority),
+                (MintMeta, derived_mint_meta),
+                (MultisigSigner, signer1),
+            ],
+        );
+        check(
+            freeze_account(&program_id, &account, &mint, &authority, &[]).unwrap(),
+            &[(Account, account), (Mint, mint), (Authority, authority)],
+        );
+        check(
+            thaw_account(&program_id, &account, &mint, &authority, &[]).unwrap(),
+            &[(Account, account), (Mint, mint), (Authority, authority)],
+        );
+        check(
+            transfer_checked(
+                &program_id,
+                &account,
+                &mint,
+                &destination,
+                &authority,
+                &[],
+                1,
+                2,
+            )
+            .unwrap(),
+            &[
+                (Source, account),
+                (Mint, mint),
+                (Destination, destination),
+                (Authority, authority),
+            ],
+        );
+        check(
+            approve_checked(
+                &program_id,
+                &account,
+                &mint,
+                &delegate,
+                &authority,
+                &[],
+                1,
+                2,
+            )
+            .unwrap(),
+            &[
+                (Source, account),
+                (Mint, mint),
+                (Delegate, delegate),
+                (Authority, authority),
+            ],
+        );
+        let decoded = check(
+            approve_recurring(
+                &program_id,
+                &account,
+                &mint,
+                &delegate,
+                &authority,
+                &[],
+                1,
+                2,
+                60,
+            )
+            .unwrap(),
+            &[
+                (Source, account),
+                (Mint, mint),
+                (Delegate, delegate),
+                (Authority, authority),
+            ],
+        );
+        // the period survives the round trip
+        assert_eq!(
+            decoded.instruction,
+            TokenInstruction::ApproveChecked {
+                amount: 1,
+                decimals: 2,
+                period: Some(60),
+            }
+        );
+        check(
+            mint_to_checked(&program_id, &mint, &account, &authority, &[], 1, 2).unwrap(),
+            &[(Mint, mint), (Destination, account), (Authority, authority)],
+        );
+        check(
+            burn_checked(&program_id, &account, &mint, &authority, &[], 1, 2).unwrap(),
+            &[(Source, account), (Mint, mint), (Authority, authority)],
+        );
+        check(
+            sync_native(&program_id, &account).unwrap(),
+            &[(Account, account)],
+        );
+        check(
+            update_token_symbol(&program_id, &mint_meta, &authority, &[], "SYM".to_string())
+                .unwrap(),
+            &[(MintMeta, mint_meta), (Authority, authority)],
+        );
+        check(
+            update_token_name(&program_id, &mint_meta, &authority, &[], "Name".to_string())
+                .unwrap(),
+            &[(MintMeta, mint_meta), (Authority, authority)],
+        );
+        check(
+            update_token_icon(&program_id, &mint_meta, &authority, &[], "icon".to_string())
+                .unwrap(),
+            &[(MintMeta, mint_meta), (Authority, authority)],
+        );
+        check(
+            create_mint_meta_account(&program_id, &payer, &mint, &mint_meta).unwrap(),
+            &[
+                (Payer, payer),
+                (Mint, mint),
+                (MintMeta, mint_meta),
+                (TokenProgram, program_id),
+                (SystemProgram, system),
+                (RentSysvar, rent),
+            ],
+        );
+        check(
+            initialize_mint_meta(
+                &program_id,
+                &mint,
+                &mint_meta,
+                "SYM".to_string(),
+                "Name".to_string(),
+                "icon".to_string(),
+            )
+            .unwrap(),
+            &[(Mint, mint), (MintMeta, mint_meta)],
+        );
+        check(
+            initialize_transfer_fee_config(&program_id, &mint, Some(&authority), None, 10, 100)
+                .unwrap(),
+            &[(Mint, mint)],
+        );
+        check(
+            set_transfer_fee(&program_id, &mint, &authority, &[], 10, 100).unwrap(),
+            &[(Mint, mint), (Authority, authority)],
+        );
+        check(
+            withdraw_withheld_tokens_from_mint(&program_id, &mint, &destination, &authority, &[])
+                .unwrap(),
+            &[
+                (Mint, mint),
+                (Destination, destination),
+                (Authority, authority),
+            ],
+        );
+        check(
+            withdraw_withheld_tokens_from_accounts(
+                &program_id,
+                &mint,
+                &destination,
+                &authority,
+                &[&signer1],
+                &[&account, &destination2],
+            )
+            .unwrap(),
+            &[
+                (Mint, mint),
+                (Destination, destination),
+                (Authority, authority),
+                (MultisigSigner, signer1),
+                (Source, account),
+                (Source, destination2),
+            ],
+        );
+        check(
+            harvest_withheld_tokens_to_mint(&program_id, &mint, &[&account, &destination2])
+                .unwrap(),
+            &[(Mint, mint), (Source, account), (Source, destination2)],
+        );
+        check(
+            initialize_interest_bearing_mint(&program_id, &mint, Some(&authority), 5).unwrap(),
+            &[(Mint, mint)],
+        );
+        check(
+            update_interest_rate(&program_id, &mint, &authority, &[], 5).unwrap(),
+            &[(Mint, mint), (Authority, authority)],
+        );
+        check(
+            amount_to_ui_amount(&program_id, &mint, 1).unwrap(),
+            &[(Mint, mint)],
+        );
+        check(
+            initialize_non_transferable_mint(&program_id, &mint).unwrap(),
+            &[(Mint, mint)],
+        );
+        check(
+            initialize_permanent_delegate(&program_id, &mint, &delegate).unwrap(),
+            &[(Mint, mint)],
+        );
+        check(
+            initialize_default_account_state(&program_id, &mint, &AccountState::Frozen).unwrap(),
+            &[(Mint, mint)],
+        );
+        check(
+            update_default_account_state(
+                &program_id,
+                &mint,
+                &authority,
+                &[],
+                &AccountState::Initialized,
+            )
+            .unwrap(),
+            &[(Mint, mint), (Authority, authority)],
+        );
+        check(
+            initialize_mint_close_authority(&program_id, &mint, Some(&authority)).unwrap(),
+            &[(Mint, mint)],
+        );
+        check(
+            enable_required_transfer_memos(&program_id, &account, &authority, &[]).unwrap(),
+            &[(Account, account), (Authority, authority)],
+        );
+        check(
+            disable_required_transfer_memos(&program_id, &account, &authority, &[]).unwrap(),
+            &[(Account, account), (Authority, authority)],
+        );
+        check(
+            enable_cpi_guard(&program_id, &account, &authority, &[]).unwrap(),
+            &[(Account, account), (Authority, authority)],
+        );
+        check(
+            disable_cpi_guard(&program_id, &account, &authority, &[&signer1]).unwrap(),
+            &[
+                (Account, account),
+                (Authority, authority),
+                (MultisigSigner, signer1),
+                (InstructionsSysvar, instructions),
+            ],
+        );
+        check(
+            migrate_mint_meta(&program_id, &mint_meta).unwrap(),
+            &[(MintMeta, mint_meta)],
+        );
+        check(
+            set_mint_meta_field(
+                &program_id,
+                &mint_meta,
+                &payer,
+                &authority,
+                &[],
+                "key".to_string(),
+                "value".to_string(),
+            )
+            .unwrap(),
+            &[
+                (MintMeta, mint_meta),
+                (Payer, payer),
+                (SystemProgram, system),
+                (RentSysvar, rent),
+                (Authority, authority),
+            ],
+        );
+        check(
+            remove_mint_meta_field(
+                &program_id,
+                &mint_meta,
+                &destination,
+                &authority,
+                &[],
+                "key".to_string(),
+            )
+            .unwrap(),
+            &[
+                (MintMeta, mint_meta),
+                (Destination, destination),
+                (RentSysvar, rent),
+                (Authority, authority),
+            ],
+        );
+        let decoded = check(
+            batch_transfer(
+                &program_id,
+                &account,
+                &[(&destination, 1), (&destination2, 2)],
+                &authority,
+                &[&signer1],
+            )
+            .unwrap(),
+            &[
+                (Source, account),
+                (Destination, destination),
+                (Destination, destination2),
+                (Authority, authority),
+                (MultisigSigner, signer1),
+            ],
+        );
+        assert_eq!(
+            decoded.accounts_with_role(Destination).collect::<Vec<_>>(),
+            vec![&destination, &destination2]
+        );
+        check(
+            batch_transfer_checked(
+                &program_id,
+                &account,
+                &mint,
+                &[(&destination, 1), (&destination2, 2)],
+                &authority,
+                &[],
+                2,
+            )
+            .unwrap(),
+            &[
+                (Source, account),
+                (Mint, mint),
+                (Destination, destination),
+                (Destination, destination2),
+                (Authority, authority),
+            ],
+        );
+        check(
+            record_balance_checkpoint(&program_id, &payer, &account, &authority, &[], 7)
+                .unwrap(),
+            &[
+                (Payer, payer),
+                (
+                    BalanceCheckpoint,
+                    get_balance_checkpoint_address(&account, 7),
+                ),
+                (Account, account),
+                (SystemProgram, system),
+                (RentSysvar, rent),
+                (Authority, authority),
+            ],
+        );
+        check(
+            transfer_with_encrypted_note(
+                &program_id,
+                &account,
+                &mint,
+                &destination,
+                &authority,
+                &[],
+                1,
+                2,
+                vec![1, 2, 3],
+            )
+            .unwrap(),
+            &[
+                (Source, account),
+                (Mint, mint),
+                (Destination, destination),
+                (Authority, authority),
+            ],
+        );
+        check(
+            reallocate(
+                &program_id,
+                &account,
+                &payer,
+                &authority,
+                &[&signer1],
+                &[ExtensionType::MemoTransfer],
+            )
+            .unwrap(),
+            &[
+                (Account, account),
+                (Payer, payer),
+                (SystemProgram, system),
+                (RentSysvar, rent),
+                (Authority, authority),
+                (MultisigSigner, signer1),
+            ],
+        );
+        let operator_approval = get_operator_approval_address(&mint, &authority, &delegate);
+        check(
+            approve_operator(
+                &program_id,
+                &payer,
+                &mint,
+                &delegate,
+                &authority,
+                &[],
+                Some(1),
+                2,
+            )
+            .unwrap(),
+            &[
+                (Payer, payer),
+                (OperatorApproval, operator_approval),
+                (Mint, mint),
+                (Delegate, delegate),
+                (SystemProgram, system),
+                (RentSysvar, rent),
+                (Authority, authority),
+                (InstructionsSysvar, instructions),
+            ],
+        );
+        check(
+            revoke_operator(
+                &program_id,
+                &mint,
+                &delegate,
+                &destination,
+                &authority,
+                &[&signer1],
+            )
+            .unwrap(),
+            &[
+                (OperatorApproval, operator_approval),
+                (Destination, destination),
+                (Authority, authority),
+                (MultisigSigner, signer1),
+            ],
+        );
+        let mut instruction = transfer_checked(
+            &program_id,
+            &account,
+            &mint,
+            &destination,
+            &delegate,
+            &[],
+            1,
+            2,
+        )
+        .unwrap();
+        add_operator_approval(&mut instruction, &mint, &authority, &delegate);
+        check(
+            instruction,
+            &[
+                (Source, account),
+                (Mint, mint),
+                (Destination, destination),
+                (Authority, delegate),
+                (OperatorApproval, operator_approval),
+            ],
+        );
+        check(
+            deposit_native(&program_id, &account, &payer, 1).unwrap(),
+            &[(Account, account), (Payer, payer), (SystemProgram, system)],
+        );
+        check(
+            withdraw_native(&program_id, &account, &destination, &authority, &[], 1).unwrap(),
+            &[
+                (Source, account),
+                (Destination, destination),
+                (Authority, authority),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        let program_id = crate::id();
+        let account = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let mut instruction =
+            transfer(&program_id, &account, &destination, &authority, &[], 1).unwrap();
+
+        // missing accounts
+        let mut missing = instruction.clone();
+        missing.accounts.pop();
+        assert_eq!(decode(&missing), Err(ProgramError::NotEnoughAccountKeys));
+        let mut missing = withdraw_withheld_tokens_from_accounts(
+            &program_id,
+            &account,
+            &destination,
+            &authority,
+            &[],
+            &[&account],
+        )
+        .unwrap();
+        missing.accounts.pop();
+        assert_eq!(decode(&missing), Err(ProgramError::NotEnoughAccountKeys));
+
+        // unexpected accounts
+        let mut extra = sync_native(&program_id, &account).unwrap();
+        extra
+            .accounts
+            .push(AccountMeta::new_readonly(destination, false));
+        assert_eq!(decode(&extra).unwrap().accounts[1].role, Unknown);
+
+        // malformed data
+        let mut truncated = transfer_checked(
+            &program_id,
+            &account,
+            &authority,
+            &destination,
+            &authority,
+            &[],
+            1,
+            2,
+        )
+        .unwrap();
+        truncated.data.truncate(8);
+        assert_eq!(
+            decode(&truncated),
+            Err(crate::error::TokenError::InvalidInstruction.into())
+        );
+        instruction.data.truncate(1);
+        assert_eq!(
+            decode(&instruction),
+            Err(crate::error::TokenError::InvalidInstruction.into())
+        );
+
+        // another program
+        instruction.program_id = Pubkey::new_unique();
+        assert_eq!(decode(&instruction), Err(ProgramError::IncorrectProgramId));
+    }
+
+    #[cfg(feature = "serde-traits")]
+    #[test]
+    fn test_serde() {
+        let program_id = crate::id();
+        let mint = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let decoded = decode(
+            &set_authority(
+                &program_id,
+                &mint,
+                Some(&authority),
+                AuthorityType::MintTokens,
+                &authority,
+                &[],
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        let json = serde_json::to_string(&decoded).unwrap();
+        assert!(json.contains(&format!("\"{}\"", mint)));
+        assert!(json.contains(&format!("\"new_authority\":\"{}\"", authority)));
+        assert_eq!(
+            serde_json::from_str::<DecodedTokenInstruction>(&json).unwrap(),
+            decoded
+        );
+
+        let decoded =
+            decode(&initialize_mint(&program_id, &mint, &authority, None, 9).unwrap()).unwrap();
+        let json = serde_json::to_string(&decoded).unwrap();
+        assert!(json.contains(&format!("\"mint_authority\":\"{}\"", authority)));
+        assert!(json.contains("\"freeze_authority\":null"));
+        assert_eq!(
+            serde_json::from_str::<DecodedTokenInstruction>(&json).unwrap(),
+            decoded
+        );
+    }
+}
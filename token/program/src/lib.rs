@@ -1,5 +1,5 @@
 #![deny(missing_docs)]
-#![forbid(unsafe_code)]
+#![cfg_attr(not(test), forbid(unsafe_code))]
 // #![feature(round_char_boundary)]
 
 //! An ERC20-like Token program for the PUT blockchain
@@ -8,7 +8,10 @@ pub mod error;
 pub mod instruction;
 pub mod native_mint;
 pub mod native_mint_info;
+pub mod note;
 pub mod processor;
+#[cfg(feature = "serde-traits")]
+pub mod serialization;
 pub mod state;
 
 #[cfg(not(feature = "no-entrypoint"))]
@@ -16,14 +19,60 @@ mod entrypoint;
 
 // Export current sdk types for downstream users building with a different sdk version
 pub use put_program;
-use put_program::{entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey};
+use put_program::{
+    clock::{Epoch, UnixTimestamp},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use error::TokenError;
+use state::InterestBearingConfig;
 
 /// Convert the UI representation of a token amount (using the decimals field defined in its mint)
-/// to the raw amount
+/// to the raw amount.  The fractional part goes through `f64`, so prefer
+/// `ui_amount_str_to_amount`, which is exact.
 pub fn ui_amount_to_amount(inte: u128, frac: f64, decimals: u8) -> u128 {
     inte * 10_usize.pow(decimals as u32) as u128 + (frac * 10_usize.pow(decimals as u32) as f64) as u128
 }
 
+/// Convert the UI representation of a token amount, such as `"1.25"`, to the raw amount, exactly
+/// (using the decimals field defined in its mint).
+///
+/// Fails with `InvalidArgument` if the string is not a plain decimal number or has more
+/// significant fractional digits than `decimals`, and with `TokenError::Overflow` if the amount
+/// does not fit in a `u128`.
+pub fn ui_amount_str_to_amount(ui_amount: &str, decimals: u8) -> Result<u128, ProgramError> {
+    let (integer, fraction) = ui_amount.split_once('.').unwrap_or((ui_amount, ""));
+    if (integer.is_empty() && fraction.is_empty())
+        || !integer.bytes().all(|b| b.is_ascii_digit())
+        || !fraction.bytes().all(|b| b.is_ascii_digit())
+    {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.len() > decimals as usize {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Only digits are left, so parsing can only fail by overflowing
+    let parse = |digits: &str| match digits {
+        "" => Ok(0),
+        digits => digits.parse::<u128>().map_err(|_| TokenError::Overflow),
+    };
+    let scale = |digits: usize| {
+        10u128
+            .checked_pow(digits as u32)
+            .ok_or(TokenError::Overflow)
+    };
+    let fraction = parse(fraction)?
+        .checked_mul(scale(decimals as usize - fraction.len())?)
+        .ok_or(TokenError::Overflow)?;
+    parse(integer)?
+        .checked_mul(scale(decimals as usize)?)
+        .and_then(|amount| amount.checked_add(fraction))
+        .ok_or_else(|| TokenError::Overflow.into())
+}
+
 /// Convert a raw amount to its UI representation (using the decimals field defined in its mint)
 pub fn amount_to_ui_amount(amount: u128, decimals: u8) -> String {
     let decimals = decimals as usize;
@@ -40,6 +89,27 @@ pub fn amount_to_ui_amount(amount: u128, decimals: u8) -> String {
     }
 }
 
+/// Convert a raw amount of an interest-bearing mint to its UI representation, including the
+/// interest accrued up to `unix_timestamp`, usually the current `Clock::unix_timestamp`
+pub fn amount_to_ui_amount_with_interest(
+    amount: u128,
+    decimals: u8,
+    interest_bearing_config: &InterestBearingConfig,
+    unix_timestamp: UnixTimestamp,
+) -> Option<String> {
+    let scale = interest_bearing_config.total_scale(unix_timestamp)?;
+    let ui_amount = amount as f64 * scale / 10_f64.powi(decimals as i32);
+    if !ui_amount.is_finite() {
+        return None;
+    }
+    let s = format!("{:.*}", decimals as usize, ui_amount);
+    if decimals > 0 {
+        Some(s.trim_end_matches('0').trim_end_matches('.').to_string())
+    } else {
+        Some(s)
+    }
+}
+
 put_program::declare_id!("PutToken11111111111111111111111111111111111");
 // put_program::declare_id!("3LbxkbtrnKaUeqYLpmXx8QNUAYfw8PUqcjib4xLdvGyJ");
 
@@ -52,6 +122,42 @@ pub fn check_program_account(ppl_token_program_id: &Pubkey) -> ProgramResult {
     Ok(())
 }
 
+pub(crate) fn get_balance_checkpoint_address_with_seed(
+    account: &Pubkey,
+    epoch: Epoch,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[br"Checkpoint", &account.to_bytes(), &epoch.to_le_bytes()],
+        &id(),
+    )
+}
+
+/// Derive the address of the balance checkpoint of the token account `account` during `epoch`
+pub fn get_balance_checkpoint_address(account: &Pubkey, epoch: Epoch) -> Pubkey {
+    get_balance_checkpoint_address_with_seed(account, epoch).0
+}
+
+pub(crate) fn get_operator_approval_address_with_seed(
+    mint: &Pubkey,
+    owner: &Pubkey,
+    operator: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            br"Operator",
+            &mint.to_bytes(),
+            &owner.to_bytes(),
+            &operator.to_bytes(),
+        ],
+        &id(),
+    )
+}
+
+/// Derive the address of the approval of `operator` over the `mint` token accounts of `owner`
+pub fn get_operator_approval_address(mint: &Pubkey, owner: &Pubkey, operator: &Pubkey) -> Pubkey {
+    get_operator_approval_address_with_seed(mint, owner, operator).0
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -70,4 +176,101 @@ mod tests {
         );
 
     }
+
+    #[test]
+    fn test_ui_amount_str_to_amount() {
+        assert_eq!(ui_amount_str_to_amount("1.25", 2), Ok(125));
+        assert_eq!(ui_amount_str_to_amount("1.250000", 2), Ok(125));
+        assert_eq!(ui_amount_str_to_amount("1", 2), Ok(100));
+        assert_eq!(ui_amount_str_to_amount("1.", 2), Ok(100));
+        assert_eq!(ui_amount_str_to_amount(".5", 2), Ok(50));
+        assert_eq!(ui_amount_str_to_amount("007", 0), Ok(7));
+        assert_eq!(ui_amount_str_to_amount("0", 38), Ok(0));
+
+        // exact where f64 is not
+        assert_eq!(
+            ui_amount_str_to_amount("340282366920938463463.374607431768211455", 18),
+            Ok(u128::MAX)
+        );
+        assert_eq!(
+            ui_amount_str_to_amount("123456789.123456789123456789", 27),
+            Ok(123456789_123456789123456789123456789)
+        );
+        assert_eq!(ui_amount_str_to_amount("0.000000000000000001", 18), Ok(1));
+
+        // excess precision
+        assert_eq!(
+            ui_amount_str_to_amount("1.0000000000000000001", 18),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(
+            ui_amount_str_to_amount("1.001", 2),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(
+            ui_amount_str_to_amount("1.5", 0),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        // overflow
+        assert_eq!(
+            ui_amount_str_to_amount("340282366920938463463.374607431768211456", 18),
+            Err(TokenError::Overflow.into())
+        );
+        assert_eq!(
+            ui_amount_str_to_amount("340282366920938463463374607431768211456", 0),
+            Err(TokenError::Overflow.into())
+        );
+        assert_eq!(
+            ui_amount_str_to_amount("1", 39),
+            Err(TokenError::Overflow.into())
+        );
+
+        // malformed
+        for ui_amount in [
+            "", ".", "-1", "+1", "1.2.3", "1,5", " 1", "1e3", "0x10", "ALL",
+        ] {
+            assert_eq!(
+                ui_amount_str_to_amount(ui_amount, 9),
+                Err(ProgramError::InvalidArgument)
+            );
+        }
+    }
+
+    #[test]
+    fn test_amount_to_ui_amount_with_interest() {
+        let config = InterestBearingConfig {
+            initialization_timestamp: 0,
+            pre_update_average_rate: 500,
+            last_update_timestamp: 0,
+            current_rate: 500,
+            ..InterestBearingConfig::default()
+        };
+        assert_eq!(
+            Some("10000".to_string()),
+            amount_to_ui_amount_with_interest(1_000_000, 2, &config, 0)
+        );
+        // 5% a year, compounded continuously
+        let one_year = state::SECONDS_PER_YEAR as UnixTimestamp;
+        assert_eq!(
+            Some("10512.71".to_string()),
+            amount_to_ui_amount_with_interest(1_000_000, 2, &config, one_year)
+        );
+        assert_eq!(
+            Some("10513".to_string()),
+            amount_to_ui_amount_with_interest(10_000, 0, &config, one_year)
+        );
+
+        // 5% for a year, then -5% for a year
+        let config = InterestBearingConfig {
+            last_update_timestamp: one_year,
+            current_rate: -500,
+            ..config
+        };
+        assert_eq!(
+            Some("10000".to_string()),
+            amount_to_ui_amount_with_interest(1_000_000, 2, &config, 2 * one_year)
+        );
+        assert_eq!(Some(0), config.time_weighted_average_rate(2 * one_year));
+    }
 }
@@ -0,0 +1,355 @@
+//! Encrypted notes attached to transfers by `TransferWithEncryptedNote`
+//!
+//! A note holds an amount and a free-form reference, encrypted with AES-256-GCM
+//! under a key derived from the X25519 shared secret of the sender and the
+//! recipient, so either of them can decrypt it. The X25519 keys of a wallet are
+//! derived from its ed25519 keypair, so a note can be addressed to a wallet
+//! address directly.
+//!
+//! Packed as the version byte, the sender and recipient X25519 public keys, the
+//! nonce, and then the ciphertext of the little-endian `u128` amount followed by
+//! the reference, with its authentication tag.
+
+use crate::error::TokenError;
+use put_program::program_error::ProgramError;
+#[cfg(not(target_arch = "bpf"))]
+use {
+    aes_gcm::{
+        aead::{Aead, NewAead, Payload},
+        Aes256Gcm, Key, Nonce,
+    },
+    curve25519_dalek::{
+        constants::X25519_BASEPOINT, edwards::CompressedEdwardsY, montgomery::MontgomeryPoint,
+        scalar::Scalar,
+    },
+    put_program::pubkey::Pubkey,
+    rand_core::{CryptoRng, RngCore},
+    sha2::{Digest, Sha256, Sha512},
+    std::convert::TryInto,
+};
+
+/// Version of the note layout written by `encrypt_note`
+pub const NOTE_VERSION: u8 = 1;
+/// Length of an X25519 key
+pub const NOTE_KEY_LEN: usize = 32;
+/// Maximum length in bytes of the reference carried by a note
+pub const MAX_NOTE_REFERENCE_LEN: usize = 128;
+
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+const AMOUNT_LEN: usize = 16;
+const HEADER_LEN: usize = 1 + NOTE_KEY_LEN * 2 + NONCE_LEN;
+
+/// Length of a note carrying an empty reference
+pub const MIN_NOTE_LEN: usize = HEADER_LEN + AMOUNT_LEN + TAG_LEN;
+/// Length of a note carrying the longest reference
+pub const MAX_NOTE_LEN: usize = MIN_NOTE_LEN + MAX_NOTE_REFERENCE_LEN;
+
+/// Checks the version and length of an encrypted note. Its contents can only be
+/// checked by the holder of one of its keys.
+pub fn check_encrypted_note(note: &[u8]) -> Result<(), ProgramError> {
+    if note.len() < MIN_NOTE_LEN || note.len() > MAX_NOTE_LEN || note[0] != NOTE_VERSION {
+        return Err(TokenError::InvalidEncryptedNote.into());
+    }
+    Ok(())
+}
+
+/// Plaintext of an encrypted note
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DecryptedNote {
+    /// The amount the note refers to
+    pub amount: u128,
+    /// Free-form reference, such as an invoice number
+    pub reference: Vec<u8>,
+}
+
+/// Derives the X25519 secret key of a wallet from the 32-byte seed of its
+/// ed25519 keypair, the first half of its 64-byte keypair encoding
+#[cfg(not(target_arch = "bpf"))]
+pub fn note_secret_key_from_seed(seed: &[u8; 32]) -> [u8; NOTE_KEY_LEN] {
+    let hash = Sha512::digest(seed);
+    hash[..NOTE_KEY_LEN].try_into().unwrap()
+}
+
+/// Derives the X25519 public key of an X25519 secret key
+#[cfg(not(target_arch = "bpf"))]
+pub fn note_public_key(secret_key: &[u8; NOTE_KEY_LEN]) -> [u8; NOTE_KEY_LEN] {
+    (X25519_BASEPOINT * clamp(secret_key)).to_bytes()
+}
+
+/// Derives the X25519 public key of a wallet from its address, matching the
+/// secret key from `note_secret_key_from_seed`. Returns `None` if the address
+/// is not a point on the curve, as for program derived addresses.
+#[cfg(not(target_arch = "bpf"))]
+pub fn note_public_key_from_wallet(wallet: &Pubkey) -> Option<[u8; NOTE_KEY_LEN]> {
+    CompressedEdwardsY(wallet.to_bytes())
+        .decompress()
+        .map(|point| point.to_montgomery().to_bytes())
+}
+
+/// Encrypts a note from the sender to the recipient
+#[cfg(not(target_arch = "bpf"))]
+pub fn encrypt_note<R: RngCore + CryptoRng>(
+    sender_secret_key: &[u8; NOTE_KEY_LEN],
+    recipient_public_key: &[u8; NOTE_KEY_LEN],
+    note: &DecryptedNote,
+    rng: &mut R,
+) -> Result<Vec<u8>, ProgramError> {
+    if note.reference.len() > MAX_NOTE_REFERENCE_LEN {
+        return Err(TokenError::InvalidEncryptedNote.into());
+    }
+    let sender_public_key = note_public_key(sender_secret_key);
+    let cipher = note_cipher(
+        sender_secret_key,
+        recipient_public_key,
+        &sender_public_key,
+        recipient_public_key,
+    )?;
+
+    let mut packed = Vec::with_capacity(MIN_NOTE_LEN + note.reference.len());
+    packed.push(NOTE_VERSION);
+    packed.extend_from_slice(&sender_public_key);
+    packed.extend_from_slice(recipient_public_key);
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill_bytes(&mut nonce);
+    packed.extend_from_slice(&nonce);
+
+    let mut plaintext = Vec::with_capacity(AMOUNT_LEN + note.reference.len());
+    plaintext.extend_from_slice(&note.amount.to_le_bytes());
+    plaintext.extend_from_slice(&note.reference);
+    let ciphertext = cipher
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: &plaintext,
+                aad: &packed[..HEADER_LEN - NONCE_LEN],
+            },
+        )
+        .map_err(|_| TokenError::InvalidEncryptedNote)?;
+    packed.extend_from_slice(&ciphertext);
+    Ok(packed)
+}
+
+/// Decrypts a note with the X25519 secret key of either its sender or its
+/// recipient
+#[cfg(not(target_arch = "bpf"))]
+pub fn decrypt_note(
+    note: &[u8],
+    secret_key: &[u8; NOTE_KEY_LEN],
+) -> Result<DecryptedNote, ProgramError> {
+    check_encrypted_note(note)?;
+    let (header, ciphertext) = note.split_at(HEADER_LEN);
+    let sender_public_key: &[u8; NOTE_KEY_LEN] = header[1..1 + NOTE_KEY_LEN].try_into().unwrap();
+    let recipient_public_key: &[u8; NOTE_KEY_LEN] = header[1 + NOTE_KEY_LEN..1 + NOTE_KEY_LEN * 2]
+        .try_into()
+        .unwrap();
+    let nonce = &header[HEADER_LEN - NONCE_LEN..];
+
+    let public_key = note_public_key(secret_key);
+    let counterparty_public_key = if public_key == *sender_public_key {
+        recipient_public_key
+    } else if public_key == *recipient_public_key {
+        sender_public_key
+    } else {
+        return Err(TokenError::InvalidEncryptedNote.into());
+    };
+    let cipher = note_cipher(
+        secret_key,
+        counterparty_public_key,
+        sender_public_key,
+        recipient_public_key,
+    )?;
+    let plaintext = cipher
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: &header[..HEADER_LEN - NONCE_LEN],
+            },
+        )
+        .map_err(|_| TokenError::InvalidEncryptedNote)?;
+
+    let (amount, reference) = plaintext.split_at(AMOUNT_LEN);
+    Ok(DecryptedNote {
+        amount: u128::from_le_bytes(amount.try_into().unwrap()),
+        reference: reference.to_vec(),
+    })
+}
+
+#[cfg(not(target_arch = "bpf"))]
+fn clamp(secret_key: &[u8; NOTE_KEY_LEN]) -> Scalar {
+    let mut bytes = *secret_key;
+    bytes[0] &= 248;
+    bytes[31] &= 127;
+    bytes[31] |= 64;
+    Scalar::from_bits(bytes)
+}
+
+/// Builds the cipher of a note from the X25519 shared secret of its parties,
+/// bound to both of their public keys
+#[cfg(not(target_arch = "bpf"))]
+fn note_cipher(
+    secret_key: &[u8; NOTE_KEY_LEN],
+    counterparty_public_key: &[u8; NOTE_KEY_LEN],
+    sender_public_key: &[u8; NOTE_KEY_LEN],
+    recipient_public_key: &[u8; NOTE_KEY_LEN],
+) -> Result<Aes256Gcm, ProgramError> {
+    let shared_secret = (MontgomeryPoint(*counterparty_public_key) * clamp(secret_key)).to_bytes();
+    // Low-order public keys yield a shared secret known to everyone
+    if shared_secret == [0; NOTE_KEY_LEN] {
+        return Err(TokenError::InvalidEncryptedNote.into());
+    }
+    let key = Sha256::new()
+        .chain(b"ppl-token-encrypted-note")
+        .chain(shared_secret)
+        .chain(sender_public_key)
+        .chain(recipient_public_key)
+        .finalize();
+    Ok(Aes256Gcm::new(Key::from_slice(&key)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use put_sdk::signature::{Keypair, Signer};
+
+    fn secret_key_of(keypair: &Keypair) -> [u8; NOTE_KEY_LEN] {
+        note_secret_key_from_seed(&keypair.to_bytes()[..32].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_wallet_keys() {
+        let wallet = Keypair::new();
+        assert_eq!(
+            note_public_key_from_wallet(&wallet.pubkey()),
+            Some(note_public_key(&secret_key_of(&wallet)))
+        );
+
+        let (program_address, _) = Pubkey::find_program_address(&[b"note"], &crate::id());
+        assert_eq!(note_public_key_from_wallet(&program_address), None);
+    }
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let sender = Keypair::new();
+        let recipient = Keypair::new();
+        let outsider = Keypair::new();
+        let note = DecryptedNote {
+            amount: u128::MAX - 1,
+            reference: b"invoice 42".to_vec(),
+        };
+
+        let encrypted = encrypt_note(
+            &secret_key_of(&sender),
+            &note_public_key_from_wallet(&recipient.pubkey()).unwrap(),
+            &note,
+            &mut rand::thread_rng(),
+        )
+        .unwrap();
+        assert_eq!(encrypted.len(), MIN_NOTE_LEN + note.reference.len());
+        assert_eq!(check_encrypted_note(&encrypted), Ok(()));
+
+        // both parties can read it, nobody else
+        assert_eq!(
+            decrypt_note(&encrypted, &secret_key_of(&sender)).unwrap(),
+            note
+        );
+        assert_eq!(
+            decrypt_note(&encrypted, &secret_key_of(&recipient)).unwrap(),
+            note
+        );
+        assert_eq!(
+            decrypt_note(&encrypted, &secret_key_of(&outsider)),
+            Err(TokenError::InvalidEncryptedNote.into())
+        );
+
+        // tampering is detected, including with the keys
+        for i in [
+            0,
+            1,
+            1 + NOTE_KEY_LEN,
+            HEADER_LEN - 1,
+            HEADER_LEN,
+            encrypted.len() - 1,
+        ] {
+            let mut tampered = encrypted.clone();
+            tampered[i] ^= 1;
+            assert_eq!(
+                decrypt_note(&tampered, &secret_key_of(&recipient)),
+                Err(TokenError::InvalidEncryptedNote.into())
+            );
+        }
+
+        // notes under the same keys do not share a nonce
+        let encrypted2 = encrypt_note(
+            &secret_key_of(&sender),
+            &note_public_key_from_wallet(&recipient.pubkey()).unwrap(),
+            &note,
+            &mut rand::thread_rng(),
+        )
+        .unwrap();
+        assert_ne!(encrypted, encrypted2);
+    }
+
+    #[test]
+    fn test_check_encrypted_note() {
+        let sender_secret_key = [1; NOTE_KEY_LEN];
+        let recipient_public_key = note_public_key(&[2; NOTE_KEY_LEN]);
+        let note = DecryptedNote {
+            amount: 1,
+            reference: vec![b'x'; MAX_NOTE_REFERENCE_LEN],
+        };
+        let encrypted = encrypt_note(
+            &sender_secret_key,
+            &recipient_public_key,
+            &note,
+            &mut rand::thread_rng(),
+        )
+        .unwrap();
+        assert_eq!(encrypted.len(), MAX_NOTE_LEN);
+        assert_eq!(check_encrypted_note(&encrypted), Ok(()));
+
+        let mut too_long = encrypted.clone();
+        too_long.push(0);
+        assert_eq!(
+            check_encrypted_note(&too_long),
+            Err(TokenError::InvalidEncryptedNote.into())
+        );
+        assert_eq!(
+            check_encrypted_note(&encrypted[..MIN_NOTE_LEN - 1]),
+            Err(TokenError::InvalidEncryptedNote.into())
+        );
+        let mut unknown_version = encrypted;
+        unknown_version[0] = NOTE_VERSION + 1;
+        assert_eq!(
+            check_encrypted_note(&unknown_version),
+            Err(TokenError::InvalidEncryptedNote.into())
+        );
+
+        // references are bounded
+        let note = DecryptedNote {
+            amount: 1,
+            reference: vec![b'x'; MAX_NOTE_REFERENCE_LEN + 1],
+        };
+        assert_eq!(
+            encrypt_note(
+                &sender_secret_key,
+                &recipient_public_key,
+                &note,
+                &mut rand::thread_rng(),
+            ),
+            Err(TokenError::InvalidEncryptedNote.into())
+        );
+
+        // low-order keys are refused
+        assert_eq!(
+            encrypt_note(
+                &sender_secret_key,
+                &[0; NOTE_KEY_LEN],
+                &DecryptedNote::default(),
+                &mut rand::thread_rng(),
+            ),
+            Err(TokenError::InvalidEncryptedNote.into())
+        );
+    }
+}
@@ -2,22 +2,40 @@
 
 use crate::{
     error::{TokenError},
+    get_balance_checkpoint_address_with_seed, get_operator_approval_address_with_seed,
     instruction::{is_valid_signer_index, AuthorityType, TokenInstruction, MAX_SIGNERS},
-    state::{Account, AccountState, Mint, MintMeta, Multisig},
+    note::check_encrypted_note,
+    state::{
+        get_account_type, get_additional_metadata, get_extension, get_extension_types,
+        get_mint_meta_base, get_mint_meta_len, init_extension, init_required_account_extension,
+        pack_additional_metadata, set_extension, Account, AccountState, AccountType,
+        BalanceCheckpoint, BaseState, CpiGuard, DefaultAccountState, ExtensionType,
+        InterestBearingConfig, Mint,
+        MemoTransfer, MintCloseAuthority, MintMeta, Multisig, OperatorApproval,
+        RecurringDelegation,
+        MAX_ICON_LEN, MAX_METADATA_KEY_LEN, MAX_METADATA_VALUE_LEN, MAX_NAME_LEN, MAX_SYMBOL_LEN,
+        NonTransferable, NonTransferableAccount, PermanentDelegate, TransferFee,
+        TransferFeeAmount, TransferFeeConfig, MAX_FEE_BASIS_POINTS, TRANSFER_FEE_EPOCH_DELAY,
+    },
 };
 use num_traits::FromPrimitive;
 use put_program::{
     account_info::{next_account_info, AccountInfo},
+    clock::Clock,
     decode_error::DecodeError,
     entrypoint::ProgramResult,
     msg,
-    program::{invoke, invoke_signed},
+    program::{invoke, invoke_signed, set_return_data},
     program_error::{PrintProgramError, ProgramError},
     program_option::COption,
     program_pack::{IsInitialized, Pack},
     pubkey::Pubkey,
     system_instruction,
-    sysvar::{rent::Rent, Sysvar},
+    sysvar::{
+        instructions::{load_current_index_checked, load_instruction_at_checked},
+        rent::Rent,
+        Sysvar,
+    },
 };
 
 /// Program state handler.
@@ -39,16 +57,24 @@ impl Processor {
             return Err(TokenError::NotRentExempt.into());
         }
 
-        let mut mint_data = Mint::unpack_unchecked(&mint.data.borrow())?;
+        let mut mint_data = Mint::unpack_base_unchecked(&mint.data.borrow())?;
         if mint_data.is_initialized {
             return Err(TokenError::AlreadyInUse.into());
         }
+        // Accounts frozen by default would never be usable without a freeze authority
+        if let Some(default_account_state) =
+            get_extension::<DefaultAccountState>(&mint.data.borrow())?
+        {
+            if default_account_state.state == AccountState::Frozen && freeze_authority.is_none() {
+                return Err(TokenError::MintCannotFreeze.into());
+            }
+        }
 
         mint_data.mint_authority = COption::Some(mint_authority);
         mint_data.decimals = decimals;
         mint_data.is_initialized = true;
         mint_data.freeze_authority = freeze_authority;
-        Mint::pack(mint_data, &mut mint.data.borrow_mut())?;
+        Mint::pack_base(mint_data, &mut mint.data.borrow_mut())?;
 
         Ok(())
     }
@@ -68,7 +94,7 @@ impl Processor {
         let new_account_info_data_len = new_account_info.data_len();
         let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
 
-        let mut account = Account::unpack_unchecked(&new_account_info.data.borrow())?;
+        let mut account = Account::unpack_base_unchecked(&new_account_info.data.borrow())?;
         if account.is_initialized() {
             return Err(TokenError::AlreadyInUse.into());
         }
@@ -77,16 +103,26 @@ impl Processor {
             return Err(TokenError::NotRentExempt.into());
         }
 
-        if *mint_info.key != crate::native_mint::id() {
-            let _ = Mint::unpack(&mint_info.data.borrow_mut())
+        let (required_extensions, starting_state) = if *mint_info.key != crate::native_mint::id()
+        {
+            let mint_data = mint_info.data.borrow();
+            let _ = Mint::unpack_base(&mint_data)
                 .map_err(|_| Into::<ProgramError>::into(TokenError::InvalidMint))?;
-        }
+            let starting_state = get_extension::<DefaultAccountState>(&mint_data)?
+                .map_or(AccountState::Initialized, |extension| extension.state);
+            (
+                ExtensionType::get_required_account_extensions(&get_extension_types(&mint_data)?),
+                starting_state,
+            )
+        } else {
+            (vec![], AccountState::Initialized)
+        };
 
         account.mint = *mint_info.key;
         account.owner = *owner;
         account.delegate = COption::None;
         account.delegated_amount = 0;
-        account.state = AccountState::Initialized;
+        account.state = starting_state;
         if *mint_info.key == crate::native_mint::id() {
             let rent_exempt_reserve = rent.minimum_balance(new_account_info_data_len);
             account.is_native = COption::Some(rent_exempt_reserve);
@@ -99,7 +135,11 @@ impl Processor {
             account.amount = 0;
         };
 
-        Account::pack(account, &mut new_account_info.data.borrow_mut())?;
+        let mut new_account_data = new_account_info.data.borrow_mut();
+        Account::pack_base(account, &mut new_account_data)?;
+        for extension_type in required_extensions {
+            init_required_account_extension(&mut new_account_data, extension_type)?;
+        }
 
         Ok(())
     }
@@ -169,8 +209,8 @@ impl Processor {
         let dest_account_info = next_account_info(account_info_iter)?;
         let authority_info = next_account_info(account_info_iter)?;
 
-        let mut source_account = Account::unpack(&source_account_info.data.borrow())?;
-        let mut dest_account = Account::unpack(&dest_account_info.data.borrow())?;
+        let mut source_account = Account::unpack_base(&source_account_info.data.borrow())?;
+        let mut dest_account = Account::unpack_base(&dest_account_info.data.borrow())?;
 
         if source_account.is_frozen() || dest_account.is_frozen() {
             return Err(TokenError::AccountFrozen.into());
@@ -181,21 +221,51 @@ impl Processor {
         if source_account.mint != dest_account.mint {
             return Err(TokenError::MintMismatch.into());
         }
+        if get_extension::<NonTransferableAccount>(&source_account_info.data.borrow())?.is_some() {
+            return Err(TokenError::NonTransferable.into());
+        }
 
+        let mut fee = 0;
+        let mut permanent_delegate = COption::None;
         if let Some((mint_info, expected_decimals)) = expected_mint_info {
             if source_account.mint != *mint_info.key {
                 return Err(TokenError::MintMismatch.into());
             }
 
-            let mint = Mint::unpack(&mint_info.data.borrow_mut())?;
+            let mint_data = mint_info.data.borrow();
+            let mint = Mint::unpack_base(&mint_data)?;
             if expected_decimals != mint.decimals {
                 return Err(TokenError::MintDecimalsMismatch.into());
             }
+
+            if let Some(transfer_fee_config) = get_extension::<TransferFeeConfig>(&mint_data)? {
+                fee = transfer_fee_config.calculate_epoch_fee(Clock::get()?.epoch, amount);
+            }
+            if let Some(extension) = get_extension::<PermanentDelegate>(&mint_data)? {
+                permanent_delegate = extension.delegate;
+            }
+        } else if get_extension::<TransferFeeAmount>(&source_account_info.data.borrow())?.is_some()
+        {
+            return Err(TokenError::MintRequiredForTransfer.into());
+        }
+
+        let memo_transfer = get_extension::<MemoTransfer>(&dest_account_info.data.borrow())?;
+        if memo_transfer.map_or(false, |extension| extension.require_incoming_transfer_memos) {
+            Self::check_previous_instruction_is_memo(account_info_iter.as_slice())?;
         }
 
         let self_transfer = source_account_info.key == dest_account_info.key;
 
         match source_account.delegate {
+            // The permanent delegate is not bound by any delegated amount
+            _ if permanent_delegate == COption::Some(*authority_info.key) => {
+                Self::validate_owner(
+                    program_id,
+                    authority_info.key,
+                    authority_info,
+                    account_info_iter.as_slice(),
+                )?
+            }
             COption::Some(ref delegate) if authority_info.key == delegate => {
                 Self::validate_owner(
                     program_id,
@@ -203,6 +273,8 @@ impl Processor {
                     authority_info,
                     account_info_iter.as_slice(),
                 )?;
+                let recurring =
+                    Self::refresh_recurring_delegation(source_account_info, &mut source_account)?;
                 if source_account.delegated_amount < amount {
                     return Err(TokenError::InsufficientFunds.into());
                 }
@@ -211,17 +283,40 @@ impl Processor {
                         .delegated_amount
                         .checked_sub(amount)
                         .ok_or(TokenError::Overflow)?;
-                    if source_account.delegated_amount == 0 {
+                    // A recurring delegation outlives the allowance of a period
+                    if source_account.delegated_amount == 0 && !recurring {
                         source_account.delegate = COption::None;
                     }
                 }
             }
-            _ => Self::validate_owner(
+            // An operator acts as the owner, so the owner's CPI guard binds it too
+            _ if Self::check_operator_approval(
                 program_id,
-                &source_account.owner,
+                &source_account,
                 authority_info,
                 account_info_iter.as_slice(),
-            )?,
+                if self_transfer { 0 } else { amount },
+            )? =>
+            {
+                Self::check_cpi_guard(
+                    program_id,
+                    source_account_info,
+                    account_info_iter.as_slice(),
+                )?;
+            }
+            _ => {
+                Self::validate_owner(
+                    program_id,
+                    &source_account.owner,
+                    authority_info,
+                    account_info_iter.as_slice(),
+                )?;
+                Self::check_cpi_guard(
+                    program_id,
+                    source_account_info,
+                    account_info_iter.as_slice(),
+                )?;
+            }
         };
 
         // This check MUST occur just before the amounts are manipulated
@@ -234,9 +329,11 @@ impl Processor {
             .amount
             .checked_sub(amount)
             .ok_or(TokenError::Overflow)?;
+        // The fee stays withheld on the destination until it is harvested or withdrawn
+        let credited_amount = amount.checked_sub(fee).ok_or(TokenError::Overflow)?;
         dest_account.amount = dest_account
             .amount
-            .checked_add(amount)
+            .checked_add(credited_amount)
             .ok_or(TokenError::Overflow)?;
 
         if source_account.is_native() {
@@ -251,8 +348,66 @@ impl Processor {
                 .ok_or(TokenError::Overflow)?;
         }
 
-        Account::pack(source_account, &mut source_account_info.data.borrow_mut())?;
-        Account::pack(dest_account, &mut dest_account_info.data.borrow_mut())?;
+        Account::pack_base(source_account, &mut source_account_info.data.borrow_mut())?;
+        let mut dest_account_data = dest_account_info.data.borrow_mut();
+        Account::pack_base(dest_account, &mut dest_account_data)?;
+        if fee > 0 {
+            let mut transfer_fee_amount = get_extension::<TransferFeeAmount>(&dest_account_data)?
+                .ok_or(TokenError::ExtensionNotFound)?;
+            transfer_fee_amount.withheld_amount = transfer_fee_amount
+                .withheld_amount
+                .checked_add(fee)
+                .ok_or(TokenError::Overflow)?;
+            set_extension(&mut dest_account_data, transfer_fee_amount)?;
+        }
+
+        Ok(())
+    }
+
+    /// Processes a [BatchTransfer](enum.TokenInstruction.html) instruction.
+    pub fn process_batch_transfer(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amounts: Vec<u128>,
+        expected_decimals: Option<u8>,
+    ) -> ProgramResult {
+        if amounts.is_empty() {
+            return Err(TokenError::InvalidInstruction.into());
+        }
+        let account_info_iter = &mut accounts.iter();
+
+        let source_account_info = next_account_info(account_info_iter)?;
+        let mint_info = if expected_decimals.is_some() {
+            Some(next_account_info(account_info_iter)?)
+        } else {
+            None
+        };
+        let mut dest_account_infos = Vec::with_capacity(amounts.len());
+        for _ in 0..amounts.len() {
+            dest_account_infos.push(next_account_info(account_info_iter)?);
+        }
+        let remaining_account_infos = account_info_iter.as_slice();
+
+        // Fail up front rather than part way through the batch
+        let total = amounts.iter().try_fold(0u128, |total, amount| {
+            total.checked_add(*amount).ok_or(TokenError::Overflow)
+        })?;
+        let source_account = Account::unpack_base(&source_account_info.data.borrow())?;
+        if source_account.amount < total {
+            return Err(TokenError::InsufficientFunds.into());
+        }
+
+        // Each leg is a regular transfer, so any failure aborts the whole instruction
+        for (dest_account_info, amount) in dest_account_infos.into_iter().zip(amounts) {
+            let mut transfer_accounts = Vec::with_capacity(3 + remaining_account_infos.len());
+            transfer_accounts.push(source_account_info.clone());
+            if let Some(mint_info) = mint_info {
+                transfer_accounts.push(mint_info.clone());
+            }
+            transfer_accounts.push(dest_account_info.clone());
+            transfer_accounts.extend_from_slice(remaining_account_infos);
+            Self::process_transfer(program_id, &transfer_accounts, amount, expected_decimals)?;
+        }
 
         Ok(())
     }
@@ -263,6 +418,7 @@ impl Processor {
         accounts: &[AccountInfo],
         amount: u128,
         expected_decimals: Option<u8>,
+        period: Option<u64>,
     ) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
 
@@ -276,18 +432,21 @@ impl Processor {
         let delegate_info = next_account_info(account_info_iter)?;
         let owner_info = next_account_info(account_info_iter)?;
 
-        let mut source_account = Account::unpack(&source_account_info.data.borrow())?;
+        let mut source_account = Account::unpack_base(&source_account_info.data.borrow())?;
 
         if source_account.is_frozen() {
             return Err(TokenError::AccountFrozen.into());
         }
+        if get_extension::<NonTransferableAccount>(&source_account_info.data.borrow())?.is_some() {
+            return Err(TokenError::NonTransferable.into());
+        }
 
         if let Some((mint_info, expected_decimals)) = expected_mint_info {
             if source_account.mint != *mint_info.key {
                 return Err(TokenError::MintMismatch.into());
             }
 
-            let mint = Mint::unpack(&mint_info.data.borrow_mut())?;
+            let mint = Mint::unpack_base(&mint_info.data.borrow_mut())?;
             if expected_decimals != mint.decimals {
                 return Err(TokenError::MintDecimalsMismatch.into());
             }
@@ -299,13 +458,51 @@ impl Processor {
             owner_info,
             account_info_iter.as_slice(),
         )?;
+        Self::check_cpi_guard(program_id, source_account_info, account_info_iter.as_slice())?;
 
         source_account.delegate = COption::Some(*delegate_info.key);
         source_account.delegated_amount = amount;
 
-        Account::pack(source_account, &mut source_account_info.data.borrow_mut())?;
+        let mut source_account_data = source_account_info.data.borrow_mut();
+        Account::pack_base(source_account, &mut source_account_data)?;
 
-        Ok(())
+        // A new approval replaces any recurring delegation of the previous one
+        let period = period.unwrap_or(0);
+        let recurring_delegation = if period > 0 {
+            RecurringDelegation {
+                period,
+                period_start: Clock::get()?.unix_timestamp,
+                allowance: amount,
+            }
+        } else {
+            RecurringDelegation::default()
+        };
+        match get_extension::<RecurringDelegation>(&source_account_data)? {
+            Some(_) => set_extension(&mut source_account_data, recurring_delegation),
+            None if period > 0 => init_extension(&mut source_account_data, recurring_delegation),
+            None => Ok(()),
+        }
+    }
+
+    /// Restores the delegated amount of an account whose delegation recurs if a new period
+    /// has started, recording both the account and the period. Returns whether the
+    /// delegation recurs.
+    fn refresh_recurring_delegation(
+        source_account_info: &AccountInfo,
+        source_account: &mut Account,
+    ) -> Result<bool, ProgramError> {
+        let mut source_account_data = source_account_info.data.borrow_mut();
+        let mut recurring_delegation =
+            match get_extension::<RecurringDelegation>(&source_account_data)? {
+                Some(extension) if extension.period > 0 => extension,
+                _ => return Ok(false),
+            };
+        if recurring_delegation.roll_over(Clock::get()?.unix_timestamp) {
+            source_account.delegated_amount = recurring_delegation.allowance;
+            Account::pack_base(*source_account, &mut source_account_data)?;
+            set_extension(&mut source_account_data, recurring_delegation)?;
+        }
+        Ok(true)
     }
 
     /// Processes an [Revoke](enum.TokenInstruction.html) instruction.
@@ -313,7 +510,7 @@ impl Processor {
         let account_info_iter = &mut accounts.iter();
         let source_account_info = next_account_info(account_info_iter)?;
 
-        let mut source_account = Account::unpack(&source_account_info.data.borrow())?;
+        let mut source_account = Account::unpack_base(&source_account_info.data.borrow())?;
 
         let owner_info = next_account_info(account_info_iter)?;
 
@@ -331,7 +528,7 @@ impl Processor {
         source_account.delegate = COption::None;
         source_account.delegated_amount = 0;
 
-        Account::pack(source_account, &mut source_account_info.data.borrow_mut())?;
+        Account::pack_base(source_account, &mut source_account_info.data.borrow_mut())?;
 
         Ok(())
     }
@@ -347,8 +544,20 @@ impl Processor {
         let account_info = next_account_info(account_info_iter)?;
         let authority_info = next_account_info(account_info_iter)?;
 
-        if account_info.data_len() == Account::get_packed_len() {
-            let mut account = Account::unpack(&account_info.data.borrow())?;
+        // Mint meta accounts carrying additional metadata are longer than `MintMeta::LEN`
+        let account_type = if account_info.data_len() == MintMeta::get_packed_len()
+            || authority_type == AuthorityType::UpdateMeta
+        {
+            None
+        } else {
+            Some(
+                get_account_type(&account_info.data.borrow())
+                    .map_err(|_| ProgramError::InvalidArgument)?,
+            )
+        };
+
+        if account_type == Some(AccountType::Account) {
+            let mut account = Account::unpack_base(&account_info.data.borrow())?;
 
             if account.is_frozen() {
                 return Err(TokenError::AccountFrozen.into());
@@ -362,6 +571,7 @@ impl Processor {
                         authority_info,
                         account_info_iter.as_slice(),
                     )?;
+                    Self::check_cpi_guard(program_id, account_info, account_info_iter.as_slice())?;
 
                     if let COption::Some(authority) = new_authority {
                         account.owner = authority;
@@ -384,15 +594,22 @@ impl Processor {
                         authority_info,
                         account_info_iter.as_slice(),
                     )?;
+                    if authority == account.owner {
+                        Self::check_cpi_guard(
+                            program_id,
+                            account_info,
+                            account_info_iter.as_slice(),
+                        )?;
+                    }
                     account.close_authority = new_authority;
                 }
                 _ => {
                     return Err(TokenError::AuthorityTypeNotSupported.into());
                 }
             }
-            Account::pack(account, &mut account_info.data.borrow_mut())?;
-        } else if account_info.data_len() == Mint::get_packed_len() {
-            let mut mint = Mint::unpack(&account_info.data.borrow())?;
+            Account::pack_base(account, &mut account_info.data.borrow_mut())?;
+        } else if account_type == Some(AccountType::Mint) {
+            let mut mint = Mint::unpack_base(&account_info.data.borrow())?;
             match authority_type {
                 AuthorityType::MintTokens => {
                     // Once a mint's supply is fixed, it cannot be undone by setting a new
@@ -422,13 +639,81 @@ impl Processor {
                     )?;
                     mint.freeze_authority = new_authority;
                 }
+                AuthorityType::TransferFeeConfig | AuthorityType::WithheldWithdraw => {
+                    let mut transfer_fee_config =
+                        get_extension::<TransferFeeConfig>(&account_info.data.borrow())?
+                            .ok_or(TokenError::ExtensionNotFound)?;
+                    let authority = if authority_type == AuthorityType::TransferFeeConfig {
+                        &mut transfer_fee_config.transfer_fee_config_authority
+                    } else {
+                        &mut transfer_fee_config.withdraw_withheld_authority
+                    };
+                    let current_authority = authority
+                        .ok_or(Into::<ProgramError>::into(TokenError::NoAuthorityExists))?;
+                    Self::validate_owner(
+                        program_id,
+                        &current_authority,
+                        authority_info,
+                        account_info_iter.as_slice(),
+                    )?;
+                    *authority = new_authority;
+                    set_extension(&mut account_info.data.borrow_mut(), transfer_fee_config)?;
+                }
+                AuthorityType::InterestRate => {
+                    let mut interest_bearing_config =
+                        get_extension::<InterestBearingConfig>(&account_info.data.borrow())?
+                            .ok_or(TokenError::ExtensionNotFound)?;
+                    let rate_authority = interest_bearing_config
+                        .rate_authority
+                        .ok_or(Into::<ProgramError>::into(TokenError::NoAuthorityExists))?;
+                    Self::validate_owner(
+                        program_id,
+                        &rate_authority,
+                        authority_info,
+                        account_info_iter.as_slice(),
+                    )?;
+                    interest_bearing_config.rate_authority = new_authority;
+                    set_extension(&mut account_info.data.borrow_mut(), interest_bearing_config)?;
+                }
+                AuthorityType::PermanentDelegate => {
+                    let mut permanent_delegate =
+                        get_extension::<PermanentDelegate>(&account_info.data.borrow())?
+                            .ok_or(TokenError::ExtensionNotFound)?;
+                    let delegate = permanent_delegate
+                        .delegate
+                        .ok_or(Into::<ProgramError>::into(TokenError::NoAuthorityExists))?;
+                    Self::validate_owner(
+                        program_id,
+                        &delegate,
+                        authority_info,
+                        account_info_iter.as_slice(),
+                    )?;
+                    permanent_delegate.delegate = new_authority;
+                    set_extension(&mut account_info.data.borrow_mut(), permanent_delegate)?;
+                }
+                AuthorityType::CloseMint => {
+                    let mut mint_close_authority =
+                        get_extension::<MintCloseAuthority>(&account_info.data.borrow())?
+                            .ok_or(TokenError::ExtensionNotFound)?;
+                    let close_authority = mint_close_authority
+                        .close_authority
+                        .ok_or(Into::<ProgramError>::into(TokenError::NoAuthorityExists))?;
+                    Self::validate_owner(
+                        program_id,
+                        &close_authority,
+                        authority_info,
+                        account_info_iter.as_slice(),
+                    )?;
+                    mint_close_authority.close_authority = new_authority;
+                    set_extension(&mut account_info.data.borrow_mut(), mint_close_authority)?;
+                }
                 _ => {
                     return Err(TokenError::AuthorityTypeNotSupported.into());
                 }
             }
-            Mint::pack(mint, &mut account_info.data.borrow_mut())?;
-        } else if account_info.data_len() == MintMeta::get_packed_len() {
-            let mut mint_meta = MintMeta::unpack(&account_info.data.borrow())?;
+            Mint::pack_base(mint, &mut account_info.data.borrow_mut())?;
+        } else if account_type.is_none() {
+            let mut mint_meta = MintMeta::unpack(get_mint_meta_base(&account_info.data.borrow())?)?;
             match authority_type {
                 AuthorityType::UpdateMeta => {
                     // Once a mint's supply is fixed, it cannot be undone by setting a new
@@ -452,7 +737,10 @@ impl Processor {
                     return Err(TokenError::AuthorityTypeNotSupported.into());
                 }
             }
-            MintMeta::pack(mint_meta, &mut account_info.data.borrow_mut())?;
+            MintMeta::pack(
+                mint_meta,
+                &mut account_info.data.borrow_mut()[..MintMeta::LEN],
+            )?;
         } else {
             return Err(ProgramError::InvalidArgument);
         }
@@ -472,7 +760,7 @@ impl Processor {
         let dest_account_info = next_account_info(account_info_iter)?;
         let owner_info = next_account_info(account_info_iter)?;
 
-        let mut dest_account = Account::unpack(&dest_account_info.data.borrow())?;
+        let mut dest_account = Account::unpack_base(&dest_account_info.data.borrow())?;
         if dest_account.is_frozen() {
             return Err(TokenError::AccountFrozen.into());
         }
@@ -484,7 +772,7 @@ impl Processor {
             return Err(TokenError::MintMismatch.into());
         }
 
-        let mut mint = Mint::unpack(&mint_info.data.borrow())?;
+        let mut mint = Mint::unpack_base(&mint_info.data.borrow())?;
         if let Some(expected_decimals) = expected_decimals {
             if expected_decimals != mint.decimals {
                 return Err(TokenError::MintDecimalsMismatch.into());
@@ -511,8 +799,8 @@ impl Processor {
             .checked_add(amount)
             .ok_or(TokenError::Overflow)?;
 
-        Account::pack(dest_account, &mut dest_account_info.data.borrow_mut())?;
-        Mint::pack(mint, &mut mint_info.data.borrow_mut())?;
+        Account::pack_base(dest_account, &mut dest_account_info.data.borrow_mut())?;
+        Mint::pack_base(mint, &mut mint_info.data.borrow_mut())?;
 
         Ok(())
     }
@@ -530,8 +818,10 @@ impl Processor {
         let mint_info = next_account_info(account_info_iter)?;
         let authority_info = next_account_info(account_info_iter)?;
 
-        let mut source_account = Account::unpack(&source_account_info.data.borrow())?;
-        let mut mint = Mint::unpack(&mint_info.data.borrow())?;
+        let mut source_account = Account::unpack_base(&source_account_info.data.borrow())?;
+        let mut mint = Mint::unpack_base(&mint_info.data.borrow())?;
+        let permanent_delegate = get_extension::<PermanentDelegate>(&mint_info.data.borrow())?
+            .map_or(COption::None, |extension| extension.delegate);
 
         if source_account.is_frozen() {
             return Err(TokenError::AccountFrozen.into());
@@ -553,6 +843,14 @@ impl Processor {
         }
 
         match source_account.delegate {
+            _ if permanent_delegate == COption::Some(*authority_info.key) => {
+                Self::validate_owner(
+                    program_id,
+                    authority_info.key,
+                    authority_info,
+                    account_info_iter.as_slice(),
+                )?
+            }
             COption::Some(ref delegate) if authority_info.key == delegate => {
                 Self::validate_owner(
                     program_id,
@@ -560,6 +858,8 @@ impl Processor {
                     authority_info,
                     account_info_iter.as_slice(),
                 )?;
+                let recurring =
+                    Self::refresh_recurring_delegation(source_account_info, &mut source_account)?;
 
                 if source_account.delegated_amount < amount {
                     return Err(TokenError::InsufficientFunds.into());
@@ -568,16 +868,38 @@ impl Processor {
                     .delegated_amount
                     .checked_sub(amount)
                     .ok_or(TokenError::Overflow)?;
-                if source_account.delegated_amount == 0 {
+                if source_account.delegated_amount == 0 && !recurring {
                     source_account.delegate = COption::None;
                 }
             }
-            _ => Self::validate_owner(
+            // An operator acts as the owner, so the owner's CPI guard binds it too
+            _ if Self::check_operator_approval(
                 program_id,
-                &source_account.owner,
+                &source_account,
                 authority_info,
                 account_info_iter.as_slice(),
-            )?,
+                amount,
+            )? =>
+            {
+                Self::check_cpi_guard(
+                    program_id,
+                    source_account_info,
+                    account_info_iter.as_slice(),
+                )?;
+            }
+            _ => {
+                Self::validate_owner(
+                    program_id,
+                    &source_account.owner,
+                    authority_info,
+                    account_info_iter.as_slice(),
+                )?;
+                Self::check_cpi_guard(
+                    program_id,
+                    source_account_info,
+                    account_info_iter.as_slice(),
+                )?;
+            }
         }
 
         source_account.amount = source_account
@@ -589,8 +911,8 @@ impl Processor {
             .checked_sub(amount)
             .ok_or(TokenError::Overflow)?;
 
-        Account::pack(source_account, &mut source_account_info.data.borrow_mut())?;
-        Mint::pack(mint, &mut mint_info.data.borrow_mut())?;
+        Account::pack_base(source_account, &mut source_account_info.data.borrow_mut())?;
+        Mint::pack_base(mint, &mut mint_info.data.borrow_mut())?;
 
         Ok(())
     }
@@ -602,10 +924,28 @@ impl Processor {
         let dest_account_info = next_account_info(account_info_iter)?;
         let authority_info = next_account_info(account_info_iter)?;
 
-        let mut source_account = Account::unpack(&source_account_info.data.borrow())?;
+        let account_type = get_account_type(&source_account_info.data.borrow());
+        if let Ok(AccountType::Mint) = account_type {
+            return Self::_process_close_mint(
+                program_id,
+                source_account_info,
+                dest_account_info,
+                authority_info,
+                account_info_iter,
+            );
+        }
+
+        let mut source_account = Account::unpack_base(&source_account_info.data.borrow())?;
         if !source_account.is_native() && source_account.amount != 0 {
             return Err(TokenError::NonNativeHasBalance.into());
         }
+        if let Some(transfer_fee_amount) =
+            get_extension::<TransferFeeAmount>(&source_account_info.data.borrow())?
+        {
+            if transfer_fee_amount.withheld_amount != 0 {
+                return Err(TokenError::AccountHasWithheldTransferFees.into());
+            }
+        }
 
         let authority = source_account
             .close_authority
@@ -616,6 +956,9 @@ impl Processor {
             authority_info,
             account_info_iter.as_slice(),
         )?;
+        if authority == source_account.owner {
+            Self::check_cpi_guard(program_id, source_account_info, account_info_iter.as_slice())?;
+        }
 
         let dest_starting_lamports = dest_account_info.lamports();
         **dest_account_info.lamports.borrow_mut() = dest_starting_lamports
@@ -625,7 +968,60 @@ impl Processor {
         **source_account_info.lamports.borrow_mut() = 0;
         source_account.amount = 0;
 
-        Account::pack(source_account, &mut source_account_info.data.borrow_mut())?;
+        Account::pack_base(source_account, &mut source_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    fn _process_close_mint<'a, 'b>(
+        program_id: &Pubkey,
+        mint_info: &'a AccountInfo<'b>,
+        dest_account_info: &'a AccountInfo<'b>,
+        authority_info: &'a AccountInfo<'b>,
+        account_info_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+    ) -> ProgramResult {
+        let mint_meta_info = next_account_info(account_info_iter)?;
+
+        let mint_data = mint_info.data.borrow();
+        let mint = Mint::unpack_base(&mint_data)?;
+        if mint.supply != 0 {
+            return Err(TokenError::MintHasSupply.into());
+        }
+        let close_authority = get_extension::<MintCloseAuthority>(&mint_data)?
+            .map_or(COption::None, |extension| extension.close_authority)
+            .ok_or(Into::<ProgramError>::into(TokenError::NoAuthorityExists))?;
+        drop(mint_data);
+        Self::validate_owner(
+            program_id,
+            &close_authority,
+            authority_info,
+            account_info_iter.as_slice(),
+        )?;
+
+        let (mint_meta_key, _) =
+            Pubkey::find_program_address(&[b"MintMeta", &mint_info.key.to_bytes()], &crate::id());
+        if mint_meta_key != *mint_meta_info.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        if dest_account_info.key == mint_info.key || dest_account_info.key == mint_meta_info.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let mut dest_lamports = dest_account_info
+            .lamports()
+            .checked_add(mint_info.lamports())
+            .ok_or(TokenError::Overflow)?;
+        // The meta account may never have been created
+        if mint_meta_info.owner == program_id {
+            dest_lamports = dest_lamports
+                .checked_add(mint_meta_info.lamports())
+                .ok_or(TokenError::Overflow)?;
+            **mint_meta_info.lamports.borrow_mut() = 0;
+            mint_meta_info.data.borrow_mut().fill(0);
+        }
+        **dest_account_info.lamports.borrow_mut() = dest_lamports;
+        **mint_info.lamports.borrow_mut() = 0;
+        mint_info.data.borrow_mut().fill(0);
 
         Ok(())
     }
@@ -642,7 +1038,7 @@ impl Processor {
         let mint_info = next_account_info(account_info_iter)?;
         let authority_info = next_account_info(account_info_iter)?;
 
-        let mut source_account = Account::unpack(&source_account_info.data.borrow())?;
+        let mut source_account = Account::unpack_base(&source_account_info.data.borrow())?;
         if freeze && source_account.is_frozen() || !freeze && !source_account.is_frozen() {
             return Err(TokenError::InvalidState.into());
         }
@@ -653,7 +1049,7 @@ impl Processor {
             return Err(TokenError::MintMismatch.into());
         }
 
-        let mint = Mint::unpack(&mint_info.data.borrow_mut())?;
+        let mint = Mint::unpack_base(&mint_info.data.borrow_mut())?;
         match mint.freeze_authority {
             COption::Some(authority) => Self::validate_owner(
                 program_id,
@@ -670,7 +1066,7 @@ impl Processor {
             AccountState::Initialized
         };
 
-        Account::pack(source_account, &mut source_account_info.data.borrow_mut())?;
+        Account::pack_base(source_account, &mut source_account_info.data.borrow_mut())?;
 
         Ok(())
     }
@@ -683,7 +1079,7 @@ impl Processor {
         if native_account_info.owner != program_id {
             return Err(ProgramError::IncorrectProgramId);
         }
-        let mut native_account = Account::unpack(&native_account_info.data.borrow())?;
+        let mut native_account = Account::unpack_base(&native_account_info.data.borrow())?;
 
         if let COption::Some(rent_exempt_reserve) = native_account.is_native {
             let new_amount = native_account_info
@@ -698,7 +1094,97 @@ impl Processor {
             return Err(TokenError::NonNativeNotSupported.into());
         }
 
-        Account::pack(native_account, &mut native_account_info.data.borrow_mut())?;
+        Account::pack_base(native_account, &mut native_account_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes a [DepositNative](enum.TokenInstruction.html) instruction
+    pub fn process_deposit_native(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u128,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let native_account_info = next_account_info(account_info_iter)?;
+        let funder_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        if native_account_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let native_account = Account::unpack_base(&native_account_info.data.borrow())?;
+        if !native_account.is_native() {
+            return Err(TokenError::NonNativeNotSupported.into());
+        }
+        if native_account.is_frozen() {
+            return Err(TokenError::AccountFrozen.into());
+        }
+
+        invoke(
+            &system_instruction::transfer(funder_info.key, native_account_info.key, amount),
+            &[
+                funder_info.clone(),
+                native_account_info.clone(),
+                system_program_info.clone(),
+            ],
+        )?;
+        Self::process_sync_native(program_id, &accounts[..1])
+    }
+
+    /// Processes a [WithdrawNative](enum.TokenInstruction.html) instruction
+    pub fn process_withdraw_native(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u128,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let native_account_info = next_account_info(account_info_iter)?;
+        let dest_account_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+
+        if native_account_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        if native_account_info.key == dest_account_info.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let mut native_account = Account::unpack_base(&native_account_info.data.borrow())?;
+        if !native_account.is_native() {
+            return Err(TokenError::NonNativeNotSupported.into());
+        }
+        if native_account.is_frozen() {
+            return Err(TokenError::AccountFrozen.into());
+        }
+        Self::validate_owner(
+            program_id,
+            &native_account.owner,
+            owner_info,
+            account_info_iter.as_slice(),
+        )?;
+        Self::check_cpi_guard(
+            program_id,
+            native_account_info,
+            account_info_iter.as_slice(),
+        )?;
+
+        if native_account.amount < amount {
+            return Err(TokenError::InsufficientFunds.into());
+        }
+        native_account.amount = native_account
+            .amount
+            .checked_sub(amount)
+            .ok_or(TokenError::Overflow)?;
+
+        let native_starting_lamports = native_account_info.lamports();
+        **native_account_info.lamports.borrow_mut() = native_starting_lamports
+            .checked_sub(amount)
+            .ok_or(TokenError::Overflow)?;
+        let dest_starting_lamports = dest_account_info.lamports();
+        **dest_account_info.lamports.borrow_mut() = dest_starting_lamports
+            .checked_add(amount)
+            .ok_or(TokenError::Overflow)?;
+
+        Account::pack_base(native_account, &mut native_account_info.data.borrow_mut())?;
         Ok(())
     }
 
@@ -712,6 +1198,8 @@ impl Processor {
         let mint_meta = next_account_info(account_info_iter)?;
         let owner_info = next_account_info(account_info_iter)?;
 
+        Self::check_mint_meta_value(&symbol, MAX_SYMBOL_LEN)?;
+
         if mint_meta.owner != program_id {
             msg!("mint_meta.key:{:?}", mint_meta.key);
             msg!("mint_meta.owner:{:?}", mint_meta.owner);
@@ -719,7 +1207,8 @@ impl Processor {
             return Err(ProgramError::IncorrectProgramId);
         }
 
-        let mut mint_meta_data = MintMeta::unpack_unchecked(&mint_meta.data.borrow())?;
+        let mut mint_meta_data =
+            MintMeta::unpack_unchecked(get_mint_meta_base(&mint_meta.data.borrow())?)?;
 
         match mint_meta_data.authority {
             COption::Some(authority) => Self::validate_owner(
@@ -731,7 +1220,10 @@ impl Processor {
             COption::None => return Err(TokenError::OwnerMismatch.into()),
         }
         mint_meta_data.symbol = symbol;
-        MintMeta::pack(mint_meta_data, &mut mint_meta.data.borrow_mut())?;
+        MintMeta::pack(
+            mint_meta_data,
+            &mut mint_meta.data.borrow_mut()[..MintMeta::LEN],
+        )?;
         Ok(())
     }
 
@@ -745,6 +1237,8 @@ impl Processor {
         let mint_meta = next_account_info(account_info_iter)?;
         let owner_info = next_account_info(account_info_iter)?;
 
+        Self::check_mint_meta_value(&name, MAX_NAME_LEN)?;
+
         if mint_meta.owner != program_id {
             msg!("mint_meta.key:{:?}", mint_meta.key);
             msg!("mint_meta.owner:{:?}", mint_meta.owner);
@@ -752,7 +1246,8 @@ impl Processor {
             return Err(ProgramError::IncorrectProgramId);
         }
 
-        let mut mint_meta_data = MintMeta::unpack_unchecked(&mint_meta.data.borrow())?;
+        let mut mint_meta_data =
+            MintMeta::unpack_unchecked(get_mint_meta_base(&mint_meta.data.borrow())?)?;
 
         match mint_meta_data.authority {
             COption::Some(authority) => Self::validate_owner(
@@ -764,7 +1259,10 @@ impl Processor {
             COption::None => return Err(TokenError::OwnerMismatch.into()),
         }
         mint_meta_data.name = name;
-        MintMeta::pack(mint_meta_data, &mut mint_meta.data.borrow_mut())?;
+        MintMeta::pack(
+            mint_meta_data,
+            &mut mint_meta.data.borrow_mut()[..MintMeta::LEN],
+        )?;
         Ok(())
     }
 
@@ -778,6 +1276,8 @@ impl Processor {
         let mint_meta = next_account_info(account_info_iter)?;
         let owner_info = next_account_info(account_info_iter)?;
 
+        Self::check_mint_meta_value(&icon, MAX_ICON_LEN)?;
+
         if mint_meta.owner != program_id {
             msg!("mint_meta.key:{:?}", mint_meta.key);
             msg!("mint_meta.owner:{:?}", mint_meta.owner);
@@ -785,7 +1285,8 @@ impl Processor {
             return Err(ProgramError::IncorrectProgramId);
         }
 
-        let mut mint_meta_data = MintMeta::unpack_unchecked(&mint_meta.data.borrow())?;
+        let mut mint_meta_data =
+            MintMeta::unpack_unchecked(get_mint_meta_base(&mint_meta.data.borrow())?)?;
 
         match mint_meta_data.authority {
             COption::Some(authority) => Self::validate_owner(
@@ -797,7 +1298,10 @@ impl Processor {
             COption::None => return Err(TokenError::OwnerMismatch.into()),
         }
         mint_meta_data.icon = icon;
-        MintMeta::pack(mint_meta_data, &mut mint_meta.data.borrow_mut())?;
+        MintMeta::pack(
+            mint_meta_data,
+            &mut mint_meta.data.borrow_mut()[..MintMeta::LEN],
+        )?;
         Ok(())
     }
 
@@ -884,7 +1388,11 @@ impl Processor {
             return Err(ProgramError::InvalidSeeds);
         }
 
-        let mint_data =  Mint::unpack_unchecked(&mint.data.borrow())?;
+        Self::check_mint_meta_value(&token_symbol, MAX_SYMBOL_LEN)?;
+        Self::check_mint_meta_value(&token_name, MAX_NAME_LEN)?;
+        Self::check_mint_meta_value(&token_icon, MAX_ICON_LEN)?;
+
+        let mint_data =  Mint::unpack_base_unchecked(&mint.data.borrow())?;
 
         if !mint_data.is_initialized {
             return Err(TokenError::InvalidMint.into());
@@ -905,824 +1413,5550 @@ impl Processor {
         Ok(())
     }
 
-    /// Processes an [Instruction](enum.Instruction.html).
-    pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], input: &[u8]) -> ProgramResult {
-        let instruction = TokenInstruction::unpack(input)?;
+    /// Processes a [MigrateMintMeta](enum.TokenInstruction.html) instruction.
+    pub fn process_migrate_mint_meta(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_meta_info = next_account_info(account_info_iter)?;
 
-        match instruction {
-            TokenInstruction::InitializeMint {
-                decimals,
-                mint_authority,
-                freeze_authority,
-            } => {
-                msg!("Instruction: InitializeMint");
-                Self::process_initialize_mint(accounts, decimals, mint_authority, freeze_authority)
-            }
-            TokenInstruction::InitializeAccount => {
-                msg!("Instruction: InitializeAccount");
-                Self::process_initialize_account(accounts)
-            }
-            TokenInstruction::InitializeAccount2 { owner } => {
-                msg!("Instruction: InitializeAccount2");
-                Self::process_initialize_account2(accounts, owner)
-            }
-            TokenInstruction::InitializeMultisig { m } => {
-                msg!("Instruction: InitializeMultisig");
-                Self::process_initialize_multisig(accounts, m)
-            }
-            TokenInstruction::Transfer { amount } => {
-                msg!("Instruction: Transfer");
-                Self::process_transfer(program_id, accounts, amount, None)
-            }
-            TokenInstruction::Approve { amount } => {
-                msg!("Instruction: Approve");
-                Self::process_approve(program_id, accounts, amount, None)
-            }
-            TokenInstruction::Revoke => {
-                msg!("Instruction: Revoke");
-                Self::process_revoke(program_id, accounts)
-            }
-            TokenInstruction::SetAuthority {
-                authority_type,
-                new_authority,
-            } => {
-                msg!("Instruction: SetAuthority");
-                Self::process_set_authority(program_id, accounts, authority_type, new_authority)
-            }
-            TokenInstruction::MintTo { amount } => {
-                msg!("Instruction: MintTo");
-                Self::process_mint_to(program_id, accounts, amount, None)
-            }
-            TokenInstruction::Burn { amount } => {
-                msg!("Instruction: Burn");
-                Self::process_burn(program_id, accounts, amount, None)
-            }
-            TokenInstruction::CloseAccount => {
-                msg!("Instruction: CloseAccount");
-                Self::process_close_account(program_id, accounts)
-            }
-            TokenInstruction::FreezeAccount => {
-                msg!("Instruction: FreezeAccount");
-                Self::process_toggle_freeze_account(program_id, accounts, true)
-            }
-            TokenInstruction::ThawAccount => {
-                msg!("Instruction: ThawAccount");
-                Self::process_toggle_freeze_account(program_id, accounts, false)
-            }
-            TokenInstruction::TransferChecked { amount, decimals } => {
-                msg!("Instruction: TransferChecked");
-                Self::process_transfer(program_id, accounts, amount, Some(decimals))
-            }
-            TokenInstruction::ApproveChecked { amount, decimals } => {
-                msg!("Instruction: ApproveChecked");
-                Self::process_approve(program_id, accounts, amount, Some(decimals))
-            }
-            TokenInstruction::MintToChecked { amount, decimals } => {
-                msg!("Instruction: MintToChecked");
-                Self::process_mint_to(program_id, accounts, amount, Some(decimals))
-            }
-            TokenInstruction::BurnChecked { amount, decimals } => {
-                msg!("Instruction: BurnChecked");
-                Self::process_burn(program_id, accounts, amount, Some(decimals))
-            }
-            TokenInstruction::SyncNative => {
-                msg!("Instruction: SyncNative");
-                Self::process_sync_native(program_id, accounts)
-            }
-            TokenInstruction::UpdateSymbol { new_symbol } => {
-                msg!("Instruction: UpdateSymbol");
-                Self::process_update_symbol(program_id, accounts, new_symbol)
-            }
-            TokenInstruction::UpdateName { new_name } => {
-                msg!("Instruction: UpdateName");
-                Self::process_update_name(program_id, accounts, new_name)
-            }
-            TokenInstruction::UpdateIcon { new_icon } => {
-                msg!("Instruction: UpdateIcon");
-                Self::process_update_icon(program_id, accounts, new_icon)
-            }
-            TokenInstruction::CreateMintMetaAccount => {
-                msg!("Instruction: CreateMintInfoAcount");
-                Self::process_create_mint_meta_account(program_id, accounts)
-            }
-            TokenInstruction::InitMintMetaAccount { symbol, name, icon } => {
-                msg!("Instruction: InitializeMintMeta");
-                Self::process_init_mint_meta_account(accounts, symbol, name, icon)
-            }
+        if mint_meta_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        if !MintMeta::is_legacy_layout(&mint_meta_info.data.borrow()) {
+            return Ok(());
         }
+
+        let mint_meta = MintMeta::unpack(&mint_meta_info.data.borrow())?;
+        MintMeta::pack(mint_meta, &mut mint_meta_info.data.borrow_mut())
     }
 
-    /// Validates owner(s) are present
-    pub fn validate_owner(
+    /// Processes a [SetMintMetaField](enum.TokenInstruction.html) instruction.
+    pub fn process_set_mint_meta_field(
         program_id: &Pubkey,
-        expected_owner: &Pubkey,
-        owner_account_info: &AccountInfo,
-        signers: &[AccountInfo],
+        accounts: &[AccountInfo],
+        key: String,
+        value: String,
     ) -> ProgramResult {
-        if expected_owner != owner_account_info.key {
-            msg!("expected_owner:{}", expected_owner.to_string());
-            msg!("owner_account_info:{}", owner_account_info.key.to_string());
-            return Err(TokenError::OwnerMismatch.into());
+        let account_info_iter = &mut accounts.iter();
+        let mint_meta_info = next_account_info(account_info_iter)?;
+        let payer_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+        let authority_info = next_account_info(account_info_iter)?;
+
+        if key.is_empty() {
+            return Err(TokenError::InvalidInstruction.into());
         }
-        if program_id == owner_account_info.owner
-            && owner_account_info.data_len() == Multisig::get_packed_len()
+        if key.len() > MAX_METADATA_KEY_LEN || value.len() > MAX_METADATA_VALUE_LEN {
+            return Err(TokenError::MintMetaTooLong.into());
+        }
+        let (mint_meta, mut additional_metadata) = Self::unpack_mint_meta_with_authority(
+            program_id,
+            mint_meta_info,
+            authority_info,
+            account_info_iter.as_slice(),
+        )?;
+        mint_meta.check_values()?;
+
+        match additional_metadata
+            .iter_mut()
+            .find(|(entry_key, _)| *entry_key == key)
         {
-            let multisig = Multisig::unpack(&owner_account_info.data.borrow())?;
-            let mut num_signers = 0;
-            let mut matched = [false; MAX_SIGNERS];
-            for signer in signers.iter() {
-                for (position, key) in multisig.signers[0..multisig.n as usize].iter().enumerate() {
-                    if key == signer.key && !matched[position] {
-                        if !signer.is_signer {
-                            return Err(ProgramError::MissingRequiredSignature);
-                        }
-                        matched[position] = true;
-                        num_signers += 1;
-                    }
-                }
-            }
-            if num_signers < multisig.m {
-                return Err(ProgramError::MissingRequiredSignature);
-            }
-            return Ok(());
-        } else if !owner_account_info.is_signer {
-            return Err(ProgramError::MissingRequiredSignature);
+            Some(entry) => entry.1 = value,
+            None => additional_metadata.push((key, value)),
         }
-        Ok(())
-    }
-}
 
-impl PrintProgramError for TokenError {
-    fn print<E>(&self)
-    where
-        E: 'static + std::error::Error + DecodeError<E> + PrintProgramError + FromPrimitive,
-    {
-        match self {
-            TokenError::NotRentExempt => msg!("Error: Lamport balance below rent-exempt threshold"),
-            TokenError::InsufficientFunds => msg!("Error: insufficient funds"),
-            TokenError::InvalidMint => msg!("Error: Invalid Mint"),
-            TokenError::MintMismatch => msg!("Error: Account not associated with this Mint"),
-            TokenError::OwnerMismatch => msg!("Error: owner does not match"),
-            TokenError::FixedSupply => msg!("Error: the total supply of this token is fixed"),
-            TokenError::AlreadyInUse => msg!("Error: account or token already in use"),
-            TokenError::InvalidNumberOfProvidedSigners => {
-                msg!("Error: Invalid number of provided signers")
-            }
-            TokenError::InvalidNumberOfRequiredSigners => {
-                msg!("Error: Invalid number of required signers")
-            }
-            TokenError::UninitializedState => msg!("Error: State is uninitialized"),
-            TokenError::NativeNotSupported => {
-                msg!("Error: Instruction does not support native tokens")
-            }
-            TokenError::NonNativeHasBalance => {
-                msg!("Error: Non-native account can only be closed if its balance is zero")
-            }
-            TokenError::InvalidInstruction => msg!("Error: Invalid instruction"),
-            TokenError::InvalidState => msg!("Error: Invalid account state for operation"),
-            TokenError::Overflow => msg!("Error: Operation overflowed"),
-            TokenError::AuthorityTypeNotSupported => {
-                msg!("Error: Account does not support specified authority type")
-            }
-            TokenError::MintCannotFreeze => msg!("Error: This token mint cannot freeze accounts"),
-            TokenError::AccountFrozen => msg!("Error: Account is frozen"),
-            TokenError::MintDecimalsMismatch => {
-                msg!("Error: decimals different from the Mint decimals")
-            }
-            TokenError::NonNativeNotSupported => {
-                msg!("Error: Instruction does not support non-native tokens")
-            }
-            // TokenError::OwnerNotSign => {
-            //     msg!("Error: Instruction does not support non-native tokens")
-            // }
-            // TokenError::TokenNotinitialized => {
-            //     msg!("Error: Instruction does not support non-native tokens")
-            // }
+        let new_len = get_mint_meta_len(&additional_metadata);
+        let required_lamports = rent.minimum_balance(new_len);
+        let lamports = mint_meta_info.lamports();
+        if required_lamports > lamports {
+            invoke(
+                &system_instruction::transfer(
+                    payer_info.key,
+                    mint_meta_info.key,
+                    required_lamports - lamports,
+                ),
+                &[
+                    payer_info.clone(),
+                    mint_meta_info.clone(),
+                    system_program_info.clone(),
+                ],
+            )?;
         }
-    }
-}
+        mint_meta_info.realloc(new_len, false)?;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::instruction::*;
-    use put_program::{
-        account_info::IntoAccountInfo, clock::Epoch, instruction::Instruction, sysvar::rent,
-    };
-    use put_sdk::account::{
-        create_account_for_test, create_is_signer_account_infos, Account as PUTAccount,
-    };
+        let mut mint_meta_data = mint_meta_info.data.borrow_mut();
+        MintMeta::pack(mint_meta, &mut mint_meta_data[..MintMeta::LEN])?;
+        pack_additional_metadata(&additional_metadata, &mut mint_meta_data)
+    }
 
-    fn do_process_instruction(
-        instruction: Instruction,
-        accounts: Vec<&mut PUTAccount>,
+    /// Processes a [RemoveMintMetaField](enum.TokenInstruction.html) instruction.
+    pub fn process_remove_mint_meta_field(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        key: String,
     ) -> ProgramResult {
-        let mut meta = instruction
-            .accounts
+        let account_info_iter = &mut accounts.iter();
+        let mint_meta_info = next_account_info(account_info_iter)?;
+        let dest_account_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+        let authority_info = next_account_info(account_info_iter)?;
+
+        let (mint_meta, mut additional_metadata) = Self::unpack_mint_meta_with_authority(
+            program_id,
+            mint_meta_info,
+            authority_info,
+            account_info_iter.as_slice(),
+        )?;
+        let position = additional_metadata
             .iter()
-            .zip(accounts)
-            .map(|(account_meta, account)| (&account_meta.pubkey, account_meta.is_signer, account))
-            .collect::<Vec<_>>();
+            .position(|(entry_key, _)| *entry_key == key)
+            .ok_or(TokenError::MintMetaFieldNotFound)?;
+        additional_metadata.remove(position);
 
-        let account_infos = create_is_signer_account_infos(&mut meta);
-        Processor::process(&instruction.program_id, &account_infos, &instruction.data)
-    }
+        let new_len = get_mint_meta_len(&additional_metadata);
+        mint_meta_info.realloc(new_len, false)?;
+        {
+            let mut mint_meta_data = mint_meta_info.data.borrow_mut();
+            MintMeta::pack(mint_meta, &mut mint_meta_data[..MintMeta::LEN])?;
+            pack_additional_metadata(&additional_metadata, &mut mint_meta_data)?;
+        }
 
-    fn do_process_instruction_dups(
-        instruction: Instruction,
-        account_infos: Vec<AccountInfo>,
-    ) -> ProgramResult {
-        Processor::process(&instruction.program_id, &account_infos, &instruction.data)
+        let required_lamports = rent.minimum_balance(new_len);
+        let lamports = mint_meta_info.lamports();
+        if lamports > required_lamports {
+            let dest_starting_lamports = dest_account_info.lamports();
+            **dest_account_info.lamports.borrow_mut() = dest_starting_lamports
+                .checked_add(lamports - required_lamports)
+                .ok_or(TokenError::Overflow)?;
+            **mint_meta_info.lamports.borrow_mut() = required_lamports;
+        }
+        Ok(())
     }
 
-    fn return_token_error_as_program_error() -> ProgramError {
-        TokenError::MintMismatch.into()
+    /// Unpacks a mint meta account and its additional metadata after checking the
+    /// signature of its update authority
+    fn unpack_mint_meta_with_authority(
+        program_id: &Pubkey,
+        mint_meta_info: &AccountInfo,
+        authority_info: &AccountInfo,
+        signers: &[AccountInfo],
+    ) -> Result<(MintMeta, Vec<(String, String)>), ProgramError> {
+        if mint_meta_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mint_meta_data = mint_meta_info.data.borrow();
+        let mint_meta = MintMeta::unpack(get_mint_meta_base(&mint_meta_data)?)?;
+        let authority = mint_meta
+            .authority
+            .ok_or(Into::<ProgramError>::into(TokenError::OwnerMismatch))?;
+        Self::validate_owner(program_id, &authority, authority_info, signers)?;
+        let additional_metadata = get_additional_metadata(&mint_meta_data)?;
+        Ok((mint_meta, additional_metadata))
     }
 
-    fn rent_sysvar() -> PUTAccount {
-        create_account_for_test(&Rent::default())
+    fn check_mint_meta_value(value: &str, max_len: usize) -> ProgramResult {
+        if value.len() > max_len {
+            return Err(TokenError::MintMetaTooLong.into());
+        }
+        if value.chars().any(char::is_control) {
+            return Err(TokenError::InvalidMintMetaCharacter.into());
+        }
+        Ok(())
     }
 
-    fn mint_minimum_balance() -> u128 {
-        Rent::default().minimum_balance(Mint::get_packed_len())
-    }
+    /// Processes an [InitializeTransferFeeConfig](enum.TokenInstruction.html) instruction.
+    pub fn process_initialize_transfer_fee_config(
+        accounts: &[AccountInfo],
+        transfer_fee_config_authority: COption<Pubkey>,
+        withdraw_withheld_authority: COption<Pubkey>,
+        transfer_fee_basis_points: u16,
+        maximum_fee: u128,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_info = next_account_info(account_info_iter)?;
 
-    fn mintmeta_minimum_balance() -> u128 {
-        Rent::default().minimum_balance(MintMeta::get_packed_len())
-    }
+        let mut mint_data = mint_info.data.borrow_mut();
+        let mint = Mint::unpack_base_unchecked(&mint_data)?;
+        if mint.is_initialized {
+            return Err(TokenError::AlreadyInUse.into());
+        }
+        if transfer_fee_basis_points > MAX_FEE_BASIS_POINTS {
+            return Err(TokenError::TransferFeeExceedsMaximum.into());
+        }
 
-    fn account_minimum_balance() -> u128 {
-        Rent::default().minimum_balance(Account::get_packed_len())
+        let transfer_fee = TransferFee {
+            epoch: Clock::get()?.epoch,
+            maximum_fee,
+            transfer_fee_basis_points,
+        };
+        init_extension(
+            &mut mint_data,
+            TransferFeeConfig {
+                transfer_fee_config_authority,
+                withdraw_withheld_authority,
+                withheld_amount: 0,
+                older_transfer_fee: transfer_fee,
+                newer_transfer_fee: transfer_fee,
+            },
+        )
     }
 
-    fn multisig_minimum_balance() -> u128 {
-        Rent::default().minimum_balance(Multisig::get_packed_len())
-    }
+    /// Processes a [SetTransferFee](enum.TokenInstruction.html) instruction.
+    pub fn process_set_transfer_fee(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        transfer_fee_basis_points: u16,
+        maximum_fee: u128,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
 
-    #[test]
-    fn test_print_error() {
+        let mut mint_data = mint_info.data.borrow_mut();
+        let _ = Mint::unpack_base(&mint_data)?;
+        let mut transfer_fee_config = get_extension::<TransferFeeConfig>(&mint_data)?
+            .ok_or(TokenError::ExtensionNotFound)?;
+
+        let authority = transfer_fee_config
+            .transfer_fee_config_authority
+            .ok_or(Into::<ProgramError>::into(TokenError::NoAuthorityExists))?;
+        Self::validate_owner(
+            program_id,
+            &authority,
+            authority_info,
+            account_info_iter.as_slice(),
+        )?;
+
+        if transfer_fee_basis_points > MAX_FEE_BASIS_POINTS {
+            return Err(TokenError::TransferFeeExceedsMaximum.into());
+        }
+
+        // If the newer fee is already in effect it becomes the older one, otherwise the
+        // pending fee is simply replaced
+        let epoch = Clock::get()?.epoch;
+        if transfer_fee_config.newer_transfer_fee.epoch <= epoch {
+            transfer_fee_config.older_transfer_fee = transfer_fee_config.newer_transfer_fee;
+        }
+        transfer_fee_config.newer_transfer_fee = TransferFee {
+            epoch: epoch
+                .checked_add(TRANSFER_FEE_EPOCH_DELAY)
+                .ok_or(TokenError::Overflow)?,
+            maximum_fee,
+            transfer_fee_basis_points,
+        };
+
+        set_extension(&mut mint_data, transfer_fee_config)
+    }
+
+    /// Processes a [WithdrawWithheldTokensFromMint](enum.TokenInstruction.html) instruction.
+    pub fn process_withdraw_withheld_tokens_from_mint(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_info = next_account_info(account_info_iter)?;
+        let dest_account_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+
+        // The mint cannot also be the destination, its data is borrowed again below
+        if dest_account_info.key == mint_info.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let mut transfer_fee_config = {
+            let mint_data = mint_info.data.borrow();
+            let _ = Mint::unpack_base(&mint_data)?;
+            get_extension::<TransferFeeConfig>(&mint_data)?.ok_or(TokenError::ExtensionNotFound)?
+        };
+
+        let authority = transfer_fee_config
+            .withdraw_withheld_authority
+            .ok_or(Into::<ProgramError>::into(TokenError::NoAuthorityExists))?;
+        Self::validate_owner(
+            program_id,
+            &authority,
+            authority_info,
+            account_info_iter.as_slice(),
+        )?;
+
+        let mut dest_account = Account::unpack_base(&dest_account_info.data.borrow())?;
+        if dest_account.mint != *mint_info.key {
+            return Err(TokenError::MintMismatch.into());
+        }
+        if dest_account.is_frozen() {
+            return Err(TokenError::AccountFrozen.into());
+        }
+
+        dest_account.amount = dest_account
+            .amount
+            .checked_add(transfer_fee_config.withheld_amount)
+            .ok_or(TokenError::Overflow)?;
+        transfer_fee_config.withheld_amount = 0;
+
+        set_extension(&mut mint_info.data.borrow_mut(), transfer_fee_config)?;
+        Account::pack_base(dest_account, &mut dest_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    /// Processes a [WithdrawWithheldTokensFromAccounts](enum.TokenInstruction.html) instruction.
+    pub fn process_withdraw_withheld_tokens_from_accounts(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        num_token_accounts: u8,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_info = next_account_info(account_info_iter)?;
+        let dest_account_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let account_infos = account_info_iter.as_slice();
+        let num_signers = account_infos
+            .len()
+            .checked_sub(num_token_accounts as usize)
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let (signer_infos, source_account_infos) = account_infos.split_at(num_signers);
+
+        {
+            let mint_data = mint_info.data.borrow();
+            let _ = Mint::unpack_base(&mint_data)?;
+            let transfer_fee_config = get_extension::<TransferFeeConfig>(&mint_data)?
+                .ok_or(TokenError::ExtensionNotFound)?;
+            let authority = transfer_fee_config
+                .withdraw_withheld_authority
+                .ok_or(Into::<ProgramError>::into(TokenError::NoAuthorityExists))?;
+            Self::validate_owner(program_id, &authority, authority_info, signer_infos)?;
+        }
+
+        let mut withdrawn_amount: u128 = 0;
+        for source_account_info in source_account_infos {
+            let mut source_account_data = source_account_info.data.borrow_mut();
+            let source_account = Account::unpack_base(&source_account_data)?;
+            if source_account.mint != *mint_info.key {
+                return Err(TokenError::MintMismatch.into());
+            }
+            let mut transfer_fee_amount = get_extension::<TransferFeeAmount>(&source_account_data)?
+                .ok_or(TokenError::ExtensionNotFound)?;
+            withdrawn_amount = withdrawn_amount
+                .checked_add(transfer_fee_amount.withheld_amount)
+                .ok_or(TokenError::Overflow)?;
+            transfer_fee_amount.withheld_amount = 0;
+            set_extension(&mut source_account_data, transfer_fee_amount)?;
+        }
+
+        // The destination is unpacked last as it may also be one of the sources
+        let mut dest_account = Account::unpack_base(&dest_account_info.data.borrow())?;
+        if dest_account.mint != *mint_info.key {
+            return Err(TokenError::MintMismatch.into());
+        }
+        if dest_account.is_frozen() {
+            return Err(TokenError::AccountFrozen.into());
+        }
+        dest_account.amount = dest_account
+            .amount
+            .checked_add(withdrawn_amount)
+            .ok_or(TokenError::Overflow)?;
+        Account::pack_base(dest_account, &mut dest_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    /// Processes a [HarvestWithheldTokensToMint](enum.TokenInstruction.html) instruction.
+    pub fn process_harvest_withheld_tokens_to_mint(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_info = next_account_info(account_info_iter)?;
+
+        let mut mint_data = mint_info.data.borrow_mut();
+        let _ = Mint::unpack_base(&mint_data)?;
+        let mut transfer_fee_config = get_extension::<TransferFeeConfig>(&mint_data)?
+            .ok_or(TokenError::ExtensionNotFound)?;
+
+        for source_account_info in account_info_iter {
+            if source_account_info.owner != program_id || source_account_info.key == mint_info.key
+            {
+                msg!("Skipping {}: not a token account", source_account_info.key);
+                continue;
+            }
+            let mut source_account_data = source_account_info.data.borrow_mut();
+            match Account::unpack_base(&source_account_data) {
+                Ok(source_account) if source_account.mint == *mint_info.key => {}
+                _ => {
+                    msg!(
+                        "Skipping {}: not a token account of this mint",
+                        source_account_info.key
+                    );
+                    continue;
+                }
+            }
+            if let Some(mut transfer_fee_amount) =
+                get_extension::<TransferFeeAmount>(&source_account_data)?
+            {
+                transfer_fee_config.withheld_amount = transfer_fee_config
+                    .withheld_amount
+                    .checked_add(transfer_fee_amount.withheld_amount)
+                    .ok_or(TokenError::Overflow)?;
+                transfer_fee_amount.withheld_amount = 0;
+                set_extension(&mut source_account_data, transfer_fee_amount)?;
+            }
+        }
+
+        set_extension(&mut mint_data, transfer_fee_config)
+    }
+
+    /// Processes an [InitializeInterestBearingMint](enum.TokenInstruction.html) instruction.
+    pub fn process_initialize_interest_bearing_mint(
+        accounts: &[AccountInfo],
+        rate_authority: COption<Pubkey>,
+        rate: i16,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_info = next_account_info(account_info_iter)?;
+
+        let mut mint_data = mint_info.data.borrow_mut();
+        let mint = Mint::unpack_base_unchecked(&mint_data)?;
+        if mint.is_initialized {
+            return Err(TokenError::AlreadyInUse.into());
+        }
+
+        let timestamp = Clock::get()?.unix_timestamp;
+        init_extension(
+            &mut mint_data,
+            InterestBearingConfig {
+                rate_authority,
+                initialization_timestamp: timestamp,
+                pre_update_average_rate: rate,
+                last_update_timestamp: timestamp,
+                current_rate: rate,
+            },
+        )
+    }
+
+    /// Processes an [UpdateInterestRate](enum.TokenInstruction.html) instruction.
+    pub fn process_update_interest_rate(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        rate: i16,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+
+        let mut mint_data = mint_info.data.borrow_mut();
+        let _ = Mint::unpack_base(&mint_data)?;
+        let mut interest_bearing_config = get_extension::<InterestBearingConfig>(&mint_data)?
+            .ok_or(TokenError::ExtensionNotFound)?;
+
+        let authority = interest_bearing_config
+            .rate_authority
+            .ok_or(Into::<ProgramError>::into(TokenError::NoAuthorityExists))?;
+        Self::validate_owner(
+            program_id,
+            &authority,
+            authority_info,
+            account_info_iter.as_slice(),
+        )?;
+
+        // Fold the rate in effect so far into the average, so the interest
+        // already accrued is preserved
+        let timestamp = Clock::get()?.unix_timestamp;
+        interest_bearing_config.pre_update_average_rate = interest_bearing_config
+            .time_weighted_average_rate(timestamp)
+            .ok_or(TokenError::Overflow)?;
+        interest_bearing_config.last_update_timestamp = timestamp;
+        interest_bearing_config.current_rate = rate;
+
+        set_extension(&mut mint_data, interest_bearing_config)
+    }
+
+    /// Processes an [AmountToUiAmount](enum.TokenInstruction.html) instruction.
+    pub fn process_amount_to_ui_amount(accounts: &[AccountInfo], amount: u128) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_info = next_account_info(account_info_iter)?;
+
+        let mint_data = mint_info.data.borrow();
+        let mint = Mint::unpack_base(&mint_data)?;
+        let ui_amount = match get_extension::<InterestBearingConfig>(&mint_data)? {
+            Some(interest_bearing_config) => crate::amount_to_ui_amount_with_interest(
+                amount,
+                mint.decimals,
+                &interest_bearing_config,
+                Clock::get()?.unix_timestamp,
+            )
+            .ok_or(TokenError::Overflow)?,
+            None => crate::amount_to_ui_amount(amount, mint.decimals),
+        };
+
+        msg!("{}", ui_amount);
+        set_return_data(ui_amount.as_bytes());
+        Ok(())
+    }
+
+    /// Processes an [InitializeNonTransferableMint](enum.TokenInstruction.html) instruction.
+    pub fn process_initialize_non_transferable_mint(accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_info = next_account_info(account_info_iter)?;
+
+        let mut mint_data = mint_info.data.borrow_mut();
+        let mint = Mint::unpack_base_unchecked(&mint_data)?;
+        if mint.is_initialized {
+            return Err(TokenError::AlreadyInUse.into());
+        }
+
+        init_extension(&mut mint_data, NonTransferable)
+    }
+
+    /// Processes an [InitializePermanentDelegate](enum.TokenInstruction.html) instruction.
+    pub fn process_initialize_permanent_delegate(
+        accounts: &[AccountInfo],
+        delegate: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_info = next_account_info(account_info_iter)?;
+
+        let mut mint_data = mint_info.data.borrow_mut();
+        let mint = Mint::unpack_base_unchecked(&mint_data)?;
+        if mint.is_initialized {
+            return Err(TokenError::AlreadyInUse.into());
+        }
+
+        init_extension(
+            &mut mint_data,
+            PermanentDelegate {
+                delegate: COption::Some(delegate),
+            },
+        )
+    }
+
+    /// Processes an [InitializeDefaultAccountState](enum.TokenInstruction.html) instruction.
+    pub fn process_initialize_default_account_state(
+        accounts: &[AccountInfo],
+        state: AccountState,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_info = next_account_info(account_info_iter)?;
+
+        let mut mint_data = mint_info.data.borrow_mut();
+        let mint = Mint::unpack_base_unchecked(&mint_data)?;
+        if mint.is_initialized {
+            return Err(TokenError::AlreadyInUse.into());
+        }
+        if state == AccountState::Uninitialized {
+            return Err(TokenError::InvalidState.into());
+        }
+
+        init_extension(&mut mint_data, DefaultAccountState { state })
+    }
+
+    /// Processes an [UpdateDefaultAccountState](enum.TokenInstruction.html) instruction.
+    pub fn process_update_default_account_state(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        state: AccountState,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_info = next_account_info(account_info_iter)?;
+        let freeze_authority_info = next_account_info(account_info_iter)?;
+
+        let mut mint_data = mint_info.data.borrow_mut();
+        let mint = Mint::unpack_base(&mint_data)?;
+        let mut default_account_state = get_extension::<DefaultAccountState>(&mint_data)?
+            .ok_or(TokenError::ExtensionNotFound)?;
+
+        match mint.freeze_authority {
+            COption::Some(freeze_authority) => Self::validate_owner(
+                program_id,
+                &freeze_authority,
+                freeze_authority_info,
+                account_info_iter.as_slice(),
+            ),
+            COption::None => Err(TokenError::MintCannotFreeze.into()),
+        }?;
+        if state == AccountState::Uninitialized {
+            return Err(TokenError::InvalidState.into());
+        }
+
+        default_account_state.state = state;
+        set_extension(&mut mint_data, default_account_state)
+    }
+
+    /// Processes an [InitializeMintCloseAuthority](enum.TokenInstruction.html) instruction.
+    pub fn process_initialize_mint_close_authority(
+        accounts: &[AccountInfo],
+        close_authority: COption<Pubkey>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_info = next_account_info(account_info_iter)?;
+
+        let mut mint_data = mint_info.data.borrow_mut();
+        let mint = Mint::unpack_base_unchecked(&mint_data)?;
+        if mint.is_initialized {
+            return Err(TokenError::AlreadyInUse.into());
+        }
+
+        init_extension(&mut mint_data, MintCloseAuthority { close_authority })
+    }
+
+    /// Processes an [EnableRequiredTransferMemos](enum.TokenInstruction.html) or a
+    /// [DisableRequiredTransferMemos](enum.TokenInstruction.html) instruction.
+    pub fn process_toggle_required_transfer_memos(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        enable: bool,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let source_account_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+
+        let mut source_account_data = source_account_info.data.borrow_mut();
+        let source_account = Account::unpack_base(&source_account_data)?;
+        Self::validate_owner(
+            program_id,
+            &source_account.owner,
+            owner_info,
+            account_info_iter.as_slice(),
+        )?;
+
+        let memo_transfer = MemoTransfer {
+            require_incoming_transfer_memos: enable,
+        };
+        match get_extension::<MemoTransfer>(&source_account_data)? {
+            Some(_) => set_extension(&mut source_account_data, memo_transfer),
+            None if enable => init_extension(&mut source_account_data, memo_transfer),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks that the instruction preceding the current one in the transaction is a memo,
+    /// using the instructions sysvar found among `account_infos`
+    fn check_previous_instruction_is_memo(account_infos: &[AccountInfo]) -> ProgramResult {
+        let instructions_sysvar_info = account_infos
+            .iter()
+            .find(|account_info| put_program::sysvar::instructions::check_id(account_info.key))
+            .ok_or(TokenError::NoMemo)?;
+        let current_index = load_current_index_checked(instructions_sysvar_info)?;
+        if current_index == 0 {
+            return Err(TokenError::NoMemo.into());
+        }
+        let previous_instruction =
+            load_instruction_at_checked(current_index as usize - 1, instructions_sysvar_info)?;
+        if previous_instruction.program_id != ppl_memo::id() {
+            return Err(TokenError::NoMemo.into());
+        }
+        Ok(())
+    }
+
+    /// Processes an [EnableCpiGuard](enum.TokenInstruction.html) or a
+    /// [DisableCpiGuard](enum.TokenInstruction.html) instruction.
+    pub fn process_toggle_cpi_guard(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        enable: bool,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let source_account_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+
+        let source_account = Account::unpack_base(&source_account_info.data.borrow())?;
+        Self::validate_owner(
+            program_id,
+            &source_account.owner,
+            owner_info,
+            account_info_iter.as_slice(),
+        )?;
+        // A program holding the owner's signature must not be able to lift the guard
+        if !enable {
+            Self::check_cpi_guard(program_id, source_account_info, account_info_iter.as_slice())?;
+        }
+
+        let mut source_account_data = source_account_info.data.borrow_mut();
+        let cpi_guard = CpiGuard { lock_cpi: enable };
+        match get_extension::<CpiGuard>(&source_account_data)? {
+            Some(_) => set_extension(&mut source_account_data, cpi_guard),
+            None if enable => init_extension(&mut source_account_data, cpi_guard),
+            None => Ok(()),
+        }
+    }
+
+    /// Fails if the account has its CPI guard enabled and the current instruction is not
+    /// a top-level instruction of this program, according to the instructions sysvar found
+    /// among `account_infos`. Without the sysvar, the instruction is assumed to be a CPI.
+    fn check_cpi_guard(
+        program_id: &Pubkey,
+        account_info: &AccountInfo,
+        account_infos: &[AccountInfo],
+    ) -> ProgramResult {
+        let cpi_guard = get_extension::<CpiGuard>(&account_info.data.borrow())?;
+        if !cpi_guard.map_or(false, |extension| extension.lock_cpi) {
+            return Ok(());
+        }
+        Self::check_top_level(program_id, account_infos)
+    }
+
+    /// Fails unless the current instruction is a top-level instruction of this program,
+    /// according to the instructions sysvar found among `account_infos`. Without the
+    /// sysvar, the instruction is assumed to be a CPI.
+    fn check_top_level(program_id: &Pubkey, account_infos: &[AccountInfo]) -> ProgramResult {
+        let instructions_sysvar_info = account_infos
+            .iter()
+            .find(|info| put_program::sysvar::instructions::check_id(info.key))
+            .ok_or(TokenError::CpiGuardBlocked)?;
+        let current_index = load_current_index_checked(instructions_sysvar_info)?;
+        let current_instruction =
+            load_instruction_at_checked(current_index as usize, instructions_sysvar_info)?;
+        // This program never invokes itself, so the top-level instruction only names it
+        // when it is being executed directly
+        if current_instruction.program_id != *program_id {
+            return Err(TokenError::CpiGuardBlocked.into());
+        }
+        Ok(())
+    }
+
+    /// Processes a [TransferWithEncryptedNote](enum.TokenInstruction.html) instruction.
+    pub fn process_transfer_with_encrypted_note(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u128,
+        decimals: u8,
+        note: &[u8],
+    ) -> ProgramResult {
+        check_encrypted_note(note)?;
+        Self::process_transfer(program_id, accounts, amount, Some(decimals))
+    }
+
+    /// Processes a [RecordBalanceCheckpoint](enum.TokenInstruction.html) instruction.
+    pub fn process_record_balance_checkpoint(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let payer_info = next_account_info(account_info_iter)?;
+        let checkpoint_info = next_account_info(account_info_iter)?;
+        let source_account_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+        let owner_info = next_account_info(account_info_iter)?;
+
+        if source_account_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let source_account = Account::unpack_base(&source_account_info.data.borrow())?;
+        Self::validate_owner(
+            program_id,
+            &source_account.owner,
+            owner_info,
+            account_info_iter.as_slice(),
+        )?;
+
+        let clock = Clock::get()?;
+        let (checkpoint_address, bump_seed) =
+            get_balance_checkpoint_address_with_seed(source_account_info.key, clock.epoch);
+        if checkpoint_address != *checkpoint_info.key {
+            msg!("Error: balance checkpoint address does not match seed derivation");
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        if checkpoint_info.data_is_empty() {
+            let signer_seeds: &[&[_]] = &[
+                br"Checkpoint",
+                &source_account_info.key.to_bytes(),
+                &clock.epoch.to_le_bytes(),
+                &[bump_seed],
+            ];
+            let required_lamports = rent.minimum_balance(BalanceCheckpoint::LEN);
+            let lamports = checkpoint_info.lamports();
+            if required_lamports > lamports {
+                invoke(
+                    &system_instruction::transfer(
+                        payer_info.key,
+                        checkpoint_info.key,
+                        required_lamports - lamports,
+                    ),
+                    &[
+                        payer_info.clone(),
+                        checkpoint_info.clone(),
+                        system_program_info.clone(),
+                    ],
+                )?;
+            }
+            invoke_signed(
+                &system_instruction::allocate(checkpoint_info.key, BalanceCheckpoint::LEN as u64),
+                &[checkpoint_info.clone(), system_program_info.clone()],
+                &[signer_seeds],
+            )?;
+            invoke_signed(
+                &system_instruction::assign(checkpoint_info.key, program_id),
+                &[checkpoint_info.clone(), system_program_info.clone()],
+                &[signer_seeds],
+            )?;
+        } else if checkpoint_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        BalanceCheckpoint::pack(
+            BalanceCheckpoint {
+                is_initialized: true,
+                mint: source_account.mint,
+                owner: source_account.owner,
+                account: *source_account_info.key,
+                epoch: clock.epoch,
+                slot: clock.slot,
+                amount: source_account.amount,
+            },
+            &mut checkpoint_info.data.borrow_mut(),
+        )
+    }
+
+    /// Processes a [Reallocate](enum.TokenInstruction.html) instruction.
+    pub fn process_reallocate(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        new_extension_types: &[ExtensionType],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let token_account_info = next_account_info(account_info_iter)?;
+        let payer_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+        let owner_info = next_account_info(account_info_iter)?;
+
+        if token_account_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let (token_account, mut extension_types) = {
+            let token_account_data = token_account_info.data.borrow();
+            (
+                Account::unpack_base(&token_account_data)?,
+                get_extension_types(&token_account_data)?,
+            )
+        };
+        // The rent-exempt reserve of a native account is fixed at initialization
+        if token_account.is_native() {
+            return Err(TokenError::NativeNotSupported.into());
+        }
+        Self::validate_owner(
+            program_id,
+            &token_account.owner,
+            owner_info,
+            account_info_iter.as_slice(),
+        )?;
+
+        for extension_type in new_extension_types {
+            if extension_type.get_account_type() != AccountType::Account {
+                msg!("Error: {:?} is not an account extension", extension_type);
+                return Err(ProgramError::InvalidArgument);
+            }
+            if !extension_types.contains(extension_type) {
+                extension_types.push(*extension_type);
+            }
+        }
+        let new_len = ExtensionType::get_account_len::<Account>(&extension_types);
+        if new_len <= token_account_info.data_len() {
+            return Ok(());
+        }
+
+        let required_lamports = rent.minimum_balance(new_len);
+        let lamports = token_account_info.lamports();
+        if required_lamports > lamports {
+            invoke(
+                &system_instruction::transfer(
+                    payer_info.key,
+                    token_account_info.key,
+                    required_lamports - lamports,
+                ),
+                &[
+                    payer_info.clone(),
+                    token_account_info.clone(),
+                    system_program_info.clone(),
+                ],
+            )?;
+        }
+        token_account_info.realloc(new_len, false)?;
+
+        // A plain account becomes an extended one, which records its account type
+        Account::pack_base(token_account, &mut token_account_info.data.borrow_mut())
+    }
+
+    /// Processes an [ApproveOperator](enum.TokenInstruction.html) instruction.
+    pub fn process_approve_operator(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        limit: Option<u128>,
+        period: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let payer_info = next_account_info(account_info_iter)?;
+        let operator_approval_info = next_account_info(account_info_iter)?;
+        let mint_info = next_account_info(account_info_iter)?;
+        let operator_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+        let owner_info = next_account_info(account_info_iter)?;
+
+        if mint_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        Mint::unpack_base(&mint_info.data.borrow())?;
+        Self::validate_owner(
+            program_id,
+            owner_info.key,
+            owner_info,
+            account_info_iter.as_slice(),
+        )?;
+        // The approval reaches every account of the owner, including those whose CPI guard
+        // keeps a program holding the owner's signature from acting on them
+        Self::check_top_level(program_id, account_info_iter.as_slice())?;
+
+        let (operator_approval_address, bump_seed) = get_operator_approval_address_with_seed(
+            mint_info.key,
+            owner_info.key,
+            operator_info.key,
+        );
+        if operator_approval_address != *operator_approval_info.key {
+            msg!("Error: operator approval address does not match seed derivation");
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        if operator_approval_info.data_is_empty() {
+            let signer_seeds: &[&[_]] = &[
+                br"Operator",
+                &mint_info.key.to_bytes(),
+                &owner_info.key.to_bytes(),
+                &operator_info.key.to_bytes(),
+                &[bump_seed],
+            ];
+            let required_lamports = rent.minimum_balance(OperatorApproval::LEN);
+            let lamports = operator_approval_info.lamports();
+            if required_lamports > lamports {
+                invoke(
+                    &system_instruction::transfer(
+                        payer_info.key,
+                        operator_approval_info.key,
+                        required_lamports - lamports,
+                    ),
+                    &[
+                        payer_info.clone(),
+                        operator_approval_info.clone(),
+                        system_program_info.clone(),
+                    ],
+                )?;
+            }
+            invoke_signed(
+                &system_instruction::allocate(
+                    operator_approval_info.key,
+                    OperatorApproval::LEN as u64,
+                ),
+                &[operator_approval_info.clone(), system_program_info.clone()],
+                &[signer_seeds],
+            )?;
+            invoke_signed(
+                &system_instruction::assign(operator_approval_info.key, program_id),
+                &[operator_approval_info.clone(), system_program_info.clone()],
+                &[signer_seeds],
+            )?;
+        } else if operator_approval_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        OperatorApproval::pack(
+            OperatorApproval {
+                is_initialized: true,
+                mint: *mint_info.key,
+                owner: *owner_info.key,
+                operator: *operator_info.key,
+                limit: limit.map_or(COption::None, COption::Some),
+                period,
+                period_start: Clock::get()?.unix_timestamp,
+                spent: 0,
+            },
+            &mut operator_approval_info.data.borrow_mut(),
+        )
+    }
+
+    /// Processes a [RevokeOperator](enum.TokenInstruction.html) instruction.
+    pub fn process_revoke_operator(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let operator_approval_info = next_account_info(account_info_iter)?;
+        let dest_account_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+
+        if operator_approval_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        if operator_approval_info.key == dest_account_info.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let operator_approval = OperatorApproval::unpack(&operator_approval_info.data.borrow())?;
+        Self::validate_owner(
+            program_id,
+            &operator_approval.owner,
+            owner_info,
+            account_info_iter.as_slice(),
+        )?;
+
+        let dest_starting_lamports = dest_account_info.lamports();
+        **dest_account_info.lamports.borrow_mut() = dest_starting_lamports
+            .checked_add(operator_approval_info.lamports())
+            .ok_or(TokenError::Overflow)?;
+        **operator_approval_info.lamports.borrow_mut() = 0;
+        operator_approval_info.data.borrow_mut().fill(0);
+        Ok(())
+    }
+
+    /// Checks whether the authority acts as an operator approved by the owner of the source
+    /// account, using the operator approval account found among `account_infos`, and records
+    /// `amount` tokens against the limit of the approval. Returns `false` if no approval of the
+    /// authority was supplied, so that the authority is checked as the owner instead.
+    fn check_operator_approval(
+        program_id: &Pubkey,
+        source_account: &Account,
+        authority_info: &AccountInfo,
+        account_infos: &[AccountInfo],
+        amount: u128,
+    ) -> Result<bool, ProgramError> {
+        if *authority_info.key == source_account.owner {
+            return Ok(false);
+        }
+        let operator_approval_address = get_operator_approval_address_with_seed(
+            &source_account.mint,
+            &source_account.owner,
+            authority_info.key,
+        )
+        .0;
+        let operator_approval_info = match account_infos
+            .iter()
+            .find(|info| *info.key == operator_approval_address)
+        {
+            Some(operator_approval_info) => operator_approval_info,
+            None => return Ok(false),
+        };
+        if operator_approval_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        Self::validate_owner(
+            program_id,
+            authority_info.key,
+            authority_info,
+            account_infos,
+        )?;
+
+        let mut operator_approval =
+            OperatorApproval::unpack(&operator_approval_info.data.borrow())?;
+        operator_approval.spend(amount, Clock::get()?.unix_timestamp)?;
+        OperatorApproval::pack(
+            operator_approval,
+            &mut operator_approval_info.data.borrow_mut(),
+        )?;
+        Ok(true)
+    }
+
+    /// Processes an [Instruction](enum.Instruction.html).
+    pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], input: &[u8]) -> ProgramResult {
+        let instruction = TokenInstruction::unpack(input)?;
+
+        match instruction {
+            TokenInstruction::InitializeMint {
+                decimals,
+                mint_authority,
+                freeze_authority,
+            } => {
+                msg!("Instruction: InitializeMint");
+                Self::process_initialize_mint(accounts, decimals, mint_authority, freeze_authority)
+            }
+            TokenInstruction::InitializeAccount => {
+                msg!("Instruction: InitializeAccount");
+                Self::process_initialize_account(accounts)
+            }
+            TokenInstruction::InitializeAccount2 { owner } => {
+                msg!("Instruction: InitializeAccount2");
+                Self::process_initialize_account2(accounts, owner)
+            }
+            TokenInstruction::InitializeMultisig { m } => {
+                msg!("Instruction: InitializeMultisig");
+                Self::process_initialize_multisig(accounts, m)
+            }
+            TokenInstruction::Transfer { amount } => {
+                msg!("Instruction: Transfer");
+                Self::process_transfer(program_id, accounts, amount, None)
+            }
+            TokenInstruction::Approve { amount } => {
+                msg!("Instruction: Approve");
+                Self::process_approve(program_id, accounts, amount, None, None)
+            }
+            TokenInstruction::Revoke => {
+                msg!("Instruction: Revoke");
+                Self::process_revoke(program_id, accounts)
+            }
+            TokenInstruction::SetAuthority {
+                authority_type,
+                new_authority,
+            } => {
+                msg!("Instruction: SetAuthority");
+                Self::process_set_authority(program_id, accounts, authority_type, new_authority)
+            }
+            TokenInstruction::MintTo { amount } => {
+                msg!("Instruction: MintTo");
+                Self::process_mint_to(program_id, accounts, amount, None)
+            }
+            TokenInstruction::Burn { amount } => {
+                msg!("Instruction: Burn");
+                Self::process_burn(program_id, accounts, amount, None)
+            }
+            TokenInstruction::CloseAccount => {
+                msg!("Instruction: CloseAccount");
+                Self::process_close_account(program_id, accounts)
+            }
+            TokenInstruction::FreezeAccount => {
+                msg!("Instruction: FreezeAccount");
+                Self::process_toggle_freeze_account(program_id, accounts, true)
+            }
+            TokenInstruction::ThawAccount => {
+                msg!("Instruction: ThawAccount");
+                Self::process_toggle_freeze_account(program_id, accounts, false)
+            }
+            TokenInstruction::TransferChecked { amount, decimals } => {
+                msg!("Instruction: TransferChecked");
+                Self::process_transfer(program_id, accounts, amount, Some(decimals))
+            }
+            TokenInstruction::ApproveChecked {
+                amount,
+                decimals,
+                period,
+            } => {
+                msg!("Instruction: ApproveChecked");
+                Self::process_approve(program_id, accounts, amount, Some(decimals), period)
+            }
+            TokenInstruction::MintToChecked { amount, decimals } => {
+                msg!("Instruction: MintToChecked");
+                Self::process_mint_to(program_id, accounts, amount, Some(decimals))
+            }
+            TokenInstruction::BurnChecked { amount, decimals } => {
+                msg!("Instruction: BurnChecked");
+                Self::process_burn(program_id, accounts, amount, Some(decimals))
+            }
+            TokenInstruction::SyncNative => {
+                msg!("Instruction: SyncNative");
+                Self::process_sync_native(program_id, accounts)
+            }
+            TokenInstruction::UpdateSymbol { new_symbol } => {
+                msg!("Instruction: UpdateSymbol");
+                Self::process_update_symbol(program_id, accounts, new_symbol)
+            }
+            TokenInstruction::UpdateName { new_name } => {
+                msg!("Instruction: UpdateName");
+                Self::process_update_name(program_id, accounts, new_name)
+            }
+            TokenInstruction::UpdateIcon { new_icon } => {
+                msg!("Instruction: UpdateIcon");
+                Self::process_update_icon(program_id, accounts, new_icon)
+            }
+            TokenInstruction::CreateMintMetaAccount => {
+                msg!("Instruction: CreateMintInfoAcount");
+                Self::process_create_mint_meta_account(program_id, accounts)
+            }
+            TokenInstruction::InitMintMetaAccount { symbol, name, icon } => {
+                msg!("Instruction: InitializeMintMeta");
+                Self::process_init_mint_meta_account(accounts, symbol, name, icon)
+            }
+            TokenInstruction::InitializeTransferFeeConfig {
+                transfer_fee_config_authority,
+                withdraw_withheld_authority,
+                transfer_fee_basis_points,
+                maximum_fee,
+            } => {
+                msg!("Instruction: InitializeTransferFeeConfig");
+                Self::process_initialize_transfer_fee_config(
+                    accounts,
+                    transfer_fee_config_authority,
+                    withdraw_withheld_authority,
+                    transfer_fee_basis_points,
+                    maximum_fee,
+                )
+            }
+            TokenInstruction::SetTransferFee {
+                transfer_fee_basis_points,
+                maximum_fee,
+            } => {
+                msg!("Instruction: SetTransferFee");
+                Self::process_set_transfer_fee(
+                    program_id,
+                    accounts,
+                    transfer_fee_basis_points,
+                    maximum_fee,
+                )
+            }
+            TokenInstruction::WithdrawWithheldTokensFromMint => {
+                msg!("Instruction: WithdrawWithheldTokensFromMint");
+                Self::process_withdraw_withheld_tokens_from_mint(program_id, accounts)
+            }
+            TokenInstruction::WithdrawWithheldTokensFromAccounts { num_token_accounts } => {
+                msg!("Instruction: WithdrawWithheldTokensFromAccounts");
+                Self::process_withdraw_withheld_tokens_from_accounts(
+                    program_id,
+                    accounts,
+                    num_token_accounts,
+                )
+            }
+            TokenInstruction::HarvestWithheldTokensToMint => {
+                msg!("Instruction: HarvestWithheldTokensToMint");
+                Self::process_harvest_withheld_tokens_to_mint(program_id, accounts)
+            }
+            TokenInstruction::InitializeInterestBearingMint {
+                rate_authority,
+                rate,
+            } => {
+                msg!("Instruction: InitializeInterestBearingMint");
+                Self::process_initialize_interest_bearing_mint(accounts, rate_authority, rate)
+            }
+            TokenInstruction::UpdateInterestRate { rate } => {
+                msg!("Instruction: UpdateInterestRate");
+                Self::process_update_interest_rate(program_id, accounts, rate)
+            }
+            TokenInstruction::AmountToUiAmount { amount } => {
+                msg!("Instruction: AmountToUiAmount");
+                Self::process_amount_to_ui_amount(accounts, amount)
+            }
+            TokenInstruction::InitializeNonTransferableMint => {
+                msg!("Instruction: InitializeNonTransferableMint");
+                Self::process_initialize_non_transferable_mint(accounts)
+            }
+            TokenInstruction::InitializePermanentDelegate { delegate } => {
+                msg!("Instruction: InitializePermanentDelegate");
+                Self::process_initialize_permanent_delegate(accounts, delegate)
+            }
+            TokenInstruction::InitializeDefaultAccountState { state } => {
+                msg!("Instruction: InitializeDefaultAccountState");
+                Self::process_initialize_default_account_state(accounts, state)
+            }
+            TokenInstruction::UpdateDefaultAccountState { state } => {
+                msg!("Instruction: UpdateDefaultAccountState");
+                Self::process_update_default_account_state(program_id, accounts, state)
+            }
+            TokenInstruction::InitializeMintCloseAuthority { close_authority } => {
+                msg!("Instruction: InitializeMintCloseAuthority");
+                Self::process_initialize_mint_close_authority(accounts, close_authority)
+            }
+            TokenInstruction::EnableRequiredTransferMemos => {
+                msg!("Instruction: EnableRequiredTransferMemos");
+                Self::process_toggle_required_transfer_memos(program_id, accounts, true)
+            }
+            TokenInstruction::DisableRequiredTransferMemos => {
+                msg!("Instruction: DisableRequiredTransferMemos");
+                Self::process_toggle_required_transfer_memos(program_id, accounts, false)
+            }
+            TokenInstruction::EnableCpiGuard => {
+                msg!("Instruction: EnableCpiGuard");
+                Self::process_toggle_cpi_guard(program_id, accounts, true)
+            }
+            TokenInstruction::DisableCpiGuard => {
+                msg!("Instruction: DisableCpiGuard");
+                Self::process_toggle_cpi_guard(program_id, accounts, false)
+            }
+            TokenInstruction::MigrateMintMeta => {
+                msg!("Instruction: MigrateMintMeta");
+                Self::process_migrate_mint_meta(program_id, accounts)
+            }
+            TokenInstruction::SetMintMetaField { key, value } => {
+                msg!("Instruction: SetMintMetaField");
+                Self::process_set_mint_meta_field(program_id, accounts, key, value)
+            }
+            TokenInstruction::RemoveMintMetaField { key } => {
+                msg!("Instruction: RemoveMintMetaField");
+                Self::process_remove_mint_meta_field(program_id, accounts, key)
+            }
+            TokenInstruction::BatchTransfer { amounts } => {
+                msg!("Instruction: BatchTransfer");
+                Self::process_batch_transfer(program_id, accounts, amounts, None)
+            }
+            TokenInstruction::BatchTransferChecked { amounts, decimals } => {
+                msg!("Instruction: BatchTransferChecked");
+                Self::process_batch_transfer(program_id, accounts, amounts, Some(decimals))
+            }
+            TokenInstruction::RecordBalanceCheckpoint => {
+                msg!("Instruction: RecordBalanceCheckpoint");
+                Self::process_record_balance_checkpoint(program_id, accounts)
+            }
+            TokenInstruction::TransferWithEncryptedNote {
+                amount,
+                decimals,
+                note,
+            } => {
+                msg!("Instruction: TransferWithEncryptedNote");
+                Self::process_transfer_with_encrypted_note(
+                    program_id, accounts, amount, decimals, &note,
+                )
+            }
+            TokenInstruction::Reallocate { extension_types } => {
+                msg!("Instruction: Reallocate");
+                Self::process_reallocate(program_id, accounts, &extension_types)
+            }
+            TokenInstruction::ApproveOperator { limit, period } => {
+                msg!("Instruction: ApproveOperator");
+                Self::process_approve_operator(program_id, accounts, limit, period)
+            }
+            TokenInstruction::RevokeOperator => {
+                msg!("Instruction: RevokeOperator");
+                Self::process_revoke_operator(program_id, accounts)
+            }
+            TokenInstruction::DepositNative { amount } => {
+                msg!("Instruction: DepositNative");
+                Self::process_deposit_native(program_id, accounts, amount)
+            }
+            TokenInstruction::WithdrawNative { amount } => {
+                msg!("Instruction: WithdrawNative");
+                Self::process_withdraw_native(program_id, accounts, amount)
+            }
+        }
+    }
+
+    /// Validates owner(s) are present
+    pub fn validate_owner(
+        program_id: &Pubkey,
+        expected_owner: &Pubkey,
+        owner_account_info: &AccountInfo,
+        signers: &[AccountInfo],
+    ) -> ProgramResult {
+        if expected_owner != owner_account_info.key {
+            msg!("expected_owner:{}", expected_owner.to_string());
+            msg!("owner_account_info:{}", owner_account_info.key.to_string());
+            return Err(TokenError::OwnerMismatch.into());
+        }
+        if program_id == owner_account_info.owner
+            && owner_account_info.data_len() == Multisig::get_packed_len()
+        {
+            let multisig = Multisig::unpack(&owner_account_info.data.borrow())?;
+            let mut num_signers = 0;
+            let mut matched = [false; MAX_SIGNERS];
+            for signer in signers.iter() {
+                for (position, key) in multisig.signers[0..multisig.n as usize].iter().enumerate() {
+                    if key == signer.key && !matched[position] {
+                        if !signer.is_signer {
+                            return Err(ProgramError::MissingRequiredSignature);
+                        }
+                        matched[position] = true;
+                        num_signers += 1;
+                    }
+                }
+            }
+            if num_signers < multisig.m {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            return Ok(());
+        } else if !owner_account_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        Ok(())
+    }
+}
+
+impl PrintProgramError for TokenError {
+    fn print<E>(&self)
+    where
+        E: 'static + std::error::Error + DecodeError<E> + PrintProgramError + FromPrimitive,
+    {
+        match self {
+            TokenError::NotRentExempt => msg!("Error: Lamport balance below rent-exempt threshold"),
+            TokenError::InsufficientFunds => msg!("Error: insufficient funds"),
+            TokenError::InvalidMint => msg!("Error: Invalid Mint"),
+            TokenError::MintMismatch => msg!("Error: Account not associated with this Mint"),
+            TokenError::OwnerMismatch => msg!("Error: owner does not match"),
+            TokenError::FixedSupply => msg!("Error: the total supply of this token is fixed"),
+            TokenError::AlreadyInUse => msg!("Error: account or token already in use"),
+            TokenError::InvalidNumberOfProvidedSigners => {
+                msg!("Error: Invalid number of provided signers")
+            }
+            TokenError::InvalidNumberOfRequiredSigners => {
+                msg!("Error: Invalid number of required signers")
+            }
+            TokenError::UninitializedState => msg!("Error: State is uninitialized"),
+            TokenError::NativeNotSupported => {
+                msg!("Error: Instruction does not support native tokens")
+            }
+            TokenError::NonNativeHasBalance => {
+                msg!("Error: Non-native account can only be closed if its balance is zero")
+            }
+            TokenError::InvalidInstruction => msg!("Error: Invalid instruction"),
+            TokenError::InvalidState => msg!("Error: Invalid account state for operation"),
+            TokenError::Overflow => msg!("Error: Operation overflowed"),
+            TokenError::AuthorityTypeNotSupported => {
+                msg!("Error: Account does not support specified authority type")
+            }
+            TokenError::MintCannotFreeze => msg!("Error: This token mint cannot freeze accounts"),
+            TokenError::AccountFrozen => msg!("Error: Account is frozen"),
+            TokenError::MintDecimalsMismatch => {
+                msg!("Error: decimals different from the Mint decimals")
+            }
+            TokenError::NonNativeNotSupported => {
+                msg!("Error: Instruction does not support non-native tokens")
+            }
+            TokenError::TransferFeeExceedsMaximum => {
+                msg!("Error: Transfer fee exceeds maximum of 10,000 basis points")
+            }
+            TokenError::MintRequiredForTransfer => {
+                msg!("Error: Mint required for this account to transfer tokens, use `transfer_checked`")
+            }
+            TokenError::AccountHasWithheldTransferFees => {
+                msg!("Error: Account has withheld transfer fees")
+            }
+            TokenError::NoAuthorityExists => {
+                msg!("Error: No authority exists to perform the desired operation")
+            }
+            TokenError::ExtensionNotFound => {
+                msg!("Error: Mint or account does not carry the required extension")
+            }
+            TokenError::NonTransferable => {
+                msg!("Error: Transfers are disabled for this mint")
+            }
+            TokenError::MintHasSupply => msg!("Error: Mint has a non-zero supply"),
+            TokenError::NoMemo => msg!(
+                "Error: No memo in previous instruction; required for recipient to receive a transfer"
+            ),
+            TokenError::CpiGuardBlocked => {
+                msg!("Error: Owner action blocked by the account's CPI guard")
+            }
+            TokenError::MintMetaTooLong => {
+                msg!("Error: Mint meta symbol, name or icon is too long")
+            }
+            TokenError::InvalidMintMetaCharacter => {
+                msg!("Error: Mint meta symbol, name or icon contains a control character")
+            }
+            TokenError::MintMetaFieldNotFound => msg!("Error: Mint meta field not found"),
+            TokenError::InvalidEncryptedNote => msg!("Error: Invalid encrypted note"),
+            TokenError::OperatorLimitExceeded => msg!("Error: Operator approval limit exceeded"),
+            // TokenError::OwnerNotSign => {
+            //     msg!("Error: Instruction does not support non-native tokens")
+            // }
+            // TokenError::TokenNotinitialized => {
+            //     msg!("Error: Instruction does not support non-native tokens")
+            // }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::*;
+    use crate::{
+        get_balance_checkpoint_address, get_operator_approval_address,
+        note::{decrypt_note, encrypt_note, note_public_key, DecryptedNote, MAX_NOTE_LEN},
+        state::find_balance_checkpoint,
+    };
+    use put_program::{
+        account_info::IntoAccountInfo,
+        clock::Epoch,
+        instruction::{AccountMeta, BorrowedAccountMeta, BorrowedInstruction, Instruction},
+        sysvar::{
+            instructions::{construct_instructions_data, store_current_index},
+            rent,
+        },
+    };
+    use put_sdk::account::{
+        create_account_for_test, create_is_signer_account_infos, Account as PUTAccount,
+    };
+    use std::sync::Once;
+
+    fn do_process_instruction(
+        instruction: Instruction,
+        accounts: Vec<&mut PUTAccount>,
+    ) -> ProgramResult {
+        let mut meta = instruction
+            .accounts
+            .iter()
+            .zip(accounts)
+            .map(|(account_meta, account)| (&account_meta.pubkey, account_meta.is_signer, account))
+            .collect::<Vec<_>>();
+
+        let account_infos = create_is_signer_account_infos(&mut meta);
+        Processor::process(&instruction.program_id, &account_infos, &instruction.data)
+    }
+
+    fn do_process_instruction_dups(
+        instruction: Instruction,
+        account_infos: Vec<AccountInfo>,
+    ) -> ProgramResult {
+        Processor::process(&instruction.program_id, &account_infos, &instruction.data)
+    }
+
+    fn return_token_error_as_program_error() -> ProgramError {
+        TokenError::MintMismatch.into()
+    }
+
+    fn rent_sysvar() -> PUTAccount {
+        create_account_for_test(&Rent::default())
+    }
+
+    fn mint_minimum_balance() -> u128 {
+        Rent::default().minimum_balance(Mint::get_packed_len())
+    }
+
+    fn mintmeta_minimum_balance() -> u128 {
+        Rent::default().minimum_balance(MintMeta::get_packed_len())
+    }
+
+    fn account_minimum_balance() -> u128 {
+        Rent::default().minimum_balance(Account::get_packed_len())
+    }
+
+    fn multisig_minimum_balance() -> u128 {
+        Rent::default().minimum_balance(Multisig::get_packed_len())
+    }
+
+    #[test]
+    fn test_print_error() {
         let error = return_token_error_as_program_error();
         error.print::<TokenError>();
     }
 
-    #[test]
-    #[should_panic(expected = "Custom(3)")]
-    fn test_error_unwrap() {
-        Err::<(), ProgramError>(return_token_error_as_program_error()).unwrap();
+    #[test]
+    #[should_panic(expected = "Custom(3)")]
+    fn test_error_unwrap() {
+        Err::<(), ProgramError>(return_token_error_as_program_error()).unwrap();
+    }
+
+    #[test]
+    fn test_unique_account_sizes() {
+        assert_ne!(Mint::get_packed_len(), 0);
+        assert_ne!(Mint::get_packed_len(), Account::get_packed_len());
+        assert_ne!(Mint::get_packed_len(), Multisig::get_packed_len());
+        assert_ne!(Account::get_packed_len(), 0);
+        assert_ne!(Account::get_packed_len(), Multisig::get_packed_len());
+        assert_ne!(Multisig::get_packed_len(), 0);
+        assert_ne!(BalanceCheckpoint::get_packed_len(), Mint::get_packed_len());
+        assert_ne!(
+            BalanceCheckpoint::get_packed_len(),
+            Account::get_packed_len()
+        );
+        assert_ne!(
+            BalanceCheckpoint::get_packed_len(),
+            Multisig::get_packed_len()
+        );
+    }
+
+    #[test]
+    fn test_pack_unpack() {
+        // Mint
+        let check = Mint {
+            mint_authority: COption::Some(Pubkey::new(&[1; 32])),
+            supply: 42,
+            decimals: 7,
+            is_initialized: true,
+            freeze_authority: COption::Some(Pubkey::new(&[2; 32])),
+        };
+
+        let mut packed = vec![0; Mint::get_packed_len() + 1];
+        assert_eq!(
+            Err(ProgramError::InvalidAccountData),
+            Mint::pack(check, &mut packed)
+        );
+        let mut packed = vec![0; Mint::get_packed_len() - 1];
+        assert_eq!(
+            Err(ProgramError::InvalidAccountData),
+            Mint::pack(check, &mut packed)
+        );
+        let mut packed = vec![0; Mint::get_packed_len()];
+        Mint::pack(check, &mut packed).unwrap();
+        let expect = vec![
+            1, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+            1, 1, 1, 1, 1, 1, 1, 42, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 7, 1, 1, 0, 0, 0,
+            2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2,
+            2, 2, 2,
+        ];
+        assert_eq!(packed, expect);
+        let unpacked = Mint::unpack(&packed).unwrap();
+        assert_eq!(unpacked, check);
+
+        // Account
+        let check = Account {
+            mint: Pubkey::new(&[1; 32]),
+            owner: Pubkey::new(&[2; 32]),
+            amount: 3,
+            delegate: COption::Some(Pubkey::new(&[4; 32])),
+            state: AccountState::Frozen,
+            is_native: COption::Some(5),
+            delegated_amount: 6,
+            close_authority: COption::Some(Pubkey::new(&[7; 32])),
+        };
+        let mut packed = vec![0; Account::get_packed_len() + 1];
+        assert_eq!(
+            Err(ProgramError::InvalidAccountData),
+            Account::pack(check, &mut packed)
+        );
+        let mut packed = vec![0; Account::get_packed_len() - 1];
+        assert_eq!(
+            Err(ProgramError::InvalidAccountData),
+            Account::pack(check, &mut packed)
+        );
+        let mut packed = vec![0; Account::get_packed_len()];
+        Account::pack(check, &mut packed).unwrap();
+        let expect = vec![
+            1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+            1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2,
+            2, 2, 2, 2, 2, 2, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 4, 4, 4,
+            4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4,
+            2, 1, 0, 0, 0, 5, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 6, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7,
+            7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7,
+        ];
+        assert_eq!(packed, expect);
+        let unpacked = Account::unpack(&packed).unwrap();
+        assert_eq!(unpacked, check);
+
+        // Multisig
+        let check = Multisig {
+            m: 1,
+            n: 2,
+            is_initialized: true,
+            signers: [Pubkey::new(&[3; 32]); MAX_SIGNERS],
+        };
+        let mut packed = vec![0; Multisig::get_packed_len() + 1];
+        assert_eq!(
+            Err(ProgramError::InvalidAccountData),
+            Multisig::pack(check, &mut packed)
+        );
+        let mut packed = vec![0; Multisig::get_packed_len() - 1];
+        assert_eq!(
+            Err(ProgramError::InvalidAccountData),
+            Multisig::pack(check, &mut packed)
+        );
+        let mut packed = vec![0; Multisig::get_packed_len()];
+        Multisig::pack(check, &mut packed).unwrap();
+        let expect = vec![
+            1, 2, 1, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
+            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
+            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
+            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
+            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
+            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
+            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
+            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
+            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
+            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
+            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
+            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
+            3, 3, 3, 3, 3, 3, 3,
+        ];
+        assert_eq!(packed, expect);
+        let unpacked = Multisig::unpack(&packed).unwrap();
+        assert_eq!(unpacked, check);
+
+        // BalanceCheckpoint
+        let check = BalanceCheckpoint {
+            is_initialized: true,
+            mint: Pubkey::new(&[1; 32]),
+            owner: Pubkey::new(&[2; 32]),
+            epoch: 3,
+            slot: 4,
+            amount: 5,
+        };
+        let mut packed = vec![0; BalanceCheckpoint::get_packed_len() + 1];
+        assert_eq!(
+            Err(ProgramError::InvalidAccountData),
+            BalanceCheckpoint::pack(check, &mut packed)
+        );
+        let mut packed = vec![0; BalanceCheckpoint::get_packed_len()];
+        BalanceCheckpoint::pack(check, &mut packed).unwrap();
+        let mut expect = vec![1];
+        expect.extend_from_slice(&[1; 32]);
+        expect.extend_from_slice(&[2; 32]);
+        expect.extend_from_slice(&3u64.to_le_bytes());
+        expect.extend_from_slice(&4u64.to_le_bytes());
+        expect.extend_from_slice(&5u128.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = BalanceCheckpoint::unpack(&packed).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn test_initialize_mint() {
+        let program_id = crate::id();
+        let owner_key = Pubkey::new_unique();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account = PUTAccount::new(42, Mint::get_packed_len(), &program_id);
+        let mint2_key = Pubkey::new_unique();
+        let mut mint2_account =
+            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mut rent_sysvar = rent_sysvar();
+
+        // mint is not rent exempt
+        assert_eq!(
+            Err(TokenError::NotRentExempt.into()),
+            do_process_instruction(
+                initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+                vec![&mut mint_account, &mut rent_sysvar]
+            )
+        );
+
+        mint_account.lamports = mint_minimum_balance();
+
+        // create new mint
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
+        )
+        .unwrap();
+
+        // create twice
+        assert_eq!(
+            Err(TokenError::AlreadyInUse.into()),
+            do_process_instruction(
+                initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+                vec![&mut mint_account, &mut rent_sysvar]
+            )
+        );
+
+        // create another mint that can freeze
+        do_process_instruction(
+            initialize_mint(&program_id, &mint2_key, &owner_key, Some(&owner_key), 2).unwrap(),
+            vec![&mut mint2_account, &mut rent_sysvar],
+        )
+        .unwrap();
+        let mint = Mint::unpack_unchecked(&mint2_account.data).unwrap();
+        assert_eq!(mint.freeze_authority, COption::Some(owner_key));
+    }
+
+    #[test]
+    fn test_initialize_mint_account() {
+        let program_id = crate::id();
+        let account_key = Pubkey::new_unique();
+        let mut account_account = PUTAccount::new(42, Account::get_packed_len(), &program_id);
+        let owner_key = Pubkey::new_unique();
+        let mut owner_account = PUTAccount::default();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mut rent_sysvar = rent_sysvar();
+
+        // account is not rent exempt
+        assert_eq!(
+            Err(TokenError::NotRentExempt.into()),
+            do_process_instruction(
+                initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut mint_account,
+                    &mut owner_account,
+                    &mut rent_sysvar
+                ],
+            )
+        );
+
+        account_account.lamports = account_minimum_balance();
+
+        // mint is not valid (not initialized)
+        assert_eq!(
+            Err(TokenError::InvalidMint.into()),
+            do_process_instruction(
+                initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut mint_account,
+                    &mut owner_account,
+                    &mut rent_sysvar
+                ],
+            )
+        );
+
+        // create mint
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
+        )
+        .unwrap();
+
+        // create account
+        do_process_instruction(
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+
+        // create twice
+        assert_eq!(
+            Err(TokenError::AlreadyInUse.into()),
+            do_process_instruction(
+                initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut mint_account,
+                    &mut owner_account,
+                    &mut rent_sysvar
+                ],
+            )
+        );
+    }
+
+    #[test]
+    fn test_transfer_dups() {
+        let program_id = crate::id();
+        let account1_key = Pubkey::new_unique();
+        let mut account1_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let mut account1_info: AccountInfo = (&account1_key, true, &mut account1_account).into();
+        let account2_key = Pubkey::new_unique();
+        let mut account2_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let mut account2_info: AccountInfo = (&account2_key, false, &mut account2_account).into();
+        let account3_key = Pubkey::new_unique();
+        let mut account3_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account3_info: AccountInfo = (&account3_key, false, &mut account3_account).into();
+        let account4_key = Pubkey::new_unique();
+        let mut account4_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account4_info: AccountInfo = (&account4_key, true, &mut account4_account).into();
+        let multisig_key = Pubkey::new_unique();
+        let mut multisig_account = PUTAccount::new(
+            multisig_minimum_balance(),
+            Multisig::get_packed_len(),
+            &program_id,
+        );
+        let multisig_info: AccountInfo = (&multisig_key, true, &mut multisig_account).into();
+        let owner_key = Pubkey::new_unique();
+        let mut owner_account = PUTAccount::default();
+        let owner_info: AccountInfo = (&owner_key, true, &mut owner_account).into();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mint_info: AccountInfo = (&mint_key, false, &mut mint_account).into();
+        let rent_key = rent::id();
+        let mut rent_sysvar = rent_sysvar();
+        let rent_info: AccountInfo = (&rent_key, false, &mut rent_sysvar).into();
+
+        // create mint
+        do_process_instruction_dups(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![mint_info.clone(), rent_info.clone()],
+        )
+        .unwrap();
+
+        // create account
+        do_process_instruction_dups(
+            initialize_account(&program_id, &account1_key, &mint_key, &account1_key).unwrap(),
+            vec![
+                account1_info.clone(),
+                mint_info.clone(),
+                account1_info.clone(),
+                rent_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // create another account
+        do_process_instruction_dups(
+            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                account2_info.clone(),
+                mint_info.clone(),
+                owner_info.clone(),
+                rent_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // mint to account
+        do_process_instruction_dups(
+            mint_to(&program_id, &mint_key, &account1_key, &owner_key, &[], 1000).unwrap(),
+            vec![mint_info.clone(), account1_info.clone(), owner_info.clone()],
+        )
+        .unwrap();
+
+        // source-owner transfer
+        do_process_instruction_dups(
+            transfer(
+                &program_id,
+                &account1_key,
+                &account2_key,
+                &account1_key,
+                &[],
+                500,
+            )
+            .unwrap(),
+            vec![
+                account1_info.clone(),
+                account2_info.clone(),
+                account1_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // source-owner TransferChecked
+        do_process_instruction_dups(
+            transfer_checked(
+                &program_id,
+                &account1_key,
+                &mint_key,
+                &account2_key,
+                &account1_key,
+                &[],
+                500,
+                2,
+            )
+            .unwrap(),
+            vec![
+                account1_info.clone(),
+                mint_info.clone(),
+                account2_info.clone(),
+                account1_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // source-delegate transfer
+        let mut account = Account::unpack_unchecked(&account1_info.data.borrow()).unwrap();
+        account.amount = 1000;
+        account.delegated_amount = 1000;
+        account.delegate = COption::Some(account1_key);
+        account.owner = owner_key;
+        Account::pack(account, &mut account1_info.data.borrow_mut()).unwrap();
+
+        do_process_instruction_dups(
+            transfer(
+                &program_id,
+                &account1_key,
+                &account2_key,
+                &account1_key,
+                &[],
+                500,
+            )
+            .unwrap(),
+            vec![
+                account1_info.clone(),
+                account2_info.clone(),
+                account1_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // source-delegate TransferChecked
+        do_process_instruction_dups(
+            transfer_checked(
+                &program_id,
+                &account1_key,
+                &mint_key,
+                &account2_key,
+                &account1_key,
+                &[],
+                500,
+                2,
+            )
+            .unwrap(),
+            vec![
+                account1_info.clone(),
+                mint_info.clone(),
+                account2_info.clone(),
+                account1_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // test destination-owner transfer
+        do_process_instruction_dups(
+            initialize_account(&program_id, &account3_key, &mint_key, &account2_key).unwrap(),
+            vec![
+                account3_info.clone(),
+                mint_info.clone(),
+                account2_info.clone(),
+                rent_info.clone(),
+            ],
+        )
+        .unwrap();
+        do_process_instruction_dups(
+            mint_to(&program_id, &mint_key, &account3_key, &owner_key, &[], 1000).unwrap(),
+            vec![mint_info.clone(), account3_info.clone(), owner_info.clone()],
+        )
+        .unwrap();
+
+        account1_info.is_signer = false;
+        account2_info.is_signer = true;
+        do_process_instruction_dups(
+            transfer(
+                &program_id,
+                &account3_key,
+                &account2_key,
+                &account2_key,
+                &[],
+                500,
+            )
+            .unwrap(),
+            vec![
+                account3_info.clone(),
+                account2_info.clone(),
+                account2_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // destination-owner TransferChecked
+        do_process_instruction_dups(
+            transfer_checked(
+                &program_id,
+                &account3_key,
+                &mint_key,
+                &account2_key,
+                &account2_key,
+                &[],
+                500,
+                2,
+            )
+            .unwrap(),
+            vec![
+                account3_info.clone(),
+                mint_info.clone(),
+                account2_info.clone(),
+                account2_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // test source-multisig signer
+        do_process_instruction_dups(
+            initialize_multisig(&program_id, &multisig_key, &[&account4_key], 1).unwrap(),
+            vec![
+                multisig_info.clone(),
+                rent_info.clone(),
+                account4_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        do_process_instruction_dups(
+            initialize_account(&program_id, &account4_key, &mint_key, &multisig_key).unwrap(),
+            vec![
+                account4_info.clone(),
+                mint_info.clone(),
+                multisig_info.clone(),
+                rent_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        do_process_instruction_dups(
+            mint_to(&program_id, &mint_key, &account4_key, &owner_key, &[], 1000).unwrap(),
+            vec![mint_info.clone(), account4_info.clone(), owner_info.clone()],
+        )
+        .unwrap();
+
+        // source-multisig-signer transfer
+        do_process_instruction_dups(
+            transfer(
+                &program_id,
+                &account4_key,
+                &account2_key,
+                &multisig_key,
+                &[&account4_key],
+                500,
+            )
+            .unwrap(),
+            vec![
+                account4_info.clone(),
+                account2_info.clone(),
+                multisig_info.clone(),
+                account4_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // source-multisig-signer TransferChecked
+        do_process_instruction_dups(
+            transfer_checked(
+                &program_id,
+                &account4_key,
+                &mint_key,
+                &account2_key,
+                &multisig_key,
+                &[&account4_key],
+                500,
+                2,
+            )
+            .unwrap(),
+            vec![
+                account4_info.clone(),
+                mint_info.clone(),
+                account2_info.clone(),
+                multisig_info.clone(),
+                account4_info.clone(),
+            ],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_transfer() {
+        let program_id = crate::id();
+        let account_key = Pubkey::new_unique();
+        let mut account_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account2_key = Pubkey::new_unique();
+        let mut account2_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account3_key = Pubkey::new_unique();
+        let mut account3_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let delegate_key = Pubkey::new_unique();
+        let mut delegate_account = PUTAccount::default();
+        let mismatch_key = Pubkey::new_unique();
+        let mut mismatch_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let owner_key = Pubkey::new_unique();
+        let mut owner_account = PUTAccount::default();
+        let owner2_key = Pubkey::new_unique();
+        let mut owner2_account = PUTAccount::default();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mint2_key = Pubkey::new_unique();
+        let mut rent_sysvar = rent_sysvar();
+
+        // create mint
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
+        )
+        .unwrap();
+
+        // create account
+        do_process_instruction(
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+
+        // create another account
+        do_process_instruction(
+            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account2_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+
+        // create another account
+        do_process_instruction(
+            initialize_account(&program_id, &account3_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account3_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+
+        // create mismatch account
+        do_process_instruction(
+            initialize_account(&program_id, &mismatch_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut mismatch_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+        let mut account = Account::unpack_unchecked(&mismatch_account.data).unwrap();
+        account.mint = mint2_key;
+        Account::pack(account, &mut mismatch_account.data).unwrap();
+
+        // mint to account
+        do_process_instruction(
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1000).unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
+        )
+        .unwrap();
+
+        // missing signer
+        let mut instruction = transfer(
+            &program_id,
+            &account_key,
+            &account2_key,
+            &owner_key,
+            &[],
+            1000,
+        )
+        .unwrap();
+        instruction.accounts[2].is_signer = false;
+        assert_eq!(
+            Err(ProgramError::MissingRequiredSignature),
+            do_process_instruction(
+                instruction,
+                vec![
+                    &mut account_account,
+                    &mut account2_account,
+                    &mut owner_account,
+                ],
+            )
+        );
+
+        // mismatch mint
+        assert_eq!(
+            Err(TokenError::MintMismatch.into()),
+            do_process_instruction(
+                transfer(
+                    &program_id,
+                    &account_key,
+                    &mismatch_key,
+                    &owner_key,
+                    &[],
+                    1000
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut mismatch_account,
+                    &mut owner_account,
+                ],
+            )
+        );
+
+        // missing owner
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                transfer(
+                    &program_id,
+                    &account_key,
+                    &account2_key,
+                    &owner2_key,
+                    &[],
+                    1000
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut account2_account,
+                    &mut owner2_account,
+                ],
+            )
+        );
+
+        // transfer
+        do_process_instruction(
+            transfer(
+                &program_id,
+                &account_key,
+                &account2_key,
+                &owner_key,
+                &[],
+                1000,
+            )
+            .unwrap(),
+            vec![
+                &mut account_account,
+                &mut account2_account,
+                &mut owner_account,
+            ],
+        )
+        .unwrap();
+
+        // insufficient funds
+        assert_eq!(
+            Err(TokenError::InsufficientFunds.into()),
+            do_process_instruction(
+                transfer(&program_id, &account_key, &account2_key, &owner_key, &[], 1).unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut account2_account,
+                    &mut owner_account,
+                ],
+            )
+        );
+
+        // transfer half back
+        do_process_instruction(
+            transfer(
+                &program_id,
+                &account2_key,
+                &account_key,
+                &owner_key,
+                &[],
+                500,
+            )
+            .unwrap(),
+            vec![
+                &mut account2_account,
+                &mut account_account,
+                &mut owner_account,
+            ],
+        )
+        .unwrap();
+
+        // incorrect decimals
+        assert_eq!(
+            Err(TokenError::MintDecimalsMismatch.into()),
+            do_process_instruction(
+                transfer_checked(
+                    &program_id,
+                    &account2_key,
+                    &mint_key,
+                    &account_key,
+                    &owner_key,
+                    &[],
+                    1,
+                    10 // <-- incorrect decimals
+                )
+                .unwrap(),
+                vec![
+                    &mut account2_account,
+                    &mut mint_account,
+                    &mut account_account,
+                    &mut owner_account,
+                ],
+            )
+        );
+
+        // incorrect mint
+        assert_eq!(
+            Err(TokenError::MintMismatch.into()),
+            do_process_instruction(
+                transfer_checked(
+                    &program_id,
+                    &account2_key,
+                    &account3_key, // <-- incorrect mint
+                    &account_key,
+                    &owner_key,
+                    &[],
+                    1,
+                    2
+                )
+                .unwrap(),
+                vec![
+                    &mut account2_account,
+                    &mut account3_account, // <-- incorrect mint
+                    &mut account_account,
+                    &mut owner_account,
+                ],
+            )
+        );
+        // transfer rest with explicit decimals
+        do_process_instruction(
+            transfer_checked(
+                &program_id,
+                &account2_key,
+                &mint_key,
+                &account_key,
+                &owner_key,
+                &[],
+                500,
+                2,
+            )
+            .unwrap(),
+            vec![
+                &mut account2_account,
+                &mut mint_account,
+                &mut account_account,
+                &mut owner_account,
+            ],
+        )
+        .unwrap();
+
+        // insufficient funds
+        assert_eq!(
+            Err(TokenError::InsufficientFunds.into()),
+            do_process_instruction(
+                transfer(&program_id, &account2_key, &account_key, &owner_key, &[], 1).unwrap(),
+                vec![
+                    &mut account2_account,
+                    &mut account_account,
+                    &mut owner_account,
+                ],
+            )
+        );
+
+        // approve delegate
+        do_process_instruction(
+            approve(
+                &program_id,
+                &account_key,
+                &delegate_key,
+                &owner_key,
+                &[],
+                100,
+            )
+            .unwrap(),
+            vec![
+                &mut account_account,
+                &mut delegate_account,
+                &mut owner_account,
+            ],
+        )
+        .unwrap();
+
+        // transfer via delegate
+        do_process_instruction(
+            transfer(
+                &program_id,
+                &account_key,
+                &account2_key,
+                &delegate_key,
+                &[],
+                100,
+            )
+            .unwrap(),
+            vec![
+                &mut account_account,
+                &mut account2_account,
+                &mut delegate_account,
+            ],
+        )
+        .unwrap();
+
+        // insufficient funds approved via delegate
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                transfer(
+                    &program_id,
+                    &account_key,
+                    &account2_key,
+                    &delegate_key,
+                    &[],
+                    100
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut account2_account,
+                    &mut delegate_account,
+                ],
+            )
+        );
+
+        // transfer rest
+        do_process_instruction(
+            transfer(
+                &program_id,
+                &account_key,
+                &account2_key,
+                &owner_key,
+                &[],
+                900,
+            )
+            .unwrap(),
+            vec![
+                &mut account_account,
+                &mut account2_account,
+                &mut owner_account,
+            ],
+        )
+        .unwrap();
+
+        // approve delegate
+        do_process_instruction(
+            approve(
+                &program_id,
+                &account_key,
+                &delegate_key,
+                &owner_key,
+                &[],
+                100,
+            )
+            .unwrap(),
+            vec![
+                &mut account_account,
+                &mut delegate_account,
+                &mut owner_account,
+            ],
+        )
+        .unwrap();
+
+        // insufficient funds in source account via delegate
+        assert_eq!(
+            Err(TokenError::InsufficientFunds.into()),
+            do_process_instruction(
+                transfer(
+                    &program_id,
+                    &account_key,
+                    &account2_key,
+                    &delegate_key,
+                    &[],
+                    100
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut account2_account,
+                    &mut delegate_account,
+                ],
+            )
+        );
+    }
+
+    #[test]
+    fn test_self_transfer() {
+        let program_id = crate::id();
+        let account_key = Pubkey::new_unique();
+        let mut account_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account2_key = Pubkey::new_unique();
+        let mut account2_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account3_key = Pubkey::new_unique();
+        let mut account3_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let delegate_key = Pubkey::new_unique();
+        let mut delegate_account = PUTAccount::default();
+        let owner_key = Pubkey::new_unique();
+        let mut owner_account = PUTAccount::default();
+        let owner2_key = Pubkey::new_unique();
+        let mut owner2_account = PUTAccount::default();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mut rent_sysvar = rent_sysvar();
+
+        // create mint
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
+        )
+        .unwrap();
+
+        // create account
+        do_process_instruction(
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+
+        // create another account
+        do_process_instruction(
+            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account2_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+
+        // create another account
+        do_process_instruction(
+            initialize_account(&program_id, &account3_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account3_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+
+        // mint to account
+        do_process_instruction(
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1000).unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
+        )
+        .unwrap();
+
+        let account_info = (&account_key, false, &mut account_account).into_account_info();
+        let account3_info = (&account3_key, false, &mut account3_account).into_account_info();
+        let delegate_info = (&delegate_key, true, &mut delegate_account).into_account_info();
+        let owner_info = (&owner_key, true, &mut owner_account).into_account_info();
+        let owner2_info = (&owner2_key, true, &mut owner2_account).into_account_info();
+        let mint_info = (&mint_key, false, &mut mint_account).into_account_info();
+
+        // transfer
+        let instruction = transfer(
+            &program_id,
+            account_info.key,
+            account_info.key,
+            owner_info.key,
+            &[],
+            1000,
+        )
+        .unwrap();
+        assert_eq!(
+            Ok(()),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    account_info.clone(),
+                    owner_info.clone(),
+                ],
+                &instruction.data,
+            )
+        );
+        // no balance change...
+        let account = Account::unpack_unchecked(&account_info.try_borrow_data().unwrap()).unwrap();
+        assert_eq!(account.amount, 1000);
+
+        // transfer checked
+        let instruction = transfer_checked(
+            &program_id,
+            account_info.key,
+            mint_info.key,
+            account_info.key,
+            owner_info.key,
+            &[],
+            1000,
+            2,
+        )
+        .unwrap();
+        assert_eq!(
+            Ok(()),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    mint_info.clone(),
+                    account_info.clone(),
+                    owner_info.clone(),
+                ],
+                &instruction.data,
+            )
+        );
+        // no balance change...
+        let account = Account::unpack_unchecked(&account_info.try_borrow_data().unwrap()).unwrap();
+        assert_eq!(account.amount, 1000);
+
+        // missing signer
+        let mut owner_no_sign_info = owner_info.clone();
+        let mut instruction = transfer(
+            &program_id,
+            account_info.key,
+            account_info.key,
+            owner_no_sign_info.key,
+            &[],
+            1000,
+        )
+        .unwrap();
+        instruction.accounts[2].is_signer = false;
+        owner_no_sign_info.is_signer = false;
+        assert_eq!(
+            Err(ProgramError::MissingRequiredSignature),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    account_info.clone(),
+                    owner_no_sign_info.clone(),
+                ],
+                &instruction.data,
+            )
+        );
+
+        // missing signer checked
+        let mut instruction = transfer_checked(
+            &program_id,
+            account_info.key,
+            mint_info.key,
+            account_info.key,
+            owner_no_sign_info.key,
+            &[],
+            1000,
+            2,
+        )
+        .unwrap();
+        instruction.accounts[3].is_signer = false;
+        assert_eq!(
+            Err(ProgramError::MissingRequiredSignature),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    mint_info.clone(),
+                    account_info.clone(),
+                    owner_no_sign_info,
+                ],
+                &instruction.data,
+            )
+        );
+
+        // missing owner
+        let instruction = transfer(
+            &program_id,
+            account_info.key,
+            account_info.key,
+            owner2_info.key,
+            &[],
+            1000,
+        )
+        .unwrap();
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    account_info.clone(),
+                    owner2_info.clone(),
+                ],
+                &instruction.data,
+            )
+        );
+
+        // missing owner checked
+        let instruction = transfer_checked(
+            &program_id,
+            account_info.key,
+            mint_info.key,
+            account_info.key,
+            owner2_info.key,
+            &[],
+            1000,
+            2,
+        )
+        .unwrap();
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    mint_info.clone(),
+                    account_info.clone(),
+                    owner2_info.clone(),
+                ],
+                &instruction.data,
+            )
+        );
+
+        // insufficient funds
+        let instruction = transfer(
+            &program_id,
+            account_info.key,
+            account_info.key,
+            owner_info.key,
+            &[],
+            1001,
+        )
+        .unwrap();
+        assert_eq!(
+            Err(TokenError::InsufficientFunds.into()),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    account_info.clone(),
+                    owner_info.clone(),
+                ],
+                &instruction.data,
+            )
+        );
+
+        // insufficient funds checked
+        let instruction = transfer_checked(
+            &program_id,
+            account_info.key,
+            mint_info.key,
+            account_info.key,
+            owner_info.key,
+            &[],
+            1001,
+            2,
+        )
+        .unwrap();
+        assert_eq!(
+            Err(TokenError::InsufficientFunds.into()),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    mint_info.clone(),
+                    account_info.clone(),
+                    owner_info.clone(),
+                ],
+                &instruction.data,
+            )
+        );
+
+        // incorrect decimals
+        let instruction = transfer_checked(
+            &program_id,
+            account_info.key,
+            mint_info.key,
+            account_info.key,
+            owner_info.key,
+            &[],
+            1,
+            10, // <-- incorrect decimals
+        )
+        .unwrap();
+        assert_eq!(
+            Err(TokenError::MintDecimalsMismatch.into()),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    mint_info.clone(),
+                    account_info.clone(),
+                    owner_info.clone(),
+                ],
+                &instruction.data,
+            )
+        );
+
+        // incorrect mint
+        let instruction = transfer_checked(
+            &program_id,
+            account_info.key,
+            account3_info.key, // <-- incorrect mint
+            account_info.key,
+            owner_info.key,
+            &[],
+            1,
+            2,
+        )
+        .unwrap();
+        assert_eq!(
+            Err(TokenError::MintMismatch.into()),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    account3_info.clone(), // <-- incorrect mint
+                    account_info.clone(),
+                    owner_info.clone(),
+                ],
+                &instruction.data,
+            )
+        );
+
+        // approve delegate
+        let instruction = approve(
+            &program_id,
+            account_info.key,
+            delegate_info.key,
+            owner_info.key,
+            &[],
+            100,
+        )
+        .unwrap();
+        Processor::process(
+            &instruction.program_id,
+            &[
+                account_info.clone(),
+                delegate_info.clone(),
+                owner_info.clone(),
+            ],
+            &instruction.data,
+        )
+        .unwrap();
+
+        // delegate transfer
+        let instruction = transfer(
+            &program_id,
+            account_info.key,
+            account_info.key,
+            delegate_info.key,
+            &[],
+            100,
+        )
+        .unwrap();
+        assert_eq!(
+            Ok(()),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    account_info.clone(),
+                    delegate_info.clone(),
+                ],
+                &instruction.data,
+            )
+        );
+        // no balance change...
+        let account = Account::unpack_unchecked(&account_info.try_borrow_data().unwrap()).unwrap();
+        assert_eq!(account.amount, 1000);
+        assert_eq!(account.delegated_amount, 100);
+
+        // delegate transfer checked
+        let instruction = transfer_checked(
+            &program_id,
+            account_info.key,
+            mint_info.key,
+            account_info.key,
+            delegate_info.key,
+            &[],
+            100,
+            2,
+        )
+        .unwrap();
+        assert_eq!(
+            Ok(()),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    mint_info.clone(),
+                    account_info.clone(),
+                    delegate_info.clone(),
+                ],
+                &instruction.data,
+            )
+        );
+        // no balance change...
+        let account = Account::unpack_unchecked(&account_info.try_borrow_data().unwrap()).unwrap();
+        assert_eq!(account.amount, 1000);
+        assert_eq!(account.delegated_amount, 100);
+
+        // delegate insufficient funds
+        let instruction = transfer(
+            &program_id,
+            account_info.key,
+            account_info.key,
+            delegate_info.key,
+            &[],
+            101,
+        )
+        .unwrap();
+        assert_eq!(
+            Err(TokenError::InsufficientFunds.into()),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    account_info.clone(),
+                    delegate_info.clone(),
+                ],
+                &instruction.data,
+            )
+        );
+
+        // delegate insufficient funds checked
+        let instruction = transfer_checked(
+            &program_id,
+            account_info.key,
+            mint_info.key,
+            account_info.key,
+            delegate_info.key,
+            &[],
+            101,
+            2,
+        )
+        .unwrap();
+        assert_eq!(
+            Err(TokenError::InsufficientFunds.into()),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    mint_info.clone(),
+                    account_info.clone(),
+                    delegate_info.clone(),
+                ],
+                &instruction.data,
+            )
+        );
+
+        // owner transfer with delegate assigned
+        let instruction = transfer(
+            &program_id,
+            account_info.key,
+            account_info.key,
+            owner_info.key,
+            &[],
+            1000,
+        )
+        .unwrap();
+        assert_eq!(
+            Ok(()),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    account_info.clone(),
+                    owner_info.clone(),
+                ],
+                &instruction.data,
+            )
+        );
+        // no balance change...
+        let account = Account::unpack_unchecked(&account_info.try_borrow_data().unwrap()).unwrap();
+        assert_eq!(account.amount, 1000);
+
+        // owner transfer with delegate assigned checked
+        let instruction = transfer_checked(
+            &program_id,
+            account_info.key,
+            mint_info.key,
+            account_info.key,
+            owner_info.key,
+            &[],
+            1000,
+            2,
+        )
+        .unwrap();
+        assert_eq!(
+            Ok(()),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    mint_info.clone(),
+                    account_info.clone(),
+                    owner_info.clone(),
+                ],
+                &instruction.data,
+            )
+        );
+        // no balance change...
+        let account = Account::unpack_unchecked(&account_info.try_borrow_data().unwrap()).unwrap();
+        assert_eq!(account.amount, 1000);
+    }
+
+    #[test]
+    fn test_mintable_token_with_zero_supply() {
+        let program_id = crate::id();
+        let account_key = Pubkey::new_unique();
+        let mut account_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let owner_key = Pubkey::new_unique();
+        let mut owner_account = PUTAccount::default();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mut rent_sysvar = rent_sysvar();
+
+        // create mint-able token with zero supply
+        let decimals = 2;
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, decimals).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
+        )
+        .unwrap();
+        let mint = Mint::unpack_unchecked(&mint_account.data).unwrap();
+        assert_eq!(
+            mint,
+            Mint {
+                mint_authority: COption::Some(owner_key),
+                supply: 0,
+                decimals,
+                is_initialized: true,
+                freeze_authority: COption::None
+            }
+        );
+
+        // create account
+        do_process_instruction(
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+
+        // mint to
+        do_process_instruction(
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 42).unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
+        )
+        .unwrap();
+        let _ = Mint::unpack(&mint_account.data).unwrap();
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.amount, 42);
+
+        // mint to 2, with incorrect decimals
+        assert_eq!(
+            Err(TokenError::MintDecimalsMismatch.into()),
+            do_process_instruction(
+                mint_to_checked(
+                    &program_id,
+                    &mint_key,
+                    &account_key,
+                    &owner_key,
+                    &[],
+                    42,
+                    decimals + 1
+                )
+                .unwrap(),
+                vec![&mut mint_account, &mut account_account, &mut owner_account],
+            )
+        );
+
+        let _ = Mint::unpack(&mint_account.data).unwrap();
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.amount, 42);
+
+        // mint to 2
+        do_process_instruction(
+            mint_to_checked(
+                &program_id,
+                &mint_key,
+                &account_key,
+                &owner_key,
+                &[],
+                42,
+                decimals,
+            )
+            .unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
+        )
+        .unwrap();
+        let _ = Mint::unpack(&mint_account.data).unwrap();
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.amount, 84);
+    }
+
+    #[test]
+    fn test_approve_dups() {
+        let program_id = crate::id();
+        let account1_key = Pubkey::new_unique();
+        let mut account1_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account1_info: AccountInfo = (&account1_key, true, &mut account1_account).into();
+        let account2_key = Pubkey::new_unique();
+        let mut account2_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account2_info: AccountInfo = (&account2_key, false, &mut account2_account).into();
+        let account3_key = Pubkey::new_unique();
+        let mut account3_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account3_info: AccountInfo = (&account3_key, true, &mut account3_account).into();
+        let multisig_key = Pubkey::new_unique();
+        let mut multisig_account = PUTAccount::new(
+            multisig_minimum_balance(),
+            Multisig::get_packed_len(),
+            &program_id,
+        );
+        let multisig_info: AccountInfo = (&multisig_key, true, &mut multisig_account).into();
+        let owner_key = Pubkey::new_unique();
+        let mut owner_account = PUTAccount::default();
+        let owner_info: AccountInfo = (&owner_key, true, &mut owner_account).into();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mint_info: AccountInfo = (&mint_key, false, &mut mint_account).into();
+        let rent_key = rent::id();
+        let mut rent_sysvar = rent_sysvar();
+        let rent_info: AccountInfo = (&rent_key, false, &mut rent_sysvar).into();
+
+        // create mint
+        do_process_instruction_dups(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![mint_info.clone(), rent_info.clone()],
+        )
+        .unwrap();
+
+        // create account
+        do_process_instruction_dups(
+            initialize_account(&program_id, &account1_key, &mint_key, &account1_key).unwrap(),
+            vec![
+                account1_info.clone(),
+                mint_info.clone(),
+                account1_info.clone(),
+                rent_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // create another account
+        do_process_instruction_dups(
+            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                account2_info.clone(),
+                mint_info.clone(),
+                owner_info.clone(),
+                rent_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // mint to account
+        do_process_instruction_dups(
+            mint_to(&program_id, &mint_key, &account1_key, &owner_key, &[], 1000).unwrap(),
+            vec![mint_info.clone(), account1_info.clone(), owner_info.clone()],
+        )
+        .unwrap();
+
+        // source-owner approve
+        do_process_instruction_dups(
+            approve(
+                &program_id,
+                &account1_key,
+                &account2_key,
+                &account1_key,
+                &[],
+                500,
+            )
+            .unwrap(),
+            vec![
+                account1_info.clone(),
+                account2_info.clone(),
+                account1_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // source-owner approve_checked
+        do_process_instruction_dups(
+            approve_checked(
+                &program_id,
+                &account1_key,
+                &mint_key,
+                &account2_key,
+                &account1_key,
+                &[],
+                500,
+                2,
+            )
+            .unwrap(),
+            vec![
+                account1_info.clone(),
+                mint_info.clone(),
+                account2_info.clone(),
+                account1_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // source-owner revoke
+        do_process_instruction_dups(
+            revoke(&program_id, &account1_key, &account1_key, &[]).unwrap(),
+            vec![account1_info.clone(), account1_info.clone()],
+        )
+        .unwrap();
+
+        // test source-multisig signer
+        do_process_instruction_dups(
+            initialize_multisig(&program_id, &multisig_key, &[&account3_key], 1).unwrap(),
+            vec![
+                multisig_info.clone(),
+                rent_info.clone(),
+                account3_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        do_process_instruction_dups(
+            initialize_account(&program_id, &account3_key, &mint_key, &multisig_key).unwrap(),
+            vec![
+                account3_info.clone(),
+                mint_info.clone(),
+                multisig_info.clone(),
+                rent_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        do_process_instruction_dups(
+            mint_to(&program_id, &mint_key, &account3_key, &owner_key, &[], 1000).unwrap(),
+            vec![mint_info.clone(), account3_info.clone(), owner_info.clone()],
+        )
+        .unwrap();
+
+        // source-multisig-signer approve
+        do_process_instruction_dups(
+            approve(
+                &program_id,
+                &account3_key,
+                &account2_key,
+                &multisig_key,
+                &[&account3_key],
+                500,
+            )
+            .unwrap(),
+            vec![
+                account3_info.clone(),
+                account2_info.clone(),
+                multisig_info.clone(),
+                account3_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // source-multisig-signer approve_checked
+        do_process_instruction_dups(
+            approve_checked(
+                &program_id,
+                &account3_key,
+                &mint_key,
+                &account2_key,
+                &multisig_key,
+                &[&account3_key],
+                500,
+                2,
+            )
+            .unwrap(),
+            vec![
+                account3_info.clone(),
+                mint_info.clone(),
+                account2_info.clone(),
+                multisig_info.clone(),
+                account3_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // source-owner multisig-signer
+        do_process_instruction_dups(
+            revoke(&program_id, &account3_key, &multisig_key, &[&account3_key]).unwrap(),
+            vec![
+                account3_info.clone(),
+                multisig_info.clone(),
+                account3_info.clone(),
+            ],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_approve() {
+        let program_id = crate::id();
+        let account_key = Pubkey::new_unique();
+        let mut account_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account2_key = Pubkey::new_unique();
+        let mut account2_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let delegate_key = Pubkey::new_unique();
+        let mut delegate_account = PUTAccount::default();
+        let owner_key = Pubkey::new_unique();
+        let mut owner_account = PUTAccount::default();
+        let owner2_key = Pubkey::new_unique();
+        let mut owner2_account = PUTAccount::default();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mut rent_sysvar = rent_sysvar();
+
+        // create mint
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
+        )
+        .unwrap();
+
+        // create account
+        do_process_instruction(
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+
+        // create another account
+        do_process_instruction(
+            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account2_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+
+        // mint to account
+        do_process_instruction(
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1000).unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
+        )
+        .unwrap();
+
+        // missing signer
+        let mut instruction = approve(
+            &program_id,
+            &account_key,
+            &delegate_key,
+            &owner_key,
+            &[],
+            100,
+        )
+        .unwrap();
+        instruction.accounts[2].is_signer = false;
+        assert_eq!(
+            Err(ProgramError::MissingRequiredSignature),
+            do_process_instruction(
+                instruction,
+                vec![
+                    &mut account_account,
+                    &mut delegate_account,
+                    &mut owner_account,
+                ],
+            )
+        );
+
+        // no owner
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                approve(
+                    &program_id,
+                    &account_key,
+                    &delegate_key,
+                    &owner2_key,
+                    &[],
+                    100
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut delegate_account,
+                    &mut owner2_account,
+                ],
+            )
+        );
+
+        // approve delegate
+        do_process_instruction(
+            approve(
+                &program_id,
+                &account_key,
+                &delegate_key,
+                &owner_key,
+                &[],
+                100,
+            )
+            .unwrap(),
+            vec![
+                &mut account_account,
+                &mut delegate_account,
+                &mut owner_account,
+            ],
+        )
+        .unwrap();
+
+        // approve delegate 2, with incorrect decimals
+        assert_eq!(
+            Err(TokenError::MintDecimalsMismatch.into()),
+            do_process_instruction(
+                approve_checked(
+                    &program_id,
+                    &account_key,
+                    &mint_key,
+                    &delegate_key,
+                    &owner_key,
+                    &[],
+                    100,
+                    0 // <-- incorrect decimals
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut mint_account,
+                    &mut delegate_account,
+                    &mut owner_account,
+                ],
+            )
+        );
+
+        // approve delegate 2, with incorrect mint
+        assert_eq!(
+            Err(TokenError::MintMismatch.into()),
+            do_process_instruction(
+                approve_checked(
+                    &program_id,
+                    &account_key,
+                    &account2_key, // <-- bad mint
+                    &delegate_key,
+                    &owner_key,
+                    &[],
+                    100,
+                    0
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut account2_account, // <-- bad mint
+                    &mut delegate_account,
+                    &mut owner_account,
+                ],
+            )
+        );
+
+        // approve delegate 2
+        do_process_instruction(
+            approve_checked(
+                &program_id,
+                &account_key,
+                &mint_key,
+                &delegate_key,
+                &owner_key,
+                &[],
+                100,
+                2,
+            )
+            .unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut delegate_account,
+                &mut owner_account,
+            ],
+        )
+        .unwrap();
+
+        // revoke delegate
+        do_process_instruction(
+            revoke(&program_id, &account_key, &owner_key, &[]).unwrap(),
+            vec![&mut account_account, &mut owner_account],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_set_authority_dups() {
+        let program_id = crate::id();
+        let account1_key = Pubkey::new_unique();
+        let mut account1_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account1_info: AccountInfo = (&account1_key, true, &mut account1_account).into();
+        let owner_key = Pubkey::new_unique();
+        let mint_key = Pubkey::new_unique();
+        let (mintmeta_key, _) =
+            Pubkey::find_program_address(&[b"MintMeta", &mint_key.to_bytes()], &program_id);
+        let mut mint_account =
+            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mut mintmeta_account = PUTAccount::new(
+            mintmeta_minimum_balance(),
+            MintMeta::get_packed_len(),
+            &program_id,
+        );
+        let mint_info: AccountInfo = (&mint_key, true, &mut mint_account).into();
+        let mintmeta_info: AccountInfo = (&mintmeta_key, false,  &mut mintmeta_account).into();
+        let rent_key = rent::id();
+        let mut rent_sysvar = rent_sysvar();
+        let rent_info: AccountInfo = (&rent_key, false, &mut rent_sysvar).into();
+
+        // create mint
+        do_process_instruction_dups(
+            initialize_mint(&program_id, &mint_key, &mint_key, Some(&mint_key), 2).unwrap(),
+            vec![mint_info.clone(), rent_info.clone()],
+        )
+        .unwrap();
+
+        // create new mintmeta
+        do_process_instruction_dups(
+            initialize_mint_meta(&program_id,  &mint_key, &mintmeta_key , "symbol".to_string(),"name".to_string(),"icon".to_string()).unwrap(),
+            vec![ mint_info.clone(), mintmeta_info.clone()],
+        )
+        .unwrap();
+
+        // create account
+        do_process_instruction_dups(
+            initialize_account(&program_id, &account1_key, &mint_key, &account1_key).unwrap(),
+            vec![
+                account1_info.clone(),
+                mint_info.clone(),
+                account1_info.clone(),
+                rent_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // set mint_authority when currently self
+        do_process_instruction_dups(
+            set_authority(
+                &program_id,
+                &mint_key,
+                Some(&owner_key),
+                AuthorityType::MintTokens,
+                &mint_key,
+                &[],
+            )
+            .unwrap(),
+            vec![mint_info.clone(), mint_info.clone()],
+        )
+        .unwrap();
+
+        // set freeze_authority when currently self
+        do_process_instruction_dups(
+            set_authority(
+                &program_id,
+                &mint_key,
+                Some(&owner_key),
+                AuthorityType::FreezeAccount,
+                &mint_key,
+                &[],
+            )
+            .unwrap(),
+            vec![mint_info.clone(), mint_info.clone()],
+        )
+        .unwrap();
+
+        // set account owner when currently self
+        do_process_instruction_dups(
+            set_authority(
+                &program_id,
+                &account1_key,
+                Some(&owner_key),
+                AuthorityType::AccountOwner,
+                &account1_key,
+                &[],
+            )
+            .unwrap(),
+            vec![account1_info.clone(), account1_info.clone()],
+        )
+        .unwrap();
+
+        // set close_authority when currently self
+        let mut account = Account::unpack_unchecked(&account1_info.data.borrow()).unwrap();
+        account.close_authority = COption::Some(account1_key);
+        Account::pack(account, &mut account1_info.data.borrow_mut()).unwrap();
+
+        do_process_instruction_dups(
+            set_authority(
+                &program_id,
+                &account1_key,
+                Some(&owner_key),
+                AuthorityType::CloseAccount,
+                &account1_key,
+                &[],
+            )
+            .unwrap(),
+            vec![account1_info.clone(), account1_info.clone()],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_set_authority() {
+        let program_id = crate::id();
+        let account_key = Pubkey::new_unique();
+        let mut account_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account2_key = Pubkey::new_unique();
+        let mut account2_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let owner_key = Pubkey::new_unique();
+        let mut owner_account = PUTAccount::default();
+        let owner2_key = Pubkey::new_unique();
+        let mut owner2_account = PUTAccount::default();
+        let owner3_key = Pubkey::new_unique();
+        let mut owner3_account = PUTAccount::default();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+
+        let (mintmeta_key,_) = Pubkey::find_program_address(&[b"MintMeta",&mint_key.to_bytes()], &program_id);
+        let mut mintmeta_account = PUTAccount::new(mintmeta_minimum_balance(), MintMeta::get_packed_len(), &program_id);
+        let mint2_key = Pubkey::new_unique();
+        let mut mint2_account =
+            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mut rent_sysvar = rent_sysvar();
+
+        // create new mint with owner
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
+        )
+        .unwrap();
+
+        // create mint with owner and freeze_authority
+        do_process_instruction(
+            initialize_mint(&program_id, &mint2_key, &owner_key, Some(&owner_key), 2).unwrap(),
+            vec![&mut mint2_account, &mut rent_sysvar],
+        )
+        .unwrap();
+
+         // create new mintmeta
+         do_process_instruction(
+            initialize_mint_meta(&program_id, &mint_key, &mintmeta_key , "symbol".to_string(),"name".to_string(),"icon".to_string()).unwrap(),
+            vec![ &mut mint_account, &mut mintmeta_account],
+        )
+        .unwrap();
+
+        // invalid account
+        assert_eq!(
+            Err(ProgramError::UninitializedAccount),
+            do_process_instruction(
+                set_authority(
+                    &program_id,
+                    &account_key,
+                    Some(&owner2_key),
+                    AuthorityType::AccountOwner,
+                    &owner_key,
+                    &[]
+                )
+                .unwrap(),
+                vec![&mut account_account, &mut mintmeta_account, &mut owner_account],
+            )
+        );
+
+        // create account
+        do_process_instruction(
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+
+        // create another account
+        do_process_instruction(
+            initialize_account(&program_id, &account2_key, &mint2_key, &owner_key).unwrap(),
+            vec![
+                &mut account2_account,
+                &mut mint2_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+
+        // missing owner
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                set_authority(
+                    &program_id,
+                    &account_key,
+                    Some(&owner_key),
+                    AuthorityType::AccountOwner,
+                    &owner2_key,
+                    &[]
+                )
+                .unwrap(),
+                vec![&mut account_account, &mut mintmeta_account, &mut owner2_account],
+            )
+        );
+
+        // owner did not sign
+        let mut instruction = set_authority(
+            &program_id,
+            &account_key,
+            Some(&owner2_key),
+            AuthorityType::AccountOwner,
+            &owner_key,
+            &[],
+        )
+        .unwrap();
+        instruction.accounts[1].is_signer = false;
+        assert_eq!(
+            Err(ProgramError::MissingRequiredSignature),
+            do_process_instruction(instruction, vec![&mut account_account, &mut mintmeta_account, &mut owner_account,])
+        );
+
+        // wrong authority type
+        assert_eq!(
+            Err(TokenError::AuthorityTypeNotSupported.into()),
+            do_process_instruction(
+                set_authority(
+                    &program_id,
+                    &account_key,
+                    Some(&owner2_key),
+                    AuthorityType::FreezeAccount,
+                    &owner_key,
+                    &[],
+                )
+                .unwrap(),
+                vec![&mut account_account, &mut mintmeta_account,&mut owner_account],
+            )
+        );
+
+        // account owner may not be set to None
+        assert_eq!(
+            Err(TokenError::InvalidInstruction.into()),
+            do_process_instruction(
+                set_authority(
+                    &program_id,
+                    &account_key,
+                    None,
+                    AuthorityType::AccountOwner,
+                    &owner_key,
+                    &[],
+                )
+                .unwrap(),
+                vec![&mut account_account, &mut mintmeta_account, &mut owner_account],
+            )
+        );
+
+        // set delegate
+        do_process_instruction(
+            approve(
+                &program_id,
+                &account_key,
+                &owner2_key,
+                &owner_key,
+                &[],
+                u128::MAX,
+            )
+            .unwrap(),
+            vec![
+                &mut account_account,
+                &mut owner2_account,
+                &mut owner_account,
+            ],
+        )
+        .unwrap();
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.delegate, COption::Some(owner2_key));
+        assert_eq!(account.delegated_amount, u128::MAX);
+
+        // set owner
+        do_process_instruction(
+            set_authority(
+                &program_id,
+                &account_key,
+                Some(&owner3_key),
+                AuthorityType::AccountOwner,
+                &owner_key,
+                &[],
+            )
+            .unwrap(),
+            vec![&mut account_account, &mut mintmeta_account, &mut owner_account],
+        )
+        .unwrap();
+
+        // check delegate cleared
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.delegate, COption::None);
+        assert_eq!(account.delegated_amount, 0);
+
+        // set owner without existing delegate
+        do_process_instruction(
+            set_authority(
+                &program_id,
+                &account_key,
+                Some(&owner2_key),
+                AuthorityType::AccountOwner,
+                &owner3_key,
+                &[],
+            )
+            .unwrap(),
+            vec![&mut account_account, &mut mintmeta_account, &mut owner3_account],
+        )
+        .unwrap();
+
+        // set close_authority
+        do_process_instruction(
+            set_authority(
+                &program_id,
+                &account_key,
+                Some(&owner2_key),
+                AuthorityType::CloseAccount,
+                &owner2_key,
+                &[],
+            )
+            .unwrap(),
+            vec![&mut account_account, &mut mintmeta_account, &mut owner2_account],
+        )
+        .unwrap();
+
+        // close_authority may be set to None
+        do_process_instruction(
+            set_authority(
+                &program_id,
+                &account_key,
+                None,
+                AuthorityType::CloseAccount,
+                &owner2_key,
+                &[],
+            )
+            .unwrap(),
+            vec![&mut account_account, &mut mintmeta_account, &mut owner2_account],
+        )
+        .unwrap();
+
+        // wrong owner
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                set_authority(
+                    &program_id,
+                    &mint_key,
+                    Some(&owner3_key),
+                    AuthorityType::MintTokens,
+                    &owner2_key,
+                    &[]
+                )
+                .unwrap(),
+                vec![&mut mint_account, &mut mintmeta_account, &mut owner2_account],
+            )
+        );
+
+        // owner did not sign
+        let mut instruction = set_authority(
+            &program_id,
+            &mint_key,
+            Some(&owner2_key),
+            AuthorityType::MintTokens,
+            &owner_key,
+            &[],
+        )
+        .unwrap();
+        instruction.accounts[1].is_signer = false;
+        assert_eq!(
+            Err(ProgramError::MissingRequiredSignature),
+            do_process_instruction(instruction, vec![&mut mint_account, &mut mintmeta_account, &mut owner_account],)
+        );
+
+        // cannot freeze
+        assert_eq!(
+            Err(TokenError::MintCannotFreeze.into()),
+            do_process_instruction(
+                set_authority(
+                    &program_id,
+                    &mint_key,
+                    Some(&owner2_key),
+                    AuthorityType::FreezeAccount,
+                    &owner_key,
+                    &[],
+                )
+                .unwrap(),
+                vec![&mut mint_account, &mut mintmeta_account, &mut owner_account],
+            )
+        );
+
+        // set owner
+        do_process_instruction(
+            set_authority(
+                &program_id,
+                &mint_key,
+                Some(&owner2_key),
+                AuthorityType::MintTokens,
+                &owner_key,
+                &[],
+            )
+            .unwrap(),
+            vec![&mut mint_account, &mut mintmeta_account, &mut owner_account],
+        )
+        .unwrap();
+
+        // set owner to None
+        do_process_instruction(
+            set_authority(
+                &program_id,
+                &mint_key,
+                None,
+                AuthorityType::MintTokens,
+                &owner2_key,
+                &[],
+            )
+            .unwrap(),
+            vec![&mut mint_account, &mut mintmeta_account, &mut owner2_account],
+        )
+        .unwrap();
+
+        // test unsetting mint_authority is one-way operation
+        assert_eq!(
+            Err(TokenError::FixedSupply.into()),
+            do_process_instruction(
+                set_authority(
+                    &program_id,
+                    &mint2_key,
+                    Some(&owner2_key),
+                    AuthorityType::MintTokens,
+                    &owner_key,
+                    &[]
+                )
+                .unwrap(),
+                vec![&mut mint_account, &mut mintmeta_account, &mut owner_account],
+            )
+        );
+
+        // set freeze_authority
+        do_process_instruction(
+            set_authority(
+                &program_id,
+                &mint2_key,
+                Some(&owner2_key),
+                AuthorityType::FreezeAccount,
+                &owner_key,
+                &[],
+            )
+            .unwrap(),
+            vec![&mut mint2_account, &mut mintmeta_account, &mut owner_account],
+        )
+        .unwrap();
+
+        // test unsetting freeze_authority is one-way operation
+        do_process_instruction(
+            set_authority(
+                &program_id,
+                &mint2_key,
+                None,
+                AuthorityType::FreezeAccount,
+                &owner2_key,
+                &[],
+            )
+            .unwrap(),
+            vec![&mut mint2_account, &mut mintmeta_account, &mut owner2_account],
+        )
+        .unwrap();
+
+        assert_eq!(
+            Err(TokenError::MintCannotFreeze.into()),
+            do_process_instruction(
+                set_authority(
+                    &program_id,
+                    &mint2_key,
+                    Some(&owner2_key),
+                    AuthorityType::FreezeAccount,
+                    &owner_key,
+                    &[],
+                )
+                .unwrap(),
+                vec![&mut mint2_account, &mut mintmeta_account, &mut owner2_account],
+            )
+        );
+    }
+
+    #[test]
+    fn test_mint_to_dups() {
+        let program_id = crate::id();
+        let account1_key = Pubkey::new_unique();
+        let mut account1_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account1_info: AccountInfo = (&account1_key, true, &mut account1_account).into();
+        let owner_key = Pubkey::new_unique();
+        let mut owner_account = PUTAccount::default();
+        let owner_info: AccountInfo = (&owner_key, true, &mut owner_account).into();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let _mintmeta_account = PUTAccount::new(
+            mint_minimum_balance(),
+            MintMeta::get_packed_len(),
+            &program_id,
+        );
+        let mint_info: AccountInfo = (&mint_key, true, &mut mint_account).into();
+        let rent_key = rent::id();
+        let mut rent_sysvar = rent_sysvar();
+        let rent_info: AccountInfo = (&rent_key, false, &mut rent_sysvar).into();
+
+        // create mint
+        do_process_instruction_dups(
+            initialize_mint(&program_id, &mint_key, &mint_key, None, 2).unwrap(),
+            vec![mint_info.clone(), rent_info.clone()],
+        )
+        .unwrap();
+
+        // create account
+        do_process_instruction_dups(
+            initialize_account(&program_id, &account1_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                account1_info.clone(),
+                mint_info.clone(),
+                owner_info.clone(),
+                rent_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // mint_to when mint_authority is self
+        do_process_instruction_dups(
+            mint_to(&program_id, &mint_key, &account1_key, &mint_key, &[], 42).unwrap(),
+            vec![mint_info.clone(), account1_info.clone(), mint_info.clone()],
+        )
+        .unwrap();
+
+        // mint_to_checked when mint_authority is self
+        do_process_instruction_dups(
+            mint_to_checked(&program_id, &mint_key, &account1_key, &mint_key, &[], 42, 2).unwrap(),
+            vec![mint_info.clone(), account1_info.clone(), mint_info.clone()],
+        )
+        .unwrap();
+
+        // mint_to when mint_authority is account owner
+        let mut mint = Mint::unpack_unchecked(&mint_info.data.borrow()).unwrap();
+        mint.mint_authority = COption::Some(account1_key);
+        Mint::pack(mint, &mut mint_info.data.borrow_mut()).unwrap();
+        do_process_instruction_dups(
+            mint_to(
+                &program_id,
+                &mint_key,
+                &account1_key,
+                &account1_key,
+                &[],
+                42,
+            )
+            .unwrap(),
+            vec![
+                mint_info.clone(),
+                account1_info.clone(),
+                account1_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // mint_to_checked when mint_authority is account owner
+        do_process_instruction_dups(
+            mint_to(
+                &program_id,
+                &mint_key,
+                &account1_key,
+                &account1_key,
+                &[],
+                42,
+            )
+            .unwrap(),
+            vec![
+                mint_info.clone(),
+                account1_info.clone(),
+                account1_info.clone(),
+            ],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_mint_to() {
+        let program_id = crate::id();
+        let account_key = Pubkey::new_unique();
+        let mut account_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account2_key = Pubkey::new_unique();
+        let mut account2_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account3_key = Pubkey::new_unique();
+        let mut account3_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let mismatch_key = Pubkey::new_unique();
+        let mut mismatch_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let owner_key = Pubkey::new_unique();
+        let mut owner_account = PUTAccount::default();
+        let owner2_key = Pubkey::new_unique();
+        let mut owner2_account = PUTAccount::default();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+
+        let (mintmeta_key, _) =
+            Pubkey::find_program_address(&[b"MintMeta", &mint_key.to_bytes()], &program_id);
+        let mut mintmeta_account = PUTAccount::new(
+            mintmeta_minimum_balance(),
+            MintMeta::get_packed_len(),
+            &program_id,
+        );
+        let mint2_key = Pubkey::new_unique();
+        let uninitialized_key = Pubkey::new_unique();
+        let mut uninitialized_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let mut rent_sysvar = rent_sysvar();
+
+        // create new mint with owner
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
+        )
+        .unwrap();
+
+        // create new mintmeta
+        do_process_instruction(
+            initialize_mint_meta(
+                &program_id,
+                &mint_key,
+                &mintmeta_key,
+                "symbol".to_string(),
+                "name".to_string(),
+                "icon".to_string(),
+            )
+            .unwrap(),
+            vec![
+                &mut mint_account,
+                &mut mintmeta_account,
+            ],
+        )
+        .unwrap();
+
+        // create account
+        do_process_instruction(
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+
+        // create another account
+        do_process_instruction(
+            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account2_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+
+        // create another account
+        do_process_instruction(
+            initialize_account(&program_id, &account3_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account3_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+
+        // create mismatch account
+        do_process_instruction(
+            initialize_account(&program_id, &mismatch_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut mismatch_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+        let mut account = Account::unpack_unchecked(&mismatch_account.data).unwrap();
+        account.mint = mint2_key;
+        Account::pack(account, &mut mismatch_account.data).unwrap();
+
+        // mint to
+        do_process_instruction(
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 42).unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
+        )
+        .unwrap();
+
+        let mint = Mint::unpack_unchecked(&mint_account.data).unwrap();
+        
+        assert_eq!(mint.supply, 42);
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.amount, 42);
+
+        // mint to another account to test supply accumulation
+        do_process_instruction(
+            mint_to(&program_id, &mint_key, &account2_key, &owner_key, &[], 42).unwrap(),
+            vec![&mut mint_account, &mut account2_account, &mut owner_account],
+        )
+        .unwrap();
+
+        let mint = Mint::unpack_unchecked(&mint_account.data).unwrap();
+        assert_eq!(mint.supply, 84);
+        let account = Account::unpack_unchecked(&account2_account.data).unwrap();
+        assert_eq!(account.amount, 42);
+
+        // missing signer
+        let mut instruction =
+            mint_to(&program_id, &mint_key, &account2_key, &owner_key, &[], 42).unwrap();
+        instruction.accounts[2].is_signer = false;
+        assert_eq!(
+            Err(ProgramError::MissingRequiredSignature),
+            do_process_instruction(
+                instruction,
+                vec![&mut mint_account, &mut account2_account, &mut owner_account],
+            )
+        );
+
+        // mismatch account
+        assert_eq!(
+            Err(TokenError::MintMismatch.into()),
+            do_process_instruction(
+                mint_to(&program_id, &mint_key, &mismatch_key, &owner_key, &[], 42).unwrap(),
+                vec![&mut mint_account, &mut mismatch_account, &mut owner_account],
+            )
+        );
+
+        // missing owner
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                mint_to(&program_id, &mint_key, &account2_key, &owner2_key, &[], 42).unwrap(),
+                vec![
+                    &mut mint_account,
+                    &mut account2_account,
+                    &mut owner2_account,
+                ],
+            )
+        );
+
+        // uninitialized destination account
+        assert_eq!(
+            Err(ProgramError::UninitializedAccount),
+            do_process_instruction(
+                mint_to(
+                    &program_id,
+                    &mint_key,
+                    &uninitialized_key,
+                    &owner_key,
+                    &[],
+                    42
+                )
+                .unwrap(),
+                vec![
+                    &mut mint_account,
+                    &mut uninitialized_account,
+                    &mut owner_account,
+                ],
+            )
+        );
+
+        // unset mint_authority and test minting fails
+        do_process_instruction(
+            set_authority(
+                &program_id,
+                &mint_key,
+                None,
+                AuthorityType::MintTokens,
+                &owner_key,
+                &[],
+            )
+            .unwrap(),
+            vec![&mut mint_account, &mut mintmeta_account, &mut owner_account],
+        )
+        .unwrap();
+        assert_eq!(
+            Err(TokenError::FixedSupply.into()),
+            do_process_instruction(
+                mint_to(&program_id, &mint_key, &account2_key, &owner_key, &[], 42).unwrap(),
+                vec![&mut mint_account, &mut account2_account, &mut owner_account],
+            )
+        );
     }
 
     #[test]
-    fn test_unique_account_sizes() {
-        assert_ne!(Mint::get_packed_len(), 0);
-        assert_ne!(Mint::get_packed_len(), Account::get_packed_len());
-        assert_ne!(Mint::get_packed_len(), Multisig::get_packed_len());
-        assert_ne!(Account::get_packed_len(), 0);
-        assert_ne!(Account::get_packed_len(), Multisig::get_packed_len());
-        assert_ne!(Multisig::get_packed_len(), 0);
+    fn test_burn_dups() {
+        let program_id = crate::id();
+        let account1_key = Pubkey::new_unique();
+        let mut account1_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account1_info: AccountInfo = (&account1_key, true, &mut account1_account).into();
+        let owner_key = Pubkey::new_unique();
+        let mut owner_account = PUTAccount::default();
+        let owner_info: AccountInfo = (&owner_key, true, &mut owner_account).into();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mint_info: AccountInfo = (&mint_key, true, &mut mint_account).into();
+        let rent_key = rent::id();
+        let mut rent_sysvar = rent_sysvar();
+        let rent_info: AccountInfo = (&rent_key, false, &mut rent_sysvar).into();
+
+        // create mint
+        do_process_instruction_dups(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![mint_info.clone(), rent_info.clone()],
+        )
+        .unwrap();
+
+        // create account
+        do_process_instruction_dups(
+            initialize_account(&program_id, &account1_key, &mint_key, &account1_key).unwrap(),
+            vec![
+                account1_info.clone(),
+                mint_info.clone(),
+                account1_info.clone(),
+                rent_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // mint to account
+        do_process_instruction_dups(
+            mint_to(&program_id, &mint_key, &account1_key, &owner_key, &[], 1000).unwrap(),
+            vec![mint_info.clone(), account1_info.clone(), owner_info.clone()],
+        )
+        .unwrap();
+
+        // source-owner burn
+        do_process_instruction_dups(
+            burn(
+                &program_id,
+                &mint_key,
+                &account1_key,
+                &account1_key,
+                &[],
+                500,
+            )
+            .unwrap(),
+            vec![
+                account1_info.clone(),
+                mint_info.clone(),
+                account1_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // source-owner burn_checked
+        do_process_instruction_dups(
+            burn_checked(
+                &program_id,
+                &account1_key,
+                &mint_key,
+                &account1_key,
+                &[],
+                500,
+                2,
+            )
+            .unwrap(),
+            vec![
+                account1_info.clone(),
+                mint_info.clone(),
+                account1_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // mint-owner burn
+        do_process_instruction_dups(
+            mint_to(&program_id, &mint_key, &account1_key, &owner_key, &[], 1000).unwrap(),
+            vec![mint_info.clone(), account1_info.clone(), owner_info.clone()],
+        )
+        .unwrap();
+        let mut account = Account::unpack_unchecked(&account1_info.data.borrow()).unwrap();
+        account.owner = mint_key;
+        Account::pack(account, &mut account1_info.data.borrow_mut()).unwrap();
+        do_process_instruction_dups(
+            burn(&program_id, &account1_key, &mint_key, &mint_key, &[], 500).unwrap(),
+            vec![account1_info.clone(), mint_info.clone(), mint_info.clone()],
+        )
+        .unwrap();
+
+        // mint-owner burn_checked
+        do_process_instruction_dups(
+            burn_checked(
+                &program_id,
+                &account1_key,
+                &mint_key,
+                &mint_key,
+                &[],
+                500,
+                2,
+            )
+            .unwrap(),
+            vec![account1_info.clone(), mint_info.clone(), mint_info.clone()],
+        )
+        .unwrap();
+
+        // source-delegate burn
+        do_process_instruction_dups(
+            mint_to(&program_id, &mint_key, &account1_key, &owner_key, &[], 1000).unwrap(),
+            vec![mint_info.clone(), account1_info.clone(), owner_info.clone()],
+        )
+        .unwrap();
+        let mut account = Account::unpack_unchecked(&account1_info.data.borrow()).unwrap();
+        account.delegated_amount = 1000;
+        account.delegate = COption::Some(account1_key);
+        account.owner = owner_key;
+        Account::pack(account, &mut account1_info.data.borrow_mut()).unwrap();
+        do_process_instruction_dups(
+            burn(
+                &program_id,
+                &account1_key,
+                &mint_key,
+                &account1_key,
+                &[],
+                500,
+            )
+            .unwrap(),
+            vec![
+                account1_info.clone(),
+                mint_info.clone(),
+                account1_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // source-delegate burn_checked
+        do_process_instruction_dups(
+            burn_checked(
+                &program_id,
+                &account1_key,
+                &mint_key,
+                &account1_key,
+                &[],
+                500,
+                2,
+            )
+            .unwrap(),
+            vec![
+                account1_info.clone(),
+                mint_info.clone(),
+                account1_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // mint-delegate burn
+        do_process_instruction_dups(
+            mint_to(&program_id, &mint_key, &account1_key, &owner_key, &[], 1000).unwrap(),
+            vec![mint_info.clone(), account1_info.clone(), owner_info.clone()],
+        )
+        .unwrap();
+        let mut account = Account::unpack_unchecked(&account1_info.data.borrow()).unwrap();
+        account.delegated_amount = 1000;
+        account.delegate = COption::Some(mint_key);
+        account.owner = owner_key;
+        Account::pack(account, &mut account1_info.data.borrow_mut()).unwrap();
+        do_process_instruction_dups(
+            burn(&program_id, &account1_key, &mint_key, &mint_key, &[], 500).unwrap(),
+            vec![account1_info.clone(), mint_info.clone(), mint_info.clone()],
+        )
+        .unwrap();
+
+        // mint-delegate burn_checked
+        do_process_instruction_dups(
+            burn_checked(
+                &program_id,
+                &account1_key,
+                &mint_key,
+                &mint_key,
+                &[],
+                500,
+                2,
+            )
+            .unwrap(),
+            vec![account1_info.clone(), mint_info.clone(), mint_info.clone()],
+        )
+        .unwrap();
     }
 
     #[test]
-    fn test_pack_unpack() {
-        // Mint
-        let check = Mint {
-            mint_authority: COption::Some(Pubkey::new(&[1; 32])),
-            supply: 42,
-            decimals: 7,
-            is_initialized: true,
-            freeze_authority: COption::Some(Pubkey::new(&[2; 32])),
-        };
-
-        let mut packed = vec![0; Mint::get_packed_len() + 1];
-        assert_eq!(
-            Err(ProgramError::InvalidAccountData),
-            Mint::pack(check, &mut packed)
-        );
-        let mut packed = vec![0; Mint::get_packed_len() - 1];
-        assert_eq!(
-            Err(ProgramError::InvalidAccountData),
-            Mint::pack(check, &mut packed)
-        );
-        let mut packed = vec![0; Mint::get_packed_len()];
-        Mint::pack(check, &mut packed).unwrap();
-        let expect = vec![
-            1, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
-            1, 1, 1, 1, 1, 1, 1, 42, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 7, 1, 1, 0, 0, 0,
-            2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2,
-            2, 2, 2,
-        ];
-        assert_eq!(packed, expect);
-        let unpacked = Mint::unpack(&packed).unwrap();
-        assert_eq!(unpacked, check);
-
-        // Account
-        let check = Account {
-            mint: Pubkey::new(&[1; 32]),
-            owner: Pubkey::new(&[2; 32]),
-            amount: 3,
-            delegate: COption::Some(Pubkey::new(&[4; 32])),
-            state: AccountState::Frozen,
-            is_native: COption::Some(5),
-            delegated_amount: 6,
-            close_authority: COption::Some(Pubkey::new(&[7; 32])),
-        };
-        let mut packed = vec![0; Account::get_packed_len() + 1];
-        assert_eq!(
-            Err(ProgramError::InvalidAccountData),
-            Account::pack(check, &mut packed)
+    fn test_burn() {
+        let program_id = crate::id();
+        let account_key = Pubkey::new_unique();
+        let mut account_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
         );
-        let mut packed = vec![0; Account::get_packed_len() - 1];
-        assert_eq!(
-            Err(ProgramError::InvalidAccountData),
-            Account::pack(check, &mut packed)
+        let account2_key = Pubkey::new_unique();
+        let mut account2_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
         );
-        let mut packed = vec![0; Account::get_packed_len()];
-        Account::pack(check, &mut packed).unwrap();
-        let expect = vec![
-            1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
-            1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2,
-            2, 2, 2, 2, 2, 2, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 4, 4, 4,
-            4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4,
-            2, 1, 0, 0, 0, 5, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 6, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7,
-            7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7,
-        ];
-        assert_eq!(packed, expect);
-        let unpacked = Account::unpack(&packed).unwrap();
-        assert_eq!(unpacked, check);
-
-        // Multisig
-        let check = Multisig {
-            m: 1,
-            n: 2,
-            is_initialized: true,
-            signers: [Pubkey::new(&[3; 32]); MAX_SIGNERS],
-        };
-        let mut packed = vec![0; Multisig::get_packed_len() + 1];
-        assert_eq!(
-            Err(ProgramError::InvalidAccountData),
-            Multisig::pack(check, &mut packed)
+        let account3_key = Pubkey::new_unique();
+        let mut account3_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
         );
-        let mut packed = vec![0; Multisig::get_packed_len() - 1];
-        assert_eq!(
-            Err(ProgramError::InvalidAccountData),
-            Multisig::pack(check, &mut packed)
+        let delegate_key = Pubkey::new_unique();
+        let mut delegate_account = PUTAccount::default();
+        let mismatch_key = Pubkey::new_unique();
+        let mut mismatch_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
         );
-        let mut packed = vec![0; Multisig::get_packed_len()];
-        Multisig::pack(check, &mut packed).unwrap();
-        let expect = vec![
-            1, 2, 1, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
-            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
-            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
-            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
-            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
-            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
-            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
-            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
-            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
-            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
-            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
-            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
-            3, 3, 3, 3, 3, 3, 3,
-        ];
-        assert_eq!(packed, expect);
-        let unpacked = Multisig::unpack(&packed).unwrap();
-        assert_eq!(unpacked, check);
-    }
-
-    #[test]
-    fn test_initialize_mint() {
-        let program_id = crate::id();
         let owner_key = Pubkey::new_unique();
+        let mut owner_account = PUTAccount::default();
+        let owner2_key = Pubkey::new_unique();
+        let mut owner2_account = PUTAccount::default();
         let mint_key = Pubkey::new_unique();
-        let mut mint_account = PUTAccount::new(42, Mint::get_packed_len(), &program_id);
-        let mint2_key = Pubkey::new_unique();
-        let mut mint2_account =
+        let mut mint_account =
             PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mint2_key = Pubkey::new_unique();
         let mut rent_sysvar = rent_sysvar();
 
-        // mint is not rent exempt
+        // create new mint
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
+        )
+        .unwrap();
+
+        // create account
+        do_process_instruction(
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+
+        // create another account
+        do_process_instruction(
+            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account2_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+
+        // create another account
+        do_process_instruction(
+            initialize_account(&program_id, &account3_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account3_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+
+        // create mismatch account
+        do_process_instruction(
+            initialize_account(&program_id, &mismatch_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut mismatch_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+
+        // mint to account
+        do_process_instruction(
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1000).unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
+        )
+        .unwrap();
+
+        // mint to mismatch account and change mint key
+        do_process_instruction(
+            mint_to(&program_id, &mint_key, &mismatch_key, &owner_key, &[], 1000).unwrap(),
+            vec![&mut mint_account, &mut mismatch_account, &mut owner_account],
+        )
+        .unwrap();
+        let mut account = Account::unpack_unchecked(&mismatch_account.data).unwrap();
+        account.mint = mint2_key;
+        Account::pack(account, &mut mismatch_account.data).unwrap();
+
+        // missing signer
+        let mut instruction =
+            burn(&program_id, &account_key, &mint_key, &delegate_key, &[], 42).unwrap();
+        instruction.accounts[1].is_signer = false;
         assert_eq!(
-            Err(TokenError::NotRentExempt.into()),
+            Err(TokenError::OwnerMismatch.into()),
             do_process_instruction(
-                initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
-                vec![&mut mint_account, &mut rent_sysvar]
+                instruction,
+                vec![
+                    &mut account_account,
+                    &mut mint_account,
+                    &mut delegate_account
+                ],
             )
         );
 
-        mint_account.lamports = mint_minimum_balance();
+        // missing owner
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                burn(&program_id, &account_key, &mint_key, &owner2_key, &[], 42).unwrap(),
+                vec![&mut account_account, &mut mint_account, &mut owner2_account],
+            )
+        );
 
-        // create new mint
+        // mint mismatch
+        assert_eq!(
+            Err(TokenError::MintMismatch.into()),
+            do_process_instruction(
+                burn(&program_id, &mismatch_key, &mint_key, &owner_key, &[], 42).unwrap(),
+                vec![&mut mismatch_account, &mut mint_account, &mut owner_account],
+            )
+        );
+
+        // burn
         do_process_instruction(
-            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
-            vec![&mut mint_account, &mut rent_sysvar],
+            burn(&program_id, &account_key, &mint_key, &owner_key, &[], 21).unwrap(),
+            vec![&mut account_account, &mut mint_account, &mut owner_account],
         )
         .unwrap();
 
-        // create twice
+        // burn_checked, with incorrect decimals
         assert_eq!(
-            Err(TokenError::AlreadyInUse.into()),
+            Err(TokenError::MintDecimalsMismatch.into()),
             do_process_instruction(
-                initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
-                vec![&mut mint_account, &mut rent_sysvar]
+                burn_checked(&program_id, &account_key, &mint_key, &owner_key, &[], 21, 3).unwrap(),
+                vec![&mut account_account, &mut mint_account, &mut owner_account],
             )
         );
 
-        // create another mint that can freeze
+        // burn_checked
         do_process_instruction(
-            initialize_mint(&program_id, &mint2_key, &owner_key, Some(&owner_key), 2).unwrap(),
-            vec![&mut mint2_account, &mut rent_sysvar],
+            burn_checked(&program_id, &account_key, &mint_key, &owner_key, &[], 21, 2).unwrap(),
+            vec![&mut account_account, &mut mint_account, &mut owner_account],
         )
         .unwrap();
-        let mint = Mint::unpack_unchecked(&mint2_account.data).unwrap();
-        assert_eq!(mint.freeze_authority, COption::Some(owner_key));
-    }
 
-    #[test]
-    fn test_initialize_mint_account() {
-        let program_id = crate::id();
-        let account_key = Pubkey::new_unique();
-        let mut account_account = PUTAccount::new(42, Account::get_packed_len(), &program_id);
-        let owner_key = Pubkey::new_unique();
-        let mut owner_account = PUTAccount::default();
-        let mint_key = Pubkey::new_unique();
-        let mut mint_account =
-            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-        let mut rent_sysvar = rent_sysvar();
+        let mint = Mint::unpack_unchecked(&mint_account.data).unwrap();
+        assert_eq!(mint.supply, 2000 - 42);
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.amount, 1000 - 42);
 
-        // account is not rent exempt
+        // insufficient funds
         assert_eq!(
-            Err(TokenError::NotRentExempt.into()),
+            Err(TokenError::InsufficientFunds.into()),
             do_process_instruction(
-                initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
-                vec![
-                    &mut account_account,
-                    &mut mint_account,
-                    &mut owner_account,
-                    &mut rent_sysvar
-                ],
+                burn(
+                    &program_id,
+                    &account_key,
+                    &mint_key,
+                    &owner_key,
+                    &[],
+                    100_000_000
+                )
+                .unwrap(),
+                vec![&mut account_account, &mut mint_account, &mut owner_account],
             )
         );
 
-        account_account.lamports = account_minimum_balance();
+        // approve delegate
+        do_process_instruction(
+            approve(
+                &program_id,
+                &account_key,
+                &delegate_key,
+                &owner_key,
+                &[],
+                84,
+            )
+            .unwrap(),
+            vec![
+                &mut account_account,
+                &mut delegate_account,
+                &mut owner_account,
+            ],
+        )
+        .unwrap();
 
-        // mint is not valid (not initialized)
+        // not a delegate of source account
         assert_eq!(
-            Err(TokenError::InvalidMint.into()),
+            Err(TokenError::InsufficientFunds.into()),
             do_process_instruction(
-                initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
-                vec![
-                    &mut account_account,
-                    &mut mint_account,
-                    &mut owner_account,
-                    &mut rent_sysvar
-                ],
+                burn(
+                    &program_id,
+                    &account_key,
+                    &mint_key,
+                    &owner_key,
+                    &[],
+                    100_000_000
+                )
+                .unwrap(),
+                vec![&mut account_account, &mut mint_account, &mut owner_account],
             )
         );
 
-        // create mint
-        do_process_instruction(
-            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
-            vec![&mut mint_account, &mut rent_sysvar],
-        )
-        .unwrap();
-
-        // create account
+        // burn via delegate
         do_process_instruction(
-            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            burn(&program_id, &account_key, &mint_key, &delegate_key, &[], 84).unwrap(),
             vec![
                 &mut account_account,
                 &mut mint_account,
-                &mut owner_account,
-                &mut rent_sysvar,
+                &mut delegate_account,
             ],
         )
         .unwrap();
 
-        // create twice
+        // match
+        let mint = Mint::unpack_unchecked(&mint_account.data).unwrap();
+        assert_eq!(mint.supply, 2000 - 42 - 84);
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.amount, 1000 - 42 - 84);
+
+        // insufficient funds approved via delegate
         assert_eq!(
-            Err(TokenError::AlreadyInUse.into()),
+            Err(TokenError::OwnerMismatch.into()),
             do_process_instruction(
-                initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+                burn(
+                    &program_id,
+                    &account_key,
+                    &mint_key,
+                    &delegate_key,
+                    &[],
+                    100
+                )
+                .unwrap(),
                 vec![
                     &mut account_account,
                     &mut mint_account,
-                    &mut owner_account,
-                    &mut rent_sysvar
+                    &mut delegate_account
                 ],
             )
         );
     }
 
     #[test]
-    fn test_transfer_dups() {
+    fn test_multisig() {
         let program_id = crate::id();
-        let account1_key = Pubkey::new_unique();
-        let mut account1_account = PUTAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
-        );
-        let mut account1_info: AccountInfo = (&account1_key, true, &mut account1_account).into();
-        let account2_key = Pubkey::new_unique();
-        let mut account2_account = PUTAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+
+        let (mintmeta_key, _) =
+            Pubkey::find_program_address(&[b"MintMeta", &mint_key.to_bytes()], &program_id);
+        let mut mintmeta_account = PUTAccount::new(
+            mintmeta_minimum_balance(),
+            MintMeta::get_packed_len(),
             &program_id,
         );
-        let mut account2_info: AccountInfo = (&account2_key, false, &mut account2_account).into();
-        let account3_key = Pubkey::new_unique();
-        let mut account3_account = PUTAccount::new(
+
+        let account_key = Pubkey::new_unique();
+        let mut account = PUTAccount::new(
             account_minimum_balance(),
             Account::get_packed_len(),
             &program_id,
         );
-        let account3_info: AccountInfo = (&account3_key, false, &mut account3_account).into();
-        let account4_key = Pubkey::new_unique();
-        let mut account4_account = PUTAccount::new(
+        let account2_key = Pubkey::new_unique();
+        let mut account2_account = PUTAccount::new(
             account_minimum_balance(),
             Account::get_packed_len(),
             &program_id,
         );
-        let account4_info: AccountInfo = (&account4_key, true, &mut account4_account).into();
+        let owner_key = Pubkey::new_unique();
+        let mut owner_account = PUTAccount::default();
         let multisig_key = Pubkey::new_unique();
-        let mut multisig_account = PUTAccount::new(
+        let mut multisig_account = PUTAccount::new(42, Multisig::get_packed_len(), &program_id);
+        let multisig_delegate_key = Pubkey::new_unique();
+        let mut multisig_delegate_account = PUTAccount::new(
             multisig_minimum_balance(),
             Multisig::get_packed_len(),
             &program_id,
         );
-        let multisig_info: AccountInfo = (&multisig_key, true, &mut multisig_account).into();
-        let owner_key = Pubkey::new_unique();
-        let mut owner_account = PUTAccount::default();
-        let owner_info: AccountInfo = (&owner_key, true, &mut owner_account).into();
-        let mint_key = Pubkey::new_unique();
-        let mut mint_account =
-            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-        let mint_info: AccountInfo = (&mint_key, false, &mut mint_account).into();
-        let rent_key = rent::id();
+        let signer_keys = vec![Pubkey::new_unique(); MAX_SIGNERS];
+        let signer_key_refs: Vec<&Pubkey> = signer_keys.iter().collect();
+        let mut signer_accounts = vec![PUTAccount::new(0, 0, &program_id); MAX_SIGNERS];
         let mut rent_sysvar = rent_sysvar();
-        let rent_info: AccountInfo = (&rent_key, false, &mut rent_sysvar).into();
 
-        // create mint
-        do_process_instruction_dups(
-            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
-            vec![mint_info.clone(), rent_info.clone()],
+        // multisig is not rent exempt
+        let account_info_iter = &mut signer_accounts.iter_mut();
+        assert_eq!(
+            Err(TokenError::NotRentExempt.into()),
+            do_process_instruction(
+                initialize_multisig(&program_id, &multisig_key, &[&signer_keys[0]], 1).unwrap(),
+                vec![
+                    &mut multisig_account,
+                    &mut rent_sysvar,
+                    &mut account_info_iter.next().unwrap(),
+                ],
+            )
+        );
+
+        multisig_account.lamports = multisig_minimum_balance();
+
+        // single signer
+        let account_info_iter = &mut signer_accounts.iter_mut();
+        do_process_instruction(
+            initialize_multisig(&program_id, &multisig_key, &[&signer_keys[0]], 1).unwrap(),
+            vec![
+                &mut multisig_account,
+                &mut rent_sysvar,
+                &mut account_info_iter.next().unwrap(),
+            ],
+        )
+        .unwrap();
+
+        // multiple signer
+        let account_info_iter = &mut signer_accounts.iter_mut();
+        do_process_instruction(
+            initialize_multisig(
+                &program_id,
+                &multisig_delegate_key,
+                &signer_key_refs,
+                MAX_SIGNERS as u8,
+            )
+            .unwrap(),
+            vec![
+                &mut multisig_delegate_account,
+                &mut rent_sysvar,
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+            ],
+        )
+        .unwrap();
+
+        // create new mint with multisig owner
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &multisig_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
+        )
+        .unwrap();
+
+        // create new mintmeta
+        do_process_instruction(
+            initialize_mint_meta(
+                &program_id,
+                &mint_key,
+                &mintmeta_key,
+                "symbol".to_string(),
+                "name".to_string(),
+                "icon".to_string(),
+            )
+            .unwrap(),
+            vec![
+                &mut mint_account,
+                &mut mintmeta_account,
+            ],
         )
         .unwrap();
 
-        // create account
-        do_process_instruction_dups(
-            initialize_account(&program_id, &account1_key, &mint_key, &account1_key).unwrap(),
+        // create account with multisig owner
+        do_process_instruction(
+            initialize_account(&program_id, &account_key, &mint_key, &multisig_key).unwrap(),
             vec![
-                account1_info.clone(),
-                mint_info.clone(),
-                account1_info.clone(),
-                rent_info.clone(),
+                &mut account,
+                &mut mint_account,
+                &mut multisig_account,
+                &mut rent_sysvar,
             ],
         )
         .unwrap();
 
-        // create another account
-        do_process_instruction_dups(
-            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
+        // create another account with multisig owner
+        do_process_instruction(
+            initialize_account(
+                &program_id,
+                &account2_key,
+                &mint_key,
+                &multisig_delegate_key,
+            )
+            .unwrap(),
             vec![
-                account2_info.clone(),
-                mint_info.clone(),
-                owner_info.clone(),
-                rent_info.clone(),
+                &mut account2_account,
+                &mut mint_account,
+                &mut multisig_account,
+                &mut rent_sysvar,
             ],
         )
         .unwrap();
 
         // mint to account
-        do_process_instruction_dups(
-            mint_to(&program_id, &mint_key, &account1_key, &owner_key, &[], 1000).unwrap(),
-            vec![mint_info.clone(), account1_info.clone(), owner_info.clone()],
+        let account_info_iter = &mut signer_accounts.iter_mut();
+        do_process_instruction(
+            mint_to(
+                &program_id,
+                &mint_key,
+                &account_key,
+                &multisig_key,
+                &[&signer_keys[0]],
+                1000,
+            )
+            .unwrap(),
+            vec![
+                &mut mint_account,
+                &mut account,
+                &mut multisig_account,
+                &mut account_info_iter.next().unwrap(),
+            ],
         )
         .unwrap();
 
-        // source-owner transfer
-        do_process_instruction_dups(
+        // approve
+        let account_info_iter = &mut signer_accounts.iter_mut();
+        do_process_instruction(
+            approve(
+                &program_id,
+                &account_key,
+                &multisig_delegate_key,
+                &multisig_key,
+                &[&signer_keys[0]],
+                100,
+            )
+            .unwrap(),
+            vec![
+                &mut account,
+                &mut multisig_delegate_account,
+                &mut multisig_account,
+                &mut account_info_iter.next().unwrap(),
+            ],
+        )
+        .unwrap();
+
+        // transfer
+        let account_info_iter = &mut signer_accounts.iter_mut();
+        do_process_instruction(
             transfer(
                 &program_id,
-                &account1_key,
+                &account_key,
                 &account2_key,
-                &account1_key,
-                &[],
-                500,
+                &multisig_key,
+                &[&signer_keys[0]],
+                42,
             )
             .unwrap(),
             vec![
-                account1_info.clone(),
-                account2_info.clone(),
-                account1_info.clone(),
+                &mut account,
+                &mut account2_account,
+                &mut multisig_account,
+                &mut account_info_iter.next().unwrap(),
             ],
         )
         .unwrap();
 
-        // source-owner TransferChecked
-        do_process_instruction_dups(
-            transfer_checked(
+        // transfer via delegate
+        let account_info_iter = &mut signer_accounts.iter_mut();
+        do_process_instruction(
+            transfer(
+                &program_id,
+                &account_key,
+                &account2_key,
+                &multisig_delegate_key,
+                &signer_key_refs,
+                42,
+            )
+            .unwrap(),
+            vec![
+                &mut account,
+                &mut account2_account,
+                &mut multisig_delegate_account,
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+            ],
+        )
+        .unwrap();
+
+        // mint to
+        let account_info_iter = &mut signer_accounts.iter_mut();
+        do_process_instruction(
+            mint_to(
                 &program_id,
-                &account1_key,
                 &mint_key,
                 &account2_key,
-                &account1_key,
-                &[],
-                500,
-                2,
+                &multisig_key,
+                &[&signer_keys[0]],
+                42,
             )
             .unwrap(),
             vec![
-                account1_info.clone(),
-                mint_info.clone(),
-                account2_info.clone(),
-                account1_info.clone(),
+                &mut mint_account,
+                &mut account2_account,
+                &mut multisig_account,
+                &mut account_info_iter.next().unwrap(),
             ],
         )
         .unwrap();
 
-        // source-delegate transfer
-        let mut account = Account::unpack_unchecked(&account1_info.data.borrow()).unwrap();
-        account.amount = 1000;
-        account.delegated_amount = 1000;
-        account.delegate = COption::Some(account1_key);
-        account.owner = owner_key;
-        Account::pack(account, &mut account1_info.data.borrow_mut()).unwrap();
+        // burn
+        let account_info_iter = &mut signer_accounts.iter_mut();
+        do_process_instruction(
+            burn(
+                &program_id,
+                &account_key,
+                &mint_key,
+                &multisig_key,
+                &[&signer_keys[0]],
+                42,
+            )
+            .unwrap(),
+            vec![
+                &mut account,
+                &mut mint_account,
+                &mut multisig_account,
+                &mut account_info_iter.next().unwrap(),
+            ],
+        )
+        .unwrap();
 
-        do_process_instruction_dups(
-            transfer(
+        // burn via delegate
+        let account_info_iter = &mut signer_accounts.iter_mut();
+        do_process_instruction(
+            burn(
                 &program_id,
-                &account1_key,
-                &account2_key,
-                &account1_key,
-                &[],
-                500,
+                &account_key,
+                &mint_key,
+                &multisig_delegate_key,
+                &signer_key_refs,
+                42,
             )
             .unwrap(),
             vec![
-                account1_info.clone(),
-                account2_info.clone(),
-                account1_info.clone(),
+                &mut account,
+                &mut mint_account,
+                &mut multisig_delegate_account,
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
             ],
         )
         .unwrap();
 
-        // source-delegate TransferChecked
-        do_process_instruction_dups(
-            transfer_checked(
+        // freeze account
+        let account3_key = Pubkey::new_unique();
+        let mut account3_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let mint2_key = Pubkey::new_unique();
+        let mut mint2_account =
+            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        do_process_instruction(
+            initialize_mint(
+                &program_id,
+                &mint2_key,
+                &multisig_key,
+                Some(&multisig_key),
+                2,
+            )
+            .unwrap(),
+            vec![&mut mint2_account, &mut rent_sysvar],
+        )
+        .unwrap();
+        do_process_instruction(
+            initialize_account(&program_id, &account3_key, &mint2_key, &owner_key).unwrap(),
+            vec![
+                &mut account3_account,
+                &mut mint2_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+        let account_info_iter = &mut signer_accounts.iter_mut();
+        do_process_instruction(
+            mint_to(
                 &program_id,
-                &account1_key,
-                &mint_key,
-                &account2_key,
-                &account1_key,
-                &[],
-                500,
-                2,
+                &mint2_key,
+                &account3_key,
+                &multisig_key,
+                &[&signer_keys[0]],
+                1000,
             )
             .unwrap(),
             vec![
-                account1_info.clone(),
-                mint_info.clone(),
-                account2_info.clone(),
-                account1_info.clone(),
+                &mut mint2_account,
+                &mut account3_account,
+                &mut multisig_account,
+                &mut account_info_iter.next().unwrap(),
             ],
         )
         .unwrap();
-
-        // test destination-owner transfer
-        do_process_instruction_dups(
-            initialize_account(&program_id, &account3_key, &mint_key, &account2_key).unwrap(),
+        let account_info_iter = &mut signer_accounts.iter_mut();
+        do_process_instruction(
+            freeze_account(
+                &program_id,
+                &account3_key,
+                &mint2_key,
+                &multisig_key,
+                &[&signer_keys[0]],
+            )
+            .unwrap(),
             vec![
-                account3_info.clone(),
-                mint_info.clone(),
-                account2_info.clone(),
-                rent_info.clone(),
+                &mut account3_account,
+                &mut mint2_account,
+                &mut multisig_account,
+                &mut account_info_iter.next().unwrap(),
             ],
         )
         .unwrap();
-        do_process_instruction_dups(
-            mint_to(&program_id, &mint_key, &account3_key, &owner_key, &[], 1000).unwrap(),
-            vec![mint_info.clone(), account3_info.clone(), owner_info.clone()],
-        )
-        .unwrap();
 
-        account1_info.is_signer = false;
-        account2_info.is_signer = true;
-        do_process_instruction_dups(
-            transfer(
+        // do SetAuthority on mint
+        let account_info_iter = &mut signer_accounts.iter_mut();
+        do_process_instruction(
+            set_authority(
                 &program_id,
-                &account3_key,
-                &account2_key,
-                &account2_key,
-                &[],
-                500,
+                &mint_key,
+                Some(&owner_key),
+                AuthorityType::MintTokens,
+                &multisig_key,
+                &[&signer_keys[0]],
             )
             .unwrap(),
             vec![
-                account3_info.clone(),
-                account2_info.clone(),
-                account2_info.clone(),
+                &mut mint_account,
+                &mut multisig_account,
+                &mut account_info_iter.next().unwrap(),
             ],
         )
         .unwrap();
 
-        // destination-owner TransferChecked
-        do_process_instruction_dups(
-            transfer_checked(
+        // do SetAuthority on account
+        let account_info_iter = &mut signer_accounts.iter_mut();
+        do_process_instruction(
+            set_authority(
                 &program_id,
-                &account3_key,
-                &mint_key,
-                &account2_key,
-                &account2_key,
-                &[],
-                500,
-                2,
+                &account_key,
+                Some(&owner_key),
+                AuthorityType::AccountOwner,
+                &multisig_key,
+                &[&signer_keys[0]],
             )
             .unwrap(),
             vec![
-                account3_info.clone(),
-                mint_info.clone(),
-                account2_info.clone(),
-                account2_info.clone(),
+                &mut account,
+                &mut multisig_account,
+                &mut account_info_iter.next().unwrap(),
             ],
         )
         .unwrap();
+    }
+
+    #[test]
+    fn test_validate_owner() {
+        let program_id = crate::id();
+        let owner_key = Pubkey::new_unique();
+        let mut signer_keys = [Pubkey::default(); MAX_SIGNERS];
+        for signer_key in signer_keys.iter_mut().take(MAX_SIGNERS) {
+            *signer_key = Pubkey::new_unique();
+        }
+        let mut signer_lamports = 0;
+        let mut signer_data = vec![];
+        let mut signers = vec![
+            AccountInfo::new(
+                &owner_key,
+                true,
+                false,
+                &mut signer_lamports,
+                &mut signer_data,
+                &program_id,
+                false,
+                Epoch::default(),
+            );
+            MAX_SIGNERS + 1
+        ];
+        for (signer, key) in signers.iter_mut().zip(&signer_keys) {
+            signer.key = key;
+        }
+        let mut lamports = 0;
+        let mut data = vec![0; Multisig::get_packed_len()];
+        let mut multisig = Multisig::unpack_unchecked(&data).unwrap();
+        multisig.m = MAX_SIGNERS as u8;
+        multisig.n = MAX_SIGNERS as u8;
+        multisig.signers = signer_keys;
+        multisig.is_initialized = true;
+        Multisig::pack(multisig, &mut data).unwrap();
+        let owner_account_info = AccountInfo::new(
+            &owner_key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &program_id,
+            false,
+            Epoch::default(),
+        );
+
+        // full 11 of 11
+        Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers).unwrap();
+
+        // 1 of 11
+        {
+            let mut multisig =
+                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
+            multisig.m = 1;
+            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
+        }
+        Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers).unwrap();
+
+        // 2:1
+        {
+            let mut multisig =
+                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
+            multisig.m = 2;
+            multisig.n = 1;
+            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
+        }
+        assert_eq!(
+            Err(ProgramError::MissingRequiredSignature),
+            Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers)
+        );
+
+        // 0:11
+        {
+            let mut multisig =
+                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
+            multisig.m = 0;
+            multisig.n = 11;
+            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
+        }
+        Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers).unwrap();
+
+        // 2:11 but 0 provided
+        {
+            let mut multisig =
+                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
+            multisig.m = 2;
+            multisig.n = 11;
+            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
+        }
+        assert_eq!(
+            Err(ProgramError::MissingRequiredSignature),
+            Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &[])
+        );
+        // 2:11 but 1 provided
+        {
+            let mut multisig =
+                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
+            multisig.m = 2;
+            multisig.n = 11;
+            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
+        }
+        assert_eq!(
+            Err(ProgramError::MissingRequiredSignature),
+            Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers[0..1])
+        );
+
+        // 2:11, 2 from middle provided
+        {
+            let mut multisig =
+                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
+            multisig.m = 2;
+            multisig.n = 11;
+            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
+        }
+        Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers[5..7])
+            .unwrap();
+
+        // 11:11, one is not a signer
+        {
+            let mut multisig =
+                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
+            multisig.m = 11;
+            multisig.n = 11;
+            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
+        }
+        signers[5].is_signer = false;
+        assert_eq!(
+            Err(ProgramError::MissingRequiredSignature),
+            Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers)
+        );
+        signers[5].is_signer = true;
+
+        // 11:11, single signer signs multiple times
+        {
+            let mut signer_lamports = 0;
+            let mut signer_data = vec![];
+            let signers = vec![
+                AccountInfo::new(
+                    &signer_keys[5],
+                    true,
+                    false,
+                    &mut signer_lamports,
+                    &mut signer_data,
+                    &program_id,
+                    false,
+                    Epoch::default(),
+                );
+                MAX_SIGNERS + 1
+            ];
+            let mut multisig =
+                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
+            multisig.m = 11;
+            multisig.n = 11;
+            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
+            assert_eq!(
+                Err(ProgramError::MissingRequiredSignature),
+                Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers)
+            );
+        }
+    }
 
-        // test source-multisig signer
+    #[test]
+    fn test_close_account_dups() {
+        let program_id = crate::id();
+        let account1_key = Pubkey::new_unique();
+        let mut account1_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account1_info: AccountInfo = (&account1_key, true, &mut account1_account).into();
+        let account2_key = Pubkey::new_unique();
+        let mut account2_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account2_info: AccountInfo = (&account2_key, true, &mut account2_account).into();
+        let owner_key = Pubkey::new_unique();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mint_info: AccountInfo = (&mint_key, false, &mut mint_account).into();
+        let rent_key = rent::id();
+        let mut rent_sysvar = rent_sysvar();
+        let rent_info: AccountInfo = (&rent_key, false, &mut rent_sysvar).into();
+
+        // create mint
         do_process_instruction_dups(
-            initialize_multisig(&program_id, &multisig_key, &[&account4_key], 1).unwrap(),
-            vec![
-                multisig_info.clone(),
-                rent_info.clone(),
-                account4_info.clone(),
-            ],
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![mint_info.clone(), rent_info.clone()],
         )
         .unwrap();
 
+        // create account
         do_process_instruction_dups(
-            initialize_account(&program_id, &account4_key, &mint_key, &multisig_key).unwrap(),
+            initialize_account(&program_id, &account1_key, &mint_key, &account1_key).unwrap(),
             vec![
-                account4_info.clone(),
+                account1_info.clone(),
                 mint_info.clone(),
-                multisig_info.clone(),
+                account1_info.clone(),
                 rent_info.clone(),
             ],
         )
         .unwrap();
 
+        // source-owner close
         do_process_instruction_dups(
-            mint_to(&program_id, &mint_key, &account4_key, &owner_key, &[], 1000).unwrap(),
-            vec![mint_info.clone(), account4_info.clone(), owner_info.clone()],
-        )
-        .unwrap();
-
-        // source-multisig-signer transfer
-        do_process_instruction_dups(
-            transfer(
+            close_account(
                 &program_id,
-                &account4_key,
+                &account1_key,
                 &account2_key,
-                &multisig_key,
-                &[&account4_key],
-                500,
+                &account1_key,
+                &[],
             )
             .unwrap(),
             vec![
-                account4_info.clone(),
+                account1_info.clone(),
                 account2_info.clone(),
-                multisig_info.clone(),
-                account4_info.clone(),
+                account1_info.clone(),
             ],
         )
         .unwrap();
 
-        // source-multisig-signer TransferChecked
+        // source-close-authority close
+        let mut account = Account::unpack_unchecked(&account1_info.data.borrow()).unwrap();
+        account.close_authority = COption::Some(account1_key);
+        account.owner = owner_key;
+        Account::pack(account, &mut account1_info.data.borrow_mut()).unwrap();
         do_process_instruction_dups(
-            transfer_checked(
+            close_account(
                 &program_id,
-                &account4_key,
-                &mint_key,
+                &account1_key,
                 &account2_key,
-                &multisig_key,
-                &[&account4_key],
-                500,
-                2,
+                &account1_key,
+                &[],
             )
             .unwrap(),
             vec![
-                account4_info.clone(),
-                mint_info.clone(),
+                account1_info.clone(),
                 account2_info.clone(),
-                multisig_info.clone(),
-                account4_info.clone(),
+                account1_info.clone(),
             ],
         )
         .unwrap();
     }
 
     #[test]
-    fn test_transfer() {
+    fn test_close_account() {
         let program_id = crate::id();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
         let account_key = Pubkey::new_unique();
         let mut account_account = PUTAccount::new(
             account_minimum_balance(),
@@ -1731,7 +6965,7 @@ mod tests {
         );
         let account2_key = Pubkey::new_unique();
         let mut account2_account = PUTAccount::new(
-            account_minimum_balance(),
+            account_minimum_balance() + 42,
             Account::get_packed_len(),
             &program_id,
         );
@@ -1741,32 +6975,31 @@ mod tests {
             Account::get_packed_len(),
             &program_id,
         );
-        let delegate_key = Pubkey::new_unique();
-        let mut delegate_account = PUTAccount::default();
-        let mismatch_key = Pubkey::new_unique();
-        let mut mismatch_account = PUTAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
-        );
         let owner_key = Pubkey::new_unique();
         let mut owner_account = PUTAccount::default();
         let owner2_key = Pubkey::new_unique();
         let mut owner2_account = PUTAccount::default();
-        let mint_key = Pubkey::new_unique();
-        let mut mint_account =
-            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-        let mint2_key = Pubkey::new_unique();
         let mut rent_sysvar = rent_sysvar();
 
-        // create mint
+        // uninitialized
+        assert_eq!(
+            Err(ProgramError::UninitializedAccount),
+            do_process_instruction(
+                close_account(&program_id, &account_key, &account3_key, &owner2_key, &[]).unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut account3_account,
+                    &mut owner2_account,
+                ],
+            )
+        );
+
+        // initialize and mint to non-native account
         do_process_instruction(
             initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
             vec![&mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
-
-        // create account
         do_process_instruction(
             initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
             vec![
@@ -1777,311 +7010,324 @@ mod tests {
             ],
         )
         .unwrap();
-
-        // create another account
         do_process_instruction(
-            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 42).unwrap(),
             vec![
-                &mut account2_account,
                 &mut mint_account,
+                &mut account_account,
                 &mut owner_account,
                 &mut rent_sysvar,
             ],
         )
         .unwrap();
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.amount, 42);
 
-        // create another account
+        // initialize native account
         do_process_instruction(
-            initialize_account(&program_id, &account3_key, &mint_key, &owner_key).unwrap(),
+            initialize_account(
+                &program_id,
+                &account2_key,
+                &crate::native_mint::id(),
+                &owner_key,
+            )
+            .unwrap(),
             vec![
-                &mut account3_account,
+                &mut account2_account,
                 &mut mint_account,
                 &mut owner_account,
                 &mut rent_sysvar,
             ],
         )
         .unwrap();
+        let account = Account::unpack_unchecked(&account2_account.data).unwrap();
+        assert!(account.is_native());
+        assert_eq!(account.amount, 42);
 
-        // create mismatch account
+        // close non-native account with balance
+        assert_eq!(
+            Err(TokenError::NonNativeHasBalance.into()),
+            do_process_instruction(
+                close_account(&program_id, &account_key, &account3_key, &owner_key, &[]).unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut account3_account,
+                    &mut owner_account,
+                ],
+            )
+        );
+        assert_eq!(account_account.lamports, account_minimum_balance());
+
+        // empty account
         do_process_instruction(
-            initialize_account(&program_id, &mismatch_key, &mint_key, &owner_key).unwrap(),
-            vec![
-                &mut mismatch_account,
-                &mut mint_account,
-                &mut owner_account,
-                &mut rent_sysvar,
-            ],
+            burn(&program_id, &account_key, &mint_key, &owner_key, &[], 42).unwrap(),
+            vec![&mut account_account, &mut mint_account, &mut owner_account],
         )
         .unwrap();
-        let mut account = Account::unpack_unchecked(&mismatch_account.data).unwrap();
-        account.mint = mint2_key;
-        Account::pack(account, &mut mismatch_account.data).unwrap();
 
-        // mint to account
+        // wrong owner
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                close_account(&program_id, &account_key, &account3_key, &owner2_key, &[]).unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut account3_account,
+                    &mut owner2_account,
+                ],
+            )
+        );
+
+        // close account
         do_process_instruction(
-            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1000).unwrap(),
-            vec![&mut mint_account, &mut account_account, &mut owner_account],
+            close_account(&program_id, &account_key, &account3_key, &owner_key, &[]).unwrap(),
+            vec![
+                &mut account_account,
+                &mut account3_account,
+                &mut owner_account,
+            ],
         )
         .unwrap();
+        assert_eq!(account_account.lamports, 0);
+        assert_eq!(account3_account.lamports, 2 * account_minimum_balance());
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.amount, 0);
 
-        // missing signer
-        let mut instruction = transfer(
+        // fund and initialize new non-native account to test close authority
+        let account_key = Pubkey::new_unique();
+        let mut account_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
             &program_id,
-            &account_key,
-            &account2_key,
-            &owner_key,
-            &[],
-            1000,
+        );
+        let owner2_key = Pubkey::new_unique();
+        let mut owner2_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let mut meta_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        do_process_instruction(
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
         )
         .unwrap();
-        instruction.accounts[2].is_signer = false;
-        assert_eq!(
-            Err(ProgramError::MissingRequiredSignature),
-            do_process_instruction(
-                instruction,
-                vec![
-                    &mut account_account,
-                    &mut account2_account,
-                    &mut owner_account,
-                ],
+        account_account.lamports = 2;
+
+        do_process_instruction(
+            set_authority(
+                &program_id,
+                &account_key,
+                Some(&owner2_key),
+                AuthorityType::CloseAccount,
+                &owner_key,
+                &[],
             )
-        );
+            .unwrap(),
+            vec![&mut account_account, &mut meta_account, &mut owner_account],
+        )
+        .unwrap();
 
-        // mismatch mint
+        // account owner cannot authorize close if close_authority is set
         assert_eq!(
-            Err(TokenError::MintMismatch.into()),
+            Err(TokenError::OwnerMismatch.into()),
             do_process_instruction(
-                transfer(
-                    &program_id,
-                    &account_key,
-                    &mismatch_key,
-                    &owner_key,
-                    &[],
-                    1000
-                )
-                .unwrap(),
+                close_account(&program_id, &account_key, &account3_key, &owner_key, &[]).unwrap(),
                 vec![
                     &mut account_account,
-                    &mut mismatch_account,
+                    &mut account3_account,
                     &mut owner_account,
                 ],
             )
         );
 
-        // missing owner
+        // close non-native account with close_authority
+        do_process_instruction(
+            close_account(&program_id, &account_key, &account3_key, &owner2_key, &[]).unwrap(),
+            vec![
+                &mut account_account,
+                &mut account3_account,
+                &mut owner2_account,
+            ],
+        )
+        .unwrap();
+        assert_eq!(account_account.lamports, 0);
+        assert_eq!(account3_account.lamports, 2 * account_minimum_balance() + 2);
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.amount, 0);
+
+        // close native account
+        do_process_instruction(
+            close_account(&program_id, &account2_key, &account3_key, &owner_key, &[]).unwrap(),
+            vec![
+                &mut account2_account,
+                &mut account3_account,
+                &mut owner_account,
+            ],
+        )
+        .unwrap();
+        let account = Account::unpack_unchecked(&account2_account.data).unwrap();
+        assert!(account.is_native());
+        assert_eq!(account_account.lamports, 0);
+        assert_eq!(account.amount, 0);
         assert_eq!(
-            Err(TokenError::OwnerMismatch.into()),
-            do_process_instruction(
-                transfer(
-                    &program_id,
-                    &account_key,
-                    &account2_key,
-                    &owner2_key,
-                    &[],
-                    1000
-                )
-                .unwrap(),
-                vec![
-                    &mut account_account,
-                    &mut account2_account,
-                    &mut owner2_account,
-                ],
-            )
+            account3_account.lamports,
+            3 * account_minimum_balance() + 2 + 42
         );
+    }
 
-        // transfer
+    #[test]
+    fn test_native_token() {
+        let program_id = crate::id();
+        let mut mint_account =
+            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let account_key = Pubkey::new_unique();
+        let mut account_account = PUTAccount::new(
+            account_minimum_balance() + 40,
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account2_key = Pubkey::new_unique();
+        let mut account2_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account3_key = Pubkey::new_unique();
+        let mut account3_account = PUTAccount::new(account_minimum_balance(), 0, &program_id);
+        let owner_key = Pubkey::new_unique();
+        let mut owner_account = PUTAccount::default();
+        let owner2_key = Pubkey::new_unique();
+        let mut owner2_account = PUTAccount::default();
+        let owner3_key = Pubkey::new_unique();
+        let mut rent_sysvar = rent_sysvar();
+
+        let mut meta_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+
+        // initialize native account
         do_process_instruction(
-            transfer(
+            initialize_account(
                 &program_id,
                 &account_key,
-                &account2_key,
+                &crate::native_mint::id(),
                 &owner_key,
-                &[],
-                1000,
             )
             .unwrap(),
             vec![
                 &mut account_account,
-                &mut account2_account,
+                &mut mint_account,
                 &mut owner_account,
+                &mut rent_sysvar,
             ],
         )
         .unwrap();
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert!(account.is_native());
+        assert_eq!(account.amount, 40);
 
-        // insufficient funds
-        assert_eq!(
-            Err(TokenError::InsufficientFunds.into()),
-            do_process_instruction(
-                transfer(&program_id, &account_key, &account2_key, &owner_key, &[], 1).unwrap(),
-                vec![
-                    &mut account_account,
-                    &mut account2_account,
-                    &mut owner_account,
-                ],
-            )
-        );
-
-        // transfer half back
+        // initialize native account
         do_process_instruction(
-            transfer(
+            initialize_account(
                 &program_id,
                 &account2_key,
-                &account_key,
+                &crate::native_mint::id(),
                 &owner_key,
-                &[],
-                500,
             )
             .unwrap(),
             vec![
                 &mut account2_account,
-                &mut account_account,
+                &mut mint_account,
                 &mut owner_account,
+                &mut rent_sysvar,
             ],
         )
         .unwrap();
+        let account = Account::unpack_unchecked(&account2_account.data).unwrap();
+        assert!(account.is_native());
+        assert_eq!(account.amount, 0);
 
-        // incorrect decimals
+        // mint_to unsupported
         assert_eq!(
-            Err(TokenError::MintDecimalsMismatch.into()),
+            Err(TokenError::NativeNotSupported.into()),
             do_process_instruction(
-                transfer_checked(
+                mint_to(
                     &program_id,
-                    &account2_key,
-                    &mint_key,
+                    &crate::native_mint::id(),
                     &account_key,
                     &owner_key,
                     &[],
-                    1,
-                    10 // <-- incorrect decimals
+                    42
                 )
                 .unwrap(),
-                vec![
-                    &mut account2_account,
-                    &mut mint_account,
-                    &mut account_account,
-                    &mut owner_account,
-                ],
+                vec![&mut mint_account, &mut account_account, &mut owner_account],
             )
         );
 
-        // incorrect mint
+        // burn unsupported
+        let bogus_mint_key = Pubkey::new_unique();
+        let mut bogus_mint_account =
+            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        do_process_instruction(
+            initialize_mint(&program_id, &bogus_mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut bogus_mint_account, &mut rent_sysvar],
+        )
+        .unwrap();
+
         assert_eq!(
-            Err(TokenError::MintMismatch.into()),
+            Err(TokenError::NativeNotSupported.into()),
             do_process_instruction(
-                transfer_checked(
+                burn(
                     &program_id,
-                    &account2_key,
-                    &account3_key, // <-- incorrect mint
                     &account_key,
+                    &bogus_mint_key,
                     &owner_key,
                     &[],
-                    1,
-                    2
+                    42
                 )
                 .unwrap(),
                 vec![
-                    &mut account2_account,
-                    &mut account3_account, // <-- incorrect mint
-                    &mut account_account,
-                    &mut owner_account,
-                ],
-            )
-        );
-        // transfer rest with explicit decimals
-        do_process_instruction(
-            transfer_checked(
-                &program_id,
-                &account2_key,
-                &mint_key,
-                &account_key,
-                &owner_key,
-                &[],
-                500,
-                2,
-            )
-            .unwrap(),
-            vec![
-                &mut account2_account,
-                &mut mint_account,
-                &mut account_account,
-                &mut owner_account,
-            ],
-        )
-        .unwrap();
-
-        // insufficient funds
-        assert_eq!(
-            Err(TokenError::InsufficientFunds.into()),
-            do_process_instruction(
-                transfer(&program_id, &account2_key, &account_key, &owner_key, &[], 1).unwrap(),
-                vec![
-                    &mut account2_account,
                     &mut account_account,
-                    &mut owner_account,
-                ],
-            )
-        );
-
-        // approve delegate
-        do_process_instruction(
-            approve(
-                &program_id,
-                &account_key,
-                &delegate_key,
-                &owner_key,
-                &[],
-                100,
-            )
-            .unwrap(),
-            vec![
-                &mut account_account,
-                &mut delegate_account,
-                &mut owner_account,
-            ],
-        )
-        .unwrap();
-
-        // transfer via delegate
-        do_process_instruction(
-            transfer(
-                &program_id,
-                &account_key,
-                &account2_key,
-                &delegate_key,
-                &[],
-                100,
+                    &mut bogus_mint_account,
+                    &mut owner_account
+                ],
             )
-            .unwrap(),
-            vec![
-                &mut account_account,
-                &mut account2_account,
-                &mut delegate_account,
-            ],
-        )
-        .unwrap();
+        );
 
-        // insufficient funds approved via delegate
+        // ensure can't transfer below rent-exempt reserve
         assert_eq!(
-            Err(TokenError::OwnerMismatch.into()),
+            Err(TokenError::InsufficientFunds.into()),
             do_process_instruction(
                 transfer(
                     &program_id,
                     &account_key,
                     &account2_key,
-                    &delegate_key,
+                    &owner_key,
                     &[],
-                    100
+                    50,
                 )
                 .unwrap(),
                 vec![
                     &mut account_account,
                     &mut account2_account,
-                    &mut delegate_account,
+                    &mut owner_account,
                 ],
             )
         );
 
-        // transfer rest
+        // transfer between native accounts
         do_process_instruction(
             transfer(
                 &program_id,
@@ -2089,7 +7335,7 @@ mod tests {
                 &account2_key,
                 &owner_key,
                 &[],
-                900,
+                40,
             )
             .unwrap(),
             vec![
@@ -2099,50 +7345,70 @@ mod tests {
             ],
         )
         .unwrap();
+        assert_eq!(account_account.lamports, account_minimum_balance());
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert!(account.is_native());
+        assert_eq!(account.amount, 0);
+        assert_eq!(account2_account.lamports, account_minimum_balance() + 40);
+        let account = Account::unpack_unchecked(&account2_account.data).unwrap();
+        assert!(account.is_native());
+        assert_eq!(account.amount, 40);
+
+        // set close authority
+        do_process_instruction(
+            set_authority(
+                &program_id,
+                &account_key,
+                Some(&owner3_key),
+                AuthorityType::CloseAccount,
+                &owner_key,
+                &[],
+            )
+            .unwrap(),
+            vec![&mut account_account, &mut meta_account, &mut owner_account],
+        )
+        .unwrap();
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.close_authority, COption::Some(owner3_key));
 
-        // approve delegate
+        // set new account owner
         do_process_instruction(
-            approve(
+            set_authority(
                 &program_id,
                 &account_key,
-                &delegate_key,
+                Some(&owner2_key),
+                AuthorityType::AccountOwner,
                 &owner_key,
                 &[],
-                100,
             )
             .unwrap(),
+            vec![&mut account_account, &mut meta_account, &mut owner_account],
+        )
+        .unwrap();
+
+        // close authority cleared
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.close_authority, COption::None);
+
+        // close native account
+        do_process_instruction(
+            close_account(&program_id, &account_key, &account3_key, &owner2_key, &[]).unwrap(),
             vec![
                 &mut account_account,
-                &mut delegate_account,
-                &mut owner_account,
+                &mut account3_account,
+                &mut owner2_account,
             ],
         )
         .unwrap();
-
-        // insufficient funds in source account via delegate
-        assert_eq!(
-            Err(TokenError::InsufficientFunds.into()),
-            do_process_instruction(
-                transfer(
-                    &program_id,
-                    &account_key,
-                    &account2_key,
-                    &delegate_key,
-                    &[],
-                    100
-                )
-                .unwrap(),
-                vec![
-                    &mut account_account,
-                    &mut account2_account,
-                    &mut delegate_account,
-                ],
-            )
-        );
+        assert_eq!(account_account.lamports, 0);
+        assert_eq!(account3_account.lamports, 2 * account_minimum_balance());
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert!(account.is_native());
+        assert_eq!(account.amount, 0);
     }
 
     #[test]
-    fn test_self_transfer() {
+    fn test_overflow() {
         let program_id = crate::id();
         let account_key = Pubkey::new_unique();
         let mut account_account = PUTAccount::new(
@@ -2156,622 +7422,362 @@ mod tests {
             Account::get_packed_len(),
             &program_id,
         );
-        let account3_key = Pubkey::new_unique();
-        let mut account3_account = PUTAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
-        );
-        let delegate_key = Pubkey::new_unique();
-        let mut delegate_account = PUTAccount::default();
         let owner_key = Pubkey::new_unique();
         let mut owner_account = PUTAccount::default();
         let owner2_key = Pubkey::new_unique();
         let mut owner2_account = PUTAccount::default();
+        let mint_owner_key = Pubkey::new_unique();
+        let mut mint_owner_account = PUTAccount::default();
         let mint_key = Pubkey::new_unique();
         let mut mint_account =
             PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
         let mut rent_sysvar = rent_sysvar();
 
-        // create mint
+        // create new mint with owner
         do_process_instruction(
-            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            initialize_mint(&program_id, &mint_key, &mint_owner_key, None, 2).unwrap(),
             vec![&mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
 
-        // create account
-        do_process_instruction(
-            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
-            vec![
-                &mut account_account,
-                &mut mint_account,
-                &mut owner_account,
-                &mut rent_sysvar,
-            ],
-        )
-        .unwrap();
-
-        // create another account
-        do_process_instruction(
-            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
-            vec![
-                &mut account2_account,
-                &mut mint_account,
-                &mut owner_account,
-                &mut rent_sysvar,
-            ],
-        )
-        .unwrap();
-
-        // create another account
-        do_process_instruction(
-            initialize_account(&program_id, &account3_key, &mint_key, &owner_key).unwrap(),
-            vec![
-                &mut account3_account,
-                &mut mint_account,
-                &mut owner_account,
-                &mut rent_sysvar,
-            ],
-        )
-        .unwrap();
-
-        // mint to account
+        // create an account
         do_process_instruction(
-            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1000).unwrap(),
-            vec![&mut mint_account, &mut account_account, &mut owner_account],
-        )
-        .unwrap();
-
-        let account_info = (&account_key, false, &mut account_account).into_account_info();
-        let account3_info = (&account3_key, false, &mut account3_account).into_account_info();
-        let delegate_info = (&delegate_key, true, &mut delegate_account).into_account_info();
-        let owner_info = (&owner_key, true, &mut owner_account).into_account_info();
-        let owner2_info = (&owner2_key, true, &mut owner2_account).into_account_info();
-        let mint_info = (&mint_key, false, &mut mint_account).into_account_info();
-
-        // transfer
-        let instruction = transfer(
-            &program_id,
-            account_info.key,
-            account_info.key,
-            owner_info.key,
-            &[],
-            1000,
-        )
-        .unwrap();
-        assert_eq!(
-            Ok(()),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    account_info.clone(),
-                    owner_info.clone(),
-                ],
-                &instruction.data,
-            )
-        );
-        // no balance change...
-        let account = Account::unpack_unchecked(&account_info.try_borrow_data().unwrap()).unwrap();
-        assert_eq!(account.amount, 1000);
-
-        // transfer checked
-        let instruction = transfer_checked(
-            &program_id,
-            account_info.key,
-            mint_info.key,
-            account_info.key,
-            owner_info.key,
-            &[],
-            1000,
-            2,
-        )
-        .unwrap();
-        assert_eq!(
-            Ok(()),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    mint_info.clone(),
-                    account_info.clone(),
-                    owner_info.clone(),
-                ],
-                &instruction.data,
-            )
-        );
-        // no balance change...
-        let account = Account::unpack_unchecked(&account_info.try_borrow_data().unwrap()).unwrap();
-        assert_eq!(account.amount, 1000);
-
-        // missing signer
-        let mut owner_no_sign_info = owner_info.clone();
-        let mut instruction = transfer(
-            &program_id,
-            account_info.key,
-            account_info.key,
-            owner_no_sign_info.key,
-            &[],
-            1000,
-        )
-        .unwrap();
-        instruction.accounts[2].is_signer = false;
-        owner_no_sign_info.is_signer = false;
-        assert_eq!(
-            Err(ProgramError::MissingRequiredSignature),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    account_info.clone(),
-                    owner_no_sign_info.clone(),
-                ],
-                &instruction.data,
-            )
-        );
-
-        // missing signer checked
-        let mut instruction = transfer_checked(
-            &program_id,
-            account_info.key,
-            mint_info.key,
-            account_info.key,
-            owner_no_sign_info.key,
-            &[],
-            1000,
-            2,
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
         )
         .unwrap();
-        instruction.accounts[3].is_signer = false;
-        assert_eq!(
-            Err(ProgramError::MissingRequiredSignature),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    mint_info.clone(),
-                    account_info.clone(),
-                    owner_no_sign_info,
-                ],
-                &instruction.data,
-            )
-        );
 
-        // missing owner
-        let instruction = transfer(
-            &program_id,
-            account_info.key,
-            account_info.key,
-            owner2_info.key,
-            &[],
-            1000,
+        // create another account
+        do_process_instruction(
+            initialize_account(&program_id, &account2_key, &mint_key, &owner2_key).unwrap(),
+            vec![
+                &mut account2_account,
+                &mut mint_account,
+                &mut owner2_account,
+                &mut rent_sysvar,
+            ],
         )
         .unwrap();
-        assert_eq!(
-            Err(TokenError::OwnerMismatch.into()),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    account_info.clone(),
-                    owner2_info.clone(),
-                ],
-                &instruction.data,
-            )
-        );
 
-        // missing owner checked
-        let instruction = transfer_checked(
-            &program_id,
-            account_info.key,
-            mint_info.key,
-            account_info.key,
-            owner2_info.key,
-            &[],
-            1000,
-            2,
+        // mint the max to an account
+        do_process_instruction(
+            mint_to(
+                &program_id,
+                &mint_key,
+                &account_key,
+                &mint_owner_key,
+                &[],
+                u128::MAX,
+            )
+            .unwrap(),
+            vec![
+                &mut mint_account,
+                &mut account_account,
+                &mut mint_owner_account,
+            ],
         )
         .unwrap();
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.amount, u128::MAX);
+
+        // attempt to mint one more to account
         assert_eq!(
-            Err(TokenError::OwnerMismatch.into()),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    mint_info.clone(),
-                    account_info.clone(),
-                    owner2_info.clone(),
+            Err(TokenError::Overflow.into()),
+            do_process_instruction(
+                mint_to(
+                    &program_id,
+                    &mint_key,
+                    &account_key,
+                    &mint_owner_key,
+                    &[],
+                    1,
+                )
+                .unwrap(),
+                vec![
+                    &mut mint_account,
+                    &mut account_account,
+                    &mut mint_owner_account,
                 ],
-                &instruction.data,
             )
         );
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.amount, u128::MAX);
 
-        // insufficient funds
-        let instruction = transfer(
-            &program_id,
-            account_info.key,
-            account_info.key,
-            owner_info.key,
-            &[],
-            1001,
-        )
-        .unwrap();
+        // atttempt to mint one more to the other account
         assert_eq!(
-            Err(TokenError::InsufficientFunds.into()),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    account_info.clone(),
-                    owner_info.clone(),
+            Err(TokenError::Overflow.into()),
+            do_process_instruction(
+                mint_to(
+                    &program_id,
+                    &mint_key,
+                    &account2_key,
+                    &mint_owner_key,
+                    &[],
+                    1,
+                )
+                .unwrap(),
+                vec![
+                    &mut mint_account,
+                    &mut account2_account,
+                    &mut mint_owner_account,
                 ],
-                &instruction.data,
             )
         );
 
-        // insufficient funds checked
-        let instruction = transfer_checked(
-            &program_id,
-            account_info.key,
-            mint_info.key,
-            account_info.key,
-            owner_info.key,
-            &[],
-            1001,
-            2,
+        // burn some of the supply
+        do_process_instruction(
+            burn(&program_id, &account_key, &mint_key, &owner_key, &[], 100).unwrap(),
+            vec![&mut account_account, &mut mint_account, &mut owner_account],
         )
         .unwrap();
-        assert_eq!(
-            Err(TokenError::InsufficientFunds.into()),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    mint_info.clone(),
-                    account_info.clone(),
-                    owner_info.clone(),
-                ],
-                &instruction.data,
-            )
-        );
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.amount, u128::MAX - 100);
 
-        // incorrect decimals
-        let instruction = transfer_checked(
-            &program_id,
-            account_info.key,
-            mint_info.key,
-            account_info.key,
-            owner_info.key,
-            &[],
-            1,
-            10, // <-- incorrect decimals
+        do_process_instruction(
+            mint_to(
+                &program_id,
+                &mint_key,
+                &account_key,
+                &mint_owner_key,
+                &[],
+                100,
+            )
+            .unwrap(),
+            vec![
+                &mut mint_account,
+                &mut account_account,
+                &mut mint_owner_account,
+            ],
         )
         .unwrap();
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.amount, u128::MAX);
+
+        // manipulate account balance to attempt overflow transfer
+        let mut account = Account::unpack_unchecked(&account2_account.data).unwrap();
+        account.amount = 1;
+        Account::pack(account, &mut account2_account.data).unwrap();
+
         assert_eq!(
-            Err(TokenError::MintDecimalsMismatch.into()),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    mint_info.clone(),
-                    account_info.clone(),
-                    owner_info.clone(),
+            Err(TokenError::Overflow.into()),
+            do_process_instruction(
+                transfer(
+                    &program_id,
+                    &account2_key,
+                    &account_key,
+                    &owner2_key,
+                    &[],
+                    1,
+                )
+                .unwrap(),
+                vec![
+                    &mut account2_account,
+                    &mut account_account,
+                    &mut owner2_account,
                 ],
-                &instruction.data,
             )
         );
+    }
 
-        // incorrect mint
-        let instruction = transfer_checked(
-            &program_id,
-            account_info.key,
-            account3_info.key, // <-- incorrect mint
-            account_info.key,
-            owner_info.key,
-            &[],
-            1,
-            2,
-        )
-        .unwrap();
-        assert_eq!(
-            Err(TokenError::MintMismatch.into()),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    account3_info.clone(), // <-- incorrect mint
-                    account_info.clone(),
-                    owner_info.clone(),
-                ],
-                &instruction.data,
-            )
+    #[test]
+    fn test_frozen() {
+        let program_id = crate::id();
+        let account_key = Pubkey::new_unique();
+        let mut account_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
         );
-
-        // approve delegate
-        let instruction = approve(
+        let account2_key = Pubkey::new_unique();
+        let mut account2_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
             &program_id,
-            account_info.key,
-            delegate_info.key,
-            owner_info.key,
-            &[],
-            100,
+        );
+        let owner_key = Pubkey::new_unique();
+        let mut owner_account = PUTAccount::default();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mut rent_sysvar = rent_sysvar();
+
+        // create new mint and fund first account
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
-        Processor::process(
-            &instruction.program_id,
-            &[
-                account_info.clone(),
-                delegate_info.clone(),
-                owner_info.clone(),
+
+        // create account
+        do_process_instruction(
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
             ],
-            &instruction.data,
         )
         .unwrap();
 
-        // delegate transfer
-        let instruction = transfer(
-            &program_id,
-            account_info.key,
-            account_info.key,
-            delegate_info.key,
-            &[],
-            100,
+        // create another account
+        do_process_instruction(
+            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account2_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
         )
         .unwrap();
-        assert_eq!(
-            Ok(()),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    account_info.clone(),
-                    delegate_info.clone(),
-                ],
-                &instruction.data,
-            )
-        );
-        // no balance change...
-        let account = Account::unpack_unchecked(&account_info.try_borrow_data().unwrap()).unwrap();
-        assert_eq!(account.amount, 1000);
-        assert_eq!(account.delegated_amount, 100);
 
-        // delegate transfer checked
-        let instruction = transfer_checked(
-            &program_id,
-            account_info.key,
-            mint_info.key,
-            account_info.key,
-            delegate_info.key,
-            &[],
-            100,
-            2,
+        // fund first account
+        do_process_instruction(
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1000).unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
         )
         .unwrap();
-        assert_eq!(
-            Ok(()),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    mint_info.clone(),
-                    account_info.clone(),
-                    delegate_info.clone(),
-                ],
-                &instruction.data,
-            )
-        );
-        // no balance change...
-        let account = Account::unpack_unchecked(&account_info.try_borrow_data().unwrap()).unwrap();
-        assert_eq!(account.amount, 1000);
-        assert_eq!(account.delegated_amount, 100);
 
-        // delegate insufficient funds
-        let instruction = transfer(
-            &program_id,
-            account_info.key,
-            account_info.key,
-            delegate_info.key,
-            &[],
-            101,
-        )
-        .unwrap();
+        // no transfer if either account is frozen
+        let mut account = Account::unpack_unchecked(&account2_account.data).unwrap();
+        account.state = AccountState::Frozen;
+        Account::pack(account, &mut account2_account.data).unwrap();
         assert_eq!(
-            Err(TokenError::InsufficientFunds.into()),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    account_info.clone(),
-                    delegate_info.clone(),
+            Err(TokenError::AccountFrozen.into()),
+            do_process_instruction(
+                transfer(
+                    &program_id,
+                    &account_key,
+                    &account2_key,
+                    &owner_key,
+                    &[],
+                    500,
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut account2_account,
+                    &mut owner_account,
                 ],
-                &instruction.data,
             )
         );
 
-        // delegate insufficient funds checked
-        let instruction = transfer_checked(
-            &program_id,
-            account_info.key,
-            mint_info.key,
-            account_info.key,
-            delegate_info.key,
-            &[],
-            101,
-            2,
-        )
-        .unwrap();
+        let mut account = Account::unpack_unchecked(&account_account.data).unwrap();
+        account.state = AccountState::Initialized;
+        Account::pack(account, &mut account_account.data).unwrap();
+        let mut account = Account::unpack_unchecked(&account2_account.data).unwrap();
+        account.state = AccountState::Frozen;
+        Account::pack(account, &mut account2_account.data).unwrap();
         assert_eq!(
-            Err(TokenError::InsufficientFunds.into()),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    mint_info.clone(),
-                    account_info.clone(),
-                    delegate_info.clone(),
+            Err(TokenError::AccountFrozen.into()),
+            do_process_instruction(
+                transfer(
+                    &program_id,
+                    &account_key,
+                    &account2_key,
+                    &owner_key,
+                    &[],
+                    500,
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut account2_account,
+                    &mut owner_account,
                 ],
-                &instruction.data,
             )
         );
 
-        // owner transfer with delegate assigned
-        let instruction = transfer(
-            &program_id,
-            account_info.key,
-            account_info.key,
-            owner_info.key,
-            &[],
-            1000,
-        )
-        .unwrap();
+        // no approve if account is frozen
+        let mut account = Account::unpack_unchecked(&account_account.data).unwrap();
+        account.state = AccountState::Frozen;
+        Account::pack(account, &mut account_account.data).unwrap();
+        let delegate_key = Pubkey::new_unique();
+        let mut delegate_account = PUTAccount::default();
         assert_eq!(
-            Ok(()),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    account_info.clone(),
-                    owner_info.clone(),
+            Err(TokenError::AccountFrozen.into()),
+            do_process_instruction(
+                approve(
+                    &program_id,
+                    &account_key,
+                    &delegate_key,
+                    &owner_key,
+                    &[],
+                    100
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut delegate_account,
+                    &mut owner_account,
                 ],
-                &instruction.data,
             )
         );
-        // no balance change...
-        let account = Account::unpack_unchecked(&account_info.try_borrow_data().unwrap()).unwrap();
-        assert_eq!(account.amount, 1000);
 
-        // owner transfer with delegate assigned checked
-        let instruction = transfer_checked(
-            &program_id,
-            account_info.key,
-            mint_info.key,
-            account_info.key,
-            owner_info.key,
-            &[],
-            1000,
-            2,
-        )
-        .unwrap();
+        // no revoke if account is frozen
+        let mut account = Account::unpack_unchecked(&account_account.data).unwrap();
+        account.delegate = COption::Some(delegate_key);
+        account.delegated_amount = 100;
+        Account::pack(account, &mut account_account.data).unwrap();
         assert_eq!(
-            Ok(()),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    mint_info.clone(),
-                    account_info.clone(),
-                    owner_info.clone(),
-                ],
-                &instruction.data,
+            Err(TokenError::AccountFrozen.into()),
+            do_process_instruction(
+                revoke(&program_id, &account_key, &owner_key, &[]).unwrap(),
+                vec![&mut account_account, &mut owner_account],
             )
         );
-        // no balance change...
-        let account = Account::unpack_unchecked(&account_info.try_borrow_data().unwrap()).unwrap();
-        assert_eq!(account.amount, 1000);
-    }
 
-    #[test]
-    fn test_mintable_token_with_zero_supply() {
-        let program_id = crate::id();
-        let account_key = Pubkey::new_unique();
-        let mut account_account = PUTAccount::new(
+        let mut meta_account = PUTAccount::new(
             account_minimum_balance(),
             Account::get_packed_len(),
             &program_id,
         );
-        let owner_key = Pubkey::new_unique();
-        let mut owner_account = PUTAccount::default();
-        let mint_key = Pubkey::new_unique();
-        let mut mint_account =
-            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-        let mut rent_sysvar = rent_sysvar();
-
-        // create mint-able token with zero supply
-        let decimals = 2;
-        do_process_instruction(
-            initialize_mint(&program_id, &mint_key, &owner_key, None, decimals).unwrap(),
-            vec![&mut mint_account, &mut rent_sysvar],
-        )
-        .unwrap();
-        let mint = Mint::unpack_unchecked(&mint_account.data).unwrap();
-        assert_eq!(
-            mint,
-            Mint {
-                mint_authority: COption::Some(owner_key),
-                supply: 0,
-                decimals,
-                is_initialized: true,
-                freeze_authority: COption::None
-            }
-        );
-
-        // create account
-        do_process_instruction(
-            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
-            vec![
-                &mut account_account,
-                &mut mint_account,
-                &mut owner_account,
-                &mut rent_sysvar,
-            ],
-        )
-        .unwrap();
-
-        // mint to
-        do_process_instruction(
-            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 42).unwrap(),
-            vec![&mut mint_account, &mut account_account, &mut owner_account],
-        )
-        .unwrap();
-        let _ = Mint::unpack(&mint_account.data).unwrap();
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.amount, 42);
 
-        // mint to 2, with incorrect decimals
+        // no set authority if account is frozen
+        let new_owner_key = Pubkey::new_unique();
         assert_eq!(
-            Err(TokenError::MintDecimalsMismatch.into()),
+            Err(TokenError::AccountFrozen.into()),
             do_process_instruction(
-                mint_to_checked(
+                set_authority(
                     &program_id,
-                    &mint_key,
                     &account_key,
+                    Some(&new_owner_key),
+                    AuthorityType::AccountOwner,
                     &owner_key,
-                    &[],
-                    42,
-                    decimals + 1
+                    &[]
                 )
                 .unwrap(),
-                vec![&mut mint_account, &mut account_account, &mut owner_account],
+                vec![&mut account_account, &mut meta_account, &mut owner_account,],
             )
         );
 
-        let _ = Mint::unpack(&mint_account.data).unwrap();
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.amount, 42);
+        // no mint_to if destination account is frozen
+        assert_eq!(
+            Err(TokenError::AccountFrozen.into()),
+            do_process_instruction(
+                mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 100).unwrap(),
+                vec![&mut mint_account, &mut account_account, &mut owner_account,],
+            )
+        );
 
-        // mint to 2
-        do_process_instruction(
-            mint_to_checked(
-                &program_id,
-                &mint_key,
-                &account_key,
-                &owner_key,
-                &[],
-                42,
-                decimals,
+        // no burn if account is frozen
+        assert_eq!(
+            Err(TokenError::AccountFrozen.into()),
+            do_process_instruction(
+                burn(&program_id, &account_key, &mint_key, &owner_key, &[], 100).unwrap(),
+                vec![&mut account_account, &mut mint_account, &mut owner_account],
             )
-            .unwrap(),
-            vec![&mut mint_account, &mut account_account, &mut owner_account],
-        )
-        .unwrap();
-        let _ = Mint::unpack(&mint_account.data).unwrap();
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.amount, 84);
+        );
     }
 
     #[test]
-    fn test_approve_dups() {
+    fn test_freeze_thaw_dups() {
         let program_id = crate::id();
         let account1_key = Pubkey::new_unique();
         let mut account1_account = PUTAccount::new(
@@ -2780,41 +7786,18 @@ mod tests {
             &program_id,
         );
         let account1_info: AccountInfo = (&account1_key, true, &mut account1_account).into();
-        let account2_key = Pubkey::new_unique();
-        let mut account2_account = PUTAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
-        );
-        let account2_info: AccountInfo = (&account2_key, false, &mut account2_account).into();
-        let account3_key = Pubkey::new_unique();
-        let mut account3_account = PUTAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
-        );
-        let account3_info: AccountInfo = (&account3_key, true, &mut account3_account).into();
-        let multisig_key = Pubkey::new_unique();
-        let mut multisig_account = PUTAccount::new(
-            multisig_minimum_balance(),
-            Multisig::get_packed_len(),
-            &program_id,
-        );
-        let multisig_info: AccountInfo = (&multisig_key, true, &mut multisig_account).into();
         let owner_key = Pubkey::new_unique();
-        let mut owner_account = PUTAccount::default();
-        let owner_info: AccountInfo = (&owner_key, true, &mut owner_account).into();
         let mint_key = Pubkey::new_unique();
         let mut mint_account =
             PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-        let mint_info: AccountInfo = (&mint_key, false, &mut mint_account).into();
+        let mint_info: AccountInfo = (&mint_key, true, &mut mint_account).into();
         let rent_key = rent::id();
         let mut rent_sysvar = rent_sysvar();
         let rent_info: AccountInfo = (&rent_key, false, &mut rent_sysvar).into();
 
         // create mint
         do_process_instruction_dups(
-            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            initialize_mint(&program_id, &mint_key, &owner_key, Some(&account1_key), 2).unwrap(),
             vec![mint_info.clone(), rent_info.clone()],
         )
         .unwrap();
@@ -2831,158 +7814,34 @@ mod tests {
         )
         .unwrap();
 
-        // create another account
-        do_process_instruction_dups(
-            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
-            vec![
-                account2_info.clone(),
-                mint_info.clone(),
-                owner_info.clone(),
-                rent_info.clone(),
-            ],
-        )
-        .unwrap();
-
-        // mint to account
-        do_process_instruction_dups(
-            mint_to(&program_id, &mint_key, &account1_key, &owner_key, &[], 1000).unwrap(),
-            vec![mint_info.clone(), account1_info.clone(), owner_info.clone()],
-        )
-        .unwrap();
-
-        // source-owner approve
-        do_process_instruction_dups(
-            approve(
-                &program_id,
-                &account1_key,
-                &account2_key,
-                &account1_key,
-                &[],
-                500,
-            )
-            .unwrap(),
-            vec![
-                account1_info.clone(),
-                account2_info.clone(),
-                account1_info.clone(),
-            ],
-        )
-        .unwrap();
-
-        // source-owner approve_checked
-        do_process_instruction_dups(
-            approve_checked(
-                &program_id,
-                &account1_key,
-                &mint_key,
-                &account2_key,
-                &account1_key,
-                &[],
-                500,
-                2,
-            )
-            .unwrap(),
-            vec![
-                account1_info.clone(),
-                mint_info.clone(),
-                account2_info.clone(),
-                account1_info.clone(),
-            ],
-        )
-        .unwrap();
-
-        // source-owner revoke
-        do_process_instruction_dups(
-            revoke(&program_id, &account1_key, &account1_key, &[]).unwrap(),
-            vec![account1_info.clone(), account1_info.clone()],
-        )
-        .unwrap();
-
-        // test source-multisig signer
-        do_process_instruction_dups(
-            initialize_multisig(&program_id, &multisig_key, &[&account3_key], 1).unwrap(),
-            vec![
-                multisig_info.clone(),
-                rent_info.clone(),
-                account3_info.clone(),
-            ],
-        )
-        .unwrap();
-
-        do_process_instruction_dups(
-            initialize_account(&program_id, &account3_key, &mint_key, &multisig_key).unwrap(),
-            vec![
-                account3_info.clone(),
-                mint_info.clone(),
-                multisig_info.clone(),
-                rent_info.clone(),
-            ],
-        )
-        .unwrap();
-
-        do_process_instruction_dups(
-            mint_to(&program_id, &mint_key, &account3_key, &owner_key, &[], 1000).unwrap(),
-            vec![mint_info.clone(), account3_info.clone(), owner_info.clone()],
-        )
-        .unwrap();
-
-        // source-multisig-signer approve
-        do_process_instruction_dups(
-            approve(
-                &program_id,
-                &account3_key,
-                &account2_key,
-                &multisig_key,
-                &[&account3_key],
-                500,
-            )
-            .unwrap(),
-            vec![
-                account3_info.clone(),
-                account2_info.clone(),
-                multisig_info.clone(),
-                account3_info.clone(),
-            ],
-        )
-        .unwrap();
-
-        // source-multisig-signer approve_checked
+        // freeze where mint freeze_authority is account
         do_process_instruction_dups(
-            approve_checked(
-                &program_id,
-                &account3_key,
-                &mint_key,
-                &account2_key,
-                &multisig_key,
-                &[&account3_key],
-                500,
-                2,
-            )
-            .unwrap(),
+            freeze_account(&program_id, &account1_key, &mint_key, &account1_key, &[]).unwrap(),
             vec![
-                account3_info.clone(),
+                account1_info.clone(),
                 mint_info.clone(),
-                account2_info.clone(),
-                multisig_info.clone(),
-                account3_info.clone(),
+                account1_info.clone(),
             ],
         )
         .unwrap();
 
-        // source-owner multisig-signer
+        // thaw where mint freeze_authority is account
+        let mut account = Account::unpack_unchecked(&account1_info.data.borrow()).unwrap();
+        account.state = AccountState::Frozen;
+        Account::pack(account, &mut account1_info.data.borrow_mut()).unwrap();
         do_process_instruction_dups(
-            revoke(&program_id, &account3_key, &multisig_key, &[&account3_key]).unwrap(),
+            thaw_account(&program_id, &account1_key, &mint_key, &account1_key, &[]).unwrap(),
             vec![
-                account3_info.clone(),
-                multisig_info.clone(),
-                account3_info.clone(),
+                account1_info.clone(),
+                mint_info.clone(),
+                account1_info.clone(),
             ],
         )
         .unwrap();
     }
 
     #[test]
-    fn test_approve() {
+    fn test_freeze_account() {
         let program_id = crate::id();
         let account_key = Pubkey::new_unique();
         let mut account_account = PUTAccount::new(
@@ -2990,14 +7849,8 @@ mod tests {
             Account::get_packed_len(),
             &program_id,
         );
-        let account2_key = Pubkey::new_unique();
-        let mut account2_account = PUTAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
-        );
-        let delegate_key = Pubkey::new_unique();
-        let mut delegate_account = PUTAccount::default();
+        let account_owner_key = Pubkey::new_unique();
+        let mut account_owner_account = PUTAccount::default();
         let owner_key = Pubkey::new_unique();
         let mut owner_account = PUTAccount::default();
         let owner2_key = Pubkey::new_unique();
@@ -3007,7 +7860,7 @@ mod tests {
             PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
         let mut rent_sysvar = rent_sysvar();
 
-        // create mint
+        // create new mint with owner different from account owner
         do_process_instruction(
             initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
             vec![&mut mint_account, &mut rent_sysvar],
@@ -3016,23 +7869,11 @@ mod tests {
 
         // create account
         do_process_instruction(
-            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            initialize_account(&program_id, &account_key, &mint_key, &account_owner_key).unwrap(),
             vec![
                 &mut account_account,
                 &mut mint_account,
-                &mut owner_account,
-                &mut rent_sysvar,
-            ],
-        )
-        .unwrap();
-
-        // create another account
-        do_process_instruction(
-            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
-            vec![
-                &mut account2_account,
-                &mut mint_account,
-                &mut owner_account,
+                &mut account_owner_account,
                 &mut rent_sysvar,
             ],
         )
@@ -3045,761 +7886,1101 @@ mod tests {
         )
         .unwrap();
 
-        // missing signer
-        let mut instruction = approve(
-            &program_id,
-            &account_key,
-            &delegate_key,
-            &owner_key,
-            &[],
-            100,
-        )
-        .unwrap();
-        instruction.accounts[2].is_signer = false;
+        // mint cannot freeze
         assert_eq!(
-            Err(ProgramError::MissingRequiredSignature),
+            Err(TokenError::MintCannotFreeze.into()),
             do_process_instruction(
-                instruction,
-                vec![
-                    &mut account_account,
-                    &mut delegate_account,
-                    &mut owner_account,
-                ],
+                freeze_account(&program_id, &account_key, &mint_key, &owner_key, &[]).unwrap(),
+                vec![&mut account_account, &mut mint_account, &mut owner_account],
             )
         );
 
-        // no owner
+        // missing freeze_authority
+        let mut mint = Mint::unpack_unchecked(&mint_account.data).unwrap();
+        mint.freeze_authority = COption::Some(owner_key);
+        Mint::pack(mint, &mut mint_account.data).unwrap();
         assert_eq!(
             Err(TokenError::OwnerMismatch.into()),
             do_process_instruction(
-                approve(
-                    &program_id,
-                    &account_key,
-                    &delegate_key,
-                    &owner2_key,
-                    &[],
-                    100
-                )
-                .unwrap(),
-                vec![
-                    &mut account_account,
-                    &mut delegate_account,
-                    &mut owner2_account,
-                ],
+                freeze_account(&program_id, &account_key, &mint_key, &owner2_key, &[]).unwrap(),
+                vec![&mut account_account, &mut mint_account, &mut owner2_account],
             )
         );
 
-        // approve delegate
-        do_process_instruction(
-            approve(
-                &program_id,
-                &account_key,
-                &delegate_key,
-                &owner_key,
-                &[],
-                100,
+        // check explicit thaw
+        assert_eq!(
+            Err(TokenError::InvalidState.into()),
+            do_process_instruction(
+                thaw_account(&program_id, &account_key, &mint_key, &owner2_key, &[]).unwrap(),
+                vec![&mut account_account, &mut mint_account, &mut owner2_account],
             )
-            .unwrap(),
-            vec![
-                &mut account_account,
-                &mut delegate_account,
-                &mut owner_account,
-            ],
+        );
+
+        // freeze
+        do_process_instruction(
+            freeze_account(&program_id, &account_key, &mint_key, &owner_key, &[]).unwrap(),
+            vec![&mut account_account, &mut mint_account, &mut owner_account],
         )
         .unwrap();
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.state, AccountState::Frozen);
 
-        // approve delegate 2, with incorrect decimals
+        // check explicit freeze
         assert_eq!(
-            Err(TokenError::MintDecimalsMismatch.into()),
+            Err(TokenError::InvalidState.into()),
             do_process_instruction(
-                approve_checked(
-                    &program_id,
-                    &account_key,
-                    &mint_key,
-                    &delegate_key,
-                    &owner_key,
-                    &[],
-                    100,
-                    0 // <-- incorrect decimals
-                )
-                .unwrap(),
-                vec![
-                    &mut account_account,
-                    &mut mint_account,
-                    &mut delegate_account,
-                    &mut owner_account,
-                ],
+                freeze_account(&program_id, &account_key, &mint_key, &owner_key, &[]).unwrap(),
+                vec![&mut account_account, &mut mint_account, &mut owner_account],
             )
         );
 
-        // approve delegate 2, with incorrect mint
+        // check thaw authority
         assert_eq!(
-            Err(TokenError::MintMismatch.into()),
+            Err(TokenError::OwnerMismatch.into()),
             do_process_instruction(
-                approve_checked(
-                    &program_id,
-                    &account_key,
-                    &account2_key, // <-- bad mint
-                    &delegate_key,
-                    &owner_key,
-                    &[],
-                    100,
-                    0
-                )
-                .unwrap(),
-                vec![
-                    &mut account_account,
-                    &mut account2_account, // <-- bad mint
-                    &mut delegate_account,
-                    &mut owner_account,
-                ],
+                thaw_account(&program_id, &account_key, &mint_key, &owner2_key, &[]).unwrap(),
+                vec![&mut account_account, &mut mint_account, &mut owner2_account],
             )
         );
 
-        // approve delegate 2
+        // thaw
         do_process_instruction(
-            approve_checked(
-                &program_id,
-                &account_key,
-                &mint_key,
-                &delegate_key,
-                &owner_key,
-                &[],
-                100,
-                2,
-            )
-            .unwrap(),
+            thaw_account(&program_id, &account_key, &mint_key, &owner_key, &[]).unwrap(),
+            vec![&mut account_account, &mut mint_account, &mut owner_account],
+        )
+        .unwrap();
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.state, AccountState::Initialized);
+    }
+
+    #[test]
+    fn test_initialize_account2() {
+        let program_id = crate::id();
+        let account_key = Pubkey::new_unique();
+        let mut account_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let mut account2_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let owner_key = Pubkey::new_unique();
+        let mut owner_account = PUTAccount::default();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mut rent_sysvar = rent_sysvar();
+
+        // create mint
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
+        )
+        .unwrap();
+
+        do_process_instruction(
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
             vec![
                 &mut account_account,
                 &mut mint_account,
-                &mut delegate_account,
                 &mut owner_account,
+                &mut rent_sysvar,
             ],
         )
         .unwrap();
 
-        // revoke delegate
         do_process_instruction(
-            revoke(&program_id, &account_key, &owner_key, &[]).unwrap(),
-            vec![&mut account_account, &mut owner_account],
+            initialize_account2(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            vec![&mut account2_account, &mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
+
+        assert_eq!(account_account, account2_account);
     }
 
     #[test]
-    fn test_set_authority_dups() {
+    fn test_sync_native() {
         let program_id = crate::id();
-        let account1_key = Pubkey::new_unique();
-        let mut account1_account = PUTAccount::new(
-            account_minimum_balance(),
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let native_account_key = Pubkey::new_unique();
+        let lamports = 40;
+        let mut native_account = PUTAccount::new(
+            account_minimum_balance() + lamports,
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let non_native_account_key = Pubkey::new_unique();
+        let mut non_native_account = PUTAccount::new(
+            account_minimum_balance() + 50,
             Account::get_packed_len(),
             &program_id,
         );
-        let account1_info: AccountInfo = (&account1_key, true, &mut account1_account).into();
+
         let owner_key = Pubkey::new_unique();
-        let mint_key = Pubkey::new_unique();
-        let (mintmeta_key, _) =
-            Pubkey::find_program_address(&[b"MintMeta", &mint_key.to_bytes()], &program_id);
-        let mut mint_account =
-            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-        let mut mintmeta_account = PUTAccount::new(
-            mintmeta_minimum_balance(),
-            MintMeta::get_packed_len(),
-            &program_id,
-        );
-        let mint_info: AccountInfo = (&mint_key, true, &mut mint_account).into();
-        let mintmeta_info: AccountInfo = (&mintmeta_key, false,  &mut mintmeta_account).into();
-        let rent_key = rent::id();
+        let mut owner_account = PUTAccount::default();
         let mut rent_sysvar = rent_sysvar();
-        let rent_info: AccountInfo = (&rent_key, false, &mut rent_sysvar).into();
-
-        // create mint
-        do_process_instruction_dups(
-            initialize_mint(&program_id, &mint_key, &mint_key, Some(&mint_key), 2).unwrap(),
-            vec![mint_info.clone(), rent_info.clone()],
-        )
-        .unwrap();
 
-        // create new mintmeta
-        do_process_instruction_dups(
-            initialize_mint_meta(&program_id,  &mint_key, &mintmeta_key , "symbol".to_string(),"name".to_string(),"icon".to_string()).unwrap(),
-            vec![ mint_info.clone(), mintmeta_info.clone()],
+        // initialize non-native mint
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
 
-        // create account
-        do_process_instruction_dups(
-            initialize_account(&program_id, &account1_key, &mint_key, &account1_key).unwrap(),
+        // initialize non-native account
+        do_process_instruction(
+            initialize_account(&program_id, &non_native_account_key, &mint_key, &owner_key)
+                .unwrap(),
             vec![
-                account1_info.clone(),
-                mint_info.clone(),
-                account1_info.clone(),
-                rent_info.clone(),
+                &mut non_native_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
             ],
         )
         .unwrap();
 
-        // set mint_authority when currently self
-        do_process_instruction_dups(
-            set_authority(
-                &program_id,
-                &mint_key,
-                Some(&owner_key),
-                AuthorityType::MintTokens,
-                &mint_key,
-                &[],
+        let account = Account::unpack_unchecked(&non_native_account.data).unwrap();
+        assert!(!account.is_native());
+        assert_eq!(account.amount, 0);
+
+        // fail sync non-native
+        assert_eq!(
+            Err(TokenError::NonNativeNotSupported.into()),
+            do_process_instruction(
+                sync_native(&program_id, &non_native_account_key,).unwrap(),
+                vec![&mut non_native_account],
             )
-            .unwrap(),
-            vec![mint_info.clone(), mint_info.clone()],
-        )
-        .unwrap();
+        );
 
-        // set freeze_authority when currently self
-        do_process_instruction_dups(
-            set_authority(
+        // fail sync uninitialized
+        assert_eq!(
+            Err(ProgramError::UninitializedAccount),
+            do_process_instruction(
+                sync_native(&program_id, &native_account_key,).unwrap(),
+                vec![&mut native_account],
+            )
+        );
+
+        // wrap native account
+        do_process_instruction(
+            initialize_account(
                 &program_id,
-                &mint_key,
-                Some(&owner_key),
-                AuthorityType::FreezeAccount,
-                &mint_key,
-                &[],
+                &native_account_key,
+                &crate::native_mint::id(),
+                &owner_key,
             )
             .unwrap(),
-            vec![mint_info.clone(), mint_info.clone()],
+            vec![
+                &mut native_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
         )
         .unwrap();
+        let account = Account::unpack_unchecked(&native_account.data).unwrap();
+        assert!(account.is_native());
+        assert_eq!(account.amount, lamports as u128);
 
-        // set account owner when currently self
-        do_process_instruction_dups(
-            set_authority(
-                &program_id,
-                &account1_key,
-                Some(&owner_key),
-                AuthorityType::AccountOwner,
-                &account1_key,
-                &[],
-            )
-            .unwrap(),
-            vec![account1_info.clone(), account1_info.clone()],
+        // sync, no change
+        do_process_instruction(
+            sync_native(&program_id, &native_account_key).unwrap(),
+            vec![&mut native_account],
         )
         .unwrap();
+        let account = Account::unpack_unchecked(&native_account.data).unwrap();
+        assert_eq!(account.amount, lamports as u128);
 
-        // set close_authority when currently self
-        let mut account = Account::unpack_unchecked(&account1_info.data.borrow()).unwrap();
-        account.close_authority = COption::Some(account1_key);
-        Account::pack(account, &mut account1_info.data.borrow_mut()).unwrap();
+        // transfer put
+        let new_lamports = lamports + 50;
+        native_account.lamports = account_minimum_balance() + new_lamports;
 
-        do_process_instruction_dups(
-            set_authority(
-                &program_id,
-                &account1_key,
-                Some(&owner_key),
-                AuthorityType::CloseAccount,
-                &account1_key,
-                &[],
-            )
-            .unwrap(),
-            vec![account1_info.clone(), account1_info.clone()],
+        // success sync
+        do_process_instruction(
+            sync_native(&program_id, &native_account_key).unwrap(),
+            vec![&mut native_account],
         )
         .unwrap();
+        let account = Account::unpack_unchecked(&native_account.data).unwrap();
+        assert_eq!(account.amount, new_lamports as u128);
+
+        // reduce put
+        native_account.lamports -= 1;
+
+        // fail sync
+        assert_eq!(
+            Err(TokenError::InvalidState.into()),
+            do_process_instruction(
+                sync_native(&program_id, &native_account_key,).unwrap(),
+                vec![&mut native_account],
+            )
+        );
+    }
+
+    struct SyscallStubs {}
+    impl put_program::program_stubs::SyscallStubs for SyscallStubs {
+        fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe {
+                *(var_addr as *mut _ as *mut Clock) = Clock::default();
+            }
+            put_program::entrypoint::SUCCESS
+        }
+    }
+
+    fn set_syscall_stubs() {
+        static ONCE: Once = Once::new();
+        ONCE.call_once(|| {
+            put_program::program_stubs::set_syscall_stubs(Box::new(SyscallStubs {}));
+        });
     }
 
     #[test]
-    fn test_set_authority() {
+    fn test_transfer_fee_calculation() {
+        let transfer_fee = TransferFee {
+            epoch: 0,
+            maximum_fee: 10,
+            transfer_fee_basis_points: 100,
+        };
+        assert_eq!(transfer_fee.calculate_fee(0), 0);
+        assert_eq!(transfer_fee.calculate_fee(1), 1);
+        assert_eq!(transfer_fee.calculate_fee(100), 1);
+        assert_eq!(transfer_fee.calculate_fee(101), 2);
+        assert_eq!(transfer_fee.calculate_fee(5_000), 10);
+        assert_eq!(transfer_fee.calculate_fee(u128::MAX), 10);
+
+        let transfer_fee = TransferFee {
+            epoch: 0,
+            maximum_fee: u128::MAX,
+            transfer_fee_basis_points: MAX_FEE_BASIS_POINTS,
+        };
+        assert_eq!(transfer_fee.calculate_fee(u128::MAX), u128::MAX);
+
+        let transfer_fee_config = TransferFeeConfig {
+            older_transfer_fee: TransferFee {
+                epoch: 0,
+                maximum_fee: 10,
+                transfer_fee_basis_points: 100,
+            },
+            newer_transfer_fee: TransferFee {
+                epoch: 2,
+                maximum_fee: 20,
+                transfer_fee_basis_points: 200,
+            },
+            ..TransferFeeConfig::default()
+        };
+        assert_eq!(transfer_fee_config.calculate_epoch_fee(1, 1_000), 10);
+        assert_eq!(transfer_fee_config.calculate_epoch_fee(2, 1_000), 20);
+        assert_eq!(transfer_fee_config.calculate_epoch_fee(3, 500), 10);
+    }
+
+    #[test]
+    fn test_transfer_fee() {
+        set_syscall_stubs();
         let program_id = crate::id();
+        let mint_len = ExtensionType::get_account_len::<Mint>(&[ExtensionType::TransferFeeConfig]);
+        let account_len =
+            ExtensionType::get_account_len::<Account>(&[ExtensionType::TransferFeeAmount]);
         let account_key = Pubkey::new_unique();
         let mut account_account = PUTAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
+            Rent::default().minimum_balance(account_len),
+            account_len,
             &program_id,
         );
         let account2_key = Pubkey::new_unique();
         let mut account2_account = PUTAccount::new(
+            Rent::default().minimum_balance(account_len),
+            account_len,
+            &program_id,
+        );
+        let account3_key = Pubkey::new_unique();
+        let mut account3_account = PUTAccount::new(
+            Rent::default().minimum_balance(account_len),
+            account_len,
+            &program_id,
+        );
+        let small_key = Pubkey::new_unique();
+        let mut small_account = PUTAccount::new(
             account_minimum_balance(),
             Account::get_packed_len(),
             &program_id,
         );
         let owner_key = Pubkey::new_unique();
         let mut owner_account = PUTAccount::default();
-        let owner2_key = Pubkey::new_unique();
-        let mut owner2_account = PUTAccount::default();
-        let owner3_key = Pubkey::new_unique();
-        let mut owner3_account = PUTAccount::default();
+        let authority_key = Pubkey::new_unique();
+        let mut authority_account = PUTAccount::default();
+        let close_dest_key = Pubkey::new_unique();
+        let mut close_dest_account = PUTAccount::default();
         let mint_key = Pubkey::new_unique();
-        let mut mint_account =
-            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-
-        let (mintmeta_key,_) = Pubkey::find_program_address(&[b"MintMeta",&mint_key.to_bytes()], &program_id);
-        let mut mintmeta_account = PUTAccount::new(mintmeta_minimum_balance(), MintMeta::get_packed_len(), &program_id);
-        let mint2_key = Pubkey::new_unique();
-        let mut mint2_account =
-            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mut mint_account = PUTAccount::new(
+            Rent::default().minimum_balance(mint_len),
+            mint_len,
+            &program_id,
+        );
         let mut rent_sysvar = rent_sysvar();
 
-        // create new mint with owner
+        // fee above 100%
+        assert_eq!(
+            Err(TokenError::TransferFeeExceedsMaximum.into()),
+            do_process_instruction(
+                initialize_transfer_fee_config(
+                    &program_id,
+                    &mint_key,
+                    Some(&authority_key),
+                    Some(&authority_key),
+                    MAX_FEE_BASIS_POINTS + 1,
+                    10,
+                )
+                .unwrap(),
+                vec![&mut mint_account],
+            )
+        );
+
+        // 1% fee capped at 10 tokens
+        do_process_instruction(
+            initialize_transfer_fee_config(
+                &program_id,
+                &mint_key,
+                Some(&authority_key),
+                Some(&authority_key),
+                100,
+                10,
+            )
+            .unwrap(),
+            vec![&mut mint_account],
+        )
+        .unwrap();
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
+        )
+        .unwrap();
+        assert_eq!(
+            get_extension_types(&mint_account.data).unwrap(),
+            vec![ExtensionType::TransferFeeConfig]
+        );
+        assert_eq!(Mint::unpack_base(&mint_account.data).unwrap().decimals, 2);
+
+        // fee configuration cannot be added to an initialized mint
+        assert_eq!(
+            Err(TokenError::AlreadyInUse.into()),
+            do_process_instruction(
+                initialize_transfer_fee_config(&program_id, &mint_key, None, None, 100, 10)
+                    .unwrap(),
+                vec![&mut mint_account],
+            )
+        );
+
+        // accounts of the mint need room for withheld fees
+        assert_eq!(
+            Err(ProgramError::InvalidAccountData),
+            do_process_instruction(
+                initialize_account(&program_id, &small_key, &mint_key, &owner_key).unwrap(),
+                vec![
+                    &mut small_account,
+                    &mut mint_account,
+                    &mut owner_account,
+                    &mut rent_sysvar,
+                ],
+            )
+        );
+
+        do_process_instruction(
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+        do_process_instruction(
+            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account2_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
         do_process_instruction(
-            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
-            vec![&mut mint_account, &mut rent_sysvar],
+            initialize_account(&program_id, &account3_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account3_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
         )
         .unwrap();
+        assert_eq!(
+            get_extension_types(&account_account.data).unwrap(),
+            vec![ExtensionType::TransferFeeAmount]
+        );
 
-        // create mint with owner and freeze_authority
         do_process_instruction(
-            initialize_mint(&program_id, &mint2_key, &owner_key, Some(&owner_key), 2).unwrap(),
-            vec![&mut mint2_account, &mut rent_sysvar],
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 10_000).unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
         )
         .unwrap();
 
-         // create new mintmeta
-         do_process_instruction(
-            initialize_mint_meta(&program_id, &mint_key, &mintmeta_key , "symbol".to_string(),"name".to_string(),"icon".to_string()).unwrap(),
-            vec![ &mut mint_account, &mut mintmeta_account],
+        // fee is rounded up
+        do_process_instruction(
+            transfer_checked(
+                &program_id,
+                &account_key,
+                &mint_key,
+                &account2_key,
+                &owner_key,
+                &[],
+                150,
+                2,
+            )
+            .unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut account2_account,
+                &mut owner_account,
+            ],
         )
         .unwrap();
-
-        // invalid account
         assert_eq!(
-            Err(ProgramError::UninitializedAccount),
-            do_process_instruction(
-                set_authority(
-                    &program_id,
-                    &account_key,
-                    Some(&owner2_key),
-                    AuthorityType::AccountOwner,
-                    &owner_key,
-                    &[]
-                )
-                .unwrap(),
-                vec![&mut account_account, &mut mintmeta_account, &mut owner_account],
-            )
+            Account::unpack_base(&account2_account.data).unwrap().amount,
+            148
+        );
+        assert_eq!(
+            get_extension::<TransferFeeAmount>(&account2_account.data)
+                .unwrap()
+                .unwrap()
+                .withheld_amount,
+            2
         );
 
-        // create account
+        // fee is capped
         do_process_instruction(
-            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            transfer_checked(
+                &program_id,
+                &account_key,
+                &mint_key,
+                &account2_key,
+                &owner_key,
+                &[],
+                5_000,
+                2,
+            )
+            .unwrap(),
             vec![
                 &mut account_account,
                 &mut mint_account,
+                &mut account2_account,
                 &mut owner_account,
-                &mut rent_sysvar,
             ],
         )
         .unwrap();
+        assert_eq!(
+            Account::unpack_base(&account_account.data).unwrap().amount,
+            4_850
+        );
+        assert_eq!(
+            Account::unpack_base(&account2_account.data).unwrap().amount,
+            5_138
+        );
+        assert_eq!(
+            get_extension::<TransferFeeAmount>(&account2_account.data)
+                .unwrap()
+                .unwrap()
+                .withheld_amount,
+            12
+        );
 
-        // create another account
+        // unchecked transfers cannot assess the fee
+        assert_eq!(
+            Err(TokenError::MintRequiredForTransfer.into()),
+            do_process_instruction(
+                transfer(&program_id, &account_key, &account2_key, &owner_key, &[], 1).unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut account2_account,
+                    &mut owner_account,
+                ],
+            )
+        );
+
+        // empty account2 back into account
         do_process_instruction(
-            initialize_account(&program_id, &account2_key, &mint2_key, &owner_key).unwrap(),
+            transfer_checked(
+                &program_id,
+                &account2_key,
+                &mint_key,
+                &account_key,
+                &owner_key,
+                &[],
+                5_138,
+                2,
+            )
+            .unwrap(),
             vec![
                 &mut account2_account,
-                &mut mint2_account,
+                &mut mint_account,
+                &mut account_account,
                 &mut owner_account,
-                &mut rent_sysvar,
             ],
         )
         .unwrap();
+        assert_eq!(
+            Account::unpack_base(&account_account.data).unwrap().amount,
+            9_978
+        );
 
-        // missing owner
+        // withheld fees block closing
         assert_eq!(
-            Err(TokenError::OwnerMismatch.into()),
+            Err(TokenError::AccountHasWithheldTransferFees.into()),
             do_process_instruction(
-                set_authority(
-                    &program_id,
-                    &account_key,
-                    Some(&owner_key),
-                    AuthorityType::AccountOwner,
-                    &owner2_key,
-                    &[]
-                )
-                .unwrap(),
-                vec![&mut account_account, &mut mintmeta_account, &mut owner2_account],
+                close_account(&program_id, &account2_key, &close_dest_key, &owner_key, &[])
+                    .unwrap(),
+                vec![
+                    &mut account2_account,
+                    &mut close_dest_account,
+                    &mut owner_account,
+                ],
             )
         );
 
-        // owner did not sign
-        let mut instruction = set_authority(
-            &program_id,
-            &account_key,
-            Some(&owner2_key),
-            AuthorityType::AccountOwner,
-            &owner_key,
-            &[],
+        // anyone can harvest into the mint, other accounts are skipped
+        do_process_instruction(
+            harvest_withheld_tokens_to_mint(&program_id, &mint_key, &[&account2_key, &owner_key])
+                .unwrap(),
+            vec![
+                &mut mint_account,
+                &mut account2_account,
+                &mut owner_account,
+            ],
         )
         .unwrap();
-        instruction.accounts[1].is_signer = false;
         assert_eq!(
-            Err(ProgramError::MissingRequiredSignature),
-            do_process_instruction(instruction, vec![&mut account_account, &mut mintmeta_account, &mut owner_account,])
+            get_extension::<TransferFeeAmount>(&account2_account.data)
+                .unwrap()
+                .unwrap()
+                .withheld_amount,
+            0
         );
-
-        // wrong authority type
         assert_eq!(
-            Err(TokenError::AuthorityTypeNotSupported.into()),
-            do_process_instruction(
-                set_authority(
-                    &program_id,
-                    &account_key,
-                    Some(&owner2_key),
-                    AuthorityType::FreezeAccount,
-                    &owner_key,
-                    &[],
-                )
-                .unwrap(),
-                vec![&mut account_account, &mut mintmeta_account,&mut owner_account],
-            )
+            get_extension::<TransferFeeConfig>(&mint_account.data)
+                .unwrap()
+                .unwrap()
+                .withheld_amount,
+            12
         );
+        do_process_instruction(
+            close_account(&program_id, &account2_key, &close_dest_key, &owner_key, &[]).unwrap(),
+            vec![
+                &mut account2_account,
+                &mut close_dest_account,
+                &mut owner_account,
+            ],
+        )
+        .unwrap();
 
-        // account owner may not be set to None
+        // withdraw authority must sign
         assert_eq!(
-            Err(TokenError::InvalidInstruction.into()),
+            Err(TokenError::OwnerMismatch.into()),
             do_process_instruction(
-                set_authority(
+                withdraw_withheld_tokens_from_mint(
                     &program_id,
-                    &account_key,
-                    None,
-                    AuthorityType::AccountOwner,
+                    &mint_key,
+                    &account3_key,
                     &owner_key,
                     &[],
                 )
                 .unwrap(),
-                vec![&mut account_account, &mut mintmeta_account, &mut owner_account],
+                vec![
+                    &mut mint_account,
+                    &mut account3_account,
+                    &mut owner_account,
+                ],
             )
         );
-
-        // set delegate
+        // the mint cannot be its own destination
+        {
+            let mint_info: AccountInfo = (&mint_key, false, &mut mint_account).into();
+            let authority_info: AccountInfo = (&authority_key, true, &mut authority_account).into();
+            assert_eq!(
+                Err(ProgramError::InvalidAccountData),
+                do_process_instruction_dups(
+                    withdraw_withheld_tokens_from_mint(
+                        &program_id,
+                        &mint_key,
+                        &mint_key,
+                        &authority_key,
+                        &[],
+                    )
+                    .unwrap(),
+                    vec![mint_info.clone(), mint_info, authority_info],
+                )
+            );
+        }
         do_process_instruction(
-            approve(
+            withdraw_withheld_tokens_from_mint(
                 &program_id,
-                &account_key,
-                &owner2_key,
-                &owner_key,
+                &mint_key,
+                &account3_key,
+                &authority_key,
                 &[],
-                u128::MAX,
             )
             .unwrap(),
             vec![
-                &mut account_account,
-                &mut owner2_account,
-                &mut owner_account,
+                &mut mint_account,
+                &mut account3_account,
+                &mut authority_account,
             ],
         )
         .unwrap();
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.delegate, COption::Some(owner2_key));
-        assert_eq!(account.delegated_amount, u128::MAX);
-
-        // set owner
         do_process_instruction(
-            set_authority(
+            withdraw_withheld_tokens_from_accounts(
                 &program_id,
-                &account_key,
-                Some(&owner3_key),
-                AuthorityType::AccountOwner,
-                &owner_key,
+                &mint_key,
+                &account3_key,
+                &authority_key,
                 &[],
+                &[&account_key],
             )
             .unwrap(),
-            vec![&mut account_account, &mut mintmeta_account, &mut owner_account],
+            vec![
+                &mut mint_account,
+                &mut account3_account,
+                &mut authority_account,
+                &mut account_account,
+            ],
         )
         .unwrap();
+        assert_eq!(
+            Account::unpack_base(&account3_account.data).unwrap().amount,
+            22
+        );
+        assert_eq!(
+            get_extension::<TransferFeeAmount>(&account_account.data)
+                .unwrap()
+                .unwrap()
+                .withheld_amount,
+            0
+        );
+        assert_eq!(
+            get_extension::<TransferFeeConfig>(&mint_account.data)
+                .unwrap()
+                .unwrap()
+                .withheld_amount,
+            0
+        );
 
-        // check delegate cleared
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.delegate, COption::None);
-        assert_eq!(account.delegated_amount, 0);
-
-        // set owner without existing delegate
-        do_process_instruction(
-            set_authority(
-                &program_id,
-                &account_key,
-                Some(&owner2_key),
-                AuthorityType::AccountOwner,
-                &owner3_key,
-                &[],
+        // new fee takes effect after the epoch delay
+        assert_eq!(
+            Err(TokenError::TransferFeeExceedsMaximum.into()),
+            do_process_instruction(
+                set_transfer_fee(
+                    &program_id,
+                    &mint_key,
+                    &authority_key,
+                    &[],
+                    MAX_FEE_BASIS_POINTS + 1,
+                    20,
+                )
+                .unwrap(),
+                vec![&mut mint_account, &mut authority_account],
             )
-            .unwrap(),
-            vec![&mut account_account, &mut mintmeta_account, &mut owner3_account],
-        )
-        .unwrap();
-
-        // set close_authority
+        );
         do_process_instruction(
-            set_authority(
-                &program_id,
-                &account_key,
-                Some(&owner2_key),
-                AuthorityType::CloseAccount,
-                &owner2_key,
-                &[],
-            )
-            .unwrap(),
-            vec![&mut account_account, &mut mintmeta_account, &mut owner2_account],
+            set_transfer_fee(&program_id, &mint_key, &authority_key, &[], 200, 20).unwrap(),
+            vec![&mut mint_account, &mut authority_account],
         )
         .unwrap();
+        let transfer_fee_config = get_extension::<TransferFeeConfig>(&mint_account.data)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            transfer_fee_config.older_transfer_fee,
+            TransferFee {
+                epoch: 0,
+                maximum_fee: 10,
+                transfer_fee_basis_points: 100,
+            }
+        );
+        assert_eq!(
+            transfer_fee_config.newer_transfer_fee,
+            TransferFee {
+                epoch: TRANSFER_FEE_EPOCH_DELAY,
+                maximum_fee: 20,
+                transfer_fee_basis_points: 200,
+            }
+        );
 
-        // close_authority may be set to None
+        // fee config authority can be removed
         do_process_instruction(
             set_authority(
                 &program_id,
-                &account_key,
+                &mint_key,
                 None,
-                AuthorityType::CloseAccount,
-                &owner2_key,
+                AuthorityType::TransferFeeConfig,
+                &authority_key,
                 &[],
             )
             .unwrap(),
-            vec![&mut account_account, &mut mintmeta_account, &mut owner2_account],
+            vec![&mut mint_account, &mut authority_account],
         )
         .unwrap();
-
-        // wrong owner
         assert_eq!(
-            Err(TokenError::OwnerMismatch.into()),
+            Err(TokenError::NoAuthorityExists.into()),
             do_process_instruction(
-                set_authority(
-                    &program_id,
-                    &mint_key,
-                    Some(&owner3_key),
-                    AuthorityType::MintTokens,
-                    &owner2_key,
-                    &[]
-                )
-                .unwrap(),
-                vec![&mut mint_account, &mut mintmeta_account, &mut owner2_account],
+                set_transfer_fee(&program_id, &mint_key, &authority_key, &[], 0, 0).unwrap(),
+                vec![&mut mint_account, &mut authority_account],
             )
         );
+    }
 
-        // owner did not sign
-        let mut instruction = set_authority(
+    #[test]
+    fn test_interest_bearing_mint() {
+        set_syscall_stubs();
+        let program_id = crate::id();
+        let mint_len =
+            ExtensionType::get_account_len::<Mint>(&[ExtensionType::InterestBearingConfig]);
+        let owner_key = Pubkey::new_unique();
+        let rate_authority_key = Pubkey::new_unique();
+        let mut rate_authority_account = PUTAccount::default();
+        let new_rate_authority_key = Pubkey::new_unique();
+        let mut new_rate_authority_account = PUTAccount::default();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account = PUTAccount::new(
+            Rent::default().minimum_balance(mint_len),
+            mint_len,
             &program_id,
-            &mint_key,
-            Some(&owner2_key),
-            AuthorityType::MintTokens,
-            &owner_key,
-            &[],
+        );
+        let plain_mint_key = Pubkey::new_unique();
+        let mut plain_mint_account =
+            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mut rent_sysvar = rent_sysvar();
+
+        do_process_instruction(
+            initialize_interest_bearing_mint(&program_id, &mint_key, Some(&rate_authority_key), 500)
+                .unwrap(),
+            vec![&mut mint_account],
+        )
+        .unwrap();
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
-        instruction.accounts[1].is_signer = false;
         assert_eq!(
-            Err(ProgramError::MissingRequiredSignature),
-            do_process_instruction(instruction, vec![&mut mint_account, &mut mintmeta_account, &mut owner_account],)
+            get_extension_types(&mint_account.data).unwrap(),
+            vec![ExtensionType::InterestBearingConfig]
+        );
+        let interest_bearing_config = get_extension::<InterestBearingConfig>(&mint_account.data)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            interest_bearing_config.rate_authority,
+            COption::Some(rate_authority_key)
         );
+        assert_eq!(interest_bearing_config.current_rate, 500);
 
-        // cannot freeze
+        // interest rate cannot be added to an initialized mint
         assert_eq!(
-            Err(TokenError::MintCannotFreeze.into()),
+            Err(TokenError::AlreadyInUse.into()),
             do_process_instruction(
-                set_authority(
-                    &program_id,
-                    &mint_key,
-                    Some(&owner2_key),
-                    AuthorityType::FreezeAccount,
-                    &owner_key,
-                    &[],
-                )
-                .unwrap(),
-                vec![&mut mint_account, &mut mintmeta_account, &mut owner_account],
+                initialize_interest_bearing_mint(&program_id, &mint_key, None, 500).unwrap(),
+                vec![&mut mint_account],
             )
         );
 
-        // set owner
-        do_process_instruction(
-            set_authority(
-                &program_id,
-                &mint_key,
-                Some(&owner2_key),
-                AuthorityType::MintTokens,
-                &owner_key,
-                &[],
+        // only the rate authority may update the rate
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                update_interest_rate(&program_id, &mint_key, &owner_key, &[], -100).unwrap(),
+                vec![&mut mint_account, &mut rate_authority_account],
             )
-            .unwrap(),
-            vec![&mut mint_account, &mut mintmeta_account, &mut owner_account],
+        );
+        do_process_instruction(
+            update_interest_rate(&program_id, &mint_key, &rate_authority_key, &[], -100).unwrap(),
+            vec![&mut mint_account, &mut rate_authority_account],
         )
         .unwrap();
+        let interest_bearing_config = get_extension::<InterestBearingConfig>(&mint_account.data)
+            .unwrap()
+            .unwrap();
+        assert_eq!(interest_bearing_config.pre_update_average_rate, 500);
+        assert_eq!(interest_bearing_config.current_rate, -100);
 
-        // set owner to None
+        // no interest has accrued yet
         do_process_instruction(
-            set_authority(
-                &program_id,
-                &mint_key,
-                None,
-                AuthorityType::MintTokens,
-                &owner2_key,
-                &[],
-            )
-            .unwrap(),
-            vec![&mut mint_account, &mut mintmeta_account, &mut owner2_account],
+            amount_to_ui_amount(&program_id, &mint_key, 12_345).unwrap(),
+            vec![&mut mint_account],
+        )
+        .unwrap();
+        do_process_instruction(
+            initialize_mint(&program_id, &plain_mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut plain_mint_account, &mut rent_sysvar],
+        )
+        .unwrap();
+        do_process_instruction(
+            amount_to_ui_amount(&program_id, &plain_mint_key, 12_345).unwrap(),
+            vec![&mut plain_mint_account],
         )
         .unwrap();
 
-        // test unsetting mint_authority is one-way operation
+        // plain mints have no rate to update
         assert_eq!(
-            Err(TokenError::FixedSupply.into()),
+            Err(TokenError::ExtensionNotFound.into()),
             do_process_instruction(
-                set_authority(
-                    &program_id,
-                    &mint2_key,
-                    Some(&owner2_key),
-                    AuthorityType::MintTokens,
-                    &owner_key,
-                    &[]
-                )
-                .unwrap(),
-                vec![&mut mint_account, &mut mintmeta_account, &mut owner_account],
+                update_interest_rate(&program_id, &plain_mint_key, &rate_authority_key, &[], 1)
+                    .unwrap(),
+                vec![&mut plain_mint_account, &mut rate_authority_account],
             )
         );
 
-        // set freeze_authority
+        // hand over, then drop, the rate authority
         do_process_instruction(
             set_authority(
                 &program_id,
-                &mint2_key,
-                Some(&owner2_key),
-                AuthorityType::FreezeAccount,
-                &owner_key,
+                &mint_key,
+                Some(&new_rate_authority_key),
+                AuthorityType::InterestRate,
+                &rate_authority_key,
                 &[],
             )
             .unwrap(),
-            vec![&mut mint2_account, &mut mintmeta_account, &mut owner_account],
+            vec![&mut mint_account, &mut rate_authority_account],
         )
         .unwrap();
-
-        // test unsetting freeze_authority is one-way operation
         do_process_instruction(
             set_authority(
                 &program_id,
-                &mint2_key,
+                &mint_key,
                 None,
-                AuthorityType::FreezeAccount,
-                &owner2_key,
+                AuthorityType::InterestRate,
+                &new_rate_authority_key,
                 &[],
             )
             .unwrap(),
-            vec![&mut mint2_account, &mut mintmeta_account, &mut owner2_account],
+            vec![&mut mint_account, &mut new_rate_authority_account],
         )
         .unwrap();
-
         assert_eq!(
-            Err(TokenError::MintCannotFreeze.into()),
+            Err(TokenError::NoAuthorityExists.into()),
             do_process_instruction(
-                set_authority(
-                    &program_id,
-                    &mint2_key,
-                    Some(&owner2_key),
-                    AuthorityType::FreezeAccount,
-                    &owner_key,
-                    &[],
-                )
-                .unwrap(),
-                vec![&mut mint2_account, &mut mintmeta_account, &mut owner2_account],
+                update_interest_rate(&program_id, &mint_key, &new_rate_authority_key, &[], 1)
+                    .unwrap(),
+                vec![&mut mint_account, &mut new_rate_authority_account],
             )
         );
     }
 
     #[test]
-    fn test_mint_to_dups() {
+    fn test_non_transferable_mint() {
         let program_id = crate::id();
-        let account1_key = Pubkey::new_unique();
-        let mut account1_account = PUTAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
+        let mint_len = ExtensionType::get_account_len::<Mint>(&[ExtensionType::NonTransferable]);
+        let account_len =
+            ExtensionType::get_account_len::<Account>(&[ExtensionType::NonTransferableAccount]);
+        let account_key = Pubkey::new_unique();
+        let mut account_account = PUTAccount::new(
+            Rent::default().minimum_balance(account_len),
+            account_len,
+            &program_id,
+        );
+        let account2_key = Pubkey::new_unique();
+        let mut account2_account = PUTAccount::new(
+            Rent::default().minimum_balance(account_len),
+            account_len,
             &program_id,
         );
-        let account1_info: AccountInfo = (&account1_key, true, &mut account1_account).into();
         let owner_key = Pubkey::new_unique();
         let mut owner_account = PUTAccount::default();
-        let owner_info: AccountInfo = (&owner_key, true, &mut owner_account).into();
+        let delegate_key = Pubkey::new_unique();
+        let mut delegate_account = PUTAccount::default();
+        let close_dest_key = Pubkey::new_unique();
+        let mut close_dest_account = PUTAccount::default();
         let mint_key = Pubkey::new_unique();
-        let mut mint_account =
-            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-        let _mintmeta_account = PUTAccount::new(
-            mint_minimum_balance(),
-            MintMeta::get_packed_len(),
+        let mut mint_account = PUTAccount::new(
+            Rent::default().minimum_balance(mint_len),
+            mint_len,
             &program_id,
         );
-        let mint_info: AccountInfo = (&mint_key, true, &mut mint_account).into();
-        let rent_key = rent::id();
         let mut rent_sysvar = rent_sysvar();
-        let rent_info: AccountInfo = (&rent_key, false, &mut rent_sysvar).into();
 
-        // create mint
-        do_process_instruction_dups(
-            initialize_mint(&program_id, &mint_key, &mint_key, None, 2).unwrap(),
-            vec![mint_info.clone(), rent_info.clone()],
+        do_process_instruction(
+            initialize_non_transferable_mint(&program_id, &mint_key).unwrap(),
+            vec![&mut mint_account],
+        )
+        .unwrap();
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
+        assert_eq!(
+            get_extension_types(&mint_account.data).unwrap(),
+            vec![ExtensionType::NonTransferable]
+        );
 
-        // create account
-        do_process_instruction_dups(
-            initialize_account(&program_id, &account1_key, &mint_key, &owner_key).unwrap(),
+        // the mint cannot be made non-transferable once initialized
+        assert_eq!(
+            Err(TokenError::AlreadyInUse.into()),
+            do_process_instruction(
+                initialize_non_transferable_mint(&program_id, &mint_key).unwrap(),
+                vec![&mut mint_account],
+            )
+        );
+
+        do_process_instruction(
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
             vec![
-                account1_info.clone(),
-                mint_info.clone(),
-                owner_info.clone(),
-                rent_info.clone(),
+                &mut account_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
             ],
         )
         .unwrap();
-
-        // mint_to when mint_authority is self
-        do_process_instruction_dups(
-            mint_to(&program_id, &mint_key, &account1_key, &mint_key, &[], 42).unwrap(),
-            vec![mint_info.clone(), account1_info.clone(), mint_info.clone()],
+        do_process_instruction(
+            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account2_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
         )
         .unwrap();
+        assert_eq!(
+            get_extension_types(&account_account.data).unwrap(),
+            vec![ExtensionType::NonTransferableAccount]
+        );
 
-        // mint_to_checked when mint_authority is self
-        do_process_instruction_dups(
-            mint_to_checked(&program_id, &mint_key, &account1_key, &mint_key, &[], 42, 2).unwrap(),
-            vec![mint_info.clone(), account1_info.clone(), mint_info.clone()],
+        // minting still works
+        do_process_instruction(
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1_000).unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
         )
         .unwrap();
 
-        // mint_to when mint_authority is account owner
-        let mut mint = Mint::unpack_unchecked(&mint_info.data.borrow()).unwrap();
-        mint.mint_authority = COption::Some(account1_key);
-        Mint::pack(mint, &mut mint_info.data.borrow_mut()).unwrap();
-        do_process_instruction_dups(
-            mint_to(
-                &program_id,
-                &mint_key,
-                &account1_key,
-                &account1_key,
-                &[],
-                42,
+        // transfers fail, with or without the mint
+        assert_eq!(
+            Err(TokenError::NonTransferable.into()),
+            do_process_instruction(
+                transfer(
+                    &program_id,
+                    &account_key,
+                    &account2_key,
+                    &owner_key,
+                    &[],
+                    100
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut account2_account,
+                    &mut owner_account,
+                ],
+            )
+        );
+        assert_eq!(
+            Err(TokenError::NonTransferable.into()),
+            do_process_instruction(
+                transfer_checked(
+                    &program_id,
+                    &account_key,
+                    &mint_key,
+                    &account2_key,
+                    &owner_key,
+                    &[],
+                    100,
+                    2,
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut mint_account,
+                    &mut account2_account,
+                    &mut owner_account,
+                ],
             )
-            .unwrap(),
-            vec![
-                mint_info.clone(),
-                account1_info.clone(),
-                account1_info.clone(),
-            ],
-        )
-        .unwrap();
+        );
 
-        // mint_to_checked when mint_authority is account owner
-        do_process_instruction_dups(
-            mint_to(
-                &program_id,
-                &mint_key,
-                &account1_key,
-                &account1_key,
-                &[],
-                42,
+        // delegating fails too
+        assert_eq!(
+            Err(TokenError::NonTransferable.into()),
+            do_process_instruction(
+                approve(
+                    &program_id,
+                    &account_key,
+                    &delegate_key,
+                    &owner_key,
+                    &[],
+                    100
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut delegate_account,
+                    &mut owner_account,
+                ],
             )
-            .unwrap(),
+        );
+        assert_eq!(
+            Err(TokenError::NonTransferable.into()),
+            do_process_instruction(
+                approve_checked(
+                    &program_id,
+                    &account_key,
+                    &mint_key,
+                    &delegate_key,
+                    &owner_key,
+                    &[],
+                    100,
+                    2,
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut mint_account,
+                    &mut delegate_account,
+                    &mut owner_account,
+                ],
+            )
+        );
+
+        // burning and closing still work
+        do_process_instruction(
+            burn(&program_id, &account_key, &mint_key, &owner_key, &[], 1_000).unwrap(),
+            vec![&mut account_account, &mut mint_account, &mut owner_account],
+        )
+        .unwrap();
+        let account = Account::unpack_base(&account_account.data).unwrap();
+        assert_eq!(account.amount, 0);
+        assert_eq!(Mint::unpack_base(&mint_account.data).unwrap().supply, 0);
+        do_process_instruction(
+            close_account(&program_id, &account_key, &close_dest_key, &owner_key, &[]).unwrap(),
             vec![
-                mint_info.clone(),
-                account1_info.clone(),
-                account1_info.clone(),
+                &mut account_account,
+                &mut close_dest_account,
+                &mut owner_account,
             ],
         )
         .unwrap();
+        assert_eq!(account_account.lamports, 0);
     }
 
     #[test]
-    fn test_mint_to() {
+    fn test_permanent_delegate() {
         let program_id = crate::id();
+        let mint_len = ExtensionType::get_account_len::<Mint>(&[ExtensionType::PermanentDelegate]);
         let account_key = Pubkey::new_unique();
         let mut account_account = PUTAccount::new(
             account_minimum_balance(),
@@ -3812,68 +8993,46 @@ mod tests {
             Account::get_packed_len(),
             &program_id,
         );
-        let account3_key = Pubkey::new_unique();
-        let mut account3_account = PUTAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
-        );
-        let mismatch_key = Pubkey::new_unique();
-        let mut mismatch_account = PUTAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
-        );
         let owner_key = Pubkey::new_unique();
         let mut owner_account = PUTAccount::default();
-        let owner2_key = Pubkey::new_unique();
-        let mut owner2_account = PUTAccount::default();
+        let delegate_key = Pubkey::new_unique();
+        let mut delegate_account = PUTAccount::default();
+        let new_delegate_key = Pubkey::new_unique();
+        let mut new_delegate_account = PUTAccount::default();
         let mint_key = Pubkey::new_unique();
-        let mut mint_account =
-            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-
-        let (mintmeta_key, _) =
-            Pubkey::find_program_address(&[b"MintMeta", &mint_key.to_bytes()], &program_id);
-        let mut mintmeta_account = PUTAccount::new(
-            mintmeta_minimum_balance(),
-            MintMeta::get_packed_len(),
-            &program_id,
-        );
-        let mint2_key = Pubkey::new_unique();
-        let uninitialized_key = Pubkey::new_unique();
-        let mut uninitialized_account = PUTAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
+        let mut mint_account = PUTAccount::new(
+            Rent::default().minimum_balance(mint_len),
+            mint_len,
             &program_id,
         );
         let mut rent_sysvar = rent_sysvar();
 
-        // create new mint with owner
+        do_process_instruction(
+            initialize_permanent_delegate(&program_id, &mint_key, &delegate_key).unwrap(),
+            vec![&mut mint_account],
+        )
+        .unwrap();
         do_process_instruction(
             initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
             vec![&mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
+        assert_eq!(
+            get_extension::<PermanentDelegate>(&mint_account.data).unwrap(),
+            Some(PermanentDelegate {
+                delegate: COption::Some(delegate_key)
+            })
+        );
 
-        // create new mintmeta
-        do_process_instruction(
-            initialize_mint_meta(
-                &program_id,
-                &mint_key,
-                &mintmeta_key,
-                "symbol".to_string(),
-                "name".to_string(),
-                "icon".to_string(),
+        // the permanent delegate cannot be added to an initialized mint
+        assert_eq!(
+            Err(TokenError::AlreadyInUse.into()),
+            do_process_instruction(
+                initialize_permanent_delegate(&program_id, &mint_key, &delegate_key).unwrap(),
+                vec![&mut mint_account],
             )
-            .unwrap(),
-            vec![
-                &mut mint_account,
-                &mut mintmeta_account,
-            ],
-        )
-        .unwrap();
+        );
 
-        // create account
         do_process_instruction(
             initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
             vec![
@@ -3884,352 +9043,493 @@ mod tests {
             ],
         )
         .unwrap();
-
-        // create another account
         do_process_instruction(
-            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
+            initialize_account(&program_id, &account2_key, &mint_key, &delegate_key).unwrap(),
             vec![
                 &mut account2_account,
                 &mut mint_account,
-                &mut owner_account,
-                &mut rent_sysvar,
-            ],
-        )
-        .unwrap();
-
-        // create another account
-        do_process_instruction(
-            initialize_account(&program_id, &account3_key, &mint_key, &owner_key).unwrap(),
-            vec![
-                &mut account3_account,
-                &mut mint_account,
-                &mut owner_account,
-                &mut rent_sysvar,
-            ],
-        )
-        .unwrap();
-
-        // create mismatch account
-        do_process_instruction(
-            initialize_account(&program_id, &mismatch_key, &mint_key, &owner_key).unwrap(),
-            vec![
-                &mut mismatch_account,
-                &mut mint_account,
-                &mut owner_account,
+                &mut delegate_account,
                 &mut rent_sysvar,
             ],
         )
         .unwrap();
-        let mut account = Account::unpack_unchecked(&mismatch_account.data).unwrap();
-        account.mint = mint2_key;
-        Account::pack(account, &mut mismatch_account.data).unwrap();
-
-        // mint to
         do_process_instruction(
-            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 42).unwrap(),
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1_000).unwrap(),
             vec![&mut mint_account, &mut account_account, &mut owner_account],
         )
         .unwrap();
 
-        let mint = Mint::unpack_unchecked(&mint_account.data).unwrap();
-        
-        assert_eq!(mint.supply, 42);
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.amount, 42);
+        // the permanent delegate needs the mint to be provided to transfer
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                transfer(
+                    &program_id,
+                    &account_key,
+                    &account2_key,
+                    &delegate_key,
+                    &[],
+                    100
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut account2_account,
+                    &mut delegate_account,
+                ],
+            )
+        );
 
-        // mint to another account to test supply accumulation
-        do_process_instruction(
-            mint_to(&program_id, &mint_key, &account2_key, &owner_key, &[], 42).unwrap(),
-            vec![&mut mint_account, &mut account2_account, &mut owner_account],
+        // permanent delegate not signed
+        let mut instruction = transfer_checked(
+            &program_id,
+            &account_key,
+            &mint_key,
+            &account2_key,
+            &delegate_key,
+            &[],
+            100,
+            2,
         )
         .unwrap();
-
-        let mint = Mint::unpack_unchecked(&mint_account.data).unwrap();
-        assert_eq!(mint.supply, 84);
-        let account = Account::unpack_unchecked(&account2_account.data).unwrap();
-        assert_eq!(account.amount, 42);
-
-        // missing signer
-        let mut instruction =
-            mint_to(&program_id, &mint_key, &account2_key, &owner_key, &[], 42).unwrap();
-        instruction.accounts[2].is_signer = false;
+        instruction.accounts[3].is_signer = false;
         assert_eq!(
             Err(ProgramError::MissingRequiredSignature),
             do_process_instruction(
                 instruction,
-                vec![&mut mint_account, &mut account2_account, &mut owner_account],
+                vec![
+                    &mut account_account,
+                    &mut mint_account,
+                    &mut account2_account,
+                    &mut delegate_account,
+                ],
             )
         );
 
-        // mismatch account
-        assert_eq!(
-            Err(TokenError::MintMismatch.into()),
-            do_process_instruction(
-                mint_to(&program_id, &mint_key, &mismatch_key, &owner_key, &[], 42).unwrap(),
-                vec![&mut mint_account, &mut mismatch_account, &mut owner_account],
+        // transfer without the owner's consent
+        do_process_instruction(
+            transfer_checked(
+                &program_id,
+                &account_key,
+                &mint_key,
+                &account2_key,
+                &delegate_key,
+                &[],
+                100,
+                2,
             )
-        );
+            .unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut account2_account,
+                &mut delegate_account,
+            ],
+        )
+        .unwrap();
+        let account = Account::unpack_base(&account_account.data).unwrap();
+        assert_eq!(account.amount, 900);
+        assert_eq!(account.delegate, COption::None);
+        let account = Account::unpack_base(&account2_account.data).unwrap();
+        assert_eq!(account.amount, 100);
+
+        // burn without the owner's consent
+        do_process_instruction(
+            burn(&program_id, &account_key, &mint_key, &delegate_key, &[], 400).unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut delegate_account,
+            ],
+        )
+        .unwrap();
+        let account = Account::unpack_base(&account_account.data).unwrap();
+        assert_eq!(account.amount, 500);
+        assert_eq!(Mint::unpack_base(&mint_account.data).unwrap().supply, 600);
 
-        // missing owner
+        // only the permanent delegate may hand over the role
         assert_eq!(
             Err(TokenError::OwnerMismatch.into()),
             do_process_instruction(
-                mint_to(&program_id, &mint_key, &account2_key, &owner2_key, &[], 42).unwrap(),
-                vec![
-                    &mut mint_account,
-                    &mut account2_account,
-                    &mut owner2_account,
-                ],
-            )
-        );
-
-        // uninitialized destination account
-        assert_eq!(
-            Err(ProgramError::UninitializedAccount),
-            do_process_instruction(
-                mint_to(
+                set_authority(
                     &program_id,
                     &mint_key,
-                    &uninitialized_key,
+                    Some(&new_delegate_key),
+                    AuthorityType::PermanentDelegate,
                     &owner_key,
                     &[],
-                    42
                 )
                 .unwrap(),
-                vec![
-                    &mut mint_account,
-                    &mut uninitialized_account,
-                    &mut owner_account,
-                ],
+                vec![&mut mint_account, &mut owner_account],
             )
         );
-
-        // unset mint_authority and test minting fails
         do_process_instruction(
             set_authority(
                 &program_id,
                 &mint_key,
-                None,
-                AuthorityType::MintTokens,
-                &owner_key,
+                Some(&new_delegate_key),
+                AuthorityType::PermanentDelegate,
+                &delegate_key,
                 &[],
             )
             .unwrap(),
-            vec![&mut mint_account, &mut mintmeta_account, &mut owner_account],
+            vec![&mut mint_account, &mut delegate_account],
         )
         .unwrap();
+
+        // the previous permanent delegate lost its rights
         assert_eq!(
-            Err(TokenError::FixedSupply.into()),
+            Err(TokenError::OwnerMismatch.into()),
             do_process_instruction(
-                mint_to(&program_id, &mint_key, &account2_key, &owner_key, &[], 42).unwrap(),
-                vec![&mut mint_account, &mut account2_account, &mut owner_account],
+                burn(&program_id, &account_key, &mint_key, &delegate_key, &[], 100).unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut mint_account,
+                    &mut delegate_account,
+                ],
             )
         );
+        do_process_instruction(
+            burn(&program_id, &account_key, &mint_key, &new_delegate_key, &[], 100).unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut new_delegate_account,
+            ],
+        )
+        .unwrap();
+        let account = Account::unpack_base(&account_account.data).unwrap();
+        assert_eq!(account.amount, 400);
     }
 
     #[test]
-    fn test_burn_dups() {
+    fn test_default_account_state() {
         let program_id = crate::id();
-        let account1_key = Pubkey::new_unique();
-        let mut account1_account = PUTAccount::new(
+        let mint_len =
+            ExtensionType::get_account_len::<Mint>(&[ExtensionType::DefaultAccountState]);
+        let account_key = Pubkey::new_unique();
+        let mut account_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account2_key = Pubkey::new_unique();
+        let mut account2_account = PUTAccount::new(
             account_minimum_balance(),
             Account::get_packed_len(),
             &program_id,
         );
-        let account1_info: AccountInfo = (&account1_key, true, &mut account1_account).into();
         let owner_key = Pubkey::new_unique();
         let mut owner_account = PUTAccount::default();
-        let owner_info: AccountInfo = (&owner_key, true, &mut owner_account).into();
+        let freeze_authority_key = Pubkey::new_unique();
+        let mut freeze_authority_account = PUTAccount::default();
         let mint_key = Pubkey::new_unique();
-        let mut mint_account =
-            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-        let mint_info: AccountInfo = (&mint_key, true, &mut mint_account).into();
-        let rent_key = rent::id();
+        let mut mint_account = PUTAccount::new(
+            Rent::default().minimum_balance(mint_len),
+            mint_len,
+            &program_id,
+        );
         let mut rent_sysvar = rent_sysvar();
-        let rent_info: AccountInfo = (&rent_key, false, &mut rent_sysvar).into();
 
-        // create mint
-        do_process_instruction_dups(
-            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
-            vec![mint_info.clone(), rent_info.clone()],
+        // uninitialized is not a valid default
+        assert_eq!(
+            Err(TokenError::InvalidState.into()),
+            do_process_instruction(
+                initialize_default_account_state(
+                    &program_id,
+                    &mint_key,
+                    &AccountState::Uninitialized
+                )
+                .unwrap(),
+                vec![&mut mint_account],
+            )
+        );
+        do_process_instruction(
+            initialize_default_account_state(&program_id, &mint_key, &AccountState::Frozen)
+                .unwrap(),
+            vec![&mut mint_account],
         )
         .unwrap();
 
-        // create account
-        do_process_instruction_dups(
-            initialize_account(&program_id, &account1_key, &mint_key, &account1_key).unwrap(),
-            vec![
-                account1_info.clone(),
-                mint_info.clone(),
-                account1_info.clone(),
-                rent_info.clone(),
-            ],
+        // frozen accounts need a freeze authority to thaw them
+        assert_eq!(
+            Err(TokenError::MintCannotFreeze.into()),
+            do_process_instruction(
+                initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+                vec![&mut mint_account, &mut rent_sysvar],
+            )
+        );
+        do_process_instruction(
+            initialize_mint(
+                &program_id,
+                &mint_key,
+                &owner_key,
+                Some(&freeze_authority_key),
+                2,
+            )
+            .unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
 
-        // mint to account
-        do_process_instruction_dups(
-            mint_to(&program_id, &mint_key, &account1_key, &owner_key, &[], 1000).unwrap(),
-            vec![mint_info.clone(), account1_info.clone(), owner_info.clone()],
+        // new accounts start frozen
+        do_process_instruction(
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
         )
         .unwrap();
-
-        // source-owner burn
-        do_process_instruction_dups(
-            burn(
+        let account = Account::unpack_base(&account_account.data).unwrap();
+        assert_eq!(account.state, AccountState::Frozen);
+        assert_eq!(
+            Err(TokenError::AccountFrozen.into()),
+            do_process_instruction(
+                mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1_000).unwrap(),
+                vec![&mut mint_account, &mut account_account, &mut owner_account],
+            )
+        );
+        do_process_instruction(
+            thaw_account(
                 &program_id,
+                &account_key,
                 &mint_key,
-                &account1_key,
-                &account1_key,
+                &freeze_authority_key,
                 &[],
-                500,
             )
             .unwrap(),
             vec![
-                account1_info.clone(),
-                mint_info.clone(),
-                account1_info.clone(),
+                &mut account_account,
+                &mut mint_account,
+                &mut freeze_authority_account,
             ],
         )
         .unwrap();
+        do_process_instruction(
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1_000).unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
+        )
+        .unwrap();
 
-        // source-owner burn_checked
-        do_process_instruction_dups(
-            burn_checked(
+        // only the freeze authority may change the default
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                update_default_account_state(
+                    &program_id,
+                    &mint_key,
+                    &owner_key,
+                    &[],
+                    &AccountState::Initialized,
+                )
+                .unwrap(),
+                vec![&mut mint_account, &mut owner_account],
+            )
+        );
+        do_process_instruction(
+            update_default_account_state(
                 &program_id,
-                &account1_key,
                 &mint_key,
-                &account1_key,
+                &freeze_authority_key,
                 &[],
-                500,
-                2,
+                &AccountState::Initialized,
             )
             .unwrap(),
-            vec![
-                account1_info.clone(),
-                mint_info.clone(),
-                account1_info.clone(),
-            ],
+            vec![&mut mint_account, &mut freeze_authority_account],
         )
         .unwrap();
 
-        // mint-owner burn
-        do_process_instruction_dups(
-            mint_to(&program_id, &mint_key, &account1_key, &owner_key, &[], 1000).unwrap(),
-            vec![mint_info.clone(), account1_info.clone(), owner_info.clone()],
+        // new accounts start initialized, existing ones are untouched
+        do_process_instruction(
+            initialize_account2(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
+            vec![&mut account2_account, &mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
-        let mut account = Account::unpack_unchecked(&account1_info.data.borrow()).unwrap();
-        account.owner = mint_key;
-        Account::pack(account, &mut account1_info.data.borrow_mut()).unwrap();
-        do_process_instruction_dups(
-            burn(&program_id, &account1_key, &mint_key, &mint_key, &[], 500).unwrap(),
-            vec![account1_info.clone(), mint_info.clone(), mint_info.clone()],
+        let account = Account::unpack_base(&account2_account.data).unwrap();
+        assert_eq!(account.state, AccountState::Initialized);
+        let account = Account::unpack_base(&account_account.data).unwrap();
+        assert_eq!(account.state, AccountState::Initialized);
+        assert_eq!(account.amount, 1_000);
+    }
+
+    #[test]
+    fn test_close_mint() {
+        let program_id = crate::id();
+        let mint_len = ExtensionType::get_account_len::<Mint>(&[ExtensionType::MintCloseAuthority]);
+        let account_key = Pubkey::new_unique();
+        let mut account_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let owner_key = Pubkey::new_unique();
+        let mut owner_account = PUTAccount::default();
+        let close_authority_key = Pubkey::new_unique();
+        let mut close_authority_account = PUTAccount::default();
+        let dest_key = Pubkey::new_unique();
+        let mut dest_account = PUTAccount::default();
+        let mint_key = Pubkey::new_unique();
+        let mint_lamports = Rent::default().minimum_balance(mint_len);
+        let mut mint_account = PUTAccount::new(mint_lamports, mint_len, &program_id);
+        let (mint_meta_key, _) =
+            Pubkey::find_program_address(&[b"MintMeta", &mint_key.to_bytes()], &program_id);
+        let mint_meta_lamports = Rent::default().minimum_balance(MintMeta::LEN);
+        let mut mint_meta_account =
+            PUTAccount::new(mint_meta_lamports, MintMeta::LEN, &program_id);
+        let plain_mint_key = Pubkey::new_unique();
+        let mut plain_mint_account =
+            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mut plain_mint_meta_account = PUTAccount::default();
+        let mut rent_sysvar = rent_sysvar();
+
+        do_process_instruction(
+            initialize_mint_close_authority(&program_id, &mint_key, Some(&close_authority_key))
+                .unwrap(),
+            vec![&mut mint_account],
         )
         .unwrap();
-
-        // mint-owner burn_checked
-        do_process_instruction_dups(
-            burn_checked(
-                &program_id,
-                &account1_key,
-                &mint_key,
-                &mint_key,
-                &[],
-                500,
-                2,
-            )
-            .unwrap(),
-            vec![account1_info.clone(), mint_info.clone(), mint_info.clone()],
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
-
-        // source-delegate burn
-        do_process_instruction_dups(
-            mint_to(&program_id, &mint_key, &account1_key, &owner_key, &[], 1000).unwrap(),
-            vec![mint_info.clone(), account1_info.clone(), owner_info.clone()],
+        do_process_instruction(
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
         )
         .unwrap();
-        let mut account = Account::unpack_unchecked(&account1_info.data.borrow()).unwrap();
-        account.delegated_amount = 1000;
-        account.delegate = COption::Some(account1_key);
-        account.owner = owner_key;
-        Account::pack(account, &mut account1_info.data.borrow_mut()).unwrap();
-        do_process_instruction_dups(
-            burn(
-                &program_id,
-                &account1_key,
-                &mint_key,
-                &account1_key,
-                &[],
-                500,
+        do_process_instruction(
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 100).unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
+        )
+        .unwrap();
+
+        // supply must be zero
+        assert_eq!(
+            Err(TokenError::MintHasSupply.into()),
+            do_process_instruction(
+                close_mint(&program_id, &mint_key, &dest_key, &close_authority_key, &[])
+                    .unwrap(),
+                vec![
+                    &mut mint_account,
+                    &mut dest_account,
+                    &mut close_authority_account,
+                    &mut mint_meta_account,
+                ],
             )
-            .unwrap(),
-            vec![
-                account1_info.clone(),
-                mint_info.clone(),
-                account1_info.clone(),
-            ],
+        );
+        do_process_instruction(
+            burn(&program_id, &account_key, &mint_key, &owner_key, &[], 100).unwrap(),
+            vec![&mut account_account, &mut mint_account, &mut owner_account],
         )
         .unwrap();
 
-        // source-delegate burn_checked
-        do_process_instruction_dups(
-            burn_checked(
-                &program_id,
-                &account1_key,
-                &mint_key,
-                &account1_key,
-                &[],
-                500,
-                2,
+        // only the close authority may close the mint
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                close_mint(&program_id, &mint_key, &dest_key, &owner_key, &[]).unwrap(),
+                vec![
+                    &mut mint_account,
+                    &mut dest_account,
+                    &mut owner_account,
+                    &mut mint_meta_account,
+                ],
             )
-            .unwrap(),
+        );
+
+        // the meta account must be the mint's
+        let mut instruction =
+            close_mint(&program_id, &mint_key, &dest_key, &close_authority_key, &[]).unwrap();
+        instruction.accounts[3].pubkey = Pubkey::new_unique();
+        assert_eq!(
+            Err(ProgramError::InvalidSeeds),
+            do_process_instruction(
+                instruction,
+                vec![
+                    &mut mint_account,
+                    &mut dest_account,
+                    &mut close_authority_account,
+                    &mut mint_meta_account,
+                ],
+            )
+        );
+
+        // close the mint and its meta account
+        do_process_instruction(
+            close_mint(&program_id, &mint_key, &dest_key, &close_authority_key, &[]).unwrap(),
             vec![
-                account1_info.clone(),
-                mint_info.clone(),
-                account1_info.clone(),
+                &mut mint_account,
+                &mut dest_account,
+                &mut close_authority_account,
+                &mut mint_meta_account,
             ],
         )
         .unwrap();
+        assert_eq!(dest_account.lamports, mint_lamports + mint_meta_lamports);
+        assert_eq!(mint_account.lamports, 0);
+        assert_eq!(mint_meta_account.lamports, 0);
+        assert!(mint_account.data.iter().all(|byte| *byte == 0));
 
-        // mint-delegate burn
-        do_process_instruction_dups(
-            mint_to(&program_id, &mint_key, &account1_key, &owner_key, &[], 1000).unwrap(),
-            vec![mint_info.clone(), account1_info.clone(), owner_info.clone()],
-        )
-        .unwrap();
-        let mut account = Account::unpack_unchecked(&account1_info.data.borrow()).unwrap();
-        account.delegated_amount = 1000;
-        account.delegate = COption::Some(mint_key);
-        account.owner = owner_key;
-        Account::pack(account, &mut account1_info.data.borrow_mut()).unwrap();
-        do_process_instruction_dups(
-            burn(&program_id, &account1_key, &mint_key, &mint_key, &[], 500).unwrap(),
-            vec![account1_info.clone(), mint_info.clone(), mint_info.clone()],
+        // mints without a close authority cannot be closed
+        do_process_instruction(
+            initialize_mint(&program_id, &plain_mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut plain_mint_account, &mut rent_sysvar],
         )
         .unwrap();
-
-        // mint-delegate burn_checked
-        do_process_instruction_dups(
-            burn_checked(
-                &program_id,
-                &account1_key,
-                &mint_key,
-                &mint_key,
-                &[],
-                500,
-                2,
+        assert_eq!(
+            Err(TokenError::NoAuthorityExists.into()),
+            do_process_instruction(
+                close_mint(&program_id, &plain_mint_key, &dest_key, &owner_key, &[]).unwrap(),
+                vec![
+                    &mut plain_mint_account,
+                    &mut dest_account,
+                    &mut owner_account,
+                    &mut plain_mint_meta_account,
+                ],
             )
-            .unwrap(),
-            vec![account1_info.clone(), mint_info.clone(), mint_info.clone()],
-        )
-        .unwrap();
+        );
+    }
+
+    fn instructions_sysvar_account(
+        instructions: &[&Instruction],
+        current_index: u16,
+    ) -> PUTAccount {
+        let borrowed_instructions = instructions
+            .iter()
+            .map(|instruction| BorrowedInstruction {
+                program_id: &instruction.program_id,
+                accounts: instruction
+                    .accounts
+                    .iter()
+                    .map(|account_meta| BorrowedAccountMeta {
+                        pubkey: &account_meta.pubkey,
+                        is_signer: account_meta.is_signer,
+                        is_writable: account_meta.is_writable,
+                    })
+                    .collect(),
+                data: &instruction.data,
+            })
+            .collect::<Vec<_>>();
+        let mut data = construct_instructions_data(&borrowed_instructions);
+        store_current_index(&mut data, current_index);
+        let mut account = PUTAccount::new(0, data.len(), &put_program::sysvar::id());
+        account.data = data;
+        account
     }
 
     #[test]
-    fn test_burn() {
+    fn test_required_transfer_memos() {
         let program_id = crate::id();
+        let account_len = ExtensionType::get_account_len::<Account>(&[ExtensionType::MemoTransfer]);
         let account_key = Pubkey::new_unique();
         let mut account_account = PUTAccount::new(
             account_minimum_balance(),
@@ -4238,22 +9538,8 @@ mod tests {
         );
         let account2_key = Pubkey::new_unique();
         let mut account2_account = PUTAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
-        );
-        let account3_key = Pubkey::new_unique();
-        let mut account3_account = PUTAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
-        );
-        let delegate_key = Pubkey::new_unique();
-        let mut delegate_account = PUTAccount::default();
-        let mismatch_key = Pubkey::new_unique();
-        let mut mismatch_account = PUTAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
+            Rent::default().minimum_balance(account_len),
+            account_len,
             &program_id,
         );
         let owner_key = Pubkey::new_unique();
@@ -4263,17 +9549,13 @@ mod tests {
         let mint_key = Pubkey::new_unique();
         let mut mint_account =
             PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-        let mint2_key = Pubkey::new_unique();
         let mut rent_sysvar = rent_sysvar();
 
-        // create new mint
         do_process_instruction(
             initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
             vec![&mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
-
-        // create account
         do_process_instruction(
             initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
             vec![
@@ -4284,233 +9566,159 @@ mod tests {
             ],
         )
         .unwrap();
-
-        // create another account
         do_process_instruction(
-            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
+            initialize_account(&program_id, &account2_key, &mint_key, &owner2_key).unwrap(),
             vec![
                 &mut account2_account,
                 &mut mint_account,
-                &mut owner_account,
-                &mut rent_sysvar,
-            ],
-        )
-        .unwrap();
-
-        // create another account
-        do_process_instruction(
-            initialize_account(&program_id, &account3_key, &mint_key, &owner_key).unwrap(),
-            vec![
-                &mut account3_account,
-                &mut mint_account,
-                &mut owner_account,
-                &mut rent_sysvar,
-            ],
-        )
-        .unwrap();
-
-        // create mismatch account
-        do_process_instruction(
-            initialize_account(&program_id, &mismatch_key, &mint_key, &owner_key).unwrap(),
-            vec![
-                &mut mismatch_account,
-                &mut mint_account,
-                &mut owner_account,
+                &mut owner2_account,
                 &mut rent_sysvar,
             ],
         )
         .unwrap();
-
-        // mint to account
         do_process_instruction(
-            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1000).unwrap(),
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1_000).unwrap(),
             vec![&mut mint_account, &mut account_account, &mut owner_account],
         )
         .unwrap();
 
-        // mint to mismatch account and change mint key
-        do_process_instruction(
-            mint_to(&program_id, &mint_key, &mismatch_key, &owner_key, &[], 1000).unwrap(),
-            vec![&mut mint_account, &mut mismatch_account, &mut owner_account],
-        )
-        .unwrap();
-        let mut account = Account::unpack_unchecked(&mismatch_account.data).unwrap();
-        account.mint = mint2_key;
-        Account::pack(account, &mut mismatch_account.data).unwrap();
-
-        // missing signer
-        let mut instruction =
-            burn(&program_id, &account_key, &mint_key, &delegate_key, &[], 42).unwrap();
-        instruction.accounts[1].is_signer = false;
+        // accounts need room for the setting
         assert_eq!(
-            Err(TokenError::OwnerMismatch.into()),
+            Err(ProgramError::InvalidAccountData),
             do_process_instruction(
-                instruction,
-                vec![
-                    &mut account_account,
-                    &mut mint_account,
-                    &mut delegate_account
-                ],
+                enable_required_transfer_memos(&program_id, &account_key, &owner_key, &[])
+                    .unwrap(),
+                vec![&mut account_account, &mut owner_account],
             )
         );
 
-        // missing owner
+        // only the owner may require memos
         assert_eq!(
             Err(TokenError::OwnerMismatch.into()),
             do_process_instruction(
-                burn(&program_id, &account_key, &mint_key, &owner2_key, &[], 42).unwrap(),
-                vec![&mut account_account, &mut mint_account, &mut owner2_account],
-            )
-        );
-
-        // mint mismatch
-        assert_eq!(
-            Err(TokenError::MintMismatch.into()),
-            do_process_instruction(
-                burn(&program_id, &mismatch_key, &mint_key, &owner_key, &[], 42).unwrap(),
-                vec![&mut mismatch_account, &mut mint_account, &mut owner_account],
+                enable_required_transfer_memos(&program_id, &account2_key, &owner_key, &[])
+                    .unwrap(),
+                vec![&mut account2_account, &mut owner_account],
             )
         );
-
-        // burn
         do_process_instruction(
-            burn(&program_id, &account_key, &mint_key, &owner_key, &[], 21).unwrap(),
-            vec![&mut account_account, &mut mint_account, &mut owner_account],
+            enable_required_transfer_memos(&program_id, &account2_key, &owner2_key, &[]).unwrap(),
+            vec![&mut account2_account, &mut owner2_account],
         )
         .unwrap();
+        assert_eq!(
+            get_extension::<MemoTransfer>(&account2_account.data).unwrap(),
+            Some(MemoTransfer {
+                require_incoming_transfer_memos: true
+            })
+        );
 
-        // burn_checked, with incorrect decimals
+        // transfers without the instructions sysvar fail
         assert_eq!(
-            Err(TokenError::MintDecimalsMismatch.into()),
+            Err(TokenError::NoMemo.into()),
             do_process_instruction(
-                burn_checked(&program_id, &account_key, &mint_key, &owner_key, &[], 21, 3).unwrap(),
-                vec![&mut account_account, &mut mint_account, &mut owner_account],
+                transfer(&program_id, &account_key, &account2_key, &owner_key, &[], 100).unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut account2_account,
+                    &mut owner_account,
+                ],
             )
         );
 
-        // burn_checked
-        do_process_instruction(
-            burn_checked(&program_id, &account_key, &mint_key, &owner_key, &[], 21, 2).unwrap(),
-            vec![&mut account_account, &mut mint_account, &mut owner_account],
-        )
-        .unwrap();
-
-        let mint = Mint::unpack_unchecked(&mint_account.data).unwrap();
-        assert_eq!(mint.supply, 2000 - 42);
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.amount, 1000 - 42);
-
-        // insufficient funds
+        // transfers not preceded by a memo fail
+        let mut instruction =
+            transfer(&program_id, &account_key, &account2_key, &owner_key, &[], 100).unwrap();
+        instruction.accounts.push(AccountMeta::new_readonly(
+            put_program::sysvar::instructions::id(),
+            false,
+        ));
+        let mut instructions_sysvar = instructions_sysvar_account(&[&instruction], 0);
         assert_eq!(
-            Err(TokenError::InsufficientFunds.into()),
+            Err(TokenError::NoMemo.into()),
             do_process_instruction(
-                burn(
-                    &program_id,
-                    &account_key,
-                    &mint_key,
-                    &owner_key,
-                    &[],
-                    100_000_000
-                )
-                .unwrap(),
-                vec![&mut account_account, &mut mint_account, &mut owner_account],
+                instruction.clone(),
+                vec![
+                    &mut account_account,
+                    &mut account2_account,
+                    &mut owner_account,
+                    &mut instructions_sysvar,
+                ],
             )
         );
-
-        // approve delegate
-        do_process_instruction(
-            approve(
-                &program_id,
-                &account_key,
-                &delegate_key,
-                &owner_key,
-                &[],
-                84,
-            )
-            .unwrap(),
-            vec![
-                &mut account_account,
-                &mut delegate_account,
-                &mut owner_account,
-            ],
-        )
-        .unwrap();
-
-        // not a delegate of source account
+        let other_instruction = sync_native(&program_id, &account_key).unwrap();
+        let mut instructions_sysvar =
+            instructions_sysvar_account(&[&other_instruction, &instruction], 1);
         assert_eq!(
-            Err(TokenError::InsufficientFunds.into()),
+            Err(TokenError::NoMemo.into()),
             do_process_instruction(
-                burn(
-                    &program_id,
-                    &account_key,
-                    &mint_key,
-                    &owner_key,
-                    &[],
-                    100_000_000
-                )
-                .unwrap(),
-                vec![&mut account_account, &mut mint_account, &mut owner_account],
+                instruction.clone(),
+                vec![
+                    &mut account_account,
+                    &mut account2_account,
+                    &mut owner_account,
+                    &mut instructions_sysvar,
+                ],
             )
         );
 
-        // burn via delegate
+        // transfers preceded by a memo succeed
+        let memo_instruction = ppl_memo::build_memo(b"deposit 42", &[]);
+        let mut instructions_sysvar =
+            instructions_sysvar_account(&[&memo_instruction, &instruction], 1);
         do_process_instruction(
-            burn(&program_id, &account_key, &mint_key, &delegate_key, &[], 84).unwrap(),
+            instruction,
             vec![
                 &mut account_account,
-                &mut mint_account,
-                &mut delegate_account,
+                &mut account2_account,
+                &mut owner_account,
+                &mut instructions_sysvar,
             ],
         )
         .unwrap();
+        let account = Account::unpack_base(&account2_account.data).unwrap();
+        assert_eq!(account.amount, 100);
 
-        // match
-        let mint = Mint::unpack_unchecked(&mint_account.data).unwrap();
-        assert_eq!(mint.supply, 2000 - 42 - 84);
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.amount, 1000 - 42 - 84);
+        // outgoing transfers are not affected
+        do_process_instruction(
+            transfer(&program_id, &account2_key, &account_key, &owner2_key, &[], 100).unwrap(),
+            vec![
+                &mut account2_account,
+                &mut account_account,
+                &mut owner2_account,
+            ],
+        )
+        .unwrap();
 
-        // insufficient funds approved via delegate
-        assert_eq!(
-            Err(TokenError::OwnerMismatch.into()),
-            do_process_instruction(
-                burn(
-                    &program_id,
-                    &account_key,
-                    &mint_key,
-                    &delegate_key,
-                    &[],
-                    100
-                )
+        // memos are no longer required once disabled
+        do_process_instruction(
+            disable_required_transfer_memos(&program_id, &account2_key, &owner2_key, &[])
                 .unwrap(),
-                vec![
-                    &mut account_account,
-                    &mut mint_account,
-                    &mut delegate_account
-                ],
-            )
-        );
+            vec![&mut account2_account, &mut owner2_account],
+        )
+        .unwrap();
+        do_process_instruction(
+            transfer(&program_id, &account_key, &account2_key, &owner_key, &[], 100).unwrap(),
+            vec![
+                &mut account_account,
+                &mut account2_account,
+                &mut owner_account,
+            ],
+        )
+        .unwrap();
+        let account = Account::unpack_base(&account2_account.data).unwrap();
+        assert_eq!(account.amount, 100);
     }
 
     #[test]
-    fn test_multisig() {
+    fn test_cpi_guard() {
+        set_syscall_stubs();
         let program_id = crate::id();
-        let mint_key = Pubkey::new_unique();
-        let mut mint_account =
-            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-
-        let (mintmeta_key, _) =
-            Pubkey::find_program_address(&[b"MintMeta", &mint_key.to_bytes()], &program_id);
-        let mut mintmeta_account = PUTAccount::new(
-            mintmeta_minimum_balance(),
-            MintMeta::get_packed_len(),
-            &program_id,
-        );
-
+        let account_len = ExtensionType::get_account_len::<Account>(&[ExtensionType::CpiGuard]);
         let account_key = Pubkey::new_unique();
-        let mut account = PUTAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
+        let mut account_account = PUTAccount::new(
+            Rent::default().minimum_balance(account_len),
+            account_len,
             &program_id,
         );
         let account2_key = Pubkey::new_unique();
@@ -4521,698 +9729,1030 @@ mod tests {
         );
         let owner_key = Pubkey::new_unique();
         let mut owner_account = PUTAccount::default();
-        let multisig_key = Pubkey::new_unique();
-        let mut multisig_account = PUTAccount::new(42, Multisig::get_packed_len(), &program_id);
-        let multisig_delegate_key = Pubkey::new_unique();
-        let mut multisig_delegate_account = PUTAccount::new(
-            multisig_minimum_balance(),
-            Multisig::get_packed_len(),
-            &program_id,
-        );
-        let signer_keys = vec![Pubkey::new_unique(); MAX_SIGNERS];
-        let signer_key_refs: Vec<&Pubkey> = signer_keys.iter().collect();
-        let mut signer_accounts = vec![PUTAccount::new(0, 0, &program_id); MAX_SIGNERS];
+        let delegate_key = Pubkey::new_unique();
+        let mut delegate_account = PUTAccount::default();
+        let destination_key = Pubkey::new_unique();
+        let mut destination_account = PUTAccount::default();
+        let operator_key = Pubkey::new_unique();
+        let mut operator_account = PUTAccount::default();
+        let payer_key = Pubkey::new_unique();
+        let mut payer_account = PUTAccount::default();
+        let mut system_account = PUTAccount::default();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
         let mut rent_sysvar = rent_sysvar();
 
-        // multisig is not rent exempt
-        let account_info_iter = &mut signer_accounts.iter_mut();
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
+        )
+        .unwrap();
+        do_process_instruction(
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+        do_process_instruction(
+            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account2_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+        do_process_instruction(
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1_000).unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
+        )
+        .unwrap();
+
+        do_process_instruction(
+            enable_cpi_guard(&program_id, &account_key, &owner_key, &[]).unwrap(),
+            vec![&mut account_account, &mut owner_account],
+        )
+        .unwrap();
         assert_eq!(
-            Err(TokenError::NotRentExempt.into()),
+            get_extension::<CpiGuard>(&account_account.data).unwrap(),
+            Some(CpiGuard { lock_cpi: true })
+        );
+
+        let with_instructions_sysvar = |mut instruction: Instruction| {
+            instruction.accounts.push(AccountMeta::new_readonly(
+                put_program::sysvar::instructions::id(),
+                false,
+            ));
+            instruction
+        };
+        // stands in for the top-level instruction of a program invoking this one
+        let caller_instruction = ppl_memo::build_memo(b"caller", &[]);
+
+        // owner transfers need proof of being at the top level
+        assert_eq!(
+            Err(TokenError::CpiGuardBlocked.into()),
             do_process_instruction(
-                initialize_multisig(&program_id, &multisig_key, &[&signer_keys[0]], 1).unwrap(),
+                transfer(&program_id, &account_key, &account2_key, &owner_key, &[], 100).unwrap(),
                 vec![
-                    &mut multisig_account,
-                    &mut rent_sysvar,
-                    &mut account_info_iter.next().unwrap(),
+                    &mut account_account,
+                    &mut account2_account,
+                    &mut owner_account,
                 ],
             )
         );
-
-        multisig_account.lamports = multisig_minimum_balance();
-
-        // single signer
-        let account_info_iter = &mut signer_accounts.iter_mut();
+        let instruction = with_instructions_sysvar(
+            transfer(&program_id, &account_key, &account2_key, &owner_key, &[], 100).unwrap(),
+        );
+        let mut instructions_sysvar = instructions_sysvar_account(&[&caller_instruction], 0);
+        assert_eq!(
+            Err(TokenError::CpiGuardBlocked.into()),
+            do_process_instruction(
+                instruction.clone(),
+                vec![
+                    &mut account_account,
+                    &mut account2_account,
+                    &mut owner_account,
+                    &mut instructions_sysvar,
+                ],
+            )
+        );
+        let mut instructions_sysvar = instructions_sysvar_account(&[&instruction], 0);
         do_process_instruction(
-            initialize_multisig(&program_id, &multisig_key, &[&signer_keys[0]], 1).unwrap(),
+            instruction,
             vec![
-                &mut multisig_account,
-                &mut rent_sysvar,
-                &mut account_info_iter.next().unwrap(),
+                &mut account_account,
+                &mut account2_account,
+                &mut owner_account,
+                &mut instructions_sysvar,
             ],
         )
         .unwrap();
 
-        // multiple signer
-        let account_info_iter = &mut signer_accounts.iter_mut();
-        do_process_instruction(
-            initialize_multisig(
-                &program_id,
-                &multisig_delegate_key,
-                &signer_key_refs,
-                MAX_SIGNERS as u8,
+        // approvals by the owner are guarded too
+        let instruction = with_instructions_sysvar(
+            approve(&program_id, &account_key, &delegate_key, &owner_key, &[], 50).unwrap(),
+        );
+        let mut instructions_sysvar = instructions_sysvar_account(&[&caller_instruction], 0);
+        assert_eq!(
+            Err(TokenError::CpiGuardBlocked.into()),
+            do_process_instruction(
+                instruction.clone(),
+                vec![
+                    &mut account_account,
+                    &mut delegate_account,
+                    &mut owner_account,
+                    &mut instructions_sysvar,
+                ],
             )
-            .unwrap(),
+        );
+        let mut instructions_sysvar = instructions_sysvar_account(&[&instruction], 0);
+        do_process_instruction(
+            instruction,
             vec![
-                &mut multisig_delegate_account,
-                &mut rent_sysvar,
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
+                &mut account_account,
+                &mut delegate_account,
+                &mut owner_account,
+                &mut instructions_sysvar,
             ],
         )
         .unwrap();
 
-        // create new mint with multisig owner
+        // delegates are not affected
         do_process_instruction(
-            initialize_mint(&program_id, &mint_key, &multisig_key, None, 2).unwrap(),
-            vec![&mut mint_account, &mut rent_sysvar],
+            transfer(&program_id, &account_key, &account2_key, &delegate_key, &[], 50).unwrap(),
+            vec![
+                &mut account_account,
+                &mut account2_account,
+                &mut delegate_account,
+            ],
         )
         .unwrap();
 
-        // create new mintmeta
-        do_process_instruction(
-            initialize_mint_meta(
+        // the owner cannot hand over closing through CPI
+        let instruction = with_instructions_sysvar(
+            set_authority(
                 &program_id,
-                &mint_key,
-                &mintmeta_key,
-                "symbol".to_string(),
-                "name".to_string(),
-                "icon".to_string(),
+                &account_key,
+                Some(&delegate_key),
+                AuthorityType::CloseAccount,
+                &owner_key,
+                &[],
             )
             .unwrap(),
-            vec![
-                &mut mint_account,
-                &mut mintmeta_account,
-            ],
-        )
-        .unwrap();
+        );
+        let mut instructions_sysvar = instructions_sysvar_account(&[&caller_instruction], 0);
+        assert_eq!(
+            Err(TokenError::CpiGuardBlocked.into()),
+            do_process_instruction(
+                instruction,
+                vec![
+                    &mut account_account,
+                    &mut owner_account,
+                    &mut instructions_sysvar,
+                ],
+            )
+        );
 
-        // create account with multisig owner
+        // nor the account itself
+        let instruction = with_instructions_sysvar(
+            set_authority(
+                &program_id,
+                &account_key,
+                Some(&delegate_key),
+                AuthorityType::AccountOwner,
+                &owner_key,
+                &[],
+            )
+            .unwrap(),
+        );
+        let mut instructions_sysvar = instructions_sysvar_account(&[&caller_instruction], 0);
+        assert_eq!(
+            Err(TokenError::CpiGuardBlocked.into()),
+            do_process_instruction(
+                instruction,
+                vec![
+                    &mut account_account,
+                    &mut owner_account,
+                    &mut instructions_sysvar,
+                ],
+            )
+        );
+
+        // owner burns are guarded like transfers
+        let instruction = with_instructions_sysvar(
+            burn(&program_id, &account_key, &mint_key, &owner_key, &[], 10).unwrap(),
+        );
+        let mut instructions_sysvar = instructions_sysvar_account(&[&caller_instruction], 0);
+        assert_eq!(
+            Err(TokenError::CpiGuardBlocked.into()),
+            do_process_instruction(
+                instruction.clone(),
+                vec![
+                    &mut account_account,
+                    &mut mint_account,
+                    &mut owner_account,
+                    &mut instructions_sysvar,
+                ],
+            )
+        );
+        let mut instructions_sysvar = instructions_sysvar_account(&[&instruction], 0);
         do_process_instruction(
-            initialize_account(&program_id, &account_key, &mint_key, &multisig_key).unwrap(),
+            instruction,
             vec![
-                &mut account,
+                &mut account_account,
                 &mut mint_account,
-                &mut multisig_account,
-                &mut rent_sysvar,
+                &mut owner_account,
+                &mut instructions_sysvar,
             ],
         )
         .unwrap();
 
-        // create another account with multisig owner
-        do_process_instruction(
-            initialize_account(
-                &program_id,
-                &account2_key,
-                &mint_key,
-                &multisig_delegate_key,
-            )
-            .unwrap(),
-            vec![
-                &mut account2_account,
-                &mut mint_account,
-                &mut multisig_account,
-                &mut rent_sysvar,
-            ],
+        // operators act as the owner, so the guard binds them too
+        let instruction = approve_operator(
+            &program_id,
+            &payer_key,
+            &mint_key,
+            &operator_key,
+            &owner_key,
+            &[],
+            None,
+            0,
         )
         .unwrap();
-
-        // mint to account
-        let account_info_iter = &mut signer_accounts.iter_mut();
+        let mut operator_approval_account = PUTAccount::new(
+            Rent::default().minimum_balance(OperatorApproval::get_packed_len()),
+            OperatorApproval::get_packed_len(),
+            &program_id,
+        );
+        let mut instructions_sysvar = instructions_sysvar_account(&[&instruction], 0);
         do_process_instruction(
-            mint_to(
-                &program_id,
-                &mint_key,
-                &account_key,
-                &multisig_key,
-                &[&signer_keys[0]],
-                1000,
-            )
-            .unwrap(),
+            instruction,
             vec![
+                &mut payer_account,
+                &mut operator_approval_account,
                 &mut mint_account,
-                &mut account,
-                &mut multisig_account,
-                &mut account_info_iter.next().unwrap(),
+                &mut operator_account,
+                &mut system_account,
+                &mut rent_sysvar,
+                &mut owner_account,
+                &mut instructions_sysvar,
             ],
         )
         .unwrap();
-
-        // approve
-        let account_info_iter = &mut signer_accounts.iter_mut();
-        do_process_instruction(
-            approve(
-                &program_id,
-                &account_key,
-                &multisig_delegate_key,
-                &multisig_key,
-                &[&signer_keys[0]],
-                100,
-            )
-            .unwrap(),
-            vec![
-                &mut account,
-                &mut multisig_delegate_account,
-                &mut multisig_account,
-                &mut account_info_iter.next().unwrap(),
-            ],
+        let mut instruction = transfer(
+            &program_id,
+            &account_key,
+            &account2_key,
+            &operator_key,
+            &[],
+            20,
         )
         .unwrap();
-
-        // transfer
-        let account_info_iter = &mut signer_accounts.iter_mut();
-        do_process_instruction(
-            transfer(
-                &program_id,
-                &account_key,
-                &account2_key,
-                &multisig_key,
-                &[&signer_keys[0]],
-                42,
+        add_operator_approval(&mut instruction, &mint_key, &owner_key, &operator_key);
+        let instruction = with_instructions_sysvar(instruction);
+        let mut instructions_sysvar = instructions_sysvar_account(&[&caller_instruction], 0);
+        assert_eq!(
+            Err(TokenError::CpiGuardBlocked.into()),
+            do_process_instruction(
+                instruction.clone(),
+                vec![
+                    &mut account_account,
+                    &mut account2_account,
+                    &mut operator_account,
+                    &mut operator_approval_account,
+                    &mut instructions_sysvar,
+                ],
             )
-            .unwrap(),
+        );
+        let mut instructions_sysvar = instructions_sysvar_account(&[&instruction], 0);
+        do_process_instruction(
+            instruction,
             vec![
-                &mut account,
+                &mut account_account,
                 &mut account2_account,
-                &mut multisig_account,
-                &mut account_info_iter.next().unwrap(),
+                &mut operator_account,
+                &mut operator_approval_account,
+                &mut instructions_sysvar,
             ],
         )
         .unwrap();
-
-        // transfer via delegate
-        let account_info_iter = &mut signer_accounts.iter_mut();
-        do_process_instruction(
-            transfer(
-                &program_id,
-                &account_key,
-                &account2_key,
-                &multisig_delegate_key,
-                &signer_key_refs,
-                42,
+        let mut instruction =
+            burn(&program_id, &account_key, &mint_key, &operator_key, &[], 20).unwrap();
+        add_operator_approval(&mut instruction, &mint_key, &owner_key, &operator_key);
+        let instruction = with_instructions_sysvar(instruction);
+        let mut instructions_sysvar = instructions_sysvar_account(&[&caller_instruction], 0);
+        assert_eq!(
+            Err(TokenError::CpiGuardBlocked.into()),
+            do_process_instruction(
+                instruction.clone(),
+                vec![
+                    &mut account_account,
+                    &mut mint_account,
+                    &mut operator_account,
+                    &mut operator_approval_account,
+                    &mut instructions_sysvar,
+                ],
             )
-            .unwrap(),
+        );
+        let mut instructions_sysvar = instructions_sysvar_account(&[&instruction], 0);
+        do_process_instruction(
+            instruction,
             vec![
-                &mut account,
-                &mut account2_account,
-                &mut multisig_delegate_account,
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
+                &mut account_account,
+                &mut mint_account,
+                &mut operator_account,
+                &mut operator_approval_account,
+                &mut instructions_sysvar,
             ],
         )
         .unwrap();
+        assert_eq!(
+            Account::unpack_base(&account_account.data).unwrap().amount,
+            800
+        );
 
-        // mint to
-        let account_info_iter = &mut signer_accounts.iter_mut();
+        // nor close the account through CPI
+        let instruction = with_instructions_sysvar(
+            transfer(&program_id, &account_key, &account2_key, &owner_key, &[], 800).unwrap(),
+        );
+        let mut instructions_sysvar = instructions_sysvar_account(&[&instruction], 0);
         do_process_instruction(
-            mint_to(
-                &program_id,
-                &mint_key,
-                &account2_key,
-                &multisig_key,
-                &[&signer_keys[0]],
-                42,
-            )
-            .unwrap(),
+            instruction,
             vec![
-                &mut mint_account,
+                &mut account_account,
                 &mut account2_account,
-                &mut multisig_account,
-                &mut account_info_iter.next().unwrap(),
+                &mut owner_account,
+                &mut instructions_sysvar,
             ],
         )
         .unwrap();
+        let instruction = with_instructions_sysvar(
+            close_account(&program_id, &account_key, &destination_key, &owner_key, &[]).unwrap(),
+        );
+        let mut instructions_sysvar = instructions_sysvar_account(&[&caller_instruction], 0);
+        assert_eq!(
+            Err(TokenError::CpiGuardBlocked.into()),
+            do_process_instruction(
+                instruction,
+                vec![
+                    &mut account_account,
+                    &mut destination_account,
+                    &mut owner_account,
+                    &mut instructions_sysvar,
+                ],
+            )
+        );
 
-        // burn
-        let account_info_iter = &mut signer_accounts.iter_mut();
-        do_process_instruction(
-            burn(
-                &program_id,
-                &account_key,
-                &mint_key,
-                &multisig_key,
-                &[&signer_keys[0]],
-                42,
+        // the guard can only be lifted at the top level
+        let instruction = disable_cpi_guard(&program_id, &account_key, &owner_key, &[]).unwrap();
+        let mut instructions_sysvar = instructions_sysvar_account(&[&caller_instruction], 0);
+        assert_eq!(
+            Err(TokenError::CpiGuardBlocked.into()),
+            do_process_instruction(
+                instruction.clone(),
+                vec![
+                    &mut account_account,
+                    &mut owner_account,
+                    &mut instructions_sysvar,
+                ],
             )
-            .unwrap(),
+        );
+        let mut instructions_sysvar = instructions_sysvar_account(&[&instruction], 0);
+        do_process_instruction(
+            instruction,
             vec![
-                &mut account,
-                &mut mint_account,
-                &mut multisig_account,
-                &mut account_info_iter.next().unwrap(),
+                &mut account_account,
+                &mut owner_account,
+                &mut instructions_sysvar,
             ],
         )
         .unwrap();
-
-        // burn via delegate
-        let account_info_iter = &mut signer_accounts.iter_mut();
+        assert_eq!(
+            get_extension::<CpiGuard>(&account_account.data).unwrap(),
+            Some(CpiGuard { lock_cpi: false })
+        );
         do_process_instruction(
-            burn(
-                &program_id,
-                &account_key,
-                &mint_key,
-                &multisig_delegate_key,
-                &signer_key_refs,
-                42,
-            )
-            .unwrap(),
+            close_account(&program_id, &account_key, &destination_key, &owner_key, &[]).unwrap(),
             vec![
-                &mut account,
-                &mut mint_account,
-                &mut multisig_delegate_account,
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
+                &mut account_account,
+                &mut destination_account,
+                &mut owner_account,
             ],
         )
         .unwrap();
+    }
 
-        // freeze account
-        let account3_key = Pubkey::new_unique();
-        let mut account3_account = PUTAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
+    #[test]
+    fn test_mint_meta() {
+        let program_id = crate::id();
+        let owner_key = Pubkey::new_unique();
+        let mut owner_account = PUTAccount::default();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let (mintmeta_key, _) =
+            Pubkey::find_program_address(&[b"MintMeta", &mint_key.to_bytes()], &program_id);
+        let mut mintmeta_account = PUTAccount::new(
+            mintmeta_minimum_balance(),
+            MintMeta::get_packed_len(),
             &program_id,
         );
-        let mint2_key = Pubkey::new_unique();
-        let mut mint2_account =
-            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-        do_process_instruction(
-            initialize_mint(
-                &program_id,
-                &mint2_key,
-                &multisig_key,
-                Some(&multisig_key),
-                2,
-            )
-            .unwrap(),
-            vec![&mut mint2_account, &mut rent_sysvar],
-        )
-        .unwrap();
+        let mut rent_sysvar = rent_sysvar();
+
+        // newlines no longer split the record
+        let mint_meta = MintMeta {
+            is_initialized: true,
+            authority: COption::Some(owner_key),
+            symbol: "TKN".to_string(),
+            name: "two\nlines".to_string(),
+            icon: "https://example.com/icon.png".to_string(),
+        };
+        let mut packed = vec![0; MintMeta::get_packed_len()];
+        MintMeta::pack(mint_meta.clone(), &mut packed).unwrap();
+        assert!(!MintMeta::is_legacy_layout(&packed));
+        assert_eq!(MintMeta::unpack(&packed).unwrap(), mint_meta);
+
+        // a record that does not fit is rejected rather than cut short
+        let too_long = MintMeta {
+            icon: "\u{e9}".repeat(MAX_ICON_LEN),
+            ..mint_meta.clone()
+        };
+        assert_eq!(
+            Err(TokenError::MintMetaTooLong.into()),
+            MintMeta::pack(too_long, &mut packed)
+        );
+
         do_process_instruction(
-            initialize_account(&program_id, &account3_key, &mint2_key, &owner_key).unwrap(),
-            vec![
-                &mut account3_account,
-                &mut mint2_account,
-                &mut owner_account,
-                &mut rent_sysvar,
-            ],
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
-        let account_info_iter = &mut signer_accounts.iter_mut();
+
+        // values over their maximum length cannot be encoded
+        assert_eq!(
+            Err(TokenError::MintMetaTooLong.into()),
+            initialize_mint_meta(
+                &program_id,
+                &mint_key,
+                &mintmeta_key,
+                "S".repeat(MAX_SYMBOL_LEN + 1),
+                "name".to_string(),
+                "icon".to_string(),
+            )
+        );
         do_process_instruction(
-            mint_to(
+            initialize_mint_meta(
                 &program_id,
-                &mint2_key,
-                &account3_key,
-                &multisig_key,
-                &[&signer_keys[0]],
-                1000,
+                &mint_key,
+                &mintmeta_key,
+                "S".repeat(MAX_SYMBOL_LEN),
+                "N".repeat(MAX_NAME_LEN),
+                "I".repeat(MAX_ICON_LEN),
             )
             .unwrap(),
-            vec![
-                &mut mint2_account,
-                &mut account3_account,
-                &mut multisig_account,
-                &mut account_info_iter.next().unwrap(),
-            ],
+            vec![&mut mint_account, &mut mintmeta_account],
         )
         .unwrap();
-        let account_info_iter = &mut signer_accounts.iter_mut();
-        do_process_instruction(
-            freeze_account(
+        assert_eq!(
+            Err(TokenError::MintMetaTooLong.into()),
+            update_token_name(
                 &program_id,
-                &account3_key,
-                &mint2_key,
-                &multisig_key,
-                &[&signer_keys[0]],
+                &mintmeta_key,
+                &owner_key,
+                &[],
+                "N".repeat(MAX_NAME_LEN + 1),
             )
-            .unwrap(),
-            vec![
-                &mut account3_account,
-                &mut mint2_account,
-                &mut multisig_account,
-                &mut account_info_iter.next().unwrap(),
-            ],
+        );
+        do_process_instruction(
+            update_token_name(&program_id, &mintmeta_key, &owner_key, &[], "Token".to_string())
+                .unwrap(),
+            vec![&mut mintmeta_account, &mut owner_account],
         )
         .unwrap();
+        let mint_meta = MintMeta::unpack(&mintmeta_account.data).unwrap();
+        assert_eq!(mint_meta.symbol, "S".repeat(MAX_SYMBOL_LEN));
+        assert_eq!(mint_meta.name, "Token");
+        assert_eq!(mint_meta.icon, "I".repeat(MAX_ICON_LEN));
+
+        // records in the original layout are still read, and can be migrated
+        let legacy = b"OLD\nOld token\nhttps://example.com/old.png\n";
+        mintmeta_account.data[37..].fill(0);
+        mintmeta_account.data[37..37 + legacy.len()].copy_from_slice(legacy);
+        assert!(MintMeta::is_legacy_layout(&mintmeta_account.data));
+        let legacy_mint_meta = MintMeta::unpack(&mintmeta_account.data).unwrap();
+        assert_eq!(legacy_mint_meta.authority, COption::Some(owner_key));
+        assert_eq!(legacy_mint_meta.symbol, "OLD");
+        assert_eq!(legacy_mint_meta.name, "Old token");
+        assert_eq!(legacy_mint_meta.icon, "https://example.com/old.png");
 
-        // do SetAuthority on mint
-        let account_info_iter = &mut signer_accounts.iter_mut();
         do_process_instruction(
-            set_authority(
-                &program_id,
-                &mint_key,
-                Some(&owner_key),
-                AuthorityType::MintTokens,
-                &multisig_key,
-                &[&signer_keys[0]],
-            )
-            .unwrap(),
-            vec![
-                &mut mint_account,
-                &mut multisig_account,
-                &mut account_info_iter.next().unwrap(),
-            ],
+            migrate_mint_meta(&program_id, &mintmeta_key).unwrap(),
+            vec![&mut mintmeta_account],
         )
         .unwrap();
-
-        // do SetAuthority on account
-        let account_info_iter = &mut signer_accounts.iter_mut();
+        assert!(!MintMeta::is_legacy_layout(&mintmeta_account.data));
+        assert_eq!(
+            MintMeta::unpack(&mintmeta_account.data).unwrap(),
+            legacy_mint_meta
+        );
+        let migrated_data = mintmeta_account.data.clone();
         do_process_instruction(
-            set_authority(
-                &program_id,
-                &account_key,
-                Some(&owner_key),
-                AuthorityType::AccountOwner,
-                &multisig_key,
-                &[&signer_keys[0]],
-            )
-            .unwrap(),
-            vec![
-                &mut account,
-                &mut multisig_account,
-                &mut account_info_iter.next().unwrap(),
-            ],
+            migrate_mint_meta(&program_id, &mintmeta_key).unwrap(),
+            vec![&mut mintmeta_account],
         )
         .unwrap();
+        assert_eq!(mintmeta_account.data, migrated_data);
+
+        // legacy records that do not fit the current layout are reported, not truncated
+        let legacy = format!("S\nN\n{}\n", "I".repeat(163));
+        mintmeta_account.data[37..].copy_from_slice(legacy.as_bytes());
+        assert_eq!(
+            Err(TokenError::MintMetaTooLong.into()),
+            do_process_instruction(
+                migrate_mint_meta(&program_id, &mintmeta_key).unwrap(),
+                vec![&mut mintmeta_account],
+            )
+        );
     }
 
     #[test]
-    fn test_validate_owner() {
+    fn test_mint_meta_additional_metadata() {
         let program_id = crate::id();
         let owner_key = Pubkey::new_unique();
-        let mut signer_keys = [Pubkey::default(); MAX_SIGNERS];
-        for signer_key in signer_keys.iter_mut().take(MAX_SIGNERS) {
-            *signer_key = Pubkey::new_unique();
-        }
-        let mut signer_lamports = 0;
-        let mut signer_data = vec![];
-        let mut signers = vec![
-            AccountInfo::new(
-                &owner_key,
-                true,
-                false,
-                &mut signer_lamports,
-                &mut signer_data,
-                &program_id,
-                false,
-                Epoch::default(),
-            );
-            MAX_SIGNERS + 1
-        ];
-        for (signer, key) in signers.iter_mut().zip(&signer_keys) {
-            signer.key = key;
-        }
-        let mut lamports = 0;
-        let mut data = vec![0; Multisig::get_packed_len()];
-        let mut multisig = Multisig::unpack_unchecked(&data).unwrap();
-        multisig.m = MAX_SIGNERS as u8;
-        multisig.n = MAX_SIGNERS as u8;
-        multisig.signers = signer_keys;
-        multisig.is_initialized = true;
-        Multisig::pack(multisig, &mut data).unwrap();
-        let owner_account_info = AccountInfo::new(
-            &owner_key,
-            false,
-            false,
-            &mut lamports,
-            &mut data,
+        let mut owner_account = PUTAccount::default();
+        let payer_key = Pubkey::new_unique();
+        let mut payer_account = PUTAccount::default();
+        let mut other_account = PUTAccount::default();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let (mintmeta_key, _) =
+            Pubkey::find_program_address(&[b"MintMeta", &mint_key.to_bytes()], &program_id);
+        let mut mintmeta_account = PUTAccount::new(
+            mintmeta_minimum_balance(),
+            MintMeta::get_packed_len(),
             &program_id,
-            false,
-            Epoch::default(),
         );
+        let mut system_program_account = PUTAccount::default();
+        let mut rent_sysvar = rent_sysvar();
 
-        // full 11 of 11
-        Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers).unwrap();
-
-        // 1 of 11
-        {
-            let mut multisig =
-                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
-            multisig.m = 1;
-            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
-        }
-        Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers).unwrap();
-
-        // 2:1
-        {
-            let mut multisig =
-                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
-            multisig.m = 2;
-            multisig.n = 1;
-            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
-        }
+        // key/value pairs follow the packed meta
+        let mint_meta = MintMeta {
+            is_initialized: true,
+            authority: COption::Some(owner_key),
+            symbol: "S".repeat(MAX_SYMBOL_LEN),
+            name: "N".repeat(MAX_NAME_LEN),
+            icon: "I".repeat(MAX_ICON_LEN),
+        };
+        let additional_metadata = vec![
+            ("website".to_string(), "https://example.com".to_string()),
+            ("decimals_hint".to_string(), "2".to_string()),
+        ];
+        let mut data = vec![0; get_mint_meta_len(&additional_metadata)];
+        MintMeta::pack(mint_meta.clone(), &mut data[..MintMeta::LEN]).unwrap();
+        pack_additional_metadata(&additional_metadata, &mut data).unwrap();
         assert_eq!(
-            Err(ProgramError::MissingRequiredSignature),
-            Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers)
-        );
-
-        // 0:11
-        {
-            let mut multisig =
-                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
-            multisig.m = 0;
-            multisig.n = 11;
-            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
-        }
-        Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers).unwrap();
+            MintMeta::unpack(get_mint_meta_base(&data).unwrap()).unwrap(),
+            mint_meta
+        );
+        assert_eq!(get_additional_metadata(&data).unwrap(), additional_metadata);
+
+        // a grown mint meta account is never taken for a mint or a token account
+        assert!(get_account_type(&data).is_err());
+        assert!(Mint::unpack_base(&data).is_err());
+        assert!(Account::unpack_base(&data).is_err());
+        let mint_meta = MintMeta {
+            symbol: "TKN".to_string(),
+            name: "Token".to_string(),
+            icon: "https://example.com/icon.png".to_string(),
+            ..mint_meta
+        };
+        MintMeta::pack(mint_meta, &mut data[..MintMeta::LEN]).unwrap();
+        assert!(get_account_type(&data).is_err());
+        assert!(Account::unpack_base(&data).is_err());
 
-        // 2:11 but 0 provided
-        {
-            let mut multisig =
-                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
-            multisig.m = 2;
-            multisig.n = 11;
-            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
-        }
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
+        )
+        .unwrap();
         assert_eq!(
-            Err(ProgramError::MissingRequiredSignature),
-            Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &[])
+            Err(TokenError::InvalidMintMetaCharacter.into()),
+            do_process_instruction(
+                initialize_mint_meta(
+                    &program_id,
+                    &mint_key,
+                    &mintmeta_key,
+                    "TKN".to_string(),
+                    "Tok\ten".to_string(),
+                    "icon".to_string(),
+                )
+                .unwrap(),
+                vec![&mut mint_account, &mut mintmeta_account],
+            )
         );
-        // 2:11 but 1 provided
-        {
-            let mut multisig =
-                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
-            multisig.m = 2;
-            multisig.n = 11;
-            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
-        }
+        do_process_instruction(
+            initialize_mint_meta(
+                &program_id,
+                &mint_key,
+                &mintmeta_key,
+                "TKN".to_string(),
+                "Token".to_string(),
+                "icon".to_string(),
+            )
+            .unwrap(),
+            vec![&mut mint_account, &mut mintmeta_account],
+        )
+        .unwrap();
+
+        // only the update authority may edit the pairs
         assert_eq!(
-            Err(ProgramError::MissingRequiredSignature),
-            Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers[0..1])
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                set_mint_meta_field(
+                    &program_id,
+                    &mintmeta_key,
+                    &payer_key,
+                    &payer_key,
+                    &[],
+                    "website".to_string(),
+                    "https://example.com".to_string(),
+                )
+                .unwrap(),
+                vec![
+                    &mut mintmeta_account,
+                    &mut payer_account,
+                    &mut system_program_account,
+                    &mut rent_sysvar,
+                    &mut other_account,
+                ],
+            )
         );
 
-        // 2:11, 2 from middle provided
-        {
-            let mut multisig =
-                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
-            multisig.m = 2;
-            multisig.n = 11;
-            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
-        }
-        Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers[5..7])
-            .unwrap();
-
-        // 11:11, one is not a signer
-        {
-            let mut multisig =
-                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
-            multisig.m = 11;
-            multisig.n = 11;
-            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
-        }
-        signers[5].is_signer = false;
+        // keys and values are bounded
+        assert_eq!(
+            Err(TokenError::MintMetaTooLong.into()),
+            set_mint_meta_field(
+                &program_id,
+                &mintmeta_key,
+                &payer_key,
+                &owner_key,
+                &[],
+                "k".repeat(MAX_METADATA_KEY_LEN + 1),
+                "value".to_string(),
+            )
+        );
         assert_eq!(
-            Err(ProgramError::MissingRequiredSignature),
-            Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers)
+            Err(TokenError::MintMetaTooLong.into()),
+            do_process_instruction(
+                set_mint_meta_field(
+                    &program_id,
+                    &mintmeta_key,
+                    &payer_key,
+                    &owner_key,
+                    &[],
+                    "description".to_string(),
+                    "v".repeat(MAX_METADATA_VALUE_LEN + 1),
+                )
+                .unwrap(),
+                vec![
+                    &mut mintmeta_account,
+                    &mut payer_account,
+                    &mut system_program_account,
+                    &mut rent_sysvar,
+                    &mut owner_account,
+                ],
+            )
         );
-        signers[5].is_signer = true;
 
-        // 11:11, single signer signs multiple times
-        {
-            let mut signer_lamports = 0;
-            let mut signer_data = vec![];
-            let signers = vec![
-                AccountInfo::new(
-                    &signer_keys[5],
-                    true,
-                    false,
-                    &mut signer_lamports,
-                    &mut signer_data,
+        // only existing keys can be removed
+        assert_eq!(
+            Err(TokenError::MintMetaFieldNotFound.into()),
+            do_process_instruction(
+                remove_mint_meta_field(
                     &program_id,
-                    false,
-                    Epoch::default(),
-                );
-                MAX_SIGNERS + 1
-            ];
-            let mut multisig =
-                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
-            multisig.m = 11;
-            multisig.n = 11;
-            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
-            assert_eq!(
-                Err(ProgramError::MissingRequiredSignature),
-                Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers)
-            );
-        }
+                    &mintmeta_key,
+                    &payer_key,
+                    &owner_key,
+                    &[],
+                    "website".to_string(),
+                )
+                .unwrap(),
+                vec![
+                    &mut mintmeta_account,
+                    &mut payer_account,
+                    &mut rent_sysvar,
+                    &mut owner_account,
+                ],
+            )
+        );
     }
 
     #[test]
-    fn test_close_account_dups() {
+    fn test_batch_transfer() {
         let program_id = crate::id();
-        let account1_key = Pubkey::new_unique();
-        let mut account1_account = PUTAccount::new(
+        let account_key = Pubkey::new_unique();
+        let mut account_account = PUTAccount::new(
             account_minimum_balance(),
             Account::get_packed_len(),
             &program_id,
         );
-        let account1_info: AccountInfo = (&account1_key, true, &mut account1_account).into();
         let account2_key = Pubkey::new_unique();
         let mut account2_account = PUTAccount::new(
             account_minimum_balance(),
             Account::get_packed_len(),
             &program_id,
         );
-        let account2_info: AccountInfo = (&account2_key, true, &mut account2_account).into();
+        let account3_key = Pubkey::new_unique();
+        let mut account3_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let mismatch_key = Pubkey::new_unique();
+        let mut mismatch_account = PUTAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
         let owner_key = Pubkey::new_unique();
+        let mut owner_account = PUTAccount::default();
+        let delegate_key = Pubkey::new_unique();
+        let mut delegate_account = PUTAccount::default();
         let mint_key = Pubkey::new_unique();
         let mut mint_account =
             PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-        let mint_info: AccountInfo = (&mint_key, false, &mut mint_account).into();
-        let rent_key = rent::id();
+        let mint2_key = Pubkey::new_unique();
+        let mut mint2_account =
+            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
         let mut rent_sysvar = rent_sysvar();
-        let rent_info: AccountInfo = (&rent_key, false, &mut rent_sysvar).into();
 
-        // create mint
-        do_process_instruction_dups(
+        do_process_instruction(
             initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
-            vec![mint_info.clone(), rent_info.clone()],
+            vec![&mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
-
-        // create account
-        do_process_instruction_dups(
-            initialize_account(&program_id, &account1_key, &mint_key, &account1_key).unwrap(),
+        do_process_instruction(
+            initialize_mint(&program_id, &mint2_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint2_account, &mut rent_sysvar],
+        )
+        .unwrap();
+        for (key, account) in [
+            (&account_key, &mut account_account),
+            (&account2_key, &mut account2_account),
+            (&account3_key, &mut account3_account),
+        ] {
+            do_process_instruction(
+                initialize_account(&program_id, key, &mint_key, &owner_key).unwrap(),
+                vec![
+                    account,
+                    &mut mint_account,
+                    &mut owner_account,
+                    &mut rent_sysvar,
+                ],
+            )
+            .unwrap();
+        }
+        do_process_instruction(
+            initialize_account(&program_id, &mismatch_key, &mint2_key, &owner_key).unwrap(),
             vec![
-                account1_info.clone(),
-                mint_info.clone(),
-                account1_info.clone(),
-                rent_info.clone(),
+                &mut mismatch_account,
+                &mut mint2_account,
+                &mut owner_account,
+                &mut rent_sysvar,
             ],
         )
         .unwrap();
+        do_process_instruction(
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1_000).unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
+        )
+        .unwrap();
 
-        // source-owner close
-        do_process_instruction_dups(
-            close_account(
+        // at least one destination is required
+        assert_eq!(
+            Err(TokenError::InvalidInstruction.into()),
+            batch_transfer(&program_id, &account_key, &[], &owner_key, &[])
+        );
+        assert_eq!(
+            Err(TokenError::InvalidInstruction.into()),
+            do_process_instruction(
+                Instruction {
+                    program_id,
+                    accounts: vec![
+                        AccountMeta::new(account_key, false),
+                        AccountMeta::new_readonly(owner_key, true),
+                    ],
+                    data: TokenInstruction::BatchTransfer { amounts: vec![] }.pack(),
+                },
+                vec![&mut account_account, &mut owner_account],
+            )
+        );
+
+        // the whole batch must be covered by the source balance
+        assert_eq!(
+            Err(TokenError::InsufficientFunds.into()),
+            do_process_instruction(
+                batch_transfer(
+                    &program_id,
+                    &account_key,
+                    &[(&account2_key, 600), (&account3_key, 600)],
+                    &owner_key,
+                    &[],
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut account2_account,
+                    &mut account3_account,
+                    &mut owner_account,
+                ],
+            )
+        );
+        assert_eq!(
+            Account::unpack_unchecked(&account_account.data)
+                .unwrap()
+                .amount,
+            1_000
+        );
+
+        // missing signer
+        let mut instruction = batch_transfer(
+            &program_id,
+            &account_key,
+            &[(&account2_key, 100), (&account3_key, 200)],
+            &owner_key,
+            &[],
+        )
+        .unwrap();
+        instruction.accounts[3].is_signer = false;
+        assert_eq!(
+            Err(ProgramError::MissingRequiredSignature),
+            do_process_instruction(
+                instruction,
+                vec![
+                    &mut account_account,
+                    &mut account2_account,
+                    &mut account3_account,
+                    &mut owner_account,
+                ],
+            )
+        );
+
+        // destinations must share the source mint
+        assert_eq!(
+            Err(TokenError::MintMismatch.into()),
+            do_process_instruction(
+                batch_transfer(
+                    &program_id,
+                    &account_key,
+                    &[(&mismatch_key, 100)],
+                    &owner_key,
+                    &[],
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut mismatch_account,
+                    &mut owner_account,
+                ],
+            )
+        );
+
+        // success
+        do_process_instruction(
+            batch_transfer(
                 &program_id,
-                &account1_key,
-                &account2_key,
-                &account1_key,
+                &account_key,
+                &[(&account2_key, 100), (&account3_key, 200)],
+                &owner_key,
                 &[],
             )
             .unwrap(),
             vec![
-                account1_info.clone(),
-                account2_info.clone(),
-                account1_info.clone(),
+                &mut account_account,
+                &mut account2_account,
+                &mut account3_account,
+                &mut owner_account,
             ],
         )
         .unwrap();
+        assert_eq!(
+            Account::unpack_unchecked(&account_account.data)
+                .unwrap()
+                .amount,
+            700
+        );
+        assert_eq!(
+            Account::unpack_unchecked(&account2_account.data)
+                .unwrap()
+                .amount,
+            100
+        );
+        assert_eq!(
+            Account::unpack_unchecked(&account3_account.data)
+                .unwrap()
+                .amount,
+            200
+        );
 
-        // source-close-authority close
-        let mut account = Account::unpack_unchecked(&account1_info.data.borrow()).unwrap();
-        account.close_authority = COption::Some(account1_key);
-        account.owner = owner_key;
-        Account::pack(account, &mut account1_info.data.borrow_mut()).unwrap();
-        do_process_instruction_dups(
-            close_account(
+        // checked variant verifies the mint decimals
+        assert_eq!(
+            Err(TokenError::MintDecimalsMismatch.into()),
+            do_process_instruction(
+                batch_transfer_checked(
+                    &program_id,
+                    &account_key,
+                    &mint_key,
+                    &[(&account2_key, 100), (&account3_key, 100)],
+                    &owner_key,
+                    &[],
+                    10,
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut mint_account,
+                    &mut account2_account,
+                    &mut account3_account,
+                    &mut owner_account,
+                ],
+            )
+        );
+        do_process_instruction(
+            batch_transfer_checked(
+                &program_id,
+                &account_key,
+                &mint_key,
+                &[(&account2_key, 100), (&account3_key, 100)],
+                &owner_key,
+                &[],
+                2,
+            )
+            .unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut account2_account,
+                &mut account3_account,
+                &mut owner_account,
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            Account::unpack_unchecked(&account_account.data)
+                .unwrap()
+                .amount,
+            500
+        );
+
+        // a delegate spends its allowance across the whole batch
+        do_process_instruction(
+            approve(
                 &program_id,
-                &account1_key,
-                &account2_key,
-                &account1_key,
+                &account_key,
+                &delegate_key,
+                &owner_key,
                 &[],
+                150,
             )
             .unwrap(),
             vec![
-                account1_info.clone(),
-                account2_info.clone(),
-                account1_info.clone(),
+                &mut account_account,
+                &mut delegate_account,
+                &mut owner_account,
             ],
         )
         .unwrap();
+        assert_eq!(
+            Err(TokenError::InsufficientFunds.into()),
+            do_process_instruction(
+                batch_transfer(
+                    &program_id,
+                    &account_key,
+                    &[(&account2_key, 100), (&account3_key, 100)],
+                    &delegate_key,
+                    &[],
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut account2_account,
+                    &mut account3_account,
+                    &mut delegate_account,
+                ],
+            )
+        );
     }
 
     #[test]
-    fn test_close_account() {
+    fn test_balance_checkpoint() {
+        set_syscall_stubs();
         let program_id = crate::id();
-        let mint_key = Pubkey::new_unique();
-        let mut mint_account =
-            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
         let account_key = Pubkey::new_unique();
         let mut account_account = PUTAccount::new(
             account_minimum_balance(),
             Account::get_packed_len(),
             &program_id,
         );
-        let account2_key = Pubkey::new_unique();
-        let mut account2_account = PUTAccount::new(
-            account_minimum_balance() + 42,
-            Account::get_packed_len(),
-            &program_id,
-        );
-        let account3_key = Pubkey::new_unique();
-        let mut account3_account = PUTAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
-        );
         let owner_key = Pubkey::new_unique();
         let mut owner_account = PUTAccount::default();
         let owner2_key = Pubkey::new_unique();
         let mut owner2_account = PUTAccount::default();
+        let payer_key = Pubkey::new_unique();
+        let mut payer_account = PUTAccount::default();
+        let mut system_account = PUTAccount::default();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
         let mut rent_sysvar = rent_sysvar();
 
-        // uninitialized
-        assert_eq!(
-            Err(ProgramError::UninitializedAccount),
-            do_process_instruction(
-                close_account(&program_id, &account_key, &account3_key, &owner2_key, &[]).unwrap(),
-                vec![
-                    &mut account_account,
-                    &mut account3_account,
-                    &mut owner2_account,
-                ],
-            )
-        );
-
-        // initialize and mint to non-native account
         do_process_instruction(
             initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
             vec![&mut mint_account, &mut rent_sysvar],
@@ -5229,188 +10769,285 @@ mod tests {
         )
         .unwrap();
         do_process_instruction(
-            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 42).unwrap(),
-            vec![
-                &mut mint_account,
-                &mut account_account,
-                &mut owner_account,
-                &mut rent_sysvar,
-            ],
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 100).unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
         )
         .unwrap();
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.amount, 42);
 
-        // initialize native account
-        do_process_instruction(
-            initialize_account(
-                &program_id,
-                &account2_key,
-                &crate::native_mint::id(),
-                &owner_key,
-            )
-            .unwrap(),
-            vec![
-                &mut account2_account,
-                &mut mint_account,
-                &mut owner_account,
-                &mut rent_sysvar,
-            ],
-        )
-        .unwrap();
-        let account = Account::unpack_unchecked(&account2_account.data).unwrap();
-        assert!(account.is_native());
-        assert_eq!(account.amount, 42);
+        // the test clock sits in epoch 0
+        let checkpoint_key = get_balance_checkpoint_address(&account_key, 0);
+        let checkpoint_balance =
+            Rent::default().minimum_balance(BalanceCheckpoint::get_packed_len());
+        let mut checkpoint_account = PUTAccount::new(
+            checkpoint_balance,
+            BalanceCheckpoint::get_packed_len(),
+            &program_id,
+        );
 
-        // close non-native account with balance
+        // checkpoint of another epoch
         assert_eq!(
-            Err(TokenError::NonNativeHasBalance.into()),
+            Err(ProgramError::InvalidSeeds),
             do_process_instruction(
-                close_account(&program_id, &account_key, &account3_key, &owner_key, &[]).unwrap(),
+                record_balance_checkpoint(
+                    &program_id,
+                    &payer_key,
+                    &account_key,
+                    &owner_key,
+                    &[],
+                    1,
+                )
+                .unwrap(),
                 vec![
+                    &mut payer_account,
+                    &mut checkpoint_account,
                     &mut account_account,
-                    &mut account3_account,
+                    &mut system_account,
+                    &mut rent_sysvar,
                     &mut owner_account,
                 ],
             )
         );
-        assert_eq!(account_account.lamports, account_minimum_balance());
 
-        // empty account
-        do_process_instruction(
-            burn(&program_id, &account_key, &mint_key, &owner_key, &[], 42).unwrap(),
-            vec![&mut account_account, &mut mint_account, &mut owner_account],
-        )
-        .unwrap();
-
-        // wrong owner
+        // only the owner may record
         assert_eq!(
             Err(TokenError::OwnerMismatch.into()),
             do_process_instruction(
-                close_account(&program_id, &account_key, &account3_key, &owner2_key, &[]).unwrap(),
+                record_balance_checkpoint(
+                    &program_id,
+                    &payer_key,
+                    &account_key,
+                    &owner2_key,
+                    &[],
+                    0,
+                )
+                .unwrap(),
                 vec![
+                    &mut payer_account,
+                    &mut checkpoint_account,
                     &mut account_account,
-                    &mut account3_account,
+                    &mut system_account,
+                    &mut rent_sysvar,
                     &mut owner2_account,
                 ],
             )
         );
+        let mut instruction =
+            record_balance_checkpoint(&program_id, &payer_key, &account_key, &owner_key, &[], 0)
+                .unwrap();
+        instruction.accounts[5].is_signer = false;
+        assert_eq!(
+            Err(ProgramError::MissingRequiredSignature),
+            do_process_instruction(
+                instruction,
+                vec![
+                    &mut payer_account,
+                    &mut checkpoint_account,
+                    &mut account_account,
+                    &mut system_account,
+                    &mut rent_sysvar,
+                    &mut owner_account,
+                ],
+            )
+        );
 
-        // close account
+        // checkpoint account held by another program
+        let mut foreign_account = PUTAccount::new(
+            checkpoint_balance,
+            BalanceCheckpoint::get_packed_len(),
+            &Pubkey::new_unique(),
+        );
+        assert_eq!(
+            Err(ProgramError::IncorrectProgramId),
+            do_process_instruction(
+                record_balance_checkpoint(
+                    &program_id,
+                    &payer_key,
+                    &account_key,
+                    &owner_key,
+                    &[],
+                    0,
+                )
+                .unwrap(),
+                vec![
+                    &mut payer_account,
+                    &mut foreign_account,
+                    &mut account_account,
+                    &mut system_account,
+                    &mut rent_sysvar,
+                    &mut owner_account,
+                ],
+            )
+        );
+
+        // success
         do_process_instruction(
-            close_account(&program_id, &account_key, &account3_key, &owner_key, &[]).unwrap(),
+            record_balance_checkpoint(&program_id, &payer_key, &account_key, &owner_key, &[], 0)
+                .unwrap(),
             vec![
+                &mut payer_account,
+                &mut checkpoint_account,
                 &mut account_account,
-                &mut account3_account,
+                &mut system_account,
+                &mut rent_sysvar,
                 &mut owner_account,
             ],
         )
         .unwrap();
-        assert_eq!(account_account.lamports, 0);
-        assert_eq!(account3_account.lamports, 2 * account_minimum_balance());
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.amount, 0);
-
-        // fund and initialize new non-native account to test close authority
-        let account_key = Pubkey::new_unique();
-        let mut account_account = PUTAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
+        assert_eq!(
+            BalanceCheckpoint::unpack(&checkpoint_account.data).unwrap(),
+            BalanceCheckpoint {
+                is_initialized: true,
+                mint: mint_key,
+                owner: owner_key,
+                account: account_key,
+                epoch: 0,
+                slot: 0,
+                amount: 100,
+            }
         );
-        let owner2_key = Pubkey::new_unique();
-        let mut owner2_account = PUTAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
+
+        // recording again within the epoch overwrites the checkpoint
+        do_process_instruction(
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 50).unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
+        )
+        .unwrap();
+        do_process_instruction(
+            record_balance_checkpoint(&program_id, &payer_key, &account_key, &owner_key, &[], 0)
+                .unwrap(),
+            vec![
+                &mut payer_account,
+                &mut checkpoint_account,
+                &mut account_account,
+                &mut system_account,
+                &mut rent_sysvar,
+                &mut owner_account,
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            BalanceCheckpoint::unpack(&checkpoint_account.data)
+                .unwrap()
+                .amount,
+            150
         );
-        let mut meta_account = PUTAccount::new(
+
+        // another account of the same owner keeps a checkpoint of its own
+        let account2_key = Pubkey::new_unique();
+        let mut account2_account = PUTAccount::new(
             account_minimum_balance(),
             Account::get_packed_len(),
             &program_id,
         );
         do_process_instruction(
-            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
             vec![
-                &mut account_account,
+                &mut account2_account,
                 &mut mint_account,
                 &mut owner_account,
                 &mut rent_sysvar,
             ],
         )
         .unwrap();
-        account_account.lamports = 2;
-
         do_process_instruction(
-            set_authority(
-                &program_id,
-                &account_key,
-                Some(&owner2_key),
-                AuthorityType::CloseAccount,
-                &owner_key,
-                &[],
-            )
-            .unwrap(),
-            vec![&mut account_account, &mut meta_account, &mut owner_account],
+            mint_to(&program_id, &mint_key, &account2_key, &owner_key, &[], 70).unwrap(),
+            vec![&mut mint_account, &mut account2_account, &mut owner_account],
         )
         .unwrap();
+        let checkpoint2_key = get_balance_checkpoint_address(&account2_key, 0);
+        assert_ne!(checkpoint2_key, checkpoint_key);
+        let mut checkpoint2_account = PUTAccount::new(
+            checkpoint_balance,
+            BalanceCheckpoint::get_packed_len(),
+            &program_id,
+        );
 
-        // account owner cannot authorize close if close_authority is set
+        // the checkpoint of the first account cannot record the second one
+        let mut instruction =
+            record_balance_checkpoint(&program_id, &payer_key, &account2_key, &owner_key, &[], 0)
+                .unwrap();
+        instruction.accounts[1].pubkey = checkpoint_key;
         assert_eq!(
-            Err(TokenError::OwnerMismatch.into()),
+            Err(ProgramError::InvalidSeeds),
             do_process_instruction(
-                close_account(&program_id, &account_key, &account3_key, &owner_key, &[]).unwrap(),
+                instruction,
                 vec![
-                    &mut account_account,
-                    &mut account3_account,
+                    &mut payer_account,
+                    &mut checkpoint_account,
+                    &mut account2_account,
+                    &mut system_account,
+                    &mut rent_sysvar,
                     &mut owner_account,
                 ],
             )
         );
 
-        // close non-native account with close_authority
-        do_process_instruction(
-            close_account(&program_id, &account_key, &account3_key, &owner2_key, &[]).unwrap(),
-            vec![
-                &mut account_account,
-                &mut account3_account,
-                &mut owner2_account,
-            ],
-        )
-        .unwrap();
-        assert_eq!(account_account.lamports, 0);
-        assert_eq!(account3_account.lamports, 2 * account_minimum_balance() + 2);
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.amount, 0);
-
-        // close native account
         do_process_instruction(
-            close_account(&program_id, &account2_key, &account3_key, &owner_key, &[]).unwrap(),
+            record_balance_checkpoint(&program_id, &payer_key, &account2_key, &owner_key, &[], 0)
+                .unwrap(),
             vec![
+                &mut payer_account,
+                &mut checkpoint2_account,
                 &mut account2_account,
-                &mut account3_account,
+                &mut system_account,
+                &mut rent_sysvar,
                 &mut owner_account,
-            ],
-        )
-        .unwrap();
-        let account = Account::unpack_unchecked(&account2_account.data).unwrap();
-        assert!(account.is_native());
-        assert_eq!(account_account.lamports, 0);
-        assert_eq!(account.amount, 0);
+            ],
+        )
+        .unwrap();
         assert_eq!(
-            account3_account.lamports,
-            3 * account_minimum_balance() + 2 + 42
+            BalanceCheckpoint::unpack(&checkpoint2_account.data).unwrap(),
+            BalanceCheckpoint {
+                is_initialized: true,
+                mint: mint_key,
+                owner: owner_key,
+                account: account2_key,
+                epoch: 0,
+                slot: 0,
+                amount: 70,
+            }
+        );
+        assert_eq!(
+            BalanceCheckpoint::unpack(&checkpoint_account.data)
+                .unwrap()
+                .amount,
+            150
+        );
+
+        // lookup of the balance as of a slot
+        let checkpoints: Vec<BalanceCheckpoint> = [10, 30, 20]
+            .iter()
+            .map(|slot| BalanceCheckpoint {
+                is_initialized: true,
+                mint: mint_key,
+                owner: owner_key,
+                account: account_key,
+                epoch: slot / 10,
+                slot: *slot,
+                amount: *slot as u128,
+            })
+            .collect();
+        assert_eq!(find_balance_checkpoint(&checkpoints, 5), None);
+        assert_eq!(
+            find_balance_checkpoint(&checkpoints, 10).unwrap().amount,
+            10
+        );
+        assert_eq!(
+            find_balance_checkpoint(&checkpoints, 25).unwrap().amount,
+            20
+        );
+        assert_eq!(
+            find_balance_checkpoint(&checkpoints, 100).unwrap().amount,
+            30
         );
     }
 
     #[test]
-    fn test_native_token() {
+    fn test_transfer_with_encrypted_note() {
         let program_id = crate::id();
-        let mut mint_account =
-            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
         let account_key = Pubkey::new_unique();
         let mut account_account = PUTAccount::new(
-            account_minimum_balance() + 40,
+            account_minimum_balance(),
             Account::get_packed_len(),
             &program_id,
         );
@@ -5420,213 +11057,159 @@ mod tests {
             Account::get_packed_len(),
             &program_id,
         );
-        let account3_key = Pubkey::new_unique();
-        let mut account3_account = PUTAccount::new(account_minimum_balance(), 0, &program_id);
         let owner_key = Pubkey::new_unique();
         let mut owner_account = PUTAccount::default();
-        let owner2_key = Pubkey::new_unique();
-        let mut owner2_account = PUTAccount::default();
-        let owner3_key = Pubkey::new_unique();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
         let mut rent_sysvar = rent_sysvar();
 
-        let mut meta_account = PUTAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
-        );
-
-        // initialize native account
         do_process_instruction(
-            initialize_account(
-                &program_id,
-                &account_key,
-                &crate::native_mint::id(),
-                &owner_key,
-            )
-            .unwrap(),
-            vec![
-                &mut account_account,
-                &mut mint_account,
-                &mut owner_account,
-                &mut rent_sysvar,
-            ],
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert!(account.is_native());
-        assert_eq!(account.amount, 40);
-
-        // initialize native account
-        do_process_instruction(
-            initialize_account(
-                &program_id,
-                &account2_key,
-                &crate::native_mint::id(),
-                &owner_key,
+        for (key, account) in [
+            (&account_key, &mut account_account),
+            (&account2_key, &mut account2_account),
+        ] {
+            do_process_instruction(
+                initialize_account(&program_id, key, &mint_key, &owner_key).unwrap(),
+                vec![
+                    account,
+                    &mut mint_account,
+                    &mut owner_account,
+                    &mut rent_sysvar,
+                ],
             )
-            .unwrap(),
-            vec![
-                &mut account2_account,
-                &mut mint_account,
-                &mut owner_account,
-                &mut rent_sysvar,
-            ],
+            .unwrap();
+        }
+        do_process_instruction(
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1_000).unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
         )
         .unwrap();
-        let account = Account::unpack_unchecked(&account2_account.data).unwrap();
-        assert!(account.is_native());
-        assert_eq!(account.amount, 0);
-
-        // mint_to unsupported
-        assert_eq!(
-            Err(TokenError::NativeNotSupported.into()),
-            do_process_instruction(
-                mint_to(
-                    &program_id,
-                    &crate::native_mint::id(),
-                    &account_key,
-                    &owner_key,
-                    &[],
-                    42
-                )
-                .unwrap(),
-                vec![&mut mint_account, &mut account_account, &mut owner_account],
-            )
-        );
 
-        // burn unsupported
-        let bogus_mint_key = Pubkey::new_unique();
-        let mut bogus_mint_account =
-            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-        do_process_instruction(
-            initialize_mint(&program_id, &bogus_mint_key, &owner_key, None, 2).unwrap(),
-            vec![&mut bogus_mint_account, &mut rent_sysvar],
+        let sender_secret_key = [1; 32];
+        let recipient_secret_key = [2; 32];
+        let contents = DecryptedNote {
+            amount: 42,
+            reference: b"invoice 42".to_vec(),
+        };
+        let note = encrypt_note(
+            &sender_secret_key,
+            &note_public_key(&recipient_secret_key),
+            &contents,
+            &mut rand::thread_rng(),
         )
         .unwrap();
 
+        // malformed note
+        let mut bad_note = note.clone();
+        bad_note[0] += 1;
         assert_eq!(
-            Err(TokenError::NativeNotSupported.into()),
+            Err(TokenError::InvalidEncryptedNote.into()),
             do_process_instruction(
-                burn(
+                transfer_with_encrypted_note(
                     &program_id,
                     &account_key,
-                    &bogus_mint_key,
+                    &mint_key,
+                    &account2_key,
                     &owner_key,
                     &[],
-                    42
+                    42,
+                    2,
+                    bad_note,
                 )
                 .unwrap(),
                 vec![
                     &mut account_account,
-                    &mut bogus_mint_account,
-                    &mut owner_account
+                    &mut mint_account,
+                    &mut account2_account,
+                    &mut owner_account,
                 ],
             )
         );
+        assert_eq!(
+            Err(TokenError::InvalidEncryptedNote.into()),
+            transfer_with_encrypted_note(
+                &program_id,
+                &account_key,
+                &mint_key,
+                &account2_key,
+                &owner_key,
+                &[],
+                42,
+                2,
+                vec![0; MAX_NOTE_LEN + 1],
+            )
+        );
 
-        // ensure can't transfer below rent-exempt reserve
+        // checked like a `TransferChecked`
         assert_eq!(
-            Err(TokenError::InsufficientFunds.into()),
+            Err(TokenError::MintDecimalsMismatch.into()),
             do_process_instruction(
-                transfer(
+                transfer_with_encrypted_note(
                     &program_id,
                     &account_key,
+                    &mint_key,
                     &account2_key,
                     &owner_key,
                     &[],
-                    50,
+                    42,
+                    3,
+                    note.clone(),
                 )
                 .unwrap(),
                 vec![
                     &mut account_account,
+                    &mut mint_account,
                     &mut account2_account,
                     &mut owner_account,
                 ],
             )
         );
 
-        // transfer between native accounts
+        // success
+        let instruction = transfer_with_encrypted_note(
+            &program_id,
+            &account_key,
+            &mint_key,
+            &account2_key,
+            &owner_key,
+            &[],
+            42,
+            2,
+            note,
+        )
+        .unwrap();
         do_process_instruction(
-            transfer(
-                &program_id,
-                &account_key,
-                &account2_key,
-                &owner_key,
-                &[],
-                40,
-            )
-            .unwrap(),
+            instruction.clone(),
             vec![
                 &mut account_account,
+                &mut mint_account,
                 &mut account2_account,
                 &mut owner_account,
             ],
         )
         .unwrap();
-        assert_eq!(account_account.lamports, account_minimum_balance());
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert!(account.is_native());
-        assert_eq!(account.amount, 0);
-        assert_eq!(account2_account.lamports, account_minimum_balance() + 40);
-        let account = Account::unpack_unchecked(&account2_account.data).unwrap();
-        assert!(account.is_native());
-        assert_eq!(account.amount, 40);
-
-        // set close authority
-        do_process_instruction(
-            set_authority(
-                &program_id,
-                &account_key,
-                Some(&owner3_key),
-                AuthorityType::CloseAccount,
-                &owner_key,
-                &[],
-            )
-            .unwrap(),
-            vec![&mut account_account, &mut meta_account, &mut owner_account],
-        )
-        .unwrap();
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.close_authority, COption::Some(owner3_key));
-
-        // set new account owner
-        do_process_instruction(
-            set_authority(
-                &program_id,
-                &account_key,
-                Some(&owner2_key),
-                AuthorityType::AccountOwner,
-                &owner_key,
-                &[],
-            )
-            .unwrap(),
-            vec![&mut account_account, &mut meta_account, &mut owner_account],
-        )
-        .unwrap();
+        assert_eq!(Account::unpack(&account_account.data).unwrap().amount, 958);
+        assert_eq!(Account::unpack(&account2_account.data).unwrap().amount, 42);
 
-        // close authority cleared
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.close_authority, COption::None);
-
-        // close native account
-        do_process_instruction(
-            close_account(&program_id, &account_key, &account3_key, &owner2_key, &[]).unwrap(),
-            vec![
-                &mut account_account,
-                &mut account3_account,
-                &mut owner2_account,
-            ],
-        )
-        .unwrap();
-        assert_eq!(account_account.lamports, 0);
-        assert_eq!(account3_account.lamports, 2 * account_minimum_balance());
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert!(account.is_native());
-        assert_eq!(account.amount, 0);
+        // the note can be read back from the instruction by both parties
+        let note = match TokenInstruction::unpack(&instruction.data).unwrap() {
+            TokenInstruction::TransferWithEncryptedNote { note, .. } => note,
+            _ => panic!("not a TransferWithEncryptedNote"),
+        };
+        assert_eq!(decrypt_note(&note, &sender_secret_key).unwrap(), contents);
+        assert_eq!(
+            decrypt_note(&note, &recipient_secret_key).unwrap(),
+            contents
+        );
     }
 
     #[test]
-    fn test_overflow() {
+    fn test_reallocate() {
         let program_id = crate::id();
         let account_key = Pubkey::new_unique();
         let mut account_account = PUTAccount::new(
@@ -5634,31 +11217,30 @@ mod tests {
             Account::get_packed_len(),
             &program_id,
         );
+        let account_len = ExtensionType::get_account_len::<Account>(&[ExtensionType::MemoTransfer]);
         let account2_key = Pubkey::new_unique();
         let mut account2_account = PUTAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
+            Rent::default().minimum_balance(account_len),
+            account_len,
             &program_id,
         );
         let owner_key = Pubkey::new_unique();
         let mut owner_account = PUTAccount::default();
         let owner2_key = Pubkey::new_unique();
         let mut owner2_account = PUTAccount::default();
-        let mint_owner_key = Pubkey::new_unique();
-        let mut mint_owner_account = PUTAccount::default();
+        let payer_key = Pubkey::new_unique();
+        let mut payer_account = PUTAccount::default();
+        let mut system_program_account = PUTAccount::default();
         let mint_key = Pubkey::new_unique();
         let mut mint_account =
             PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
         let mut rent_sysvar = rent_sysvar();
 
-        // create new mint with owner
         do_process_instruction(
-            initialize_mint(&program_id, &mint_key, &mint_owner_key, None, 2).unwrap(),
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
             vec![&mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
-
-        // create an account
         do_process_instruction(
             initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
             vec![
@@ -5669,141 +11251,155 @@ mod tests {
             ],
         )
         .unwrap();
-
-        // create another account
-        do_process_instruction(
-            initialize_account(&program_id, &account2_key, &mint_key, &owner2_key).unwrap(),
-            vec![
-                &mut account2_account,
-                &mut mint_account,
-                &mut owner2_account,
-                &mut rent_sysvar,
-            ],
-        )
-        .unwrap();
-
-        // mint the max to an account
         do_process_instruction(
-            mint_to(
-                &program_id,
-                &mint_key,
-                &account_key,
-                &mint_owner_key,
-                &[],
-                u128::MAX,
-            )
-            .unwrap(),
+            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
             vec![
+                &mut account2_account,
                 &mut mint_account,
-                &mut account_account,
-                &mut mint_owner_account,
+                &mut owner_account,
+                &mut rent_sysvar,
             ],
         )
         .unwrap();
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.amount, u128::MAX);
 
-        // attempt to mint one more to account
+        // only token accounts of the program can be reallocated
+        account_account.owner = Pubkey::new_unique();
         assert_eq!(
-            Err(TokenError::Overflow.into()),
+            Err(ProgramError::IncorrectProgramId),
             do_process_instruction(
-                mint_to(
+                reallocate(
                     &program_id,
-                    &mint_key,
                     &account_key,
-                    &mint_owner_key,
+                    &payer_key,
+                    &owner_key,
                     &[],
-                    1,
+                    &[ExtensionType::MemoTransfer],
                 )
                 .unwrap(),
                 vec![
-                    &mut mint_account,
                     &mut account_account,
-                    &mut mint_owner_account,
+                    &mut payer_account,
+                    &mut system_program_account,
+                    &mut rent_sysvar,
+                    &mut owner_account,
                 ],
             )
         );
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.amount, u128::MAX);
+        account_account.owner = program_id;
 
-        // atttempt to mint one more to the other account
+        // only account extensions may be requested
         assert_eq!(
-            Err(TokenError::Overflow.into()),
+            Err(ProgramError::InvalidArgument),
             do_process_instruction(
-                mint_to(
+                reallocate(
                     &program_id,
-                    &mint_key,
-                    &account2_key,
-                    &mint_owner_key,
+                    &account_key,
+                    &payer_key,
+                    &owner_key,
                     &[],
-                    1,
+                    &[ExtensionType::MintCloseAuthority],
                 )
                 .unwrap(),
                 vec![
-                    &mut mint_account,
-                    &mut account2_account,
-                    &mut mint_owner_account,
+                    &mut account_account,
+                    &mut payer_account,
+                    &mut system_program_account,
+                    &mut rent_sysvar,
+                    &mut owner_account,
                 ],
             )
         );
 
-        // burn some of the supply
-        do_process_instruction(
-            burn(&program_id, &account_key, &mint_key, &owner_key, &[], 100).unwrap(),
-            vec![&mut account_account, &mut mint_account, &mut owner_account],
+        // only the owner may reallocate
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                reallocate(
+                    &program_id,
+                    &account_key,
+                    &payer_key,
+                    &owner2_key,
+                    &[],
+                    &[ExtensionType::MemoTransfer],
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut payer_account,
+                    &mut system_program_account,
+                    &mut rent_sysvar,
+                    &mut owner2_account,
+                ],
+            )
+        );
+
+        // the owner must sign
+        let mut instruction = reallocate(
+            &program_id,
+            &account_key,
+            &payer_key,
+            &owner_key,
+            &[],
+            &[ExtensionType::MemoTransfer],
         )
         .unwrap();
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.amount, u128::MAX - 100);
+        instruction.accounts[4].is_signer = false;
+        assert_eq!(
+            Err(ProgramError::MissingRequiredSignature),
+            do_process_instruction(
+                instruction,
+                vec![
+                    &mut account_account,
+                    &mut payer_account,
+                    &mut system_program_account,
+                    &mut rent_sysvar,
+                    &mut owner_account,
+                ],
+            )
+        );
 
+        // an account with room for the extensions is left untouched
+        let data = account2_account.data.clone();
+        let lamports = account2_account.lamports;
         do_process_instruction(
-            mint_to(
+            reallocate(
                 &program_id,
-                &mint_key,
-                &account_key,
-                &mint_owner_key,
+                &account2_key,
+                &payer_key,
+                &owner_key,
                 &[],
-                100,
+                &[ExtensionType::MemoTransfer, ExtensionType::MemoTransfer],
             )
             .unwrap(),
             vec![
-                &mut mint_account,
-                &mut account_account,
-                &mut mint_owner_account,
+                &mut account2_account,
+                &mut payer_account,
+                &mut system_program_account,
+                &mut rent_sysvar,
+                &mut owner_account,
             ],
         )
         .unwrap();
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.amount, u128::MAX);
-
-        // manipulate account balance to attempt overflow transfer
-        let mut account = Account::unpack_unchecked(&account2_account.data).unwrap();
-        account.amount = 1;
-        Account::pack(account, &mut account2_account.data).unwrap();
+        assert_eq!(account2_account.data, data);
+        assert_eq!(account2_account.lamports, lamports);
 
+        // and the extension can then be enabled in that room
+        do_process_instruction(
+            enable_required_transfer_memos(&program_id, &account2_key, &owner_key, &[]).unwrap(),
+            vec![&mut account2_account, &mut owner_account],
+        )
+        .unwrap();
         assert_eq!(
-            Err(TokenError::Overflow.into()),
-            do_process_instruction(
-                transfer(
-                    &program_id,
-                    &account2_key,
-                    &account_key,
-                    &owner2_key,
-                    &[],
-                    1,
-                )
-                .unwrap(),
-                vec![
-                    &mut account2_account,
-                    &mut account_account,
-                    &mut owner2_account,
-                ],
-            )
+            get_extension::<MemoTransfer>(&account2_account.data).unwrap(),
+            Some(MemoTransfer {
+                require_incoming_transfer_memos: true
+            })
         );
     }
 
     #[test]
-    fn test_frozen() {
+    fn test_operator_approval() {
+        set_syscall_stubs();
         let program_id = crate::id();
         let account_key = Pubkey::new_unique();
         let mut account_account = PUTAccount::new(
@@ -5819,19 +11415,23 @@ mod tests {
         );
         let owner_key = Pubkey::new_unique();
         let mut owner_account = PUTAccount::default();
+        let owner2_key = Pubkey::new_unique();
+        let mut owner2_account = PUTAccount::default();
+        let operator_key = Pubkey::new_unique();
+        let mut operator_account = PUTAccount::default();
+        let payer_key = Pubkey::new_unique();
+        let mut payer_account = PUTAccount::default();
+        let mut system_account = PUTAccount::default();
         let mint_key = Pubkey::new_unique();
         let mut mint_account =
             PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
         let mut rent_sysvar = rent_sysvar();
 
-        // create new mint and fund first account
         do_process_instruction(
             initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
             vec![&mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
-
-        // create account
         do_process_instruction(
             initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
             vec![
@@ -5842,415 +11442,728 @@ mod tests {
             ],
         )
         .unwrap();
-
-        // create another account
         do_process_instruction(
-            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
+            initialize_account(&program_id, &account2_key, &mint_key, &owner2_key).unwrap(),
             vec![
                 &mut account2_account,
                 &mut mint_account,
-                &mut owner_account,
+                &mut owner2_account,
                 &mut rent_sysvar,
             ],
         )
         .unwrap();
-
-        // fund first account
         do_process_instruction(
-            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1000).unwrap(),
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1_000).unwrap(),
             vec![&mut mint_account, &mut account_account, &mut owner_account],
         )
         .unwrap();
 
-        // no transfer if either account is frozen
-        let mut account = Account::unpack_unchecked(&account2_account.data).unwrap();
-        account.state = AccountState::Frozen;
-        Account::pack(account, &mut account2_account.data).unwrap();
+        let operator_approval_key =
+            get_operator_approval_address(&mint_key, &owner_key, &operator_key);
+        let operator_approval_balance =
+            Rent::default().minimum_balance(OperatorApproval::get_packed_len());
+        let mut operator_approval_account = PUTAccount::new(
+            operator_approval_balance,
+            OperatorApproval::get_packed_len(),
+            &program_id,
+        );
+
+        // approval account of another derivation
+        let mut instruction = approve_operator(
+            &program_id,
+            &payer_key,
+            &mint_key,
+            &operator_key,
+            &owner_key,
+            &[],
+            Some(100),
+            0,
+        )
+        .unwrap();
+        instruction.accounts[1].pubkey = Pubkey::new_unique();
+        let mut instructions_sysvar = instructions_sysvar_account(&[&instruction], 0);
         assert_eq!(
-            Err(TokenError::AccountFrozen.into()),
+            Err(ProgramError::InvalidSeeds),
             do_process_instruction(
-                transfer(
-                    &program_id,
-                    &account_key,
-                    &account2_key,
-                    &owner_key,
-                    &[],
-                    500,
-                )
-                .unwrap(),
+                instruction,
                 vec![
-                    &mut account_account,
-                    &mut account2_account,
+                    &mut payer_account,
+                    &mut operator_approval_account,
+                    &mut mint_account,
+                    &mut operator_account,
+                    &mut system_account,
+                    &mut rent_sysvar,
                     &mut owner_account,
+                    &mut instructions_sysvar,
                 ],
             )
         );
 
-        let mut account = Account::unpack_unchecked(&account_account.data).unwrap();
-        account.state = AccountState::Initialized;
-        Account::pack(account, &mut account_account.data).unwrap();
-        let mut account = Account::unpack_unchecked(&account2_account.data).unwrap();
-        account.state = AccountState::Frozen;
-        Account::pack(account, &mut account2_account.data).unwrap();
+        // the owner must sign
+        let mut instruction = approve_operator(
+            &program_id,
+            &payer_key,
+            &mint_key,
+            &operator_key,
+            &owner_key,
+            &[],
+            Some(100),
+            0,
+        )
+        .unwrap();
+        instruction.accounts[6].is_signer = false;
+        let mut instructions_sysvar = instructions_sysvar_account(&[&instruction], 0);
         assert_eq!(
-            Err(TokenError::AccountFrozen.into()),
+            Err(ProgramError::MissingRequiredSignature),
+            do_process_instruction(
+                instruction,
+                vec![
+                    &mut payer_account,
+                    &mut operator_approval_account,
+                    &mut mint_account,
+                    &mut operator_account,
+                    &mut system_account,
+                    &mut rent_sysvar,
+                    &mut owner_account,
+                    &mut instructions_sysvar,
+                ],
+            )
+        );
+
+        // approvals must be granted at the top level
+        let mut instruction = approve_operator(
+            &program_id,
+            &payer_key,
+            &mint_key,
+            &operator_key,
+            &owner_key,
+            &[],
+            Some(100),
+            0,
+        )
+        .unwrap();
+        // stands in for the top-level instruction of a program invoking this one
+        let caller_instruction = ppl_memo::build_memo(b"caller", &[]);
+        let mut instructions_sysvar = instructions_sysvar_account(&[&caller_instruction], 0);
+        assert_eq!(
+            Err(TokenError::CpiGuardBlocked.into()),
+            do_process_instruction(
+                instruction.clone(),
+                vec![
+                    &mut payer_account,
+                    &mut operator_approval_account,
+                    &mut mint_account,
+                    &mut operator_account,
+                    &mut system_account,
+                    &mut rent_sysvar,
+                    &mut owner_account,
+                    &mut instructions_sysvar,
+                ],
+            )
+        );
+        instruction.accounts.pop();
+        assert_eq!(
+            Err(TokenError::CpiGuardBlocked.into()),
+            do_process_instruction(
+                instruction,
+                vec![
+                    &mut payer_account,
+                    &mut operator_approval_account,
+                    &mut mint_account,
+                    &mut operator_account,
+                    &mut system_account,
+                    &mut rent_sysvar,
+                    &mut owner_account,
+                ],
+            )
+        );
+
+        // success
+        let instruction = approve_operator(
+            &program_id,
+            &payer_key,
+            &mint_key,
+            &operator_key,
+            &owner_key,
+            &[],
+            Some(100),
+            0,
+        )
+        .unwrap();
+        let mut instructions_sysvar = instructions_sysvar_account(&[&instruction], 0);
+        do_process_instruction(
+            instruction,
+            vec![
+                &mut payer_account,
+                &mut operator_approval_account,
+                &mut mint_account,
+                &mut operator_account,
+                &mut system_account,
+                &mut rent_sysvar,
+                &mut owner_account,
+                &mut instructions_sysvar,
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            OperatorApproval::unpack(&operator_approval_account.data).unwrap(),
+            OperatorApproval {
+                is_initialized: true,
+                mint: mint_key,
+                owner: owner_key,
+                operator: operator_key,
+                limit: COption::Some(100),
+                period: 0,
+                period_start: 0,
+                spent: 0,
+            }
+        );
+
+        // the operator needs its approval account
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
             do_process_instruction(
                 transfer(
                     &program_id,
                     &account_key,
                     &account2_key,
-                    &owner_key,
+                    &operator_key,
                     &[],
-                    500,
+                    60,
                 )
                 .unwrap(),
                 vec![
                     &mut account_account,
                     &mut account2_account,
-                    &mut owner_account,
+                    &mut operator_account,
                 ],
             )
         );
 
-        // no approve if account is frozen
-        let mut account = Account::unpack_unchecked(&account_account.data).unwrap();
-        account.state = AccountState::Frozen;
-        Account::pack(account, &mut account_account.data).unwrap();
-        let delegate_key = Pubkey::new_unique();
-        let mut delegate_account = PUTAccount::default();
-        assert_eq!(
-            Err(TokenError::AccountFrozen.into()),
-            do_process_instruction(
-                approve(
-                    &program_id,
-                    &account_key,
-                    &delegate_key,
-                    &owner_key,
-                    &[],
-                    100
-                )
-                .unwrap(),
+        // the operator must sign
+        let mut instruction = transfer(
+            &program_id,
+            &account_key,
+            &account2_key,
+            &operator_key,
+            &[],
+            60,
+        )
+        .unwrap();
+        add_operator_approval(&mut instruction, &mint_key, &owner_key, &operator_key);
+        instruction.accounts[2].is_signer = false;
+        assert_eq!(
+            Err(ProgramError::MissingRequiredSignature),
+            do_process_instruction(
+                instruction,
                 vec![
                     &mut account_account,
-                    &mut delegate_account,
-                    &mut owner_account,
+                    &mut account2_account,
+                    &mut operator_account,
+                    &mut operator_approval_account,
                 ],
             )
         );
 
-        // no revoke if account is frozen
-        let mut account = Account::unpack_unchecked(&account_account.data).unwrap();
-        account.delegate = COption::Some(delegate_key);
-        account.delegated_amount = 100;
-        Account::pack(account, &mut account_account.data).unwrap();
+        // operator transfer
+        let mut instruction = transfer(
+            &program_id,
+            &account_key,
+            &account2_key,
+            &operator_key,
+            &[],
+            60,
+        )
+        .unwrap();
+        add_operator_approval(&mut instruction, &mint_key, &owner_key, &operator_key);
+        do_process_instruction(
+            instruction,
+            vec![
+                &mut account_account,
+                &mut account2_account,
+                &mut operator_account,
+                &mut operator_approval_account,
+            ],
+        )
+        .unwrap();
+        assert_eq!(Account::unpack(&account_account.data).unwrap().amount, 940);
+        assert_eq!(Account::unpack(&account2_account.data).unwrap().amount, 60);
         assert_eq!(
-            Err(TokenError::AccountFrozen.into()),
-            do_process_instruction(
-                revoke(&program_id, &account_key, &owner_key, &[]).unwrap(),
-                vec![&mut account_account, &mut owner_account],
-            )
+            OperatorApproval::unpack(&operator_approval_account.data)
+                .unwrap()
+                .spent,
+            60
         );
 
-        let mut meta_account = PUTAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
+        // the limit covers transfers and burns together
+        let mut instruction = transfer(
             &program_id,
-        );
-
-        // no set authority if account is frozen
-        let new_owner_key = Pubkey::new_unique();
+            &account_key,
+            &account2_key,
+            &operator_key,
+            &[],
+            50,
+        )
+        .unwrap();
+        add_operator_approval(&mut instruction, &mint_key, &owner_key, &operator_key);
         assert_eq!(
-            Err(TokenError::AccountFrozen.into()),
+            Err(TokenError::OperatorLimitExceeded.into()),
             do_process_instruction(
-                set_authority(
-                    &program_id,
-                    &account_key,
-                    Some(&new_owner_key),
-                    AuthorityType::AccountOwner,
-                    &owner_key,
-                    &[]
-                )
-                .unwrap(),
-                vec![&mut account_account, &mut meta_account, &mut owner_account,],
+                instruction,
+                vec![
+                    &mut account_account,
+                    &mut account2_account,
+                    &mut operator_account,
+                    &mut operator_approval_account,
+                ],
             )
         );
-
-        // no mint_to if destination account is frozen
+        let mut instruction =
+            burn(&program_id, &account_key, &mint_key, &operator_key, &[], 40).unwrap();
+        add_operator_approval(&mut instruction, &mint_key, &owner_key, &operator_key);
+        do_process_instruction(
+            instruction,
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut operator_account,
+                &mut operator_approval_account,
+            ],
+        )
+        .unwrap();
+        assert_eq!(Account::unpack(&account_account.data).unwrap().amount, 900);
+        assert_eq!(Mint::unpack(&mint_account.data).unwrap().supply, 960);
+        let mut instruction =
+            burn(&program_id, &account_key, &mint_key, &operator_key, &[], 1).unwrap();
+        add_operator_approval(&mut instruction, &mint_key, &owner_key, &operator_key);
         assert_eq!(
-            Err(TokenError::AccountFrozen.into()),
+            Err(TokenError::OperatorLimitExceeded.into()),
             do_process_instruction(
-                mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 100).unwrap(),
-                vec![&mut mint_account, &mut account_account, &mut owner_account,],
+                instruction,
+                vec![
+                    &mut account_account,
+                    &mut mint_account,
+                    &mut operator_account,
+                    &mut operator_approval_account,
+                ],
             )
         );
 
-        // no burn if account is frozen
+        // the approval does not extend to accounts of other owners
+        let mut instruction = transfer(
+            &program_id,
+            &account2_key,
+            &account_key,
+            &operator_key,
+            &[],
+            1,
+        )
+        .unwrap();
+        add_operator_approval(&mut instruction, &mint_key, &owner_key, &operator_key);
         assert_eq!(
-            Err(TokenError::AccountFrozen.into()),
+            Err(TokenError::OwnerMismatch.into()),
             do_process_instruction(
-                burn(&program_id, &account_key, &mint_key, &owner_key, &[], 100).unwrap(),
-                vec![&mut account_account, &mut mint_account, &mut owner_account],
+                instruction,
+                vec![
+                    &mut account2_account,
+                    &mut account_account,
+                    &mut operator_account,
+                    &mut operator_approval_account,
+                ],
             )
         );
-    }
 
-    #[test]
-    fn test_freeze_thaw_dups() {
-        let program_id = crate::id();
-        let account1_key = Pubkey::new_unique();
-        let mut account1_account = PUTAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
+        // approving again lifts the limit and resets the spent amount
+        let instruction = approve_operator(
             &program_id,
-        );
-        let account1_info: AccountInfo = (&account1_key, true, &mut account1_account).into();
-        let owner_key = Pubkey::new_unique();
-        let mint_key = Pubkey::new_unique();
-        let mut mint_account =
-            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-        let mint_info: AccountInfo = (&mint_key, true, &mut mint_account).into();
-        let rent_key = rent::id();
-        let mut rent_sysvar = rent_sysvar();
-        let rent_info: AccountInfo = (&rent_key, false, &mut rent_sysvar).into();
-
-        // create mint
-        do_process_instruction_dups(
-            initialize_mint(&program_id, &mint_key, &owner_key, Some(&account1_key), 2).unwrap(),
-            vec![mint_info.clone(), rent_info.clone()],
+            &payer_key,
+            &mint_key,
+            &operator_key,
+            &owner_key,
+            &[],
+            None,
+            0,
         )
         .unwrap();
-
-        // create account
-        do_process_instruction_dups(
-            initialize_account(&program_id, &account1_key, &mint_key, &account1_key).unwrap(),
+        let mut instructions_sysvar = instructions_sysvar_account(&[&instruction], 0);
+        do_process_instruction(
+            instruction,
             vec![
-                account1_info.clone(),
-                mint_info.clone(),
-                account1_info.clone(),
-                rent_info.clone(),
+                &mut payer_account,
+                &mut operator_approval_account,
+                &mut mint_account,
+                &mut operator_account,
+                &mut system_account,
+                &mut rent_sysvar,
+                &mut owner_account,
+                &mut instructions_sysvar,
             ],
         )
         .unwrap();
-
-        // freeze where mint freeze_authority is account
-        do_process_instruction_dups(
-            freeze_account(&program_id, &account1_key, &mint_key, &account1_key, &[]).unwrap(),
+        let mut instruction = transfer(
+            &program_id,
+            &account_key,
+            &account2_key,
+            &operator_key,
+            &[],
+            500,
+        )
+        .unwrap();
+        add_operator_approval(&mut instruction, &mint_key, &owner_key, &operator_key);
+        do_process_instruction(
+            instruction,
             vec![
-                account1_info.clone(),
-                mint_info.clone(),
-                account1_info.clone(),
+                &mut account_account,
+                &mut account2_account,
+                &mut operator_account,
+                &mut operator_approval_account,
             ],
         )
         .unwrap();
+        assert_eq!(Account::unpack(&account_account.data).unwrap().amount, 400);
 
-        // thaw where mint freeze_authority is account
-        let mut account = Account::unpack_unchecked(&account1_info.data.borrow()).unwrap();
-        account.state = AccountState::Frozen;
-        Account::pack(account, &mut account1_info.data.borrow_mut()).unwrap();
-        do_process_instruction_dups(
-            thaw_account(&program_id, &account1_key, &mint_key, &account1_key, &[]).unwrap(),
+        // only the owner may revoke
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                revoke_operator(
+                    &program_id,
+                    &mint_key,
+                    &operator_key,
+                    &payer_key,
+                    &owner2_key,
+                    &[],
+                )
+                .unwrap(),
+                vec![
+                    &mut operator_approval_account,
+                    &mut payer_account,
+                    &mut owner2_account,
+                ],
+            )
+        );
+        let instruction = revoke_operator(
+            &program_id,
+            &mint_key,
+            &operator_key,
+            &payer_key,
+            &owner_key,
+            &[],
+        )
+        .unwrap();
+        assert_eq!(instruction.accounts[0].pubkey, operator_approval_key);
+        do_process_instruction(
+            instruction,
             vec![
-                account1_info.clone(),
-                mint_info.clone(),
-                account1_info.clone(),
+                &mut operator_approval_account,
+                &mut payer_account,
+                &mut owner_account,
             ],
         )
         .unwrap();
+        assert_eq!(operator_approval_account.lamports, 0);
+        assert_eq!(payer_account.lamports, operator_approval_balance);
+        assert!(operator_approval_account.data.iter().all(|byte| *byte == 0));
+
+        // limits per period
+        let mut operator_approval = OperatorApproval {
+            is_initialized: true,
+            mint: mint_key,
+            owner: owner_key,
+            operator: operator_key,
+            limit: COption::Some(100),
+            period: 60,
+            period_start: 1_000,
+            spent: 0,
+        };
+        operator_approval.spend(100, 1_059).unwrap();
+        assert_eq!(
+            operator_approval.spend(1, 1_059),
+            Err(TokenError::OperatorLimitExceeded.into())
+        );
+        operator_approval.spend(30, 1_060).unwrap();
+        assert_eq!(operator_approval.period_start, 1_060);
+        assert_eq!(operator_approval.spent, 30);
     }
 
     #[test]
-    fn test_freeze_account() {
+    fn test_recurring_delegation() {
+        set_syscall_stubs();
         let program_id = crate::id();
+        let account_len =
+            ExtensionType::get_account_len::<Account>(&[ExtensionType::RecurringDelegation]);
         let account_key = Pubkey::new_unique();
         let mut account_account = PUTAccount::new(
+            Rent::default().minimum_balance(account_len),
+            account_len,
+            &program_id,
+        );
+        let account2_key = Pubkey::new_unique();
+        let mut account2_account = PUTAccount::new(
             account_minimum_balance(),
             Account::get_packed_len(),
             &program_id,
         );
-        let account_owner_key = Pubkey::new_unique();
-        let mut account_owner_account = PUTAccount::default();
         let owner_key = Pubkey::new_unique();
         let mut owner_account = PUTAccount::default();
-        let owner2_key = Pubkey::new_unique();
-        let mut owner2_account = PUTAccount::default();
+        let delegate_key = Pubkey::new_unique();
+        let mut delegate_account = PUTAccount::default();
         let mint_key = Pubkey::new_unique();
         let mut mint_account =
             PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
         let mut rent_sysvar = rent_sysvar();
 
-        // create new mint with owner different from account owner
         do_process_instruction(
             initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
             vec![&mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
-
-        // create account
         do_process_instruction(
-            initialize_account(&program_id, &account_key, &mint_key, &account_owner_key).unwrap(),
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
             vec![
                 &mut account_account,
                 &mut mint_account,
-                &mut account_owner_account,
+                &mut owner_account,
                 &mut rent_sysvar,
             ],
         )
         .unwrap();
-
-        // mint to account
         do_process_instruction(
-            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1000).unwrap(),
+            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account2_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+        do_process_instruction(
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1_000).unwrap(),
             vec![&mut mint_account, &mut account_account, &mut owner_account],
         )
         .unwrap();
 
-        // mint cannot freeze
-        assert_eq!(
-            Err(TokenError::MintCannotFreeze.into()),
-            do_process_instruction(
-                freeze_account(&program_id, &account_key, &mint_key, &owner_key, &[]).unwrap(),
-                vec![&mut account_account, &mut mint_account, &mut owner_account],
-            )
-        );
-
-        // missing freeze_authority
-        let mut mint = Mint::unpack_unchecked(&mint_account.data).unwrap();
-        mint.freeze_authority = COption::Some(owner_key);
-        Mint::pack(mint, &mut mint_account.data).unwrap();
-        assert_eq!(
-            Err(TokenError::OwnerMismatch.into()),
-            do_process_instruction(
-                freeze_account(&program_id, &account_key, &mint_key, &owner2_key, &[]).unwrap(),
-                vec![&mut account_account, &mut mint_account, &mut owner2_account],
-            )
-        );
-
-        // check explicit thaw
+        // accounts need room for the delegation period
         assert_eq!(
-            Err(TokenError::InvalidState.into()),
+            Err(ProgramError::InvalidAccountData),
             do_process_instruction(
-                thaw_account(&program_id, &account_key, &mint_key, &owner2_key, &[]).unwrap(),
-                vec![&mut account_account, &mut mint_account, &mut owner2_account],
+                approve_recurring(
+                    &program_id,
+                    &account2_key,
+                    &mint_key,
+                    &delegate_key,
+                    &owner_key,
+                    &[],
+                    100,
+                    2,
+                    60,
+                )
+                .unwrap(),
+                vec![
+                    &mut account2_account,
+                    &mut mint_account,
+                    &mut delegate_account,
+                    &mut owner_account,
+                ],
             )
         );
 
-        // freeze
+        // success
         do_process_instruction(
-            freeze_account(&program_id, &account_key, &mint_key, &owner_key, &[]).unwrap(),
-            vec![&mut account_account, &mut mint_account, &mut owner_account],
+            approve_recurring(
+                &program_id,
+                &account_key,
+                &mint_key,
+                &delegate_key,
+                &owner_key,
+                &[],
+                100,
+                2,
+                60,
+            )
+            .unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut delegate_account,
+                &mut owner_account,
+            ],
         )
         .unwrap();
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.state, AccountState::Frozen);
-
-        // check explicit freeze
+        let account = Account::unpack_base(&account_account.data).unwrap();
+        assert_eq!(account.delegate, COption::Some(delegate_key));
+        assert_eq!(account.delegated_amount, 100);
         assert_eq!(
-            Err(TokenError::InvalidState.into()),
-            do_process_instruction(
-                freeze_account(&program_id, &account_key, &mint_key, &owner_key, &[]).unwrap(),
-                vec![&mut account_account, &mut mint_account, &mut owner_account],
-            )
+            get_extension::<RecurringDelegation>(&account_account.data).unwrap(),
+            Some(RecurringDelegation {
+                period: 60,
+                period_start: 0,
+                allowance: 100,
+            })
         );
 
-        // check thaw authority
+        // the delegate keeps its approval once the allowance of the period is spent
+        do_process_instruction(
+            transfer(
+                &program_id,
+                &account_key,
+                &account2_key,
+                &delegate_key,
+                &[],
+                100,
+            )
+            .unwrap(),
+            vec![
+                &mut account_account,
+                &mut account2_account,
+                &mut delegate_account,
+            ],
+        )
+        .unwrap();
+        let account = Account::unpack_base(&account_account.data).unwrap();
+        assert_eq!(account.amount, 900);
+        assert_eq!(account.delegate, COption::Some(delegate_key));
+        assert_eq!(account.delegated_amount, 0);
         assert_eq!(
-            Err(TokenError::OwnerMismatch.into()),
+            Err(TokenError::InsufficientFunds.into()),
             do_process_instruction(
-                thaw_account(&program_id, &account_key, &mint_key, &owner2_key, &[]).unwrap(),
-                vec![&mut account_account, &mut mint_account, &mut owner2_account],
+                transfer(
+                    &program_id,
+                    &account_key,
+                    &account2_key,
+                    &delegate_key,
+                    &[],
+                    1,
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut account2_account,
+                    &mut delegate_account,
+                ],
             )
         );
 
-        // thaw
-        do_process_instruction(
-            thaw_account(&program_id, &account_key, &mint_key, &owner_key, &[]).unwrap(),
-            vec![&mut account_account, &mut mint_account, &mut owner_account],
+        // the allowance is restored once the period is over
+        set_extension(
+            &mut account_account.data,
+            RecurringDelegation {
+                period: 60,
+                period_start: -130,
+                allowance: 100,
+            },
         )
         .unwrap();
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.state, AccountState::Initialized);
-    }
-
-    #[test]
-    fn test_initialize_account2() {
-        let program_id = crate::id();
-        let account_key = Pubkey::new_unique();
-        let mut account_account = PUTAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
-        );
-        let mut account2_account = PUTAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
-        );
-        let owner_key = Pubkey::new_unique();
-        let mut owner_account = PUTAccount::default();
-        let mint_key = Pubkey::new_unique();
-        let mut mint_account =
-            PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-        let mut rent_sysvar = rent_sysvar();
-
-        // create mint
         do_process_instruction(
-            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
-            vec![&mut mint_account, &mut rent_sysvar],
+            burn(&program_id, &account_key, &mint_key, &delegate_key, &[], 30).unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut delegate_account,
+            ],
         )
         .unwrap();
+        let account = Account::unpack_base(&account_account.data).unwrap();
+        assert_eq!(account.amount, 870);
+        assert_eq!(account.delegated_amount, 70);
+        assert_eq!(
+            get_extension::<RecurringDelegation>(&account_account.data)
+                .unwrap()
+                .unwrap()
+                .period_start,
+            -10
+        );
 
+        // a plain approval ends the recurring delegation
         do_process_instruction(
-            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            approve(
+                &program_id,
+                &account_key,
+                &delegate_key,
+                &owner_key,
+                &[],
+                10,
+            )
+            .unwrap(),
             vec![
                 &mut account_account,
-                &mut mint_account,
+                &mut delegate_account,
                 &mut owner_account,
-                &mut rent_sysvar,
             ],
         )
         .unwrap();
-
+        assert_eq!(
+            get_extension::<RecurringDelegation>(&account_account.data).unwrap(),
+            Some(RecurringDelegation::default())
+        );
         do_process_instruction(
-            initialize_account2(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
-            vec![&mut account2_account, &mut mint_account, &mut rent_sysvar],
+            transfer(
+                &program_id,
+                &account_key,
+                &account2_key,
+                &delegate_key,
+                &[],
+                10,
+            )
+            .unwrap(),
+            vec![
+                &mut account_account,
+                &mut account2_account,
+                &mut delegate_account,
+            ],
         )
         .unwrap();
+        let account = Account::unpack_base(&account_account.data).unwrap();
+        assert_eq!(account.delegate, COption::None);
+        assert_eq!(account.delegated_amount, 0);
 
-        assert_eq!(account_account, account2_account);
+        // periods follow each other from the first one
+        let mut recurring_delegation = RecurringDelegation {
+            period: 60,
+            period_start: 0,
+            allowance: 100,
+        };
+        assert!(!recurring_delegation.roll_over(59));
+        assert!(recurring_delegation.roll_over(125));
+        assert_eq!(recurring_delegation.period_start, 120);
+        assert!(!RecurringDelegation::default().roll_over(125));
     }
 
     #[test]
-    fn test_sync_native() {
+    fn test_native_deposit_withdraw() {
         let program_id = crate::id();
         let mint_key = Pubkey::new_unique();
         let mut mint_account =
             PUTAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
         let native_account_key = Pubkey::new_unique();
-        let lamports = 40;
         let mut native_account = PUTAccount::new(
-            account_minimum_balance() + lamports,
+            account_minimum_balance() + 40,
             Account::get_packed_len(),
             &program_id,
         );
         let non_native_account_key = Pubkey::new_unique();
         let mut non_native_account = PUTAccount::new(
-            account_minimum_balance() + 50,
+            account_minimum_balance(),
             Account::get_packed_len(),
             &program_id,
         );
-
         let owner_key = Pubkey::new_unique();
         let mut owner_account = PUTAccount::default();
+        let owner2_key = Pubkey::new_unique();
+        let mut owner2_account = PUTAccount::default();
+        let dest_key = Pubkey::new_unique();
+        let mut dest_account = PUTAccount::default();
+        let funder_key = Pubkey::new_unique();
+        let mut funder_account = PUTAccount::default();
+        let mut system_account = PUTAccount::default();
         let mut rent_sysvar = rent_sysvar();
 
-        // initialize non-native mint
         do_process_instruction(
             initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
             vec![&mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
-
-        // initialize non-native account
         do_process_instruction(
             initialize_account(&program_id, &non_native_account_key, &mint_key, &owner_key)
                 .unwrap(),
@@ -6262,30 +12175,6 @@ mod tests {
             ],
         )
         .unwrap();
-
-        let account = Account::unpack_unchecked(&non_native_account.data).unwrap();
-        assert!(!account.is_native());
-        assert_eq!(account.amount, 0);
-
-        // fail sync non-native
-        assert_eq!(
-            Err(TokenError::NonNativeNotSupported.into()),
-            do_process_instruction(
-                sync_native(&program_id, &non_native_account_key,).unwrap(),
-                vec![&mut non_native_account],
-            )
-        );
-
-        // fail sync uninitialized
-        assert_eq!(
-            Err(ProgramError::UninitializedAccount),
-            do_process_instruction(
-                sync_native(&program_id, &native_account_key,).unwrap(),
-                vec![&mut native_account],
-            )
-        );
-
-        // wrap native account
         do_process_instruction(
             initialize_account(
                 &program_id,
@@ -6302,43 +12191,99 @@ mod tests {
             ],
         )
         .unwrap();
-        let account = Account::unpack_unchecked(&native_account.data).unwrap();
-        assert!(account.is_native());
-        assert_eq!(account.amount, lamports as u128);
 
-        // sync, no change
+        // only native accounts hold lamports as tokens
+        assert_eq!(
+            Err(TokenError::NonNativeNotSupported.into()),
+            do_process_instruction(
+                deposit_native(&program_id, &non_native_account_key, &funder_key, 10).unwrap(),
+                vec![
+                    &mut non_native_account,
+                    &mut funder_account,
+                    &mut system_account,
+                ],
+            )
+        );
+        assert_eq!(
+            Err(TokenError::NonNativeNotSupported.into()),
+            do_process_instruction(
+                withdraw_native(
+                    &program_id,
+                    &non_native_account_key,
+                    &dest_key,
+                    &owner_key,
+                    &[],
+                    10,
+                )
+                .unwrap(),
+                vec![
+                    &mut non_native_account,
+                    &mut dest_account,
+                    &mut owner_account,
+                ],
+            )
+        );
+
+        // only the owner may withdraw
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                withdraw_native(
+                    &program_id,
+                    &native_account_key,
+                    &dest_key,
+                    &owner2_key,
+                    &[],
+                    10,
+                )
+                .unwrap(),
+                vec![&mut native_account, &mut dest_account, &mut owner2_account,],
+            )
+        );
+
+        // the rent-exempt reserve cannot be withdrawn
+        assert_eq!(
+            Err(TokenError::InsufficientFunds.into()),
+            do_process_instruction(
+                withdraw_native(
+                    &program_id,
+                    &native_account_key,
+                    &dest_key,
+                    &owner_key,
+                    &[],
+                    41,
+                )
+                .unwrap(),
+                vec![&mut native_account, &mut dest_account, &mut owner_account,],
+            )
+        );
+
+        // withdraw part of the balance
         do_process_instruction(
-            sync_native(&program_id, &native_account_key).unwrap(),
-            vec![&mut native_account],
+            withdraw_native(
+                &program_id,
+                &native_account_key,
+                &dest_key,
+                &owner_key,
+                &[],
+                15,
+            )
+            .unwrap(),
+            vec![&mut native_account, &mut dest_account, &mut owner_account],
         )
         .unwrap();
-        let account = Account::unpack_unchecked(&native_account.data).unwrap();
-        assert_eq!(account.amount, lamports as u128);
-
-        // transfer put
-        let new_lamports = lamports + 50;
-        native_account.lamports = account_minimum_balance() + new_lamports;
+        let account = Account::unpack(&native_account.data).unwrap();
+        assert_eq!(account.amount, 25);
+        assert_eq!(account.is_native, COption::Some(account_minimum_balance()));
+        assert_eq!(native_account.lamports, account_minimum_balance() + 25);
+        assert_eq!(dest_account.lamports, 15);
 
-        // success sync
+        // the account stays in sync with its lamports
         do_process_instruction(
             sync_native(&program_id, &native_account_key).unwrap(),
             vec![&mut native_account],
         )
         .unwrap();
-        let account = Account::unpack_unchecked(&native_account.data).unwrap();
-        assert_eq!(account.amount, new_lamports as u128);
-
-        // reduce put
-        native_account.lamports -= 1;
-
-        // fail sync
-        assert_eq!(
-            Err(TokenError::InvalidState.into()),
-            do_process_instruction(
-                sync_native(&program_id, &native_account_key,).unwrap(),
-                vec![&mut native_account],
-            )
-        );
+        assert_eq!(Account::unpack(&native_account.data).unwrap().amount, 25);
     }
 }
-
@@ -0,0 +1,44 @@
+//! Serde helpers writing public keys as base58 strings, behind the `serde-traits` feature
+
+use put_program::{program_option::COption, pubkey::Pubkey};
+use serde::{Deserialize, Deserializer, Serializer};
+use std::str::FromStr;
+
+/// Serializes a `Pubkey` as its base58 string
+pub mod pubkey_fromstr {
+    use super::*;
+
+    /// Serializes a `Pubkey` as its base58 string
+    pub fn serialize<S: Serializer>(pubkey: &Pubkey, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(pubkey)
+    }
+
+    /// Deserializes a `Pubkey` from its base58 string
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Pubkey, D::Error> {
+        let s = String::deserialize(d)?;
+        Pubkey::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Serializes a `COption<Pubkey>` as its base58 string, or `null`
+pub mod coption_pubkey_fromstr {
+    use super::*;
+
+    /// Serializes a `COption<Pubkey>` as its base58 string, or `null`
+    pub fn serialize<S: Serializer>(pubkey: &COption<Pubkey>, s: S) -> Result<S::Ok, S::Error> {
+        match pubkey {
+            COption::Some(pubkey) => s.serialize_some(&pubkey.to_string()),
+            COption::None => s.serialize_none(),
+        }
+    }
+
+    /// Deserializes a `COption<Pubkey>` from its base58 string, or `null`
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<COption<Pubkey>, D::Error> {
+        match Option::<String>::deserialize(d)? {
+            Some(s) => Pubkey::from_str(&s)
+                .map(COption::Some)
+                .map_err(serde::de::Error::custom),
+            None => Ok(COption::None),
+        }
+    }
+}
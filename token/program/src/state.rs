@@ -1,15 +1,20 @@
 //! State transition types
 use crate::{
+    error::TokenError,
     instruction::MAX_SIGNERS,
 };
 use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
 use num_enum::TryFromPrimitive;
 use put_program::{
+    clock::{Epoch, Slot, UnixTimestamp},
     program_error::ProgramError,
     program_option::COption,
     program_pack::{IsInitialized, Pack, Sealed},
     pubkey::Pubkey,
 };
+#[cfg(feature = "serde-traits")]
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
 
 
 
@@ -84,23 +89,92 @@ impl Pack for Mint {
     }
 }
 
+/// Maximum length in bytes of the symbol set by an instruction
+pub const MAX_SYMBOL_LEN: usize = 8;
+/// Maximum length in bytes of the name set by an instruction
+pub const MAX_NAME_LEN: usize = 32;
+/// Maximum length in bytes of the icon url set by an instruction
+pub const MAX_ICON_LEN: usize = 124;
+/// Version of the `MintMeta` layout written by this program
+pub const MINT_META_VERSION: u8 = 1;
+
+/// Maximum length in bytes of an additional metadata key
+pub const MAX_METADATA_KEY_LEN: usize = 32;
+/// Maximum length in bytes of an additional metadata value
+pub const MAX_METADATA_VALUE_LEN: usize = 256;
+
+/// Space left for the symbol, name and icon in a packed `MintMeta`, after the
+/// version byte and the three length bytes
+const MINT_META_STRINGS_CAPACITY: usize = 164;
+
+/// Fills the unused part of a packed `MintMeta`. Along with the checks of
+/// `MintMeta::check_values`, it keeps a mint meta account grown past
+/// `MintMeta::LEN` from holding a valid `AccountType` at `ACCOUNT_TYPE_OFFSET`.
+const MINT_META_PADDING: u8 = 0xff;
+
 /// Mint describe meta data
+///
+/// Packed as the initialized flag, the authority, a version byte, and then the
+/// symbol, name and icon, each as a length byte followed by its UTF-8 bytes.
+/// Records written before the layout was versioned hold the three strings
+/// separated by newlines instead; they are still read, and are rewritten in
+/// the current layout the next time they are packed.
+///
+/// Additional key/value pairs may follow the packed `MintMeta`, see
+/// `get_additional_metadata`. The account is reallocated to fit them exactly.
 #[repr(C)]
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct MintMeta {
-    /// Is `true` if this structure has been initialized  1
+    /// Is `true` if this structure has been initialized
     pub is_initialized: bool,
-
-    /// update authority        +36
+    /// Authority allowed to update the symbol, name and icon
     pub authority: COption<Pubkey>,
-    /// oa
-    /// symbol [u8;8]   +8
-    pub symbol: String, //TokenSymbol,
-    /// oa
-    /// name [u8;32]    +32
-    pub name: String, //TokenName,
-    /// icon [u8;128]   +128
-    pub icon: String, // TokenIcon,
+    /// Symbol of the token, at most `MAX_SYMBOL_LEN` bytes
+    pub symbol: String,
+    /// Name of the token, at most `MAX_NAME_LEN` bytes
+    pub name: String,
+    /// Icon url of the token, at most `MAX_ICON_LEN` bytes
+    pub icon: String,
+}
+impl MintMeta {
+    /// Checks that the symbol, name and icon fit in the packed layout, as
+    /// `MintMeta::pack` does before writing them. Values set by instructions
+    /// are bounded by `MAX_SYMBOL_LEN`, `MAX_NAME_LEN` and `MAX_ICON_LEN`,
+    /// which together fill it exactly, but records written before the layout
+    /// was versioned may divide the space differently.
+    pub fn check_packed_len(&self) -> Result<(), TokenError> {
+        let strings_len = self.symbol.len() + self.name.len() + self.icon.len();
+        if strings_len > MINT_META_STRINGS_CAPACITY {
+            return Err(TokenError::MintMetaTooLong);
+        }
+        Ok(())
+    }
+
+    /// Checks that the symbol, name and icon are within their maximum lengths
+    /// and hold no control characters, as required before the account may
+    /// carry additional metadata
+    pub fn check_values(&self) -> Result<(), TokenError> {
+        let values = [
+            (&self.symbol, MAX_SYMBOL_LEN),
+            (&self.name, MAX_NAME_LEN),
+            (&self.icon, MAX_ICON_LEN),
+        ];
+        for (value, max_len) in values.iter() {
+            if value.len() > *max_len {
+                return Err(TokenError::MintMetaTooLong);
+            }
+            if value.chars().any(char::is_control) {
+                return Err(TokenError::InvalidMintMetaCharacter);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if the packed record was written before the layout was
+    /// versioned
+    pub fn is_legacy_layout(src: &[u8]) -> bool {
+        src.len() == Self::LEN && src[0] == 1 && src[37] != MINT_META_VERSION
+    }
 }
 
 impl Sealed for MintMeta {}
@@ -123,18 +197,28 @@ impl Pack for MintMeta {
             _ => return Err(ProgramError::InvalidAccountData),
         };
         if is_initialized {
-            let s = String::from_utf8(data.to_vec()).expect("Found invalid UTF-8");
-            let mut item = s.split('\n');
-            let symbol = item.next().expect("Found invalid Symbol");
-            let name = item.next().expect("Found invalid Name");
-            let icon = item.next().expect("Found invalid Icon");
+            let (symbol, name, icon) = if data[0] == MINT_META_VERSION {
+                let (symbol, rest) = unpack_meta_string(&data[1..])?;
+                let (name, rest) = unpack_meta_string(rest)?;
+                let (icon, _rest) = unpack_meta_string(rest)?;
+                (symbol, name, icon)
+            } else {
+                let s = std::str::from_utf8(data).map_err(|_| ProgramError::InvalidAccountData)?;
+                let mut item = s.split('\n');
+                let mut next_item = || {
+                    item.next()
+                        .map(str::to_string)
+                        .ok_or(ProgramError::InvalidAccountData)
+                };
+                (next_item()?, next_item()?, next_item()?)
+            };
 
             Ok(MintMeta {
                 is_initialized,
                 authority: unpack_coption_key(authority)?,
-                symbol: symbol.to_string(),
-                name: name.to_string(),
-                icon: icon.to_string(),
+                symbol,
+                name,
+                icon,
             })
         } else {
             Ok(MintMeta::default())
@@ -152,14 +236,114 @@ impl Pack for MintMeta {
         } = self;
         is_initialized_dst[0] = is_initialized as u8;
         pack_coption_key(authority, authority_dst);
-        let met_str = symbol.to_string() + "\n" + name + "\n" + icon + "\n";
-        let mut meta = [0u8; 168];
-        let (pos, _) = meta.split_at_mut(met_str.len());
-        pos.copy_from_slice(met_str.as_ref());
-        meta_dst.copy_from_slice(meta.as_ref());
+        // `MintMeta::pack` checks `check_packed_len` first. Values that do not fit are never
+        // cut short, which could split a character and store a record other than the one given
+        assert!(
+            symbol.len() + name.len() + icon.len() <= MINT_META_STRINGS_CAPACITY,
+            "mint meta symbol, name and icon do not fit the packed layout"
+        );
+        *meta_dst = [MINT_META_PADDING; 168];
+        meta_dst[0] = MINT_META_VERSION;
+        let mut offset = 1;
+        for value in [symbol, name, icon].iter() {
+            let len = value.len();
+            meta_dst[offset] = len as u8;
+            meta_dst[offset + 1..offset + 1 + len].copy_from_slice(value.as_bytes());
+            offset += 1 + len;
+        }
+    }
+    fn pack(src: Self, dst: &mut [u8]) -> Result<(), ProgramError> {
+        if dst.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        src.check_packed_len()?;
+        src.pack_into_slice(dst);
+        Ok(())
     }
 }
 
+/// Returns the packed `MintMeta` at the start of a mint meta account
+pub fn get_mint_meta_base(data: &[u8]) -> Result<&[u8], ProgramError> {
+    data.get(..MintMeta::LEN)
+        .ok_or(ProgramError::InvalidAccountData)
+}
+
+/// Length of a mint meta account carrying the given additional metadata
+pub fn get_mint_meta_len(additional_metadata: &[(String, String)]) -> usize {
+    additional_metadata
+        .iter()
+        .fold(MintMeta::LEN, |len, (key, value)| {
+            len + 1 + key.len() + 2 + value.len()
+        })
+}
+
+/// Unpacks the key/value pairs following the packed `MintMeta`. Each pair is
+/// stored as a length byte and the key, then a little-endian `u16` length and
+/// the value.
+pub fn get_additional_metadata(data: &[u8]) -> Result<Vec<(String, String)>, ProgramError> {
+    let mut src = data
+        .get(MintMeta::LEN..)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    let mut additional_metadata = vec![];
+    while !src.is_empty() {
+        let (&key_len, rest) = src.split_first().ok_or(ProgramError::InvalidAccountData)?;
+        let key = rest
+            .get(..key_len as usize)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let rest = &rest[key_len as usize..];
+        let value_len = rest
+            .get(..2)
+            .map(|len| u16::from_le_bytes([len[0], len[1]]) as usize)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let value = rest
+            .get(2..2 + value_len)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        additional_metadata.push((
+            String::from_utf8(key.to_vec()).map_err(|_| ProgramError::InvalidAccountData)?,
+            String::from_utf8(value.to_vec()).map_err(|_| ProgramError::InvalidAccountData)?,
+        ));
+        src = &rest[2 + value_len..];
+    }
+    Ok(additional_metadata)
+}
+
+/// Packs key/value pairs after the packed `MintMeta`. The account must be
+/// exactly `get_mint_meta_len` bytes long.
+pub fn pack_additional_metadata(
+    additional_metadata: &[(String, String)],
+    data: &mut [u8],
+) -> Result<(), ProgramError> {
+    if data.len() != get_mint_meta_len(additional_metadata) {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let mut offset = MintMeta::LEN;
+    for (key, value) in additional_metadata {
+        if key.len() > MAX_METADATA_KEY_LEN || value.len() > MAX_METADATA_VALUE_LEN {
+            return Err(TokenError::MintMetaTooLong.into());
+        }
+        data[offset] = key.len() as u8;
+        offset += 1;
+        data[offset..offset + key.len()].copy_from_slice(key.as_bytes());
+        offset += key.len();
+        data[offset..offset + 2].copy_from_slice(&(value.len() as u16).to_le_bytes());
+        offset += 2;
+        data[offset..offset + value.len()].copy_from_slice(value.as_bytes());
+        offset += value.len();
+    }
+    Ok(())
+}
+
+fn unpack_meta_string(src: &[u8]) -> Result<(String, &[u8]), ProgramError> {
+    let (&len, rest) = src.split_first().ok_or(ProgramError::InvalidAccountData)?;
+    let len = len as usize;
+    if len > rest.len() {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let (value, rest) = rest.split_at(len);
+    let value = String::from_utf8(value.to_vec()).map_err(|_| ProgramError::InvalidAccountData)?;
+    Ok((value, rest))
+}
+
 /// Account data.
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
@@ -254,6 +438,7 @@ impl Pack for Account {
 /// Account state.
 #[repr(u8)]
 #[derive(Clone, Copy, Debug, PartialEq, TryFromPrimitive)]
+#[cfg_attr(feature = "serde-traits", derive(Serialize, Deserialize))]
 pub enum AccountState {
     /// Account is not yet initialized
     Uninitialized,
@@ -325,6 +510,1009 @@ impl Pack for Multisig {
     }
 }
 
+/// Balance of a token account recorded at a given slot, kept in the account at
+/// `get_balance_checkpoint_address(account, epoch)`. Recording again within the
+/// same epoch overwrites the checkpoint. Each token account has checkpoints of its
+/// own, so the balance of an owner holding several accounts of a mint is the sum
+/// of their checkpoints.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BalanceCheckpoint {
+    /// Is `true` if this structure has been initialized
+    pub is_initialized: bool,
+    /// The mint of the recorded balance
+    pub mint: Pubkey,
+    /// The owner of the recorded token account
+    pub owner: Pubkey,
+    /// The recorded token account
+    pub account: Pubkey,
+    /// The epoch the checkpoint account belongs to
+    pub epoch: Epoch,
+    /// The slot at which the balance was recorded
+    pub slot: Slot,
+    /// The recorded balance
+    pub amount: u128,
+}
+impl Sealed for BalanceCheckpoint {}
+impl IsInitialized for BalanceCheckpoint {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+impl Pack for BalanceCheckpoint {
+    const LEN: usize = 129;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, 129];
+        let (is_initialized, mint, owner, account, epoch, slot, amount) =
+            array_refs![src, 1, 32, 32, 32, 8, 8, 16];
+        Ok(BalanceCheckpoint {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            mint: Pubkey::new_from_array(*mint),
+            owner: Pubkey::new_from_array(*owner),
+            account: Pubkey::new_from_array(*account),
+            epoch: Epoch::from_le_bytes(*epoch),
+            slot: Slot::from_le_bytes(*slot),
+            amount: u128::from_le_bytes(*amount),
+        })
+    }
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, 129];
+        let (is_initialized_dst, mint_dst, owner_dst, account_dst, epoch_dst, slot_dst, amount_dst) =
+            mut_array_refs![dst, 1, 32, 32, 32, 8, 8, 16];
+        is_initialized_dst[0] = self.is_initialized as u8;
+        mint_dst.copy_from_slice(self.mint.as_ref());
+        owner_dst.copy_from_slice(self.owner.as_ref());
+        account_dst.copy_from_slice(self.account.as_ref());
+        *epoch_dst = self.epoch.to_le_bytes();
+        *slot_dst = self.slot.to_le_bytes();
+        *amount_dst = self.amount.to_le_bytes();
+    }
+}
+
+/// Returns the most recent of the given checkpoints recorded at or before `slot`,
+/// so a tally can use the balance held as of that slot. Checkpoints of other token
+/// accounts must be filtered out by the caller.
+pub fn find_balance_checkpoint<'a, I>(checkpoints: I, slot: Slot) -> Option<&'a BalanceCheckpoint>
+where
+    I: IntoIterator<Item = &'a BalanceCheckpoint>,
+{
+    checkpoints
+        .into_iter()
+        .filter(|checkpoint| checkpoint.is_initialized && checkpoint.slot <= slot)
+        .max_by_key(|checkpoint| checkpoint.slot)
+}
+
+/// Authority granted by a wallet to an operator over all of its token accounts of a mint,
+/// kept in the account at `get_operator_approval_address(mint, owner, operator)`. The
+/// operator may transfer and burn from those accounts, up to `limit` tokens per `period`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct OperatorApproval {
+    /// Is `true` if this structure has been initialized
+    pub is_initialized: bool,
+    /// The mint of the token accounts the operator may act on
+    pub mint: Pubkey,
+    /// The owner of the token accounts the operator may act on
+    pub owner: Pubkey,
+    /// The approved operator
+    pub operator: Pubkey,
+    /// Amount of tokens the operator may move per period, unlimited if `None`
+    pub limit: COption<u128>,
+    /// Length of a period in seconds. With a limit and no period, the limit covers the
+    /// lifetime of the approval.
+    pub period: u64,
+    /// Start of the current period
+    pub period_start: UnixTimestamp,
+    /// Amount of tokens moved by the operator during the current period
+    pub spent: u128,
+}
+impl OperatorApproval {
+    /// Records `amount` tokens moved by the operator at `unix_timestamp`, starting a new
+    /// period if the current one is over, and fails if that exceeds the limit
+    pub fn spend(
+        &mut self,
+        amount: u128,
+        unix_timestamp: UnixTimestamp,
+    ) -> Result<(), ProgramError> {
+        let limit = match self.limit {
+            COption::Some(limit) => limit,
+            COption::None => return Ok(()),
+        };
+        let elapsed = unix_timestamp.saturating_sub(self.period_start);
+        if self.period > 0 && elapsed >= 0 && elapsed as u64 >= self.period {
+            self.period_start = unix_timestamp;
+            self.spent = 0;
+        }
+        let spent = self.spent.checked_add(amount).ok_or(TokenError::Overflow)?;
+        if spent > limit {
+            return Err(TokenError::OperatorLimitExceeded.into());
+        }
+        self.spent = spent;
+        Ok(())
+    }
+}
+impl Sealed for OperatorApproval {}
+impl IsInitialized for OperatorApproval {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+impl Pack for OperatorApproval {
+    const LEN: usize = 149;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, 149];
+        let (is_initialized, mint, owner, operator, limit, period, period_start, spent) =
+            array_refs![src, 1, 32, 32, 32, 20, 8, 8, 16];
+        Ok(OperatorApproval {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            mint: Pubkey::new_from_array(*mint),
+            owner: Pubkey::new_from_array(*owner),
+            operator: Pubkey::new_from_array(*operator),
+            limit: unpack_coption_u128(limit)?,
+            period: u64::from_le_bytes(*period),
+            period_start: UnixTimestamp::from_le_bytes(*period_start),
+            spent: u128::from_le_bytes(*spent),
+        })
+    }
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, 149];
+        let (
+            is_initialized_dst,
+            mint_dst,
+            owner_dst,
+            operator_dst,
+            limit_dst,
+            period_dst,
+            period_start_dst,
+            spent_dst,
+        ) = mut_array_refs![dst, 1, 32, 32, 32, 20, 8, 8, 16];
+        is_initialized_dst[0] = self.is_initialized as u8;
+        mint_dst.copy_from_slice(self.mint.as_ref());
+        owner_dst.copy_from_slice(self.owner.as_ref());
+        operator_dst.copy_from_slice(self.operator.as_ref());
+        pack_coption_u128(&self.limit, limit_dst);
+        *period_dst = self.period.to_le_bytes();
+        *period_start_dst = self.period_start.to_le_bytes();
+        *spent_dst = self.spent.to_le_bytes();
+    }
+}
+
+/// Maximum transfer fee, in basis points of the transferred amount
+pub const MAX_FEE_BASIS_POINTS: u16 = 10_000;
+
+/// Number of epochs between setting a new transfer fee and the fee taking effect
+pub const TRANSFER_FEE_EPOCH_DELAY: Epoch = 2;
+
+/// Transfer fee schedule that applies from a given epoch onwards
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TransferFee {
+    /// First epoch where the transfer fee takes effect
+    pub epoch: Epoch,
+    /// Maximum fee assessed on a single transfer, expressed as an amount of tokens
+    pub maximum_fee: u128,
+    /// Share of the transferred amount withheld as a fee, in basis points (0.01%)
+    pub transfer_fee_basis_points: u16,
+}
+impl TransferFee {
+    const LEN: usize = 8 + 16 + 2;
+
+    /// Calculates the fee withheld from a transfer of `amount` tokens, rounding up and
+    /// capping at `maximum_fee`
+    pub fn calculate_fee(&self, amount: u128) -> u128 {
+        let basis_points = self.transfer_fee_basis_points as u128;
+        if basis_points == 0 || amount == 0 {
+            return 0;
+        }
+        let one_in_basis_points = MAX_FEE_BASIS_POINTS as u128;
+        // Split the amount so the multiplication cannot overflow
+        let whole = (amount / one_in_basis_points).saturating_mul(basis_points);
+        let remainder =
+            ((amount % one_in_basis_points) * basis_points + one_in_basis_points - 1)
+                / one_in_basis_points;
+        whole.saturating_add(remainder).min(self.maximum_fee)
+    }
+
+    fn unpack_from_array(src: &[u8; 26]) -> Self {
+        let (epoch, maximum_fee, transfer_fee_basis_points) = array_refs![src, 8, 16, 2];
+        TransferFee {
+            epoch: Epoch::from_le_bytes(*epoch),
+            maximum_fee: u128::from_le_bytes(*maximum_fee),
+            transfer_fee_basis_points: u16::from_le_bytes(*transfer_fee_basis_points),
+        }
+    }
+
+    fn pack_into_array(&self, dst: &mut [u8; 26]) {
+        let (epoch_dst, maximum_fee_dst, transfer_fee_basis_points_dst) =
+            mut_array_refs![dst, 8, 16, 2];
+        *epoch_dst = self.epoch.to_le_bytes();
+        *maximum_fee_dst = self.maximum_fee.to_le_bytes();
+        *transfer_fee_basis_points_dst = self.transfer_fee_basis_points.to_le_bytes();
+    }
+}
+
+/// Mint extension holding the transfer fee configuration.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TransferFeeConfig {
+    /// Optional authority allowed to change the transfer fee
+    pub transfer_fee_config_authority: COption<Pubkey>,
+    /// Optional authority allowed to withdraw withheld fees from the mint and token accounts
+    pub withdraw_withheld_authority: COption<Pubkey>,
+    /// Withheld fees harvested from token accounts into the mint
+    pub withheld_amount: u128,
+    /// Fee used while the current epoch is before `newer_transfer_fee.epoch`
+    pub older_transfer_fee: TransferFee,
+    /// Fee used once the current epoch reaches `newer_transfer_fee.epoch`
+    pub newer_transfer_fee: TransferFee,
+}
+impl TransferFeeConfig {
+    /// Returns the fee schedule in effect during `epoch`
+    pub fn get_epoch_fee(&self, epoch: Epoch) -> &TransferFee {
+        if epoch >= self.newer_transfer_fee.epoch {
+            &self.newer_transfer_fee
+        } else {
+            &self.older_transfer_fee
+        }
+    }
+    /// Calculates the fee withheld from a transfer of `amount` tokens during `epoch`
+    pub fn calculate_epoch_fee(&self, epoch: Epoch, amount: u128) -> u128 {
+        self.get_epoch_fee(epoch).calculate_fee(amount)
+    }
+}
+impl Sealed for TransferFeeConfig {}
+impl Pack for TransferFeeConfig {
+    const LEN: usize = 36 + 36 + 16 + TransferFee::LEN * 2;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, 140];
+        let (
+            transfer_fee_config_authority,
+            withdraw_withheld_authority,
+            withheld_amount,
+            older_transfer_fee,
+            newer_transfer_fee,
+        ) = array_refs![src, 36, 36, 16, 26, 26];
+        Ok(TransferFeeConfig {
+            transfer_fee_config_authority: unpack_coption_key(transfer_fee_config_authority)?,
+            withdraw_withheld_authority: unpack_coption_key(withdraw_withheld_authority)?,
+            withheld_amount: u128::from_le_bytes(*withheld_amount),
+            older_transfer_fee: TransferFee::unpack_from_array(older_transfer_fee),
+            newer_transfer_fee: TransferFee::unpack_from_array(newer_transfer_fee),
+        })
+    }
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, 140];
+        let (
+            transfer_fee_config_authority_dst,
+            withdraw_withheld_authority_dst,
+            withheld_amount_dst,
+            older_transfer_fee_dst,
+            newer_transfer_fee_dst,
+        ) = mut_array_refs![dst, 36, 36, 16, 26, 26];
+        pack_coption_key(
+            &self.transfer_fee_config_authority,
+            transfer_fee_config_authority_dst,
+        );
+        pack_coption_key(
+            &self.withdraw_withheld_authority,
+            withdraw_withheld_authority_dst,
+        );
+        *withheld_amount_dst = self.withheld_amount.to_le_bytes();
+        self.older_transfer_fee
+            .pack_into_array(older_transfer_fee_dst);
+        self.newer_transfer_fee
+            .pack_into_array(newer_transfer_fee_dst);
+    }
+}
+impl Extension for TransferFeeConfig {
+    const TYPE: ExtensionType = ExtensionType::TransferFeeConfig;
+}
+
+/// Account extension holding the transfer fees withheld on incoming transfers.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TransferFeeAmount {
+    /// Withheld transfer fees, claimable by the mint's withdraw withheld authority
+    pub withheld_amount: u128,
+}
+impl Sealed for TransferFeeAmount {}
+impl Pack for TransferFeeAmount {
+    const LEN: usize = 16;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let withheld_amount = array_ref![src, 0, 16];
+        Ok(TransferFeeAmount {
+            withheld_amount: u128::from_le_bytes(*withheld_amount),
+        })
+    }
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let withheld_amount_dst = array_mut_ref![dst, 0, 16];
+        *withheld_amount_dst = self.withheld_amount.to_le_bytes();
+    }
+}
+impl Extension for TransferFeeAmount {
+    const TYPE: ExtensionType = ExtensionType::TransferFeeAmount;
+}
+
+/// Average number of seconds in a year, used to compound interest rates
+pub const SECONDS_PER_YEAR: f64 = 60.0 * 60.0 * 24.0 * 365.24;
+
+/// Mint extension making UI amounts accrue interest, compounded continuously, without
+/// changing raw balances.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct InterestBearingConfig {
+    /// Optional authority allowed to change the interest rate
+    pub rate_authority: COption<Pubkey>,
+    /// Timestamp of the initialization, from which interest accrues
+    pub initialization_timestamp: UnixTimestamp,
+    /// Average rate, in basis points per year, between initialization and the last update
+    pub pre_update_average_rate: i16,
+    /// Timestamp of the last rate update
+    pub last_update_timestamp: UnixTimestamp,
+    /// Current rate, in basis points per year, since the last update
+    pub current_rate: i16,
+}
+impl InterestBearingConfig {
+    fn rate_scale(rate: i16, elapsed: UnixTimestamp) -> f64 {
+        (rate as f64 * elapsed as f64 / SECONDS_PER_YEAR / MAX_FEE_BASIS_POINTS as f64).exp()
+    }
+
+    /// Scale applied to raw amounts at `unix_timestamp`, combining the interest accrued
+    /// before and after the last rate update
+    pub fn total_scale(&self, unix_timestamp: UnixTimestamp) -> Option<f64> {
+        let pre_update_elapsed = self
+            .last_update_timestamp
+            .checked_sub(self.initialization_timestamp)?;
+        let post_update_elapsed = unix_timestamp.checked_sub(self.last_update_timestamp)?;
+        Some(
+            Self::rate_scale(self.pre_update_average_rate, pre_update_elapsed)
+                * Self::rate_scale(self.current_rate, post_update_elapsed),
+        )
+    }
+
+    /// Average rate since initialization, weighted by the time each rate was in effect
+    pub fn time_weighted_average_rate(&self, unix_timestamp: UnixTimestamp) -> Option<i16> {
+        let pre_update_elapsed = self
+            .last_update_timestamp
+            .checked_sub(self.initialization_timestamp)? as i128;
+        let post_update_elapsed = unix_timestamp.checked_sub(self.last_update_timestamp)? as i128;
+        let total_elapsed = pre_update_elapsed.checked_add(post_update_elapsed)?;
+        if total_elapsed == 0 {
+            return Some(self.current_rate);
+        }
+        let weighted_rates = (self.pre_update_average_rate as i128)
+            .checked_mul(pre_update_elapsed)?
+            .checked_add((self.current_rate as i128).checked_mul(post_update_elapsed)?)?;
+        i16::try_from(weighted_rates.checked_div(total_elapsed)?).ok()
+    }
+}
+impl Sealed for InterestBearingConfig {}
+impl Pack for InterestBearingConfig {
+    const LEN: usize = 36 + 8 + 2 + 8 + 2;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, 56];
+        let (
+            rate_authority,
+            initialization_timestamp,
+            pre_update_average_rate,
+            last_update_timestamp,
+            current_rate,
+        ) = array_refs![src, 36, 8, 2, 8, 2];
+        Ok(InterestBearingConfig {
+            rate_authority: unpack_coption_key(rate_authority)?,
+            initialization_timestamp: UnixTimestamp::from_le_bytes(*initialization_timestamp),
+            pre_update_average_rate: i16::from_le_bytes(*pre_update_average_rate),
+            last_update_timestamp: UnixTimestamp::from_le_bytes(*last_update_timestamp),
+            current_rate: i16::from_le_bytes(*current_rate),
+        })
+    }
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, 56];
+        let (
+            rate_authority_dst,
+            initialization_timestamp_dst,
+            pre_update_average_rate_dst,
+            last_update_timestamp_dst,
+            current_rate_dst,
+        ) = mut_array_refs![dst, 36, 8, 2, 8, 2];
+        pack_coption_key(&self.rate_authority, rate_authority_dst);
+        *initialization_timestamp_dst = self.initialization_timestamp.to_le_bytes();
+        *pre_update_average_rate_dst = self.pre_update_average_rate.to_le_bytes();
+        *last_update_timestamp_dst = self.last_update_timestamp.to_le_bytes();
+        *current_rate_dst = self.current_rate.to_le_bytes();
+    }
+}
+impl Extension for InterestBearingConfig {
+    const TYPE: ExtensionType = ExtensionType::InterestBearingConfig;
+}
+
+/// Mint extension marking its tokens as non-transferable. Tokens can still be minted,
+/// burned, and their accounts closed.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct NonTransferable;
+impl Sealed for NonTransferable {}
+impl Pack for NonTransferable {
+    const LEN: usize = 0;
+    fn unpack_from_slice(_src: &[u8]) -> Result<Self, ProgramError> {
+        Ok(NonTransferable)
+    }
+    fn pack_into_slice(&self, _dst: &mut [u8]) {}
+}
+impl Extension for NonTransferable {
+    const TYPE: ExtensionType = ExtensionType::NonTransferable;
+}
+
+/// Account extension marking a token account of a non-transferable mint, so that
+/// instructions which do not take the mint can still reject transfers.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct NonTransferableAccount;
+impl Sealed for NonTransferableAccount {}
+impl Pack for NonTransferableAccount {
+    const LEN: usize = 0;
+    fn unpack_from_slice(_src: &[u8]) -> Result<Self, ProgramError> {
+        Ok(NonTransferableAccount)
+    }
+    fn pack_into_slice(&self, _dst: &mut [u8]) {}
+}
+impl Extension for NonTransferableAccount {
+    const TYPE: ExtensionType = ExtensionType::NonTransferableAccount;
+}
+
+/// Mint extension naming a delegate that may transfer or burn tokens from any account
+/// of the mint.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PermanentDelegate {
+    /// Optional permanent delegate of every account of the mint
+    pub delegate: COption<Pubkey>,
+}
+impl Sealed for PermanentDelegate {}
+impl Pack for PermanentDelegate {
+    const LEN: usize = 36;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let delegate = array_ref![src, 0, 36];
+        Ok(PermanentDelegate {
+            delegate: unpack_coption_key(delegate)?,
+        })
+    }
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let delegate_dst = array_mut_ref![dst, 0, 36];
+        pack_coption_key(&self.delegate, delegate_dst);
+    }
+}
+impl Extension for PermanentDelegate {
+    const TYPE: ExtensionType = ExtensionType::PermanentDelegate;
+}
+
+/// Mint extension setting the state new token accounts of the mint start in.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DefaultAccountState {
+    /// State given to new token accounts, either initialized or frozen
+    pub state: AccountState,
+}
+impl Sealed for DefaultAccountState {}
+impl Pack for DefaultAccountState {
+    const LEN: usize = 1;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let state = array_ref![src, 0, 1];
+        Ok(DefaultAccountState {
+            state: AccountState::try_from_primitive(state[0])
+                .or(Err(ProgramError::InvalidAccountData))?,
+        })
+    }
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let state_dst = array_mut_ref![dst, 0, 1];
+        state_dst[0] = self.state as u8;
+    }
+}
+impl Extension for DefaultAccountState {
+    const TYPE: ExtensionType = ExtensionType::DefaultAccountState;
+}
+
+/// Mint extension naming the authority allowed to close the mint once its supply is zero.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MintCloseAuthority {
+    /// Optional authority to close the mint
+    pub close_authority: COption<Pubkey>,
+}
+impl Sealed for MintCloseAuthority {}
+impl Pack for MintCloseAuthority {
+    const LEN: usize = 36;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let close_authority = array_ref![src, 0, 36];
+        Ok(MintCloseAuthority {
+            close_authority: unpack_coption_key(close_authority)?,
+        })
+    }
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let close_authority_dst = array_mut_ref![dst, 0, 36];
+        pack_coption_key(&self.close_authority, close_authority_dst);
+    }
+}
+impl Extension for MintCloseAuthority {
+    const TYPE: ExtensionType = ExtensionType::MintCloseAuthority;
+}
+
+/// Account extension letting the owner require a memo on incoming transfers.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MemoTransfer {
+    /// Is `true` if incoming transfers must be preceded by a memo instruction
+    pub require_incoming_transfer_memos: bool,
+}
+impl Sealed for MemoTransfer {}
+impl Pack for MemoTransfer {
+    const LEN: usize = 1;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let require_incoming_transfer_memos = array_ref![src, 0, 1];
+        Ok(MemoTransfer {
+            require_incoming_transfer_memos: match require_incoming_transfer_memos {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+        })
+    }
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let require_incoming_transfer_memos_dst = array_mut_ref![dst, 0, 1];
+        require_incoming_transfer_memos_dst[0] = self.require_incoming_transfer_memos as u8;
+    }
+}
+impl Extension for MemoTransfer {
+    const TYPE: ExtensionType = ExtensionType::MemoTransfer;
+}
+
+/// Account extension letting the owner block owner actions made through
+/// cross-program invocation.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CpiGuard {
+    /// Is `true` if owner transfers, approvals and closes must be invoked
+    /// at the top level of a transaction
+    pub lock_cpi: bool,
+}
+impl Sealed for CpiGuard {}
+impl Pack for CpiGuard {
+    const LEN: usize = 1;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let lock_cpi = array_ref![src, 0, 1];
+        Ok(CpiGuard {
+            lock_cpi: match lock_cpi {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+        })
+    }
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let lock_cpi_dst = array_mut_ref![dst, 0, 1];
+        lock_cpi_dst[0] = self.lock_cpi as u8;
+    }
+}
+impl Extension for CpiGuard {
+    const TYPE: ExtensionType = ExtensionType::CpiGuard;
+}
+
+/// Account extension letting the delegate move up to `allowance` tokens per period, the
+/// delegated amount being restored to the allowance whenever a new period starts.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RecurringDelegation {
+    /// Length of a period in seconds, the delegation does not recur if zero
+    pub period: u64,
+    /// Start of the current period
+    pub period_start: UnixTimestamp,
+    /// Amount the delegate may move per period
+    pub allowance: u128,
+}
+impl RecurringDelegation {
+    /// Moves to the period containing `unix_timestamp` if the current one is over. Returns
+    /// `true` if a new period started, in which case the delegated amount must be restored
+    /// to the allowance.
+    pub fn roll_over(&mut self, unix_timestamp: UnixTimestamp) -> bool {
+        let elapsed = unix_timestamp.saturating_sub(self.period_start);
+        if self.period == 0 || elapsed < 0 || (elapsed as u64) < self.period {
+            return false;
+        }
+        let elapsed = elapsed as u64;
+        self.period_start += (elapsed - elapsed % self.period) as UnixTimestamp;
+        true
+    }
+}
+impl Sealed for RecurringDelegation {}
+impl Pack for RecurringDelegation {
+    const LEN: usize = 32;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, 32];
+        let (period, period_start, allowance) = array_refs![src, 8, 8, 16];
+        Ok(RecurringDelegation {
+            period: u64::from_le_bytes(*period),
+            period_start: UnixTimestamp::from_le_bytes(*period_start),
+            allowance: u128::from_le_bytes(*allowance),
+        })
+    }
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, 32];
+        let (period_dst, period_start_dst, allowance_dst) = mut_array_refs![dst, 8, 8, 16];
+        *period_dst = self.period.to_le_bytes();
+        *period_start_dst = self.period_start.to_le_bytes();
+        *allowance_dst = self.allowance.to_le_bytes();
+    }
+}
+impl Extension for RecurringDelegation {
+    const TYPE: ExtensionType = ExtensionType::RecurringDelegation;
+}
+
+// Extensions
+//
+// A mint or account that carries extensions is laid out as the base state,
+// zero padding up to `ACCOUNT_TYPE_OFFSET` (mints only), one `AccountType`
+// byte, and then a list of entries made of a little-endian `u16` extension
+// type, a little-endian `u16` length and the packed extension value. Plain
+// 90-byte mints and 189-byte accounts keep working unchanged.
+
+/// Offset of the account type byte in mints and accounts that carry extensions
+pub const ACCOUNT_TYPE_OFFSET: usize = Account::LEN;
+
+/// Length of the type and length header preceding each extension value
+pub const EXTENSION_HEADER_LEN: usize = 4;
+
+/// Kind of base state stored in an account that carries extensions
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, TryFromPrimitive)]
+pub enum AccountType {
+    /// Base state is not initialized yet
+    Uninitialized,
+    /// Mint followed by mint extensions
+    Mint,
+    /// Token account followed by account extensions
+    Account,
+}
+
+/// Extensions that may follow a mint or a token account
+#[repr(u16)]
+#[derive(Clone, Copy, Debug, PartialEq, TryFromPrimitive)]
+#[cfg_attr(feature = "serde-traits", derive(Serialize, Deserialize))]
+pub enum ExtensionType {
+    /// Unused extension space
+    Uninitialized,
+    /// Transfer fee configuration of a mint
+    TransferFeeConfig,
+    /// Transfer fees withheld on a token account
+    TransferFeeAmount,
+    /// Interest accrued on the UI amounts of a mint
+    InterestBearingConfig,
+    /// Tokens of a mint cannot be transferred
+    NonTransferable,
+    /// Token account of a non-transferable mint
+    NonTransferableAccount,
+    /// Delegate of every token account of a mint
+    PermanentDelegate,
+    /// State new token accounts of a mint start in
+    DefaultAccountState,
+    /// Authority allowed to close a mint
+    MintCloseAuthority,
+    /// Memo requirement on transfers into a token account
+    MemoTransfer,
+    /// Guard against owner actions made through cross-program invocation
+    CpiGuard,
+    /// Delegated amount restored at the start of every period
+    RecurringDelegation,
+}
+impl ExtensionType {
+    /// Packed length of the extension value
+    pub fn get_type_len(&self) -> usize {
+        match self {
+            ExtensionType::Uninitialized => 0,
+            ExtensionType::TransferFeeConfig => TransferFeeConfig::LEN,
+            ExtensionType::TransferFeeAmount => TransferFeeAmount::LEN,
+            ExtensionType::InterestBearingConfig => InterestBearingConfig::LEN,
+            ExtensionType::NonTransferable => NonTransferable::LEN,
+            ExtensionType::NonTransferableAccount => NonTransferableAccount::LEN,
+            ExtensionType::PermanentDelegate => PermanentDelegate::LEN,
+            ExtensionType::DefaultAccountState => DefaultAccountState::LEN,
+            ExtensionType::MintCloseAuthority => MintCloseAuthority::LEN,
+            ExtensionType::MemoTransfer => MemoTransfer::LEN,
+            ExtensionType::CpiGuard => CpiGuard::LEN,
+            ExtensionType::RecurringDelegation => RecurringDelegation::LEN,
+        }
+    }
+
+    /// Base state the extension may be attached to
+    pub fn get_account_type(&self) -> AccountType {
+        match self {
+            ExtensionType::Uninitialized => AccountType::Uninitialized,
+            ExtensionType::TransferFeeConfig
+            | ExtensionType::InterestBearingConfig
+            | ExtensionType::NonTransferable
+            | ExtensionType::PermanentDelegate
+            | ExtensionType::DefaultAccountState
+            | ExtensionType::MintCloseAuthority => AccountType::Mint,
+            ExtensionType::TransferFeeAmount
+            | ExtensionType::NonTransferableAccount
+            | ExtensionType::MemoTransfer
+            | ExtensionType::CpiGuard
+            | ExtensionType::RecurringDelegation => AccountType::Account,
+        }
+    }
+
+    /// Account extensions every token account of a mint with the given extensions must carry
+    pub fn get_required_account_extensions(
+        mint_extension_types: &[ExtensionType],
+    ) -> Vec<ExtensionType> {
+        let mut account_extension_types = vec![];
+        for extension_type in mint_extension_types {
+            match extension_type {
+                ExtensionType::TransferFeeConfig => {
+                    account_extension_types.push(ExtensionType::TransferFeeAmount)
+                }
+                ExtensionType::NonTransferable => {
+                    account_extension_types.push(ExtensionType::NonTransferableAccount)
+                }
+                _ => {}
+            }
+        }
+        account_extension_types
+    }
+
+    /// Data length of a base state `S` carrying the given extensions
+    pub fn get_account_len<S: BaseState>(extension_types: &[ExtensionType]) -> usize {
+        if extension_types.is_empty() {
+            return S::LEN;
+        }
+        let extensions_len: usize = extension_types
+            .iter()
+            .map(|extension_type| EXTENSION_HEADER_LEN + extension_type.get_type_len())
+            .sum();
+        let account_len = ACCOUNT_TYPE_OFFSET + 1 + extensions_len;
+        // Lengths are used to tell accounts apart, so never collide with the fixed ones
+        if is_reserved_len(account_len) {
+            account_len + EXTENSION_HEADER_LEN
+        } else {
+            account_len
+        }
+    }
+}
+
+/// Value stored in the extension area of a mint or token account
+pub trait Extension: Pack {
+    /// Extension type tag
+    const TYPE: ExtensionType;
+}
+
+/// Base state that may be followed by extensions
+pub trait BaseState: Pack + IsInitialized {
+    /// Account type recorded for the extended layout
+    const ACCOUNT_TYPE: AccountType;
+
+    /// Unpacks the base state of a plain or extended account and checks it is initialized
+    fn unpack_base(input: &[u8]) -> Result<Self, ProgramError> {
+        check_account_type(input, Self::LEN, Self::ACCOUNT_TYPE)?;
+        Self::unpack(&input[..Self::LEN])
+    }
+    /// Unpacks the base state of a plain or extended account without checking initialization
+    fn unpack_base_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        check_account_type(input, Self::LEN, Self::ACCOUNT_TYPE)?;
+        Self::unpack_unchecked(&input[..Self::LEN])
+    }
+    /// Packs the base state into a plain or extended account, recording its account type
+    fn pack_base(src: Self, dst: &mut [u8]) -> Result<(), ProgramError> {
+        check_account_type(dst, Self::LEN, Self::ACCOUNT_TYPE)?;
+        Self::pack(src, &mut dst[..Self::LEN])?;
+        if dst.len() != Self::LEN {
+            dst[ACCOUNT_TYPE_OFFSET] = Self::ACCOUNT_TYPE as u8;
+        }
+        Ok(())
+    }
+}
+impl BaseState for Mint {
+    const ACCOUNT_TYPE: AccountType = AccountType::Mint;
+}
+impl BaseState for Account {
+    const ACCOUNT_TYPE: AccountType = AccountType::Account;
+}
+
+fn is_reserved_len(len: usize) -> bool {
+    len == Multisig::LEN || len == MintMeta::LEN
+}
+
+fn is_extended_len(len: usize) -> bool {
+    len > ACCOUNT_TYPE_OFFSET && !is_reserved_len(len)
+}
+
+fn check_account_type(
+    data: &[u8],
+    base_len: usize,
+    account_type: AccountType,
+) -> Result<(), ProgramError> {
+    if data.len() == base_len {
+        return Ok(());
+    }
+    if !is_extended_len(data.len()) {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    match AccountType::try_from_primitive(data[ACCOUNT_TYPE_OFFSET]) {
+        Ok(AccountType::Uninitialized) => Ok(()),
+        Ok(stored_type) if stored_type == account_type => Ok(()),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+/// Determines whether a token program account holds a mint or a token account
+pub fn get_account_type(data: &[u8]) -> Result<AccountType, ProgramError> {
+    if data.len() == Mint::LEN {
+        Ok(AccountType::Mint)
+    } else if data.len() == Account::LEN {
+        Ok(AccountType::Account)
+    } else if is_extended_len(data.len()) {
+        AccountType::try_from_primitive(data[ACCOUNT_TYPE_OFFSET])
+            .or(Err(ProgramError::InvalidAccountData))
+    } else {
+        Err(ProgramError::InvalidAccountData)
+    }
+}
+
+fn get_tlv_data(data: &[u8]) -> &[u8] {
+    if is_extended_len(data.len()) {
+        &data[ACCOUNT_TYPE_OFFSET + 1..]
+    } else {
+        &[]
+    }
+}
+
+/// Walks the extension entries and returns the header offset of `extension_type`, or of the
+/// first unused entry when looking for `ExtensionType::Uninitialized`
+fn find_extension_offset(
+    tlv_data: &[u8],
+    extension_type: ExtensionType,
+) -> Result<Option<usize>, ProgramError> {
+    let mut start = 0;
+    while start + EXTENSION_HEADER_LEN <= tlv_data.len() {
+        let entry_type = u16::from_le_bytes(*array_ref![tlv_data, start, 2]);
+        if entry_type == extension_type as u16 {
+            return Ok(Some(start));
+        }
+        if entry_type == ExtensionType::Uninitialized as u16 {
+            return Ok(None);
+        }
+        let entry_len = u16::from_le_bytes(*array_ref![tlv_data, start + 2, 2]) as usize;
+        start = start
+            .checked_add(EXTENSION_HEADER_LEN + entry_len)
+            .ok_or(ProgramError::InvalidAccountData)?;
+    }
+    Ok(None)
+}
+
+fn check_extension_account_type<T: Extension>(data: &[u8]) -> Result<(), ProgramError> {
+    let account_type = T::TYPE.get_account_type();
+    match account_type {
+        AccountType::Mint => check_account_type(data, Mint::LEN, account_type),
+        AccountType::Account => check_account_type(data, Account::LEN, account_type),
+        AccountType::Uninitialized => Err(ProgramError::InvalidArgument),
+    }
+}
+
+fn get_extension_value_range<T: Extension>(
+    tlv_data: &[u8],
+) -> Result<Option<(usize, usize)>, ProgramError> {
+    if let Some(start) = find_extension_offset(tlv_data, T::TYPE)? {
+        let entry_len = u16::from_le_bytes(*array_ref![tlv_data, start + 2, 2]) as usize;
+        let value_start = start + EXTENSION_HEADER_LEN;
+        let value_end = value_start + T::LEN;
+        if entry_len != T::LEN || value_end > tlv_data.len() {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(Some((value_start, value_end)))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Lists the extensions carried by a mint or token account
+pub fn get_extension_types(data: &[u8]) -> Result<Vec<ExtensionType>, ProgramError> {
+    let tlv_data = get_tlv_data(data);
+    let mut extension_types = vec![];
+    let mut start = 0;
+    while start + EXTENSION_HEADER_LEN <= tlv_data.len() {
+        let extension_type =
+            ExtensionType::try_from_primitive(u16::from_le_bytes(*array_ref![tlv_data, start, 2]))
+                .or(Err(ProgramError::InvalidAccountData))?;
+        if extension_type == ExtensionType::Uninitialized {
+            break;
+        }
+        extension_types.push(extension_type);
+        let entry_len = u16::from_le_bytes(*array_ref![tlv_data, start + 2, 2]) as usize;
+        start = start
+            .checked_add(EXTENSION_HEADER_LEN + entry_len)
+            .ok_or(ProgramError::InvalidAccountData)?;
+    }
+    Ok(extension_types)
+}
+
+/// Unpacks extension `T` from a mint or token account, if present
+pub fn get_extension<T: Extension>(data: &[u8]) -> Result<Option<T>, ProgramError> {
+    check_extension_account_type::<T>(data)?;
+    let tlv_data = get_tlv_data(data);
+    match get_extension_value_range::<T>(tlv_data)? {
+        Some((value_start, value_end)) => {
+            T::unpack_from_slice(&tlv_data[value_start..value_end]).map(Some)
+        }
+        None => Ok(None),
+    }
+}
+
+/// Overwrites the existing value of extension `T` in a mint or token account
+pub fn set_extension<T: Extension>(data: &mut [u8], value: T) -> Result<(), ProgramError> {
+    check_extension_account_type::<T>(data)?;
+    if !is_extended_len(data.len()) {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let tlv_data = &mut data[ACCOUNT_TYPE_OFFSET + 1..];
+    let (value_start, value_end) =
+        get_extension_value_range::<T>(tlv_data)?.ok_or(ProgramError::InvalidAccountData)?;
+    value.pack_into_slice(&mut tlv_data[value_start..value_end]);
+    Ok(())
+}
+
+/// Adds extension `T` to the unused extension space of a mint or token account. The account
+/// must have been allocated with room for it, see `ExtensionType::get_account_len`.
+pub fn init_extension<T: Extension>(data: &mut [u8], value: T) -> Result<(), ProgramError> {
+    check_extension_account_type::<T>(data)?;
+    if !is_extended_len(data.len()) {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let tlv_data = &mut data[ACCOUNT_TYPE_OFFSET + 1..];
+    if find_extension_offset(tlv_data, T::TYPE)?.is_some() {
+        return Err(TokenError::AlreadyInUse.into());
+    }
+    let start = find_extension_offset(tlv_data, ExtensionType::Uninitialized)?
+        .ok_or(ProgramError::InvalidAccountData)?;
+    let value_start = start + EXTENSION_HEADER_LEN;
+    let value_end = value_start + T::LEN;
+    if value_end > tlv_data.len() {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    tlv_data[start..start + 2].copy_from_slice(&(T::TYPE as u16).to_le_bytes());
+    tlv_data[start + 2..value_start].copy_from_slice(&(T::LEN as u16).to_le_bytes());
+    value.pack_into_slice(&mut tlv_data[value_start..value_end]);
+    data[ACCOUNT_TYPE_OFFSET] = T::TYPE.get_account_type() as u8;
+    Ok(())
+}
+
+/// Adds the default value of an account extension required by the account's mint
+pub fn init_required_account_extension(
+    data: &mut [u8],
+    extension_type: ExtensionType,
+) -> Result<(), ProgramError> {
+    match extension_type {
+        ExtensionType::TransferFeeAmount => init_extension(data, TransferFeeAmount::default()),
+        ExtensionType::NonTransferableAccount => init_extension(data, NonTransferableAccount),
+        _ => Err(ProgramError::InvalidArgument),
+    }
+}
+
+/// Data length a token account of the given mint must be allocated with
+pub fn get_account_len_for_mint(mint_data: &[u8]) -> Result<usize, ProgramError> {
+    let mint_extension_types = get_extension_types(mint_data)?;
+    let account_extension_types =
+        ExtensionType::get_required_account_extensions(&mint_extension_types);
+    Ok(ExtensionType::get_account_len::<Account>(
+        &account_extension_types,
+    ))
+}
+
 // Helpers
 fn pack_coption_key(src: &COption<Pubkey>, dst: &mut [u8; 36]) {
     let (tag, body) = mut_array_refs![dst, 4, 32];
@@ -0,0 +1,49 @@
+//! Error types
+
+use num_derive::FromPrimitive;
+use put_program::{decode_error::DecodeError, program_error::ProgramError};
+use thiserror::Error;
+
+/// Errors that may be returned by the Vesting program.
+#[derive(Clone, Debug, Eq, Error, FromPrimitive, PartialEq)]
+pub enum VestingError {
+    // 0
+    /// The cliff must fall between the start and the end of the schedule.
+    #[error("Invalid vesting schedule")]
+    InvalidSchedule,
+    /// A grant must hold a non-zero amount of tokens.
+    #[error("Grant amount must be greater than zero")]
+    ZeroAmount,
+    /// No vested tokens are left to claim.
+    #[error("Nothing to claim")]
+    NothingToClaim,
+    /// The signer is not the recipient of the grant.
+    #[error("Recipient does not match")]
+    RecipientMismatch,
+    /// The grant was created without a revoke authority.
+    #[error("Grant is not revocable")]
+    NotRevocable,
+    // 5
+    /// The signer is not the revoke authority of the grant.
+    #[error("Revoke authority does not match")]
+    AuthorityMismatch,
+    /// The grant has already been revoked.
+    #[error("Grant already revoked")]
+    AlreadyRevoked,
+    /// An account does not hold the grant's mint.
+    #[error("Mint does not match")]
+    MintMismatch,
+    /// Operation overflowed.
+    #[error("Operation overflowed")]
+    Overflow,
+}
+impl From<VestingError> for ProgramError {
+    fn from(e: VestingError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
+impl<T> DecodeError<T> for VestingError {
+    fn type_of() -> &'static str {
+        "VestingError"
+    }
+}
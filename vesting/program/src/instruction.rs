@@ -0,0 +1,229 @@
+//! Program instructions
+
+use crate::{state::VestingSchedule, *};
+use borsh::{BorshDeserialize, BorshSerialize};
+use put_program::{
+    instruction::{AccountMeta, Instruction},
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+
+/// Instructions supported by the Vesting program
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub enum VestingInstruction {
+    /// Create a vesting grant.
+    ///
+    /// This instruction creates the grant account and its vault token account, both funded by
+    /// account 0, and moves `amount` tokens from the source token account into the vault.  The
+    /// vault is a PDA that owns itself, so the tokens can only leave it through `Claim` or
+    /// `Revoke`.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writeable,signer]` Funding account (must be a system account)
+    /// 1. `[writeable,signer]` Unallocated grant account to create
+    /// 2. `[writeable]` Vault token account address from `get_vault_address`
+    /// 3. `[]` Token mint
+    /// 4. `[writeable]` Source token account
+    /// 5. `[signer]` Source token account owner
+    /// 6. `[]` Recipient wallet
+    /// 7. `[]` System program
+    /// 8. `[]` PPL Token program
+    /// 9. `[]` Rent sysvar
+    ///
+    Create {
+        /// Release schedule of the grant
+        schedule: VestingSchedule,
+        /// Number of tokens to vest
+        amount: u128,
+        /// Wallet allowed to revoke the grant, or `None` for an irrevocable grant
+        revoke_authority: Option<Pubkey>,
+    },
+
+    /// Claim every vested token not yet claimed.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writeable]` Grant account
+    /// 1. `[writeable]` Vault token account address from `get_vault_address`
+    /// 2. `[]` Token mint
+    /// 3. `[writeable]` Destination token account
+    /// 4. `[signer]` Recipient wallet
+    /// 5. `[]` PPL Token program
+    /// 6. `[]` Clock sysvar
+    Claim,
+
+    /// Revoke a grant, moving every token not vested yet to the destination.  Tokens vested
+    /// before the revocation remain claimable by the recipient.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writeable]` Grant account
+    /// 1. `[writeable]` Vault token account address from `get_vault_address`
+    /// 2. `[]` Token mint
+    /// 3. `[writeable]` Destination token account
+    /// 4. `[signer]` Revoke authority
+    /// 5. `[]` PPL Token program
+    /// 6. `[]` Clock sysvar
+    Revoke,
+}
+
+impl VestingInstruction {
+    /// Unpacks a byte buffer into a [VestingInstruction](enum.VestingInstruction.html).
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        Self::try_from_slice(input).map_err(|err| {
+            msg!("Error: failed to deserialize vesting instruction: {}", err);
+            ProgramError::InvalidInstructionData
+        })
+    }
+
+    /// Packs a [VestingInstruction](enum.VestingInstruction.html) into a byte buffer.
+    pub fn pack(&self) -> Vec<u8> {
+        self.try_to_vec().expect("try_to_vec")
+    }
+}
+
+/// Create a `VestingInstruction::Create` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn create(
+    funding_address: &Pubkey,
+    grant_address: &Pubkey,
+    mint_address: &Pubkey,
+    source_address: &Pubkey,
+    source_owner_address: &Pubkey,
+    recipient_address: &Pubkey,
+    schedule: VestingSchedule,
+    amount: u128,
+    revoke_authority: Option<Pubkey>,
+) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*funding_address, true),
+            AccountMeta::new(*grant_address, true),
+            AccountMeta::new(get_vault_address(grant_address), false),
+            AccountMeta::new_readonly(*mint_address, false),
+            AccountMeta::new(*source_address, false),
+            AccountMeta::new_readonly(*source_owner_address, true),
+            AccountMeta::new_readonly(*recipient_address, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(ppl_token::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+        ],
+        data: VestingInstruction::Create {
+            schedule,
+            amount,
+            revoke_authority,
+        }
+        .pack(),
+    }
+}
+
+/// Create a `VestingInstruction::Claim` instruction
+pub fn claim(
+    grant_address: &Pubkey,
+    mint_address: &Pubkey,
+    destination_address: &Pubkey,
+    recipient_address: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*grant_address, false),
+            AccountMeta::new(get_vault_address(grant_address), false),
+            AccountMeta::new_readonly(*mint_address, false),
+            AccountMeta::new(*destination_address, false),
+            AccountMeta::new_readonly(*recipient_address, true),
+            AccountMeta::new_readonly(ppl_token::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        data: VestingInstruction::Claim.pack(),
+    }
+}
+
+/// Create a `VestingInstruction::Revoke` instruction
+pub fn revoke(
+    grant_address: &Pubkey,
+    mint_address: &Pubkey,
+    destination_address: &Pubkey,
+    revoke_authority_address: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*grant_address, false),
+            AccountMeta::new(get_vault_address(grant_address), false),
+            AccountMeta::new_readonly(*mint_address, false),
+            AccountMeta::new(*destination_address, false),
+            AccountMeta::new_readonly(*revoke_authority_address, true),
+            AccountMeta::new_readonly(ppl_token::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        data: VestingInstruction::Revoke.pack(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialize_bytes() {
+        assert_eq!(VestingInstruction::Claim.pack(), vec![1]);
+        assert_eq!(VestingInstruction::Revoke.pack(), vec![2]);
+
+        let check = VestingInstruction::Create {
+            schedule: VestingSchedule {
+                start: 1,
+                cliff: 2,
+                end: -1,
+            },
+            amount: 42,
+            revoke_authority: None,
+        };
+        let packed = check.pack();
+        let mut expect = vec![0u8];
+        expect.extend_from_slice(&1i64.to_le_bytes());
+        expect.extend_from_slice(&2i64.to_le_bytes());
+        expect.extend_from_slice(&(-1i64).to_le_bytes());
+        expect.extend_from_slice(&42u128.to_le_bytes());
+        expect.push(0);
+        assert_eq!(packed, expect);
+        assert_eq!(VestingInstruction::unpack(&expect), Ok(check));
+
+        let authority = Pubkey::new_unique();
+        let check = VestingInstruction::Create {
+            schedule: VestingSchedule {
+                start: 1,
+                cliff: 2,
+                end: 3,
+            },
+            amount: 42,
+            revoke_authority: Some(authority),
+        };
+        let packed = check.pack();
+        assert_eq!(packed.len(), 1 + 24 + 16 + 33);
+        assert_eq!(packed[41], 1);
+        assert_eq!(&packed[42..], authority.as_ref());
+        assert_eq!(VestingInstruction::unpack(&packed), Ok(check));
+    }
+
+    #[test]
+    fn test_deserialize_invalid() {
+        assert_eq!(
+            VestingInstruction::unpack(&[3]),
+            Err(ProgramError::InvalidInstructionData),
+        );
+        assert_eq!(
+            VestingInstruction::unpack(&[0, 1]),
+            Err(ProgramError::InvalidInstructionData),
+        );
+        // trailing bytes are rejected
+        assert_eq!(
+            VestingInstruction::unpack(&[1, 0]),
+            Err(ProgramError::InvalidInstructionData),
+        );
+    }
+}
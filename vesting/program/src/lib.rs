@@ -0,0 +1,25 @@
+//! Token Vesting program
+#![deny(missing_docs)]
+#![forbid(unsafe_code)]
+
+mod entrypoint;
+pub mod error;
+pub mod instruction;
+pub mod processor;
+pub mod state;
+
+// Export current SDK types for downstream users building with a different SDK version
+pub use put_program;
+use put_program::pubkey::Pubkey;
+
+put_program::declare_id!("PutVesting111111111111111111111111111111111");
+
+pub(crate) fn get_vault_address_with_seed(grant_address: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[&grant_address.to_bytes(), br"vault"], &id())
+}
+
+/// Derive the PPL Token account address that holds the tokens of a vesting grant.  The account is
+/// its own token owner, so only this program can move tokens out of it.
+pub fn get_vault_address(grant_address: &Pubkey) -> Pubkey {
+    get_vault_address_with_seed(grant_address).0
+}
@@ -0,0 +1,780 @@
+//! Program state processor
+
+use crate::{error::VestingError, instruction::*, state::*, *};
+use ppl_token::state::{get_account_len_for_mint, Account, BaseState, Mint};
+use put_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+/// Instruction processor
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input: &[u8],
+) -> ProgramResult {
+    let instruction = VestingInstruction::unpack(input)?;
+
+    match instruction {
+        VestingInstruction::Create {
+            schedule,
+            amount,
+            revoke_authority,
+        } => {
+            msg!("VestingInstruction::Create");
+            process_create(program_id, accounts, schedule, amount, revoke_authority)
+        }
+        VestingInstruction::Claim => {
+            msg!("VestingInstruction::Claim");
+            process_claim(program_id, accounts)
+        }
+        VestingInstruction::Revoke => {
+            msg!("VestingInstruction::Revoke");
+            process_revoke(program_id, accounts)
+        }
+    }
+}
+
+fn process_create(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    schedule: VestingSchedule,
+    amount: u128,
+    revoke_authority: Option<Pubkey>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let funder_info = next_account_info(account_info_iter)?;
+    let grant_info = next_account_info(account_info_iter)?;
+    let vault_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let source_info = next_account_info(account_info_iter)?;
+    let source_owner_info = next_account_info(account_info_iter)?;
+    let recipient_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let ppl_token_program_info = next_account_info(account_info_iter)?;
+    let rent_sysvar_info = next_account_info(account_info_iter)?;
+    let rent = &Rent::from_account_info(rent_sysvar_info)?;
+
+    if !schedule.is_valid() {
+        return Err(VestingError::InvalidSchedule.into());
+    }
+    if amount == 0 {
+        return Err(VestingError::ZeroAmount.into());
+    }
+
+    let vault_bump_seed = check_vault_address(grant_info.key, vault_info.key)?;
+    let vault_signer_seeds: &[&[_]] = &[&grant_info.key.to_bytes(), br"vault", &[vault_bump_seed]];
+
+    if *mint_info.owner != ppl_token::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let (decimals, vault_len) = {
+        let mint_data = mint_info.data.borrow();
+        let mint = Mint::unpack_base(&mint_data)?;
+        (mint.decimals, get_account_len_for_mint(&mint_data)?)
+    };
+
+    msg!("Creating grant account");
+    invoke(
+        &system_instruction::create_account(
+            funder_info.key,
+            grant_info.key,
+            1.max(rent.minimum_balance(VestingGrant::get_packed_len())),
+            VestingGrant::get_packed_len() as u64,
+            program_id,
+        ),
+        &[
+            funder_info.clone(),
+            grant_info.clone(),
+            system_program_info.clone(),
+        ],
+    )?;
+
+    msg!("Creating vault token account");
+    invoke_signed(
+        &system_instruction::create_account(
+            funder_info.key,
+            vault_info.key,
+            1.max(rent.minimum_balance(vault_len)),
+            vault_len as u64,
+            &ppl_token::id(),
+        ),
+        &[
+            funder_info.clone(),
+            vault_info.clone(),
+            system_program_info.clone(),
+        ],
+        &[vault_signer_seeds],
+    )?;
+
+    msg!("Initializing vault token account");
+    invoke(
+        &ppl_token::instruction::initialize_account(
+            &ppl_token::id(),
+            vault_info.key,
+            mint_info.key,
+            vault_info.key,
+        )?,
+        &[
+            vault_info.clone(),
+            mint_info.clone(),
+            ppl_token_program_info.clone(),
+            rent_sysvar_info.clone(),
+        ],
+    )?;
+
+    msg!("Depositing {} tokens", amount);
+    invoke(
+        &ppl_token::instruction::transfer_checked(
+            &ppl_token::id(),
+            source_info.key,
+            mint_info.key,
+            vault_info.key,
+            source_owner_info.key,
+            &[],
+            amount,
+            decimals,
+        )?,
+        &[
+            source_info.clone(),
+            mint_info.clone(),
+            vault_info.clone(),
+            source_owner_info.clone(),
+            ppl_token_program_info.clone(),
+        ],
+    )?;
+
+    // Vest what actually arrived, which is less than `amount` if the mint charges a transfer fee
+    let total_amount = Account::unpack_base(&vault_info.data.borrow())?.amount;
+    if total_amount == 0 {
+        return Err(VestingError::ZeroAmount.into());
+    }
+
+    VestingGrant {
+        is_initialized: true,
+        mint: *mint_info.key,
+        recipient: *recipient_info.key,
+        revoke_authority,
+        schedule,
+        total_amount,
+        claimed_amount: 0,
+        revoked_at: None,
+    }
+    .pack_into_slice(&mut grant_info.data.borrow_mut());
+
+    Ok(())
+}
+
+fn process_claim(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let grant_info = next_account_info(account_info_iter)?;
+    let vault_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let recipient_info = next_account_info(account_info_iter)?;
+    let ppl_token_program_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::from_account_info(clock_sysvar_info)?;
+
+    let mut grant = unpack_grant(program_id, grant_info)?;
+    if grant.recipient != *recipient_info.key {
+        return Err(VestingError::RecipientMismatch.into());
+    }
+    if !recipient_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let amount = grant.claimable_amount(clock.unix_timestamp);
+    if amount == 0 {
+        return Err(VestingError::NothingToClaim.into());
+    }
+    grant.claimed_amount = grant
+        .claimed_amount
+        .checked_add(amount)
+        .ok_or(VestingError::Overflow)?;
+
+    msg!("Claiming {} tokens", amount);
+    transfer_from_vault(
+        grant_info,
+        &grant,
+        vault_info,
+        mint_info,
+        destination_info,
+        ppl_token_program_info,
+        amount,
+    )?;
+
+    grant.pack_into_slice(&mut grant_info.data.borrow_mut());
+    Ok(())
+}
+
+fn process_revoke(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let grant_info = next_account_info(account_info_iter)?;
+    let vault_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let revoke_authority_info = next_account_info(account_info_iter)?;
+    let ppl_token_program_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::from_account_info(clock_sysvar_info)?;
+
+    let mut grant = unpack_grant(program_id, grant_info)?;
+    match grant.revoke_authority {
+        None => return Err(VestingError::NotRevocable.into()),
+        Some(ref revoke_authority) if revoke_authority != revoke_authority_info.key => {
+            return Err(VestingError::AuthorityMismatch.into())
+        }
+        Some(_) => {}
+    }
+    if !revoke_authority_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if grant.revoked_at.is_some() {
+        return Err(VestingError::AlreadyRevoked.into());
+    }
+
+    grant.revoked_at = Some(clock.unix_timestamp);
+    let unvested_amount = grant
+        .total_amount
+        .checked_sub(grant.vested_amount(clock.unix_timestamp))
+        .ok_or(VestingError::Overflow)?;
+
+    if unvested_amount > 0 {
+        msg!("Returning {} unvested tokens", unvested_amount);
+        transfer_from_vault(
+            grant_info,
+            &grant,
+            vault_info,
+            mint_info,
+            destination_info,
+            ppl_token_program_info,
+            unvested_amount,
+        )?;
+    }
+
+    grant.pack_into_slice(&mut grant_info.data.borrow_mut());
+    Ok(())
+}
+
+fn unpack_grant(
+    program_id: &Pubkey,
+    grant_info: &AccountInfo,
+) -> Result<VestingGrant, ProgramError> {
+    if grant_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let grant = VestingGrant::unpack_from_slice(&grant_info.data.borrow())?;
+    if !grant.is_initialized {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    Ok(grant)
+}
+
+fn check_vault_address(grant_address: &Pubkey, vault_address: &Pubkey) -> Result<u8, ProgramError> {
+    let (expected_vault_address, vault_bump_seed) = get_vault_address_with_seed(grant_address);
+    if expected_vault_address != *vault_address {
+        msg!("Error: vault address derivation mismatch");
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(vault_bump_seed)
+}
+
+fn transfer_from_vault<'a>(
+    grant_info: &AccountInfo<'a>,
+    grant: &VestingGrant,
+    vault_info: &AccountInfo<'a>,
+    mint_info: &AccountInfo<'a>,
+    destination_info: &AccountInfo<'a>,
+    ppl_token_program_info: &AccountInfo<'a>,
+    amount: u128,
+) -> ProgramResult {
+    let vault_bump_seed = check_vault_address(grant_info.key, vault_info.key)?;
+    let vault_signer_seeds: &[&[_]] = &[&grant_info.key.to_bytes(), br"vault", &[vault_bump_seed]];
+    if grant.mint != *mint_info.key {
+        return Err(VestingError::MintMismatch.into());
+    }
+    let decimals = Mint::unpack_base(&mint_info.data.borrow())?.decimals;
+
+    invoke_signed(
+        &ppl_token::instruction::transfer_checked(
+            &ppl_token::id(),
+            vault_info.key,
+            mint_info.key,
+            destination_info.key,
+            vault_info.key,
+            &[],
+            amount,
+            decimals,
+        )?,
+        &[
+            vault_info.clone(),
+            mint_info.clone(),
+            destination_info.clone(),
+            ppl_token_program_info.clone(),
+        ],
+        &[vault_signer_seeds],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ppl_token::state::AccountState;
+    use put_program::{clock::UnixTimestamp, instruction::Instruction, program_stubs};
+    use put_sdk::account::{
+        create_account_for_test, create_is_signer_account_infos, Account as PUTAccount,
+    };
+    use std::sync::Once;
+
+    const SCHEDULE: VestingSchedule = VestingSchedule {
+        start: 1_000,
+        cliff: 1_250,
+        end: 2_000,
+    };
+
+    struct TestSyscallStubs;
+    impl program_stubs::SyscallStubs for TestSyscallStubs {
+        fn sol_invoke_signed(
+            &self,
+            instruction: &Instruction,
+            account_infos: &[AccountInfo],
+            signers_seeds: &[&[&[u8]]],
+        ) -> ProgramResult {
+            // The processor only invokes the token program once the grant is set up
+            if instruction.program_id != ppl_token::id() {
+                return Err(ProgramError::IncorrectProgramId);
+            }
+            let signers = signers_seeds
+                .iter()
+                .map(|seeds| Pubkey::create_program_address(seeds, &id()).unwrap())
+                .collect::<Vec<_>>();
+            let mut new_account_infos = vec![];
+            for meta in instruction.accounts.iter() {
+                let mut account_info = account_infos
+                    .iter()
+                    .find(|info| *info.key == meta.pubkey)
+                    .ok_or(ProgramError::NotEnoughAccountKeys)?
+                    .clone();
+                if signers.contains(account_info.key) {
+                    account_info.is_signer = true;
+                }
+                new_account_infos.push(account_info);
+            }
+            ppl_token::processor::Processor::process(
+                &instruction.program_id,
+                &new_account_infos,
+                &instruction.data,
+            )
+        }
+    }
+
+    fn set_syscall_stubs() {
+        static ONCE: Once = Once::new();
+        ONCE.call_once(|| {
+            program_stubs::set_syscall_stubs(Box::new(TestSyscallStubs));
+        });
+    }
+
+    fn do_process_instruction(
+        instruction: Instruction,
+        accounts: Vec<&mut PUTAccount>,
+    ) -> ProgramResult {
+        let mut meta = instruction
+            .accounts
+            .iter()
+            .zip(accounts)
+            .map(|(account_meta, account)| (&account_meta.pubkey, account_meta.is_signer, account))
+            .collect::<Vec<_>>();
+
+        let account_infos = create_is_signer_account_infos(&mut meta);
+        process_instruction(&instruction.program_id, &account_infos, &instruction.data)
+    }
+
+    fn clock_sysvar(unix_timestamp: UnixTimestamp) -> PUTAccount {
+        create_account_for_test(&Clock {
+            unix_timestamp,
+            ..Clock::default()
+        })
+    }
+
+    fn token_account(mint: &Pubkey, owner: &Pubkey, amount: u128) -> PUTAccount {
+        let mut account = PUTAccount::new(0, Account::get_packed_len(), &ppl_token::id());
+        Account {
+            mint: *mint,
+            owner: *owner,
+            amount,
+            state: AccountState::Initialized,
+            ..Account::default()
+        }
+        .pack_into_slice(&mut account.data);
+        account
+    }
+
+    fn token_balance(account: &PUTAccount) -> u128 {
+        Account::unpack(&account.data).unwrap().amount
+    }
+
+    struct TestGrant {
+        grant_key: Pubkey,
+        grant_account: PUTAccount,
+        vault_account: PUTAccount,
+        mint_key: Pubkey,
+        mint_account: PUTAccount,
+        recipient_key: Pubkey,
+        authority_key: Pubkey,
+    }
+
+    impl TestGrant {
+        /// A grant of 1 000 tokens over `SCHEDULE`, as left behind by `Create`
+        fn new(revocable: bool) -> Self {
+            let grant_key = Pubkey::new_unique();
+            let mint_key = Pubkey::new_unique();
+            let recipient_key = Pubkey::new_unique();
+            let authority_key = Pubkey::new_unique();
+
+            let mut grant_account =
+                PUTAccount::new(0, VestingGrant::get_packed_len(), &crate::id());
+            VestingGrant {
+                is_initialized: true,
+                mint: mint_key,
+                recipient: recipient_key,
+                revoke_authority: if revocable { Some(authority_key) } else { None },
+                schedule: SCHEDULE,
+                total_amount: 1_000,
+                claimed_amount: 0,
+                revoked_at: None,
+            }
+            .pack_into_slice(&mut grant_account.data);
+
+            let vault_key = get_vault_address(&grant_key);
+            let vault_account = token_account(&mint_key, &vault_key, 1_000);
+
+            let mut mint_account = PUTAccount::new(0, Mint::get_packed_len(), &ppl_token::id());
+            Mint {
+                supply: 1_000,
+                decimals: 2,
+                is_initialized: true,
+                ..Mint::default()
+            }
+            .pack_into_slice(&mut mint_account.data);
+
+            Self {
+                grant_key,
+                grant_account,
+                vault_account,
+                mint_key,
+                mint_account,
+                recipient_key,
+                authority_key,
+            }
+        }
+
+        fn grant(&self) -> VestingGrant {
+            VestingGrant::unpack(&self.grant_account.data).unwrap()
+        }
+
+        /// Runs a `Claim` or `Revoke` instruction, which share their account layout
+        fn process(
+            &mut self,
+            instruction: Instruction,
+            destination_account: &mut PUTAccount,
+            unix_timestamp: UnixTimestamp,
+        ) -> ProgramResult {
+            set_syscall_stubs();
+            let mut signer_account = PUTAccount::default();
+            let mut ppl_token_program_account = PUTAccount::default();
+            let mut clock_sysvar = clock_sysvar(unix_timestamp);
+            do_process_instruction(
+                instruction,
+                vec![
+                    &mut self.grant_account,
+                    &mut self.vault_account,
+                    &mut self.mint_account,
+                    destination_account,
+                    &mut signer_account,
+                    &mut ppl_token_program_account,
+                    &mut clock_sysvar,
+                ],
+            )
+        }
+    }
+
+    #[test]
+    fn test_claim() {
+        let mut test = TestGrant::new(false);
+        let destination_key = Pubkey::new_unique();
+        let mut destination_account = token_account(&test.mint_key, &test.recipient_key, 0);
+        let claim_instruction = claim(
+            &test.grant_key,
+            &test.mint_key,
+            &destination_key,
+            &test.recipient_key,
+        );
+
+        // nothing before the cliff
+        assert_eq!(
+            Err(VestingError::NothingToClaim.into()),
+            test.process(claim_instruction.clone(), &mut destination_account, 1_249)
+        );
+        assert_eq!(token_balance(&test.vault_account), 1_000);
+        assert_eq!(token_balance(&destination_account), 0);
+
+        // linear release from the cliff
+        test.process(claim_instruction.clone(), &mut destination_account, 1_500)
+            .unwrap();
+        assert_eq!(token_balance(&test.vault_account), 500);
+        assert_eq!(token_balance(&destination_account), 500);
+        assert_eq!(test.grant().claimed_amount, 500);
+
+        // claiming again at the same time pays nothing
+        assert_eq!(
+            Err(VestingError::NothingToClaim.into()),
+            test.process(claim_instruction.clone(), &mut destination_account, 1_500)
+        );
+
+        // a later claim only pays what vested since the last one
+        test.process(claim_instruction.clone(), &mut destination_account, 1_750)
+            .unwrap();
+        assert_eq!(token_balance(&test.vault_account), 250);
+        assert_eq!(token_balance(&destination_account), 750);
+        assert_eq!(test.grant().claimed_amount, 750);
+
+        // everything from the end
+        test.process(claim_instruction.clone(), &mut destination_account, 5_000)
+            .unwrap();
+        assert_eq!(token_balance(&test.vault_account), 0);
+        assert_eq!(token_balance(&destination_account), 1_000);
+        assert_eq!(
+            Err(VestingError::NothingToClaim.into()),
+            test.process(claim_instruction, &mut destination_account, 6_000)
+        );
+    }
+
+    #[test]
+    fn test_claim_checks() {
+        let mut test = TestGrant::new(false);
+        let destination_key = Pubkey::new_unique();
+        let mut destination_account = token_account(&test.mint_key, &test.recipient_key, 0);
+
+        // wrong recipient
+        assert_eq!(
+            Err(VestingError::RecipientMismatch.into()),
+            test.process(
+                claim(
+                    &test.grant_key,
+                    &test.mint_key,
+                    &destination_key,
+                    &Pubkey::new_unique(),
+                ),
+                &mut destination_account,
+                1_500,
+            )
+        );
+
+        // recipient not signer
+        let mut instruction = claim(
+            &test.grant_key,
+            &test.mint_key,
+            &destination_key,
+            &test.recipient_key,
+        );
+        instruction.accounts[4].is_signer = false;
+        assert_eq!(
+            Err(ProgramError::MissingRequiredSignature),
+            test.process(instruction, &mut destination_account, 1_500)
+        );
+
+        // wrong vault
+        let mut instruction = claim(
+            &test.grant_key,
+            &test.mint_key,
+            &destination_key,
+            &test.recipient_key,
+        );
+        instruction.accounts[1].pubkey = Pubkey::new_unique();
+        assert_eq!(
+            Err(ProgramError::InvalidArgument),
+            test.process(instruction, &mut destination_account, 1_500)
+        );
+
+        // wrong mint
+        assert_eq!(
+            Err(VestingError::MintMismatch.into()),
+            test.process(
+                claim(
+                    &test.grant_key,
+                    &Pubkey::new_unique(),
+                    &destination_key,
+                    &test.recipient_key,
+                ),
+                &mut destination_account,
+                1_500,
+            )
+        );
+
+        // grant not owned by the program
+        test.grant_account.owner = Pubkey::new_unique();
+        assert_eq!(
+            Err(ProgramError::IncorrectProgramId),
+            test.process(
+                claim(
+                    &test.grant_key,
+                    &test.mint_key,
+                    &destination_key,
+                    &test.recipient_key,
+                ),
+                &mut destination_account,
+                1_500,
+            )
+        );
+        test.grant_account.owner = crate::id();
+
+        // nothing moved
+        assert_eq!(token_balance(&test.vault_account), 1_000);
+        assert_eq!(token_balance(&destination_account), 0);
+        assert_eq!(test.grant().claimed_amount, 0);
+    }
+
+    #[test]
+    fn test_revoke() {
+        let mut test = TestGrant::new(true);
+        let destination_key = Pubkey::new_unique();
+        let mut destination_account = token_account(&test.mint_key, &test.recipient_key, 0);
+        let refund_key = Pubkey::new_unique();
+        let mut refund_account = token_account(&test.mint_key, &test.authority_key, 0);
+        let claim_instruction = claim(
+            &test.grant_key,
+            &test.mint_key,
+            &destination_key,
+            &test.recipient_key,
+        );
+        let revoke_instruction = revoke(
+            &test.grant_key,
+            &test.mint_key,
+            &refund_key,
+            &test.authority_key,
+        );
+
+        test.process(claim_instruction.clone(), &mut destination_account, 1_300)
+            .unwrap();
+        assert_eq!(token_balance(&destination_account), 300);
+
+        // only the unvested tokens go back
+        test.process(revoke_instruction.clone(), &mut refund_account, 1_600)
+            .unwrap();
+        assert_eq!(token_balance(&refund_account), 400);
+        assert_eq!(token_balance(&test.vault_account), 300);
+        assert_eq!(test.grant().revoked_at, Some(1_600));
+
+        assert_eq!(
+            Err(VestingError::AlreadyRevoked.into()),
+            test.process(revoke_instruction, &mut refund_account, 1_700)
+        );
+
+        // vesting stops at the revocation, what vested before it stays claimable
+        test.process(claim_instruction.clone(), &mut destination_account, 2_000)
+            .unwrap();
+        assert_eq!(token_balance(&destination_account), 600);
+        assert_eq!(token_balance(&test.vault_account), 0);
+        assert_eq!(
+            Err(VestingError::NothingToClaim.into()),
+            test.process(claim_instruction, &mut destination_account, 5_000)
+        );
+    }
+
+    #[test]
+    fn test_revoke_checks() {
+        let mut test = TestGrant::new(false);
+        let refund_key = Pubkey::new_unique();
+        let mut refund_account = token_account(&test.mint_key, &test.authority_key, 0);
+
+        // not revocable
+        assert_eq!(
+            Err(VestingError::NotRevocable.into()),
+            test.process(
+                revoke(
+                    &test.grant_key,
+                    &test.mint_key,
+                    &refund_key,
+                    &test.authority_key,
+                ),
+                &mut refund_account,
+                1_500,
+            )
+        );
+
+        let mut test = TestGrant::new(true);
+        let mut refund_account = token_account(&test.mint_key, &test.authority_key, 0);
+
+        // wrong authority
+        assert_eq!(
+            Err(VestingError::AuthorityMismatch.into()),
+            test.process(
+                revoke(
+                    &test.grant_key,
+                    &test.mint_key,
+                    &refund_key,
+                    &test.recipient_key,
+                ),
+                &mut refund_account,
+                1_500,
+            )
+        );
+
+        // authority not signer
+        let mut instruction = revoke(
+            &test.grant_key,
+            &test.mint_key,
+            &refund_key,
+            &test.authority_key,
+        );
+        instruction.accounts[4].is_signer = false;
+        assert_eq!(
+            Err(ProgramError::MissingRequiredSignature),
+            test.process(instruction, &mut refund_account, 1_500)
+        );
+
+        // wrong vault
+        let mut instruction = revoke(
+            &test.grant_key,
+            &test.mint_key,
+            &refund_key,
+            &test.authority_key,
+        );
+        instruction.accounts[1].pubkey = Pubkey::new_unique();
+        assert_eq!(
+            Err(ProgramError::InvalidArgument),
+            test.process(instruction, &mut refund_account, 1_500)
+        );
+
+        // wrong mint
+        assert_eq!(
+            Err(VestingError::MintMismatch.into()),
+            test.process(
+                revoke(
+                    &test.grant_key,
+                    &Pubkey::new_unique(),
+                    &refund_key,
+                    &test.authority_key,
+                ),
+                &mut refund_account,
+                1_500,
+            )
+        );
+
+        // a failed revocation leaves the grant untouched
+        assert_eq!(token_balance(&test.vault_account), 1_000);
+        assert_eq!(token_balance(&refund_account), 0);
+        assert_eq!(test.grant().revoked_at, None);
+    }
+}
@@ -0,0 +1,236 @@
+//! Program state
+use borsh::{BorshDeserialize, BorshSerialize};
+use put_program::{
+    clock::UnixTimestamp,
+    msg,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+
+/// Release schedule of a vesting grant.
+///
+/// Nothing is released before `cliff`.  From the cliff on, tokens are released linearly as if
+/// vesting had begun at `start`, so the cliff releases everything accrued up to that point at
+/// once, and everything is released from `end`.
+#[derive(Clone, Copy, Debug, Default, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct VestingSchedule {
+    /// Time at which tokens start to accrue
+    pub start: UnixTimestamp,
+    /// Time before which no tokens can be claimed
+    pub cliff: UnixTimestamp,
+    /// Time at which all tokens are released
+    pub end: UnixTimestamp,
+}
+
+impl VestingSchedule {
+    /// Checks that `start <= cliff <= end`
+    pub fn is_valid(&self) -> bool {
+        self.start <= self.cliff && self.cliff <= self.end
+    }
+
+    /// Portion of `total_amount` released at `unix_timestamp`
+    pub fn vested_amount(&self, total_amount: u128, unix_timestamp: UnixTimestamp) -> u128 {
+        if unix_timestamp < self.cliff {
+            0
+        } else if unix_timestamp >= self.end {
+            total_amount
+        } else {
+            // `start <= cliff <= unix_timestamp < end`, so `duration` is non-zero, and
+            // splitting the product keeps it from overflowing for any `total_amount`
+            let elapsed = (unix_timestamp - self.start) as u128;
+            let duration = (self.end - self.start) as u128;
+            (total_amount / duration) * elapsed + (total_amount % duration) * elapsed / duration
+        }
+    }
+}
+
+/// Contents of a vesting grant account
+#[derive(Clone, Debug, Default, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct VestingGrant {
+    /// Is `true` if this structure has been initialized
+    pub is_initialized: bool,
+    /// The mint of the vested tokens
+    pub mint: Pubkey,
+    /// The wallet allowed to claim the vested tokens
+    pub recipient: Pubkey,
+    /// The wallet allowed to take back the unvested tokens, if the grant is revocable
+    pub revoke_authority: Option<Pubkey>,
+    /// Release schedule of the grant
+    pub schedule: VestingSchedule,
+    /// The amount of tokens deposited into the vault when the grant was created
+    pub total_amount: u128,
+    /// The amount of tokens claimed by the recipient so far
+    pub claimed_amount: u128,
+    /// Time at which the grant was revoked.  Nothing vests past it.
+    pub revoked_at: Option<UnixTimestamp>,
+}
+
+impl VestingGrant {
+    /// Amount of tokens released at `unix_timestamp`, including those already claimed
+    pub fn vested_amount(&self, unix_timestamp: UnixTimestamp) -> u128 {
+        let unix_timestamp = self
+            .revoked_at
+            .map_or(unix_timestamp, |revoked_at| revoked_at.min(unix_timestamp));
+        self.schedule
+            .vested_amount(self.total_amount, unix_timestamp)
+    }
+
+    /// Amount of tokens the recipient may claim at `unix_timestamp`
+    pub fn claimable_amount(&self, unix_timestamp: UnixTimestamp) -> u128 {
+        self.vested_amount(unix_timestamp)
+            .saturating_sub(self.claimed_amount)
+    }
+}
+
+impl Sealed for VestingGrant {}
+impl IsInitialized for VestingGrant {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for VestingGrant {
+    const LEN: usize = 163; // see `test_get_packed_len()` for justification of "163"
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let data = self.try_to_vec().unwrap();
+        dst[..data.len()].copy_from_slice(&data);
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let mut mut_src: &[u8] = src;
+        Self::deserialize(&mut mut_src).map_err(|err| {
+            msg!(
+                "Error: failed to deserialize vesting grant account: {}",
+                err
+            );
+            ProgramError::InvalidAccountData
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEDULE: VestingSchedule = VestingSchedule {
+        start: 1_000,
+        cliff: 1_250,
+        end: 2_000,
+    };
+
+    #[test]
+    fn test_get_packed_len() {
+        // Largest encoding: both options set
+        let grant = VestingGrant {
+            is_initialized: true,
+            mint: Pubkey::new_unique(),
+            recipient: Pubkey::new_unique(),
+            revoke_authority: Some(Pubkey::new_unique()),
+            schedule: SCHEDULE,
+            total_amount: u128::MAX,
+            claimed_amount: u128::MAX,
+            revoked_at: Some(i64::MAX),
+        };
+        assert_eq!(
+            VestingGrant::get_packed_len(),
+            grant.try_to_vec().unwrap().len()
+        );
+    }
+
+    #[test]
+    fn test_pack_unpack() {
+        let grant = VestingGrant {
+            is_initialized: true,
+            mint: Pubkey::new_unique(),
+            recipient: Pubkey::new_unique(),
+            revoke_authority: None,
+            schedule: SCHEDULE,
+            total_amount: 1_000,
+            claimed_amount: 10,
+            revoked_at: None,
+        };
+        let mut dst = vec![0xff; VestingGrant::get_packed_len()];
+        VestingGrant::pack(grant.clone(), &mut dst).unwrap();
+        assert_eq!(VestingGrant::unpack(&dst), Ok(grant));
+
+        let dst = vec![0; VestingGrant::get_packed_len()];
+        assert_eq!(
+            VestingGrant::unpack(&dst),
+            Err(ProgramError::UninitializedAccount)
+        );
+    }
+
+    #[test]
+    fn test_schedule_validity() {
+        assert!(SCHEDULE.is_valid());
+        assert!(VestingSchedule {
+            start: 1_000,
+            cliff: 1_000,
+            end: 1_000,
+        }
+        .is_valid());
+        assert!(!VestingSchedule {
+            start: 1_000,
+            cliff: 999,
+            end: 2_000,
+        }
+        .is_valid());
+        assert!(!VestingSchedule {
+            start: 1_000,
+            cliff: 2_001,
+            end: 2_000,
+        }
+        .is_valid());
+    }
+
+    #[test]
+    fn test_vested_amount() {
+        // nothing before the cliff
+        assert_eq!(SCHEDULE.vested_amount(1_000, 0), 0);
+        assert_eq!(SCHEDULE.vested_amount(1_000, 1_249), 0);
+        // the cliff releases what accrued since the start
+        assert_eq!(SCHEDULE.vested_amount(1_000, 1_250), 250);
+        assert_eq!(SCHEDULE.vested_amount(1_000, 1_500), 500);
+        assert_eq!(SCHEDULE.vested_amount(1_000, 1_999), 999);
+        // everything from the end
+        assert_eq!(SCHEDULE.vested_amount(1_000, 2_000), 1_000);
+        assert_eq!(SCHEDULE.vested_amount(1_000, i64::MAX), 1_000);
+        // rounds down, never overflows
+        assert_eq!(SCHEDULE.vested_amount(3, 1_500), 1);
+        assert_eq!(SCHEDULE.vested_amount(u128::MAX, 1_500), u128::MAX / 2);
+
+        // all at once
+        let schedule = VestingSchedule {
+            start: 1_000,
+            cliff: 1_000,
+            end: 1_000,
+        };
+        assert_eq!(schedule.vested_amount(1_000, 999), 0);
+        assert_eq!(schedule.vested_amount(1_000, 1_000), 1_000);
+    }
+
+    #[test]
+    fn test_claimable_amount() {
+        let mut grant = VestingGrant {
+            is_initialized: true,
+            mint: Pubkey::new_unique(),
+            recipient: Pubkey::new_unique(),
+            revoke_authority: Some(Pubkey::new_unique()),
+            schedule: SCHEDULE,
+            total_amount: 1_000,
+            claimed_amount: 300,
+            revoked_at: None,
+        };
+        assert_eq!(grant.claimable_amount(1_250), 0);
+        assert_eq!(grant.claimable_amount(1_500), 200);
+        assert_eq!(grant.claimable_amount(2_000), 700);
+
+        // nothing vests past the revocation
+        grant.revoked_at = Some(1_600);
+        assert_eq!(grant.vested_amount(2_000), 600);
+        assert_eq!(grant.claimable_amount(1_500), 200);
+        assert_eq!(grant.claimable_amount(2_000), 300);
+    }
+}